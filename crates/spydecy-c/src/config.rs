@@ -0,0 +1,320 @@
+//! Clang invocation
+//!
+//! Real CPython sources `#include "Python.h"` and depend on macros such as
+//! `Py_BUILD_CORE`, so they only resolve to the right types when clang sees
+//! the same include paths and definitions as the build. [`CParserConfig`]
+//! carries those flags, either given directly or taken from the file's entry
+//! in a `compile_commands.json` compilation database.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+
+/// Command-line flags clang parses C sources with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CParserConfig {
+    /// Directories searched for `#include` files (`-I`)
+    pub include_dirs: Vec<PathBuf>,
+    /// Macro definitions, `NAME` or `NAME=VALUE` (`-D`)
+    pub defines: Vec<String>,
+    /// Language standard, e.g. `c11` (`-std=`)
+    pub std: Option<String>,
+    /// Target triple, e.g. `x86_64-unknown-linux-gnu` (`--target=`)
+    pub target: Option<String>,
+    /// Compilation database to take per-file flags from
+    pub compile_commands: Option<PathBuf>,
+}
+
+impl CParserConfig {
+    /// Search the given directory for `#include` files
+    #[must_use]
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /// Define a macro, `NAME` or `NAME=VALUE`
+    #[must_use]
+    pub fn with_define(mut self, define: impl Into<String>) -> Self {
+        self.defines.push(define.into());
+        self
+    }
+
+    /// Parse as the given language standard, e.g. `c99`
+    #[must_use]
+    pub fn with_std(mut self, std: impl Into<String>) -> Self {
+        self.std = Some(std.into());
+        self
+    }
+
+    /// Parse for the given target triple
+    #[must_use]
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Take the flags a file was built with from a `compile_commands.json`
+    #[must_use]
+    pub fn with_compile_commands(mut self, path: impl Into<PathBuf>) -> Self {
+        self.compile_commands = Some(path.into());
+        self
+    }
+
+    /// Clang arguments for parsing `filename`
+    ///
+    /// The compilation database's flags for the file come first, so the
+    /// explicitly configured ones take precedence over them. A file the
+    /// database has no entry for gets only the configured flags.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compilation database cannot be read or parsed
+    pub fn arguments(&self, filename: &str) -> Result<Vec<String>> {
+        let mut arguments = match &self.compile_commands {
+            Some(path) => CompilationDatabase::load(path)?
+                .flags(Path::new(filename))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        arguments.extend(
+            self.include_dirs
+                .iter()
+                .map(|dir| format!("-I{}", dir.display())),
+        );
+        arguments.extend(self.defines.iter().map(|define| format!("-D{define}")));
+        arguments.extend(self.std.iter().map(|std| format!("-std={std}")));
+        arguments.extend(
+            self.target
+                .iter()
+                .map(|target| format!("--target={target}")),
+        );
+        Ok(arguments)
+    }
+}
+
+/// `compile_commands.json`: how each file of a project is compiled
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompilationDatabase {
+    entries: Vec<CompileCommand>,
+}
+
+/// Entry of a compilation database
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct CompileCommand {
+    /// Working directory of the compilation
+    directory: PathBuf,
+    /// Source file, relative to `directory` unless absolute
+    file: PathBuf,
+    /// Compiler invocation as separate arguments
+    #[serde(default)]
+    arguments: Option<Vec<String>>,
+    /// Compiler invocation as one shell command, when `arguments` is absent
+    #[serde(default)]
+    command: Option<String>,
+}
+
+impl CompilationDatabase {
+    /// Read a `compile_commands.json`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a compilation
+    /// database
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read compilation database: {}", path.display()))?;
+        Self::from_json(&json)
+            .with_context(|| format!("Invalid compilation database: {}", path.display()))
+    }
+
+    /// Parse the contents of a `compile_commands.json`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is not a compilation database
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(Self {
+            entries: serde_json::from_str(json)?,
+        })
+    }
+
+    /// Flags that matter for parsing `file` - include paths, macros,
+    /// forced includes, language standard and target - or `None` if the
+    /// database has no entry for it
+    ///
+    /// Relative paths are resolved against the compilation's directory, and
+    /// a relative `file` against the current one.
+    #[must_use]
+    pub fn flags(&self, file: &Path) -> Option<Vec<String>> {
+        let file = absolute(file, &std::env::current_dir().unwrap_or_default());
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| absolute(&entry.file, &entry.directory) == file)?;
+        let arguments = match (&entry.arguments, &entry.command) {
+            (Some(arguments), _) => arguments.clone(),
+            (None, Some(command)) => split_command(command),
+            (None, None) => Vec::new(),
+        };
+        Some(parsing_flags(&arguments, &entry.directory))
+    }
+}
+
+/// Flags of a compiler invocation that change how the source parses
+fn parsing_flags(arguments: &[String], directory: &Path) -> Vec<String> {
+    const PATH_FLAGS: [&str; 5] = ["-I", "-isystem", "-iquote", "-idirafter", "-include"];
+
+    let path = |value: &str| absolute(Path::new(value), directory).display().to_string();
+    let mut flags = Vec::new();
+    // The first argument is the compiler itself
+    let mut arguments = arguments.iter().skip(1);
+    while let Some(argument) = arguments.next() {
+        let argument = argument.as_str();
+        if let Some(flag) = PATH_FLAGS.iter().find(|flag| argument == **flag) {
+            if let Some(value) = arguments.next() {
+                flags.extend([(*flag).to_string(), path(value)]);
+            }
+        } else if matches!(argument, "-D" | "-U" | "-target") {
+            if let Some(value) = arguments.next() {
+                flags.extend([argument.to_string(), value.clone()]);
+            }
+        } else if let Some(dir) = argument.strip_prefix("-I") {
+            flags.push(format!("-I{}", path(dir)));
+        } else if argument.starts_with("-D")
+            || argument.starts_with("-U")
+            || argument.starts_with("-std=")
+            || argument.starts_with("--target=")
+        {
+            flags.push(argument.to_string());
+        }
+    }
+    flags
+}
+
+/// `path` resolved against `base`, without `.` and `..` components
+fn absolute(path: &Path, base: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+/// Split a shell command into arguments, honouring quotes and backslash
+/// escapes
+fn split_command(command: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut argument = None::<String>;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', Some('\'')) => argument.get_or_insert_with(String::new).push(c),
+            ('\\', _) => {
+                let escaped = argument.get_or_insert_with(String::new);
+                escaped.extend(chars.next());
+            }
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                argument.get_or_insert_with(String::new);
+            }
+            (c, Some(open)) if c == open => quote = None,
+            (c, None) if c.is_whitespace() => arguments.extend(argument.take()),
+            (c, _) => argument.get_or_insert_with(String::new).push(c),
+        }
+    }
+    arguments.extend(argument);
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_arguments() {
+        let config = CParserConfig::default()
+            .with_include_dir("Include")
+            .with_define("Py_BUILD_CORE")
+            .with_define("NDEBUG=1")
+            .with_std("c11")
+            .with_target("x86_64-unknown-linux-gnu");
+        assert_eq!(
+            config.arguments("listobject.c").unwrap(),
+            [
+                "-IInclude",
+                "-DPy_BUILD_CORE",
+                "-DNDEBUG=1",
+                "-std=c11",
+                "--target=x86_64-unknown-linux-gnu"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compilation_database_flags() {
+        let database = CompilationDatabase::from_json(
+            r#"[
+                {
+                    "directory": "/src/cpython",
+                    "file": "Objects/listobject.c",
+                    "arguments": ["gcc", "-c", "-I", "Include", "-I./Include/internal",
+                                  "-DPy_BUILD_CORE", "-O2", "-std=c11",
+                                  "-o", "Objects/listobject.o", "Objects/listobject.c"]
+                },
+                {
+                    "directory": "/src/cpython",
+                    "file": "/src/cpython/Objects/dictobject.c",
+                    "command": "cc -D 'Py_DEBUG' -DLABEL=\"two words\" -isystem /usr/include -c Objects/dictobject.c"
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            database
+                .flags(Path::new("/src/cpython/Objects/../Objects/listobject.c"))
+                .unwrap(),
+            [
+                "-I",
+                "/src/cpython/Include",
+                "-I/src/cpython/Include/internal",
+                "-DPy_BUILD_CORE",
+                "-std=c11"
+            ]
+        );
+        assert_eq!(
+            database
+                .flags(Path::new("/src/cpython/Objects/dictobject.c"))
+                .unwrap(),
+            [
+                "-D",
+                "Py_DEBUG",
+                "-DLABEL=two words",
+                "-isystem",
+                "/usr/include"
+            ]
+        );
+        assert_eq!(
+            database.flags(Path::new("/src/cpython/Objects/setobject.c")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_compilation_database() {
+        let error =
+            CompilationDatabase::load(Path::new("/nonexistent/compile_commands.json")).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Failed to read compilation database"));
+        assert!(CompilationDatabase::from_json(r#"{"file": "a.c"}"#).is_err());
+    }
+}
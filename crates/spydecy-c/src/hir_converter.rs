@@ -1,182 +1,1191 @@
 //! C AST to HIR converter
 //!
 //! Converts parsed C AST into Spydecy's C HIR representation.
+//!
+//! Struct, union and enum definitions and typedefs become declarations of
+//! the translation unit, from which [`spydecy_hir::c::CTypes`] resolves named
+//! types.
+//!
+//! Statements and expressions map onto the existing [`CHIR`] nodes; nested
+//! blocks are flattened into the enclosing body, since the HIR has no block
+//! node. Constructs without a HIR node (`switch`, `goto`, the conditional
+//! operator, ...) are not dropped silently: the statement containing them is
+//! skipped and reported in [`Conversion::diagnostics`].
+//!
+//! Operators, literal values and expression types are read from the
+//! `operator`, `value` and `type` attributes of [`CAST`] nodes.
 
 use crate::parser::CAST;
 use anyhow::{bail, Result};
 use spydecy_hir::{
-    c::{Parameter, StorageClass, CHIR},
+    c::{BinOp, EnumConstant, Field, Literal, Parameter, StorageClass, UnaryOp, CHIR},
+    diagnostics::Diagnostic,
     metadata::Metadata,
-    types::{CType, Type},
-    NodeId, Visibility,
+    types::{CPythonType, CType, Type},
+    NodeId, SourceLocation, Visibility,
 };
+use std::collections::HashMap;
+
+/// C HIR together with the constructs that had to be skipped
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    /// Converted node
+    pub hir: CHIR,
+    /// Skipped statements and declarations, preceded by clang's own
+    /// diagnostics when parsed with [`crate::parse_c_with_diagnostics`]
+    pub diagnostics: Vec<Diagnostic>,
+}
 
 /// Convert C AST to HIR
 ///
+/// Skipped constructs are logged as warnings; use [`convert`] to collect
+/// them instead.
+///
 /// # Errors
 ///
 /// Returns an error if the AST cannot be converted
 pub fn convert_to_hir(ast: &CAST) -> Result<CHIR> {
-    let mut id_counter = 1;
-    convert_node(ast, &mut id_counter)
+    let conversion = convert(ast)?;
+    for diagnostic in &conversion.diagnostics {
+        tracing::warn!("{diagnostic}");
+    }
+    Ok(conversion.hir)
 }
 
-fn convert_node(ast: &CAST, id_counter: &mut u64) -> Result<CHIR> {
-    match ast.node_type.as_str() {
-        "TranslationUnit" => convert_translation_unit(ast, id_counter),
-        "FunctionDecl" => convert_function_decl(ast, id_counter),
-        "ReturnStmt" => convert_return_stmt(ast, id_counter),
-        "CallExpr" => convert_call_expr(ast, id_counter),
-        "DeclRefExpr" => convert_decl_ref_expr(ast, id_counter),
-        _ => bail!("Unsupported C AST node type: {}", ast.node_type),
-    }
+/// Convert C AST to HIR, collecting the constructs that had to be skipped
+///
+/// # Errors
+///
+/// Returns an error if `ast` itself is a construct without a HIR node
+pub fn convert(ast: &CAST) -> Result<Conversion> {
+    let mut converter = Converter::new();
+    let hir = match ast.node_type.as_str() {
+        "TranslationUnit" => converter.translation_unit(ast),
+        "FunctionDecl" => converter.function_decl(ast),
+        _ => match converter.statement(ast) {
+            Ok(mut nodes) if nodes.len() == 1 => nodes.remove(0),
+            Ok(_) => bail!("C {} does not convert to a single HIR node", ast.node_type),
+            Err(unsupported) => bail!("Unsupported C {}", unsupported.what),
+        },
+    };
+    Ok(Conversion {
+        hir,
+        diagnostics: converter.diagnostics,
+    })
 }
 
-/// Convert TranslationUnit node
-fn convert_translation_unit(ast: &CAST, id_counter: &mut u64) -> Result<CHIR> {
-    let mut declarations = Vec::new();
-    for child in &ast.children {
-        // Only process function declarations for now
-        if child.node_type == "FunctionDecl" {
-            declarations.push(convert_node(child, id_counter)?);
+/// C construct without a HIR node
+struct Unsupported {
+    /// What it is, e.g. "statement `SwitchStmt`" or "operator `,`"
+    what: String,
+    location: Option<SourceLocation>,
+    /// Whether the construct is the skipped statement itself rather than
+    /// part of it
+    whole: bool,
+}
+
+impl Unsupported {
+    /// The statement or declaration `ast` itself
+    fn node(kind: &str, ast: &CAST) -> Self {
+        Self {
+            what: format!("{kind} `{}`", ast.node_type),
+            location: ast.location.clone(),
+            whole: true,
         }
     }
 
-    Ok(CHIR::TranslationUnit {
-        name: "main".to_string(),
-        declarations,
-        meta: Metadata::new(),
-    })
+    /// Part of a statement
+    fn within(what: String, ast: &CAST) -> Self {
+        Self {
+            what,
+            location: ast.location.clone(),
+            whole: false,
+        }
+    }
+
+    fn into_diagnostic(self) -> Diagnostic {
+        let message = if self.whole {
+            format!("unsupported C {} was skipped", self.what)
+        } else {
+            format!(
+                "unsupported C {}; the statement containing it was skipped",
+                self.what
+            )
+        };
+        Diagnostic::warning(message).with_location(self.location)
+    }
 }
 
-/// Convert FunctionDecl node
-#[allow(clippy::unnecessary_wraps)]
-fn convert_function_decl(ast: &CAST, id_counter: &mut u64) -> Result<CHIR> {
-    let name = ast.name.clone().unwrap_or_else(|| "unknown".to_string());
-    let return_type = parse_type(&ast.return_type);
+type Converted<T> = std::result::Result<T, Unsupported>;
 
-    let params = ast
-        .params
-        .iter()
-        .map(|p| Parameter {
-            name: p.name.clone(),
-            param_type: parse_type(&Some(p.param_type.clone())),
-        })
-        .collect();
+struct Converter {
+    next_id: u64,
+    diagnostics: Vec<Diagnostic>,
+    /// Anonymous structs, unions and enums, by the typedef naming them
+    anonymous_records: HashMap<String, CType>,
+}
+
+impl Converter {
+    fn new() -> Self {
+        Self {
+            next_id: 1,
+            diagnostics: Vec::new(),
+            anonymous_records: HashMap::new(),
+        }
+    }
 
-    // Convert function body (simplified for now)
-    let mut body = Vec::new();
-    for child in &ast.children {
-        if child.node_type.contains("Stmt") || child.node_type == "ReturnStmt" {
-            if let Ok(stmt) = convert_node(child, id_counter) {
-                body.push(stmt);
+    fn next_id(&mut self) -> NodeId {
+        let id = NodeId::new(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    fn report(&mut self, unsupported: Unsupported) {
+        self.diagnostics.push(unsupported.into_diagnostic());
+    }
+
+    /// Convert TranslationUnit node
+    fn translation_unit(&mut self, ast: &CAST) -> CHIR {
+        let file = ast.location.as_ref().map(|location| &location.file);
+        let mut declarations = Vec::new();
+        for (index, child) in ast.children.iter().enumerate() {
+            // Functions of included headers are not the input's, but their
+            // types are needed to resolve the input's
+            let included = child.location.as_ref().map(|location| &location.file) != file;
+            match child.node_type.as_str() {
+                "FunctionDecl" if !included => declarations.push(self.function_decl(child)),
+                "StructDecl" | "UnionDecl" | "EnumDecl" => {
+                    let next = ast.children.get(index + 1);
+                    match self.record_decl(child, next, &mut declarations) {
+                        Err(unsupported) if !included => self.report(unsupported),
+                        _ => {}
+                    }
+                }
+                "TypedefDecl" => declarations.push(self.typedef_decl(child)),
+                // Preprocessing record
+                "macro definition" | "macro expansion" | "inclusion directive" => {}
+                _ if included => {}
+                _ => self.report(Unsupported::node("declaration", child)),
             }
         }
+
+        CHIR::TranslationUnit {
+            name: "main".to_string(),
+            declarations,
+            meta: meta_of(ast),
+        }
     }
 
-    let id = next_id(id_counter);
-    Ok(CHIR::Function {
-        id,
-        name,
-        return_type,
-        params,
-        body,
-        storage_class: StorageClass::Static,
-        visibility: Visibility::Private,
-        meta: Metadata::new(),
-    })
-}
+    /// Convert a StructDecl, UnionDecl or EnumDecl definition, and the
+    /// named ones nested in it; forward declarations have nothing to add
+    ///
+    /// An anonymous definition takes the name of the typedef that follows
+    /// it, as in `typedef struct { ... } PyListObject;`.
+    fn record_decl(
+        &mut self,
+        ast: &CAST,
+        next: Option<&CAST>,
+        declarations: &mut Vec<CHIR>,
+    ) -> Converted<()> {
+        if ast.children.is_empty() {
+            return Ok(());
+        }
+        let name = match ast.name.as_deref() {
+            Some(name) if !is_anonymous(name) => name.to_string(),
+            _ => match next.filter(|next| next.node_type == "TypedefDecl") {
+                Some(CAST {
+                    name: Some(typedef),
+                    ..
+                }) => {
+                    self.anonymous_records
+                        .insert(typedef.clone(), record_type(ast, typedef.clone()));
+                    typedef.clone()
+                }
+                // Only enum constants are usable without a name
+                _ if ast.node_type == "EnumDecl" => String::new(),
+                _ => {
+                    return Err(Unsupported {
+                        what: format!("anonymous `{}`", ast.node_type),
+                        location: ast.location.clone(),
+                        whole: true,
+                    })
+                }
+            },
+        };
 
-/// Convert ReturnStmt node
-fn convert_return_stmt(ast: &CAST, id_counter: &mut u64) -> Result<CHIR> {
-    let value = if ast.children.is_empty() {
-        None
-    } else {
-        Some(Box::new(convert_node(&ast.children[0], id_counter)?))
-    };
+        if ast.node_type == "EnumDecl" {
+            let mut next_value = 0;
+            let constants = ast
+                .children
+                .iter()
+                .filter(|child| child.node_type == "EnumConstantDecl")
+                .map(|constant| {
+                    let value: i64 = constant
+                        .attributes
+                        .get("value")
+                        .and_then(|value| value.parse().ok())
+                        .unwrap_or(next_value);
+                    next_value = value.wrapping_add(1);
+                    EnumConstant {
+                        name: constant.name.clone().unwrap_or_default(),
+                        value,
+                    }
+                })
+                .collect();
+            let id = self.next_id();
+            declarations.push(CHIR::Enum {
+                id,
+                name,
+                constants,
+                meta: meta_of(ast),
+            });
+            return Ok(());
+        }
 
-    let id = next_id(id_counter);
-    Ok(CHIR::Return {
-        id,
-        value,
-        meta: Metadata::new(),
-    })
-}
+        for (index, child) in ast.children.iter().enumerate() {
+            if matches!(
+                child.node_type.as_str(),
+                "StructDecl" | "UnionDecl" | "EnumDecl"
+            ) && child
+                .name
+                .as_deref()
+                .is_some_and(|name| !is_anonymous(name))
+            {
+                self.record_decl(child, ast.children.get(index + 1), declarations)?;
+            }
+        }
+        let fields = ast
+            .children
+            .iter()
+            .filter(|child| child.node_type == "FieldDecl")
+            .map(|field| Field {
+                name: field.name.clone().unwrap_or_default(),
+                field_type: parse_type(&field.attributes.get("type").cloned()),
+            })
+            .collect();
+        let id = self.next_id();
+        declarations.push(if ast.node_type == "UnionDecl" {
+            CHIR::Union {
+                id,
+                name,
+                fields,
+                meta: meta_of(ast),
+            }
+        } else {
+            CHIR::Struct {
+                id,
+                name,
+                fields,
+                meta: meta_of(ast),
+            }
+        });
+        Ok(())
+    }
 
-/// Convert CallExpr node
-fn convert_call_expr(ast: &CAST, id_counter: &mut u64) -> Result<CHIR> {
-    if ast.children.is_empty() {
-        bail!("CallExpr must have at least one child (callee)");
+    /// Convert TypedefDecl node into a declaration with
+    /// [`StorageClass::Typedef`]
+    fn typedef_decl(&mut self, ast: &CAST) -> CHIR {
+        let name = ast.name.clone().unwrap_or_else(|| "unknown".to_string());
+        let var_type = match self.anonymous_records.get(&name) {
+            Some(record) => Type::C(record.clone()),
+            None => parse_type(&ast.attributes.get("type").cloned()),
+        };
+
+        let id = self.next_id();
+        CHIR::VarDecl {
+            id,
+            name,
+            var_type,
+            init: None,
+            storage_class: StorageClass::Typedef,
+            meta: meta_of(ast),
+        }
     }
 
-    let callee = Box::new(convert_node(&ast.children[0], id_counter)?);
+    /// Convert FunctionDecl node; prototypes have an empty body
+    ///
+    /// Functions with internal linkage are static and private, the others
+    /// are public.
+    fn function_decl(&mut self, ast: &CAST) -> CHIR {
+        let name = ast.name.clone().unwrap_or_else(|| "unknown".to_string());
+        let return_type = parse_type(&ast.return_type);
 
-    let args = ast.children[1..]
-        .iter()
-        .filter_map(|child| convert_node(child, id_counter).ok())
-        .collect();
+        let params = ast
+            .params
+            .iter()
+            .map(|p| Parameter {
+                name: p.name.clone(),
+                param_type: parse_type(&Some(p.param_type.clone())),
+            })
+            .collect();
 
-    let id = next_id(id_counter);
-    Ok(CHIR::Call {
-        id,
-        callee,
-        args,
-        inferred_type: None,
-        meta: Metadata::new(),
-    })
-}
+        let body = ast
+            .children
+            .iter()
+            .find(|child| child.node_type == "CompoundStmt")
+            .map(|body| self.block(body))
+            .unwrap_or_default();
 
-/// Convert DeclRefExpr node
-#[allow(clippy::unnecessary_wraps)]
-fn convert_decl_ref_expr(ast: &CAST, id_counter: &mut u64) -> Result<CHIR> {
-    let name = ast.name.clone().unwrap_or_else(|| "unknown".to_string());
-    let id = next_id(id_counter);
+        let (storage_class, visibility) = match ast.attributes.get("linkage") {
+            Some(linkage) if linkage == "internal" => (StorageClass::Static, Visibility::Private),
+            _ => (StorageClass::None, Visibility::Public),
+        };
 
-    // Check if this is a CPython macro like Py_SIZE
-    if name.starts_with("Py_") || name.starts_with("_Py") {
-        Ok(CHIR::CPythonMacro {
+        let id = self.next_id();
+        CHIR::Function {
             id,
             name,
-            args: vec![],
-            inferred_type: None,
-            meta: Metadata::new(),
+            return_type,
+            params,
+            body,
+            storage_class,
+            visibility,
+            meta: meta_of(ast),
+        }
+    }
+
+    /// Statements of a compound statement (or a single statement),
+    /// reporting and skipping the unsupported ones
+    fn block(&mut self, ast: &CAST) -> Vec<CHIR> {
+        let statements = if ast.node_type == "CompoundStmt" {
+            ast.children.as_slice()
+        } else {
+            std::slice::from_ref(ast)
+        };
+        let mut body = Vec::new();
+        for statement in statements {
+            match self.statement(statement) {
+                Ok(converted) => body.extend(converted),
+                Err(unsupported) => self.report(unsupported),
+            }
+        }
+        body
+    }
+
+    /// Convert a statement into zero or more HIR statements
+    fn statement(&mut self, ast: &CAST) -> Converted<Vec<CHIR>> {
+        let statement = match ast.node_type.as_str() {
+            "CompoundStmt" => return Ok(self.block(ast)),
+            "NullStmt" => return Ok(Vec::new()),
+            "DeclStmt" => {
+                return ast
+                    .children
+                    .iter()
+                    .map(|declaration| self.var_decl(declaration))
+                    .collect()
+            }
+            "DoStmt" => return self.do_stmt(ast),
+            "ReturnStmt" => self.return_stmt(ast)?,
+            "IfStmt" => self.if_stmt(ast)?,
+            "ForStmt" => self.for_stmt(ast)?,
+            "WhileStmt" => self.while_stmt(ast)?,
+            kind if kind.ends_with("Stmt") => return Err(Unsupported::node("statement", ast)),
+            _ => self.expression_statement(ast)?,
+        };
+        Ok(vec![statement])
+    }
+
+    /// Convert a VarDecl inside a DeclStmt
+    fn var_decl(&mut self, ast: &CAST) -> Converted<CHIR> {
+        if ast.node_type != "VarDecl" {
+            return Err(Unsupported::within(
+                format!("declaration `{}`", ast.node_type),
+                ast,
+            ));
+        }
+        let type_name = ast.attributes.get("type").cloned();
+        // An array's size is an expression child too, not an initializer
+        if type_name.as_deref().is_some_and(|name| name.contains('[')) {
+            return Err(Unsupported::within("array declaration".to_string(), ast));
+        }
+        let init = match ast.children.iter().rfind(|child| is_expression(child)) {
+            Some(init) => Some(Box::new(self.expression(init)?)),
+            None => None,
+        };
+
+        let id = self.next_id();
+        Ok(CHIR::VarDecl {
+            id,
+            name: ast.name.clone().unwrap_or_else(|| "unknown".to_string()),
+            var_type: parse_type(&type_name),
+            init,
+            storage_class: StorageClass::None,
+            meta: meta_of(ast),
         })
-    } else {
-        Ok(CHIR::Variable {
+    }
+
+    /// Convert ReturnStmt node
+    fn return_stmt(&mut self, ast: &CAST) -> Converted<CHIR> {
+        let value = match ast.children.iter().find(|child| is_expression(child)) {
+            Some(value) => Some(Box::new(self.expression(value)?)),
+            None => None,
+        };
+
+        let id = self.next_id();
+        Ok(CHIR::Return {
             id,
-            name,
-            var_type: None,
-            meta: Metadata::new(),
+            value,
+            meta: meta_of(ast),
+        })
+    }
+
+    /// Convert IfStmt node; `else if` is an `If` in the else branch
+    fn if_stmt(&mut self, ast: &CAST) -> Converted<CHIR> {
+        let (condition, then_branch, else_branch) = match ast.children.as_slice() {
+            [condition, then_branch] => (condition, then_branch, None),
+            [condition, then_branch, else_branch] => (condition, then_branch, Some(else_branch)),
+            _ => return Err(Unsupported::node("statement", ast)),
+        };
+        let condition = Box::new(self.expression(condition)?);
+        let then_branch = self.block(then_branch);
+        let else_branch = else_branch
+            .map(|else_branch| self.block(else_branch))
+            .unwrap_or_default();
+
+        let id = self.next_id();
+        Ok(CHIR::If {
+            id,
+            condition,
+            then_branch,
+            else_branch,
+            meta: meta_of(ast),
+        })
+    }
+
+    /// Convert ForStmt node
+    ///
+    /// clang leaves out the parts of the header that are missing, so the
+    /// `header` attribute names the part each child before the body fills.
+    /// Without it only an empty or complete header can be told apart.
+    fn for_stmt(&mut self, ast: &CAST) -> Converted<CHIR> {
+        let Some((body, header)) = ast.children.split_last() else {
+            return Err(Unsupported::node("statement", ast));
+        };
+        let parts: Vec<&str> = match ast.attributes.get("header") {
+            Some(parts) => parts.split(',').filter(|part| !part.is_empty()).collect(),
+            None if header.is_empty() => Vec::new(),
+            None if header.len() == 3 => vec!["init", "condition", "increment"],
+            None => {
+                return Err(Unsupported::within(
+                    "`for` header whose parts cannot be told apart".to_string(),
+                    ast,
+                ))
+            }
+        };
+        if parts.len() != header.len() {
+            return Err(Unsupported::node("statement", ast));
+        }
+        let (mut init, mut condition, mut increment) = (None, None, None);
+        for (part, child) in parts.into_iter().zip(header) {
+            match part {
+                "init" => init = Some(child),
+                "condition" => condition = Some(child),
+                "increment" => increment = Some(child),
+                _ => return Err(Unsupported::node("statement", ast)),
+            }
+        }
+
+        let init = match init {
+            Some(init) => Some(Box::new(self.for_init(init)?)),
+            None => None,
+        };
+        let condition = match condition {
+            Some(condition) => Some(Box::new(self.expression(condition)?)),
+            None => None,
+        };
+        let increment = match increment {
+            Some(increment) => Some(Box::new(self.expression_statement(increment)?)),
+            None => None,
+        };
+        let body = self.block(body);
+
+        let id = self.next_id();
+        Ok(CHIR::For {
+            id,
+            init,
+            condition,
+            increment,
+            body,
+            meta: meta_of(ast),
+        })
+    }
+
+    /// Initializer of a `for` loop, which holds a single node
+    fn for_init(&mut self, ast: &CAST) -> Converted<CHIR> {
+        if ast.node_type != "DeclStmt" {
+            return self.expression_statement(ast);
+        }
+        match ast.children.as_slice() {
+            [declaration] => self.var_decl(declaration),
+            _ => Err(Unsupported::within(
+                "`for` initializer declaring several variables".to_string(),
+                ast,
+            )),
+        }
+    }
+
+    /// Convert WhileStmt node
+    fn while_stmt(&mut self, ast: &CAST) -> Converted<CHIR> {
+        let [condition, body] = ast.children.as_slice() else {
+            return Err(Unsupported::node("statement", ast));
+        };
+        let condition = Box::new(self.expression(condition)?);
+        let body = self.block(body);
+
+        let id = self.next_id();
+        Ok(CHIR::While {
+            id,
+            condition,
+            body,
+            meta: meta_of(ast),
+        })
+    }
+
+    /// Convert DoStmt node: the body runs once, then loops as a `while`
+    ///
+    /// `do { ... } while (0)`, the usual macro wrapper, is just its body.
+    fn do_stmt(&mut self, ast: &CAST) -> Converted<Vec<CHIR>> {
+        let [body, condition] = ast.children.as_slice() else {
+            return Err(Unsupported::node("statement", ast));
+        };
+        let condition_hir = self.expression(condition)?;
+        let mut statements = self.block(body);
+        if matches!(
+            condition_hir,
+            CHIR::Literal {
+                value: Literal::Int(0) | Literal::UInt(0),
+                ..
+            }
+        ) {
+            return Ok(statements);
+        }
+
+        // Skipped statements were already reported for the first copy
+        let reported = self.diagnostics.len();
+        let looped = self.block(body);
+        self.diagnostics.truncate(reported);
+
+        let id = self.next_id();
+        statements.push(CHIR::While {
+            id,
+            condition: Box::new(condition_hir),
+            body: looped,
+            meta: meta_of(ast),
+        });
+        Ok(statements)
+    }
+
+    /// Expression evaluated for its side effects, where `i++` and `--i`
+    /// become assignments
+    fn expression_statement(&mut self, ast: &CAST) -> Converted<CHIR> {
+        if ast.node_type == "UnaryOperator" {
+            let op = match operator(ast)? {
+                "++" | "post++" => Some(BinOp::Add),
+                "--" | "post--" => Some(BinOp::Sub),
+                _ => None,
+            };
+            if let Some(op) = op {
+                let [target] = operands(ast)?;
+                let one = CHIR::Literal {
+                    id: self.next_id(),
+                    value: Literal::Int(1),
+                    meta: meta_of(ast),
+                };
+                return self.assign_op(ast, op, target, one);
+            }
+        }
+        self.expression(ast)
+    }
+
+    fn expression(&mut self, ast: &CAST) -> Converted<CHIR> {
+        match ast.node_type.as_str() {
+            // Implicit conversions and parentheses
+            "UnexposedExpr" | "ParenExpr" => {
+                let [inner] = operands(ast)?;
+                self.expression(inner)
+            }
+            "CallExpr" => self.call_expr(ast),
+            "DeclRefExpr" => Ok(self.decl_ref_expr(ast)),
+            "BinaryOperator" => self.binary_operator(ast),
+            "CompoundAssignOperator" => {
+                let op = operator(ast)?;
+                let bin_op = op
+                    .strip_suffix('=')
+                    .and_then(binary_op)
+                    .ok_or_else(|| Unsupported::within(format!("operator `{op}`"), ast))?;
+                let [target, value] = operands(ast)?;
+                let value = self.expression(value)?;
+                self.assign_op(ast, bin_op, target, value)
+            }
+            "UnaryOperator" => self.unary_operator(ast),
+            "IntegerLiteral" | "FloatingLiteral" | "CharacterLiteral" | "StringLiteral" => {
+                self.literal(ast)
+            }
+            "MemberRefExpr" => {
+                let [object] = operands(ast)?;
+                let object = Box::new(self.expression(object)?);
+                let id = self.next_id();
+                Ok(CHIR::FieldAccess {
+                    id,
+                    object,
+                    field: ast.name.clone().unwrap_or_else(|| "unknown".to_string()),
+                    is_pointer: ast.attributes.get("operator").is_some_and(|op| op == "->"),
+                    inferred_type: expr_type(ast),
+                    meta: meta_of(ast),
+                })
+            }
+            "ArraySubscriptExpr" => {
+                let [array, index] = operands(ast)?;
+                let array = Box::new(self.expression(array)?);
+                let index = Box::new(self.expression(index)?);
+                let id = self.next_id();
+                Ok(CHIR::ArraySubscript {
+                    id,
+                    array,
+                    index,
+                    inferred_type: expr_type(ast),
+                    meta: meta_of(ast),
+                })
+            }
+            "CStyleCastExpr" => {
+                let [expr] = operands(ast)?;
+                let expr = Box::new(self.expression(expr)?);
+                let id = self.next_id();
+                Ok(CHIR::Cast {
+                    id,
+                    target_type: parse_type(&ast.attributes.get("type").cloned()),
+                    expr,
+                    meta: meta_of(ast),
+                })
+            }
+            _ => Err(Unsupported::within(
+                format!("expression `{}`", ast.node_type),
+                ast,
+            )),
+        }
+    }
+
+    /// `target = target op value`, for compound assignments and `++`/`--`
+    fn assign_op(&mut self, ast: &CAST, op: BinOp, target: &CAST, value: CHIR) -> Converted<CHIR> {
+        let lhs = Box::new(self.expression(target)?);
+        let left = Box::new(self.expression(target)?);
+        let rhs = Box::new(CHIR::BinOp {
+            id: self.next_id(),
+            op,
+            left,
+            right: Box::new(value),
+            inferred_type: expr_type(ast),
+            meta: meta_of(ast),
+        });
+
+        let id = self.next_id();
+        Ok(CHIR::Assign {
+            id,
+            lhs,
+            rhs,
+            meta: meta_of(ast),
+        })
+    }
+
+    /// Convert CallExpr node
+    fn call_expr(&mut self, ast: &CAST) -> Converted<CHIR> {
+        let mut children = ast.children.iter().filter(|child| is_expression(child));
+        let Some(callee) = children.next() else {
+            return Err(Unsupported::within(
+                "call without a callee".to_string(),
+                ast,
+            ));
+        };
+        let callee = Box::new(self.expression(callee)?);
+        let args = children
+            .map(|arg| self.expression(arg))
+            .collect::<Converted<_>>()?;
+
+        let id = self.next_id();
+        Ok(CHIR::Call {
+            id,
+            callee,
+            args,
+            inferred_type: expr_type(ast),
+            meta: meta_of(ast),
+        })
+    }
+
+    /// Convert DeclRefExpr node
+    fn decl_ref_expr(&mut self, ast: &CAST) -> CHIR {
+        let name = ast.name.clone().unwrap_or_else(|| "unknown".to_string());
+        let id = self.next_id();
+
+        // Check if this is a CPython macro like Py_SIZE
+        if name.starts_with("Py_") || name.starts_with("_Py") {
+            CHIR::CPythonMacro {
+                id,
+                name,
+                args: vec![],
+                inferred_type: expr_type(ast),
+                meta: meta_of(ast),
+            }
+        } else {
+            CHIR::Variable {
+                id,
+                name,
+                var_type: expr_type(ast),
+                meta: meta_of(ast),
+            }
+        }
+    }
+
+    /// Convert BinaryOperator node; `=` is an assignment
+    fn binary_operator(&mut self, ast: &CAST) -> Converted<CHIR> {
+        let op = operator(ast)?;
+        let [left, right] = operands(ast)?;
+        if op == "=" {
+            let lhs = Box::new(self.expression(left)?);
+            let rhs = Box::new(self.expression(right)?);
+            let id = self.next_id();
+            return Ok(CHIR::Assign {
+                id,
+                lhs,
+                rhs,
+                meta: meta_of(ast),
+            });
+        }
+
+        let op =
+            binary_op(op).ok_or_else(|| Unsupported::within(format!("operator `{op}`"), ast))?;
+        let left = Box::new(self.expression(left)?);
+        let right = Box::new(self.expression(right)?);
+        let id = self.next_id();
+        Ok(CHIR::BinOp {
+            id,
+            op,
+            left,
+            right,
+            inferred_type: expr_type(ast),
+            meta: meta_of(ast),
+        })
+    }
+
+    /// Convert UnaryOperator node; `*` and `&` are dereference and
+    /// address-of
+    fn unary_operator(&mut self, ast: &CAST) -> Converted<CHIR> {
+        let op = operator(ast)?;
+        let [operand] = operands(ast)?;
+        let op = match op {
+            "!" => UnaryOp::Not,
+            "-" => UnaryOp::Neg,
+            "+" => UnaryOp::Pos,
+            "~" => UnaryOp::BitNot,
+            "*" => {
+                let pointer = Box::new(self.expression(operand)?);
+                let id = self.next_id();
+                return Ok(CHIR::Deref {
+                    id,
+                    pointer,
+                    inferred_type: expr_type(ast),
+                    meta: meta_of(ast),
+                });
+            }
+            "&" => {
+                let var = Box::new(self.expression(operand)?);
+                let id = self.next_id();
+                return Ok(CHIR::AddrOf {
+                    id,
+                    var,
+                    meta: meta_of(ast),
+                });
+            }
+            // The HIR has no increment whose value is used
+            "++" | "--" | "post++" | "post--" => {
+                return Err(Unsupported::within(
+                    format!(
+                        "operator `{}` inside an expression",
+                        op.trim_start_matches("post")
+                    ),
+                    ast,
+                ))
+            }
+            _ => return Err(Unsupported::within(format!("operator `{op}`"), ast)),
+        };
+        let operand = Box::new(self.expression(operand)?);
+        let id = self.next_id();
+        Ok(CHIR::UnaryOp {
+            id,
+            op,
+            operand,
+            inferred_type: expr_type(ast),
+            meta: meta_of(ast),
         })
     }
+
+    /// Convert an integer, floating, character or string literal from its
+    /// source spelling
+    fn literal(&mut self, ast: &CAST) -> Converted<CHIR> {
+        // clang spells string literals as their source text
+        let Some(text) = ast.attributes.get("value").or(ast.name.as_ref()) else {
+            return Err(Unsupported::within(
+                format!("`{}` without a value", ast.node_type),
+                ast,
+            ));
+        };
+        let value = match ast.node_type.as_str() {
+            "IntegerLiteral" => parse_integer(text),
+            "FloatingLiteral" => parse_float(text),
+            "CharacterLiteral" => parse_char(text),
+            _ => parse_string(text),
+        }
+        .ok_or_else(|| Unsupported::within(format!("literal `{text}`"), ast))?;
+
+        let id = self.next_id();
+        Ok(CHIR::Literal {
+            id,
+            value,
+            meta: meta_of(ast),
+        })
+    }
+}
+
+/// Whether a child cursor is an expression, rather than a type reference
+/// or an attribute
+fn is_expression(ast: &CAST) -> bool {
+    let kind = ast.node_type.as_str();
+    kind.ends_with("Expr") || kind.ends_with("Operator") || kind.ends_with("Literal")
+}
+
+/// Operator of a BinaryOperator, CompoundAssignOperator or UnaryOperator
+fn operator(ast: &CAST) -> Converted<&str> {
+    ast.attributes
+        .get("operator")
+        .map(String::as_str)
+        .ok_or_else(|| Unsupported::within(format!("`{}` without an operator", ast.node_type), ast))
+}
+
+/// The `N` expression children of `ast`
+fn operands<const N: usize>(ast: &CAST) -> Converted<[&CAST; N]> {
+    let operands: Vec<_> = ast
+        .children
+        .iter()
+        .filter(|child| is_expression(child))
+        .collect();
+    let found = operands.len();
+    operands.try_into().map_err(|_| {
+        Unsupported::within(
+            format!("`{}` with {found} operands (expected {N})", ast.node_type),
+            ast,
+        )
+    })
+}
+
+fn binary_op(op: &str) -> Option<BinOp> {
+    Some(match op {
+        "+" => BinOp::Add,
+        "-" => BinOp::Sub,
+        "*" => BinOp::Mul,
+        "/" => BinOp::Div,
+        "%" => BinOp::Mod,
+        "==" => BinOp::Eq,
+        "!=" => BinOp::Ne,
+        "<" => BinOp::Lt,
+        "<=" => BinOp::Le,
+        ">" => BinOp::Gt,
+        ">=" => BinOp::Ge,
+        "&&" => BinOp::And,
+        "||" => BinOp::Or,
+        "&" => BinOp::BitAnd,
+        "|" => BinOp::BitOr,
+        "^" => BinOp::BitXor,
+        "<<" => BinOp::Shl,
+        ">>" => BinOp::Shr,
+        _ => return None,
+    })
+}
+
+/// `42`, `0x2Au`, `052L` or `0b101`; values beyond `i64` and `u`-suffixed
+/// ones are unsigned
+fn parse_integer(text: &str) -> Option<Literal> {
+    let digits = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    let unsigned = digits.len() < text.len() && text[digits.len()..].contains(['u', 'U']);
+    let digits = digits.replace('\'', "");
+    let (digits, radix) = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        (hex, 16)
+    } else if let Some(binary) = digits
+        .strip_prefix("0b")
+        .or_else(|| digits.strip_prefix("0B"))
+    {
+        (binary, 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (&digits[1..], 8)
+    } else {
+        (digits.as_str(), 10)
+    };
+    let value = u64::from_str_radix(digits, radix).ok()?;
+    if unsigned {
+        return Some(Literal::UInt(value));
+    }
+    Some(i64::try_from(value).map_or(Literal::UInt(value), Literal::Int))
+}
+
+/// `1.5`, `1e-3f` or `2.0L`
+fn parse_float(text: &str) -> Option<Literal> {
+    let digits = text.trim_end_matches(['f', 'F', 'l', 'L']);
+    digits.parse().ok().map(Literal::Float)
+}
+
+/// `'a'`, `'\n'` or `L'x'`
+fn parse_char(text: &str) -> Option<Literal> {
+    let start = text.find('\'')?;
+    let body = text[start + 1..].strip_suffix('\'')?;
+    let value = unescape(body)?;
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(Literal::Char(c)),
+        _ => None,
+    }
+}
+
+/// `"text"`, including adjacent literals that are concatenated
+fn parse_string(text: &str) -> Option<Literal> {
+    let mut value = String::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let body = &rest[rest.find('"')? + 1..];
+        let mut escaped = false;
+        let (end, _) = body.char_indices().find(|&(_, c)| {
+            let closing = !escaped && c == '"';
+            escaped = !escaped && c == '\\';
+            closing
+        })?;
+        value.push_str(&unescape(&body[..end])?);
+        rest = body[end + 1..].trim_start();
+    }
+    Some(Literal::Str(value))
+}
+
+/// Decode the escape sequences of a string or character literal body
+fn unescape(body: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let escaped = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'a' => '\u{7}',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'v' => '\u{b}',
+            'x' => {
+                let mut code = None;
+                while let Some(digit) = chars.peek().and_then(|d| d.to_digit(16)) {
+                    code = Some(code.unwrap_or(0u32).checked_mul(16)? + digit);
+                    chars.next();
+                }
+                char::from_u32(code?)?
+            }
+            prefix @ ('u' | 'U') => {
+                let len = if prefix == 'u' { 4 } else { 8 };
+                let mut code = 0;
+                for _ in 0..len {
+                    code = code * 16 + chars.next()?.to_digit(16)?;
+                }
+                char::from_u32(code)?
+            }
+            first @ '0'..='7' => {
+                let mut code = first.to_digit(8)?;
+                for _ in 0..2 {
+                    let Some(digit) = chars.peek().and_then(|d| d.to_digit(8)) else {
+                        break;
+                    };
+                    code = code * 8 + digit;
+                    chars.next();
+                }
+                char::from_u32(code)?
+            }
+            // `\\`, `\'`, `\"` and `\?`
+            other => other,
+        };
+        value.push(escaped);
+    }
+    Some(value)
 }
 
 fn parse_type(type_str: &Option<String>) -> Type {
-    match type_str.as_deref() {
-        Some("int") => Type::C(CType::Int),
-        Some("void") => Type::C(CType::Void),
-        Some("size_t") => Type::C(CType::SizeT),
-        Some("Py_ssize_t") => Type::C(CType::CPython(spydecy_hir::types::CPythonType::PySsizeT)),
-        Some(s) if s.contains("PyListObject") => Type::C(CType::CPython(
-            spydecy_hir::types::CPythonType::PyListObject,
-        )),
-        Some(s) if s.contains("PyObject") => {
-            Type::C(CType::CPython(spydecy_hir::types::CPythonType::PyObject))
+    type_str
+        .as_deref()
+        .and_then(c_type)
+        .map_or(Type::Unknown, Type::C)
+}
+
+/// C type as clang spells it, e.g. `const struct node *` or `count_t[4]`
+fn c_type(spelling: &str) -> Option<CType> {
+    let spelling = strip_qualifiers(spelling);
+    // CPython's object types are recognized whatever the indirection
+    if spelling.contains("PyListObject") {
+        return Some(CType::CPython(CPythonType::PyListObject));
+    }
+    if spelling.contains("PyObject") {
+        return Some(CType::CPython(CPythonType::PyObject));
+    }
+    if let Some(pointee) = spelling.strip_suffix('*') {
+        return Some(CType::Pointer(Box::new(c_type(pointee)?)));
+    }
+    if let Some(open) = spelling.find('[') {
+        // `int [2][3]` is an array of two `int [3]`
+        let close = open + spelling[open..].find(']')?;
+        let size = spelling[open + 1..close].trim();
+        let element = format!("{}{}", &spelling[..open], &spelling[close + 1..]);
+        return Some(CType::Array {
+            element: Box::new(c_type(&element)?),
+            size: if size.is_empty() {
+                None
+            } else {
+                Some(size.parse().ok()?)
+            },
+        });
+    }
+    let c_type = match spelling {
+        "void" => CType::Void,
+        "char" | "signed char" | "unsigned char" => CType::Char,
+        "int" | "signed" | "signed int" | "unsigned" | "unsigned int" | "short" | "short int"
+        | "unsigned short" | "unsigned short int" | "_Bool" | "bool" => CType::Int,
+        "long"
+        | "long int"
+        | "unsigned long"
+        | "unsigned long int"
+        | "long long"
+        | "long long int"
+        | "unsigned long long"
+        | "unsigned long long int" => CType::Long,
+        "float" => CType::Float,
+        "double" | "long double" => CType::Double,
+        "size_t" => CType::SizeT,
+        "Py_ssize_t" => CType::CPython(CPythonType::PySsizeT),
+        _ if is_anonymous(spelling) => return None,
+        _ => {
+            if let Some(tag) = spelling.strip_prefix("struct ") {
+                CType::Struct(tag.trim().to_string())
+            } else if let Some(tag) = spelling.strip_prefix("union ") {
+                CType::Union(tag.trim().to_string())
+            } else if spelling.starts_with("enum ") {
+                CType::Int
+            } else if spelling
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                CType::Typedef(spelling.to_string())
+            } else {
+                // Function types and the like
+                return None;
+            }
+        }
+    };
+    Some(c_type)
+}
+
+/// A type spelling without its `const`, `volatile` and `restrict`
+/// qualifiers
+fn strip_qualifiers(spelling: &str) -> &str {
+    let mut spelling = spelling.trim();
+    loop {
+        let stripped = ["const", "volatile", "restrict"]
+            .iter()
+            .find_map(|qualifier| {
+                spelling
+                    .strip_prefix(qualifier)
+                    .filter(|rest| rest.starts_with(' '))
+                    .or_else(|| {
+                        spelling
+                            .strip_suffix(qualifier)
+                            .filter(|rest| rest.ends_with([' ', '*']))
+                    })
+            });
+        match stripped {
+            Some(rest) => spelling = rest.trim(),
+            None => return spelling,
         }
-        _ => Type::Unknown,
     }
 }
 
-fn next_id(counter: &mut u64) -> NodeId {
-    let id = NodeId::new(*counter);
-    *counter += 1;
-    id
+/// Whether clang spelled the name of an anonymous struct, union or enum:
+/// empty, or like `struct (unnamed at list.c:3:9)`
+fn is_anonymous(name: &str) -> bool {
+    name.is_empty() || name.contains("(unnamed") || name.contains("(anonymous")
+}
+
+/// Type of a struct, union or enum definition named `name`
+fn record_type(ast: &CAST, name: String) -> CType {
+    match ast.node_type.as_str() {
+        "UnionDecl" => CType::Union(name),
+        "EnumDecl" => CType::Int,
+        _ => CType::Struct(name),
+    }
+}
+
+/// Type clang gave an expression, when known
+fn expr_type(ast: &CAST) -> Option<Type> {
+    let ty = parse_type(&ast.attributes.get("type").cloned());
+    (ty != Type::Unknown).then_some(ty)
+}
+
+/// Metadata carrying the node's source span, when clang reported one
+fn meta_of(ast: &CAST) -> Metadata {
+    let mut meta = ast
+        .location
+        .clone()
+        .map_or_else(Metadata::new, Metadata::with_source);
+    meta.docs = ast.attributes.get("docs").cloned();
+    meta
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn node(node_type: &str, children: Vec<CAST>) -> CAST {
+        let mut ast = CAST::new(node_type.to_string());
+        ast.children = children;
+        ast
+    }
+
+    fn with(mut ast: CAST, key: &str, value: &str) -> CAST {
+        ast.attributes.insert(key.to_string(), value.to_string());
+        ast
+    }
+
+    fn op(node_type: &str, operator: &str, children: Vec<CAST>) -> CAST {
+        with(node(node_type, children), "operator", operator)
+    }
+
+    fn var(name: &str) -> CAST {
+        let mut ast = node("DeclRefExpr", vec![]);
+        ast.name = Some(name.to_string());
+        node("UnexposedExpr", vec![ast])
+    }
+
+    fn int(value: &str) -> CAST {
+        with(node("IntegerLiteral", vec![]), "value", value)
+    }
+
+    fn function(body: Vec<CAST>) -> Conversion {
+        let mut ast = node("FunctionDecl", vec![node("CompoundStmt", body)]);
+        ast.name = Some("f".to_string());
+        convert(&ast).unwrap()
+    }
+
+    fn body(conversion: &Conversion) -> &[CHIR] {
+        match &conversion.hir {
+            CHIR::Function { body, .. } => body,
+            other => panic!("Expected a function, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_convert_empty_translation_unit() {
         let ast = CAST::new("TranslationUnit".to_string());
@@ -184,6 +1193,466 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_convert_keeps_source_location() {
+        let mut ast = CAST::new("DeclRefExpr".to_string());
+        ast.name = Some("size".to_string());
+        ast.location = Some(
+            spydecy_hir::SourceLocation::new("list.c".to_string(), 4, 12, spydecy_hir::Language::C)
+                .with_end(4, 16),
+        );
+
+        let hir = convert_to_hir(&ast).unwrap();
+        let source = hir.metadata().source.as_ref().expect("location");
+        assert_eq!((source.line, source.column), (4, 12));
+        assert_eq!((source.end_line, source.end_column), (4, 16));
+    }
+
+    #[test]
+    fn test_convert_keeps_doc_comment() {
+        let mut ast = CAST::new("DeclRefExpr".to_string());
+        ast.name = Some("size".to_string());
+        ast.attributes
+            .insert("docs".to_string(), "Number of items".to_string());
+
+        let hir = convert_to_hir(&ast).unwrap();
+        assert_eq!(hir.metadata().docs.as_deref(), Some("Number of items"));
+    }
+
+    #[test]
+    fn test_convert_control_flow() {
+        // int total = 0;
+        // for (; i < n; i++) { if (x[i] > 0) total += x[i]; else break; }
+        let mut total = with(node("VarDecl", vec![int("0")]), "type", "int");
+        total.name = Some("total".to_string());
+        let subscript = || node("ArraySubscriptExpr", vec![var("x"), var("i")]);
+        let loop_body = node(
+            "CompoundStmt",
+            vec![node(
+                "IfStmt",
+                vec![
+                    op("BinaryOperator", ">", vec![subscript(), int("0")]),
+                    op(
+                        "CompoundAssignOperator",
+                        "+=",
+                        vec![var("total"), subscript()],
+                    ),
+                    node("BreakStmt", vec![]),
+                ],
+            )],
+        );
+        let conversion = function(vec![
+            node("DeclStmt", vec![total]),
+            with(
+                node(
+                    "ForStmt",
+                    vec![
+                        op("BinaryOperator", "<", vec![var("i"), var("n")]),
+                        op("UnaryOperator", "post++", vec![var("i")]),
+                        loop_body,
+                    ],
+                ),
+                "header",
+                "condition,increment",
+            ),
+        ]);
+
+        let [CHIR::VarDecl { name, init, .. }, CHIR::For {
+            init: None,
+            condition: Some(condition),
+            increment: Some(increment),
+            body,
+            ..
+        }] = body(&conversion)
+        else {
+            panic!("Unexpected body {:?}", conversion.hir);
+        };
+        assert_eq!(name, "total");
+        assert!(matches!(
+            init.as_deref(),
+            Some(CHIR::Literal {
+                value: Literal::Int(0),
+                ..
+            })
+        ));
+        assert!(matches!(
+            condition.as_ref(),
+            CHIR::BinOp { op: BinOp::Lt, .. }
+        ));
+        assert!(matches!(
+            increment.as_ref(),
+            CHIR::Assign { rhs, .. } if matches!(rhs.as_ref(), CHIR::BinOp { op: BinOp::Add, .. })
+        ));
+        let [CHIR::If {
+            then_branch,
+            else_branch,
+            ..
+        }] = body.as_slice()
+        else {
+            panic!("Unexpected loop body {body:?}");
+        };
+        assert!(matches!(
+            then_branch.as_slice(),
+            [CHIR::Assign { lhs, .. }] if matches!(lhs.as_ref(), CHIR::Variable { name, .. } if name == "total")
+        ));
+        assert!(else_branch.is_empty());
+
+        // The `break` is reported rather than dropped
+        assert_eq!(conversion.diagnostics.len(), 1);
+        assert_eq!(
+            conversion.diagnostics[0].message,
+            "unsupported C statement `BreakStmt` was skipped"
+        );
+    }
+
+    #[test]
+    fn test_for_header_parts_follow_the_separators() {
+        // for (i = 0; ; i = i + 1) {}
+        let assign = |value| op("BinaryOperator", "=", vec![var("i"), value]);
+        let for_stmt = |header: Option<&str>| {
+            let ast = node(
+                "ForStmt",
+                vec![
+                    assign(int("0")),
+                    assign(op("BinaryOperator", "+", vec![var("i"), int("1")])),
+                    node("CompoundStmt", vec![]),
+                ],
+            );
+            match header {
+                Some(header) => with(ast, "header", header),
+                None => ast,
+            }
+        };
+
+        let conversion = function(vec![for_stmt(Some("init,increment"))]);
+        assert!(matches!(
+            body(&conversion),
+            [CHIR::For {
+                init: Some(_),
+                condition: None,
+                increment: Some(increment),
+                ..
+            }] if matches!(increment.as_ref(), CHIR::Assign { rhs, .. } if matches!(rhs.as_ref(), CHIR::BinOp { op: BinOp::Add, .. }))
+        ));
+
+        // Without the separators two parts could be any two of the three
+        let conversion = function(vec![for_stmt(None)]);
+        assert!(body(&conversion).is_empty());
+        assert_eq!(
+            conversion.diagnostics[0].message,
+            "unsupported C `for` header whose parts cannot be told apart; the statement containing it was skipped"
+        );
+    }
+
+    #[test]
+    fn test_function_visibility_follows_linkage() {
+        let function = |linkage: &str| {
+            let mut ast = with(node("FunctionDecl", vec![]), "linkage", linkage);
+            ast.name = Some("f".to_string());
+            convert(&ast).unwrap().hir
+        };
+        assert!(matches!(
+            function("internal"),
+            CHIR::Function {
+                storage_class: StorageClass::Static,
+                visibility: Visibility::Private,
+                ..
+            }
+        ));
+        assert!(matches!(
+            function("external"),
+            CHIR::Function {
+                storage_class: StorageClass::None,
+                visibility: Visibility::Public,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_convert_do_while() {
+        let call = || node("CallExpr", vec![var("step")]);
+        let conversion = function(vec![
+            node("DoStmt", vec![node("CompoundStmt", vec![call()]), int("0")]),
+            node("DoStmt", vec![call(), var("more")]),
+        ]);
+        assert!(matches!(
+            body(&conversion),
+            [CHIR::Call { .. }, CHIR::Call { .. }, CHIR::While { body, .. }]
+                if matches!(body.as_slice(), [CHIR::Call { .. }])
+        ));
+    }
+
+    #[test]
+    fn test_convert_pointers_and_casts() {
+        // return (PyObject *)&self->ob_item[*index];
+        let mut member = with(node("MemberRefExpr", vec![var("self")]), "operator", "->");
+        member.name = Some("ob_item".to_string());
+        let element = node(
+            "ArraySubscriptExpr",
+            vec![member, op("UnaryOperator", "*", vec![var("index")])],
+        );
+        let cast = with(
+            node(
+                "CStyleCastExpr",
+                vec![
+                    node("TypeRef", vec![]),
+                    op("UnaryOperator", "&", vec![element]),
+                ],
+            ),
+            "type",
+            "PyObject *",
+        );
+        let conversion = function(vec![node(
+            "ReturnStmt",
+            vec![node("ParenExpr", vec![cast])],
+        )]);
+
+        let [CHIR::Return {
+            value: Some(value), ..
+        }] = body(&conversion)
+        else {
+            panic!("Unexpected body {:?}", conversion.hir);
+        };
+        let CHIR::Cast {
+            target_type, expr, ..
+        } = value.as_ref()
+        else {
+            panic!("Expected a cast, got {value:?}");
+        };
+        assert!(matches!(target_type, Type::C(CType::CPython(_))));
+        let CHIR::AddrOf { var, .. } = expr.as_ref() else {
+            panic!("Expected address-of, got {expr:?}");
+        };
+        assert!(matches!(
+            var.as_ref(),
+            CHIR::ArraySubscript { array, index, .. }
+                if matches!(array.as_ref(), CHIR::FieldAccess { field, is_pointer: true, .. } if field == "ob_item")
+                    && matches!(index.as_ref(), CHIR::Deref { .. })
+        ));
+    }
+
+    #[test]
+    fn test_increments_of_either_fixity() {
+        // --n; n--; x = n++;
+        let conversion = function(vec![
+            op("UnaryOperator", "--", vec![var("n")]),
+            op("UnaryOperator", "post--", vec![var("n")]),
+            op(
+                "BinaryOperator",
+                "=",
+                vec![var("x"), op("UnaryOperator", "post++", vec![var("n")])],
+            ),
+        ]);
+
+        let decrement = |statement: &CHIR| matches!(statement, CHIR::Assign { rhs, .. } if matches!(rhs.as_ref(), CHIR::BinOp { op: BinOp::Sub, .. }));
+        let [prefix, postfix] = body(&conversion) else {
+            panic!("Unexpected body {:?}", conversion.hir);
+        };
+        assert!(decrement(prefix) && decrement(postfix));
+        assert_eq!(
+            conversion.diagnostics[0].message,
+            "unsupported C operator `++` inside an expression; the statement containing it was skipped"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_expression_skips_its_statement() {
+        let mut ternary = node("ConditionalOperator", vec![var("a"), var("b"), var("c")]);
+        ternary.location = Some(spydecy_hir::SourceLocation::new(
+            "list.c".to_string(),
+            7,
+            9,
+            spydecy_hir::Language::C,
+        ));
+        let conversion = function(vec![
+            op("BinaryOperator", "=", vec![var("x"), ternary]),
+            node("ReturnStmt", vec![var("x")]),
+        ]);
+
+        assert!(matches!(body(&conversion), [CHIR::Return { .. }]));
+        let diagnostic = &conversion.diagnostics[0];
+        assert_eq!(
+            diagnostic.to_string(),
+            "warning: unsupported C expression `ConditionalOperator`; the statement containing \
+             it was skipped\n  --> list.c:7:9"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_declarations_are_reported() {
+        let ast = node(
+            "TranslationUnit",
+            vec![
+                node("macro definition", vec![]),
+                // A forward declaration adds nothing
+                node("StructDecl", vec![]),
+                node("StructDecl", vec![node("FieldDecl", vec![])]),
+                node("VarDecl", vec![]),
+                node("FunctionDecl", vec![]),
+            ],
+        );
+        let conversion = convert(&ast).unwrap();
+        let messages: Vec<_> = conversion
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "unsupported C anonymous `StructDecl` was skipped",
+                "unsupported C declaration `VarDecl` was skipped"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_type_declarations() {
+        let named = |mut ast: CAST, name: &str| {
+            ast.name = Some(name.to_string());
+            ast
+        };
+        let field = |name: &str, type_name: &str| {
+            with(named(node("FieldDecl", vec![]), name), "type", type_name)
+        };
+        let ast = node(
+            "TranslationUnit",
+            vec![
+                named(
+                    node(
+                        "StructDecl",
+                        vec![field("value", "long"), field("next", "struct node *")],
+                    ),
+                    "node",
+                ),
+                named(
+                    node(
+                        "StructDecl",
+                        vec![
+                            field("ob_item", "PyObject **"),
+                            field("allocated", "Py_ssize_t"),
+                        ],
+                    ),
+                    "",
+                ),
+                with(
+                    named(node("TypedefDecl", vec![]), "PyListObject"),
+                    "type",
+                    "PyListObject",
+                ),
+                with(
+                    named(node("TypedefDecl", vec![]), "node_t"),
+                    "type",
+                    "struct node",
+                ),
+                node(
+                    "EnumDecl",
+                    vec![
+                        with(named(node("EnumConstantDecl", vec![]), "RED"), "value", "2"),
+                        named(node("EnumConstantDecl", vec![]), "GREEN"),
+                    ],
+                ),
+            ],
+        );
+        let conversion = convert(&ast).unwrap();
+        assert!(
+            conversion.diagnostics.is_empty(),
+            "{:?}",
+            conversion.diagnostics
+        );
+        let CHIR::TranslationUnit { declarations, .. } = &conversion.hir else {
+            panic!("Expected a translation unit");
+        };
+        let [CHIR::Struct { name, fields, .. }, CHIR::Struct {
+            name: list_name, ..
+        }, CHIR::VarDecl {
+            var_type: list_type,
+            storage_class: StorageClass::Typedef,
+            ..
+        }, CHIR::VarDecl { var_type, .. }, CHIR::Enum { constants, .. }] = declarations.as_slice()
+        else {
+            panic!("Unexpected declarations {declarations:?}");
+        };
+        assert_eq!(name, "node");
+        assert_eq!(
+            fields[1].field_type,
+            Type::C(CType::Pointer(Box::new(CType::Struct("node".to_string()))))
+        );
+        assert_eq!(list_name, "PyListObject");
+        assert_eq!(
+            *list_type,
+            Type::C(CType::Struct("PyListObject".to_string()))
+        );
+        assert_eq!(*var_type, Type::C(CType::Struct("node".to_string())));
+        assert_eq!(
+            constants,
+            &[
+                EnumConstant {
+                    name: "RED".to_string(),
+                    value: 2
+                },
+                EnumConstant {
+                    name: "GREEN".to_string(),
+                    value: 3
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_included_functions_are_skipped() {
+        let located = |mut ast: CAST, file: &str| {
+            ast.location = Some(spydecy_hir::SourceLocation::new(
+                file.to_string(),
+                1,
+                1,
+                spydecy_hir::Language::C,
+            ));
+            ast
+        };
+        let ast = located(
+            node(
+                "TranslationUnit",
+                vec![
+                    located(node("FunctionDecl", vec![]), "Python.h"),
+                    located(node("VarDecl", vec![]), "Python.h"),
+                    located(node("FunctionDecl", vec![]), "list.c"),
+                ],
+            ),
+            "list.c",
+        );
+        let conversion = convert(&ast).unwrap();
+        assert!(conversion.diagnostics.is_empty());
+        let CHIR::TranslationUnit { declarations, .. } = &conversion.hir else {
+            panic!("Expected a translation unit");
+        };
+        assert!(matches!(
+            declarations.as_slice(),
+            [CHIR::Function { meta, .. }]
+                if meta.source.as_ref().is_some_and(|source| source.file == "list.c")
+        ));
+    }
+
+    #[test]
+    fn test_parse_literals() {
+        assert_eq!(parse_integer("42"), Some(Literal::Int(42)));
+        assert_eq!(parse_integer("0x2Au"), Some(Literal::UInt(42)));
+        assert_eq!(parse_integer("052L"), Some(Literal::Int(42)));
+        assert_eq!(parse_integer("0"), Some(Literal::Int(0)));
+        assert_eq!(
+            parse_integer("18446744073709551615"),
+            Some(Literal::UInt(u64::MAX))
+        );
+        assert_eq!(parse_float("1e-3f"), Some(Literal::Float(1e-3)));
+        assert_eq!(parse_char("'\\n'"), Some(Literal::Char('\n')));
+        assert_eq!(parse_char("'\\x41'"), Some(Literal::Char('A')));
+        assert_eq!(
+            parse_string(r#""pop from \"empty\" " "list\0""#),
+            Some(Literal::Str("pop from \"empty\" list\0".to_string()))
+        );
+    }
+
     #[test]
     fn test_parse_basic_types() {
         assert!(matches!(
@@ -208,4 +1677,35 @@ mod tests {
         let pylist = parse_type(&Some("PyListObject*".to_string()));
         assert!(matches!(pylist, Type::C(CType::CPython(_))));
     }
+
+    #[test]
+    fn test_parse_derived_types() {
+        let c_type = |spelling: &str| match parse_type(&Some(spelling.to_string())) {
+            Type::C(c_type) => Some(c_type),
+            _ => None,
+        };
+        let pointer = |pointee| CType::Pointer(Box::new(pointee));
+        assert_eq!(c_type("const char *"), Some(pointer(CType::Char)));
+        assert_eq!(
+            c_type("struct node *const"),
+            Some(pointer(CType::Struct("node".to_string())))
+        );
+        assert_eq!(
+            c_type("count_t"),
+            Some(CType::Typedef("count_t".to_string()))
+        );
+        assert_eq!(c_type("enum color"), Some(CType::Int));
+        assert_eq!(
+            c_type("unsigned long [2][3]"),
+            Some(CType::Array {
+                element: Box::new(CType::Array {
+                    element: Box::new(CType::Long),
+                    size: Some(3),
+                }),
+                size: Some(2),
+            })
+        );
+        assert_eq!(c_type("struct (unnamed at list.c:3:9)"), None);
+        assert_eq!(c_type("int (*)(int)"), None);
+    }
 }
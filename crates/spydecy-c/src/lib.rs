@@ -27,11 +27,13 @@
     clippy::wildcard_imports
 )]
 
+pub mod config;
 pub mod cpython;
 pub mod hir_converter;
 pub mod parser;
 
 use anyhow::Result;
+use config::CParserConfig;
 use spydecy_hir::c::CHIR;
 use std::path::Path;
 
@@ -45,6 +47,36 @@ pub fn parse_c(source: &str, filename: &str) -> Result<CHIR> {
     hir_converter::convert_to_hir(&ast)
 }
 
+/// Parse C source code into HIR, invoking clang with the given flags
+///
+/// # Errors
+///
+/// Returns an error if the compilation database cannot be read, or the C
+/// code cannot be parsed or converted to HIR
+pub fn parse_c_with(config: &CParserConfig, source: &str, filename: &str) -> Result<CHIR> {
+    let ast = parser::parse_with(config, source, filename)?;
+    hir_converter::convert_to_hir(&ast)
+}
+
+/// Parse C source code into HIR, invoking clang with the given flags, along
+/// with clang's diagnostics and the constructs that had to be skipped
+///
+/// # Errors
+///
+/// Returns an error if the compilation database cannot be read, clang
+/// reports a fatal error, or the C code cannot be converted to HIR
+pub fn parse_c_with_diagnostics(
+    config: &CParserConfig,
+    source: &str,
+    filename: &str,
+) -> Result<hir_converter::Conversion> {
+    let parsed =
+        parser::CParser::with_config(config.clone())?.parse_with_diagnostics(source, filename)?;
+    let mut conversion = hir_converter::convert(&parsed.ast)?;
+    conversion.diagnostics.splice(0..0, parsed.diagnostics);
+    Ok(conversion)
+}
+
 /// Parse C file into HIR
 ///
 /// # Errors
@@ -90,4 +122,193 @@ list_length(PyListObject *self) {
             result.err()
         );
     }
+
+    #[test]
+    fn test_parse_statements_and_expressions() {
+        let source = r"
+long count(long *items, long n, long value) {
+    long found = 0;
+    for (long i = 0; i < n; i++) {
+        if (items[i] == value) {
+            found += 1;
+        }
+    }
+    return found;
+}
+";
+        let conversion =
+            parse_c_with_diagnostics(&CParserConfig::default(), source, "count.c").unwrap();
+        assert!(
+            conversion.diagnostics.is_empty(),
+            "{:?}",
+            conversion.diagnostics
+        );
+
+        let CHIR::TranslationUnit { declarations, .. } = &conversion.hir else {
+            panic!("Expected a translation unit");
+        };
+        let [CHIR::Function { body, .. }] = declarations.as_slice() else {
+            panic!("Expected one function, got {declarations:?}");
+        };
+        let [CHIR::VarDecl { .. }, CHIR::For {
+            init: Some(_),
+            condition: Some(_),
+            increment: Some(_),
+            body: loop_body,
+            ..
+        }, CHIR::Return { .. }] = body.as_slice()
+        else {
+            panic!("Unexpected body {body:?}");
+        };
+        assert!(matches!(loop_body.as_slice(), [CHIR::If { .. }]));
+    }
+
+    #[test]
+    #[allow(clippy::too_many_lines)]
+    fn test_parse_project_file_end_to_end() {
+        use spydecy_hir::{
+            c::{BinOp, Literal, StorageClass},
+            diagnostics::Severity,
+        };
+
+        // The header's function is not the input's, but its typedef is
+        let project = tempfile::tempdir().unwrap();
+        std::fs::create_dir(project.path().join("include")).unwrap();
+        std::fs::write(
+            project.path().join("include/count.h"),
+            "typedef long count_t;\nstatic inline count_t twice(count_t n) { return n + n; }\n",
+        )
+        .unwrap();
+        let database = project.path().join("compile_commands.json");
+        std::fs::write(
+            &database,
+            serde_json::json!([{
+                "directory": project.path(),
+                "file": "sum.c",
+                "arguments": ["cc", "-Iinclude", "-DSTEP=2", "-c", "sum.c"],
+            }])
+            .to_string(),
+        )
+        .unwrap();
+        let filename = project.path().join("sum.c").display().to_string();
+        let source = r#"#include "count.h"
+
+count_t sum(const count_t *items, count_t n) {
+    count_t total = 0;
+    for (count_t i = 0; ; i++) {
+        if (i >= n) return total;
+        total += items[i] * STEP;
+    }
+}
+
+static count_t bump(count_t x) {
+    double scale = 1.5;
+    char first = 'a';
+    --x;
+    count_t old = x++;
+    return x;
+}
+
+int broken(void) { return 0 }
+"#;
+
+        let parser =
+            parser::CParser::with_config(CParserConfig::default().with_compile_commands(&database))
+                .unwrap();
+        let parsed = parser.parse_with_diagnostics(source, &filename).unwrap();
+        let conversion = hir_converter::convert(&parsed.ast).unwrap();
+
+        assert!(
+            parsed
+                .diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.severity == Severity::Error
+                    && diagnostic.message == "expected ';' after return statement"),
+            "{:?}",
+            parsed.diagnostics
+        );
+        assert_eq!(
+            conversion
+                .diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.message.as_str())
+                .collect::<Vec<_>>(),
+            ["unsupported C operator `++` inside an expression; the statement containing it was skipped"]
+        );
+
+        let CHIR::TranslationUnit { declarations, .. } = &conversion.hir else {
+            panic!("Expected a translation unit");
+        };
+        let functions: Vec<_> = declarations
+            .iter()
+            .filter_map(|declaration| match declaration {
+                CHIR::Function {
+                    name,
+                    body,
+                    storage_class,
+                    ..
+                } => Some((name.as_str(), body, storage_class)),
+                _ => None,
+            })
+            .collect();
+        let [("sum", sum, StorageClass::None), ("bump", bump, StorageClass::Static), ("broken", ..)] =
+            functions.as_slice()
+        else {
+            panic!("Unexpected functions {functions:?}");
+        };
+
+        // `i++` with no condition is the increment; STEP comes from the
+        // compilation database
+        let [CHIR::VarDecl { .. }, CHIR::For {
+            init: Some(_),
+            condition: None,
+            increment: Some(increment),
+            body: loop_body,
+            ..
+        }] = sum.as_slice()
+        else {
+            panic!("Unexpected body {sum:?}");
+        };
+        assert!(matches!(
+            increment.as_ref(),
+            CHIR::Assign { rhs, .. } if matches!(rhs.as_ref(), CHIR::BinOp { op: BinOp::Add, .. })
+        ));
+        let [CHIR::If { .. }, CHIR::Assign { rhs: total, .. }] = loop_body.as_slice() else {
+            panic!("Unexpected loop body {loop_body:?}");
+        };
+        let CHIR::BinOp {
+            op: BinOp::Add,
+            right: product,
+            ..
+        } = total.as_ref()
+        else {
+            panic!("Unexpected sum {total:?}");
+        };
+        assert!(matches!(
+            product.as_ref(),
+            CHIR::BinOp { op: BinOp::Mul, right, .. }
+                if matches!(right.as_ref(), CHIR::Literal { value: Literal::Int(2), .. })
+        ));
+
+        let literal = |statement: &CHIR| match statement {
+            CHIR::VarDecl {
+                init: Some(init), ..
+            } => match init.as_ref() {
+                CHIR::Literal { value, .. } => Some(value.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let [scale, first, CHIR::Assign { rhs: decrement, .. }, CHIR::Return { .. }] =
+            bump.as_slice()
+        else {
+            panic!("Unexpected body {bump:?}");
+        };
+        assert_eq!(literal(scale), Some(Literal::Float(1.5)));
+        assert_eq!(literal(first), Some(Literal::Char('a')));
+        assert!(matches!(
+            decrement.as_ref(),
+            CHIR::BinOp { op: BinOp::Sub, .. }
+        ));
+    }
 }
@@ -3,11 +3,17 @@
 //! This module provides C parsing functionality using LLVM/Clang bindings.
 //! Following decy's approach for production-grade C parsing.
 
+use crate::config::CParserConfig;
 use anyhow::{Context, Result};
 use clang_sys::*;
 use serde::{Deserialize, Serialize};
+use spydecy_hir::{
+    diagnostics::{Diagnostic, FixIt, Severity},
+    Language, SourceLocation,
+};
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
+use std::fmt::Write;
 use std::ptr;
 
 /// Simplified C AST representation
@@ -23,10 +29,19 @@ pub struct CAST {
     pub params: Vec<CParam>,
     /// Child nodes
     pub children: Vec<CAST>,
-    /// Attributes
+    /// Attributes: `docs` on declarations; `type` on expressions,
+    /// variables, fields and typedefs (the type they name); `operator` on
+    /// operators and member accesses (`.` or `->`; `post++` and `post--`
+    /// for postfix increments and decrements); `value` on literals,
+    /// spelled as in C source, and on enum constants; `linkage` on
+    /// functions (`internal` or `external`); `header` on `for` statements,
+    /// the header parts their children fill (see [`for_header`])
     pub attributes: HashMap<String, String>,
     /// Is this a CPython API node?
     pub is_cpython_api: bool,
+    /// Source span of the cursor
+    #[serde(default)]
+    pub location: Option<SourceLocation>,
 }
 
 /// C function parameter
@@ -50,6 +65,7 @@ impl CAST {
             children: Vec::new(),
             attributes: HashMap::new(),
             is_cpython_api: false,
+            location: None,
         }
     }
 }
@@ -57,6 +73,7 @@ impl CAST {
 /// C parser using clang-sys
 pub struct CParser {
     index: CXIndex,
+    config: CParserConfig,
 }
 
 impl CParser {
@@ -71,19 +88,70 @@ impl CParser {
         if index.is_null() {
             anyhow::bail!("Failed to create clang index");
         }
-        Ok(Self { index })
+        Ok(Self {
+            index,
+            config: CParserConfig::default(),
+        })
+    }
+
+    /// Create a C parser invoking clang with the given flags
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the clang index cannot be created
+    pub fn with_config(config: CParserConfig) -> Result<Self> {
+        Ok(Self {
+            config,
+            ..Self::new()?
+        })
     }
 
     /// Parse C source code
     ///
+    /// Clang's diagnostics are only logged; use
+    /// [`Self::parse_with_diagnostics`] to get them.
+    ///
     /// # Errors
     ///
-    /// Returns an error if parsing fails
+    /// Returns an error if parsing fails, or the configured compilation
+    /// database cannot be read
     pub fn parse(&self, source: &str, filename: &str) -> Result<CAST> {
+        let parsed = self.parse_with_diagnostics(source, filename)?;
+        for diagnostic in &parsed.diagnostics {
+            tracing::warn!("{diagnostic}");
+        }
+        Ok(parsed.ast)
+    }
+
+    /// Parse C source code, along with the warnings and errors clang
+    /// reported about it
+    ///
+    /// Clang recovers from most errors, such as unknown type names, so they
+    /// are returned with the AST; only a fatal error, such as a missing
+    /// `#include` file, fails the parse.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing fails or clang reports a fatal error, or
+    /// the configured compilation database cannot be read
+    pub fn parse_with_diagnostics(&self, source: &str, filename: &str) -> Result<ParsedSource> {
         if source.trim().is_empty() {
-            return Ok(CAST::new("TranslationUnit".to_string()));
+            return Ok(ParsedSource {
+                ast: CAST::new("TranslationUnit".to_string()),
+                diagnostics: Vec::new(),
+            });
         }
 
+        let arguments = self
+            .config
+            .arguments(filename)?
+            .into_iter()
+            .map(CString::new)
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to create argument CString")?;
+        let argument_count =
+            std::os::raw::c_int::try_from(arguments.len()).context("Too many clang arguments")?;
+        let argument_ptrs: Vec<_> = arguments.iter().map(|argument| argument.as_ptr()).collect();
         let filename_cstr = CString::new(filename).context("Failed to create filename CString")?;
         let source_cstr = CString::new(source).context("Failed to create source CString")?;
 
@@ -100,8 +168,8 @@ impl CParser {
             clang_parseTranslationUnit2(
                 self.index,
                 filename_cstr.as_ptr(),
-                ptr::null(),
-                0,
+                argument_ptrs.as_ptr(),
+                argument_count,
                 &unsaved_file as *const CXUnsavedFile as *mut CXUnsavedFile,
                 1,
                 CXTranslationUnit_DetailedPreprocessingRecord,
@@ -117,11 +185,20 @@ impl CParser {
             anyhow::bail!("Translation unit is null");
         }
 
+        let diagnostics = match unsafe { collect_diagnostics(tu) } {
+            Ok(diagnostics) => diagnostics,
+            Err(error) => {
+                unsafe { clang_disposeTranslationUnit(tu) };
+                return Err(error);
+            }
+        };
+
         // Get the root cursor
         let cursor = unsafe { clang_getTranslationUnitCursor(tu) };
 
         // Visit the AST
         let mut root = CAST::new("TranslationUnit".to_string());
+        root.location = Some(SourceLocation::new(filename.to_string(), 1, 1, Language::C));
         unsafe {
             clang_visitChildren(cursor, visit_node, &mut root as *mut CAST as CXClientData);
         }
@@ -131,10 +208,22 @@ impl CParser {
             clang_disposeTranslationUnit(tu);
         }
 
-        Ok(root)
+        Ok(ParsedSource {
+            ast: root,
+            diagnostics,
+        })
     }
 }
 
+/// C source parsed by clang
+#[derive(Debug, Clone)]
+pub struct ParsedSource {
+    /// AST of the translation unit
+    pub ast: CAST,
+    /// Notes, warnings and errors, in the order clang reported them
+    pub diagnostics: Vec<Diagnostic>,
+}
+
 impl Drop for CParser {
     fn drop(&mut self) {
         if !self.index.is_null() {
@@ -163,6 +252,7 @@ extern "C" fn visit_node(
         let node_type = to_rust_string(kind_spelling);
 
         let mut node = CAST::new(node_type);
+        node.location = cursor_location(cursor);
 
         // Get node name if available
         let cursor_spelling = clang_getCursorSpelling(cursor);
@@ -175,8 +265,48 @@ extern "C" fn visit_node(
             }
         }
 
-        // For function declarations, get return type and parameters
+        // Keep the doc comment attached to declarations
+        let comment = to_rust_string(clang_Cursor_getRawCommentText(cursor));
+        if let Some(docs) = clean_doc_comment(&comment) {
+            node.attributes.insert("docs".to_string(), docs);
+        }
+
+        if clang_isExpression(kind) != 0 {
+            record_expression(cursor, kind, &mut node);
+        } else if kind == CXCursor_VarDecl || kind == CXCursor_FieldDecl {
+            record_type(cursor, &mut node);
+        } else if kind == CXCursor_TypedefDecl {
+            let aliased = clang_getTypedefDeclUnderlyingType(cursor);
+            node.attributes.insert(
+                "type".to_string(),
+                to_rust_string(clang_getTypeSpelling(aliased)),
+            );
+        } else if kind == CXCursor_EnumConstantDecl {
+            node.attributes.insert(
+                "value".to_string(),
+                clang_getEnumConstantDeclValue(cursor).to_string(),
+            );
+        } else if kind == CXCursor_ForStmt {
+            if let Some(header) = for_header(cursor) {
+                node.attributes.insert("header".to_string(), header);
+            }
+        }
+
+        // For function declarations, get linkage, return type and parameters
         if kind == CXCursor_FunctionDecl {
+            let linkage = clang_getCursorLinkage(cursor);
+            let linkage = if linkage == CXLinkage_Internal {
+                Some("internal")
+            } else if linkage == CXLinkage_External {
+                Some("external")
+            } else {
+                None
+            };
+            if let Some(linkage) = linkage {
+                node.attributes
+                    .insert("linkage".to_string(), linkage.to_string());
+            }
+
             let func_type = clang_getCursorType(cursor);
             let return_type = clang_getResultType(func_type);
             let return_type_spelling = clang_getTypeSpelling(return_type);
@@ -206,6 +336,452 @@ extern "C" fn visit_node(
     }
 }
 
+/// Record the type of an expression, and the operator or value it is
+/// spelled with
+///
+/// # Safety
+///
+/// Must be called with a valid expression cursor
+unsafe fn record_expression(cursor: CXCursor, kind: CXCursorKind, node: &mut CAST) {
+    record_type(cursor, node);
+    let spelling = if kind == CXCursor_BinaryOperator
+        || kind == CXCursor_CompoundAssignOperator
+        || kind == CXCursor_UnaryOperator
+    {
+        operator_token(cursor).map(|operator| ("operator", operator))
+    } else if kind == CXCursor_MemberRefExpr {
+        first_child(cursor).map(|base| {
+            let base_type = clang_getCanonicalType(clang_getCursorType(base));
+            let operator = if base_type.kind == CXType_Pointer {
+                "->"
+            } else {
+                "."
+            };
+            ("operator", operator.to_string())
+        })
+    } else if kind == CXCursor_IntegerLiteral
+        || kind == CXCursor_FloatingLiteral
+        || kind == CXCursor_CharacterLiteral
+        || kind == CXCursor_StringLiteral
+    {
+        literal_value(cursor, kind).map(|value| ("value", value))
+    } else {
+        None
+    };
+    if let Some((key, spelling)) = spelling {
+        node.attributes.insert(key.to_string(), spelling);
+    }
+}
+
+/// Record the type of an expression or declaration
+///
+/// # Safety
+///
+/// Must be called with a valid cursor
+unsafe fn record_type(cursor: CXCursor, node: &mut CAST) {
+    let type_name = to_rust_string(clang_getTypeSpelling(clang_getCursorType(cursor)));
+    if !type_name.is_empty() {
+        node.attributes.insert("type".to_string(), type_name);
+    }
+}
+
+/// Tokens covered by a cursor
+struct Tokens {
+    tu: CXTranslationUnit,
+    data: *mut CXToken,
+    count: u32,
+}
+
+impl Tokens {
+    /// # Safety
+    ///
+    /// Must be called with a valid cursor
+    unsafe fn of(cursor: CXCursor) -> Self {
+        let tu = clang_Cursor_getTranslationUnit(cursor);
+        let mut data = ptr::null_mut();
+        let mut count = 0;
+        clang_tokenize(tu, clang_getCursorExtent(cursor), &mut data, &mut count);
+        Self { tu, data, count }
+    }
+
+    fn as_slice(&self) -> &[CXToken] {
+        if self.data.is_null() {
+            return &[];
+        }
+        // SAFETY: clang_tokenize returned `count` tokens
+        unsafe { std::slice::from_raw_parts(self.data, self.count as usize) }
+    }
+
+    /// # Safety
+    ///
+    /// Must be called with one of these tokens
+    unsafe fn spelling(&self, token: CXToken) -> String {
+        to_rust_string(clang_getTokenSpelling(self.tu, token))
+    }
+
+    /// # Safety
+    ///
+    /// Must be called with one of these tokens
+    unsafe fn offset(&self, token: CXToken) -> u32 {
+        file_offset(clang_getTokenLocation(self.tu, token))
+    }
+}
+
+impl Drop for Tokens {
+    fn drop(&mut self) {
+        if !self.data.is_null() {
+            unsafe {
+                clang_disposeTokens(self.tu, self.data, self.count);
+            }
+        }
+    }
+}
+
+/// Operator of a binary, compound assignment or unary operator: the
+/// first punctuation outside its first operand
+///
+/// `++` and `--` after their operand are recorded as `post++` and
+/// `post--`. Operators written inside a macro's expansion are not in the
+/// source and cannot be recovered.
+///
+/// # Safety
+///
+/// Must be called with a valid operator cursor
+unsafe fn operator_token(cursor: CXCursor) -> Option<String> {
+    let operand = clang_getCursorExtent(first_child(cursor)?);
+    let operand_start = file_offset(clang_getRangeStart(operand));
+    let operand_end = file_offset(clang_getRangeEnd(operand));
+    let tokens = Tokens::of(cursor);
+    let token = *tokens.as_slice().iter().find(|&&token| {
+        let offset = tokens.offset(token);
+        offset < operand_start || offset >= operand_end
+    })?;
+    let spelling = tokens.spelling(token);
+    let is_operator = clang_getTokenKind(token) == CXToken_Punctuation
+        && !matches!(spelling.as_str(), "(" | ")" | "[" | "]" | "{" | "}" | ";");
+    if !is_operator {
+        return None;
+    }
+    let postfix = matches!(spelling.as_str(), "++" | "--") && tokens.offset(token) >= operand_end;
+    Some(if postfix {
+        format!("post{spelling}")
+    } else {
+        spelling
+    })
+}
+
+/// Header parts of a `for` statement that its children fill, in order, as
+/// a comma-separated list of `init`, `condition` and `increment`
+///
+/// clang leaves out the missing parts, so each child is placed by where it
+/// starts relative to the two `;` of the header. Returns `None` when the
+/// header is not in the source, e.g. when written by a macro.
+///
+/// # Safety
+///
+/// Must be called with a valid `for` statement cursor
+unsafe fn for_header(cursor: CXCursor) -> Option<String> {
+    let tokens = Tokens::of(cursor);
+    let mut depth = 0;
+    let mut separators = Vec::new();
+    let mut end = None;
+    for &token in tokens.as_slice() {
+        if clang_getTokenKind(token) != CXToken_Punctuation {
+            continue;
+        }
+        match tokens.spelling(token).as_str() {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(tokens.offset(token));
+                    break;
+                }
+            }
+            ";" if depth == 1 => separators.push(tokens.offset(token)),
+            _ => {}
+        }
+    }
+    let (Some(end), [init_end, condition_end]) = (end, separators.as_slice()) else {
+        return None;
+    };
+    let parts: Vec<&str> = children(cursor)
+        .into_iter()
+        .map(|child| file_offset(clang_getRangeStart(clang_getCursorExtent(child))))
+        .take_while(|&start| start < end)
+        .map(|start| {
+            if start < *init_end {
+                "init"
+            } else if start < *condition_end {
+                "condition"
+            } else {
+                "increment"
+            }
+        })
+        .collect();
+    Some(parts.join(","))
+}
+
+/// Source spelling of a literal
+///
+/// Literals expanded from a macro are not in the source, so clang
+/// evaluates them instead.
+///
+/// # Safety
+///
+/// Must be called with a valid literal cursor
+unsafe fn literal_value(cursor: CXCursor, kind: CXCursorKind) -> Option<String> {
+    let tokens = Tokens::of(cursor);
+    let mut literals = tokens
+        .as_slice()
+        .iter()
+        .filter(|&&token| clang_getTokenKind(token) == CXToken_Literal)
+        .map(|&token| tokens.spelling(token));
+    let value = if kind == CXCursor_StringLiteral {
+        // Adjacent string literals are concatenated
+        let parts: Vec<_> = literals.collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    } else {
+        literals.next()
+    };
+    value.or_else(|| evaluated_literal(cursor, kind))
+}
+
+/// Spelling of the value clang evaluates a literal to
+///
+/// # Safety
+///
+/// Must be called with a valid literal cursor
+unsafe fn evaluated_literal(cursor: CXCursor, kind: CXCursorKind) -> Option<String> {
+    let result = clang_Cursor_Evaluate(cursor);
+    if result.is_null() {
+        return None;
+    }
+    let result_kind = clang_EvalResult_getKind(result);
+    let value = if result_kind == CXEval_Int {
+        let value = clang_EvalResult_getAsInt(result);
+        Some(if kind == CXCursor_CharacterLiteral {
+            format!("'\\x{value:x}'")
+        } else {
+            value.to_string()
+        })
+    } else if result_kind == CXEval_Float {
+        Some(format!("{:?}", clang_EvalResult_getAsDouble(result)))
+    } else if result_kind == CXEval_StrLiteral {
+        let value = clang_EvalResult_getAsStr(result);
+        (!value.is_null()).then(|| quote_c_string(&CStr::from_ptr(value).to_string_lossy()))
+    } else {
+        None
+    };
+    clang_EvalResult_dispose(result);
+    value
+}
+
+/// Spell a string as a C string literal
+fn quote_c_string(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            // Three digits, so a following digit is not taken as part of it
+            c if c.is_ascii_control() => {
+                let _ = write!(literal, "\\{:03o}", u32::from(c));
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// First child of a cursor
+///
+/// # Safety
+///
+/// Must be called with a valid cursor
+unsafe fn first_child(cursor: CXCursor) -> Option<CXCursor> {
+    extern "C" fn visit_first(
+        cursor: CXCursor,
+        _parent: CXCursor,
+        client_data: CXClientData,
+    ) -> CXChildVisitResult {
+        unsafe {
+            *(client_data as *mut Option<CXCursor>) = Some(cursor);
+        }
+        CXChildVisit_Break
+    }
+
+    let mut child = None;
+    clang_visitChildren(
+        cursor,
+        visit_first,
+        &mut child as *mut Option<CXCursor> as CXClientData,
+    );
+    child
+}
+
+/// Children of a cursor
+///
+/// # Safety
+///
+/// Must be called with a valid cursor
+unsafe fn children(cursor: CXCursor) -> Vec<CXCursor> {
+    extern "C" fn visit_child(
+        cursor: CXCursor,
+        _parent: CXCursor,
+        client_data: CXClientData,
+    ) -> CXChildVisitResult {
+        unsafe {
+            (*(client_data as *mut Vec<CXCursor>)).push(cursor);
+        }
+        CXChildVisit_Continue
+    }
+
+    let mut children = Vec::new();
+    clang_visitChildren(
+        cursor,
+        visit_child,
+        &mut children as *mut Vec<CXCursor> as CXClientData,
+    );
+    children
+}
+
+/// Strip the comment markers from a raw `/** */`, `/*! */`, `///` or `//!`
+/// doc comment
+fn clean_doc_comment(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let raw = raw.strip_suffix("*/").unwrap_or(raw);
+    let lines: Vec<&str> = raw
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            ["/**", "/*!", "///", "//!", "/*", "*"]
+                .iter()
+                .find_map(|marker| line.strip_prefix(marker))
+                .unwrap_or(line)
+                .trim()
+        })
+        .collect();
+
+    let start = lines.iter().position(|line| !line.is_empty())?;
+    let end = lines.iter().rposition(|line| !line.is_empty())?;
+    Some(lines[start..=end].join("\n"))
+}
+
+/// Get the source span covered by a cursor
+///
+/// # Safety
+///
+/// Must be called with a valid cursor
+unsafe fn cursor_location(cursor: CXCursor) -> Option<SourceLocation> {
+    range_location(clang_getCursorExtent(cursor))
+}
+
+/// Span of a source range
+///
+/// # Safety
+///
+/// Must be called with a valid source range
+unsafe fn range_location(range: CXSourceRange) -> Option<SourceLocation> {
+    let (file, line, column) = spelling_location(clang_getRangeStart(range))?;
+    let (_, end_line, end_column) = spelling_location(clang_getRangeEnd(range))?;
+    Some(SourceLocation::new(file, line, column, Language::C).with_end(end_line, end_column))
+}
+
+/// Diagnostics clang reported about a translation unit
+///
+/// # Safety
+///
+/// Must be called with a valid translation unit
+///
+/// # Errors
+///
+/// Returns the first fatal error
+unsafe fn collect_diagnostics(tu: CXTranslationUnit) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    for index in 0..clang_getNumDiagnostics(tu) {
+        let cx_diagnostic = clang_getDiagnostic(tu, index);
+        let fatal = clang_getDiagnosticSeverity(cx_diagnostic) == CXDiagnostic_Fatal;
+        let diagnostic = convert_diagnostic(cx_diagnostic);
+        clang_disposeDiagnostic(cx_diagnostic);
+        match diagnostic {
+            Some(diagnostic) if fatal => anyhow::bail!("Failed to parse C source: {diagnostic}"),
+            Some(diagnostic) => diagnostics.push(diagnostic),
+            None => {}
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Convert a clang diagnostic, unless it is ignored
+///
+/// # Safety
+///
+/// Must be called with a valid diagnostic
+unsafe fn convert_diagnostic(cx_diagnostic: CXDiagnostic) -> Option<Diagnostic> {
+    let severity = clang_getDiagnosticSeverity(cx_diagnostic);
+    let severity = if severity == CXDiagnostic_Note {
+        Severity::Note
+    } else if severity == CXDiagnostic_Warning {
+        Severity::Warning
+    } else if severity == CXDiagnostic_Error || severity == CXDiagnostic_Fatal {
+        Severity::Error
+    } else {
+        return None;
+    };
+    let message = to_rust_string(clang_getDiagnosticSpelling(cx_diagnostic));
+    let location = spelling_location(clang_getDiagnosticLocation(cx_diagnostic))
+        .map(|(file, line, column)| SourceLocation::new(file, line, column, Language::C));
+    let mut diagnostic = Diagnostic::new(severity, message).with_location(location);
+    for index in 0..clang_getDiagnosticNumFixIts(cx_diagnostic) {
+        let mut range = clang_getNullRange();
+        let replacement =
+            to_rust_string(clang_getDiagnosticFixIt(cx_diagnostic, index, &mut range));
+        diagnostic = diagnostic.with_fix_it(FixIt {
+            location: range_location(range),
+            replacement,
+        });
+    }
+    Some(diagnostic)
+}
+
+/// Byte offset of a source location in its file
+///
+/// # Safety
+///
+/// Must be called with a valid source location
+unsafe fn file_offset(location: CXSourceLocation) -> u32 {
+    let mut offset = 0;
+    clang_getSpellingLocation(
+        location,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        &mut offset,
+    );
+    offset
+}
+
+/// Resolve a source location to its file, line and column
+///
+/// # Safety
+///
+/// Must be called with a valid source location
+unsafe fn spelling_location(location: CXSourceLocation) -> Option<(String, usize, usize)> {
+    let mut file = ptr::null_mut();
+    let mut line = 0;
+    let mut column = 0;
+    clang_getSpellingLocation(location, &mut file, &mut line, &mut column, ptr::null_mut());
+    if file.is_null() || line == 0 {
+        return None;
+    }
+    let file = to_rust_string(clang_getFileName(file));
+    Some((file, line as usize, column as usize))
+}
+
 /// Convert CXString to Rust String
 ///
 /// # Safety
@@ -261,6 +837,17 @@ pub fn parse(source: &str, filename: &str) -> Result<CAST> {
     parser.parse(source, filename)
 }
 
+/// Parse C source code into AST, invoking clang with the given flags
+///
+/// # Errors
+///
+/// Returns an error if parsing fails, or the configured compilation database
+/// cannot be read
+pub fn parse_with(config: &CParserConfig, source: &str, filename: &str) -> Result<CAST> {
+    let parser = CParser::with_config(config.clone())?;
+    parser.parse(source, filename)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,6 +890,214 @@ list_length(PyListObject *self) {
         assert_eq!(ast.node_type, "TranslationUnit");
     }
 
+    #[test]
+    fn test_parse_records_locations() {
+        let source = "int add(int a, int b) {\n    return a + b;\n}\n";
+        let ast = parse(source, "add.c").unwrap();
+
+        let function = &ast.children[0];
+        let location = function.location.as_ref().expect("FunctionDecl location");
+        assert_eq!(location.file, "add.c");
+        assert_eq!((location.line, location.column), (1, 1));
+        assert_eq!((location.end_line, location.end_column), (3, 2));
+        assert_eq!(location.language, Language::C);
+    }
+
+    #[test]
+    fn test_parse_records_doc_comments() {
+        let source = "/**\n * Add two numbers.\n *\n * Never overflows.\n */\nint add(int a, int b) {\n    return a + b;\n}\n\n/// Subtract\nint sub(int a, int b) {\n    return a - b;\n}\n";
+        let ast = parse(source, "math.c").unwrap();
+
+        assert_eq!(
+            ast.children[0].attributes.get("docs").map(String::as_str),
+            Some("Add two numbers.\n\nNever overflows.")
+        );
+        assert_eq!(
+            ast.children[1].attributes.get("docs").map(String::as_str),
+            Some("Subtract")
+        );
+    }
+
+    #[test]
+    fn test_clean_doc_comment() {
+        assert_eq!(
+            clean_doc_comment("/*! Module docs */").as_deref(),
+            Some("Module docs")
+        );
+        assert_eq!(
+            clean_doc_comment("//! First\n//! Second").as_deref(),
+            Some("First\nSecond")
+        );
+        assert_eq!(clean_doc_comment(""), None);
+        assert_eq!(clean_doc_comment("/** */"), None);
+    }
+
+    /// Depth-first search for the first node of a kind
+    fn find<'a>(ast: &'a CAST, node_type: &str) -> &'a CAST {
+        fn search<'a>(ast: &'a CAST, node_type: &str) -> Option<&'a CAST> {
+            if ast.node_type == node_type {
+                return Some(ast);
+            }
+            ast.children
+                .iter()
+                .find_map(|child| search(child, node_type))
+        }
+        search(ast, node_type).unwrap_or_else(|| panic!("No {node_type} in {ast:?}"))
+    }
+
+    fn attribute<'a>(ast: &'a CAST, key: &str) -> Option<&'a str> {
+        ast.attributes.get(key).map(String::as_str)
+    }
+
+    #[test]
+    fn test_parse_records_operators_and_types() {
+        let source = r"
+struct list { long size; };
+
+long f(struct list *self, long *items, long i) {
+    i += 1;
+    return -items[i++] << self->size;
+}
+";
+        let ast = parse(source, "ops.c").unwrap();
+
+        let shift = find(&ast, "BinaryOperator");
+        assert_eq!(attribute(shift, "operator"), Some("<<"));
+        assert_eq!(attribute(shift, "type"), Some("long"));
+        assert_eq!(
+            attribute(find(&ast, "CompoundAssignOperator"), "operator"),
+            Some("+=")
+        );
+        let negate = find(&ast, "UnaryOperator");
+        assert_eq!(attribute(negate, "operator"), Some("-"));
+        assert_eq!(
+            attribute(find(&negate.children[0], "UnaryOperator"), "operator"),
+            Some("post++")
+        );
+        let member = find(&ast, "MemberRefExpr");
+        assert_eq!(member.name.as_deref(), Some("size"));
+        assert_eq!(attribute(member, "operator"), Some("->"));
+    }
+
+    #[test]
+    fn test_parse_records_for_headers_and_linkage() {
+        let source = r"
+static long sum(long n) {
+    long total = 0;
+    for (long i = 0; ; i = i + 1) {
+        if (i >= n) return total;
+        total += i;
+    }
+}
+
+long run(long n) {
+    for (; n > 0;) n -= 1;
+    return sum(n);
+}
+";
+        let ast = parse(source, "loops.c").unwrap();
+
+        let [sum, run] = ast.children.as_slice() else {
+            panic!("Unexpected declarations {:?}", ast.children);
+        };
+        assert_eq!(attribute(sum, "linkage"), Some("internal"));
+        assert_eq!(attribute(run, "linkage"), Some("external"));
+        assert_eq!(
+            attribute(find(sum, "ForStmt"), "header"),
+            Some("init,increment")
+        );
+        assert_eq!(attribute(find(run, "ForStmt"), "header"), Some("condition"));
+    }
+
+    #[test]
+    fn test_parse_records_literal_values() {
+        let source = r#"
+#define LIMIT 0x10u
+
+const char *f(void) {
+    char c = 'a';
+    double d = 1.5e3;
+    unsigned n = LIMIT;
+    return "pop from " "empty list";
+}
+"#;
+        let ast = parse(source, "literals.c").unwrap();
+
+        let char_decl = find(&ast, "VarDecl");
+        assert_eq!(attribute(char_decl, "type"), Some("char"));
+        assert_eq!(
+            attribute(find(&ast, "CharacterLiteral"), "value"),
+            Some("'a'")
+        );
+        assert_eq!(
+            attribute(find(&ast, "FloatingLiteral"), "value"),
+            Some("1.5e3")
+        );
+        // Expanded from the macro, so evaluated
+        assert_eq!(attribute(find(&ast, "IntegerLiteral"), "value"), Some("16"));
+        assert_eq!(
+            attribute(find(&ast, "StringLiteral"), "value"),
+            Some("\"pop from \" \"empty list\"")
+        );
+    }
+
+    #[test]
+    fn test_parse_with_include_dirs_and_defines() {
+        let include = tempfile::tempdir().unwrap();
+        std::fs::write(include.path().join("count.h"), "typedef long count_t;\n").unwrap();
+        let source = r#"
+#include "count.h"
+#ifdef WIDE
+count_t zero(void) { return 0; }
+#endif
+"#;
+        let config = CParserConfig::default()
+            .with_include_dir(include.path())
+            .with_define("WIDE")
+            .with_std("c11");
+        let ast = parse_with(&config, source, "zero.c").unwrap();
+        let function = find(&ast, "FunctionDecl");
+        assert_eq!(function.name.as_deref(), Some("zero"));
+        assert_eq!(function.return_type.as_deref(), Some("count_t"));
+
+        let ast = parse(source, "zero.c").unwrap();
+        assert!(ast
+            .children
+            .iter()
+            .all(|child| child.node_type != "FunctionDecl"));
+    }
+
+    #[test]
+    fn test_parse_reports_clang_diagnostics() {
+        let source = "int get(void) { return 0 }\nint missing(void) {}\n";
+        let parsed = CParser::new()
+            .unwrap()
+            .parse_with_diagnostics(source, "get.c")
+            .unwrap();
+        let [error, warning] = parsed.diagnostics.as_slice() else {
+            panic!("Unexpected diagnostics {:?}", parsed.diagnostics);
+        };
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.message, "expected ';' after return statement");
+        let location = error.location.as_ref().unwrap();
+        assert_eq!((location.file.as_str(), location.line), ("get.c", 1));
+        assert_eq!(error.fix_its[0].replacement, ";");
+        assert_eq!(warning.severity, Severity::Warning);
+        assert!(find(&parsed.ast, "FunctionDecl").name.is_some());
+
+        let error = parse("#include \"missing.h\"\nint x;\n", "include.c").unwrap_err();
+        assert!(
+            error.to_string().contains("'missing.h' file not found"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_quote_c_string() {
+        assert_eq!(quote_c_string("plain"), "\"plain\"");
+        assert_eq!(quote_c_string("say \"hi\"\n1"), "\"say \\\"hi\\\"\\0121\"");
+    }
+
     #[test]
     fn test_cpython_api_detection() {
         assert!(is_cpython_api_name("PyList_Append"));
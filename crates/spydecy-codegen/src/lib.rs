@@ -11,8 +11,82 @@
 #![deny(unsafe_code)]
 #![allow(clippy::module_name_repetitions)]
 
-use anyhow::{Context, Result};
-use spydecy_hir::unified::{UnificationPattern, UnifiedHIR};
+use anyhow::{bail, Context, Result};
+use spydecy_hir::{
+    diagnostics::Diagnostic,
+    metadata::{cleandoc, Metadata},
+    unified::{
+        BinOp, CatchClause, ClosureKind, ComprehensionClause, ComprehensionKind, EnumVariant,
+        ErrorVariant, GeneratorStage, LiteralValue, LoopKind, Pattern, Receiver, ScopeGuard,
+        UnaryOp, UnificationPattern, UnifiedField, UnifiedHIR, UnifiedParameter,
+    },
+};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// Rust keywords that are valid Python identifiers, and become raw
+/// identifiers (`r#type`) as names
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "become", "box", "const", "do", "dyn", "enum", "extern", "final", "fn", "impl",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "static", "struct", "trait", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+];
+
+/// A Python name as a Rust identifier
+fn ident(name: &str) -> Cow<'_, str> {
+    if RUST_KEYWORDS.contains(&name) {
+        Cow::Owned(format!("r#{name}"))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Lines of a doc comment with its indented blocks in ```` ```text ````
+/// fences
+fn fence_indented_blocks(text: &str) -> Vec<String> {
+    const INDENT: &str = "    ";
+    let mut lines: Vec<String> = Vec::new();
+    let mut fenced = false;
+    for line in text.lines() {
+        let after_blank = lines.last().map_or(true, String::is_empty);
+        if fenced && !line.is_empty() && !line.starts_with(INDENT) {
+            // The blank line ending the block goes after the fence
+            let blank = lines.last().is_some_and(String::is_empty);
+            if blank {
+                lines.pop();
+            }
+            lines.push("```".to_owned());
+            if blank {
+                lines.push(String::new());
+            }
+            fenced = false;
+        } else if !fenced && after_blank && line.starts_with(INDENT) {
+            lines.push("```text".to_owned());
+            fenced = true;
+        }
+        let line = if fenced {
+            line.strip_prefix(INDENT).unwrap_or(line)
+        } else {
+            line
+        };
+        lines.push(line.to_owned());
+    }
+    if fenced {
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        lines.push("```".to_owned());
+    }
+    lines
+}
+
+/// Rust source file of a generated crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustFile {
+    /// Path relative to the crate's `src` directory
+    pub path: String,
+    /// Rust source code
+    pub code: String,
+}
 
 /// Rust code generator
 ///
@@ -39,18 +113,36 @@ impl RustCodegen {
     /// # Errors
     ///
     /// Returns an error if the HIR cannot be converted to Rust code
+    #[allow(clippy::too_many_lines)]
     pub fn generate(&mut self, hir: &UnifiedHIR) -> Result<String> {
         match hir {
             UnifiedHIR::Module {
-                name, declarations, ..
-            } => self.generate_module(name, declarations),
+                name,
+                declarations,
+                meta,
+                ..
+            } => self.generate_module(name, meta.docs.as_deref(), &[], declarations),
             UnifiedHIR::Function {
                 name,
+                receiver,
                 params,
                 return_type,
                 body,
+                meta,
+                ..
+            } => self.generate_function(name, *receiver, params, return_type, body, meta),
+            UnifiedHIR::Struct {
+                name, fields, meta, ..
+            } => self.generate_struct(name, fields, meta),
+            UnifiedHIR::Enum {
+                name,
+                variants,
+                value_type,
                 ..
-            } => self.generate_function(name, params, return_type, body),
+            } => Ok(self.generate_enum(name, variants, value_type)),
+            UnifiedHIR::Impl {
+                type_name, methods, ..
+            } => self.generate_impl(type_name, methods),
             UnifiedHIR::Call {
                 callee,
                 args,
@@ -60,20 +152,165 @@ impl RustCodegen {
                 // Check if this is an optimized pattern
                 if let Some(mapping) = cross_mapping {
                     if mapping.boundary_eliminated {
-                        return Ok(Self::generate_optimized_call(callee, args, mapping.pattern));
+                        return self.generate_optimized_call(callee, args, mapping.pattern);
                     }
                 }
                 self.generate_call(callee, args)
             }
-            UnifiedHIR::Variable { name, .. } => Ok(name.clone()),
+            UnifiedHIR::Variable { name, .. } => Ok(ident(name).into_owned()),
             UnifiedHIR::Return { value, .. } => self.generate_return(value.as_deref()),
-            UnifiedHIR::Assign { target, value, .. } => self.generate_assign(target, value),
-            _ => Ok("/* Unsupported HIR node */".to_owned()),
+            UnifiedHIR::Assign {
+                target,
+                value,
+                mutable,
+                ..
+            } => self.generate_assign(target, value, mutable),
+            UnifiedHIR::Declare { name, mutable, .. } => Ok(if *mutable {
+                format!("let mut {}", ident(name))
+            } else {
+                format!("let {}", ident(name))
+            }),
+            UnifiedHIR::Literal { value, .. } => Ok(Self::generate_literal(value)),
+            UnifiedHIR::StructInit { name, fields, .. } => self.generate_struct_init(name, fields),
+            UnifiedHIR::FieldAccess { object, field, .. } => {
+                Ok(format!("{}.{}", self.generate(object)?, ident(field)))
+            }
+            UnifiedHIR::Index { object, index, .. } => Ok(format!(
+                "{}[{}]",
+                self.generate(object)?,
+                self.generate(index)?
+            )),
+            UnifiedHIR::MethodCall {
+                receiver,
+                method,
+                args,
+                ..
+            } => self.generate_method_call(receiver, method, args),
+            UnifiedHIR::Store { target, value, .. } => Ok(format!(
+                "{} = {}",
+                self.generate(target)?,
+                self.generate(value)?
+            )),
+            UnifiedHIR::AugAssign {
+                target, op, value, ..
+            } => Ok(format!(
+                "{} {}= {}",
+                self.generate(target)?,
+                Self::bin_op_symbol(*op),
+                self.generate(value)?
+            )),
+            UnifiedHIR::BinOp {
+                op, left, right, ..
+            } => Ok(format!(
+                "{} {} {}",
+                self.generate_operand(left)?,
+                Self::bin_op_symbol(*op),
+                self.generate_operand(right)?
+            )),
+            UnifiedHIR::UnaryOp { op, operand, .. } => Ok(format!(
+                "{}{}",
+                Self::unary_op_symbol(*op),
+                self.generate_operand(operand)?
+            )),
+            UnifiedHIR::Cast {
+                value, target_type, ..
+            } => Ok(format!(
+                "{} as {}",
+                self.generate_operand(value)?,
+                self.generate_type(target_type)?
+            )),
+            UnifiedHIR::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => self.generate_if(condition, then_branch, else_branch),
+            UnifiedHIR::Loop { kind, body, .. } => self.generate_loop(kind, body),
+            UnifiedHIR::List { elements, .. } => self.generate_vec(elements),
+            UnifiedHIR::Tuple { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.generate(element))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Self::generate_tuple(&elements))
+            }
+            UnifiedHIR::Dict { entries, .. } => self.generate_hash_map(entries),
+            UnifiedHIR::Comprehension {
+                kind,
+                element,
+                value,
+                clauses,
+                ..
+            } => self.generate_comprehension(*kind, element, value.as_deref(), clauses),
+            UnifiedHIR::Break { .. } => Ok("break".to_owned()),
+            UnifiedHIR::Continue { .. } => Ok("continue".to_owned()),
+            UnifiedHIR::ErrorEnum { .. }
+            | UnifiedHIR::TryCatch { .. }
+            | UnifiedHIR::Propagate { .. } => self.generate_error_handling(hir),
+            UnifiedHIR::Generator { .. }
+            | UnifiedHIR::Yield { .. }
+            | UnifiedHIR::Advance { .. } => self.generate_generator_node(hir),
+            UnifiedHIR::Format { template, args, .. } => self.generate_format(template, args),
+            UnifiedHIR::Scope {
+                guards,
+                hoisted,
+                body,
+                value,
+                ..
+            } => self.generate_scope(guards, hoisted, body, value.as_deref()),
+            UnifiedHIR::Closure {
+                params,
+                return_type,
+                body,
+                moves,
+                ..
+            } => self.generate_lambda(params, return_type, body, *moves),
+            UnifiedHIR::GuardType { name, target, .. } => {
+                Ok(self.generate_guard_type(name, target))
+            }
+            UnifiedHIR::Static {
+                name,
+                static_type,
+                value,
+                ..
+            } => {
+                // Python names module-level variables in lower case
+                Ok(format!(
+                    "#[allow(non_upper_case_globals)]\npub static {}: {} = {};",
+                    ident(name),
+                    self.generate_type(static_type)?,
+                    self.generate(value)?
+                ))
+            }
+            UnifiedHIR::Use { path, alias, .. } => Ok(Self::generate_use(path, alias.as_deref())),
         }
     }
 
-    /// Generate a module
-    fn generate_module(&mut self, name: &str, declarations: &[UnifiedHIR]) -> Result<String> {
+    /// Generate the error enum, a `try` statement or error propagation
+    fn generate_error_handling(&mut self, hir: &UnifiedHIR) -> Result<String> {
+        match hir {
+            UnifiedHIR::ErrorEnum { name, variants, .. } => {
+                Ok(self.generate_error_enum(name, variants))
+            }
+            UnifiedHIR::Propagate { value, on_none, .. } => {
+                let value = self.generate_operand(value)?;
+                match on_none {
+                    Some(error) => Ok(format!("{value}.ok_or_else(|| {})?", self.generate(error)?)),
+                    None => Ok(format!("{value}?")),
+                }
+            }
+            _ => self.generate_try(hir),
+        }
+    }
+
+    /// Generate a module, declaring its submodules
+    fn generate_module(
+        &mut self,
+        name: &str,
+        docs: Option<&str>,
+        submodules: &[&str],
+        declarations: &[UnifiedHIR],
+    ) -> Result<String> {
         let mut output = String::new();
 
         // Module header
@@ -81,42 +318,201 @@ impl RustCodegen {
         output.push_str(name);
         output.push('\n');
         output.push_str("// Generated by Spydecy\n");
+        if let Some(docs) = docs {
+            output.push_str(&Self::comment_lines("//!", docs, "\n"));
+        }
         output.push_str("#![allow(dead_code)]\n\n");
 
-        // Generate all declarations
-        for decl in declarations {
-            let code = self.generate(decl)?;
+        if !submodules.is_empty() {
+            output.push_str(&Self::mod_declarations(submodules));
+            output.push('\n');
+        }
+
+        // Generate all declarations, keeping `use` declarations together
+        for (i, decl) in declarations.iter().enumerate() {
+            let code = self.generate_documented(decl)?;
             output.push_str(&code);
-            output.push_str("\n\n");
+            let next = declarations.get(i + 1);
+            if matches!(decl, UnifiedHIR::Use { .. })
+                && matches!(next, Some(UnifiedHIR::Use { .. }))
+            {
+                output.push('\n');
+            } else {
+                output.push_str("\n\n");
+            }
         }
 
         Ok(output)
     }
 
+    /// Generate a crate from the modules of a project, one file per module
+    ///
+    /// `app.models` goes to `app/models.rs`, or to `app/models/mod.rs` when
+    /// it has submodules. `lib.rs` and modules missing from `modules`
+    /// (namespace packages) only declare their submodules.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a node is not a module or cannot be converted
+    pub fn generate_crate(&mut self, modules: &[UnifiedHIR]) -> Result<Vec<RustFile>> {
+        let mut tree: BTreeMap<String, Option<&UnifiedHIR>> = BTreeMap::new();
+        for module in modules {
+            let UnifiedHIR::Module { name, .. } = module else {
+                bail!("Expected a module, found {module:?}");
+            };
+            let mut parent = name.as_str();
+            while let Some((package, _)) = parent.rsplit_once('.') {
+                tree.entry(package.to_owned()).or_insert(None);
+                parent = package;
+            }
+            tree.insert(name.clone(), Some(module));
+        }
+
+        let submodules = |parent: &str| -> Vec<&str> {
+            tree.keys()
+                .filter_map(|name| match name.rsplit_once('.') {
+                    Some((package, last)) if package == parent => Some(last),
+                    None if parent.is_empty() => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let mut files = vec![RustFile {
+            path: "lib.rs".to_owned(),
+            code: format!(
+                "// Generated by Spydecy\n\n{}",
+                Self::mod_declarations(&submodules(""))
+            ),
+        }];
+        for (name, module) in &tree {
+            let children = submodules(name);
+            let dir = name.replace('.', "/");
+            let path = if children.is_empty() {
+                format!("{dir}.rs")
+            } else {
+                format!("{dir}/mod.rs")
+            };
+            let code = match module {
+                Some(UnifiedHIR::Module {
+                    declarations, meta, ..
+                }) => self.generate_module(name, meta.docs.as_deref(), &children, declarations)?,
+                _ => self.generate_module(name, None, &children, &[])?,
+            };
+            files.push(RustFile { path, code });
+        }
+        Ok(files)
+    }
+
+    /// Generate a node preceded by its docs: `///` on items, plain `//`
+    /// comments on statements, where rustc rejects doc comments
+    fn generate_documented(&mut self, node: &UnifiedHIR) -> Result<String> {
+        let code = self.generate(node)?;
+        let docs = match node {
+            UnifiedHIR::Module { .. } => None,
+            _ => node.metadata().docs.as_deref(),
+        };
+        let Some(docs) = docs else {
+            return Ok(code);
+        };
+
+        let marker = if matches!(
+            node,
+            UnifiedHIR::Function { .. }
+                | UnifiedHIR::Struct { .. }
+                | UnifiedHIR::Impl { .. }
+                | UnifiedHIR::Enum { .. }
+                | UnifiedHIR::ErrorEnum { .. }
+                | UnifiedHIR::GuardType { .. }
+                | UnifiedHIR::Static { .. }
+        ) {
+            "///"
+        } else {
+            "//"
+        };
+        let separator = format!("\n{}", self.indent());
+        Ok(format!(
+            "{}{code}",
+            Self::comment_lines(marker, docs, &separator)
+        ))
+    }
+
+    /// Render `text` as comment lines, each followed by `separator`
+    ///
+    /// The text is dedented like a docstring. In doc comments, indented
+    /// blocks are fenced as `text`, since rustdoc would compile them as
+    /// Rust examples.
+    fn comment_lines(marker: &str, text: &str, separator: &str) -> String {
+        let text = cleandoc(text).unwrap_or_default();
+        let lines = if marker == "//" {
+            text.lines().map(str::to_owned).collect()
+        } else {
+            fence_indented_blocks(&text)
+        };
+        lines
+            .iter()
+            .map(|line| {
+                if line.is_empty() {
+                    format!("{marker}{separator}")
+                } else {
+                    format!("{marker} {line}{separator}")
+                }
+            })
+            .collect()
+    }
+
+    /// Generate `pub mod <name>;` lines
+    fn mod_declarations(submodules: &[&str]) -> String {
+        submodules
+            .iter()
+            .fold(String::new(), |mut output, submodule| {
+                output.push_str("pub mod ");
+                output.push_str(submodule);
+                output.push_str(";\n");
+                output
+            })
+    }
+
+    /// Generate `use crate::<path>;`
+    fn generate_use(path: &[String], alias: Option<&str>) -> String {
+        let path = path.join("::");
+        match alias {
+            Some(alias) => format!("use crate::{path} as {alias};"),
+            None => format!("use crate::{path};"),
+        }
+    }
+
     /// Generate a function
     fn generate_function(
         &mut self,
         name: &str,
+        receiver: Option<Receiver>,
         params: &[spydecy_hir::unified::UnifiedParameter],
         return_type: &spydecy_hir::types::Type,
         body: &[UnifiedHIR],
+        meta: &Metadata,
     ) -> Result<String> {
         let mut output = String::new();
 
         // Function signature
         output.push_str("pub fn ");
-        output.push_str(name);
+        output.push_str(&ident(name));
         output.push('(');
 
         // Parameters
-        for (i, param) in params.iter().enumerate() {
-            if i > 0 {
-                output.push_str(", ");
-            }
-            output.push_str(&param.name);
-            output.push_str(": ");
-            output.push_str(&self.generate_type(&param.param_type)?);
+        let mut signature = Vec::new();
+        match receiver {
+            Some(Receiver::Ref) => signature.push("&self".to_owned()),
+            Some(Receiver::RefMut) => signature.push("&mut self".to_owned()),
+            None => {}
         }
+        for param in params {
+            let param_type = self.generate_declared_type(&param.param_type, meta, || {
+                format!("parameter `{}` of `{name}`", param.name)
+            })?;
+            signature.push(format!("{}: {param_type}", ident(&param.name)));
+        }
+        output.push_str(&signature.join(", "));
 
         output.push(')');
 
@@ -126,29 +522,888 @@ impl RustCodegen {
             spydecy_hir::types::Type::Rust(spydecy_hir::types::RustType::Unit)
         ) {
             output.push_str(" -> ");
-            output.push_str(&self.generate_type(return_type)?);
+            output.push_str(&self.generate_declared_type(return_type, meta, || {
+                format!("the value returned by `{name}`")
+            })?);
+            // An iterator returned by a method borrows `self`
+            if receiver.is_some()
+                && matches!(
+                    return_type,
+                    spydecy_hir::types::Type::Python(spydecy_hir::types::PythonType::Iterator(_))
+                )
+            {
+                output.push_str(" + '_");
+            }
         }
 
-        output.push_str(" {\n");
-
         // Function body
+        output.push(' ');
+        match body {
+            [generator @ UnifiedHIR::Generator { .. }] => {
+                output.push_str(&self.generate(generator)?);
+            }
+            _ => output.push_str(&self.generate_block(body)?),
+        }
+
+        Ok(output)
+    }
+
+    /// Generate a braced block of statements
+    fn generate_block(&mut self, body: &[UnifiedHIR]) -> Result<String> {
+        self.generate_block_ending(body, None)
+    }
+
+    /// Generate a braced block of statements followed by a tail expression
+    fn generate_block_ending(&mut self, body: &[UnifiedHIR], tail: Option<&str>) -> Result<String> {
+        let mut output = String::from("{\n");
+
         self.indent_level += 1;
         for stmt in body {
-            let code = self.generate(stmt)?;
+            let code = self.generate_documented(stmt)?;
             output.push_str(&self.indent());
-            output.push_str(&code);
-            if !code.trim().ends_with('}') && !code.trim().is_empty() {
-                output.push(';');
-            }
+            output.push_str(&Self::terminate(code));
+            output.push('\n');
+        }
+        if let Some(tail) = tail {
+            output.push_str(&self.indent());
+            output.push_str(tail);
             output.push('\n');
         }
         self.indent_level -= 1;
 
+        output.push_str(&self.indent());
         output.push('}');
 
         Ok(output)
     }
 
+    /// End a statement with `;` unless it is a block-like expression or
+    /// already terminated; a `return` of a block still needs one
+    fn terminate(mut code: String) -> String {
+        let trimmed = code.trim();
+        let block_like = trimmed.ends_with('}') && !trimmed.starts_with("return ");
+        if !block_like && !trimmed.ends_with(';') && !trimmed.is_empty() {
+            code.push(';');
+        }
+        code
+    }
+
+    /// Generate a `format!` invocation
+    fn generate_format(&mut self, template: &str, args: &[UnifiedHIR]) -> Result<String> {
+        let mut parts = vec![format!("{template:?}")];
+        for arg in args {
+            parts.push(self.generate(arg)?);
+        }
+        Ok(format!("format!({})", parts.join(", ")))
+    }
+
+    /// Generate a block binding its guards before the body (`with`)
+    ///
+    /// The guards are dropped in reverse order when the block ends; the
+    /// hoisted locals are declared before it. A block used as an expression
+    /// ends in its value.
+    fn generate_scope(
+        &mut self,
+        guards: &[ScopeGuard],
+        hoisted: &[(String, bool)],
+        body: &[UnifiedHIR],
+        value: Option<&UnifiedHIR>,
+    ) -> Result<String> {
+        let declarations = hoisted
+            .iter()
+            .map(|(name, mutable)| {
+                let binding = if *mutable { "let mut" } else { "let" };
+                format!("{binding} {};\n{}", ident(name), self.indent())
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        self.indent_level += 1;
+        let indent = self.indent();
+        let mut bindings = Vec::new();
+        for guard in guards {
+            let binding = if guard.mutable { "let mut" } else { "let" };
+            bindings.push(format!(
+                "{indent}{binding} {} = {};\n",
+                ident(&guard.name),
+                self.generate(&guard.value)?
+            ));
+        }
+        let value = value.map(|value| self.generate(value)).transpose()?;
+        self.indent_level -= 1;
+        let block = self.generate_block_ending(body, value.as_deref())?;
+        Ok(format!(
+            "{declarations}{{\n{}{}",
+            bindings.concat(),
+            &block[2..]
+        ))
+    }
+
+    /// Generate the guard struct of a context manager
+    ///
+    /// It borrows the manager mutably, derefs to it and calls its `exit`
+    /// method when dropped.
+    fn generate_guard_type(&self, name: &str, target: &str) -> String {
+        let indent = &self.indent;
+        format!(
+            "pub struct {name}<'a>(&'a mut {target});\n\n\
+             impl std::ops::Deref for {name}<'_> {{\n\
+             {indent}type Target = {target};\n\n\
+             {indent}fn deref(&self) -> &{target} {{\n\
+             {indent}{indent}self.0\n\
+             {indent}}}\n\
+             }}\n\n\
+             impl std::ops::DerefMut for {name}<'_> {{\n\
+             {indent}fn deref_mut(&mut self) -> &mut {target} {{\n\
+             {indent}{indent}self.0\n\
+             {indent}}}\n\
+             }}\n\n\
+             impl Drop for {name}<'_> {{\n\
+             {indent}fn drop(&mut self) {{\n\
+             {indent}{indent}self.0.exit();\n\
+             {indent}}}\n\
+             }}"
+        )
+    }
+
+    /// Generate a `try` statement
+    ///
+    /// The body runs in a closure returning `Result<(), Error>` so that
+    /// `raise` and `?` inside it stop at the handlers. With a `finally`
+    /// clause the handlers run in a second closure, whose outcome is
+    /// propagated after the `finally` statements when errors can escape.
+    fn generate_try(&mut self, node: &UnifiedHIR) -> Result<String> {
+        let UnifiedHIR::TryCatch {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            error_type,
+            propagates,
+            ..
+        } = node
+        else {
+            bail!("Expected a try statement");
+        };
+
+        let enclose = !finalbody.is_empty() && *propagates;
+        if enclose {
+            self.indent_level += 1;
+        }
+        let closure = self.generate_closure(body, error_type)?;
+        self.indent_level += 1;
+        let mut arms = vec![format!("Ok(()) => {}", self.generate_block(orelse)?)];
+        for clause in handlers {
+            arms.push(format!(
+                "Err({}) => {}",
+                Self::catch_pattern(error_type, clause),
+                self.generate_block(&clause.body)?
+            ));
+        }
+        let arms = arms
+            .iter()
+            .map(|arm| format!("{}{arm},\n", self.indent()))
+            .collect::<Vec<_>>()
+            .concat();
+        self.indent_level -= 1;
+        let mut output = format!("match {closure} {{\n{arms}{}}}", self.indent());
+        if finalbody.is_empty() {
+            return Ok(output);
+        }
+
+        if enclose {
+            self.indent_level -= 1;
+            output = format!(
+                "let outcome = (|| -> Result<(), {error_type}> {{\n{indent}    {output};\n{indent}    Ok(())\n{indent}}})()",
+                indent = self.indent()
+            );
+        }
+        let mut statements = vec![output];
+        for stmt in finalbody {
+            statements.push(self.generate_documented(stmt)?);
+        }
+        if enclose {
+            statements.push("outcome?".to_owned());
+        }
+        let last = statements.pop().unwrap_or_default();
+        let separator = format!("\n{}", self.indent());
+        let mut output = String::new();
+        for statement in statements {
+            output.push_str(&Self::terminate(statement));
+            output.push_str(&separator);
+        }
+        output.push_str(&last);
+        Ok(output)
+    }
+
+    /// Generate `(|| -> Result<(), Error> { body; Ok(()) })()`
+    fn generate_closure(&mut self, body: &[UnifiedHIR], error_type: &str) -> Result<String> {
+        // A body ending in `return` never reaches the `Ok(())`
+        let tail = match body.last() {
+            Some(UnifiedHIR::Return { .. }) => None,
+            _ => Some("Ok(())"),
+        };
+        Ok(format!(
+            "(|| -> Result<(), {error_type}> {})()",
+            self.generate_block_ending(body, tail)?
+        ))
+    }
+
+    /// Generate a lambda or nested function
+    ///
+    /// A body returning a single value becomes `|x| value`; any other body
+    /// is a block, after the return type when it is known.
+    fn generate_lambda(
+        &mut self,
+        params: &[UnifiedParameter],
+        return_type: &spydecy_hir::types::Type,
+        body: &[UnifiedHIR],
+        moves: bool,
+    ) -> Result<String> {
+        use spydecy_hir::types::{PythonType, RustType, Type};
+
+        let params = params
+            .iter()
+            .map(|param| match &param.param_type {
+                Type::Unknown => Ok(ident(&param.name).into_owned()),
+                ty => Ok(format!(
+                    "{}: {}",
+                    ident(&param.name),
+                    self.generate_type(ty)?
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let capture = if moves { "move " } else { "" };
+        let head = format!("{capture}|{}|", params.join(", "));
+        if let [UnifiedHIR::Return {
+            value: Some(value), ..
+        }] = body
+        {
+            return Ok(format!("{head} {}", self.generate(value)?));
+        }
+        let returns = match return_type {
+            Type::Unknown | Type::Rust(RustType::Unit) | Type::Python(PythonType::None) => {
+                String::new()
+            }
+            other => format!(" -> {}", self.generate_type(other)?),
+        };
+        Ok(format!("{head}{returns} {}", self.generate_block(body)?))
+    }
+
+    /// Pattern matching the errors a handler catches
+    fn catch_pattern(error_type: &str, clause: &CatchClause) -> String {
+        let variants = clause
+            .variants
+            .iter()
+            .map(|variant| format!("{error_type}::{variant}(_)"))
+            .collect::<Vec<_>>();
+        match (&clause.binding, variants.as_slice()) {
+            (Some(binding), []) => binding.clone(),
+            (Some(binding), [variant]) => format!("{binding} @ {variant}"),
+            (Some(binding), _) => format!("{binding} @ ({})", variants.join(" | ")),
+            (None, []) => "_".to_owned(),
+            (None, _) => variants.join(" | "),
+        }
+    }
+
+    /// Generate the error enum with its `Display` and `Error` impls
+    fn generate_error_enum(&self, name: &str, variants: &[ErrorVariant]) -> String {
+        let indent = &self.indent;
+        let separator = format!("\n{indent}");
+        let members = variants
+            .iter()
+            .map(|variant| {
+                let docs = variant.docs.as_deref().map_or_else(String::new, |docs| {
+                    Self::comment_lines("///", docs, &separator)
+                });
+                format!("{indent}{docs}{}(String),\n", variant.name)
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        let arms = variants
+            .iter()
+            .map(|variant| format!("Self::{}(message)", variant.name))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        // `with open(..)` propagates I/O errors as `OSError`
+        let io_error = if variants.iter().any(|variant| variant.name == "OSError") {
+            format!(
+                "\n\n\
+                 impl From<std::io::Error> for {name} {{\n\
+                 {indent}fn from(err: std::io::Error) -> Self {{\n\
+                 {indent}{indent}Self::OSError(err.to_string())\n\
+                 {indent}}}\n\
+                 }}"
+            )
+        } else {
+            String::new()
+        };
+        format!(
+            "#[derive(Debug, Clone, PartialEq, Eq)]\n\
+             pub enum {name} {{\n\
+             {members}\
+             }}\n\n\
+             impl std::fmt::Display for {name} {{\n\
+             {indent}fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{\n\
+             {indent}{indent}match self {{\n\
+             {indent}{indent}{indent}{arms} => f.write_str(message),\n\
+             {indent}{indent}}}\n\
+             {indent}}}\n\
+             }}\n\n\
+             impl std::error::Error for {name} {{}}{io_error}"
+        )
+    }
+
+    /// Generate an if/else statement
+    fn generate_if(
+        &mut self,
+        condition: &UnifiedHIR,
+        then_branch: &[UnifiedHIR],
+        else_branch: &[UnifiedHIR],
+    ) -> Result<String> {
+        let mut output = format!(
+            "if {} {}",
+            self.generate(condition)?,
+            self.generate_block(then_branch)?
+        );
+        match else_branch {
+            [] => {}
+            // `elif` chains become `else if`
+            [nested @ UnifiedHIR::If { .. }] => {
+                output.push_str(" else ");
+                output.push_str(&self.generate(nested)?);
+            }
+            _ => {
+                output.push_str(" else ");
+                output.push_str(&self.generate_block(else_branch)?);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Generate a `for` or `while` loop
+    fn generate_loop(&mut self, kind: &LoopKind, body: &[UnifiedHIR]) -> Result<String> {
+        let header = match kind {
+            LoopKind::For { target, iter } => format!(
+                "for {} in {}",
+                Self::generate_pattern(target, &[]),
+                self.generate(iter)?
+            ),
+            LoopKind::While { condition } => format!("while {}", self.generate(condition)?),
+        };
+        Ok(format!("{header} {}", self.generate_block(body)?))
+    }
+
+    /// Generate a generator body, `yield` or a stage change
+    fn generate_generator_node(&mut self, hir: &UnifiedHIR) -> Result<String> {
+        match hir {
+            UnifiedHIR::Generator {
+                setup,
+                mutable,
+                stages,
+                ..
+            } => self.generate_generator(setup, mutable, stages),
+            UnifiedHIR::Yield {
+                value,
+                delegate: true,
+                ..
+            } => Ok(format!(
+                "pending.extend({})",
+                self.generate_into_iter(value)?
+            )),
+            UnifiedHIR::Yield { value, .. } => {
+                Ok(format!("pending.push_back({})", self.generate(value)?))
+            }
+            UnifiedHIR::Advance { stage, .. } => Ok(format!(
+                "stage = {stage};\n{}continue 'resume",
+                self.indent()
+            )),
+            _ => bail!("Expected a generator node"),
+        }
+    }
+
+    /// Generate the braced body of a generator function
+    ///
+    /// After the setup, a `std::iter::from_fn` closure runs the current
+    /// stage until an item is queued in `pending`, and hands the queued
+    /// items out before resuming. Loop iterables become `iter_N`.
+    fn generate_generator(
+        &mut self,
+        setup: &[UnifiedHIR],
+        mutable: &[String],
+        stages: &[GeneratorStage],
+    ) -> Result<String> {
+        self.indent_level += 1;
+        let mut statements = Vec::new();
+        let mut declared = Vec::new();
+        for stmt in setup {
+            let mut code = self.generate_documented(stmt)?;
+            if let UnifiedHIR::Assign { target, .. } = stmt {
+                code = code.replacen(
+                    &format!("let {} =", Self::generate_binding(target, &[])),
+                    &format!("let {} =", Self::generate_binding(target, mutable)),
+                    1,
+                );
+                declared.extend(target.names());
+            }
+            statements.push(Self::terminate(code));
+        }
+        for name in mutable {
+            if !declared.contains(&name.as_str()) {
+                statements.push(format!("let mut {0} = {0};", ident(name)));
+            }
+        }
+        statements.push("let mut stage = 0;".to_owned());
+        for (index, stage) in stages.iter().enumerate() {
+            if let GeneratorStage::For { iter, .. } | GeneratorStage::Delegate(iter) = stage {
+                let iter = self.generate_into_iter(iter)?;
+                statements.push(format!("let mut iter_{index} = {iter};"));
+            }
+        }
+
+        self.indent_level += 2;
+        let mut arms = Vec::new();
+        for (index, stage) in stages.iter().enumerate() {
+            arms.push(format!(
+                "{}{},\n",
+                self.indent(),
+                self.generate_stage(index, stage)?
+            ));
+        }
+        arms.push(format!("{}_ => return None,\n", self.indent()));
+        self.indent_level -= 1;
+        let indent = self.indent();
+        let arms = arms.concat();
+
+        let mut resume = Vec::new();
+        if arms.contains("pending.") {
+            statements.push("let mut pending = std::collections::VecDeque::new();".to_owned());
+            resume.push(format!(
+                "{indent}if let Some(item) = pending.pop_front() {{\n{indent}{}return Some(item);\n{indent}}}\n",
+                self.indent
+            ));
+        }
+        resume.push(format!("{indent}match stage {{\n{arms}{indent}}}\n"));
+        let label = if arms.contains("continue 'resume") {
+            "'resume: "
+        } else {
+            ""
+        };
+        self.indent_level -= 1;
+        let indent = self.indent();
+        statements.push(format!(
+            "std::iter::from_fn(move || {label}loop {{\n{}{indent}}})",
+            resume.concat()
+        ));
+        self.indent_level -= 1;
+
+        Ok(format!(
+            "{{\n{}{}}}",
+            statements
+                .iter()
+                .map(|statement| format!("{indent}{statement}\n"))
+                .collect::<Vec<_>>()
+                .concat(),
+            self.indent()
+        ))
+    }
+
+    /// Generate the `match stage` arm running one generator stage
+    fn generate_stage(&mut self, index: usize, stage: &GeneratorStage) -> Result<String> {
+        let next = format!("stage = {};", index + 1);
+        let arm = match stage {
+            GeneratorStage::Run(body) => {
+                // A stage ending in `return` has already moved on
+                let tail = match body.last() {
+                    Some(UnifiedHIR::Advance { .. }) => None,
+                    _ => Some(next.as_str()),
+                };
+                self.generate_block_ending(body, tail)?
+            }
+            GeneratorStage::While { condition, body } => format!(
+                "if {} {} else {}",
+                self.generate(condition)?,
+                self.generate_block(body)?,
+                self.generate_block_ending(&[], Some(&next))?
+            ),
+            GeneratorStage::For { target, body, .. } => {
+                self.indent_level += 1;
+                let some = format!(
+                    "Some({}) => {}",
+                    Self::generate_pattern(target, &[]),
+                    self.generate_block(body)?
+                );
+                self.indent_level -= 1;
+                self.generate_next(index, &some, &next)
+            }
+            GeneratorStage::Delegate(_) => {
+                self.generate_next(index, "Some(item) => return Some(item)", &next)
+            }
+        };
+        Ok(format!("{index} => {arm}"))
+    }
+
+    /// Generate `match iter_N.next() { some, None => next }`
+    fn generate_next(&self, index: usize, some: &str, next: &str) -> String {
+        let indent = self.indent();
+        let next = next.trim_end_matches(';');
+        format!(
+            "match iter_{index}.next() {{\n{indent}{inner}{some},\n{indent}{inner}None => {next},\n{indent}}}",
+            inner = self.indent
+        )
+    }
+
+    /// Generate an owned iterator over a loop iterable
+    fn generate_into_iter(&mut self, iter: &UnifiedHIR) -> Result<String> {
+        if let Some(range) = self.generate_range(iter)? {
+            return Ok(range);
+        }
+        let iter_code = self.generate_operand(iter)?;
+        // Fields are borrowed through `self`, so iterate over a copy
+        if matches!(iter, UnifiedHIR::FieldAccess { .. }) {
+            Ok(format!("{iter_code}.clone().into_iter()"))
+        } else {
+            Ok(format!("{iter_code}.into_iter()"))
+        }
+    }
+
+    /// Generate a list literal
+    fn generate_vec(&mut self, elements: &[UnifiedHIR]) -> Result<String> {
+        if elements.is_empty() {
+            return Ok("Vec::new()".to_owned());
+        }
+        Ok(format!("vec![{}]", self.generate_list(elements)?))
+    }
+
+    /// Generate a dict literal
+    fn generate_hash_map(&mut self, entries: &[(UnifiedHIR, UnifiedHIR)]) -> Result<String> {
+        if entries.is_empty() {
+            return Ok("std::collections::HashMap::new()".to_owned());
+        }
+        let mut pairs = Vec::new();
+        for (key, value) in entries {
+            pairs.push(format!(
+                "({}, {})",
+                self.generate(key)?,
+                self.generate(value)?
+            ));
+        }
+        Ok(format!(
+            "std::collections::HashMap::from([{}])",
+            pairs.join(", ")
+        ))
+    }
+
+    /// Generate a comprehension as an iterator chain
+    ///
+    /// Each clause becomes `iter().filter(..)`, nested clauses are joined
+    /// with `flat_map`, and the element is produced by a final `map`.
+    fn generate_comprehension(
+        &mut self,
+        kind: ComprehensionKind,
+        element: &UnifiedHIR,
+        value: Option<&UnifiedHIR>,
+        clauses: &[ComprehensionClause],
+    ) -> Result<String> {
+        let mut element = self.generate(element)?;
+        if let Some(value) = value {
+            element = format!("({element}, {})", self.generate(value)?);
+        }
+        let chain = self.generate_clauses(clauses, &element, false)?;
+        Ok(match kind {
+            ComprehensionKind::List => format!("{chain}.collect::<Vec<_>>()"),
+            ComprehensionKind::Set => {
+                format!("{chain}.collect::<std::collections::HashSet<_>>()")
+            }
+            ComprehensionKind::Dict => {
+                format!("{chain}.collect::<std::collections::HashMap<_, _>>()")
+            }
+            ComprehensionKind::Generator => chain,
+        })
+    }
+
+    /// Generate the iterator chain for the remaining comprehension clauses
+    fn generate_clauses(
+        &mut self,
+        clauses: &[ComprehensionClause],
+        element: &str,
+        nested: bool,
+    ) -> Result<String> {
+        let Some((clause, rest)) = clauses.split_first() else {
+            bail!("Comprehension without a `for` clause");
+        };
+        // Closures inside `flat_map` must own the outer loop variables
+        let capture = if nested { "move " } else { "" };
+        let (mut chain, by_ref) = self.generate_iter_source(&clause.iter)?;
+        let pattern = format!(
+            "{}{}",
+            if by_ref { "&" } else { "" },
+            Self::generate_pattern(&clause.target, &[])
+        );
+
+        for filter in &clause.filters {
+            let filter = self.generate(filter)?;
+            chain = format!("{chain}.filter({capture}|&{pattern}| {filter})");
+        }
+        if rest.is_empty() {
+            Ok(format!("{chain}.map({capture}|{pattern}| {element})"))
+        } else {
+            let inner = self.generate_clauses(rest, element, true)?;
+            Ok(format!("{chain}.flat_map({capture}|{pattern}| {inner})"))
+        }
+    }
+
+    /// Generate the source iterator of a comprehension clause
+    ///
+    /// `range(..)` becomes a Rust range and an iterator chain, as lowered
+    /// from `d.items()`, is used as is, both yielding values; anything else
+    /// is iterated by reference. Returns whether the items are references.
+    fn generate_iter_source(&mut self, iter: &UnifiedHIR) -> Result<(String, bool)> {
+        if let Some(range) = self.generate_range(iter)? {
+            return Ok((range, false));
+        }
+        if matches!(iter, UnifiedHIR::MethodCall { method, .. } if method == "map") {
+            return Ok((self.generate(iter)?, false));
+        }
+        Ok((format!("{}.iter()", self.generate_operand(iter)?), true))
+    }
+
+    /// Generate `range(..)` as a Rust range, or `None` for other iterables
+    fn generate_range(&mut self, iter: &UnifiedHIR) -> Result<Option<String>> {
+        let UnifiedHIR::Call { callee, args, .. } = iter else {
+            return Ok(None);
+        };
+        if callee != "range" {
+            return Ok(None);
+        }
+        let mut bounds = Vec::new();
+        for arg in args {
+            bounds.push(self.generate_operand(arg)?);
+        }
+        Ok(match bounds.as_slice() {
+            [end] => Some(format!("(0..{end})")),
+            [start, end] => Some(format!("({start}..{end})")),
+            [start, end, step] => Some(format!("({start}..{end}).step_by({step} as usize)")),
+            _ => None,
+        })
+    }
+
+    /// Generate a struct definition
+    ///
+    /// Attributes named by a Rust path (`derive`) are emitted as `#[..]`;
+    /// source-language ones (`@dataclass`) are not.
+    fn generate_struct(
+        &self,
+        name: &str,
+        fields: &[UnifiedField],
+        meta: &Metadata,
+    ) -> Result<String> {
+        let mut output = meta
+            .attributes
+            .iter()
+            .filter(|attribute| {
+                attribute
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+                    && !attribute.name.starts_with("__")
+            })
+            .map(|attribute| {
+                if attribute.args.is_empty() {
+                    format!("#[{}]\n", attribute.name)
+                } else {
+                    format!("#[{}({})]\n", attribute.name, attribute.args.join(", "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        output.push_str("pub struct ");
+        output.push_str(name);
+        output.push_str(" {\n");
+        for field in fields {
+            output.push_str(&self.indent);
+            output.push_str("pub ");
+            output.push_str(&ident(&field.name));
+            output.push_str(": ");
+            output.push_str(&self.generate_declared_type(&field.field_type, meta, || {
+                format!("field `{}` of `{name}`", field.name)
+            })?);
+            output.push_str(",\n");
+        }
+        output.push('}');
+        Ok(output)
+    }
+
+    /// Generate a fieldless enum and the methods mapping its variants to
+    /// and from their values
+    fn generate_enum(
+        &self,
+        name: &str,
+        variants: &[EnumVariant],
+        value_type: &spydecy_hir::types::Type,
+    ) -> String {
+        use spydecy_hir::types::{PythonType, Type};
+
+        let indent = &self.indent;
+        let value_type = if matches!(value_type, Type::Python(PythonType::Str)) {
+            "&'static str"
+        } else {
+            "i64"
+        };
+        let members = variants
+            .iter()
+            .map(|variant| format!("{indent}{},\n", variant.name))
+            .collect::<Vec<_>>()
+            .concat();
+        let arms = |arm: &dyn Fn(&EnumVariant) -> String| {
+            variants
+                .iter()
+                .map(|variant| format!("{indent}{indent}{indent}{}\n", arm(variant)))
+                .collect::<Vec<_>>()
+                .concat()
+        };
+        let values = arms(&|variant| {
+            format!(
+                "Self::{} => {},",
+                variant.name,
+                Self::generate_literal(&variant.value)
+            )
+        });
+        let names = arms(&|variant| format!("Self::{} => {:?},", variant.name, variant.member));
+        let lookups = arms(&|variant| {
+            format!(
+                "{} => Some(Self::{}),",
+                Self::generate_literal(&variant.value),
+                variant.name
+            )
+        });
+        let (parameter, scrutinee) = if value_type == "i64" {
+            ("i64", "value")
+        } else {
+            ("impl AsRef<str>", "value.as_ref()")
+        };
+        format!(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n\
+             pub enum {name} {{\n\
+             {members}\
+             }}\n\n\
+             impl {name} {{\n\
+             {indent}/// Value of the Python member\n\
+             {indent}pub fn value(&self) -> {value_type} {{\n\
+             {indent}{indent}match self {{\n\
+             {values}\
+             {indent}{indent}}}\n\
+             {indent}}}\n\n\
+             {indent}/// Name of the Python member\n\
+             {indent}pub fn name(&self) -> &'static str {{\n\
+             {indent}{indent}match self {{\n\
+             {names}\
+             {indent}{indent}}}\n\
+             {indent}}}\n\n\
+             {indent}/// Variant whose member has the given value\n\
+             {indent}pub fn from_value(value: {parameter}) -> Option<Self> {{\n\
+             {indent}{indent}match {scrutinee} {{\n\
+             {lookups}\
+             {indent}{indent}{indent}_ => None,\n\
+             {indent}{indent}}}\n\
+             {indent}}}\n\
+             }}"
+        )
+    }
+
+    /// Generate an impl block holding a type's methods
+    fn generate_impl(&mut self, type_name: &str, methods: &[UnifiedHIR]) -> Result<String> {
+        let mut output = format!("impl {type_name} {{\n");
+
+        self.indent_level += 1;
+        let mut generated = Vec::new();
+        for method in methods {
+            generated.push(format!(
+                "{}{}\n",
+                self.indent(),
+                self.generate_documented(method)?
+            ));
+        }
+        self.indent_level -= 1;
+
+        output.push_str(&generated.join("\n"));
+        output.push('}');
+        Ok(output)
+    }
+
+    /// Generate a struct literal, using field init shorthand where possible
+    fn generate_struct_init(
+        &mut self,
+        name: &str,
+        fields: &[(String, UnifiedHIR)],
+    ) -> Result<String> {
+        if fields.is_empty() {
+            return Ok(format!("{name} {{}}"));
+        }
+        let mut inits = Vec::new();
+        for (field, value) in fields {
+            match value {
+                UnifiedHIR::Variable { name, .. } if name == field => {
+                    inits.push(ident(field).into_owned());
+                }
+                _ => inits.push(format!("{}: {}", ident(field), self.generate(value)?)),
+            }
+        }
+        Ok(format!("{name} {{ {} }}", inits.join(", ")))
+    }
+
+    /// Generate a comma-separated list of expressions
+    fn generate_list(&mut self, elements: &[UnifiedHIR]) -> Result<String> {
+        let mut output = Vec::new();
+        for element in elements {
+            output.push(self.generate(element)?);
+        }
+        Ok(output.join(", "))
+    }
+
+    /// Generate an operator operand, parenthesizing nested operations
+    fn generate_operand(&mut self, operand: &UnifiedHIR) -> Result<String> {
+        let code = self.generate(operand)?;
+        if matches!(operand, UnifiedHIR::BinOp { .. } | UnifiedHIR::Cast { .. }) {
+            Ok(format!("({code})"))
+        } else {
+            Ok(code)
+        }
+    }
+
+    /// Rust spelling of a unary operator
+    const fn unary_op_symbol(op: UnaryOp) -> &'static str {
+        match op {
+            UnaryOp::Not | UnaryOp::BitNot => "!",
+            UnaryOp::Neg => "-",
+            UnaryOp::Ref => "&",
+            UnaryOp::RefMut => "&mut ",
+        }
+    }
+
+    /// Rust spelling of a binary operator
+    const fn bin_op_symbol(op: BinOp) -> &'static str {
+        match op {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::BitXor => "^",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+        }
+    }
+
     /// Extract the receiver variable name from arguments
     ///
     /// Returns the name of the first Variable argument, or "x" as fallback
@@ -164,29 +1419,55 @@ impl RustCodegen {
             .unwrap_or_else(|| "x".to_owned())
     }
 
+    /// Generate the arguments following the receiver, if any were given
+    fn generate_trailing_args(&mut self, args: &[UnifiedHIR]) -> Result<Option<String>> {
+        if args.len() < 2 {
+            return Ok(None);
+        }
+        let mut output = Vec::new();
+        for arg in &args[1..] {
+            output.push(self.generate(arg)?);
+        }
+        Ok(Some(output.join(", ")))
+    }
+
     /// Generate an optimized call (post-boundary-elimination)
-    #[allow(clippy::unnecessary_wraps)]
     fn generate_optimized_call(
+        &mut self,
         callee: &str,
         args: &[UnifiedHIR],
         pattern: UnificationPattern,
-    ) -> String {
+    ) -> Result<String> {
         // Helper: Extract variable name from first argument
         let receiver = Self::extract_receiver_name(args);
 
         // Generate idiomatic Rust based on the pattern
-        match pattern {
+        Ok(match pattern {
             UnificationPattern::LenPattern => {
                 // Vec::len() becomes <receiver>.len()
                 format!("{receiver}.len()")
             }
             UnificationPattern::AppendPattern => {
                 // Vec::push() becomes <receiver>.push(item)
-                format!("{receiver}.push(item)")
+                let item = self
+                    .generate_trailing_args(args)?
+                    .unwrap_or_else(|| "item".to_owned());
+                format!("{receiver}.push({item})")
             }
             UnificationPattern::DictGetPattern => {
+                if let [_, key, default] = args {
+                    // d.get(k, default) becomes <receiver>.get(&k).copied().unwrap_or(default)
+                    let key = self.generate(key)?;
+                    let default = self.generate(default)?;
+                    return Ok(format!(
+                        "{receiver}.get(&{key}).copied().unwrap_or({default})"
+                    ));
+                }
                 // HashMap::get() becomes <receiver>.get(&key)
-                format!("{receiver}.get(&key)")
+                let key = self
+                    .generate_trailing_args(args)?
+                    .unwrap_or_else(|| "key".to_owned());
+                format!("{receiver}.get(&{key})")
             }
             UnificationPattern::ReversePattern => {
                 // Vec::reverse() becomes <receiver>.reverse()
@@ -202,15 +1483,24 @@ impl RustCodegen {
             }
             UnificationPattern::InsertPattern => {
                 // Vec::insert() becomes <receiver>.insert(index, value)
-                format!("{receiver}.insert(index, value)")
+                let rest = self
+                    .generate_trailing_args(args)?
+                    .unwrap_or_else(|| "index, value".to_owned());
+                format!("{receiver}.insert({rest})")
             }
             UnificationPattern::ExtendPattern => {
                 // Vec::extend() becomes <receiver>.extend(iter)
-                format!("{receiver}.extend(iter)")
+                let iter = self
+                    .generate_trailing_args(args)?
+                    .unwrap_or_else(|| "iter".to_owned());
+                format!("{receiver}.extend({iter})")
             }
             UnificationPattern::DictPopPattern => {
                 // HashMap::remove() becomes <receiver>.remove(&key)
-                format!("{receiver}.remove(&key)")
+                let key = self
+                    .generate_trailing_args(args)?
+                    .unwrap_or_else(|| "key".to_owned());
+                format!("{receiver}.remove(&{key})")
             }
             UnificationPattern::DictClearPattern => {
                 // HashMap::clear() becomes <receiver>.clear()
@@ -221,23 +1511,45 @@ impl RustCodegen {
                 format!("{receiver}.keys()")
             }
             UnificationPattern::Custom => format!("{callee}()"),
+        })
+    }
+
+    /// Generate a literal value
+    fn generate_literal(value: &LiteralValue) -> String {
+        match value {
+            LiteralValue::Int(n) => n.to_string(),
+            LiteralValue::Float(f) if f.is_nan() => "f64::NAN".to_owned(),
+            LiteralValue::Float(f) if f.is_infinite() => {
+                if f.is_sign_negative() {
+                    "f64::NEG_INFINITY".to_owned()
+                } else {
+                    "f64::INFINITY".to_owned()
+                }
+            }
+            // Debug formatting always keeps a decimal point or exponent
+            LiteralValue::Float(f) => format!("{f:?}"),
+            LiteralValue::Str(s) => format!("{s:?}"),
+            LiteralValue::Bytes(bytes) => format!("b\"{}\"", bytes.escape_ascii()),
+            LiteralValue::Bool(b) => b.to_string(),
+            LiteralValue::None => "None".to_owned(),
+            LiteralValue::Unit => "()".to_owned(),
         }
     }
 
     /// Generate a regular call
     fn generate_call(&mut self, callee: &str, args: &[UnifiedHIR]) -> Result<String> {
-        let mut output = callee.to_owned();
-        output.push('(');
-
-        for (i, arg) in args.iter().enumerate() {
-            if i > 0 {
-                output.push_str(", ");
-            }
-            output.push_str(&self.generate(arg)?);
-        }
+        Ok(format!("{}({})", ident(callee), self.generate_list(args)?))
+    }
 
-        output.push(')');
-        Ok(output)
+    /// Generate a method call
+    fn generate_method_call(
+        &mut self,
+        receiver: &UnifiedHIR,
+        method: &str,
+        args: &[UnifiedHIR],
+    ) -> Result<String> {
+        let receiver = self.generate_operand(receiver)?;
+        self.generate_call(&format!("{receiver}.{}", ident(method)), args)
     }
 
     /// Generate a return statement
@@ -249,16 +1561,126 @@ impl RustCodegen {
         }
     }
 
-    /// Generate an assignment
-    fn generate_assign(&mut self, target: &str, value: &UnifiedHIR) -> Result<String> {
+    /// Generate an assignment, binding the `mutable` variables `mut`
+    ///
+    /// An `FnMut` closure is bound `mut`; the variables it mutates are
+    /// declared `mut` where they are bound.
+    fn generate_assign(
+        &mut self,
+        target: &Pattern,
+        value: &UnifiedHIR,
+        mutable: &[String],
+    ) -> Result<String> {
         let val_code = self.generate(value)?;
-        Ok(format!("let {target} = {val_code}"))
+        let mutable = match value {
+            UnifiedHIR::Closure {
+                kind: ClosureKind::FnMut,
+                ..
+            } => target.names().into_iter().map(str::to_owned).collect(),
+            _ => mutable.to_vec(),
+        };
+        let binding = Self::generate_binding(target, &mutable);
+        let Pattern::Slice {
+            before,
+            rest,
+            after,
+        } = target
+        else {
+            // A block-like value does not end the `let` by itself
+            let end = if val_code.ends_with('}') { ";" } else { "" };
+            return Ok(format!("let {binding} = {val_code}{end}"));
+        };
+
+        // The items are matched by reference and cloned out, leaving the
+        // sequence usable
+        let owned: Vec<String> = target
+            .names()
+            .into_iter()
+            .map(|name| {
+                if rest.as_deref() == Some(name) {
+                    format!("{}.to_vec()", ident(name))
+                } else {
+                    format!("{}.clone()", ident(name))
+                }
+            })
+            .collect();
+        let owned = match owned.as_slice() {
+            [name] => name.clone(),
+            _ => Self::generate_tuple(&owned),
+        };
+        let expected = before.len() + after.len();
+        let message = if rest.is_some() {
+            format!("not enough values to unpack (expected at least {expected})")
+        } else {
+            format!("wrong number of values to unpack (expected {expected})")
+        };
+        let indent = self.indent();
+        let arm_indent = format!("{indent}{}", self.indent);
+        Ok(format!(
+            "let {binding} = match &{val_code}[..] {{\n\
+             {arm_indent}{} => {owned},\n\
+             {arm_indent}_ => panic!({message:?}),\n\
+             {indent}}};",
+            Self::generate_pattern(target, &[])
+        ))
+    }
+
+    /// Generate a destructuring pattern, marking the `mutable` variables `mut`
+    fn generate_pattern(pattern: &Pattern, mutable: &[String]) -> String {
+        let generate_all = |patterns: &[Pattern]| -> Vec<String> {
+            patterns
+                .iter()
+                .map(|pattern| Self::generate_pattern(pattern, mutable))
+                .collect()
+        };
+        match pattern {
+            Pattern::Name(name) if mutable.contains(name) => format!("mut {}", ident(name)),
+            Pattern::Name(name) => ident(name).into_owned(),
+            Pattern::Tuple(patterns) => Self::generate_tuple(&generate_all(patterns)),
+            Pattern::Slice {
+                before,
+                rest,
+                after,
+            } => {
+                let mut items = generate_all(before);
+                items.extend(rest.iter().map(|rest| format!("{} @ ..", ident(rest))));
+                items.extend(generate_all(after));
+                format!("[{}]", items.join(", "))
+            }
+        }
+    }
+
+    /// Generate the left-hand side of a `let`
+    ///
+    /// A slice pattern binds the tuple of its variables, which
+    /// `generate_assign` extracts with a `match`.
+    fn generate_binding(pattern: &Pattern, mutable: &[String]) -> String {
+        let Pattern::Slice { .. } = pattern else {
+            return Self::generate_pattern(pattern, mutable);
+        };
+        let names: Vec<String> = pattern
+            .names()
+            .into_iter()
+            .map(|name| Self::generate_pattern(&Pattern::name(name), mutable))
+            .collect();
+        match names.as_slice() {
+            [name] => name.clone(),
+            _ => Self::generate_tuple(&names),
+        }
+    }
+
+    /// Generate a tuple, with the trailing comma of a one-element tuple
+    fn generate_tuple(elements: &[String]) -> String {
+        match elements {
+            [element] => format!("({element},)"),
+            _ => format!("({})", elements.join(", ")),
+        }
     }
 
     /// Generate a type annotation
     #[allow(clippy::only_used_in_recursion)]
     fn generate_type(&self, ty: &spydecy_hir::types::Type) -> Result<String> {
-        use spydecy_hir::types::{RustType, Type};
+        use spydecy_hir::types::{PythonType, RustType, Type};
 
         match ty {
             Type::Rust(rust_ty) => match rust_ty {
@@ -279,18 +1701,108 @@ impl RustCodegen {
                 RustType::Str => Ok("&str".to_owned()),
                 RustType::Vec(inner) => Ok(format!("Vec<{}>", self.generate_type(inner)?)),
                 RustType::Option(inner) => Ok(format!("Option<{}>", self.generate_type(inner)?)),
+                RustType::Result { ok, err } => Ok(format!(
+                    "Result<{}, {}>",
+                    self.generate_type(ok)?,
+                    self.generate_type(err)?
+                )),
                 RustType::Unit => Ok("()".to_owned()),
+                RustType::Custom(name) => Ok(name.clone()),
+                RustType::Closure {
+                    mutable,
+                    params,
+                    return_type,
+                } => self.generate_fn_trait(*mutable, params, return_type),
                 RustType::Reference { mutable, inner } => {
                     let mut_str = if *mutable { "mut " } else { "" };
                     Ok(format!("&{mut_str}{}", self.generate_type(inner)?))
                 }
                 _ => Ok("/* complex type */".to_owned()),
             },
-            Type::Unknown => Ok("/* infer */".to_owned()),
+            Type::Python(py_ty) => match py_ty {
+                PythonType::Int => Ok("i64".to_owned()),
+                PythonType::Float => Ok("f64".to_owned()),
+                PythonType::Str => Ok("String".to_owned()),
+                PythonType::Bool => Ok("bool".to_owned()),
+                PythonType::None => Ok("()".to_owned()),
+                PythonType::List(inner) => Ok(format!("Vec<{}>", self.generate_type(inner)?)),
+                PythonType::Set(inner) => Ok(format!(
+                    "std::collections::HashSet<{}>",
+                    self.generate_type(inner)?
+                )),
+                PythonType::Dict { key, value } => Ok(format!(
+                    "std::collections::HashMap<{}, {}>",
+                    self.generate_type(key)?,
+                    self.generate_type(value)?
+                )),
+                PythonType::Tuple(types) => {
+                    let types = types
+                        .iter()
+                        .map(|ty| self.generate_type(ty))
+                        .collect::<Result<Vec<_>>>()?;
+                    Ok(format!("({})", types.join(", ")))
+                }
+                PythonType::Optional(inner) => {
+                    Ok(format!("Option<{}>", self.generate_type(inner)?))
+                }
+                // `threading.Lock` guards no data of its own
+                PythonType::Class(name) if name == "Lock" => Ok("std::sync::Mutex<()>".to_owned()),
+                PythonType::Class(name) => Ok(name.clone()),
+                PythonType::Iterator(item) => Ok(format!(
+                    "impl Iterator<Item = {}>",
+                    self.generate_type(item)?
+                )),
+                PythonType::Union(_) | PythonType::Any => Ok("/* dynamic type */".to_owned()),
+            },
+            // `Callable[[A], R]`
+            Type::Function {
+                params,
+                return_type,
+            } => self.generate_fn_trait(false, params, return_type),
+            Type::Unknown => bail!("Cannot generate a type that was not inferred"),
             _ => Ok("/* non-rust type */".to_owned()),
         }
     }
 
+    /// Generate the type of a declaration, reporting an uninferred type
+    /// at the declaration's source location
+    fn generate_declared_type(
+        &self,
+        ty: &spydecy_hir::types::Type,
+        meta: &Metadata,
+        what: impl FnOnce() -> String,
+    ) -> Result<String> {
+        self.generate_type(ty).map_err(|_| {
+            Diagnostic::error(format!(
+                "cannot infer the type of {}; add a type annotation",
+                what()
+            ))
+            .with_location(meta.source.clone())
+            .into()
+        })
+    }
+
+    /// Generate `impl Fn(A, B) -> R`, or `impl FnMut(..)` when `mutable`
+    fn generate_fn_trait(
+        &self,
+        mutable: bool,
+        params: &[spydecy_hir::types::Type],
+        return_type: &spydecy_hir::types::Type,
+    ) -> Result<String> {
+        use spydecy_hir::types::{PythonType, RustType, Type};
+
+        let params = params
+            .iter()
+            .map(|param| self.generate_type(param))
+            .collect::<Result<Vec<_>>>()?;
+        let returns = match return_type {
+            Type::Rust(RustType::Unit) | Type::Python(PythonType::None) => String::new(),
+            other => format!(" -> {}", self.generate_type(other)?),
+        };
+        let name = if mutable { "FnMut" } else { "Fn" };
+        Ok(format!("impl {name}({}){returns}", params.join(", ")))
+    }
+
     /// Get current indentation string
     fn indent(&self) -> String {
         self.indent.repeat(self.indent_level)
@@ -311,18 +1823,30 @@ impl Default for RustCodegen {
 pub fn generate_rust(hir: &UnifiedHIR) -> Result<String> {
     let mut codegen = RustCodegen::new();
     codegen
-        .generate(hir)
+        .generate_documented(hir)
         .context("Failed to generate Rust code")
 }
 
+/// Generate a crate from the modules of a project (convenience function)
+///
+/// # Errors
+///
+/// Returns an error if code generation fails
+pub fn generate_rust_crate(modules: &[UnifiedHIR]) -> Result<Vec<RustFile>> {
+    let mut codegen = RustCodegen::new();
+    codegen
+        .generate_crate(modules)
+        .context("Failed to generate Rust crate")
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
     use super::*;
     use spydecy_hir::{
-        metadata::Metadata,
-        types::{IntSize, RustType, Type},
-        unified::{CrossMapping, UnificationPattern},
+        metadata::Attribute,
+        types::{IntSize, PythonType, RustType, Type},
+        unified::{Capture, CrossMapping, UnificationPattern},
         Language, NodeId,
     };
 
@@ -416,6 +1940,64 @@ mod tests {
         assert_eq!(code, "my_var");
     }
 
+    #[test]
+    fn test_generate_insert_with_literal_args() {
+        let literal = |id, value| UnifiedHIR::Literal {
+            id: NodeId::new(id),
+            value,
+            lit_type: Type::Unknown,
+            meta: Metadata::new(),
+        };
+        let hir = UnifiedHIR::Call {
+            id: NodeId::new(1),
+            target_language: Language::Rust,
+            callee: "Vec::insert".to_owned(),
+            args: vec![
+                UnifiedHIR::Variable {
+                    id: NodeId::new(2),
+                    name: "xs".to_owned(),
+                    var_type: Type::Unknown,
+                    source_language: Language::Python,
+                    meta: Metadata::new(),
+                },
+                literal(3, LiteralValue::Int(0)),
+                literal(4, LiteralValue::Int(42)),
+            ],
+            inferred_type: Type::Rust(RustType::Unit),
+            source_language: Language::Python,
+            cross_mapping: Some(CrossMapping {
+                python_node: None,
+                c_node: None,
+                pattern: UnificationPattern::InsertPattern,
+                boundary_eliminated: true,
+            }),
+            meta: Metadata::new(),
+        };
+
+        let code = generate_rust(&hir).expect("Should generate code");
+        assert_eq!(code.trim(), "xs.insert(0, 42)");
+    }
+
+    #[test]
+    fn test_generate_literals() {
+        assert_eq!(
+            RustCodegen::generate_literal(&LiteralValue::Float(2.0)),
+            "2.0"
+        );
+        assert_eq!(
+            RustCodegen::generate_literal(&LiteralValue::Str("a\"b".to_owned())),
+            "\"a\\\"b\""
+        );
+        assert_eq!(
+            RustCodegen::generate_literal(&LiteralValue::Bytes(vec![0, b'a'])),
+            "b\"\\x00a\""
+        );
+        assert_eq!(
+            RustCodegen::generate_literal(&LiteralValue::Bool(true)),
+            "true"
+        );
+    }
+
     #[test]
     fn test_generate_type_int() {
         let codegen = RustCodegen::new();
@@ -428,6 +2010,785 @@ mod tests {
         assert_eq!(code, "i32");
     }
 
+    #[test]
+    fn test_generate_python_types() {
+        use spydecy_hir::types::{PythonType, Type};
+
+        let codegen = RustCodegen::new();
+        let py = |ty| Box::new(Type::Python(ty));
+        let ty = Type::Python(PythonType::Dict {
+            key: py(PythonType::Str),
+            value: py(PythonType::List(py(PythonType::Int))),
+        });
+        assert_eq!(
+            codegen.generate_type(&ty).expect("Should generate type"),
+            "std::collections::HashMap<String, Vec<i64>>"
+        );
+
+        let ty = Type::Python(PythonType::Optional(py(PythonType::Tuple(vec![
+            Type::Python(PythonType::Float),
+            Type::Python(PythonType::Class("Point".to_owned())),
+        ]))));
+        assert_eq!(
+            codegen.generate_type(&ty).expect("Should generate type"),
+            "Option<(f64, Point)>"
+        );
+    }
+
+    #[test]
+    fn test_generate_comprehensions() {
+        use spydecy_hir::unified::{BinOp, ComprehensionClause, ComprehensionKind};
+
+        let var = |name: &str| UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let int = |value| UnifiedHIR::Literal {
+            id: NodeId::new(0),
+            value: LiteralValue::Int(value),
+            lit_type: Type::Unknown,
+            meta: Metadata::new(),
+        };
+        let bin_op = |op, left, right| UnifiedHIR::BinOp {
+            id: NodeId::new(0),
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+            result_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let comprehension =
+            |kind, element, value: Option<UnifiedHIR>, clauses| UnifiedHIR::Comprehension {
+                id: NodeId::new(0),
+                kind,
+                element: Box::new(element),
+                value: value.map(Box::new),
+                clauses,
+                result_type: Type::Unknown,
+                source_language: Language::Python,
+                meta: Metadata::new(),
+            };
+
+        // [x * 2 for x in xs if x > 0]
+        let hir = comprehension(
+            ComprehensionKind::List,
+            bin_op(BinOp::Mul, var("x"), int(2)),
+            None,
+            vec![ComprehensionClause {
+                target: Pattern::name("x"),
+                iter: var("xs"),
+                filters: vec![bin_op(BinOp::Gt, var("x"), int(0))],
+            }],
+        );
+        assert_eq!(
+            generate_rust(&hir).expect("Should generate code"),
+            "xs.iter().filter(|&&x| x > 0).map(|&x| x * 2).collect::<Vec<_>>()"
+        );
+
+        // {i: x for i in range(3) for x in xs if x != i}
+        let range = UnifiedHIR::Call {
+            id: NodeId::new(0),
+            target_language: Language::Rust,
+            callee: "range".to_owned(),
+            args: vec![int(3)],
+            inferred_type: Type::Unknown,
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: Metadata::new(),
+        };
+        let hir = comprehension(
+            ComprehensionKind::Dict,
+            var("i"),
+            Some(var("x")),
+            vec![
+                ComprehensionClause {
+                    target: Pattern::name("i"),
+                    iter: range,
+                    filters: vec![],
+                },
+                ComprehensionClause {
+                    target: Pattern::name("x"),
+                    iter: var("xs"),
+                    filters: vec![bin_op(BinOp::Ne, var("x"), var("i"))],
+                },
+            ],
+        );
+        assert_eq!(
+            generate_rust(&hir).expect("Should generate code"),
+            "(0..3).flat_map(|i| xs.iter().filter(move |&&x| x != i).map(move |&x| (i, x)))\
+             .collect::<std::collections::HashMap<_, _>>()"
+        );
+    }
+
+    /// `struct Counter { count }` with `new`, `increment` and `get`
+    fn counter_module() -> UnifiedHIR {
+        use spydecy_hir::{
+            types::PythonType,
+            unified::{BinOp, Receiver, UnifiedField},
+        };
+
+        let int = Type::Python(PythonType::Int);
+        let var = |name: &str| UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let method = |name: &str, receiver, return_type, body| UnifiedHIR::Function {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            receiver,
+            params: vec![],
+            return_type,
+            body,
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: Metadata::new(),
+        };
+        let count = UnifiedHIR::FieldAccess {
+            id: NodeId::new(0),
+            object: Box::new(var("self")),
+            field: "count".to_owned(),
+            field_type: int.clone(),
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        UnifiedHIR::Module {
+            name: "counter".to_owned(),
+            source_language: Language::Python,
+            declarations: vec![
+                UnifiedHIR::Struct {
+                    id: NodeId::new(0),
+                    name: "Counter".to_owned(),
+                    fields: vec![UnifiedField {
+                        name: "count".to_owned(),
+                        field_type: int.clone(),
+                    }],
+                    source_language: Language::Python,
+                    meta: Metadata::new(),
+                },
+                UnifiedHIR::Impl {
+                    id: NodeId::new(0),
+                    type_name: "Counter".to_owned(),
+                    methods: vec![
+                        method(
+                            "new",
+                            None,
+                            Type::Rust(RustType::Custom("Self".to_owned())),
+                            vec![UnifiedHIR::StructInit {
+                                id: NodeId::new(0),
+                                name: "Self".to_owned(),
+                                fields: vec![("count".to_owned(), var("count"))],
+                                meta: Metadata::new(),
+                            }],
+                        ),
+                        method(
+                            "increment",
+                            Some(Receiver::RefMut),
+                            Type::Rust(RustType::Unit),
+                            vec![UnifiedHIR::AugAssign {
+                                id: NodeId::new(0),
+                                target: Box::new(count.clone()),
+                                op: BinOp::Add,
+                                value: Box::new(UnifiedHIR::Literal {
+                                    id: NodeId::new(0),
+                                    value: LiteralValue::Int(1),
+                                    lit_type: int.clone(),
+                                    meta: Metadata::new(),
+                                }),
+                                source_language: Language::Python,
+                                meta: Metadata::new(),
+                            }],
+                        ),
+                        method(
+                            "get",
+                            Some(Receiver::Ref),
+                            int,
+                            vec![UnifiedHIR::Return {
+                                id: NodeId::new(0),
+                                value: Some(Box::new(count)),
+                                source_language: Language::Python,
+                                meta: Metadata::new(),
+                            }],
+                        ),
+                    ],
+                    source_language: Language::Python,
+                    meta: Metadata::new(),
+                },
+            ],
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_generate_struct_and_impl() {
+        let module = counter_module();
+        let code = generate_rust(&module).expect("Should generate code");
+        assert!(
+            code.contains("pub struct Counter {\n    pub count: i64,\n}"),
+            "{code}"
+        );
+        assert!(
+            code.contains(
+                "impl Counter {\n    pub fn new() -> Self {\n        Self { count }\n    }\n"
+            ),
+            "{code}"
+        );
+        assert!(
+            code.contains("    pub fn increment(&mut self) {\n        self.count += 1;\n    }"),
+            "{code}"
+        );
+        assert!(code.contains("pub fn get(&self) -> i64 {"), "{code}");
+    }
+
+    #[test]
+    fn test_generate_struct_attributes_and_enum() {
+        let mut module = counter_module();
+        let UnifiedHIR::Module { declarations, .. } = &mut module else {
+            unreachable!()
+        };
+        declarations[0].metadata_mut().attributes = vec![
+            Attribute::new("@dataclass".to_owned()),
+            Attribute::with_args(
+                "derive".to_owned(),
+                vec!["Debug".to_owned(), "Clone".to_owned()],
+            ),
+        ];
+        declarations.push(UnifiedHIR::Enum {
+            id: NodeId::new(0),
+            name: "Mode".to_owned(),
+            variants: vec![EnumVariant {
+                name: "DarkMode".to_owned(),
+                member: "DARK_MODE".to_owned(),
+                value: LiteralValue::Str("dark".to_owned()),
+            }],
+            value_type: Type::Python(PythonType::Str),
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        });
+
+        let code = generate_rust(&module).expect("Should generate code");
+        assert!(
+            code.contains("\n#[derive(Debug, Clone)]\npub struct Counter {"),
+            "Only Rust attributes should be emitted: {code}"
+        );
+        assert!(code.contains("pub enum Mode {\n    DarkMode,\n}"), "{code}");
+        assert!(
+            code.contains("pub fn value(&self) -> &'static str {")
+                && code.contains("Self::DarkMode => \"DARK_MODE\",")
+                && code.contains(
+                    "match value.as_ref() {\n            \"dark\" => Some(Self::DarkMode),"
+                ),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn test_generate_doc_comments() {
+        let mut module = counter_module();
+        module.metadata_mut().docs = Some("Counting helpers".to_owned());
+        let UnifiedHIR::Module { declarations, .. } = &mut module else {
+            unreachable!()
+        };
+        declarations[0].metadata_mut().docs = Some("A counter\n\nStarts at zero".to_owned());
+        let UnifiedHIR::Impl { methods, .. } = &mut declarations[1] else {
+            unreachable!()
+        };
+        // Indented like a docstring in the class body, with an example
+        methods[1].metadata_mut().docs = Some(
+            "Add one.\n\n        Example:\n\n            >>> c.increment()\n        ".to_owned(),
+        );
+
+        let code = generate_rust(&module).expect("Should generate code");
+        assert!(
+            code.contains("// Generated by Spydecy\n//! Counting helpers\n#![allow(dead_code)]"),
+            "{code}"
+        );
+        assert!(
+            code.contains("/// A counter\n///\n/// Starts at zero\npub struct Counter {"),
+            "{code}"
+        );
+        assert!(
+            code.contains(
+                "    /// Add one.\n    ///\n    /// Example:\n    ///\n    \
+                 /// ```text\n    /// >>> c.increment()\n    /// ```\n    \
+                 pub fn increment(&mut self) {"
+            ),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn test_generate_error_handling() {
+        let var = |name: &str| UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let error_enum = UnifiedHIR::ErrorEnum {
+            id: NodeId::new(1),
+            name: "Error".to_owned(),
+            variants: vec![
+                ErrorVariant {
+                    name: "IndexError".to_owned(),
+                    docs: None,
+                },
+                ErrorVariant {
+                    name: "ValueError".to_owned(),
+                    docs: Some("Bad value".to_owned()),
+                },
+            ],
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let try_catch = UnifiedHIR::TryCatch {
+            id: NodeId::new(2),
+            body: vec![UnifiedHIR::Propagate {
+                id: NodeId::new(3),
+                value: Box::new(var("parsed")),
+                on_none: None,
+                source_language: Language::Python,
+                meta: Metadata::new(),
+            }],
+            handlers: vec![
+                CatchClause {
+                    variants: vec!["IndexError".to_owned(), "ValueError".to_owned()],
+                    binding: Some("err".to_owned()),
+                    body: vec![],
+                },
+                CatchClause {
+                    variants: vec![],
+                    binding: None,
+                    body: vec![],
+                },
+            ],
+            orelse: vec![],
+            finalbody: vec![var("done")],
+            error_type: "Error".to_owned(),
+            propagates: false,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+
+        let code = generate_rust(&error_enum).expect("Should generate enum");
+        assert!(
+            code.contains("pub enum Error {\n    IndexError(String),\n    /// Bad value\n    ValueError(String),\n}"),
+            "{code}"
+        );
+        assert!(
+            code.contains(
+                "Self::IndexError(message) | Self::ValueError(message) => f.write_str(message),"
+            ),
+            "{code}"
+        );
+
+        let code = generate_rust(&try_catch).expect("Should generate try");
+        assert_eq!(
+            code,
+            "match (|| -> Result<(), Error> {\n    parsed?;\n    Ok(())\n})() {\n    \
+             Ok(()) => {\n    },\n    \
+             Err(err @ (Error::IndexError(_) | Error::ValueError(_))) => {\n    },\n    \
+             Err(_) => {\n    },\n\
+             }\ndone"
+        );
+    }
+
+    #[test]
+    fn test_generate_generator() {
+        // yield from xs; for x in range(n): if x == 0: continue; yield x
+        let var = |name: &str| UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let generator = UnifiedHIR::Generator {
+            id: NodeId::new(1),
+            setup: vec![],
+            mutable: vec![],
+            stages: vec![
+                GeneratorStage::Delegate(var("xs")),
+                GeneratorStage::For {
+                    target: Pattern::name("x"),
+                    iter: UnifiedHIR::Call {
+                        id: NodeId::new(2),
+                        target_language: Language::Rust,
+                        callee: "range".to_owned(),
+                        args: vec![var("n")],
+                        inferred_type: Type::Unknown,
+                        source_language: Language::Python,
+                        cross_mapping: None,
+                        meta: Metadata::new(),
+                    },
+                    body: vec![
+                        UnifiedHIR::Advance {
+                            id: NodeId::new(3),
+                            stage: 1,
+                            meta: Metadata::new(),
+                        },
+                        UnifiedHIR::Yield {
+                            id: NodeId::new(4),
+                            value: Box::new(var("x")),
+                            delegate: false,
+                            meta: Metadata::new(),
+                        },
+                    ],
+                },
+            ],
+            item_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+
+        let code = generate_rust(&generator).expect("Should generate generator");
+        assert!(
+            code.contains("let mut iter_0 = xs.into_iter();\n    let mut iter_1 = (0..n);"),
+            "{code}"
+        );
+        assert!(
+            code.contains("std::iter::from_fn(move || 'resume: loop {"),
+            "{code}"
+        );
+        assert!(
+            code.contains(
+                "0 => match iter_0.next() {\n                Some(item) => return Some(item),\n                None => stage = 1,\n            },"
+            ),
+            "{code}"
+        );
+        assert!(
+            code.contains("stage = 1;\n                    continue 'resume;\n                    pending.push_back(x);"),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn test_generate_scope_and_guard_type() {
+        // with lock: total = 1
+        let var = |name: &str| UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let scope = UnifiedHIR::Scope {
+            id: NodeId::new(1),
+            guards: vec![ScopeGuard {
+                name: "_guard".to_owned(),
+                mutable: false,
+                value: UnifiedHIR::MethodCall {
+                    id: NodeId::new(2),
+                    receiver: Box::new(var("lock")),
+                    method: "lock".to_owned(),
+                    args: vec![],
+                    inferred_type: Type::Unknown,
+                    source_language: Language::Python,
+                    meta: Metadata::new(),
+                },
+            }],
+            hoisted: vec![("total".to_owned(), false)],
+            value: None,
+            body: vec![UnifiedHIR::Store {
+                id: NodeId::new(3),
+                target: Box::new(var("total")),
+                value: Box::new(UnifiedHIR::Literal {
+                    id: NodeId::new(4),
+                    value: LiteralValue::Int(1),
+                    lit_type: Type::Unknown,
+                    meta: Metadata::new(),
+                }),
+                source_language: Language::Python,
+                meta: Metadata::new(),
+            }],
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+
+        let code = generate_rust(&scope).expect("Should generate scope");
+        assert_eq!(
+            code,
+            "let total;\n{\n    let _guard = lock.lock();\n    total = 1;\n}"
+        );
+
+        let guard = UnifiedHIR::GuardType {
+            id: NodeId::new(5),
+            name: "TimerGuard".to_owned(),
+            target: "Timer".to_owned(),
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let code = generate_rust(&guard).expect("Should generate guard type");
+        assert!(
+            code.starts_with("pub struct TimerGuard<'a>(&'a mut Timer);"),
+            "{code}"
+        );
+        assert!(
+            code.contains(
+                "impl Drop for TimerGuard<'_> {\n    fn drop(&mut self) {\n        self.0.exit();"
+            ),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn test_generate_destructuring_assignments() {
+        let var = |name: &str| UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let assign = |target: Pattern, value: UnifiedHIR| UnifiedHIR::Assign {
+            id: NodeId::new(1),
+            target,
+            value: Box::new(value),
+            var_type: Type::Unknown,
+            mutable: vec![],
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+
+        // a, (b, c) = a, (c, b)
+        let swap = assign(
+            Pattern::Tuple(vec![
+                Pattern::name("a"),
+                Pattern::Tuple(vec![Pattern::name("b"), Pattern::name("c")]),
+            ]),
+            UnifiedHIR::Tuple {
+                id: NodeId::new(2),
+                elements: vec![
+                    var("a"),
+                    UnifiedHIR::Tuple {
+                        id: NodeId::new(3),
+                        elements: vec![var("c"), var("b")],
+                        tuple_type: Type::Unknown,
+                        meta: Metadata::new(),
+                    },
+                ],
+                tuple_type: Type::Unknown,
+                meta: Metadata::new(),
+            },
+        );
+        let code = generate_rust(&swap).expect("Should generate tuple assignment");
+        assert_eq!(code, "let (a, (b, c)) = (a, (c, b))");
+
+        // first, *rest = xs
+        let starred = assign(
+            Pattern::Slice {
+                before: vec![Pattern::name("first")],
+                rest: Some("rest".to_owned()),
+                after: vec![],
+            },
+            var("xs"),
+        );
+        let code = generate_rust(&starred).expect("Should generate slice assignment");
+        assert_eq!(
+            code,
+            "let (first, rest) = match &xs[..] {\n    \
+             [first, rest @ ..] => (first.clone(), rest.to_vec()),\n    \
+             _ => panic!(\"not enough values to unpack (expected at least 1)\"),\n\
+             };"
+        );
+    }
+
+    #[test]
+    fn test_generate_closures() {
+        let var = |name: &str| UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let param = |name: &str, param_type: Type| UnifiedParameter {
+            name: name.to_owned(),
+            param_type,
+            source_language: Language::Python,
+        };
+        let closure = |body: Vec<UnifiedHIR>, kind: ClosureKind, moves: bool| UnifiedHIR::Closure {
+            id: NodeId::new(1),
+            params: vec![param("x", Type::Unknown)],
+            return_type: Type::Python(PythonType::Int),
+            body,
+            captures: vec![Capture {
+                name: "total".to_owned(),
+                mutable: kind == ClosureKind::FnMut,
+            }],
+            kind,
+            moves,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+
+        // lambda x: x + total
+        let add = closure(
+            vec![UnifiedHIR::Return {
+                id: NodeId::new(2),
+                value: Some(Box::new(UnifiedHIR::BinOp {
+                    id: NodeId::new(3),
+                    op: BinOp::Add,
+                    left: Box::new(var("x")),
+                    right: Box::new(var("total")),
+                    result_type: Type::Unknown,
+                    source_language: Language::Python,
+                    meta: Metadata::new(),
+                })),
+                source_language: Language::Python,
+                meta: Metadata::new(),
+            }],
+            ClosureKind::Fn,
+            true,
+        );
+        let code = generate_rust(&add).expect("Should generate lambda");
+        assert_eq!(code, "move |x| x + total");
+
+        // def bump(x): nonlocal total; total += x; return total
+        let bump = UnifiedHIR::Assign {
+            id: NodeId::new(4),
+            target: Pattern::name("bump"),
+            value: Box::new(closure(
+                vec![
+                    UnifiedHIR::AugAssign {
+                        id: NodeId::new(5),
+                        target: Box::new(var("total")),
+                        op: BinOp::Add,
+                        value: Box::new(var("x")),
+                        source_language: Language::Python,
+                        meta: Metadata::new(),
+                    },
+                    UnifiedHIR::Return {
+                        id: NodeId::new(6),
+                        value: Some(Box::new(var("total"))),
+                        source_language: Language::Python,
+                        meta: Metadata::new(),
+                    },
+                ],
+                ClosureKind::FnMut,
+                false,
+            )),
+            var_type: Type::Unknown,
+            mutable: vec![],
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let code = generate_rust(&bump).expect("Should generate nested function");
+        assert_eq!(
+            code,
+            "let mut bump = |x| -> i64 {\n    \
+             total += x;\n    \
+             return total;\n\
+             };"
+        );
+    }
+
+    #[test]
+    fn test_generate_escapes_rust_keywords() {
+        // def use(type: str) -> str: return type
+        let str_type = Type::Python(PythonType::Str);
+        let function = UnifiedHIR::Function {
+            id: NodeId::new(0),
+            name: "use".to_owned(),
+            receiver: None,
+            params: vec![UnifiedParameter {
+                name: "type".to_owned(),
+                param_type: str_type.clone(),
+                source_language: Language::Python,
+            }],
+            return_type: str_type.clone(),
+            body: vec![UnifiedHIR::Return {
+                id: NodeId::new(1),
+                value: Some(Box::new(UnifiedHIR::Variable {
+                    id: NodeId::new(2),
+                    name: "type".to_owned(),
+                    var_type: str_type,
+                    source_language: Language::Python,
+                    meta: Metadata::new(),
+                })),
+                source_language: Language::Python,
+                meta: Metadata::new(),
+            }],
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: Metadata::new(),
+        };
+
+        let code = generate_rust(&function).expect("Should generate function");
+
+        assert_eq!(
+            code,
+            "pub fn r#use(r#type: String) -> String {\n    return r#type;\n}"
+        );
+    }
+
+    #[test]
+    fn test_generate_crate_declares_modules() {
+        let module = |name: &str, declarations: Vec<UnifiedHIR>| UnifiedHIR::Module {
+            name: name.to_owned(),
+            source_language: Language::Python,
+            declarations,
+            meta: Metadata::new(),
+        };
+        let use_decl = |path: &[&str], alias: Option<&str>| UnifiedHIR::Use {
+            id: NodeId::new(1),
+            path: path.iter().map(|segment| (*segment).to_owned()).collect(),
+            alias: alias.map(str::to_owned),
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        };
+        let modules = [
+            module(
+                "main",
+                vec![
+                    use_decl(&["app", "models", "User"], None),
+                    use_decl(&["app", "util"], Some("u")),
+                ],
+            ),
+            module("app.models", vec![]),
+            module("app.util", vec![]),
+        ];
+
+        let files = generate_rust_crate(&modules).expect("Should generate crate");
+
+        let paths: Vec<_> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "lib.rs",
+                "app/mod.rs",
+                "app/models.rs",
+                "app/util.rs",
+                "main.rs"
+            ]
+        );
+        assert_eq!(
+            files[0].code,
+            "// Generated by Spydecy\n\npub mod app;\npub mod main;\n"
+        );
+        assert!(
+            files[1]
+                .code
+                .ends_with("#![allow(dead_code)]\n\npub mod models;\npub mod util;\n\n"),
+            "{}",
+            files[1].code
+        );
+        assert!(
+            files[4]
+                .code
+                .ends_with("use crate::app::models::User;\nuse crate::app::util as u;\n\n"),
+            "{}",
+            files[4].code
+        );
+    }
+
     #[test]
     fn test_generate_type_vec() {
         let codegen = RustCodegen::new();
@@ -25,6 +25,7 @@ pub mod stepper;
 pub mod visualize;
 
 use anyhow::Result;
+use spydecy_c::config::CParserConfig;
 use state::TranspilationState;
 use std::path::{Path, PathBuf};
 
@@ -42,17 +43,22 @@ pub fn visualize_python_ast(file_path: &Path) -> Result<String> {
 /// # Errors
 ///
 /// Returns an error if the file cannot be read or parsed
-pub fn visualize_c_ast(file_path: &Path) -> Result<String> {
-    visualize::visualize_c(file_path)
+pub fn visualize_c_ast(file_path: &Path, config: &CParserConfig) -> Result<String> {
+    visualize::visualize_c(file_path, config)
 }
 
-/// Start interactive step-through debugging session
+/// Start interactive step-through debugging session, parsing the C file
+/// with the given clang flags
 ///
 /// # Errors
 ///
 /// Returns an error if files cannot be read or REPL fails
-pub fn start_interactive_debugger(python_file: PathBuf, c_file: PathBuf) -> Result<()> {
-    let state = TranspilationState::new(python_file, c_file);
+pub fn start_interactive_debugger(
+    python_file: PathBuf,
+    c_file: PathBuf,
+    c_config: CParserConfig,
+) -> Result<()> {
+    let state = TranspilationState::new(python_file, c_file).with_c_config(c_config);
     let stepper = stepper::Stepper::new(state);
     repl::run_repl(stepper)
 }
@@ -93,7 +99,7 @@ mod tests {
         writeln!(temp_file, "int add(int a, int b) {{\n    return a + b;\n}}").unwrap();
         temp_file.flush().unwrap(); // Ensure content is written
 
-        let result = visualize_c_ast(temp_file.path());
+        let result = visualize_c_ast(temp_file.path(), &CParserConfig::default());
         assert!(
             result.is_ok(),
             "Failed to visualize C: {:?}",
@@ -3,9 +3,11 @@
 //! Provides a command-line interface for debugging.
 
 use crate::commands::{parse_command, Command};
+use crate::state::TranspilationPhase;
 use crate::stepper::Stepper;
 use anyhow::Result;
 use colored::Colorize;
+use spydecy_hir::diagnostics::Severity;
 use std::io::{self, Write};
 
 /// Run interactive REPL session
@@ -57,6 +59,9 @@ fn handle_command(command: Command, stepper: &mut Stepper) -> Result<bool> {
             );
             println!("{} {}", "Phase:".green(), phase.name());
             print_current_state(stepper);
+            if phase == TranspilationPhase::CParsed {
+                print_diagnostics(stepper);
+            }
         }
         Command::Continue => {
             stepper.continue_execution()?;
@@ -128,7 +133,7 @@ fn print_help() {
     );
     println!("  {}  Visualize current state", "visualize, v".yellow());
     println!(
-        "  {}  Inspect target (python_hir, c_hir, etc.)",
+        "  {}  Inspect target (python_hir, c_hir, diagnostics, etc.)",
         "inspect <target>".yellow()
     );
     println!("  {}    Add breakpoint", "break <type>".yellow());
@@ -156,6 +161,17 @@ fn print_current_state(stepper: &Stepper) {
     println!("  {} {}", "Phase:".dimmed(), state.phase.name());
 }
 
+fn print_diagnostics(stepper: &Stepper) {
+    for diagnostic in &stepper.state().c_diagnostics {
+        let text = diagnostic.to_string();
+        match diagnostic.severity {
+            Severity::Error => println!("{}", text.red()),
+            Severity::Warning => println!("{}", text.yellow()),
+            Severity::Note => println!("{}", text.dimmed()),
+        }
+    }
+}
+
 fn visualize_state(stepper: &Stepper) {
     let state = stepper.state();
     println!(
@@ -204,6 +220,13 @@ fn inspect_target(target: &str, stepper: &Stepper) {
                 println!("{}", "C HIR not yet available".dimmed());
             }
         }
+        "diagnostics" => {
+            if state.c_diagnostics.is_empty() {
+                println!("{}", "No C diagnostics".dimmed());
+            } else {
+                print_diagnostics(stepper);
+            }
+        }
         "unified" => {
             if let Some(ref hir) = state.unified_hir {
                 println!("{hir:#?}");
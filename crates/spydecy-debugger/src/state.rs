@@ -4,7 +4,8 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use spydecy_hir::{c::CHIR, python::PythonHIR, unified::UnifiedHIR};
+use spydecy_c::config::CParserConfig;
+use spydecy_hir::{c::CHIR, diagnostics::Diagnostic, python::PythonHIR, unified::UnifiedHIR};
 use std::path::PathBuf;
 
 /// Current phase of transpilation
@@ -75,6 +76,8 @@ pub struct TranspilationState {
     pub python_file: Option<PathBuf>,
     /// C source file
     pub c_file: Option<PathBuf>,
+    /// Clang flags the C source file is parsed with
+    pub c_config: CParserConfig,
     /// Python source code
     pub python_source: Option<String>,
     /// C source code
@@ -83,6 +86,8 @@ pub struct TranspilationState {
     pub python_hir: Option<PythonHIR>,
     /// C HIR
     pub c_hir: Option<CHIR>,
+    /// Clang's diagnostics and the C constructs that had to be skipped
+    pub c_diagnostics: Vec<Diagnostic>,
     /// Unified HIR
     pub unified_hir: Option<UnifiedHIR>,
     /// Optimized HIR
@@ -100,16 +105,25 @@ impl TranspilationState {
             step_count: 0,
             python_file: Some(python_file),
             c_file: Some(c_file),
+            c_config: CParserConfig::default(),
             python_source: None,
             c_source: None,
             python_hir: None,
             c_hir: None,
+            c_diagnostics: Vec::new(),
             unified_hir: None,
             optimized_hir: None,
             rust_code: None,
         }
     }
 
+    /// Parse the C source file with the given clang flags
+    #[must_use]
+    pub fn with_c_config(mut self, c_config: CParserConfig) -> Self {
+        self.c_config = c_config;
+        self
+    }
+
     /// Advance to next phase
     ///
     /// # Errors
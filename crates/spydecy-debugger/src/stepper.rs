@@ -5,7 +5,7 @@
 use crate::commands::Breakpoint;
 use crate::state::{TranspilationPhase, TranspilationState};
 use anyhow::{Context, Result};
-use spydecy_c::parse_c;
+use spydecy_c::parse_c_with_diagnostics;
 use spydecy_codegen::generate_rust;
 use spydecy_hir::unified::Unifier;
 use spydecy_optimizer::OptimizationPipeline;
@@ -123,8 +123,13 @@ impl Stepper {
         let source = fs::read_to_string(c_file)?;
         self.state.c_source = Some(source.clone());
 
-        let hir = parse_c(&source, c_file.to_str().unwrap_or("input.c"))?;
-        self.state.c_hir = Some(hir);
+        let conversion = parse_c_with_diagnostics(
+            &self.state.c_config,
+            &source,
+            c_file.to_str().unwrap_or("input.c"),
+        )?;
+        self.state.c_hir = Some(conversion.hir);
+        self.state.c_diagnostics = conversion.diagnostics;
 
         Ok(())
     }
@@ -219,7 +224,10 @@ fn extract_c_function(c_hir: spydecy_hir::c::CHIR) -> Result<spydecy_hir::c::CHI
     use spydecy_hir::c::CHIR;
 
     if let CHIR::TranslationUnit { declarations, .. } = c_hir {
-        declarations.first().cloned().context("No C declarations")
+        declarations
+            .into_iter()
+            .find(|declaration| matches!(declaration, CHIR::Function { .. }))
+            .context("No C functions")
     } else {
         anyhow::bail!("Expected C TranslationUnit")
     }
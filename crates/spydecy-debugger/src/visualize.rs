@@ -4,7 +4,7 @@
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use spydecy_c::{cpython, parser::CAST};
+use spydecy_c::{config::CParserConfig, cpython, parser::CAST};
 use spydecy_python::parser::PythonAST;
 use std::fs;
 use std::path::Path;
@@ -141,14 +141,15 @@ fn count_nodes(node: &PythonAST) -> usize {
 /// # Errors
 ///
 /// Returns an error if the file cannot be read or parsed
-pub fn visualize_c(file_path: &Path) -> Result<String> {
+pub fn visualize_c(file_path: &Path, config: &CParserConfig) -> Result<String> {
     // Read the source file
     let source = fs::read_to_string(file_path)
         .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
 
     // Parse to AST
     let filename = file_path.to_string_lossy().to_string();
-    let parser = spydecy_c::parser::CParser::new().context("Failed to create C parser")?;
+    let parser = spydecy_c::parser::CParser::with_config(config.clone())
+        .context("Failed to create C parser")?;
     let ast = parser
         .parse(&source, &filename)
         .context("Failed to parse C source")?;
@@ -427,6 +428,9 @@ mod tests {
             node_type: "Module".to_string(),
             lineno: None,
             col_offset: None,
+            end_lineno: None,
+            end_col_offset: None,
+            field: None,
             children: vec![
                 PythonAST::new("FunctionDef".to_string()),
                 PythonAST::new("FunctionDef".to_string()),
@@ -446,7 +450,7 @@ mod tests {
         writeln!(temp_file, "int add(int a, int b) {{\n    return a + b;\n}}").unwrap();
         temp_file.flush().unwrap(); // Ensure content is written
 
-        let result = visualize_c(temp_file.path());
+        let result = visualize_c(temp_file.path(), &CParserConfig::default());
         assert!(
             result.is_ok(),
             "Should visualize C code: {:?}",
@@ -472,7 +476,7 @@ mod tests {
         .unwrap();
         temp_file.flush().unwrap(); // Ensure content is written
 
-        let result = visualize_c(temp_file.path());
+        let result = visualize_c(temp_file.path(), &CParserConfig::default());
         assert!(
             result.is_ok(),
             "Should visualize CPython code: {:?}",
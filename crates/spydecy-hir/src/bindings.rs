@@ -0,0 +1,704 @@
+//! Python locals as Rust bindings
+//!
+//! A Python variable is bound by its first assignment and rebound by the
+//! later ones. Rust declares each local once, with `let`, and only lets it
+//! change when it is declared `mut`:
+//!
+//! ```text
+//! total = 0                 →  let mut total = 0;
+//! for x in xs:              →  for x in xs {
+//!     total = total + x     →      total = total + x;
+//! if flag:                  →  let label;
+//!     label = "on"          →  if flag { label = "on"; } else { label = "off"; }
+//! else:
+//!     label = "off"
+//! print(label)
+//! ```
+//!
+//! Assignments to a bound variable become plain assignments. A variable
+//! first assigned inside a nested block but used after it is declared
+//! before the block; before a `try` statement, whose body runs in a
+//! closure, it is bound to its type's default value instead.
+//!
+//! Locals are `mut` when they are reassigned, augmented, stored into,
+//! mutated through a method or by a closure, and parameters that change are rebound
+//! `let mut p = p;` at the top of the body.
+//!
+//! Closures bind their locals when they are lowered, with the variables
+//! they capture counting as bound.
+
+use crate::{
+    lowering::MUTATING_METHODS,
+    metadata::Metadata,
+    types::{PythonType, Type},
+    unified::{LoopKind, Pattern, UnifiedHIR, UnifiedParameter, Unifier},
+    Language,
+};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+
+impl Unifier {
+    /// Bind the locals of a lowered function or closure body
+    ///
+    /// `captured` are the enclosing functions' variables a closure uses.
+    pub(crate) fn bind_locals(
+        &mut self,
+        params: &[UnifiedParameter],
+        captured: &[&str],
+        body: Vec<UnifiedHIR>,
+    ) -> Result<Vec<UnifiedHIR>> {
+        let bound: HashSet<String> = params
+            .iter()
+            .map(|param| param.name.clone())
+            .chain(captured.iter().map(|name| (*name).to_owned()))
+            .collect();
+        let mut body = self.rebind_block(body, &bound)?;
+
+        let mutated = mutated_names(&body, &self.classes);
+        let mut changes = |name: &str, initialized: bool| {
+            mutated.contains(name) || assignments(name, &body) > usize::from(!initialized)
+        };
+        let mutable_params: Vec<String> = params
+            .iter()
+            .map(|param| param.name.clone())
+            .filter(|name| changes(name, true))
+            .collect();
+        let mut mutable = HashSet::new();
+        collect_mutable(&body, &mut changes, &mut mutable);
+        mark_mutable(&mut body, &mutable);
+
+        let mut rebound = Vec::with_capacity(mutable_params.len() + body.len());
+        for name in mutable_params {
+            let meta = Metadata::new();
+            rebound.push(UnifiedHIR::Assign {
+                id: self.next_node_id(),
+                target: Pattern::Name(name.clone()),
+                value: Box::new(UnifiedHIR::Variable {
+                    id: self.next_node_id(),
+                    name: name.clone(),
+                    var_type: Type::Unknown,
+                    source_language: Language::Python,
+                    meta: meta.clone(),
+                }),
+                var_type: Type::Unknown,
+                mutable: vec![name],
+                source_language: Language::Python,
+                meta,
+            });
+        }
+        rebound.extend(body);
+        Ok(rebound)
+    }
+
+    /// Turn the assignments of a block to variables bound in `outer` or
+    /// earlier in the block into plain assignments
+    ///
+    /// Variables a nested block assigns first and later statements use are
+    /// declared before it.
+    fn rebind_block(
+        &mut self,
+        block: Vec<UnifiedHIR>,
+        outer: &HashSet<String>,
+    ) -> Result<Vec<UnifiedHIR>> {
+        // Variables used by the statements after each one
+        let mut used_after = vec![HashSet::new(); block.len()];
+        for index in (1..block.len()).rev() {
+            let mut used = used_after[index].clone();
+            used_names(&block[index], &mut used);
+            used_after[index - 1] = used;
+        }
+
+        let mut bound = outer.clone();
+        let mut rebound = Vec::with_capacity(block.len());
+        for (statement, used_after) in block.into_iter().zip(used_after) {
+            match statement {
+                UnifiedHIR::Assign {
+                    target,
+                    value,
+                    meta,
+                    ..
+                } if is_reassignment(&target, &value, &bound) => {
+                    rebound.push(self.reassign(&target, *value, &meta)?);
+                }
+                UnifiedHIR::Assign { ref target, .. } => {
+                    bound.extend(target.names().into_iter().map(str::to_owned));
+                    rebound.push(statement);
+                }
+                UnifiedHIR::If { .. } | UnifiedHIR::Loop { .. } | UnifiedHIR::Scope { .. } => {
+                    let mut first = Vec::new();
+                    assigned_names(&statement, &mut first);
+                    for name in first {
+                        if !bound.contains(&name) && used_after.contains(&name) {
+                            rebound.push(UnifiedHIR::Declare {
+                                id: self.next_node_id(),
+                                name: name.clone(),
+                                mutable: false,
+                                meta: statement.metadata().clone(),
+                            });
+                            bound.insert(name);
+                        }
+                    }
+                    rebound.push(self.rebind_nested(statement, &mut bound)?);
+                }
+                UnifiedHIR::TryCatch { .. } => {
+                    // The body runs in a closure, which cannot initialize a
+                    // declared variable, so it starts out as a default
+                    let mut first = Vec::new();
+                    assigned_names(&statement, &mut first);
+                    for name in first {
+                        if !bound.contains(&name) && used_after.contains(&name) {
+                            let meta = statement.metadata();
+                            rebound.push(self.default_binding(&name, meta));
+                            bound.insert(name);
+                        }
+                    }
+                    rebound.push(self.rebind_nested(statement, &mut bound)?);
+                }
+                other => rebound.push(other),
+            }
+        }
+        Ok(rebound)
+    }
+
+    /// `let name = Default::default();`
+    fn default_binding(&mut self, name: &str, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::Assign {
+            id: self.next_node_id(),
+            target: Pattern::name(name),
+            value: Box::new(UnifiedHIR::Call {
+                id: self.next_node_id(),
+                target_language: Language::Rust,
+                callee: "Default::default".to_owned(),
+                args: vec![],
+                inferred_type: Type::Unknown,
+                source_language: Language::Python,
+                cross_mapping: None,
+                meta: meta.clone(),
+            }),
+            var_type: Type::Unknown,
+            mutable: vec![],
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+
+    /// Rebind the blocks nested in a statement
+    ///
+    /// The locals a `Scope` hoists are bound after it.
+    fn rebind_nested(
+        &mut self,
+        statement: UnifiedHIR,
+        bound: &mut HashSet<String>,
+    ) -> Result<UnifiedHIR> {
+        Ok(match statement {
+            UnifiedHIR::If {
+                id,
+                condition,
+                then_branch,
+                else_branch,
+                source_language,
+                meta,
+            } => UnifiedHIR::If {
+                id,
+                condition,
+                then_branch: self.rebind_block(then_branch, bound)?,
+                else_branch: self.rebind_block(else_branch, bound)?,
+                source_language,
+                meta,
+            },
+            UnifiedHIR::Loop {
+                id,
+                kind,
+                body,
+                source_language,
+                meta,
+            } => {
+                let mut inner = bound.clone();
+                if let LoopKind::For { target, .. } = &kind {
+                    inner.extend(target.names().into_iter().map(str::to_owned));
+                }
+                UnifiedHIR::Loop {
+                    id,
+                    kind,
+                    body: self.rebind_block(body, &inner)?,
+                    source_language,
+                    meta,
+                }
+            }
+            UnifiedHIR::TryCatch {
+                id,
+                body,
+                mut handlers,
+                orelse,
+                finalbody,
+                error_type,
+                propagates,
+                source_language,
+                meta,
+            } => {
+                for handler in &mut handlers {
+                    let mut inner = bound.clone();
+                    inner.extend(handler.binding.clone());
+                    handler.body = self.rebind_block(std::mem::take(&mut handler.body), &inner)?;
+                }
+                UnifiedHIR::TryCatch {
+                    id,
+                    body: self.rebind_block(body, bound)?,
+                    handlers,
+                    orelse: self.rebind_block(orelse, bound)?,
+                    finalbody: self.rebind_block(finalbody, bound)?,
+                    error_type,
+                    propagates,
+                    source_language,
+                    meta,
+                }
+            }
+            UnifiedHIR::Scope {
+                id,
+                guards,
+                mut hoisted,
+                body,
+                value,
+                source_language,
+                meta,
+            } => {
+                // A variable bound before the block is assigned, not redeclared
+                hoisted.retain(|(name, _)| !bound.contains(name));
+                bound.extend(hoisted.iter().map(|(name, _)| name.clone()));
+                let mut inner = bound.clone();
+                inner.extend(guards.iter().map(|guard| guard.name.clone()));
+                UnifiedHIR::Scope {
+                    id,
+                    guards,
+                    hoisted,
+                    body: self.rebind_block(body, &inner)?,
+                    value,
+                    source_language,
+                    meta,
+                }
+            }
+            other => other,
+        })
+    }
+}
+
+/// Whether an assignment rebinds variables that are all bound already
+///
+/// Slice patterns and nested functions are always bound anew.
+fn is_reassignment(target: &Pattern, value: &UnifiedHIR, bound: &HashSet<String>) -> bool {
+    !matches!(target, Pattern::Slice { .. })
+        && !matches!(value, UnifiedHIR::Closure { .. })
+        && target.names().iter().all(|name| bound.contains(*name))
+}
+
+/// Visit the statements of a body and the blocks nested in them, without
+/// entering closures
+fn visit_statements<'a>(body: &'a [UnifiedHIR], visit: &mut impl FnMut(&'a UnifiedHIR)) {
+    for statement in body {
+        visit(statement);
+        match statement {
+            UnifiedHIR::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                visit_statements(then_branch, visit);
+                visit_statements(else_branch, visit);
+            }
+            UnifiedHIR::Loop { body, .. } | UnifiedHIR::Scope { body, .. } => {
+                visit_statements(body, visit);
+            }
+            UnifiedHIR::TryCatch {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => {
+                visit_statements(body, visit);
+                for handler in handlers {
+                    visit_statements(&handler.body, visit);
+                }
+                visit_statements(orelse, visit);
+                visit_statements(finalbody, visit);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Variables the blocks nested in a statement bind, in order
+fn assigned_names(statement: &UnifiedHIR, names: &mut Vec<String>) {
+    visit_statements(std::slice::from_ref(statement), &mut |node| {
+        if let UnifiedHIR::Assign { target, .. } = node {
+            for name in target.names() {
+                if !names.iter().any(|known| known == name) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+    });
+}
+
+/// Variables a node or its descendants read or write
+fn used_names(node: &UnifiedHIR, names: &mut HashSet<String>) {
+    if let UnifiedHIR::Variable { name, .. } = node {
+        names.insert(name.clone());
+    }
+    for child in node.children() {
+        used_names(child, names);
+    }
+}
+
+/// Variables that are augmented, stored into, mutated through a method or
+/// mutated by a closure capturing them
+///
+/// `classes` maps the module's classes to their methods taking `&mut self`.
+fn mutated_names(
+    body: &[UnifiedHIR],
+    classes: &BTreeMap<String, HashSet<String>>,
+) -> HashSet<String> {
+    let mut mutated = HashSet::new();
+    visit_statements(body, &mut |statement| {
+        visit_expressions(statement, &mut |node| match node {
+            UnifiedHIR::AugAssign { target, .. } => mutated.extend(root_name(target)),
+            UnifiedHIR::Store { target, .. } => match target.as_ref() {
+                UnifiedHIR::Variable { .. } => {}
+                UnifiedHIR::Tuple { elements, .. } => mutated.extend(
+                    elements
+                        .iter()
+                        .filter(|element| !matches!(element, UnifiedHIR::Variable { .. }))
+                        .filter_map(root_name),
+                ),
+                place => mutated.extend(root_name(place)),
+            },
+            UnifiedHIR::MethodCall {
+                receiver, method, ..
+            } if is_mutating(method) || mutates_instance(receiver, method, classes) => {
+                mutated.extend(root_name(receiver));
+            }
+            UnifiedHIR::Closure { captures, .. } => mutated.extend(
+                captures
+                    .iter()
+                    .filter(|capture| capture.mutable && capture.name != "self")
+                    .map(|capture| capture.name.clone()),
+            ),
+            _ => {}
+        });
+    });
+    mutated
+}
+
+/// Visit a statement's own expressions and their descendants, without
+/// entering nested blocks or closures
+fn visit_expressions<'a>(node: &'a UnifiedHIR, visit: &mut impl FnMut(&'a UnifiedHIR)) {
+    visit(node);
+    let children = match node {
+        UnifiedHIR::If { condition, .. } => vec![condition.as_ref()],
+        UnifiedHIR::Loop { kind, .. } => match kind {
+            LoopKind::For { iter, .. } => vec![iter.as_ref()],
+            LoopKind::While { condition } => vec![condition.as_ref()],
+        },
+        UnifiedHIR::Scope { guards, value, .. } => guards
+            .iter()
+            .map(|guard| &guard.value)
+            .chain(value.iter().map(Box::as_ref))
+            .collect(),
+        UnifiedHIR::TryCatch { .. } | UnifiedHIR::Closure { .. } | UnifiedHIR::Generator { .. } => {
+            vec![]
+        }
+        other => other.children(),
+    };
+    for child in children {
+        visit_expressions(child, visit);
+    }
+}
+
+/// Whether a method of the Python or Rust name mutates its receiver
+fn is_mutating(method: &str) -> bool {
+    MUTATING_METHODS.contains(&method) || matches!(method, "push" | "sort_by_key" | "sort_by")
+}
+
+/// Whether a method call on an instance of one of the module's classes
+/// takes `&mut self`
+fn mutates_instance(
+    receiver: &UnifiedHIR,
+    method: &str,
+    classes: &BTreeMap<String, HashSet<String>>,
+) -> bool {
+    let (UnifiedHIR::Variable { var_type, .. }
+    | UnifiedHIR::FieldAccess {
+        field_type: var_type,
+        ..
+    }) = receiver
+    else {
+        return false;
+    };
+    matches!(var_type, Type::Python(PythonType::Class(class))
+        if classes.get(class).is_some_and(|mutating| mutating.contains(method)))
+}
+
+/// Variable a place expression (`x`, `x.field`, `x[i]`) is rooted at
+fn root_name(place: &UnifiedHIR) -> Option<String> {
+    match place {
+        UnifiedHIR::Variable { name, .. } => Some(name.clone()),
+        UnifiedHIR::FieldAccess { object, .. } | UnifiedHIR::Index { object, .. } => {
+            root_name(object)
+        }
+        _ => None,
+    }
+}
+
+/// Most assignments to a variable any run through a body makes, counting
+/// every assignment in a loop body as two
+fn assignments(name: &str, body: &[UnifiedHIR]) -> usize {
+    body.iter()
+        .map(|statement| match statement {
+            UnifiedHIR::Store { target, .. } => usize::from(assigns(target, name)),
+            UnifiedHIR::If {
+                then_branch,
+                else_branch,
+                ..
+            } => assignments(name, then_branch).max(assignments(name, else_branch)),
+            UnifiedHIR::Loop { body, .. } => 2 * assignments(name, body),
+            UnifiedHIR::Scope { body, .. } => assignments(name, body),
+            UnifiedHIR::TryCatch {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => {
+                assignments(name, body)
+                    + handlers
+                        .iter()
+                        .map(|handler| assignments(name, &handler.body))
+                        .max()
+                        .unwrap_or_default()
+                    + assignments(name, orelse)
+                    + assignments(name, finalbody)
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Whether an assignment target is or contains the variable
+fn assigns(target: &UnifiedHIR, name: &str) -> bool {
+    match target {
+        UnifiedHIR::Variable { name: variable, .. } => variable == name,
+        UnifiedHIR::Tuple { elements, .. } => elements.iter().any(|element| assigns(element, name)),
+        _ => false,
+    }
+}
+
+/// Variables of a body whose binding must be `mut`
+///
+/// `changes(name, initialized)` tells whether a variable changes after its
+/// binding, which initializes it unless it is a declaration.
+fn collect_mutable(
+    body: &[UnifiedHIR],
+    changes: &mut impl FnMut(&str, bool) -> bool,
+    mutable: &mut HashSet<String>,
+) {
+    visit_statements(body, &mut |statement| {
+        let bindings: Vec<(&str, bool)> = match statement {
+            UnifiedHIR::Assign { target, .. } => target
+                .names()
+                .into_iter()
+                .map(|name| (name, true))
+                .collect(),
+            UnifiedHIR::Declare { name, .. } => vec![(name.as_str(), false)],
+            UnifiedHIR::Scope { hoisted, .. } => hoisted
+                .iter()
+                .map(|(name, _)| (name.as_str(), false))
+                .collect(),
+            _ => vec![],
+        };
+        for (name, initialized) in bindings {
+            if changes(name, initialized) {
+                mutable.insert(name.to_owned());
+            }
+        }
+    });
+}
+
+/// Declare the `mutable` variables of a body's bindings `mut`
+fn mark_mutable(body: &mut [UnifiedHIR], mutable: &HashSet<String>) {
+    for statement in body {
+        match statement {
+            UnifiedHIR::Assign {
+                target,
+                mutable: names,
+                ..
+            } => names.extend(
+                target
+                    .names()
+                    .into_iter()
+                    .filter(|name| mutable.contains(*name))
+                    .map(str::to_owned),
+            ),
+            UnifiedHIR::Declare {
+                name,
+                mutable: declared,
+                ..
+            } => *declared = mutable.contains(name),
+            UnifiedHIR::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                mark_mutable(then_branch, mutable);
+                mark_mutable(else_branch, mutable);
+            }
+            UnifiedHIR::Loop { body, .. } => mark_mutable(body, mutable),
+            UnifiedHIR::Scope { hoisted, body, .. } => {
+                for (name, declared) in hoisted {
+                    *declared = mutable.contains(name);
+                }
+                mark_mutable(body, mutable);
+            }
+            UnifiedHIR::TryCatch {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => {
+                mark_mutable(body, mutable);
+                for handler in handlers {
+                    mark_mutable(&mut handler.body, mutable);
+                }
+                mark_mutable(orelse, mutable);
+                mark_mutable(finalbody, mutable);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{unified::LiteralValue, NodeId};
+
+    fn var(name: &str) -> UnifiedHIR {
+        UnifiedHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn int(value: i64) -> UnifiedHIR {
+        UnifiedHIR::Literal {
+            id: NodeId::new(0),
+            value: LiteralValue::Int(value),
+            lit_type: Type::Unknown,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn assign(name: &str, value: UnifiedHIR) -> UnifiedHIR {
+        UnifiedHIR::Assign {
+            id: NodeId::new(0),
+            target: Pattern::name(name),
+            value: Box::new(value),
+            var_type: Type::Unknown,
+            mutable: vec![],
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn branch(then_branch: Vec<UnifiedHIR>, else_branch: Vec<UnifiedHIR>) -> UnifiedHIR {
+        UnifiedHIR::If {
+            id: NodeId::new(0),
+            condition: Box::new(var("flag")),
+            then_branch,
+            else_branch,
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn ret(name: &str) -> UnifiedHIR {
+        UnifiedHIR::Return {
+            id: NodeId::new(0),
+            value: Some(Box::new(var(name))),
+            source_language: Language::Python,
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_reassignment_assigns_mutable_binding() {
+        // x = 1; if flag: x = 2; return x
+        let body = vec![
+            assign("x", int(1)),
+            branch(vec![assign("x", int(2))], vec![]),
+            ret("x"),
+        ];
+        let body = Unifier::new()
+            .bind_locals(&[], &[], body)
+            .expect("Should bind locals");
+
+        let [UnifiedHIR::Assign { mutable, .. }, UnifiedHIR::If { then_branch, .. }, UnifiedHIR::Return { .. }] =
+            body.as_slice()
+        else {
+            panic!("Expected a binding, an if and a return, got {body:?}");
+        };
+        assert_eq!(mutable, &["x"]);
+        assert!(
+            matches!(then_branch.as_slice(), [UnifiedHIR::Store { target, .. }]
+                if matches!(target.as_ref(), UnifiedHIR::Variable { name, .. } if name == "x")),
+            "The reassignment should store into the binding, got {then_branch:?}"
+        );
+    }
+
+    #[test]
+    fn test_variable_first_assigned_in_branches_is_declared() {
+        // if flag: y = 1 else: y = 2; return y
+        let body = vec![
+            branch(vec![assign("y", int(1))], vec![assign("y", int(2))]),
+            ret("y"),
+        ];
+        let body = Unifier::new()
+            .bind_locals(&[], &[], body)
+            .expect("Should bind locals");
+
+        let [UnifiedHIR::Declare { name, mutable, .. }, UnifiedHIR::If {
+            then_branch,
+            else_branch,
+            ..
+        }, UnifiedHIR::Return { .. }] = body.as_slice()
+        else {
+            panic!("Expected a declaration, an if and a return, got {body:?}");
+        };
+        assert_eq!(name, "y");
+        assert!(!mutable, "Each path assigns the declaration once");
+        assert!(matches!(then_branch.as_slice(), [UnifiedHIR::Store { .. }]));
+        assert!(matches!(else_branch.as_slice(), [UnifiedHIR::Store { .. }]));
+    }
+
+    #[test]
+    fn test_variable_local_to_a_branch_stays_there() {
+        // if flag: z = 1; return 0
+        let body = vec![branch(vec![assign("z", int(1))], vec![]), ret("flag")];
+        let body = Unifier::new()
+            .bind_locals(&[], &[], body)
+            .expect("Should bind locals");
+
+        let [UnifiedHIR::If { then_branch, .. }, UnifiedHIR::Return { .. }] = body.as_slice()
+        else {
+            panic!("Expected an if and a return, got {body:?}");
+        };
+        assert!(matches!(
+            then_branch.as_slice(),
+            [UnifiedHIR::Assign { mutable, .. }] if mutable.is_empty()
+        ));
+    }
+}
@@ -3,8 +3,13 @@
 //! This module defines HIR nodes for C constructs, with special support
 //! for `CPython` API patterns.
 
-use crate::{metadata::Metadata, types::Type, NodeId, Visibility};
+use crate::{
+    metadata::Metadata,
+    types::{CType, Type},
+    NodeId, Visibility,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// C HIR node
 #[allow(clippy::module_name_repetitions)]
@@ -52,6 +57,30 @@ pub enum CHIR {
         meta: Metadata,
     },
 
+    /// Union definition
+    Union {
+        /// Node ID
+        id: NodeId,
+        /// Union name
+        name: String,
+        /// Fields, sharing their storage
+        fields: Vec<Field>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Enum definition
+    Enum {
+        /// Node ID
+        id: NodeId,
+        /// Enum name, empty for an anonymous `enum { ... }`
+        name: String,
+        /// Constants, in declaration order
+        constants: Vec<EnumConstant>,
+        /// Metadata
+        meta: Metadata,
+    },
+
     /// Function call
     Call {
         /// Node ID
@@ -78,7 +107,9 @@ pub enum CHIR {
         meta: Metadata,
     },
 
-    /// Variable declaration
+    /// Variable declaration; a `typedef` is one with
+    /// [`StorageClass::Typedef`], declaring its name as an alias of
+    /// `var_type`
     VarDecl {
         /// Node ID
         id: NodeId,
@@ -295,6 +326,15 @@ pub struct Field {
     pub field_type: Type,
 }
 
+/// Enum constant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnumConstant {
+    /// Constant name
+    pub name: String,
+    /// Value, explicit or implied by the previous constant
+    pub value: i64,
+}
+
 /// Storage class
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StorageClass {
@@ -391,6 +431,8 @@ impl CHIR {
             Self::TranslationUnit { .. } => None,
             Self::Function { id, .. }
             | Self::Struct { id, .. }
+            | Self::Union { id, .. }
+            | Self::Enum { id, .. }
             | Self::Call { id, .. }
             | Self::Variable { id, .. }
             | Self::VarDecl { id, .. }
@@ -418,6 +460,8 @@ impl CHIR {
             Self::TranslationUnit { meta, .. }
             | Self::Function { meta, .. }
             | Self::Struct { meta, .. }
+            | Self::Union { meta, .. }
+            | Self::Enum { meta, .. }
             | Self::Call { meta, .. }
             | Self::Variable { meta, .. }
             | Self::VarDecl { meta, .. }
@@ -438,6 +482,67 @@ impl CHIR {
         }
     }
 
+    /// Get the direct child nodes, in source order
+    #[must_use]
+    pub fn children(&self) -> Vec<&CHIR> {
+        match self {
+            Self::TranslationUnit {
+                declarations: nodes,
+                ..
+            }
+            | Self::Function { body: nodes, .. }
+            | Self::CPythonMacro { args: nodes, .. } => nodes.iter().collect(),
+            Self::Call { callee, args, .. } => std::iter::once(callee.as_ref())
+                .chain(args.iter())
+                .collect(),
+            Self::VarDecl { init, .. } => init.iter().map(Box::as_ref).collect(),
+            Self::Return { value, .. } => value.iter().map(Box::as_ref).collect(),
+            Self::Assign { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => std::iter::once(condition.as_ref())
+                .chain(then_branch.iter())
+                .chain(else_branch.iter())
+                .collect(),
+            Self::For {
+                init,
+                condition,
+                increment,
+                body,
+                ..
+            } => [init, condition, increment]
+                .into_iter()
+                .flatten()
+                .map(Box::as_ref)
+                .chain(body.iter())
+                .collect(),
+            Self::While {
+                condition, body, ..
+            } => std::iter::once(condition.as_ref())
+                .chain(body.iter())
+                .collect(),
+            Self::BinOp { left, right, .. }
+            | Self::ArraySubscript {
+                array: left,
+                index: right,
+                ..
+            } => vec![left, right],
+            Self::UnaryOp { operand: inner, .. }
+            | Self::FieldAccess { object: inner, .. }
+            | Self::Cast { expr: inner, .. }
+            | Self::Deref { pointer: inner, .. }
+            | Self::AddrOf { var: inner, .. } => vec![inner],
+            Self::Struct { .. }
+            | Self::Union { .. }
+            | Self::Enum { .. }
+            | Self::Variable { .. }
+            | Self::Literal { .. } => vec![],
+        }
+    }
+
     /// Check if this is a `CPython` API call
     #[must_use]
     pub fn is_cpython_api(&self) -> bool {
@@ -455,6 +560,155 @@ impl CHIR {
     }
 }
 
+/// Named types declared by a C translation unit
+///
+/// Follows `typedef` chains to the type they name, and finds the fields of
+/// [`CType::Struct`] and [`CType::Union`] types.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CTypes {
+    /// Fields of structs and unions, by tag
+    records: BTreeMap<String, Vec<Field>>,
+    /// Type named by each typedef
+    typedefs: BTreeMap<String, Type>,
+    /// Values of enum constants
+    constants: BTreeMap<String, i64>,
+}
+
+impl CTypes {
+    /// No declared types, for C code without a translation unit
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self {
+            records: BTreeMap::new(),
+            typedefs: BTreeMap::new(),
+            constants: BTreeMap::new(),
+        }
+    }
+
+    /// Collect the type declarations of a translation unit
+    #[must_use]
+    pub fn new(translation_unit: &CHIR) -> Self {
+        let mut types = Self::empty();
+        for declaration in translation_unit.children() {
+            match declaration {
+                CHIR::Struct { name, fields, .. } | CHIR::Union { name, fields, .. } => {
+                    types.records.insert(name.clone(), fields.clone());
+                }
+                CHIR::Enum { constants, .. } => types.constants.extend(
+                    constants
+                        .iter()
+                        .map(|constant| (constant.name.clone(), constant.value)),
+                ),
+                CHIR::VarDecl {
+                    name,
+                    var_type,
+                    storage_class: StorageClass::Typedef,
+                    ..
+                } => {
+                    types.typedefs.insert(name.clone(), var_type.clone());
+                }
+                _ => {}
+            }
+        }
+        types
+    }
+
+    /// The type with every typedef replaced by the type it names, including
+    /// pointed-to and element types
+    #[must_use]
+    pub fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::C(c_type) => Type::C(self.resolve_c(c_type, 0)),
+            other => other.clone(),
+        }
+    }
+
+    fn resolve_c(&self, ty: &CType, depth: usize) -> CType {
+        match ty {
+            // A chain longer than the number of typedefs is a cycle
+            CType::Typedef(name) if depth <= self.typedefs.len() => match self.typedefs.get(name) {
+                Some(Type::C(named)) => self.resolve_c(named, depth + 1),
+                _ => ty.clone(),
+            },
+            CType::Pointer(inner) => CType::Pointer(Box::new(self.resolve_c(inner, depth))),
+            CType::Array { element, size } => CType::Array {
+                element: Box::new(self.resolve_c(element, depth)),
+                size: *size,
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// Fields of a struct or union type, or of the one a pointer points to
+    /// (as accessed with `->`)
+    #[must_use]
+    pub fn fields(&self, ty: &Type) -> Option<&[Field]> {
+        let Type::C(resolved) = self.resolve(ty) else {
+            return None;
+        };
+        let record = match resolved {
+            CType::Pointer(inner) => *inner,
+            other => other,
+        };
+        match record {
+            CType::Struct(name) | CType::Union(name) => self.records.get(&name).map(Vec::as_slice),
+            _ => None,
+        }
+    }
+
+    /// Type of a field of a struct or union type, or of a pointer to one
+    #[must_use]
+    pub fn field_type(&self, ty: &Type, field: &str) -> Option<&Type> {
+        self.fields(ty)?
+            .iter()
+            .find(|candidate| candidate.name == field)
+            .map(|found| &found.field_type)
+    }
+
+    /// Value of an enum constant
+    #[must_use]
+    pub fn constant(&self, name: &str) -> Option<i64> {
+        self.constants.get(name).copied()
+    }
+
+    /// Type of an expression in a function with the given parameters, with
+    /// typedefs resolved
+    ///
+    /// Uses the type clang gave the expression where there is one; the
+    /// fields read with `.` and `->` are otherwise looked up in their struct.
+    #[must_use]
+    pub fn expr_type(&self, expr: &CHIR, params: &[Parameter]) -> Option<Type> {
+        let ty = match expr {
+            CHIR::Cast { expr, .. } => return self.expr_type(expr, params),
+            CHIR::Variable { name, var_type, .. } => var_type.clone().or_else(|| {
+                params
+                    .iter()
+                    .find(|param| param.name == *name)
+                    .map(|param| param.param_type.clone())
+            })?,
+            CHIR::FieldAccess {
+                inferred_type: Some(ty),
+                ..
+            }
+            | CHIR::ArraySubscript {
+                inferred_type: Some(ty),
+                ..
+            } => ty.clone(),
+            CHIR::FieldAccess { object, field, .. } => self
+                .field_type(&self.expr_type(object, params)?, field)?
+                .clone(),
+            CHIR::ArraySubscript { array, .. } => match self.expr_type(array, params)? {
+                Type::C(CType::Pointer(element) | CType::Array { element, .. }) => {
+                    Type::C(*element)
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(self.resolve(&ty))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -520,4 +774,84 @@ mod tests {
 
         assert!(macro_call.is_cpython_api());
     }
+
+    #[test]
+    fn test_c_types_resolve_typedef_chains_and_fields() {
+        let c = |ty: CType| Type::C(ty);
+        let typedef = |name: &str, var_type: Type| CHIR::VarDecl {
+            id: NodeId::new(1),
+            name: name.to_owned(),
+            var_type,
+            init: None,
+            storage_class: StorageClass::Typedef,
+            meta: Metadata::new(),
+        };
+        let field = |name: &str, field_type: Type| Field {
+            name: name.to_owned(),
+            field_type,
+        };
+        // typedef long ssize; typedef ssize count_t;
+        // typedef struct node { count_t size; struct node *next; } Node;
+        // enum color { RED, GREEN = 5, BLUE };
+        let unit = CHIR::TranslationUnit {
+            name: "main".to_owned(),
+            declarations: vec![
+                typedef("ssize", c(CType::Long)),
+                typedef("count_t", c(CType::Typedef("ssize".to_owned()))),
+                CHIR::Struct {
+                    id: NodeId::new(2),
+                    name: "node".to_owned(),
+                    fields: vec![
+                        field("size", c(CType::Typedef("count_t".to_owned()))),
+                        field(
+                            "next",
+                            c(CType::Pointer(Box::new(CType::Struct("node".to_owned())))),
+                        ),
+                    ],
+                    meta: Metadata::new(),
+                },
+                typedef("Node", c(CType::Struct("node".to_owned()))),
+                CHIR::Enum {
+                    id: NodeId::new(3),
+                    name: "color".to_owned(),
+                    constants: vec![
+                        EnumConstant {
+                            name: "RED".to_owned(),
+                            value: 0,
+                        },
+                        EnumConstant {
+                            name: "BLUE".to_owned(),
+                            value: 6,
+                        },
+                    ],
+                    meta: Metadata::new(),
+                },
+                // A cycle is left unresolved rather than followed forever
+                typedef("loop_a", c(CType::Typedef("loop_b".to_owned()))),
+                typedef("loop_b", c(CType::Typedef("loop_a".to_owned()))),
+            ],
+            meta: Metadata::new(),
+        };
+        let types = CTypes::new(&unit);
+
+        let node_ptr = c(CType::Pointer(Box::new(CType::Typedef("Node".to_owned()))));
+        assert_eq!(
+            types.resolve(&node_ptr),
+            c(CType::Pointer(Box::new(CType::Struct("node".to_owned()))))
+        );
+        assert_eq!(
+            types
+                .field_type(&node_ptr, "size")
+                .map(|size| types.resolve(size)),
+            Some(c(CType::Long))
+        );
+        assert_eq!(types.fields(&node_ptr).map(<[Field]>::len), Some(2));
+        assert!(types.field_type(&node_ptr, "missing").is_none());
+        assert!(types.fields(&c(CType::Int)).is_none());
+        assert_eq!(types.constant("BLUE"), Some(6));
+        assert!(matches!(
+            types.resolve(&c(CType::Typedef("loop_a".to_owned()))),
+            Type::C(CType::Typedef(_))
+        ));
+    }
 }
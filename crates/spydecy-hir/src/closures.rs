@@ -0,0 +1,785 @@
+//! Lambdas and nested functions
+//!
+//! Both become Rust closures. A lambda is an expression, and a nested `def`
+//! binds its closure to a local of the same name:
+//!
+//! ```text
+//! def scale(x: int) -> int:       →  let scale = |x: i64| x * factor;
+//!     return x * factor
+//! sorted(rows, key=lambda r: r[1])  →  { let mut sorted = rows.clone(); sorted.sort_by_key(|r| r.1); sorted }
+//! ```
+//!
+//! Capture analysis lists the variables of the enclosing functions each
+//! closure uses, and they decide its shape:
+//!
+//! - a closure assigning to a `nonlocal` variable or mutating a captured
+//!   collection is `FnMut`, and the variable is rebound `mut` before it
+//! - a closure returned by the enclosing function outlives its locals, so
+//!   it is a `move` closure and the function returns `impl Fn(..)`
+//! - any other closure is `Fn` and borrows its captures
+//!
+//! Recursive nested functions, nested generators and closures that can
+//! raise are reported, and so are lambdas mutating their captures anywhere
+//! but on the right of an assignment.
+
+use crate::{
+    generators::is_generator,
+    lowering::{expr_type, is_copy, lower_parameter, unsupported_in, walk, MUTATING_METHODS},
+    metadata::Metadata,
+    python::{Literal as PythonLiteral, Parameter, PythonHIR},
+    types::{PythonType, RustType, Type},
+    unified::{
+        bind_keyword_args, Capture, ClosureKind, Pattern, ScopeGuard, UnifiedHIR, UnifiedParameter,
+        Unifier,
+    },
+    unpacking::item_type,
+    Language, NodeId,
+};
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// What the closures of the function being lowered can capture
+#[derive(Debug, Default)]
+pub(crate) struct ClosureScope {
+    /// Parameters and locals of the enclosing functions
+    locals: HashSet<String>,
+    /// Nested functions the innermost enclosing function returns or yields
+    returned: HashSet<String>,
+    /// Lambdas the innermost enclosing function returns or yields
+    returned_lambdas: HashSet<NodeId>,
+    /// Return annotation of the innermost enclosing function
+    return_annotation: Option<Type>,
+}
+
+impl ClosureScope {
+    /// The scope of a function nested in `enclosing`, if any
+    pub(crate) fn new(
+        params: &[Parameter],
+        body: &[PythonHIR],
+        return_annotation: Option<&Type>,
+        enclosing: Option<&Self>,
+    ) -> Self {
+        let mut scope = Self {
+            locals: enclosing
+                .map(|scope| scope.locals.clone())
+                .unwrap_or_default(),
+            return_annotation: return_annotation.cloned(),
+            ..Self::default()
+        };
+        scope.locals.extend(own_names(params, body));
+        for statement in body {
+            walk(statement, &mut |node| {
+                let value = match node {
+                    PythonHIR::Return {
+                        value: Some(value), ..
+                    }
+                    | PythonHIR::Yield {
+                        value: Some(value), ..
+                    } => value.as_ref(),
+                    _ => return,
+                };
+                match value {
+                    PythonHIR::Variable { name, .. } => {
+                        scope.returned.insert(name.clone());
+                    }
+                    PythonHIR::Lambda { id, .. } => {
+                        scope.returned_lambdas.insert(*id);
+                    }
+                    _ => {}
+                }
+            });
+        }
+        scope
+    }
+}
+
+impl Unifier {
+    /// Lower a nested function into a closure bound to its name
+    pub(crate) fn lower_nested_function(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Function {
+            name,
+            params,
+            return_type,
+            body,
+            decorators,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported_in("nested function", node));
+        };
+        if let Some(decorator) = decorators.first() {
+            return Err(unsupported_in(&format!("decorator `@{decorator}`"), node));
+        }
+        if free_names(params, body).contains(name) {
+            return Err(unsupported_in("recursive nested function", node));
+        }
+        if is_generator(body) {
+            return Err(unsupported_in("nested generator function", node));
+        }
+
+        let moves = self
+            .closures
+            .as_ref()
+            .is_some_and(|scope| scope.returned.contains(name));
+        let return_type = match return_type {
+            Some(Type::Python(PythonType::None)) => Type::Rust(RustType::Unit),
+            Some(ty) => ty.clone(),
+            None => Type::Unknown,
+        };
+        let closure = self.lower_closure(node, params, body, return_type, moves)?;
+        Ok(UnifiedHIR::Assign {
+            id: self.next_node_id(),
+            target: Pattern::Name(name.clone()),
+            value: Box::new(closure),
+            var_type: Type::Unknown,
+            mutable: vec![],
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower the value of an assignment, where a lambda may mutate its captures
+    pub(crate) fn lower_assigned(&mut self, value: &PythonHIR) -> Result<UnifiedHIR> {
+        match value {
+            PythonHIR::Lambda { .. } => self.lower_lambda(value, &[], true),
+            _ => self.lower_value(value),
+        }
+    }
+
+    /// Lower a lambda
+    ///
+    /// `param_types` types the parameters' uses in the body, without
+    /// annotating the closure's parameters. A lambda that is not `bound` to
+    /// a variable cannot be declared `mut` and must not mutate its captures.
+    pub(crate) fn lower_lambda(
+        &mut self,
+        node: &PythonHIR,
+        param_types: &[Type],
+        bound: bool,
+    ) -> Result<UnifiedHIR> {
+        let PythonHIR::Lambda {
+            id,
+            params,
+            body,
+            meta,
+        } = node
+        else {
+            return Err(unsupported_in("lambda", node));
+        };
+
+        let scope = self.closures.as_ref();
+        let moves = scope.is_some_and(|scope| scope.returned_lambdas.contains(id));
+        // `return lambda x: ..` from a function returning `Callable[[A], R]`
+        let (param_types, return_type) = match scope.and_then(|scope| {
+            scope
+                .return_annotation
+                .as_ref()
+                .filter(|_| moves && param_types.is_empty())
+        }) {
+            Some(Type::Function {
+                params,
+                return_type,
+            }) => (params.as_slice(), return_type.as_ref().clone()),
+            _ => (param_types, Type::Unknown),
+        };
+
+        let mut value = body.as_ref().clone();
+        let typed: Vec<(&str, &Type)> = params
+            .iter()
+            .map(|param| param.name.as_str())
+            .zip(param_types)
+            .collect();
+        type_parameters(&mut value, &typed);
+        let body = PythonHIR::Return {
+            id: *id,
+            value: Some(Box::new(value)),
+            meta: meta.clone(),
+        };
+        let closure = self.lower_closure(node, params, &[body], return_type, moves)?;
+        if let (
+            false,
+            UnifiedHIR::Closure {
+                kind: ClosureKind::FnMut,
+                ..
+            },
+        ) = (bound, &closure)
+        {
+            return Err(unsupported_in(
+                "lambda mutating a captured variable outside an assignment",
+                node,
+            ));
+        }
+        Ok(closure)
+    }
+
+    /// Lower the parameters and body of a lambda or nested function
+    fn lower_closure(
+        &mut self,
+        node: &PythonHIR,
+        params: &[Parameter],
+        body: &[PythonHIR],
+        return_type: Type,
+        moves: bool,
+    ) -> Result<UnifiedHIR> {
+        if !self.exceptions.raised_by(body).is_empty() {
+            return Err(unsupported_in("closure that can raise", node));
+        }
+        let captures = captures(
+            params,
+            body,
+            self.closures.as_ref().map(|scope| &scope.locals),
+        );
+        let kind = if captures.iter().any(|capture| capture.mutable) {
+            ClosureKind::FnMut
+        } else {
+            ClosureKind::Fn
+        };
+
+        // The body returns from the closure, not from the enclosing function
+        let scope = ClosureScope::new(params, body, None, self.closures.as_ref());
+        let enclosing_scope = self.closures.replace(scope);
+        let generator = self.generator.take();
+        let returns_result = std::mem::replace(&mut self.exceptions.returns_result, false);
+        let lowered = self.lower_body(body);
+        self.closures = enclosing_scope;
+        self.generator = generator;
+        self.exceptions.returns_result = returns_result;
+
+        let params: Vec<UnifiedParameter> = params.iter().map(lower_parameter).collect();
+        let captured_names: Vec<&str> = captures
+            .iter()
+            .map(|capture| capture.name.as_str())
+            .collect();
+        let body = self.bind_locals(&params, &captured_names, lowered?)?;
+        Ok(UnifiedHIR::Closure {
+            id: self.next_node_id(),
+            params,
+            return_type,
+            body,
+            captures,
+            kind,
+            moves,
+            source_language: Language::Python,
+            meta: node.metadata().clone(),
+        })
+    }
+
+    /// Lower `xs.sort(key=f, reverse=r)` and `sorted(xs, key=f, reverse=r)`
+    ///
+    /// A `key` sorts with `sort_by_key`, whose key lambda's parameter has
+    /// the type of the items being sorted. `reverse=True` wraps the key in
+    /// `std::cmp::Reverse`, which keeps the sort stable as Python's is, or
+    /// without a key compares the items the other way around. `sorted`
+    /// sorts a copy in a block evaluating to it. Returns `None` for any
+    /// other call.
+    pub(crate) fn lower_sort_call(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Call {
+            callee,
+            args,
+            kwargs,
+            meta,
+            ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        let (items, copies) = match (callee.as_ref(), args.as_slice()) {
+            (PythonHIR::Attribute { object, attr, .. }, []) if attr == "sort" => {
+                (object.as_ref(), false)
+            }
+            (PythonHIR::Variable { name, .. }, [iterable]) if name == "sorted" => (iterable, true),
+            _ => return Ok(None),
+        };
+        if !copies && kwargs.is_empty() {
+            return Ok(None);
+        }
+        let keywords = bind_keyword_args(if copies { "sorted" } else { "sort" }, args, kwargs)?;
+        let reverse = match keywords.get("reverse") {
+            None => false,
+            Some(PythonHIR::Literal {
+                value: PythonLiteral::Bool(reverse),
+                ..
+            }) => *reverse,
+            Some(other) => return Err(unsupported_in("`reverse` that is not a literal", other)),
+        };
+
+        let items_type = expr_type(items);
+        let key = match keywords.get("key") {
+            Some(key @ PythonHIR::Lambda { .. }) => {
+                let item = item_type(&items_type);
+                let mut lowered = self.lower_lambda(key, std::slice::from_ref(&item), false)?;
+                // `sort_by_key` lends the items, so a key read from one is cloned
+                if !is_copy(&lambda_value_type(key, &item)) {
+                    lowered = self.map_key(lowered, |unifier, value, meta| {
+                        unifier.method_call(value, "clone", vec![], meta)
+                    });
+                }
+                if reverse {
+                    lowered = self.map_key(lowered, |unifier, value, meta| UnifiedHIR::Call {
+                        id: unifier.next_node_id(),
+                        target_language: Language::Rust,
+                        callee: "std::cmp::Reverse".to_owned(),
+                        args: vec![value],
+                        inferred_type: Type::Unknown,
+                        source_language: Language::Python,
+                        cross_mapping: None,
+                        meta: meta.clone(),
+                    });
+                }
+                Some(lowered)
+            }
+            Some(key) if reverse => {
+                return Err(unsupported_in(
+                    "`reverse` with a key that is not a lambda",
+                    key,
+                ))
+            }
+            Some(key) => Some(self.lower_expr(key)?),
+            None => None,
+        };
+        let items = self.lower_expr(items)?;
+        if !copies {
+            return Ok(Some(self.sort(items, key, reverse, meta)));
+        }
+
+        let mut copy = self.method_call(items, "clone", vec![], meta);
+        if !matches!(items_type, Type::Python(PythonType::List(_))) {
+            copy = self.method_call(copy, "into_iter", vec![], meta);
+            copy = self.method_call(copy, "collect::<Vec<_>>", vec![], meta);
+        }
+        let sorted = |unifier: &mut Self| UnifiedHIR::Variable {
+            id: unifier.next_node_id(),
+            name: "sorted".to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        };
+        let receiver = sorted(self);
+        let sort = self.sort(receiver, key, reverse, meta);
+        Ok(Some(UnifiedHIR::Scope {
+            id: self.next_node_id(),
+            guards: vec![ScopeGuard {
+                name: "sorted".to_owned(),
+                mutable: true,
+                value: copy,
+            }],
+            hoisted: vec![],
+            body: vec![sort],
+            value: Some(Box::new(sorted(self))),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }))
+    }
+
+    /// `items.sort()`, `items.sort_by_key(key)` or, reversed without a key,
+    /// `items.sort_by(|a, b| b.cmp(a))`
+    fn sort(
+        &mut self,
+        items: UnifiedHIR,
+        key: Option<UnifiedHIR>,
+        reverse: bool,
+        meta: &Metadata,
+    ) -> UnifiedHIR {
+        match (key, reverse) {
+            (Some(key), _) => self.method_call(items, "sort_by_key", vec![key], meta),
+            (None, false) => self.method_call(items, "sort", vec![], meta),
+            (None, true) => {
+                let param = |name: &str| UnifiedParameter {
+                    name: name.to_owned(),
+                    param_type: Type::Unknown,
+                    source_language: Language::Python,
+                };
+                let variable = |unifier: &mut Self, name: &str| UnifiedHIR::Variable {
+                    id: unifier.next_node_id(),
+                    name: name.to_owned(),
+                    var_type: Type::Unknown,
+                    source_language: Language::Python,
+                    meta: meta.clone(),
+                };
+                let (a, b) = (variable(self, "a"), variable(self, "b"));
+                let descending = self.method_call(b, "cmp", vec![a], meta);
+                let compare = UnifiedHIR::Closure {
+                    id: self.next_node_id(),
+                    params: vec![param("a"), param("b")],
+                    return_type: Type::Unknown,
+                    body: vec![UnifiedHIR::Return {
+                        id: self.next_node_id(),
+                        value: Some(Box::new(descending)),
+                        source_language: Language::Python,
+                        meta: meta.clone(),
+                    }],
+                    captures: vec![],
+                    kind: ClosureKind::Fn,
+                    moves: false,
+                    source_language: Language::Python,
+                    meta: meta.clone(),
+                };
+                self.method_call(items, "sort_by", vec![compare], meta)
+            }
+        }
+    }
+
+    /// Replace the value a lowered key lambda returns
+    fn map_key(
+        &mut self,
+        mut key: UnifiedHIR,
+        map: impl FnOnce(&mut Self, UnifiedHIR, &Metadata) -> UnifiedHIR,
+    ) -> UnifiedHIR {
+        if let UnifiedHIR::Closure { body, .. } = &mut key {
+            if let Some(UnifiedHIR::Return { value, meta, .. }) = body.last_mut() {
+                if let Some(inner) = value.take() {
+                    *value = Some(Box::new(map(self, *inner, meta)));
+                }
+            }
+        }
+        key
+    }
+}
+
+/// Type of the value a lambda returns when called with an item
+fn lambda_value_type(lambda: &PythonHIR, item: &Type) -> Type {
+    let PythonHIR::Lambda { params, body, .. } = lambda else {
+        return Type::Unknown;
+    };
+    let mut value = body.as_ref().clone();
+    if let Some(param) = params.first() {
+        type_parameters(&mut value, &[(param.name.as_str(), item)]);
+    }
+    expr_type(&value)
+}
+
+/// Type the function returning `body` has when it returns a closure
+///
+/// The closure's parameter and return types fall back to those of a
+/// `Callable[[A], R]` annotation. Returns `None` when no closure is
+/// returned at the top level of the body.
+pub(crate) fn returned_closure_type(annotation: &Type, body: &[UnifiedHIR]) -> Option<Type> {
+    let returned = body.iter().find_map(|statement| match statement {
+        UnifiedHIR::Return {
+            value: Some(value), ..
+        } => Some(value.as_ref()),
+        _ => None,
+    })?;
+    let closure = match returned {
+        UnifiedHIR::Variable { name, .. } => body.iter().find_map(|statement| match statement {
+            UnifiedHIR::Assign {
+                target: Pattern::Name(target),
+                value,
+                ..
+            } if target == name => Some(value.as_ref()),
+            _ => None,
+        })?,
+        other => other,
+    };
+    let UnifiedHIR::Closure {
+        params,
+        return_type,
+        kind,
+        ..
+    } = closure
+    else {
+        return None;
+    };
+
+    let (annotated_params, annotated_return) = match annotation {
+        Type::Function {
+            params,
+            return_type,
+        } => (params.as_slice(), return_type.as_ref()),
+        _ => (&[][..], &Type::Unknown),
+    };
+    let known = |ty: &Type, fallback: Option<&Type>| match (ty, fallback) {
+        (Type::Unknown, Some(fallback)) => fallback.clone(),
+        _ => ty.clone(),
+    };
+    Some(Type::Rust(RustType::Closure {
+        mutable: *kind == ClosureKind::FnMut,
+        params: params
+            .iter()
+            .enumerate()
+            .map(|(index, param)| known(&param.param_type, annotated_params.get(index)))
+            .collect(),
+        return_type: Box::new(known(return_type, Some(annotated_return))),
+    }))
+}
+
+/// Variables of the enclosing functions a closure uses, in first-use order
+///
+/// A capture is mutable when the closure assigns to it (`nonlocal`), or
+/// stores into, deletes from or calls a mutating method on it.
+fn captures(
+    params: &[Parameter],
+    body: &[PythonHIR],
+    enclosing: Option<&HashSet<String>>,
+) -> Vec<Capture> {
+    let Some(enclosing) = enclosing else {
+        return vec![];
+    };
+    let mut mutated = HashSet::new();
+    for statement in body {
+        walk(statement, &mut |node| match node {
+            PythonHIR::Assign { target, .. } => mutated.extend(target.names()),
+            PythonHIR::Store { target, .. } | PythonHIR::AugAssign { target, .. } => {
+                mutated.extend(root_name(target));
+            }
+            PythonHIR::Delete { targets, .. } => {
+                mutated.extend(targets.iter().filter_map(root_name));
+            }
+            PythonHIR::Call { callee, .. } => {
+                if let PythonHIR::Attribute { object, attr, .. } = callee.as_ref() {
+                    if MUTATING_METHODS.contains(&attr.as_str()) {
+                        mutated.extend(root_name(object));
+                    }
+                }
+            }
+            _ => {}
+        });
+    }
+
+    let mut captures: Vec<Capture> = Vec::new();
+    for name in free_names(params, body) {
+        if enclosing.contains(&name) && !captures.iter().any(|capture| capture.name == name) {
+            captures.push(Capture {
+                mutable: mutated.contains(name.as_str()),
+                name,
+            });
+        }
+    }
+    captures
+}
+
+/// Names a function body uses without binding them, in first-use order
+fn free_names(params: &[Parameter], body: &[PythonHIR]) -> Vec<String> {
+    let own = own_names(params, body);
+    let mut names = nonlocal_names(body);
+    for statement in body {
+        used_names(statement, &mut names);
+    }
+    names.retain(|name| !own.contains(name));
+    names
+}
+
+/// Names a node reads, including those nested functions use from outside
+fn used_names(node: &PythonHIR, names: &mut Vec<String>) {
+    match node {
+        PythonHIR::Variable { name, .. } => names.push(name.clone()),
+        PythonHIR::Function { params, body, .. } => names.extend(free_names(params, body)),
+        PythonHIR::Lambda { params, body, .. } => {
+            names.extend(free_names(params, std::slice::from_ref(body)));
+        }
+        PythonHIR::Class { .. } => {}
+        _ => {
+            for child in node.children() {
+                used_names(child, names);
+            }
+        }
+    }
+}
+
+/// Parameters and locals of a function body, leaving out `nonlocal` names
+fn own_names(params: &[Parameter], body: &[PythonHIR]) -> HashSet<String> {
+    let mut names: HashSet<String> = params.iter().map(|param| param.name.clone()).collect();
+    for statement in body {
+        walk(statement, &mut |node| match node {
+            PythonHIR::Assign { target, .. } | PythonHIR::For { target, .. } => {
+                names.extend(target.names().into_iter().map(str::to_owned));
+            }
+            PythonHIR::With { items, .. } => {
+                names.extend(items.iter().filter_map(|item| item.target.clone()));
+            }
+            PythonHIR::Function { name, .. } => {
+                names.insert(name.clone());
+            }
+            _ => {}
+        });
+    }
+    for name in nonlocal_names(body) {
+        names.remove(&name);
+    }
+    names
+}
+
+/// Names declared `nonlocal` by a function body
+fn nonlocal_names(body: &[PythonHIR]) -> Vec<String> {
+    let mut declared = Vec::new();
+    for statement in body {
+        walk(statement, &mut |node| {
+            if let PythonHIR::Nonlocal { names, .. } = node {
+                declared.extend(names.iter().cloned());
+            }
+        });
+    }
+    declared
+}
+
+/// The variable a place expression (`x`, `x.field`, `x[i]`) is rooted at
+fn root_name(node: &PythonHIR) -> Option<&str> {
+    match node {
+        PythonHIR::Variable { name, .. } => Some(name),
+        PythonHIR::Attribute { object, .. } | PythonHIR::Subscript { object, .. } => {
+            root_name(object)
+        }
+        _ => None,
+    }
+}
+
+/// Give the untyped uses of the named parameters in an expression their types
+fn type_parameters(node: &mut PythonHIR, params: &[(&str, &Type)]) {
+    if let PythonHIR::Variable {
+        name,
+        inferred_type,
+        ..
+    } = node
+    {
+        if let Some((_, ty)) = params.iter().find(|(param, _)| param == name) {
+            inferred_type.get_or_insert_with(|| (*ty).clone());
+        }
+        return;
+    }
+    for child in node.children_mut() {
+        type_parameters(child, params);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{metadata::Metadata, python::ParameterKind};
+
+    fn var(name: &str) -> PythonHIR {
+        PythonHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn param(name: &str) -> Parameter {
+        Parameter {
+            name: name.to_owned(),
+            kind: ParameterKind::Positional,
+            type_annotation: None,
+            default: None,
+        }
+    }
+
+    fn enclosing(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| (*name).to_owned()).collect()
+    }
+
+    #[test]
+    fn test_reading_closure_captures_by_reference() {
+        // lambda x: x * factor
+        let body = PythonHIR::Return {
+            id: NodeId::new(0),
+            value: Some(Box::new(PythonHIR::BinOp {
+                id: NodeId::new(0),
+                op: crate::python::BinOp::Mul,
+                left: Box::new(var("x")),
+                right: Box::new(var("factor")),
+                inferred_type: None,
+                meta: Metadata::new(),
+            })),
+            meta: Metadata::new(),
+        };
+
+        let captures = captures(
+            &[param("x")],
+            &[body],
+            Some(&enclosing(&["factor", "unused"])),
+        );
+
+        assert_eq!(
+            captures,
+            vec![Capture {
+                name: "factor".to_owned(),
+                mutable: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_nonlocal_assignment_is_mutable_capture() {
+        // nonlocal count; count += 1; seen.append(count)
+        let body = vec![
+            PythonHIR::Nonlocal {
+                id: NodeId::new(0),
+                names: vec!["count".to_owned()],
+                meta: Metadata::new(),
+            },
+            PythonHIR::AugAssign {
+                id: NodeId::new(0),
+                target: Box::new(var("count")),
+                op: crate::python::BinOp::Add,
+                value: Box::new(PythonHIR::Literal {
+                    id: NodeId::new(0),
+                    value: crate::python::Literal::Int(1),
+                    meta: Metadata::new(),
+                }),
+                meta: Metadata::new(),
+            },
+            PythonHIR::Call {
+                id: NodeId::new(0),
+                callee: Box::new(PythonHIR::Attribute {
+                    id: NodeId::new(0),
+                    object: Box::new(var("seen")),
+                    attr: "append".to_owned(),
+                    inferred_type: None,
+                    meta: Metadata::new(),
+                }),
+                args: vec![var("count")],
+                kwargs: vec![],
+                inferred_type: None,
+                meta: Metadata::new(),
+            },
+        ];
+
+        let captures = captures(&[], &body, Some(&enclosing(&["count", "seen"])));
+
+        assert_eq!(
+            captures,
+            vec![
+                Capture {
+                    name: "count".to_owned(),
+                    mutable: true,
+                },
+                Capture {
+                    name: "seen".to_owned(),
+                    mutable: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_local_shadowing_enclosing_variable_is_not_captured() {
+        // total = 0; return total
+        let body = vec![
+            PythonHIR::Assign {
+                id: NodeId::new(0),
+                target: crate::python::Target::name("total"),
+                value: Box::new(var("start")),
+                type_annotation: None,
+                meta: Metadata::new(),
+            },
+            PythonHIR::Return {
+                id: NodeId::new(0),
+                value: Some(Box::new(var("total"))),
+                meta: Metadata::new(),
+            },
+        ];
+
+        let captures = captures(&[], &body, Some(&enclosing(&["total", "start"])));
+
+        assert_eq!(
+            captures,
+            vec![Capture {
+                name: "start".to_owned(),
+                mutable: false,
+            }]
+        );
+    }
+}
@@ -0,0 +1,765 @@
+//! `with` statements and context managers
+//!
+//! A `with` statement becomes a Rust block binding one guard per context
+//! manager. However the block is left, the guards are dropped in reverse
+//! order, which is where Python calls `__exit__`:
+//!
+//! - `open(path, mode)` binds a `std::fs::File`; `read()` and `write()` on
+//!   it become `std::io` calls, and I/O errors raise `OSError`
+//! - a `Lock` is a `std::sync::Mutex<()>` and binds its `MutexGuard`; a
+//!   module-level lock is a `static`
+//! - a class defining `__enter__` and `__exit__` gets `enter` and `exit`
+//!   methods plus a guard struct, returned by `enter`, whose `Drop` impl
+//!   calls `exit`
+//!
+//! `Drop` cannot see the exception being raised, so `__exit__` may neither
+//! read its exception arguments nor suppress the exception.
+
+use crate::{
+    lowering::{expr_type, is_self, unsupported_in, walk},
+    metadata::Metadata,
+    python::{Literal as PythonLiteral, PythonHIR, Target},
+    types::{PythonType, RustType, Type},
+    unified::{LiteralValue, ScopeGuard, UnaryOp, UnifiedField, UnifiedHIR, Unifier},
+    Language,
+};
+use anyhow::Result;
+use std::collections::{BTreeSet, HashSet};
+
+/// Python name of `threading.Lock`
+pub(crate) const LOCK_TYPE: &str = "Lock";
+
+/// Context managers of the Python code being lowered
+#[derive(Debug, Default)]
+pub(crate) struct ContextModel {
+    /// Classes defining `__enter__` and `__exit__`
+    managers: BTreeSet<String>,
+    /// Fields of the class whose methods are being lowered
+    pub(crate) fields: Vec<UnifiedField>,
+    /// Files bound by the `with` statements being lowered
+    files: Vec<String>,
+    /// Module-level locks
+    locks: BTreeSet<String>,
+}
+
+impl ContextModel {
+    /// A model for code defining no context managers
+    pub(crate) const fn new() -> Self {
+        Self {
+            managers: BTreeSet::new(),
+            fields: Vec::new(),
+            files: Vec::new(),
+            locks: BTreeSet::new(),
+        }
+    }
+
+    /// Find the context manager classes among the top-level statements
+    pub(crate) fn analyze(body: &[PythonHIR]) -> Self {
+        let mut model = Self::new();
+        for node in body {
+            if let PythonHIR::Assign { target, value, .. } = node {
+                if let (Target::Name(name), true) = (target, is_lock_call(value)) {
+                    model.locks.insert(name.clone());
+                }
+            }
+            let PythonHIR::Class { name, body, .. } = node else {
+                continue;
+            };
+            let defines = |method: &str| {
+                body.iter().any(
+                    |member| matches!(member, PythonHIR::Function { name, .. } if name == method),
+                )
+            };
+            if defines("__enter__") && defines("__exit__") {
+                model.managers.insert(name.clone());
+            }
+        }
+        model
+    }
+
+    /// Whether a class defined by the module is a context manager
+    pub(crate) fn is_manager(&self, class: &str) -> bool {
+        self.managers.contains(class)
+    }
+
+    /// Type the untyped fields initialized by constructing a context manager
+    pub(crate) fn type_manager_fields(&self, methods: &[&PythonHIR], fields: &mut [UnifiedField]) {
+        for method in methods {
+            let PythonHIR::Function { body, .. } = method else {
+                continue;
+            };
+            for statement in body {
+                walk(statement, &mut |node| {
+                    let PythonHIR::Store { target, value, .. } = node else {
+                        return;
+                    };
+                    let PythonHIR::Attribute { object, attr, .. } = target.as_ref() else {
+                        return;
+                    };
+                    let Some(class) = manager_class(value).filter(|class| self.is_manager(class))
+                    else {
+                        return;
+                    };
+                    if let Some(field) = fields.iter_mut().find(|field| {
+                        field.name == *attr && field.field_type == Type::Unknown && is_self(object)
+                    }) {
+                        field.field_type = Type::Python(PythonType::Class(class.to_owned()));
+                    }
+                });
+            }
+        }
+    }
+
+    /// Names of the fields holding a context manager defined by the module
+    pub(crate) fn manager_fields(&self, fields: &[UnifiedField]) -> HashSet<String> {
+        fields
+            .iter()
+            .filter(|field| {
+                matches!(&field.field_type, Type::Python(PythonType::Class(class)) if self.is_manager(class))
+            })
+            .map(|field| field.name.clone())
+            .collect()
+    }
+
+    /// Type of a context manager expression
+    fn context_type(&self, context: &PythonHIR) -> Type {
+        if let Some(class) = manager_class(context).filter(|class| self.is_manager(class)) {
+            return Type::Python(PythonType::Class(class.to_owned()));
+        }
+        match context {
+            PythonHIR::Variable { name, .. } if self.locks.contains(name) => {
+                Type::Python(PythonType::Class(LOCK_TYPE.to_owned()))
+            }
+            PythonHIR::Attribute { object, attr, .. } if is_self(object) => self
+                .fields
+                .iter()
+                .find(|field| field.name == *attr)
+                .map_or(Type::Unknown, |field| field.field_type.clone()),
+            _ => expr_type(context),
+        }
+    }
+}
+
+impl Unifier {
+    /// Lower a `with` statement into a scope holding the guards
+    pub(crate) fn lower_with(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::With {
+            items, body, meta, ..
+        } = node
+        else {
+            return Err(unsupported_in("`with`", node));
+        };
+
+        let files = self.contexts.files.len();
+        let guards = self.lower_with_items(items, meta);
+        let lowered = guards.and_then(|guards| Ok((guards, self.lower_body(body)?)));
+        self.contexts.files.truncate(files);
+        let (guards, lowered) = lowered?;
+
+        // Python's `with` opens no scope: top-level assignments stay visible
+        let mut hoisted: Vec<(String, bool)> = Vec::new();
+        for statement in body {
+            let PythonHIR::Assign { target, .. } = statement else {
+                continue;
+            };
+            for target in target.names() {
+                match hoisted.iter_mut().find(|(name, _)| name == target) {
+                    Some((_, mutable)) => *mutable = true,
+                    None => hoisted.push((target.to_owned(), false)),
+                }
+            }
+        }
+        let lowered = lowered
+            .into_iter()
+            .map(|statement| match statement {
+                UnifiedHIR::Assign {
+                    target,
+                    value,
+                    meta,
+                    ..
+                } => self.reassign(&target, *value, &meta),
+                other => Ok(other),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(UnifiedHIR::Scope {
+            id: self.next_node_id(),
+            guards,
+            hoisted,
+            body: lowered,
+            value: None,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Bind a guard for each context manager of a `with` statement
+    fn lower_with_items(
+        &mut self,
+        items: &[crate::python::WithItem],
+        meta: &Metadata,
+    ) -> Result<Vec<ScopeGuard>> {
+        let mut guards = Vec::new();
+        for item in items {
+            // Shadowed guards stay alive until the scope ends
+            let name = item.target.clone().unwrap_or_else(|| "_guard".to_owned());
+            let context = item.context.as_ref();
+
+            if opens_file(context) {
+                let value = self.open_file(context)?;
+                self.contexts.files.push(name.clone());
+                guards.push(ScopeGuard {
+                    name,
+                    mutable: true,
+                    value,
+                });
+                continue;
+            }
+
+            let value = match self.contexts.context_type(context) {
+                Type::Python(PythonType::Class(class)) if class == LOCK_TYPE => {
+                    // Python locks are not poisoned by a panicking holder
+                    let lock = self.lower_expr(context)?;
+                    let lock = self.method_call(lock, "lock", vec![], meta);
+                    let recover = self.path("std::sync::PoisonError::into_inner", meta);
+                    self.method_call(lock, "unwrap_or_else", vec![recover], meta)
+                }
+                Type::Python(PythonType::Class(class)) if self.contexts.is_manager(&class) => {
+                    let manager = match context {
+                        PythonHIR::Variable { .. } | PythonHIR::Attribute { .. } => {
+                            self.lower_expr(context)?
+                        }
+                        // A temporary manager must outlive the guard borrowing it
+                        _ => {
+                            let value = self.lower_expr(context)?;
+                            guards.push(ScopeGuard {
+                                name: "_context".to_owned(),
+                                mutable: true,
+                                value,
+                            });
+                            self.path("_context", meta)
+                        }
+                    };
+                    let enter = self.method_call(manager, "enter", vec![], meta);
+                    if self.exceptions.can_raise(&format!("{class}.__enter__")) {
+                        if !self.exceptions.returns_result {
+                            // Only constructor calls and annotated names are analyzed
+                            return Err(unsupported_in(
+                                "`with` on a field whose `__enter__` can raise",
+                                context,
+                            ));
+                        }
+                        self.propagate(enter, meta)
+                    } else {
+                        enter
+                    }
+                }
+                _ => {
+                    return Err(unsupported_in(
+                        "`with` on an unknown context manager",
+                        context,
+                    ))
+                }
+            };
+            guards.push(ScopeGuard {
+                name,
+                mutable: false,
+                value,
+            });
+        }
+        Ok(guards)
+    }
+
+    /// Lower `open(path, mode)` into opening a `std::fs::File`
+    fn open_file(&mut self, call: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Call {
+            args, kwargs, meta, ..
+        } = call
+        else {
+            return Err(unsupported_in("`open`", call));
+        };
+        if let Some(extra) = args.get(2) {
+            return Err(unsupported_in("`open` argument", extra));
+        }
+        let mut path = args.first();
+        let mut mode = args.get(1);
+        for (name, value) in kwargs {
+            match name.as_deref() {
+                Some("file") => path = Some(value),
+                Some("mode") => mode = Some(value),
+                // Rust reads and writes strings as UTF-8
+                Some("encoding") if is_utf8(value) => {}
+                _ => return Err(unsupported_in("`open` keyword argument", value)),
+            }
+        }
+        let Some(path) = path else {
+            return Err(unsupported_in("`open` without a path", call));
+        };
+        let mode = match mode {
+            None => "r",
+            Some(PythonHIR::Literal {
+                value: PythonLiteral::Str(mode),
+                ..
+            }) => mode.as_str(),
+            Some(other) => return Err(unsupported_in("`open` mode that is not a literal", other)),
+        };
+        let opener = match mode {
+            "r" | "rt" => "std::fs::File::open",
+            "w" | "wt" => "std::fs::File::create",
+            "a" | "at" => "std::fs::OpenOptions::new().append(true).create(true).open",
+            other => return Err(unsupported_in(&format!("`open` mode `{other}`"), call)),
+        };
+
+        let path = self.lower_expr(path)?;
+        let file = self.call(opener, vec![path], meta);
+        Ok(self.propagate(file, meta))
+    }
+
+    /// Lower a module-level `name = threading.Lock()` into a `static`
+    pub(crate) fn lower_static(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Assign {
+            target: Target::Name(name),
+            value,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported_in("module-level assignment", node));
+        };
+        if !self.contexts.locks.contains(name) {
+            return Err(unsupported_in("module-level assignment", node));
+        }
+        let value = self.lower_expr(value)?;
+        Ok(UnifiedHIR::Static {
+            id: self.next_node_id(),
+            name: name.clone(),
+            static_type: Type::Python(PythonType::Class(LOCK_TYPE.to_owned())),
+            value: Box::new(value),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower `Lock()` or a method call on a file bound by an enclosing `with`
+    ///
+    /// Returns `None` for other calls.
+    pub(crate) fn lower_resource_call(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Call {
+            callee, args, meta, ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        if is_lock_call(node) {
+            let unit = UnifiedHIR::Literal {
+                id: self.next_node_id(),
+                value: LiteralValue::Unit,
+                lit_type: Type::Rust(RustType::Unit),
+                meta: meta.clone(),
+            };
+            return Ok(Some(self.call("std::sync::Mutex::new", vec![unit], meta)));
+        }
+        let PythonHIR::Attribute { object, attr, .. } = callee.as_ref() else {
+            return Ok(None);
+        };
+        let PythonHIR::Variable { name, .. } = object.as_ref() else {
+            return Ok(None);
+        };
+        if !self.contexts.files.contains(name) {
+            return Ok(None);
+        }
+
+        let file = self.lower_expr(object)?;
+        let file = UnifiedHIR::UnaryOp {
+            id: self.next_node_id(),
+            op: UnaryOp::RefMut,
+            operand: Box::new(file),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        };
+        let call = match (attr.as_str(), args.as_slice()) {
+            ("read", []) => self.call("std::io::read_to_string", vec![file], meta),
+            ("write", [text]) => {
+                let text = self.lower_expr(text)?;
+                let bytes = self.method_call(text, "as_bytes", vec![], meta);
+                self.call("std::io::Write::write_all", vec![file, bytes], meta)
+            }
+            _ => return Err(unsupported_in(&format!("file method `{attr}`"), node)),
+        };
+        Ok(Some(self.propagate(call, meta)))
+    }
+
+    /// Turn a lowered `__enter__` into `enter`, returning the guard
+    pub(crate) fn enter_method(&mut self, method: UnifiedHIR, class: &str) -> UnifiedHIR {
+        let UnifiedHIR::Function {
+            id,
+            receiver,
+            params,
+            return_type,
+            mut body,
+            source_language,
+            cross_mapping,
+            meta,
+            ..
+        } = method
+        else {
+            return method;
+        };
+
+        let guard = Type::Rust(RustType::Custom(format!("{}<'_>", guard_name(class))));
+        let return_type = match return_type {
+            Type::Rust(RustType::Result { err, .. }) => Type::Rust(RustType::Result {
+                ok: Box::new(guard),
+                err,
+            }),
+            _ => guard,
+        };
+        // `return self` (or `return Ok(self)`) hands out the guard instead
+        let borrowed = self.path("self", &meta);
+        let guarded = self.call(&guard_name(class), vec![borrowed], &meta);
+        if let Some(UnifiedHIR::Return {
+            value: Some(value), ..
+        }) = body.last_mut()
+        {
+            match value.as_mut() {
+                UnifiedHIR::Call { callee, args, .. } if callee == "Ok" && args.len() == 1 => {
+                    args[0] = guarded;
+                }
+                other => *other = guarded,
+            }
+        }
+
+        UnifiedHIR::Function {
+            id,
+            name: "enter".to_owned(),
+            receiver,
+            params,
+            return_type,
+            body,
+            source_language,
+            cross_mapping,
+            meta,
+        }
+    }
+
+    /// The guard struct of a context manager class
+    pub(crate) fn guard_type(&mut self, class: &str) -> UnifiedHIR {
+        UnifiedHIR::GuardType {
+            id: self.next_node_id(),
+            name: guard_name(class),
+            target: class.to_owned(),
+            source_language: Language::Python,
+            meta: Metadata::new().with_docs(format!(
+                "Guard returned by `{class}::enter`; dropping it calls `{class}::exit`"
+            )),
+        }
+    }
+
+    /// `value?`
+    fn propagate(&mut self, value: UnifiedHIR, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::Propagate {
+            id: self.next_node_id(),
+            value: Box::new(value),
+            on_none: None,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+
+    /// `callee(args)`
+    fn call(&mut self, callee: &str, args: Vec<UnifiedHIR>, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::Call {
+            id: self.next_node_id(),
+            target_language: Language::Rust,
+            callee: callee.to_owned(),
+            args,
+            inferred_type: Type::Unknown,
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: meta.clone(),
+        }
+    }
+
+    /// `receiver.method(args)`
+    pub(crate) fn method_call(
+        &mut self,
+        receiver: UnifiedHIR,
+        method: &str,
+        args: Vec<UnifiedHIR>,
+        meta: &Metadata,
+    ) -> UnifiedHIR {
+        UnifiedHIR::MethodCall {
+            id: self.next_node_id(),
+            receiver: Box::new(receiver),
+            method: method.to_owned(),
+            args,
+            inferred_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+
+    /// A variable or item path
+    fn path(&mut self, name: &str, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::Variable {
+            id: self.next_node_id(),
+            name: name.to_owned(),
+            var_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+}
+
+/// Class of a context manager expression, when known without the class fields
+///
+/// This is the constructor of a call, or an annotated variable's class.
+pub(crate) fn manager_class(context: &PythonHIR) -> Option<&str> {
+    match context {
+        PythonHIR::Call { callee, .. } => match callee.as_ref() {
+            PythonHIR::Variable { name, .. } => Some(name),
+            _ => None,
+        },
+        PythonHIR::Variable {
+            inferred_type: Some(Type::Python(PythonType::Class(class))),
+            ..
+        } => Some(class),
+        _ => None,
+    }
+}
+
+/// Whether an expression is `Lock()` or `threading.Lock()`
+pub(crate) fn is_lock_call(node: &PythonHIR) -> bool {
+    let PythonHIR::Call { callee, args, .. } = node else {
+        return false;
+    };
+    args.is_empty()
+        && match callee.as_ref() {
+            PythonHIR::Variable { name, .. } => name == LOCK_TYPE,
+            PythonHIR::Attribute { object, attr, .. } => {
+                attr == LOCK_TYPE
+                    && matches!(object.as_ref(), PythonHIR::Variable { name, .. } if name == "threading")
+            }
+            _ => false,
+        }
+}
+
+/// Whether a context manager expression is a call to `open`
+pub(crate) fn opens_file(context: &PythonHIR) -> bool {
+    matches!(context, PythonHIR::Call { callee, .. }
+        if matches!(callee.as_ref(), PythonHIR::Variable { name, .. } if name == "open"))
+}
+
+/// Check that `__enter__` ends in `return self` and returns nothing else
+pub(crate) fn check_enter(method: &PythonHIR) -> Result<()> {
+    let PythonHIR::Function { body, .. } = method else {
+        return Ok(());
+    };
+    let returns_self = |node: &PythonHIR| {
+        matches!(node, PythonHIR::Return { value: Some(value), .. }
+            if matches!(value.as_ref(), PythonHIR::Variable { name, .. } if name == "self"))
+    };
+    let Some((last, rest)) = body.split_last().filter(|(last, _)| returns_self(last)) else {
+        return Err(unsupported_in(
+            "`__enter__` not ending in `return self`",
+            method,
+        ));
+    };
+    let mut other = None;
+    for statement in rest.iter().chain(last.children()) {
+        walk(statement, &mut |node| {
+            if other.is_none() && matches!(node, PythonHIR::Return { .. }) {
+                other = Some(node);
+            }
+        });
+    }
+    match other {
+        Some(node) => Err(unsupported_in("early `return` in `__enter__`", node)),
+        None => Ok(()),
+    }
+}
+
+/// Prepare `__exit__` for lowering as `exit(&mut self)`
+///
+/// The exception arguments are dropped and `return False` becomes a bare
+/// `return`; reading the exception or suppressing it is reported.
+pub(crate) fn exit_method(method: &PythonHIR) -> Result<PythonHIR> {
+    let PythonHIR::Function { params, body, .. } = method else {
+        return Ok(method.clone());
+    };
+    let exception: HashSet<&str> = params.iter().skip(1).map(|p| p.name.as_str()).collect();
+    for statement in body {
+        let mut problem = None;
+        walk(statement, &mut |node| {
+            if problem.is_some() {
+                return;
+            }
+            match node {
+                PythonHIR::Variable { name, .. } if exception.contains(name.as_str()) => {
+                    problem = Some(("`__exit__` reading the exception", node));
+                }
+                PythonHIR::Return {
+                    value: Some(value), ..
+                } if !matches!(
+                    value.as_ref(),
+                    PythonHIR::Literal {
+                        value: PythonLiteral::None | PythonLiteral::Bool(false),
+                        ..
+                    }
+                ) =>
+                {
+                    problem = Some(("`__exit__` suppressing exceptions", node));
+                }
+                _ => {}
+            }
+        });
+        if let Some((what, node)) = problem {
+            return Err(unsupported_in(what, node));
+        }
+    }
+
+    let mut method = method.clone();
+    if let PythonHIR::Function {
+        params,
+        return_type,
+        body,
+        ..
+    } = &mut method
+    {
+        params.truncate(1);
+        *return_type = None;
+        for statement in body {
+            drop_return_values(statement);
+        }
+    }
+    Ok(method)
+}
+
+/// Turn `return <value>` into a bare `return`
+fn drop_return_values(node: &mut PythonHIR) {
+    if let PythonHIR::Return { value, .. } = node {
+        *value = None;
+    }
+    for child in node.children_mut() {
+        drop_return_values(child);
+    }
+}
+
+/// Name of a context manager's guard struct
+fn guard_name(class: &str) -> String {
+    format!("{class}Guard")
+}
+
+/// Whether an `encoding` argument names UTF-8
+fn is_utf8(value: &PythonHIR) -> bool {
+    matches!(value, PythonHIR::Literal { value: PythonLiteral::Str(encoding), .. }
+        if matches!(encoding.to_lowercase().as_str(), "utf-8" | "utf8"))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{
+        python::{Parameter, ParameterKind},
+        NodeId, Visibility,
+    };
+
+    fn var(name: &str) -> PythonHIR {
+        PythonHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn method(name: &str, params: &[&str], body: Vec<PythonHIR>) -> PythonHIR {
+        PythonHIR::Function {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            params: params
+                .iter()
+                .map(|param| Parameter {
+                    name: (*param).to_owned(),
+                    kind: ParameterKind::Positional,
+                    type_annotation: None,
+                    default: None,
+                })
+                .collect(),
+            return_type: None,
+            body,
+            decorators: vec![],
+            visibility: Visibility::Public,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn ret(value: PythonHIR) -> PythonHIR {
+        PythonHIR::Return {
+            id: NodeId::new(0),
+            value: Some(Box::new(value)),
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_analyze_finds_context_managers() {
+        let class = |name: &str, methods: &[&str]| PythonHIR::Class {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            bases: vec![],
+            body: methods
+                .iter()
+                .map(|name| method(name, &["self"], vec![]))
+                .collect(),
+            decorators: vec![],
+            meta: Metadata::new(),
+        };
+        let model = ContextModel::analyze(&[
+            class("Timer", &["__enter__", "__exit__"]),
+            class("Half", &["__enter__"]),
+        ]);
+        assert!(model.is_manager("Timer"));
+        assert!(!model.is_manager("Half"));
+    }
+
+    #[test]
+    fn test_exit_method_drops_exception_arguments() {
+        let exit = method(
+            "__exit__",
+            &["self", "exc_type", "exc", "tb"],
+            vec![ret(PythonHIR::Literal {
+                id: NodeId::new(0),
+                value: PythonLiteral::Bool(false),
+                meta: Metadata::new(),
+            })],
+        );
+        let PythonHIR::Function { params, body, .. } =
+            exit_method(&exit).expect("Should prepare __exit__")
+        else {
+            panic!("Expected Function");
+        };
+        assert_eq!(params.len(), 1);
+        assert!(matches!(body[0], PythonHIR::Return { value: None, .. }));
+
+        let suppressing = method(
+            "__exit__",
+            &["self", "exc_type"],
+            vec![ret(var("exc_type"))],
+        );
+        let error = exit_method(&suppressing).expect_err("Suppresses the exception");
+        assert!(
+            error.to_string().contains("suppressing exceptions"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_enter_must_return_self() {
+        assert!(check_enter(&method("__enter__", &["self"], vec![ret(var("self"))])).is_ok());
+        let error = check_enter(&method("__enter__", &["self"], vec![ret(var("conn"))]))
+            .expect_err("Returns something else");
+        assert!(error.to_string().contains("`return self`"), "{error}");
+    }
+}
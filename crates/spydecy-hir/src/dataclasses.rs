@@ -0,0 +1,705 @@
+//! Dataclasses
+//!
+//! A `@dataclass` becomes a struct holding its annotated class attributes,
+//! with derived traits and a `new` constructor taking every field in order:
+//!
+//! ```text
+//! @dataclass(order=True)     →  #[derive(Debug, Clone, PartialEq, PartialOrd)]
+//! class Point:                   pub struct Point { pub x: f64, pub y: f64 }
+//!     x: float                   impl Point {
+//!     y: float = 0.0                 pub fn new(x: f64, y: f64) -> Self { Self { x, y } }
+//!                                }
+//! Point(1.5)                 →  Point::new(1.5, 0.0)
+//! ```
+//!
+//! - `Debug` and `Clone` are always derived, `eq` (the default) derives
+//!   `PartialEq`, `order` derives `PartialOrd`, which compares the fields
+//!   in order as Python does, and `frozen` derives `Hash`; `Eq` and `Ord`
+//!   are added unless a field holds a float
+//! - constructing a dataclass, positionally or by keyword, calls `new`
+//!   with the defaults of the fields left out; `field(default_factory=list)`
+//!   and the like become `Default::default()`. Arguments keep their source
+//!   evaluation order, and a variable passed to several fields is cloned
+//! - methods are lowered like those of other classes, but may not assign
+//!   to the fields of a frozen dataclass
+
+use crate::{
+    generators::free_variables,
+    lowering::{mutating_methods, unsupported_in, ClassInfo},
+    metadata::{Attribute, Metadata},
+    python::{PythonHIR, Target},
+    types::{PythonType, RustType, Type},
+    unified::{ScopeGuard, UnifiedField, UnifiedHIR, UnifiedParameter, Unifier},
+    Language, NodeId,
+};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// Dataclasses defined by the Python code being lowered
+#[derive(Debug, Default)]
+pub(crate) struct DataclassModel {
+    /// Each dataclass, by name
+    classes: BTreeMap<String, Dataclass>,
+}
+
+/// What lowering knows about a `@dataclass`
+#[derive(Debug, Clone)]
+struct Dataclass {
+    /// Fields, in definition order
+    fields: Vec<DataclassField>,
+    /// `eq=True`, the default
+    eq: bool,
+    /// `order=True`
+    order: bool,
+    /// `frozen=True`
+    frozen: bool,
+}
+
+/// Field of a dataclass
+#[derive(Debug, Clone)]
+struct DataclassField {
+    /// Field name
+    name: String,
+    /// Annotated type
+    field_type: Type,
+    /// Value used when construction leaves the field out
+    default: Option<FieldDefault>,
+}
+
+/// Default of a dataclass field
+#[derive(Debug, Clone)]
+enum FieldDefault {
+    /// `= value` or `= field(default=value)`
+    Value(PythonHIR),
+    /// `= field(default_factory=factory)`
+    Factory(PythonHIR),
+}
+
+impl DataclassModel {
+    /// A model for code defining no dataclasses
+    pub(crate) const fn new() -> Self {
+        Self {
+            classes: BTreeMap::new(),
+        }
+    }
+
+    /// Find the dataclasses among the top-level statements
+    ///
+    /// Fields with a malformed default are left without one here and
+    /// reported when the class is lowered.
+    pub(crate) fn analyze(body: &[PythonHIR]) -> Self {
+        let mut model = Self::new();
+        for node in body {
+            let PythonHIR::Class {
+                name,
+                body,
+                decorators,
+                meta,
+                ..
+            } = node
+            else {
+                continue;
+            };
+            if !decorators.iter().any(|decorator| is_dataclass(decorator)) {
+                continue;
+            }
+            let option = |option: &str, default: bool| {
+                meta.attributes
+                    .iter()
+                    .filter(|attribute| is_dataclass(&attribute.name[1..]))
+                    .flat_map(|attribute| &attribute.args)
+                    .find_map(|arg| arg.strip_prefix(option)?.strip_prefix('='))
+                    .map_or(default, |value| value == "True")
+            };
+            let dataclass = Dataclass {
+                fields: body
+                    .iter()
+                    .filter_map(|statement| field(statement).ok().flatten())
+                    .collect(),
+                eq: option("eq", true),
+                order: option("order", false),
+                frozen: option("frozen", false),
+            };
+            model.classes.insert(name.clone(), dataclass);
+        }
+        model
+    }
+
+    /// Whether a class defined by the module is a dataclass
+    pub(crate) fn is_dataclass(&self, class: &str) -> bool {
+        self.classes.contains_key(class)
+    }
+}
+
+impl Unifier {
+    /// Lower a dataclass into a struct and an impl block holding `new` and
+    /// its methods
+    pub(crate) fn lower_dataclass(&mut self, class: &PythonHIR) -> Result<Vec<UnifiedHIR>> {
+        let PythonHIR::Class {
+            name, body, meta, ..
+        } = class
+        else {
+            return Err(unsupported_in("dataclass", class));
+        };
+        let Some(dataclass) = self.dataclasses.classes.get(name).cloned() else {
+            return Err(unsupported_in("dataclass", class));
+        };
+
+        let mut methods = Vec::new();
+        for node in body {
+            match node {
+                PythonHIR::Function { name, .. }
+                    if matches!(name.as_str(), "__init__" | "__post_init__") =>
+                {
+                    return Err(unsupported_in(&format!("`{name}` of a dataclass"), node));
+                }
+                PythonHIR::Function { .. } => methods.push(node),
+                PythonHIR::Literal { .. } | PythonHIR::Pass { .. } => {}
+                _ => {
+                    if field(node)?.is_none() {
+                        return Err(unsupported_in("dataclass member", node));
+                    }
+                }
+            }
+        }
+
+        let fields: Vec<UnifiedField> = dataclass
+            .fields
+            .iter()
+            .map(|field| UnifiedField {
+                name: field.name.clone(),
+                field_type: field.field_type.clone(),
+            })
+            .collect();
+        let mutating = mutating_methods(&methods, &self.contexts.manager_fields(&fields));
+        if dataclass.frozen {
+            if let Some(method) = methods.iter().find(|method| {
+                matches!(method, PythonHIR::Function { name, .. } if mutating.contains(name))
+            }) {
+                return Err(unsupported_in(
+                    "method assigning to a frozen dataclass",
+                    method,
+                ));
+            }
+        }
+        let info = ClassInfo { fields, mutating };
+
+        let mut lowered = vec![self.dataclass_constructor(&info.fields, meta)];
+        let enclosing = self.exceptions.class.replace(name.clone());
+        let fields = std::mem::replace(&mut self.contexts.fields, info.fields.clone());
+        let lowered_methods = methods
+            .into_iter()
+            .map(|method| self.lower_function(method, Some(&info)))
+            .collect::<Result<Vec<_>>>();
+        self.exceptions.class = enclosing;
+        self.contexts.fields = fields;
+        lowered.extend(lowered_methods?);
+
+        Ok(vec![
+            UnifiedHIR::Struct {
+                id: self.next_node_id(),
+                name: name.clone(),
+                fields: info.fields,
+                source_language: Language::Python,
+                meta: Metadata {
+                    attributes: vec![Attribute::with_args(
+                        "derive".to_owned(),
+                        dataclass.derives(),
+                    )],
+                    ..meta.clone()
+                },
+            },
+            UnifiedHIR::Impl {
+                id: self.next_node_id(),
+                type_name: name.clone(),
+                methods: lowered,
+                source_language: Language::Python,
+                // The class docstring is emitted on the struct
+                meta: Metadata {
+                    docs: None,
+                    attributes: vec![],
+                    ..meta.clone()
+                },
+            },
+        ])
+    }
+
+    /// `fn new(<fields>) -> Self { Self { <fields> } }`
+    fn dataclass_constructor(&mut self, fields: &[UnifiedField], meta: &Metadata) -> UnifiedHIR {
+        let synthesized = Metadata {
+            source: meta.source.clone(),
+            ..Metadata::new()
+        };
+        let init = UnifiedHIR::StructInit {
+            id: self.next_node_id(),
+            name: "Self".to_owned(),
+            fields: fields
+                .iter()
+                .map(|field| {
+                    let value = UnifiedHIR::Variable {
+                        id: self.next_node_id(),
+                        name: field.name.clone(),
+                        var_type: field.field_type.clone(),
+                        source_language: Language::Python,
+                        meta: synthesized.clone(),
+                    };
+                    (field.name.clone(), value)
+                })
+                .collect(),
+            meta: synthesized.clone(),
+        };
+        UnifiedHIR::Function {
+            id: self.next_node_id(),
+            name: "new".to_owned(),
+            receiver: None,
+            params: fields
+                .iter()
+                .map(|field| UnifiedParameter {
+                    name: field.name.clone(),
+                    param_type: field.field_type.clone(),
+                    source_language: Language::Python,
+                })
+                .collect(),
+            return_type: Type::Rust(RustType::Custom("Self".to_owned())),
+            body: vec![init],
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: synthesized,
+        }
+    }
+
+    /// Lower `Point(..)` of a dataclass to `Point::new(..)`
+    ///
+    /// Keyword arguments are put in field order and the fields left out
+    /// take their defaults. When that reorders arguments, those that are
+    /// not plain variables or literals are first bound to locals in source
+    /// order, so they are still evaluated in it:
+    ///
+    /// ```text
+    /// Job(tags=[name], name=name)  →  {
+    ///                                     let tags = vec![name.clone()];
+    ///                                     Job::new(name, 3, tags)
+    ///                                 }
+    /// ```
+    ///
+    /// Returns `None` for any other call.
+    pub(crate) fn lower_dataclass_call(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Call { callee, meta, .. } = node else {
+            return Ok(None);
+        };
+        let PythonHIR::Variable { name: class, .. } = callee.as_ref() else {
+            return Ok(None);
+        };
+        let Some(dataclass) = self.dataclasses.classes.get(class).cloned() else {
+            return Ok(None);
+        };
+
+        let mut arguments = dataclass.arguments(class, node)?;
+        clone_shared_variables(arguments.iter_mut().map(|(_, value)| value));
+        let in_order = arguments.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        let mut read = Vec::new();
+        for (_, value) in &arguments {
+            free_variables(value, &mut read);
+        }
+
+        let mut guards = Vec::new();
+        let mut values = vec![None; dataclass.fields.len()];
+        for (position, value) in &arguments {
+            let lowered = self.lower_value(value)?;
+            values[*position] = Some(
+                if in_order
+                    || matches!(
+                        value,
+                        PythonHIR::Variable { .. } | PythonHIR::Literal { .. }
+                    )
+                {
+                    lowered
+                } else {
+                    let field = &dataclass.fields[*position].name;
+                    let name = if read.contains(field) {
+                        format!("{field}_arg")
+                    } else {
+                        field.clone()
+                    };
+                    guards.push(ScopeGuard {
+                        name: name.clone(),
+                        mutable: false,
+                        value: lowered,
+                    });
+                    UnifiedHIR::Variable {
+                        id: self.next_node_id(),
+                        name,
+                        var_type: Type::Unknown,
+                        source_language: Language::Python,
+                        meta: meta.clone(),
+                    }
+                },
+            );
+        }
+
+        let mut lowered = Vec::new();
+        for (field, value) in dataclass.fields.iter().zip(values) {
+            lowered.push(match (value, &field.default) {
+                (Some(value), _) => value,
+                (None, Some(FieldDefault::Value(value))) => self.lower_value(value)?,
+                (None, Some(FieldDefault::Factory(factory))) => self.call_factory(factory)?,
+                (None, None) => {
+                    return Err(unsupported_in(
+                        &format!("dataclass `{class}` without a value for `{}`", field.name),
+                        node,
+                    ));
+                }
+            });
+        }
+        let call = UnifiedHIR::Call {
+            id: self.next_node_id(),
+            target_language: Language::Rust,
+            callee: format!("{class}::new"),
+            args: lowered,
+            inferred_type: Type::Python(PythonType::Class(class.clone())),
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: meta.clone(),
+        };
+        if guards.is_empty() {
+            return Ok(Some(call));
+        }
+        Ok(Some(UnifiedHIR::Scope {
+            id: self.next_node_id(),
+            guards,
+            hoisted: vec![],
+            body: vec![],
+            value: Some(Box::new(call)),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }))
+    }
+
+    /// Call a `default_factory`, where the empty collections are `Default::default()`
+    fn call_factory(&mut self, factory: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Variable { name, meta, .. } = factory else {
+            return Err(unsupported_in("default factory", factory));
+        };
+        let callee = match name.as_str() {
+            "list" | "dict" | "set" => "Default::default",
+            other => other,
+        };
+        Ok(UnifiedHIR::Call {
+            id: self.next_node_id(),
+            target_language: Language::Rust,
+            callee: callee.to_owned(),
+            args: vec![],
+            inferred_type: Type::Unknown,
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: meta.clone(),
+        })
+    }
+}
+
+impl Dataclass {
+    /// The arguments of a construction in source order, each with the
+    /// position of the field it sets
+    fn arguments(&self, class: &str, node: &PythonHIR) -> Result<Vec<(usize, PythonHIR)>> {
+        let PythonHIR::Call { args, kwargs, .. } = node else {
+            return Err(unsupported_in("dataclass construction", node));
+        };
+        if args.len() > self.fields.len() {
+            return Err(unsupported_in(
+                &format!("too many arguments to dataclass `{class}`"),
+                node,
+            ));
+        }
+
+        let mut arguments: Vec<(usize, PythonHIR)> = args.iter().cloned().enumerate().collect();
+        for (keyword, arg) in kwargs {
+            let Some(keyword) = keyword else {
+                return Err(unsupported_in("`**` keyword splat", node));
+            };
+            let position = self
+                .fields
+                .iter()
+                .position(|field| &field.name == keyword)
+                .filter(|&position| arguments.iter().all(|(set, _)| *set != position));
+            let Some(position) = position else {
+                return Err(unsupported_in(
+                    &format!("keyword argument `{keyword}` to dataclass `{class}`"),
+                    node,
+                ));
+            };
+            arguments.push((position, arg.clone()));
+        }
+        Ok(arguments)
+    }
+
+    /// Traits derived by the struct
+    fn derives(&self) -> Vec<String> {
+        let total = !self
+            .fields
+            .iter()
+            .any(|field| holds_float(&field.field_type));
+        let mut derives = vec!["Debug", "Clone"];
+        if self.eq {
+            derives.push("PartialEq");
+            if total {
+                derives.push("Eq");
+            }
+        }
+        if self.order {
+            derives.push("PartialOrd");
+            if total && self.eq {
+                derives.push("Ord");
+            }
+        }
+        if self.frozen && self.eq && total {
+            derives.push("Hash");
+        }
+        derives.into_iter().map(str::to_owned).collect()
+    }
+}
+
+/// Read the variables that more than one argument reads through `.clone()`
+/// at every read but the last, so that each argument owns its value
+fn clone_shared_variables<'a>(arguments: impl Iterator<Item = &'a mut PythonHIR>) {
+    let arguments: Vec<&mut PythonHIR> = arguments.collect();
+    let mut read = Vec::new();
+    for argument in &arguments {
+        free_variables(argument, &mut read);
+    }
+    let mut clones: BTreeMap<String, usize> = BTreeMap::new();
+    for name in &read {
+        *clones.entry(name.clone()).or_default() += 1;
+    }
+    clones.retain(|_, reads| {
+        *reads -= 1;
+        *reads > 0
+    });
+    for argument in arguments {
+        clone_reads(argument, &mut clones);
+    }
+}
+
+/// Replace the first reads of the variables in `clones` with `.clone()`
+/// calls, visiting them in the order `free_variables` lists them
+fn clone_reads(node: &mut PythonHIR, clones: &mut BTreeMap<String, usize>) {
+    match node {
+        PythonHIR::Variable {
+            name,
+            inferred_type,
+            meta,
+            ..
+        } => {
+            let Some(reads) = clones.get_mut(name.as_str()) else {
+                return;
+            };
+            if *reads == 0 {
+                return;
+            }
+            *reads -= 1;
+            let inferred_type = inferred_type.clone();
+            let meta = meta.clone();
+            let variable = node.clone();
+            *node = PythonHIR::Call {
+                id: NodeId::new(0),
+                callee: Box::new(PythonHIR::Attribute {
+                    id: NodeId::new(0),
+                    object: Box::new(variable),
+                    attr: "clone".to_owned(),
+                    inferred_type: None,
+                    meta: meta.clone(),
+                }),
+                args: vec![],
+                kwargs: vec![],
+                inferred_type,
+                meta,
+            };
+        }
+        PythonHIR::Call {
+            callee,
+            args,
+            kwargs,
+            ..
+        } => {
+            if !matches!(callee.as_ref(), PythonHIR::Variable { .. }) {
+                clone_reads(callee, clones);
+            }
+            for arg in args
+                .iter_mut()
+                .chain(kwargs.iter_mut().map(|(_, value)| value))
+            {
+                clone_reads(arg, clones);
+            }
+        }
+        _ => {
+            for child in node.children_mut() {
+                clone_reads(child, clones);
+            }
+        }
+    }
+}
+
+/// Whether a decorator name is `dataclass`
+fn is_dataclass(decorator: &str) -> bool {
+    matches!(decorator, "dataclass" | "dataclasses.dataclass")
+}
+
+/// The field a class body statement declares, if it declares one
+fn field(statement: &PythonHIR) -> Result<Option<DataclassField>> {
+    Ok(match statement {
+        PythonHIR::Declaration {
+            name,
+            type_annotation,
+            ..
+        } => Some(DataclassField {
+            name: name.clone(),
+            field_type: type_annotation.clone(),
+            default: None,
+        }),
+        PythonHIR::Assign {
+            target: Target::Name(name),
+            value,
+            type_annotation: Some(field_type),
+            ..
+        } => Some(DataclassField {
+            name: name.clone(),
+            field_type: field_type.clone(),
+            default: Some(field_default(value)?),
+        }),
+        _ => None,
+    })
+}
+
+/// The default a field's value gives it, unwrapping `field(..)`
+fn field_default(value: &PythonHIR) -> Result<FieldDefault> {
+    let PythonHIR::Call {
+        callee,
+        args,
+        kwargs,
+        ..
+    } = value
+    else {
+        return Ok(FieldDefault::Value(value.clone()));
+    };
+    let is_field = match callee.as_ref() {
+        PythonHIR::Variable { name, .. } => name == "field",
+        PythonHIR::Attribute { object, attr, .. } => {
+            attr == "field"
+                && matches!(object.as_ref(), PythonHIR::Variable { name, .. } if name == "dataclasses")
+        }
+        _ => false,
+    };
+    if !is_field {
+        return Ok(FieldDefault::Value(value.clone()));
+    }
+    match (args.as_slice(), kwargs.as_slice()) {
+        ([], [(Some(keyword), default)]) if keyword == "default" => {
+            Ok(FieldDefault::Value(default.clone()))
+        }
+        ([], [(Some(keyword), factory)]) if keyword == "default_factory" => {
+            Ok(FieldDefault::Factory(factory.clone()))
+        }
+        _ => Err(unsupported_in(
+            "`field()` without a single `default` or `default_factory`",
+            value,
+        )),
+    }
+}
+
+/// Whether a type is or contains a float, which rules out `Eq` and `Ord`
+fn holds_float(ty: &Type) -> bool {
+    match ty {
+        Type::Python(PythonType::Float) | Type::Rust(RustType::Float { .. }) => true,
+        Type::Python(
+            PythonType::List(item) | PythonType::Set(item) | PythonType::Optional(item),
+        ) => holds_float(item),
+        Type::Python(PythonType::Dict { key, value }) => holds_float(key) || holds_float(value),
+        Type::Python(PythonType::Tuple(types)) => types.iter().any(holds_float),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::python::Literal as PythonLiteral;
+
+    fn declaration(name: &str, ty: PythonType) -> DataclassField {
+        DataclassField {
+            name: name.to_owned(),
+            field_type: Type::Python(ty),
+            default: None,
+        }
+    }
+
+    #[test]
+    fn test_order_derives_total_ordering() {
+        let dataclass = Dataclass {
+            fields: vec![
+                declaration("rank", PythonType::Int),
+                declaration("name", PythonType::Str),
+            ],
+            eq: true,
+            order: true,
+            frozen: true,
+        };
+
+        assert_eq!(
+            dataclass.derives(),
+            vec![
+                "Debug",
+                "Clone",
+                "PartialEq",
+                "Eq",
+                "PartialOrd",
+                "Ord",
+                "Hash"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_float_field_rules_out_eq_and_ord() {
+        let dataclass = Dataclass {
+            fields: vec![declaration(
+                "scores",
+                PythonType::List(Box::new(Type::Python(PythonType::Float))),
+            )],
+            eq: true,
+            order: true,
+            frozen: false,
+        };
+
+        assert_eq!(
+            dataclass.derives(),
+            vec!["Debug", "Clone", "PartialEq", "PartialOrd"]
+        );
+    }
+
+    #[test]
+    fn test_field_call_with_other_options_is_reported() {
+        let value = PythonHIR::Call {
+            id: NodeId::new(0),
+            callee: Box::new(PythonHIR::Variable {
+                id: NodeId::new(0),
+                name: "field".to_owned(),
+                inferred_type: None,
+                meta: Metadata::new(),
+            }),
+            args: vec![],
+            kwargs: vec![(
+                Some("compare".to_owned()),
+                PythonHIR::Literal {
+                    id: NodeId::new(0),
+                    value: PythonLiteral::Bool(false),
+                    meta: Metadata::new(),
+                },
+            )],
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+
+        let error = field_default(&value).expect_err("Should reject `compare=False`");
+        assert!(error.to_string().contains("`field()` without"), "{error}");
+    }
+}
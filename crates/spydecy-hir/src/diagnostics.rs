@@ -0,0 +1,157 @@
+//! Diagnostics reported about the input
+//!
+//! Unlike errors, diagnostics do not stop compilation on their own: an
+//! import of a module outside the project is reported while the rest of
+//! the project is still compiled. Front ends collect them for the caller
+//! to report.
+
+use crate::SourceLocation;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    /// Additional information
+    Note,
+    /// Suspicious input that can still be compiled
+    Warning,
+    /// Input that cannot be compiled
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Note => write!(f, "note"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Problem reported about the input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Severity
+    pub severity: Severity,
+    /// Message, starting in lowercase
+    pub message: String,
+    /// Where the problem is, when known
+    pub location: Option<SourceLocation>,
+    /// Suggested edits that would fix the problem
+    #[serde(default)]
+    pub fix_its: Vec<FixIt>,
+}
+
+/// Edit suggested by a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixIt {
+    /// Source range to replace; empty for an insertion
+    pub location: Option<SourceLocation>,
+    /// Text to put in its place; empty for a removal
+    pub replacement: String,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic without a location
+    #[must_use]
+    pub const fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            location: None,
+            fix_its: Vec::new(),
+        }
+    }
+
+    /// Create an error
+    #[must_use]
+    pub const fn error(message: String) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    /// Create a warning
+    #[must_use]
+    pub const fn warning(message: String) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    /// Set where the problem is
+    #[must_use]
+    pub fn with_location(mut self, location: Option<SourceLocation>) -> Self {
+        self.location = location;
+        self
+    }
+
+    /// Suggest an edit that would fix the problem
+    #[must_use]
+    pub fn with_fix_it(mut self, fix_it: FixIt) -> Self {
+        self.fix_its.push(fix_it);
+        self
+    }
+
+    /// Whether the diagnostic stops compilation
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// `warning: message`, followed by `  --> file:line:col` when the
+    /// location is known and `  = fix-it: ...` for each suggested edit
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(location) = &self.location {
+            write!(f, "\n  --> {location}")?;
+        }
+        for fix_it in &self.fix_its {
+            write!(f, "\n  = fix-it: replace with `{}`", fix_it.replacement)?;
+            if let Some(location) = &fix_it.location {
+                write!(f, " at {location}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A diagnostic returned as an error stops lowering
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    #[test]
+    fn test_display_with_location() {
+        let diagnostic =
+            Diagnostic::warning("unresolved import `numpy`".to_owned()).with_location(Some(
+                SourceLocation::new("app/main.py".to_owned(), 3, 1, Language::Python),
+            ));
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "warning: unresolved import `numpy`\n  --> app/main.py:3:1"
+        );
+        assert!(!diagnostic.is_error());
+    }
+
+    #[test]
+    fn test_display_with_fix_it() {
+        let location = SourceLocation::new("list.c".to_owned(), 4, 14, Language::C);
+        let diagnostic = Diagnostic::error("expected ';' after return statement".to_owned())
+            .with_location(Some(location.clone()))
+            .with_fix_it(FixIt {
+                location: Some(location),
+                replacement: ";".to_owned(),
+            });
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: expected ';' after return statement\n  --> list.c:4:14\n  \
+             = fix-it: replace with `;` at list.c:4:14"
+        );
+    }
+}
@@ -0,0 +1,409 @@
+//! Enumerations
+//!
+//! A subclass of `enum.Enum` becomes a fieldless Rust enum, whose `value`,
+//! `name` and `from_value` methods map the variants to and from the
+//! members' values:
+//!
+//! ```text
+//! class Color(Enum):      →  pub enum Color { Red, DarkBlue }
+//!     RED = 1
+//!     DARK_BLUE = auto()     impl Color { pub fn value(&self) -> i64 { .. } .. }
+//!
+//! Color.DARK_BLUE         →  Color::DarkBlue
+//! color.value             →  color.value()
+//! color.name              →  color.name()
+//! Color(2)                →  Color::from_value(2).expect("value is not a valid Color")
+//! ```
+//!
+//! - members hold either ints or strings; `auto()` numbers the members
+//!   from 1, or gives the lowercased member name in a `StrEnum`
+//! - an unknown value panics where Python raises `ValueError`
+//! - aliases (members repeating a value) and methods are reported
+
+use crate::{
+    lowering::{expr_type, unsupported_in},
+    python::{Literal as PythonLiteral, PythonHIR, Target},
+    types::{PythonType, Type},
+    unified::{EnumVariant, LiteralValue, UnifiedHIR, Unifier},
+    Language,
+};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// Base classes making a class an enumeration
+const ENUM_BASES: &[&str] = &[
+    "Enum",
+    "IntEnum",
+    "StrEnum",
+    "enum.Enum",
+    "enum.IntEnum",
+    "enum.StrEnum",
+];
+
+/// Enumerations defined by the Python code being lowered
+#[derive(Debug, Default)]
+pub(crate) struct EnumModel {
+    /// Member names of each enumeration, by class name
+    classes: BTreeMap<String, Vec<String>>,
+}
+
+impl EnumModel {
+    /// A model for code defining no enumerations
+    pub(crate) const fn new() -> Self {
+        Self {
+            classes: BTreeMap::new(),
+        }
+    }
+
+    /// Find the enumerations among the top-level statements
+    pub(crate) fn analyze(body: &[PythonHIR]) -> Self {
+        let mut model = Self::new();
+        for node in body {
+            let PythonHIR::Class {
+                name, bases, body, ..
+            } = node
+            else {
+                continue;
+            };
+            if !bases.iter().any(|base| ENUM_BASES.contains(&base.as_str())) {
+                continue;
+            }
+            let members = body
+                .iter()
+                .filter_map(|statement| match statement {
+                    PythonHIR::Assign {
+                        target: Target::Name(member),
+                        ..
+                    } => Some(member.clone()),
+                    _ => None,
+                })
+                .collect();
+            model.classes.insert(name.clone(), members);
+        }
+        model
+    }
+
+    /// Whether a class defined by the module is an enumeration
+    pub(crate) fn is_enum(&self, class: &str) -> bool {
+        self.classes.contains_key(class)
+    }
+
+    /// Whether an expression's value is a member of an enumeration
+    fn holds_member(&self, node: &PythonHIR) -> bool {
+        let lookup = matches!(node, PythonHIR::Call { callee, .. }
+            if matches!(callee.as_ref(), PythonHIR::Variable { name, .. } if self.is_enum(name)));
+        lookup
+            || self.member(node).is_some()
+            || matches!(expr_type(node), Type::Python(PythonType::Class(class)) if self.is_enum(&class))
+    }
+
+    /// The enumeration and member named by `Class.MEMBER`
+    fn member(&self, node: &PythonHIR) -> Option<(String, String)> {
+        let PythonHIR::Attribute { object, attr, .. } = node else {
+            return None;
+        };
+        let PythonHIR::Variable { name, .. } = object.as_ref() else {
+            return None;
+        };
+        self.classes
+            .get(name)?
+            .contains(attr)
+            .then(|| (name.clone(), attr.clone()))
+    }
+}
+
+impl Unifier {
+    /// Lower an enumeration class into an enum
+    pub(crate) fn lower_enum(&mut self, class: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Class {
+            name,
+            bases,
+            body,
+            decorators,
+            meta,
+            ..
+        } = class
+        else {
+            return Err(unsupported_in("enumeration", class));
+        };
+        if let Some(decorator) = decorators
+            .iter()
+            .find(|decorator| !matches!(decorator.as_str(), "unique" | "enum.unique"))
+        {
+            return Err(unsupported_in(&format!("decorator `@{decorator}`"), class));
+        }
+        let str_enum = bases.iter().any(|base| base.ends_with("StrEnum"));
+
+        let mut variants: Vec<EnumVariant> = Vec::new();
+        for node in body {
+            let (member, value) = match node {
+                PythonHIR::Assign {
+                    target: Target::Name(member),
+                    value,
+                    ..
+                } => (member, value),
+                PythonHIR::Literal { .. } | PythonHIR::Pass { .. } => continue,
+                PythonHIR::Function { .. } => {
+                    return Err(unsupported_in("method of an enumeration", node));
+                }
+                _ => return Err(unsupported_in("enumeration member", node)),
+            };
+            let value = match value.as_ref() {
+                PythonHIR::Literal {
+                    value: PythonLiteral::Int(value),
+                    ..
+                } => LiteralValue::Int(*value),
+                PythonHIR::Literal {
+                    value: PythonLiteral::Str(value),
+                    ..
+                } => LiteralValue::Str(value.clone()),
+                PythonHIR::Call { callee, args, .. } if args.is_empty() && is_auto(callee) => {
+                    if str_enum {
+                        LiteralValue::Str(member.to_lowercase())
+                    } else {
+                        let last = variants
+                            .iter()
+                            .rev()
+                            .find_map(|variant| match variant.value {
+                                LiteralValue::Int(value) => Some(value),
+                                _ => None,
+                            });
+                        LiteralValue::Int(last.map_or(1, |last| last + 1))
+                    }
+                }
+                _ => return Err(unsupported_in("enumeration member value", node)),
+            };
+            if variants.iter().any(|variant| variant.value == value) {
+                return Err(unsupported_in("enumeration member alias", node));
+            }
+            if variants.first().is_some_and(|first| {
+                std::mem::discriminant(&first.value) != std::mem::discriminant(&value)
+            }) {
+                return Err(unsupported_in("enumeration mixing value types", node));
+            }
+            variants.push(EnumVariant {
+                name: variant_name(member),
+                member: member.clone(),
+                value,
+            });
+        }
+
+        let value_type = match variants.first().map(|variant| &variant.value) {
+            Some(LiteralValue::Str(_)) => PythonType::Str,
+            _ => PythonType::Int,
+        };
+        Ok(UnifiedHIR::Enum {
+            id: self.next_node_id(),
+            name: name.clone(),
+            variants,
+            value_type: Type::Python(value_type),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower `Class.MEMBER`, `.value` and `.name` of an enumeration
+    ///
+    /// Returns `None` for any other expression.
+    pub(crate) fn lower_enum_access(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Attribute {
+            object, attr, meta, ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        if let Some((class, member)) = self.enums.member(node) {
+            return Ok(Some(UnifiedHIR::Variable {
+                id: self.next_node_id(),
+                name: format!("{class}::{}", variant_name(&member)),
+                var_type: Type::Python(PythonType::Class(class)),
+                source_language: Language::Python,
+                meta: meta.clone(),
+            }));
+        }
+        if !matches!(attr.as_str(), "value" | "name") || !self.enums.holds_member(object) {
+            return Ok(None);
+        }
+        Ok(Some(UnifiedHIR::MethodCall {
+            id: self.next_node_id(),
+            receiver: Box::new(self.lower_expr(object)?),
+            method: attr.clone(),
+            args: vec![],
+            inferred_type: Type::Unknown,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }))
+    }
+
+    /// Lower `Class(value)` of an enumeration to `Class::from_value(value)`,
+    /// panicking on values that are not members
+    ///
+    /// Returns `None` for any other call.
+    pub(crate) fn lower_enum_call(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Call {
+            callee, args, meta, ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        let PythonHIR::Variable { name: class, .. } = callee.as_ref() else {
+            return Ok(None);
+        };
+        if !self.enums.is_enum(class) {
+            return Ok(None);
+        }
+        let [value] = args.as_slice() else {
+            return Err(unsupported_in(
+                &format!("enumeration `{class}` called without a single value"),
+                node,
+            ));
+        };
+        let lookup = UnifiedHIR::Call {
+            id: self.next_node_id(),
+            target_language: Language::Rust,
+            callee: format!("{class}::from_value"),
+            args: vec![self.lower_expr(value)?],
+            inferred_type: Type::Python(PythonType::Optional(Box::new(Type::Python(
+                PythonType::Class(class.clone()),
+            )))),
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: meta.clone(),
+        };
+        let message = UnifiedHIR::Literal {
+            id: self.next_node_id(),
+            value: LiteralValue::Str(format!("value is not a valid {class}")),
+            lit_type: Type::Python(PythonType::Str),
+            meta: meta.clone(),
+        };
+        Ok(Some(UnifiedHIR::MethodCall {
+            id: self.next_node_id(),
+            receiver: Box::new(lookup),
+            method: "expect".to_owned(),
+            args: vec![message],
+            inferred_type: Type::Python(PythonType::Class(class.clone())),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }))
+    }
+}
+
+/// Whether a callee is `auto` or `enum.auto`
+fn is_auto(callee: &PythonHIR) -> bool {
+    match callee {
+        PythonHIR::Variable { name, .. } => name == "auto",
+        PythonHIR::Attribute { object, attr, .. } => {
+            attr == "auto"
+                && matches!(object.as_ref(), PythonHIR::Variable { name, .. } if name == "enum")
+        }
+        _ => false,
+    }
+}
+
+/// Rust variant name of a member: `DARK_BLUE` → `DarkBlue`
+fn variant_name(member: &str) -> String {
+    member
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{metadata::Metadata, NodeId};
+
+    fn member(name: &str, value: PythonHIR) -> PythonHIR {
+        PythonHIR::Assign {
+            id: NodeId::new(0),
+            target: Target::Name(name.to_owned()),
+            value: Box::new(value),
+            type_annotation: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn auto() -> PythonHIR {
+        PythonHIR::Call {
+            id: NodeId::new(0),
+            callee: Box::new(PythonHIR::Variable {
+                id: NodeId::new(0),
+                name: "auto".to_owned(),
+                inferred_type: None,
+                meta: Metadata::new(),
+            }),
+            args: vec![],
+            kwargs: vec![],
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn int(value: i64) -> PythonHIR {
+        PythonHIR::Literal {
+            id: NodeId::new(0),
+            value: PythonLiteral::Int(value),
+            meta: Metadata::new(),
+        }
+    }
+
+    fn class(base: &str, body: Vec<PythonHIR>) -> PythonHIR {
+        PythonHIR::Class {
+            id: NodeId::new(0),
+            name: "Color".to_owned(),
+            bases: vec![base.to_owned()],
+            body,
+            decorators: vec![],
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_variant_names() {
+        assert_eq!(variant_name("RED"), "Red");
+        assert_eq!(variant_name("DARK_BLUE"), "DarkBlue");
+        assert_eq!(variant_name("http_2"), "Http2");
+    }
+
+    #[test]
+    fn test_auto_continues_from_last_value() {
+        let enumeration = class(
+            "Enum",
+            vec![member("RED", int(5)), member("DARK_BLUE", auto())],
+        );
+
+        let lowered = Unifier::new()
+            .lower_enum(&enumeration)
+            .expect("Should lower enumeration");
+
+        let UnifiedHIR::Enum { variants, .. } = lowered else {
+            panic!("Expected an enum, got {lowered:?}");
+        };
+        assert_eq!(variants[1].name, "DarkBlue");
+        assert_eq!(variants[1].value, LiteralValue::Int(6));
+    }
+
+    #[test]
+    fn test_alias_is_reported() {
+        let enumeration = class(
+            "IntEnum",
+            vec![member("RED", int(1)), member("ROUGE", int(1))],
+        );
+
+        let error = Unifier::new()
+            .lower_enum(&enumeration)
+            .expect_err("Should reject alias");
+
+        assert!(error.to_string().contains("alias"), "{error}");
+    }
+}
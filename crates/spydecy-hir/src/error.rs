@@ -85,61 +85,61 @@ pub fn all_patterns() -> Vec<PatternSuggestion> {
         ),
         PatternSuggestion::new(
             UnificationPattern::AppendPattern,
-            "append()",
+            "list.append()",
             "PyList_Append()",
             "Vec::push()",
         ),
         PatternSuggestion::new(
             UnificationPattern::DictGetPattern,
-            "get()",
+            "dict.get()",
             "PyDict_GetItem()",
             "HashMap::get()",
         ),
         PatternSuggestion::new(
             UnificationPattern::ReversePattern,
-            "reverse()",
+            "list.reverse()",
             "list_reverse()",
             "Vec::reverse()",
         ),
         PatternSuggestion::new(
             UnificationPattern::ClearPattern,
-            "clear()",
+            "list.clear()",
             "list_clear()",
             "Vec::clear()",
         ),
         PatternSuggestion::new(
             UnificationPattern::PopPattern,
-            "pop()",
+            "list.pop()",
             "list_pop()",
             "Vec::pop()",
         ),
         PatternSuggestion::new(
             UnificationPattern::InsertPattern,
-            "insert()",
+            "list.insert()",
             "list_insert()",
             "Vec::insert()",
         ),
         PatternSuggestion::new(
             UnificationPattern::ExtendPattern,
-            "extend()",
+            "list.extend()",
             "list_extend()",
             "Vec::extend()",
         ),
         PatternSuggestion::new(
             UnificationPattern::DictPopPattern,
-            "dict_pop()",
+            "dict.pop()",
             "PyDict_DelItem()",
             "HashMap::remove()",
         ),
         PatternSuggestion::new(
             UnificationPattern::DictClearPattern,
-            "dict_clear()",
+            "dict.clear()",
             "PyDict_Clear()",
             "HashMap::clear()",
         ),
         PatternSuggestion::new(
             UnificationPattern::DictKeysPattern,
-            "keys()",
+            "dict.keys()",
             "PyDict_Keys()",
             "HashMap::keys()",
         ),
@@ -270,6 +270,7 @@ pub fn extract_python_fn_name(python: &PythonHIR) -> String {
     match python {
         PythonHIR::Call { callee, .. } => match callee.as_ref() {
             PythonHIR::Variable { name, .. } => name.clone(),
+            PythonHIR::Attribute { attr, .. } => attr.clone(),
             _ => "<complex expression>".to_owned(),
         },
         PythonHIR::Variable { name, .. } | PythonHIR::Function { name, .. } => name.clone(),
@@ -0,0 +1,859 @@
+//! Exceptions and the `CPython` error convention
+//!
+//! Python code raises exceptions; `CPython`'s C code sets one with
+//! `PyErr_SetString` and returns `NULL` or `-1`. Both are unified into a
+//! single generated error enum:
+//!
+//! ```text
+//! raise IndexError("pop from empty list") ──┐
+//!                                           ├──→ Err(Error::IndexError(..))
+//! PyErr_SetString(PyExc_IndexError, ..);  ──┘
+//! return NULL;
+//! ```
+//!
+//! Functions that can raise return `Result<T, Error>` and calls to them
+//! propagate with `?`. This module holds the analysis deciding which
+//! functions can raise what; the lowering itself lives in
+//! [`crate::lowering`].
+
+use crate::{
+    c::{Literal as CLiteral, UnaryOp as CUnaryOp, CHIR},
+    context_managers::{manager_class, opens_file},
+    python::{ExceptHandler, PythonHIR},
+    types::{RustType, Type},
+    unified::{ErrorVariant, LiteralValue, UnifiedHIR, Unifier},
+    Language,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Name of the generated error enum
+pub const ERROR_TYPE: &str = "Error";
+
+/// Exception names that catch everything in an `except` clause
+const CATCH_ALL: &[&str] = &["Exception", "BaseException"];
+
+/// Built-in exceptions and their base class
+const BUILTIN_EXCEPTIONS: &[(&str, &str)] = &[
+    ("ArithmeticError", "Exception"),
+    ("AssertionError", "Exception"),
+    ("AttributeError", "Exception"),
+    ("IndexError", "LookupError"),
+    ("KeyError", "LookupError"),
+    ("LookupError", "Exception"),
+    ("MemoryError", "Exception"),
+    ("NotImplementedError", "RuntimeError"),
+    ("OSError", "Exception"),
+    ("OverflowError", "ArithmeticError"),
+    ("RuntimeError", "Exception"),
+    ("StopIteration", "Exception"),
+    ("TypeError", "Exception"),
+    ("ValueError", "Exception"),
+    ("ZeroDivisionError", "ArithmeticError"),
+];
+
+/// Message of the `IndexError` raised by `list.pop()` on an empty list
+pub(crate) const EMPTY_POP_MESSAGE: &str = "pop from empty list";
+
+/// An exception set by C code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaisedError {
+    /// Exception name, without the `PyExc_` prefix
+    pub exception: String,
+    /// Message set along with it
+    pub message: Option<String>,
+}
+
+/// Value a C function returns after setting an exception
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorSentinel {
+    /// `return NULL` from functions returning a pointer
+    Null,
+    /// `return -1` from functions returning a status
+    MinusOne,
+}
+
+/// How a C function reports errors
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CErrorConvention {
+    /// Value returned on error
+    pub sentinel: ErrorSentinel,
+    /// Exceptions set before returning it, in source order
+    pub raises: Vec<RaisedError>,
+}
+
+/// Detect the `CPython` error convention in a C function
+///
+/// Looks for `PyErr_SetString`, `PyErr_Format`, `PyErr_SetNone` and
+/// `PyErr_NoMemory` calls together with a `return NULL` or `return -1`.
+/// Returns `None` when the function never sets an exception.
+#[must_use]
+pub fn c_error_convention(function: &CHIR) -> Option<CErrorConvention> {
+    let mut raises = Vec::new();
+    let mut sentinel = None;
+    visit_c(function, &mut |node| match node {
+        CHIR::Call { callee, args, .. } => raises.extend(c_raised_error(callee, args)),
+        CHIR::Return {
+            value: Some(value), ..
+        } => sentinel = sentinel.or_else(|| error_sentinel(value)),
+        _ => {}
+    });
+
+    if raises.is_empty() {
+        return None;
+    }
+    sentinel.map(|sentinel| CErrorConvention { sentinel, raises })
+}
+
+/// Visit a C node and its descendants
+pub(crate) fn visit_c<'a>(node: &'a CHIR, visit: &mut impl FnMut(&'a CHIR)) {
+    visit(node);
+    for child in node.children() {
+        visit_c(child, visit);
+    }
+}
+
+/// The exception set by a `PyErr_*` call
+fn c_raised_error(callee: &CHIR, args: &[CHIR]) -> Option<RaisedError> {
+    let exception = |arg: Option<&CHIR>| {
+        c_name(strip_casts(arg?)).map(|name| name.trim_start_matches("PyExc_").to_owned())
+    };
+    match c_name(callee)? {
+        "PyErr_SetString" => Some(RaisedError {
+            exception: exception(args.first())?,
+            message: match args.get(1).map(strip_casts) {
+                Some(CHIR::Literal {
+                    value: CLiteral::Str(message),
+                    ..
+                }) => Some(message.clone()),
+                _ => None,
+            },
+        }),
+        // The format string's placeholders have no fixed text
+        "PyErr_Format" | "PyErr_SetNone" => Some(RaisedError {
+            exception: exception(args.first())?,
+            message: None,
+        }),
+        "PyErr_NoMemory" => Some(RaisedError {
+            exception: "MemoryError".to_owned(),
+            message: None,
+        }),
+        _ => None,
+    }
+}
+
+/// Whether a returned value is `NULL` or `-1`
+fn error_sentinel(value: &CHIR) -> Option<ErrorSentinel> {
+    // `NULL` expands to `((void *)0)`
+    if let CHIR::Cast { expr, .. } = value {
+        if matches!(
+            strip_casts(expr),
+            CHIR::Literal {
+                value: CLiteral::Int(0),
+                ..
+            }
+        ) {
+            return Some(ErrorSentinel::Null);
+        }
+    }
+    match strip_casts(value) {
+        CHIR::Literal {
+            value: CLiteral::Null,
+            ..
+        } => Some(ErrorSentinel::Null),
+        CHIR::Variable { name, .. } if name == "NULL" => Some(ErrorSentinel::Null),
+        CHIR::Literal {
+            value: CLiteral::Int(-1),
+            ..
+        } => Some(ErrorSentinel::MinusOne),
+        CHIR::UnaryOp {
+            op: CUnaryOp::Neg,
+            operand,
+            ..
+        } if matches!(
+            strip_casts(operand),
+            CHIR::Literal {
+                value: CLiteral::Int(1),
+                ..
+            }
+        ) =>
+        {
+            Some(ErrorSentinel::MinusOne)
+        }
+        _ => None,
+    }
+}
+
+/// Look through casts, including the implicit ones clang inserts
+fn strip_casts(node: &CHIR) -> &CHIR {
+    match node {
+        CHIR::Cast { expr, .. } => strip_casts(expr),
+        other => other,
+    }
+}
+
+/// Name of a referenced C function, variable or macro
+fn c_name(node: &CHIR) -> Option<&str> {
+    match node {
+        CHIR::Variable { name, .. } | CHIR::CPythonMacro { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+/// Exceptions raised and caught by a Python module
+///
+/// Besides the analysis results, this tracks where lowering currently is,
+/// since that decides how `raise`, `return` and calls are lowered.
+#[derive(Debug, Default)]
+pub(crate) struct ExceptionModel {
+    /// Exception classes defined by the module, with their base and docstring
+    classes: BTreeMap<String, (String, Option<String>)>,
+    /// Every exception raised or caught by the module
+    raised: BTreeSet<String>,
+    /// Exceptions escaping each function that can raise (`name` or `Class.name`)
+    fallible: BTreeMap<String, BTreeSet<String>>,
+    /// Method names of each (non-exception) class defined by the module
+    methods: BTreeMap<String, BTreeSet<String>>,
+    /// Class whose methods are being lowered
+    pub(crate) class: Option<String>,
+    /// Whether the function being lowered returns a `Result`
+    pub(crate) returns_result: bool,
+    /// Names bound by the `except` clauses being lowered, innermost last
+    pub(crate) handlers: Vec<String>,
+}
+
+impl ExceptionModel {
+    /// A model for code that raises nothing
+    pub(crate) const fn new() -> Self {
+        Self {
+            classes: BTreeMap::new(),
+            raised: BTreeSet::new(),
+            fallible: BTreeMap::new(),
+            methods: BTreeMap::new(),
+            class: None,
+            returns_result: false,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Analyze the top-level statements of a module
+    pub(crate) fn analyze(body: &[PythonHIR]) -> Self {
+        let mut model = Self::new();
+
+        // Exception classes may derive from each other in any order
+        loop {
+            let before = model.classes.len();
+            for node in body {
+                if let PythonHIR::Class {
+                    name, bases, meta, ..
+                } = node
+                {
+                    let base = bases.iter().find(|base| model.is_exception(base));
+                    if let (false, Some(base)) = (model.classes.contains_key(name), base) {
+                        model
+                            .classes
+                            .insert(name.clone(), (base.clone(), meta.docs.clone()));
+                    }
+                }
+            }
+            if model.classes.len() == before {
+                break;
+            }
+        }
+
+        for node in body {
+            collect_exception_names(node, &mut model.raised);
+        }
+        model.raised.extend(model.classes.keys().map(String::clone));
+
+        let mut functions = Vec::new();
+        for node in body {
+            match node {
+                PythonHIR::Function { name, body, .. } => {
+                    functions.push((name.clone(), None, body));
+                }
+                PythonHIR::Class {
+                    name: class, body, ..
+                } if !model.is_exception_class(class) => {
+                    let methods = model.methods.entry(class.clone()).or_default();
+                    for method in body {
+                        if let PythonHIR::Function { name, body, .. } = method {
+                            methods.insert(name.clone());
+                            functions.push((format!("{class}.{name}"), Some(class.as_str()), body));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Raising spreads to callers; resolved to a fixed point
+        loop {
+            let mut changed = false;
+            for (key, class, body) in &functions {
+                let raised = model.raised_in(body, *class, &BTreeSet::new());
+                if !raised.is_empty() && model.fallible.get(key) != Some(&raised) {
+                    model.fallible.insert(key.clone(), raised);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return model;
+            }
+        }
+    }
+
+    /// Variants of the generated error enum, one per exception
+    pub(crate) fn variants(&self) -> Vec<ErrorVariant> {
+        self.raised
+            .iter()
+            .map(|name| ErrorVariant {
+                name: name.clone(),
+                docs: self.classes.get(name).and_then(|(_, docs)| docs.clone()),
+            })
+            .collect()
+    }
+
+    /// Whether a class defined by the module is an exception
+    pub(crate) fn is_exception_class(&self, name: &str) -> bool {
+        self.classes.contains_key(name)
+    }
+
+    /// Whether a function (`name`, or `Class.name` for methods) can raise
+    pub(crate) fn can_raise(&self, function: &str) -> bool {
+        self.fallible.contains_key(function)
+    }
+
+    /// Whether calling `callee` from the code being lowered can raise
+    pub(crate) fn call_can_raise(&self, callee: &PythonHIR) -> bool {
+        self.call_key(callee, self.class.as_deref())
+            .is_some_and(|key| self.can_raise(&key))
+    }
+
+    /// Exceptions that statements of the code being lowered let escape
+    pub(crate) fn raised_by(&self, body: &[PythonHIR]) -> BTreeSet<String> {
+        self.raised_in(body, self.class.as_deref(), &BTreeSet::new())
+    }
+
+    /// Enum variants an `except` clause catches (`None` when it catches everything)
+    pub(crate) fn caught_variants(&self, handler: &ExceptHandler) -> Option<Vec<String>> {
+        handler_types(handler).map(|types| {
+            self.raised
+                .iter()
+                .filter(|name| types.iter().any(|base| self.is_subclass(name, base)))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Whether an `except` clause catches an exception
+    pub(crate) fn catches(&self, handler: &ExceptHandler, exception: &str) -> bool {
+        handler_types(handler).map_or(true, |types| {
+            types.iter().any(|base| self.is_subclass(exception, base))
+        })
+    }
+
+    /// Key under which a called function's analysis is stored
+    ///
+    /// `f(..)` resolves to `f`, `C(..)` to `C.__init__` and `self.m(..)` to
+    /// `Class.m`. Without the receiver's type, `x.m(..)` only resolves when
+    /// a single class of the module defines `m`.
+    fn call_key(&self, callee: &PythonHIR, class: Option<&str>) -> Option<String> {
+        match callee {
+            PythonHIR::Variable { name, .. } if self.methods.contains_key(name) => {
+                Some(format!("{name}.__init__"))
+            }
+            PythonHIR::Variable { name, .. } => Some(name.clone()),
+            PythonHIR::Attribute { object, attr, .. } => match (object.as_ref(), class) {
+                (PythonHIR::Variable { name, .. }, Some(class)) if name == "self" => {
+                    Some(format!("{class}.{attr}"))
+                }
+                _ => {
+                    let mut owners = self
+                        .methods
+                        .iter()
+                        .filter(|(_, methods)| methods.contains(attr));
+                    match (owners.next(), owners.next()) {
+                        (Some((owner, _)), None) => Some(format!("{owner}.{attr}")),
+                        _ => None,
+                    }
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether a name is a built-in or module-defined exception
+    fn is_exception(&self, name: &str) -> bool {
+        CATCH_ALL.contains(&name) || self.base_of(name).is_some()
+    }
+
+    /// Base class of an exception
+    fn base_of(&self, name: &str) -> Option<&str> {
+        self.classes.get(name).map_or_else(
+            || {
+                BUILTIN_EXCEPTIONS
+                    .iter()
+                    .find(|(builtin, _)| *builtin == name)
+                    .map(|(_, base)| *base)
+            },
+            |(base, _)| Some(base.as_str()),
+        )
+    }
+
+    /// Whether `exception` is `base` or derives from it
+    fn is_subclass(&self, exception: &str, base: &str) -> bool {
+        let mut current = exception;
+        // Bounded, so a cyclic hierarchy cannot hang the analysis
+        for _ in 0..32 {
+            if current == base {
+                return true;
+            }
+            match self.base_of(current) {
+                Some(parent) => current = parent,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Exceptions escaping a statement list
+    ///
+    /// `reraised` holds what a bare `raise` re-raises: the exceptions caught
+    /// by the enclosing `except` clause.
+    fn raised_in(
+        &self,
+        body: &[PythonHIR],
+        class: Option<&str>,
+        reraised: &BTreeSet<String>,
+    ) -> BTreeSet<String> {
+        let mut raised = BTreeSet::new();
+        for node in body {
+            self.collect_raised(node, class, reraised, &mut raised);
+        }
+        raised
+    }
+
+    /// Collect the exceptions escaping a node into `raised`
+    fn collect_raised(
+        &self,
+        node: &PythonHIR,
+        class: Option<&str>,
+        reraised: &BTreeSet<String>,
+        raised: &mut BTreeSet<String>,
+    ) {
+        match node {
+            PythonHIR::Function { .. } | PythonHIR::Class { .. } => return,
+            PythonHIR::Raise {
+                exception: None, ..
+            } => raised.extend(reraised.iter().cloned()),
+            PythonHIR::Raise {
+                exception: Some(exception),
+                ..
+            } => raised.extend(exception_name(exception).map(str::to_owned)),
+            PythonHIR::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => {
+                let from_body = self.raised_in(body, class, reraised);
+                raised.extend(
+                    from_body
+                        .iter()
+                        .filter(|exception| {
+                            !handlers
+                                .iter()
+                                .any(|handler| self.catches(handler, exception))
+                        })
+                        .cloned(),
+                );
+                for handler in handlers {
+                    let caught = from_body
+                        .iter()
+                        .filter(|exception| self.catches(handler, exception))
+                        .cloned()
+                        .collect();
+                    raised.extend(self.raised_in(&handler.body, class, &caught));
+                }
+                raised.extend(self.raised_in(orelse, class, reraised));
+                raised.extend(self.raised_in(finalbody, class, reraised));
+                return;
+            }
+            PythonHIR::Call { callee, args, .. } => {
+                if let Some(escaping) = self
+                    .call_key(callee, class)
+                    .and_then(|key| self.fallible.get(&key))
+                {
+                    raised.extend(escaping.iter().cloned());
+                }
+                if pops_list(callee, args) {
+                    raised.insert("IndexError".to_owned());
+                }
+            }
+            PythonHIR::With { items, .. } => {
+                for item in items {
+                    if opens_file(&item.context) {
+                        raised.insert("OSError".to_owned());
+                    }
+                    if let Some(escaping) = manager_class(&item.context)
+                        .and_then(|manager| self.fallible.get(&format!("{manager}.__enter__")))
+                    {
+                        raised.extend(escaping.iter().cloned());
+                    }
+                }
+            }
+            _ => {}
+        }
+        for child in node.children() {
+            self.collect_raised(child, class, reraised, raised);
+        }
+    }
+}
+
+impl Unifier {
+    /// Build `Error::<exception>(<message>.to_string())`
+    pub(crate) fn error_value(
+        &mut self,
+        exception: &str,
+        message: Option<UnifiedHIR>,
+        meta: &crate::metadata::Metadata,
+    ) -> UnifiedHIR {
+        let message = match message {
+            Some(message) => UnifiedHIR::MethodCall {
+                id: self.next_node_id(),
+                receiver: Box::new(message),
+                method: "to_string".to_owned(),
+                args: vec![],
+                inferred_type: Type::Rust(RustType::String),
+                source_language: Language::Python,
+                meta: meta.clone(),
+            },
+            None => UnifiedHIR::Call {
+                id: self.next_node_id(),
+                target_language: Language::Rust,
+                callee: "String::new".to_owned(),
+                args: vec![],
+                inferred_type: Type::Rust(RustType::String),
+                source_language: Language::Python,
+                cross_mapping: None,
+                meta: meta.clone(),
+            },
+        };
+        UnifiedHIR::Call {
+            id: self.next_node_id(),
+            target_language: Language::Rust,
+            callee: format!("{ERROR_TYPE}::{exception}"),
+            args: vec![message],
+            inferred_type: Type::Rust(RustType::Custom(ERROR_TYPE.to_owned())),
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: meta.clone(),
+        }
+    }
+
+    /// Build `Error::<exception>("<message>".to_string())` from a fixed message
+    pub(crate) fn error_with_message(
+        &mut self,
+        exception: &str,
+        message: Option<&str>,
+        meta: &crate::metadata::Metadata,
+    ) -> UnifiedHIR {
+        let message = message.map(|message| UnifiedHIR::Literal {
+            id: self.next_node_id(),
+            value: LiteralValue::Str(message.to_owned()),
+            lit_type: Type::Rust(RustType::Str),
+            meta: meta.clone(),
+        });
+        self.error_value(exception, message, meta)
+    }
+
+    /// Turn a unified call whose C side follows the error convention into a
+    /// propagating one
+    ///
+    /// Only calls producing an `Option` can carry the error: `list.pop()`
+    /// unified with `list_pop()` becomes
+    /// `xs.pop().ok_or_else(|| Error::IndexError(..))?`.
+    pub(crate) fn propagate_c_error(
+        &mut self,
+        unified: UnifiedHIR,
+        convention: &CErrorConvention,
+    ) -> UnifiedHIR {
+        let (
+            UnifiedHIR::Call {
+                inferred_type: Type::Rust(RustType::Option(_)),
+                ..
+            },
+            Some(raised),
+        ) = (&unified, convention.raises.first())
+        else {
+            return unified;
+        };
+        let meta = unified.metadata().clone();
+        let error = self.error_with_message(&raised.exception, raised.message.as_deref(), &meta);
+        UnifiedHIR::Propagate {
+            id: self.next_node_id(),
+            value: Box::new(unified),
+            on_none: Some(Box::new(error)),
+            source_language: Language::Python,
+            meta,
+        }
+    }
+}
+
+/// Name of the exception in `raise E`, `raise E(..)` or `except module.E`
+pub(crate) fn exception_name(node: &PythonHIR) -> Option<&str> {
+    match node {
+        PythonHIR::Variable { name, .. } => Some(name),
+        PythonHIR::Attribute { attr, .. } => Some(attr),
+        PythonHIR::Call { callee, .. } => exception_name(callee),
+        _ => None,
+    }
+}
+
+/// Exception types named by an `except` clause (`None` when it catches everything)
+fn handler_types(handler: &ExceptHandler) -> Option<Vec<&str>> {
+    let types: Vec<&str> = match handler.exception_type.as_deref()? {
+        PythonHIR::Tuple { elements, .. } => elements.iter().filter_map(exception_name).collect(),
+        other => exception_name(other).into_iter().collect(),
+    };
+    if types.iter().any(|name| CATCH_ALL.contains(name)) {
+        return None;
+    }
+    Some(types)
+}
+
+/// Collect every exception named in `raise` statements and `except` clauses
+fn collect_exception_names(node: &PythonHIR, names: &mut BTreeSet<String>) {
+    match node {
+        PythonHIR::Raise {
+            exception: Some(exception),
+            ..
+        } => names.extend(exception_name(exception).map(str::to_owned)),
+        PythonHIR::Try { handlers, .. } => {
+            for handler in handlers {
+                names.extend(
+                    handler_types(handler)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(str::to_owned),
+                );
+            }
+        }
+        PythonHIR::Call { callee, args, .. } if pops_list(callee, args) => {
+            names.insert("IndexError".to_owned());
+        }
+        PythonHIR::With { items, .. } if items.iter().any(|item| opens_file(&item.context)) => {
+            names.insert("OSError".to_owned());
+        }
+        _ => {}
+    }
+    for child in node.children() {
+        collect_exception_names(child, names);
+    }
+}
+
+/// Whether a call is `xs.pop()`, which raises `IndexError` on an empty list
+pub(crate) fn pops_list(callee: &PythonHIR, args: &[PythonHIR]) -> bool {
+    args.is_empty() && matches!(callee, PythonHIR::Attribute { attr, .. } if attr == "pop")
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{metadata::Metadata, NodeId, Visibility};
+
+    fn var(name: &str) -> PythonHIR {
+        PythonHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn raise(exception: &str) -> PythonHIR {
+        PythonHIR::Raise {
+            id: NodeId::new(0),
+            exception: Some(Box::new(var(exception))),
+            cause: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn call(name: &str) -> PythonHIR {
+        PythonHIR::Call {
+            id: NodeId::new(0),
+            callee: Box::new(var(name)),
+            args: vec![],
+            kwargs: vec![],
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn function(name: &str, body: Vec<PythonHIR>) -> PythonHIR {
+        PythonHIR::Function {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            params: vec![],
+            return_type: None,
+            body,
+            decorators: vec![],
+            visibility: Visibility::Public,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn try_except(body: Vec<PythonHIR>, caught: &str) -> PythonHIR {
+        PythonHIR::Try {
+            id: NodeId::new(0),
+            body,
+            handlers: vec![ExceptHandler {
+                exception_type: Some(Box::new(var(caught))),
+                name: None,
+                body: vec![],
+                meta: Metadata::new(),
+            }],
+            orelse: vec![],
+            finalbody: vec![],
+            meta: Metadata::new(),
+        }
+    }
+
+    fn c_var(name: &str) -> CHIR {
+        CHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn c_literal(value: CLiteral) -> CHIR {
+        CHIR::Literal {
+            id: NodeId::new(0),
+            value,
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_raising_spreads_to_callers() {
+        let model = ExceptionModel::analyze(&[
+            function("check", vec![raise("ValueError")]),
+            function("run", vec![call("check")]),
+            function("safe", vec![try_except(vec![call("check")], "Exception")]),
+            function("narrow", vec![try_except(vec![call("check")], "KeyError")]),
+        ]);
+
+        assert!(model.can_raise("check"));
+        assert!(model.can_raise("run"), "callers of raising functions raise");
+        assert!(
+            !model.can_raise("safe"),
+            "`except Exception` catches everything"
+        );
+        assert!(
+            model.can_raise("narrow"),
+            "KeyError does not catch ValueError"
+        );
+        let names: Vec<String> = model.variants().into_iter().map(|v| v.name).collect();
+        assert_eq!(names, vec!["KeyError", "ValueError"]);
+    }
+
+    #[test]
+    fn test_exception_classes_join_the_hierarchy() {
+        let class = |name: &str, base: &str| PythonHIR::Class {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            bases: vec![base.to_owned()],
+            body: vec![],
+            decorators: vec![],
+            meta: Metadata::new(),
+        };
+        let model = ExceptionModel::analyze(&[
+            class("Overdrawn", "AccountError"),
+            class("AccountError", "LookupError"),
+            class("Plain", "object"),
+            function(
+                "withdraw",
+                vec![try_except(vec![raise("Overdrawn")], "LookupError")],
+            ),
+        ]);
+
+        assert!(model.is_exception_class("Overdrawn"));
+        assert!(!model.is_exception_class("Plain"));
+        assert!(
+            !model.can_raise("withdraw"),
+            "LookupError catches a class derived from it"
+        );
+    }
+
+    #[test]
+    fn test_c_error_convention() {
+        let set_string = CHIR::Call {
+            id: NodeId::new(0),
+            callee: Box::new(c_var("PyErr_SetString")),
+            args: vec![
+                c_var("PyExc_IndexError"),
+                c_literal(CLiteral::Str("pop from empty list".to_owned())),
+            ],
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+        let list_pop = CHIR::Function {
+            id: NodeId::new(0),
+            name: "list_pop".to_owned(),
+            return_type: Type::Unknown,
+            params: vec![],
+            body: vec![CHIR::If {
+                id: NodeId::new(0),
+                condition: Box::new(c_var("empty")),
+                then_branch: vec![
+                    set_string,
+                    CHIR::Return {
+                        id: NodeId::new(0),
+                        value: Some(Box::new(c_literal(CLiteral::Null))),
+                        meta: Metadata::new(),
+                    },
+                ],
+                else_branch: vec![],
+                meta: Metadata::new(),
+            }],
+            storage_class: crate::c::StorageClass::Static,
+            visibility: Visibility::Private,
+            meta: Metadata::new(),
+        };
+
+        let convention = c_error_convention(&list_pop).expect("Should detect convention");
+        assert_eq!(convention.sentinel, ErrorSentinel::Null);
+        assert_eq!(
+            convention.raises,
+            vec![RaisedError {
+                exception: "IndexError".to_owned(),
+                message: Some("pop from empty list".to_owned()),
+            }]
+        );
+
+        let CHIR::Function { name, body, .. } = list_pop else {
+            unreachable!()
+        };
+        let CHIR::If { then_branch, .. } = &body[0] else {
+            unreachable!()
+        };
+        // Returning NULL without setting an exception is not an error
+        let lookup = CHIR::Function {
+            id: NodeId::new(0),
+            name,
+            return_type: Type::Unknown,
+            params: vec![],
+            body: vec![then_branch[1].clone()],
+            storage_class: crate::c::StorageClass::Static,
+            visibility: Visibility::Private,
+            meta: Metadata::new(),
+        };
+        assert_eq!(c_error_convention(&lookup), None);
+    }
+}
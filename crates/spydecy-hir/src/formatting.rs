@@ -0,0 +1,698 @@
+//! String formatting
+//!
+//! f-strings, `str.format` and `%`-formatting all become `format!`:
+//!
+//! ```text
+//! f"{name:>8}: {score:.2f}"           ──┐
+//! "{:>8}: {:.2f}".format(name, score) ──┼──→ format!("{:>8}: {:.2}", name, score)
+//! "%8s: %.2f" % (name, score)         ──┘
+//! ```
+//!
+//! Format specs are translated only where Rust prints the same text as
+//! Python. Anything else - digit grouping, the `e`/`g`/`%` presentation
+//! types, `=` alignment, a precision on a value that may be a float, ... -
+//! is reported together with the offending spec. `!r` and `%r` use
+//! `Debug`, which quotes strings with `"` where `repr` uses `'`.
+
+use crate::{
+    lowering::{expr_type, unsupported_in},
+    python::{BinOp as PythonBinOp, FormatPart, Literal as PythonLiteral, PythonHIR},
+    types::{PythonType, Type},
+    unified::{UnifiedHIR, Unifier},
+    Language,
+};
+use anyhow::Result;
+
+/// Argument of a `str.format` replacement field
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FieldKey {
+    /// Positional argument
+    Index(usize),
+    /// Keyword argument
+    Name(String),
+}
+
+/// Piece of a parsed `str.format` template
+enum Piece {
+    /// Literal text, braces already escaped
+    Text(String),
+    /// Replacement field referencing the `arg`-th used argument
+    Field {
+        /// Position among the used arguments
+        arg: usize,
+        /// Rust spec, including the leading `:`
+        spec: String,
+    },
+}
+
+impl Unifier {
+    /// Lower an f-string, `"...".format(..)` or `"..." % values` into
+    /// `format!`
+    ///
+    /// Returns `None` for other expressions.
+    pub(crate) fn lower_formatting(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let (template, args) = match node {
+            PythonHIR::FormattedString { parts, .. } => self.lower_f_string(parts)?,
+            PythonHIR::Call {
+                callee,
+                args,
+                kwargs,
+                ..
+            } => match callee.as_ref() {
+                PythonHIR::Attribute { object, attr, .. } if attr == "format" => {
+                    let Some(template) = string_literal(object) else {
+                        return Ok(None);
+                    };
+                    self.lower_str_format(node, template, args, kwargs)?
+                }
+                _ => return Ok(None),
+            },
+            PythonHIR::BinOp {
+                op: PythonBinOp::Mod,
+                left,
+                right,
+                ..
+            } => {
+                let Some(template) = string_literal(left) else {
+                    return Ok(None);
+                };
+                self.lower_percent_format(node, template, right)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(UnifiedHIR::Format {
+            id: self.next_node_id(),
+            template,
+            args,
+            source_language: Language::Python,
+            meta: node.metadata().clone(),
+        }))
+    }
+
+    /// Lower the parts of an f-string
+    fn lower_f_string(&mut self, parts: &[FormatPart]) -> Result<(String, Vec<UnifiedHIR>)> {
+        let mut template = String::new();
+        let mut args = Vec::new();
+        for part in parts {
+            let (value, conversion, spec) = match part {
+                FormatPart::Text(text) => {
+                    template.push_str(&escape_braces(text));
+                    continue;
+                }
+                FormatPart::Field {
+                    value,
+                    conversion,
+                    spec,
+                } => (value.as_ref(), *conversion, spec.as_deref()),
+            };
+            let (python_spec, located) = match spec {
+                None => (String::new(), value),
+                Some(spec) => (literal_spec(spec)?, spec),
+            };
+            let rust_spec =
+                field_spec(&python_spec, conversion, &expr_type(value)).map_err(|reason| {
+                    let conversion = conversion.map_or_else(String::new, |c| format!("!{c}"));
+                    unsupported_in(
+                        &format!("f-string format spec `{conversion}:{python_spec}` ({reason})"),
+                        located,
+                    )
+                })?;
+            template.push('{');
+            template.push_str(&rust_spec);
+            template.push('}');
+            args.push(self.lower_expr(value)?);
+        }
+        Ok((template, args))
+    }
+
+    /// Lower `template.format(args, name=value)`
+    ///
+    /// Each argument is passed once, in the order the template first uses
+    /// it; fields use explicit positions when that order differs from the
+    /// order of the fields.
+    fn lower_str_format(
+        &mut self,
+        node: &PythonHIR,
+        template: &str,
+        args: &[PythonHIR],
+        kwargs: &[(Option<String>, PythonHIR)],
+    ) -> Result<(String, Vec<UnifiedHIR>)> {
+        let unsupported = |field: &str, reason: String| {
+            unsupported_in(
+                &format!("`str.format` field `{{{field}}}` ({reason})"),
+                node,
+            )
+        };
+
+        let mut used: Vec<(FieldKey, &PythonHIR)> = Vec::new();
+        let mut pieces = Vec::new();
+        let (mut automatic, mut manual) = (0, false);
+        for piece in split_template(template)
+            .map_err(|reason| unsupported_in(&format!("`str.format` template ({reason})"), node))?
+        {
+            let field = match piece {
+                Ok(text) => {
+                    pieces.push(Piece::Text(text));
+                    continue;
+                }
+                Err(field) => field,
+            };
+            let (name, conversion, python_spec) =
+                split_field(&field).map_err(|reason| unsupported(&field, reason))?;
+
+            let key = if name.is_empty() {
+                automatic += 1;
+                FieldKey::Index(automatic - 1)
+            } else if let Ok(index) = name.parse() {
+                manual = true;
+                FieldKey::Index(index)
+            } else if name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                FieldKey::Name(name.to_owned())
+            } else {
+                return Err(unsupported(&field, "attribute or index lookup".to_owned()));
+            };
+            let value = match &key {
+                FieldKey::Index(index) => args.get(*index),
+                FieldKey::Name(name) => kwargs
+                    .iter()
+                    .find(|(keyword, _)| keyword.as_deref() == Some(name))
+                    .map(|(_, value)| value),
+            };
+            let Some(value) = value else {
+                return Err(unsupported(&field, "no matching argument".to_owned()));
+            };
+            if matches!(value, PythonHIR::Starred { .. }) {
+                return Err(unsupported(&field, "`*` argument splat".to_owned()));
+            }
+
+            let rust_spec = field_spec(python_spec, conversion, &expr_type(value))
+                .map_err(|reason| unsupported(&field, reason))?;
+            if automatic > 0 && manual {
+                return Err(unsupported(
+                    &field,
+                    "automatic and manual field numbering mixed".to_owned(),
+                ));
+            }
+            let arg = used
+                .iter()
+                .position(|(used, _)| *used == key)
+                .unwrap_or_else(|| {
+                    used.push((key, value));
+                    used.len() - 1
+                });
+            pieces.push(Piece::Field {
+                arg,
+                spec: rust_spec,
+            });
+        }
+        if let Some((None, splat)) = kwargs.iter().find(|(keyword, _)| keyword.is_none()) {
+            return Err(unsupported_in(
+                "`str.format` with a `**` keyword splat",
+                splat,
+            ));
+        }
+
+        let implicit = pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Field { arg, .. } => Some(*arg),
+                Piece::Text(_) => None,
+            })
+            .eq(0..used.len());
+        let template = pieces
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => text,
+                Piece::Field { spec, .. } if implicit => format!("{{{spec}}}"),
+                Piece::Field { arg, spec } => format!("{{{arg}{spec}}}"),
+            })
+            .collect();
+        let args = used
+            .into_iter()
+            .map(|(_, value)| self.lower_expr(value))
+            .collect::<Result<_>>()?;
+        Ok((template, args))
+    }
+
+    /// Lower `template % values`
+    fn lower_percent_format(
+        &mut self,
+        node: &PythonHIR,
+        template: &str,
+        values: &PythonHIR,
+    ) -> Result<(String, Vec<UnifiedHIR>)> {
+        let values = match values {
+            PythonHIR::Tuple { elements, .. } => elements.iter().collect(),
+            PythonHIR::Dict { .. } => {
+                return Err(unsupported_in("`%` formatting with a mapping", values))
+            }
+            value => vec![value],
+        };
+
+        let mut output = String::new();
+        let mut rest = template;
+        let mut count = 0;
+        while let Some(start) = rest.find('%') {
+            output.push_str(&escape_braces(&rest[..start]));
+            rest = &rest[start + 1..];
+            if let Some(after) = rest.strip_prefix('%') {
+                output.push('%');
+                rest = after;
+                continue;
+            }
+            let ty = values
+                .get(count)
+                .map_or(Type::Unknown, |value| expr_type(value));
+            let (spec, length) = percent_spec(rest, &ty).map_err(|(length, reason)| {
+                let conversion = &rest[..length.min(rest.len())];
+                unsupported_in(&format!("`%` conversion `%{conversion}` ({reason})"), node)
+            })?;
+            output.push('{');
+            output.push_str(&spec);
+            output.push('}');
+            rest = &rest[length..];
+            count += 1;
+        }
+        output.push_str(&escape_braces(rest));
+
+        if count != values.len() {
+            return Err(unsupported_in(
+                &format!(
+                    "`%` format with {count} conversions for {} values",
+                    values.len()
+                ),
+                node,
+            ));
+        }
+        let args = values
+            .into_iter()
+            .map(|value| self.lower_expr(value))
+            .collect::<Result<_>>()?;
+        Ok((output, args))
+    }
+}
+
+/// The text of a string literal
+fn string_literal(node: &PythonHIR) -> Option<&str> {
+    match node {
+        PythonHIR::Literal {
+            value: PythonLiteral::Str(text),
+            ..
+        } => Some(text),
+        _ => None,
+    }
+}
+
+/// The text of an f-string format spec without replacement fields
+fn literal_spec(spec: &PythonHIR) -> Result<String> {
+    let PythonHIR::FormattedString { parts, .. } = spec else {
+        return Err(unsupported_in("f-string format spec", spec));
+    };
+    parts
+        .iter()
+        .map(|part| match part {
+            FormatPart::Text(text) => Ok(text.as_str()),
+            FormatPart::Field { .. } => Err(unsupported_in(
+                "f-string format spec with a replacement field",
+                spec,
+            )),
+        })
+        .collect()
+}
+
+/// Escape braces for a Rust format string
+fn escape_braces(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+/// Split a `str.format` template into literal text (`Ok`, already escaped
+/// for Rust) and the contents of replacement fields (`Err`)
+fn split_template(template: &str) -> Result<Vec<Result<String, String>>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+                text.push(c);
+                text.push(c);
+            }
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') => return Err("nested replacement field".to_owned()),
+                        Some(c) => field.push(c),
+                        None => return Err("unclosed `{`".to_owned()),
+                    }
+                }
+                pieces.push(Ok(std::mem::take(&mut text)));
+                pieces.push(Err(field));
+            }
+            '}' => return Err("single `}`".to_owned()),
+            c => text.push(c),
+        }
+    }
+    pieces.push(Ok(text));
+    pieces.retain(|piece| piece.as_ref().map_or(true, |text| !text.is_empty()));
+    Ok(pieces)
+}
+
+/// Split a replacement field into its name, conversion and spec
+fn split_field(field: &str) -> Result<(&str, Option<char>, &str), String> {
+    let (head, spec) = field.split_once(':').unwrap_or((field, ""));
+    let Some((name, conversion)) = head.split_once('!') else {
+        return Ok((head, None, spec));
+    };
+    let mut chars = conversion.chars();
+    match (chars.next(), chars.next()) {
+        (Some(conversion), None) => Ok((name, Some(conversion), spec)),
+        _ => Err(format!("invalid conversion `!{conversion}`")),
+    }
+}
+
+/// Translate a replacement field's conversion and Python format spec into
+/// the Rust spec, including the leading `:` when there is one
+fn field_spec(spec: &str, conversion: Option<char>, ty: &Type) -> Result<String, String> {
+    let mut rust = format_spec(spec, ty)?;
+    match conversion {
+        None | Some('s') => {}
+        Some('r') => rust.push('?'),
+        Some(other) => return Err(format!("`!{other}` conversion")),
+    }
+    Ok(if rust.is_empty() {
+        rust
+    } else {
+        format!(":{rust}")
+    })
+}
+
+/// Translate a Python format spec into the Rust one
+///
+/// Python: `[[fill]align][sign][z][#][0][width][grouping][.precision][type]`
+/// Rust: `[[fill]align][sign][#][0][width][.precision][type]`
+fn format_spec(spec: &str, ty: &Type) -> Result<String, String> {
+    let chars: Vec<char> = spec.chars().collect();
+    let mut rest = chars.as_slice();
+    let mut translated = String::new();
+
+    let is_align = |c: &char| matches!(c, '<' | '>' | '^' | '=');
+    let align = match rest {
+        [fill, align, ..] if is_align(align) => {
+            if matches!(fill, '{' | '}') {
+                return Err(format!("`{fill}` as fill character"));
+            }
+            translated.push(*fill);
+            rest = &rest[2..];
+            Some(*align)
+        }
+        [align, ..] if is_align(align) => {
+            rest = &rest[1..];
+            Some(*align)
+        }
+        _ => None,
+    };
+    match align {
+        Some('=') => return Err("`=` alignment".to_owned()),
+        Some(align) => translated.push(align),
+        None => {}
+    }
+
+    match rest.first() {
+        Some('+') => translated.push('+'),
+        Some(' ') => return Err("space sign".to_owned()),
+        _ => {}
+    }
+    if matches!(rest.first(), Some('+' | '-')) {
+        rest = &rest[1..];
+    }
+    if rest.first() == Some(&'z') {
+        return Err("`z` negative zero coercion".to_owned());
+    }
+    let alternate = rest.first() == Some(&'#');
+    if alternate {
+        rest = &rest[1..];
+    }
+    let zero = rest.first() == Some(&'0');
+    if zero {
+        rest = &rest[1..];
+    }
+    let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+    let width: String = rest[..digits].iter().collect();
+    rest = &rest[digits..];
+    if let Some(grouping @ (',' | '_')) = rest.first() {
+        return Err(format!("digit grouping with `{grouping}`"));
+    }
+    let precision = match rest {
+        ['.', tail @ ..] => {
+            let digits = tail.iter().take_while(|c| c.is_ascii_digit()).count();
+            if digits == 0 {
+                return Err("missing precision".to_owned());
+            }
+            rest = &tail[digits..];
+            Some(tail[..digits].iter().collect::<String>())
+        }
+        _ => None,
+    };
+    let presentation = match rest {
+        [] => None,
+        [presentation] => Some(*presentation),
+        _ => return Err("invalid format spec".to_owned()),
+    };
+
+    let (precision, presentation) = presentation_type(presentation, precision, ty)?;
+    if alternate {
+        if presentation.is_empty() {
+            return Err("`#` alternate form without a `b`, `o` or `x` type".to_owned());
+        }
+        translated.push('#');
+    }
+    if zero {
+        if align.is_some() {
+            return Err("`0` padding with an explicit alignment".to_owned());
+        }
+        translated.push('0');
+    }
+    translated.push_str(&width);
+    if let Some(precision) = precision {
+        translated.push('.');
+        translated.push_str(&precision);
+    }
+    translated.push_str(presentation);
+    Ok(translated)
+}
+
+/// Translate a Python presentation type into the Rust one, filling in the
+/// precision Python defaults to
+fn presentation_type(
+    presentation: Option<char>,
+    precision: Option<String>,
+    ty: &Type,
+) -> Result<(Option<String>, &'static str), String> {
+    let is_str = matches!(ty, Type::Python(PythonType::Str));
+    Ok(match presentation {
+        // Without a type Python formats floats with `g`, which Rust lacks
+        None if precision.is_some() && !is_str => {
+            return Err(
+                "precision without a presentation type on a value not known to be a `str`"
+                    .to_owned(),
+            )
+        }
+        None | Some('s') => (precision, ""),
+        Some('d') if precision.is_none() => (None, ""),
+        // Python defaults to six digits after the point
+        Some('f' | 'F') => (precision.or_else(|| Some("6".to_owned())), ""),
+        Some(radix @ ('x' | 'X' | 'o' | 'b')) if precision.is_none() => (
+            None,
+            match radix {
+                'x' => "x",
+                'X' => "X",
+                'o' => "o",
+                _ => "b",
+            },
+        ),
+        Some(other @ ('d' | 'x' | 'X' | 'o' | 'b')) => {
+            return Err(format!("precision with the `{other}` presentation type"))
+        }
+        Some(other) => return Err(format!("`{other}` presentation type")),
+    })
+}
+
+/// Translate the `%` conversion starting `rest` (just after the `%`) into
+/// the Rust spec
+///
+/// Returns the spec, including the leading `:` when there is one, and the
+/// length of the conversion; errors carry the length read so far.
+fn percent_spec(rest: &str, ty: &Type) -> Result<(String, usize), (usize, String)> {
+    let bytes = rest.as_bytes();
+    let mut at = 0;
+    if bytes.first() == Some(&b'(') {
+        return Err((1, "mapping key".to_owned()));
+    }
+    let (mut left, mut sign, mut alternate, mut zero) = (false, false, false, false);
+    while let Some(flag) = bytes.get(at) {
+        match flag {
+            b'-' => left = true,
+            b'+' => sign = true,
+            b'#' => alternate = true,
+            b'0' => zero = true,
+            b' ' => return Err((at + 1, "space flag".to_owned())),
+            _ => break,
+        }
+        at += 1;
+    }
+    let digits = |from: usize| rest[from..].bytes().take_while(u8::is_ascii_digit).count();
+    if bytes.get(at) == Some(&b'*') {
+        return Err((at + 1, "`*` width".to_owned()));
+    }
+    let width = &rest[at..at + digits(at)];
+    at += width.len();
+    let mut precision = None;
+    if bytes.get(at) == Some(&b'.') {
+        at += 1;
+        if bytes.get(at) == Some(&b'*') {
+            return Err((at + 1, "`*` precision".to_owned()));
+        }
+        let length = digits(at);
+        // `%.f` means a precision of zero
+        precision = Some(if length == 0 {
+            "0"
+        } else {
+            &rest[at..at + length]
+        });
+        at += length;
+    }
+    while matches!(bytes.get(at), Some(b'h' | b'l' | b'L')) {
+        at += 1;
+    }
+    let Some(conversion) = rest[at..].chars().next() else {
+        return Err((at, "incomplete conversion".to_owned()));
+    };
+    at += conversion.len_utf8();
+
+    let (precision, presentation) = match conversion {
+        's' | 'r' => (precision, if conversion == 'r' { "?" } else { "" }),
+        'd' | 'i' | 'u' if precision.is_none() => (None, ""),
+        'f' | 'F' => (precision.or(Some("6")), ""),
+        'x' => (None, "x"),
+        'X' => (None, "X"),
+        'o' => (None, "o"),
+        'd' | 'i' | 'u' => return Err((at, "precision on an integer".to_owned())),
+        other => return Err((at, format!("`%{other}` conversion type"))),
+    };
+    if matches!(conversion, 'x' | 'X' | 'o') && precision.is_some() {
+        return Err((at, "precision on an integer".to_owned()));
+    }
+    let numeric = !matches!(conversion, 's' | 'r');
+    if conversion == 's' && !matches!(ty, Type::Python(PythonType::Str)) && precision.is_some() {
+        return Err((
+            at,
+            "precision on a value not known to be a `str`".to_owned(),
+        ));
+    }
+
+    let mut translated = String::new();
+    if !width.is_empty() {
+        if left {
+            translated.push('<');
+        } else if !(zero && numeric) {
+            // `%` right-aligns strings too
+            translated.push('>');
+        }
+    }
+    if sign {
+        translated.push('+');
+    }
+    if alternate {
+        if !matches!(conversion, 'x' | 'X' | 'o') {
+            return Err((at, "`#` flag without an `o` or `x` conversion".to_owned()));
+        }
+        translated.push('#');
+    }
+    if zero && numeric && !left && !width.is_empty() {
+        translated.push('0');
+    }
+    translated.push_str(width);
+    if let Some(precision) = precision {
+        translated.push('.');
+        translated.push_str(precision);
+    }
+    translated.push_str(presentation);
+    Ok((
+        if translated.is_empty() {
+            translated
+        } else {
+            format!(":{translated}")
+        },
+        at,
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+
+    fn spec(spec: &str) -> Result<String, String> {
+        format_spec(spec, &Type::Python(PythonType::Float))
+    }
+
+    #[test]
+    fn test_format_spec_translation() {
+        assert_eq!(spec(">8.2f").as_deref(), Ok(">8.2"));
+        assert_eq!(spec("*^10").as_deref(), Ok("*^10"));
+        assert_eq!(spec("+08.3f").as_deref(), Ok("+08.3"));
+        assert_eq!(spec("f").as_deref(), Ok(".6"));
+        assert_eq!(spec("#x").as_deref(), Ok("#x"));
+        assert_eq!(
+            format_spec(".3", &Type::Python(PythonType::Str)).as_deref(),
+            Ok(".3")
+        );
+    }
+
+    #[test]
+    fn test_unsupported_format_specs_name_the_problem() {
+        assert_eq!(spec(",.2f"), Err("digit grouping with `,`".to_owned()));
+        assert_eq!(spec(".2e"), Err("`e` presentation type".to_owned()));
+        assert_eq!(spec("=10"), Err("`=` alignment".to_owned()));
+        assert!(spec(".2").is_err_and(|reason| reason.contains("precision")));
+    }
+
+    #[test]
+    fn test_percent_spec_translation() {
+        let unknown = Type::Unknown;
+        assert_eq!(percent_spec("5d", &unknown), Ok((":>5".to_owned(), 2)));
+        assert_eq!(percent_spec("-5s|", &unknown), Ok((":<5".to_owned(), 3)));
+        assert_eq!(percent_spec("05.1f", &unknown), Ok((":05.1".to_owned(), 5)));
+        assert_eq!(percent_spec("r", &unknown), Ok((":?".to_owned(), 1)));
+        assert_eq!(percent_spec("s", &unknown), Ok((String::new(), 1)));
+        assert_eq!(
+            percent_spec("(name)s", &unknown),
+            Err((1, "mapping key".to_owned()))
+        );
+        assert_eq!(
+            percent_spec("g", &unknown),
+            Err((1, "`%g` conversion type".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_split_template() {
+        assert_eq!(
+            split_template("{{x}} {0!r:>4} {name}"),
+            Ok(vec![
+                Ok("{{x}} ".to_owned()),
+                Err("0!r:>4".to_owned()),
+                Ok(" ".to_owned()),
+                Err("name".to_owned()),
+            ])
+        );
+        assert_eq!(
+            split_template("{:{w}}"),
+            Err("nested replacement field".to_owned())
+        );
+        assert_eq!(split_field("0!r:>4"), Ok(("0", Some('r'), ">4")));
+    }
+}
@@ -0,0 +1,504 @@
+//! Generator functions
+//!
+//! A Python function containing `yield` is lowered into a
+//! [`UnifiedHIR::Generator`], which codegen turns into an
+//! `impl Iterator` built with `std::iter::from_fn`.
+//!
+//! The body is split into:
+//!
+//! - the setup: the statements before the first `yield` or `return`, run
+//!   when the generator is created
+//! - stages, run in order as items are requested: a top-level `for` or
+//!   `while` loop containing `yield` resumes one iteration at a time,
+//!   `yield from` hands out one item at a time, and other statements run
+//!   in one go
+//!
+//! Items yielded while a stage runs are queued, so side effects can run
+//! ahead of the consumer by up to one loop iteration. Loop iterables are
+//! evaluated during setup and may only use parameters and setup locals,
+//! which live across stages; locals assigned by a stage are private to it.
+
+use crate::{
+    lowering::{expr_type, is_known, unsupported_in, walk},
+    metadata::Metadata,
+    python::PythonHIR,
+    types::Type,
+    unified::{GeneratorStage, LiteralValue, UnifiedHIR, UnifiedParameter, Unifier},
+    unpacking::{item_type, lower_assign_target, lower_loop_target},
+    Language,
+};
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// Where generator lowering currently is
+#[derive(Debug, Default)]
+pub(crate) struct GeneratorContext {
+    /// Index of the stage being lowered
+    stage: usize,
+    /// Number of stages; advancing to it finishes the generator
+    finish: usize,
+    /// Loops entered inside the stage
+    pub(crate) loop_depth: usize,
+    /// Parameters and setup locals, which live across stages
+    state: HashSet<String>,
+}
+
+/// Top-level statements of a generator after its setup, grouped into stages
+enum RawStage<'a> {
+    /// Statements run in one go
+    Run(&'a [PythonHIR]),
+    /// `for` or `while` loop containing `yield`
+    Loop(&'a PythonHIR),
+    /// Iterable of a `yield from` statement
+    Delegate(&'a PythonHIR),
+}
+
+impl Unifier {
+    /// Lower the body of a generator function
+    ///
+    /// `item_type` comes from an `Iterator[T]` annotation; without one it
+    /// is taken from the first yielded value.
+    pub(crate) fn lower_generator(
+        &mut self,
+        params: &[UnifiedParameter],
+        body: &[PythonHIR],
+        item_type: Type,
+        meta: &Metadata,
+    ) -> Result<UnifiedHIR> {
+        let split = body
+            .iter()
+            .position(|statement| yields(statement) || returns(statement))
+            .unwrap_or(body.len());
+        let (prefix, rest) = body.split_at(split);
+
+        let mut state: HashSet<String> = params.iter().map(|param| param.name.clone()).collect();
+        // Methods also read their receiver
+        state.insert("self".to_owned());
+        state.extend(
+            prefix
+                .iter()
+                .flat_map(|statement| match statement {
+                    PythonHIR::Assign { target, .. } => target.names(),
+                    _ => vec![],
+                })
+                .map(str::to_owned),
+        );
+        let setup = self.lower_body(prefix)?;
+
+        let raw = split_stages(rest);
+        check_stages(&raw, &state)?;
+        let mutable = assigned_state(rest, &state);
+
+        let enclosing = self.generator.replace(GeneratorContext {
+            stage: 0,
+            finish: raw.len(),
+            loop_depth: 0,
+            state,
+        });
+        let stages = raw
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| {
+                if let Some(context) = &mut self.generator {
+                    context.stage = index;
+                }
+                self.lower_stage(stage)
+            })
+            .collect::<Result<Vec<_>>>();
+        self.generator = enclosing;
+
+        let item_type = match item_type {
+            Type::Unknown => first_yield_type(rest),
+            known => known,
+        };
+        Ok(UnifiedHIR::Generator {
+            id: self.next_node_id(),
+            setup,
+            mutable,
+            stages: stages?,
+            item_type,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower one stage
+    fn lower_stage(&mut self, stage: &RawStage<'_>) -> Result<GeneratorStage> {
+        Ok(match stage {
+            RawStage::Run(body) => GeneratorStage::Run(self.lower_body(body)?),
+            RawStage::Delegate(iter) => GeneratorStage::Delegate(self.lower_expr(iter)?),
+            RawStage::Loop(
+                node @ (PythonHIR::For { orelse, .. } | PythonHIR::While { orelse, .. }),
+            ) if !orelse.is_empty() => {
+                return Err(unsupported_in("`else` on a loop containing `yield`", node));
+            }
+            RawStage::Loop(
+                node @ PythonHIR::For {
+                    target, iter, body, ..
+                },
+            ) => GeneratorStage::For {
+                target: lower_loop_target(target, iter, node)?,
+                iter: self.lower_expr(iter)?,
+                body: self.lower_body(body)?,
+            },
+            RawStage::Loop(PythonHIR::While {
+                condition, body, ..
+            }) => GeneratorStage::While {
+                condition: self.lower_expr(condition)?,
+                body: self.lower_body(body)?,
+            },
+            RawStage::Loop(other) => return Err(unsupported_in("generator stage", other)),
+        })
+    }
+
+    /// Lower a statement whose meaning changes inside a generator stage
+    ///
+    /// Returns `None` for statements lowered as usual.
+    pub(crate) fn lower_generator_statement(
+        &mut self,
+        node: &PythonHIR,
+    ) -> Result<Option<UnifiedHIR>> {
+        let Some(context) = &self.generator else {
+            return Ok(None);
+        };
+        let at_stage_level = context.loop_depth == 0;
+        let (stage, finish) = (context.stage, context.finish);
+
+        Ok(Some(match node {
+            PythonHIR::Yield { value, meta, .. } => {
+                let value = match value {
+                    Some(value) => self.lower_value(value)?,
+                    // A bare `yield` produces `None`, Rust's `()`
+                    None => UnifiedHIR::Literal {
+                        id: self.next_node_id(),
+                        value: LiteralValue::Unit,
+                        lit_type: Type::Unknown,
+                        meta: meta.clone(),
+                    },
+                };
+                self.yield_node(value, false, meta)
+            }
+            PythonHIR::YieldFrom { value, meta, .. } => {
+                let value = self.lower_expr(value)?;
+                self.yield_node(value, true, meta)
+            }
+            PythonHIR::Return { value: Some(_), .. } => {
+                return Err(unsupported_in("`return` with a value in a generator", node));
+            }
+            PythonHIR::Return { meta, .. } => self.advance(finish, meta),
+            PythonHIR::Break { meta, .. } if at_stage_level => self.advance(stage + 1, meta),
+            PythonHIR::Continue { meta, .. } if at_stage_level => self.advance(stage, meta),
+            // Setup locals live across stages, so stages assign to them
+            PythonHIR::Assign {
+                target,
+                value,
+                type_annotation,
+                meta,
+                ..
+            } if target
+                .names()
+                .iter()
+                .any(|name| context.state.contains(*name)) =>
+            {
+                if !target
+                    .names()
+                    .iter()
+                    .all(|name| context.state.contains(*name))
+                {
+                    return Err(unsupported_in(
+                        "unpacking into both new locals and locals shared across `yield`",
+                        node,
+                    ));
+                }
+                let target = lower_assign_target(target, value, type_annotation.as_ref(), node)?;
+                let value = self.lower_value(value)?;
+                self.reassign(&target, value, meta)?
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    /// `yield value`, or `yield from value` when `delegate` is set
+    fn yield_node(&mut self, value: UnifiedHIR, delegate: bool, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::Yield {
+            id: self.next_node_id(),
+            value: Box::new(value),
+            delegate,
+            meta: meta.clone(),
+        }
+    }
+
+    /// Move the generator to `stage`
+    fn advance(&mut self, stage: usize, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::Advance {
+            id: self.next_node_id(),
+            stage,
+            meta: meta.clone(),
+        }
+    }
+}
+
+/// Whether a function body makes the function a generator
+pub(crate) fn is_generator(body: &[PythonHIR]) -> bool {
+    body.iter().any(yields)
+}
+
+/// Whether a statement contains `yield` or `yield from`
+fn yields(statement: &PythonHIR) -> bool {
+    let mut found = false;
+    walk(statement, &mut |node| {
+        found |= matches!(node, PythonHIR::Yield { .. } | PythonHIR::YieldFrom { .. });
+    });
+    found
+}
+
+/// Whether a statement contains `return`
+fn returns(statement: &PythonHIR) -> bool {
+    let mut found = false;
+    walk(statement, &mut |node| {
+        found |= matches!(node, PythonHIR::Return { .. });
+    });
+    found
+}
+
+/// Group the statements after the setup into stages
+fn split_stages(body: &[PythonHIR]) -> Vec<RawStage<'_>> {
+    let mut stages = Vec::new();
+    let mut run_start = 0;
+    for (index, statement) in body.iter().enumerate() {
+        let stage = match statement {
+            PythonHIR::For { .. } | PythonHIR::While { .. } if yields(statement) => {
+                RawStage::Loop(statement)
+            }
+            PythonHIR::YieldFrom { value, .. } => RawStage::Delegate(value),
+            _ => continue,
+        };
+        if run_start < index {
+            stages.push(RawStage::Run(&body[run_start..index]));
+        }
+        stages.push(stage);
+        run_start = index + 1;
+    }
+    if run_start < body.len() {
+        stages.push(RawStage::Run(&body[run_start..]));
+    }
+    stages
+}
+
+/// Report stages needing a value that is not available where they run
+fn check_stages(stages: &[RawStage<'_>], state: &HashSet<String>) -> Result<()> {
+    for stage in stages {
+        let iter = match stage {
+            RawStage::Loop(PythonHIR::For { iter, .. }) => Some(iter.as_ref()),
+            RawStage::Delegate(iter) => Some(*iter),
+            _ => None,
+        };
+        if let Some(iter) = iter {
+            let mut names = Vec::new();
+            free_variables(iter, &mut names);
+            if let Some(name) = names.iter().find(|name| !state.contains(name.as_str())) {
+                return Err(unsupported_in(
+                    &format!("iterable using `{name}`, assigned after the first `yield`,"),
+                    iter,
+                ));
+            }
+        }
+    }
+
+    for (index, stage) in stages.iter().enumerate() {
+        let RawStage::Run(body) = stage else {
+            continue;
+        };
+        let locals: HashSet<&str> = body
+            .iter()
+            .flat_map(|statement| match statement {
+                PythonHIR::Assign { target, .. } => target.names(),
+                _ => vec![],
+            })
+            .filter(|name| !state.contains(*name))
+            .collect();
+        for later in &stages[index + 1..] {
+            let statements: &[PythonHIR] = match later {
+                RawStage::Run(body) => body,
+                RawStage::Loop(node) | RawStage::Delegate(node) => std::slice::from_ref(*node),
+            };
+            let mut shared = None;
+            for statement in statements {
+                walk(statement, &mut |node| {
+                    if let PythonHIR::Variable { name, .. } = node {
+                        if shared.is_none() && locals.contains(name.as_str()) {
+                            shared = Some(node);
+                        }
+                    }
+                });
+            }
+            if let Some(node) = shared {
+                return Err(unsupported_in("local shared across `yield`", node));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Variables read by an expression, leaving out called function names
+pub(crate) fn free_variables(node: &PythonHIR, names: &mut Vec<String>) {
+    match node {
+        PythonHIR::Variable { name, .. } => names.push(name.clone()),
+        PythonHIR::Call {
+            callee,
+            args,
+            kwargs,
+            ..
+        } => {
+            if !matches!(callee.as_ref(), PythonHIR::Variable { .. }) {
+                free_variables(callee, names);
+            }
+            for arg in args.iter().chain(kwargs.iter().map(|(_, value)| value)) {
+                free_variables(arg, names);
+            }
+        }
+        _ => {
+            for child in node.children() {
+                free_variables(child, names);
+            }
+        }
+    }
+}
+
+/// Parameters and setup locals assigned by the stages, in first-assignment order
+fn assigned_state(body: &[PythonHIR], state: &HashSet<String>) -> Vec<String> {
+    let mut assigned = Vec::new();
+    for statement in body {
+        walk(statement, &mut |node| {
+            let names = match node {
+                PythonHIR::Assign { target, .. } => target.names(),
+                PythonHIR::AugAssign { target, .. } => match target.as_ref() {
+                    PythonHIR::Variable { name, .. } => vec![name.as_str()],
+                    _ => return,
+                },
+                _ => return,
+            };
+            for name in names {
+                if state.contains(name) && !assigned.iter().any(|known| known == name) {
+                    assigned.push(name.to_owned());
+                }
+            }
+        });
+    }
+    assigned
+}
+
+/// Type of the first value yielded whose type is fully known, or else
+/// of the first value yielded
+///
+/// `yield from xs` yields the items of `xs`.
+pub(crate) fn first_yield_type(body: &[PythonHIR]) -> Type {
+    let mut yielded = Type::Unknown;
+    for statement in body {
+        walk(statement, &mut |node| {
+            if is_known(&yielded) {
+                return;
+            }
+            let ty = match node {
+                PythonHIR::Yield {
+                    value: Some(value), ..
+                } => expr_type(value),
+                PythonHIR::YieldFrom { value, .. } => item_type(&expr_type(value)),
+                _ => return,
+            };
+            if is_known(&ty) || yielded == Type::Unknown {
+                yielded = ty;
+            }
+        });
+    }
+    yielded
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{
+        python::{Literal, Target},
+        NodeId,
+    };
+
+    fn var(name: &str) -> PythonHIR {
+        PythonHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn yield_value(value: PythonHIR) -> PythonHIR {
+        PythonHIR::Yield {
+            id: NodeId::new(0),
+            value: Some(Box::new(value)),
+            meta: Metadata::new(),
+        }
+    }
+
+    fn for_loop(target: &str, iter: PythonHIR, body: Vec<PythonHIR>) -> PythonHIR {
+        PythonHIR::For {
+            id: NodeId::new(0),
+            target: Target::name(target),
+            iter: Box::new(iter),
+            body,
+            orelse: vec![],
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_split_stages() {
+        // yield 0; for x in xs: yield x; done(); yield from ys
+        let body = vec![
+            yield_value(PythonHIR::Literal {
+                id: NodeId::new(0),
+                value: Literal::Int(0),
+                meta: Metadata::new(),
+            }),
+            for_loop("x", var("xs"), vec![yield_value(var("x"))]),
+            var("done"),
+            PythonHIR::YieldFrom {
+                id: NodeId::new(0),
+                value: Box::new(var("ys")),
+                meta: Metadata::new(),
+            },
+        ];
+
+        let kinds: Vec<&str> = split_stages(&body)
+            .iter()
+            .map(|stage| match stage {
+                RawStage::Run(_) => "run",
+                RawStage::Loop(_) => "loop",
+                RawStage::Delegate(_) => "delegate",
+            })
+            .collect();
+        assert_eq!(kinds, vec!["run", "loop", "run", "delegate"]);
+        assert_eq!(first_yield_type(&body).to_string(), "int");
+    }
+
+    #[test]
+    fn test_iterable_assigned_after_yield_is_reported() {
+        // yield 0; rows = load(); for row in rows: yield row
+        let body = vec![
+            yield_value(var("header")),
+            PythonHIR::Assign {
+                id: NodeId::new(0),
+                target: Target::name("rows"),
+                value: Box::new(var("load")),
+                type_annotation: None,
+                meta: Metadata::new(),
+            },
+            for_loop("row", var("rows"), vec![yield_value(var("row"))]),
+        ];
+        let state = HashSet::from(["header".to_owned()]);
+
+        let error = check_stages(&split_stages(&body), &state)
+            .expect_err("rows is only assigned by a stage");
+        assert!(error.to_string().contains("`rows`"), "{error}");
+    }
+}
@@ -0,0 +1,566 @@
+//! Imports between the modules of a Python project
+//!
+//! A project is a tree of modules named by their dotted path. The
+//! [`SymbolTable`] records the names each module binds, so imports can be
+//! checked against it, and lowering turns imports of project modules into
+//! `use` declarations:
+//!
+//! ```text
+//! from .models import User     →  use crate::app::models::User;
+//! from app import util as u    →  use crate::app::util as u;
+//! import app.util              →  use crate::app;
+//! util.slugify(name)           →  util::slugify(name)
+//! ```
+//!
+//! - relative imports are resolved against the importing module's package
+//! - imports of the standard library produce no `use`; imports of other
+//!   modules outside the project are reported as unresolved
+//! - a module bound by an import is referred to with a path, not a field
+
+use crate::{
+    diagnostics::Diagnostic,
+    lowering::{unsupported, unsupported_in},
+    metadata::Metadata,
+    python::{ImportName, PythonHIR, Target},
+    types::Type,
+    unified::{UnifiedHIR, Unifier},
+    Language,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Standard library modules imports of which are not reported
+///
+/// The ones lowering understands (`typing`, `dataclasses`, `enum`, ...)
+/// only name things that have a Rust counterpart.
+const STDLIB_MODULES: &[&str] = &[
+    "__future__",
+    "abc",
+    "argparse",
+    "array",
+    "asyncio",
+    "base64",
+    "bisect",
+    "collections",
+    "contextlib",
+    "copy",
+    "csv",
+    "dataclasses",
+    "datetime",
+    "decimal",
+    "enum",
+    "fractions",
+    "functools",
+    "hashlib",
+    "heapq",
+    "io",
+    "itertools",
+    "json",
+    "logging",
+    "math",
+    "operator",
+    "os",
+    "pathlib",
+    "random",
+    "re",
+    "shutil",
+    "statistics",
+    "string",
+    "struct",
+    "subprocess",
+    "sys",
+    "tempfile",
+    "threading",
+    "time",
+    "types",
+    "typing",
+    "unittest",
+    "uuid",
+];
+
+/// What a name bound at the top level of a module refers to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Symbol {
+    /// Function defined by the module
+    Function,
+    /// Class defined by the module
+    Class,
+    /// Variable assigned by the module
+    Variable,
+    /// Module or name imported from another project module, by its
+    /// dotted path (`app.models.User`)
+    Import(String),
+}
+
+/// Names bound at the top level of a module
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleSymbols {
+    /// Whether the module is a package (`__init__.py`)
+    pub is_package: bool,
+    /// Bound names
+    pub symbols: BTreeMap<String, Symbol>,
+}
+
+/// Modules of a project and the names they bind
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolTable {
+    /// Modules by dotted name
+    modules: BTreeMap<String, ModuleSymbols>,
+}
+
+impl SymbolTable {
+    /// Create an empty symbol table
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            modules: BTreeMap::new(),
+        }
+    }
+
+    /// Add a module with the functions, classes and variables it defines
+    ///
+    /// Imports are bound by [`SymbolTable::resolve_imports`] once every
+    /// module is known.
+    pub fn define_module(&mut self, name: &str, is_package: bool, body: &[PythonHIR]) {
+        let mut symbols = BTreeMap::new();
+        for node in body {
+            match node {
+                PythonHIR::Function { name, .. } => {
+                    symbols.insert(name.clone(), Symbol::Function);
+                }
+                PythonHIR::Class { name, .. } => {
+                    symbols.insert(name.clone(), Symbol::Class);
+                }
+                PythonHIR::Assign {
+                    target: Target::Name(name),
+                    ..
+                }
+                | PythonHIR::Declaration { name, .. } => {
+                    symbols.insert(name.clone(), Symbol::Variable);
+                }
+                _ => {}
+            }
+        }
+        self.modules.insert(
+            name.to_owned(),
+            ModuleSymbols {
+                is_package,
+                symbols,
+            },
+        );
+    }
+
+    /// Whether a module is part of the project
+    #[must_use]
+    pub fn contains_module(&self, name: &str) -> bool {
+        self.modules.contains_key(name)
+    }
+
+    /// Names bound by a module
+    #[must_use]
+    pub fn module(&self, name: &str) -> Option<&ModuleSymbols> {
+        self.modules.get(name)
+    }
+
+    /// Dotted names of the project's modules, sorted
+    pub fn module_names(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(String::as_str)
+    }
+
+    /// Resolve the imports of a module, binding the imported names
+    ///
+    /// Returns a diagnostic for each import that cannot be resolved: a
+    /// warning for modules outside the project and the standard library,
+    /// an error for names missing from project modules.
+    pub fn resolve_imports(&mut self, module: &str, body: &[PythonHIR]) -> Vec<Diagnostic> {
+        let mut bound = Vec::new();
+        let mut diagnostics = Vec::new();
+        for node in body {
+            let PythonHIR::Import {
+                from,
+                level,
+                names,
+                meta,
+                ..
+            } = node
+            else {
+                continue;
+            };
+            let location = meta.source.clone();
+            match self.resolve(module, from.as_deref(), *level, names) {
+                Ok(bindings) => bound.extend(bindings),
+                Err(diagnostic) => diagnostics.push(diagnostic.with_location(location)),
+            }
+        }
+        if let Some(symbols) = self.modules.get_mut(module) {
+            for (name, target) in bound {
+                symbols.symbols.insert(name, Symbol::Import(target));
+            }
+        }
+        diagnostics
+    }
+
+    /// Names bound by a single import statement, with their targets
+    ///
+    /// Imports from outside the project bind nothing.
+    fn resolve(
+        &self,
+        module: &str,
+        from: Option<&str>,
+        level: usize,
+        names: &[ImportName],
+    ) -> Result<Vec<(String, String)>, Diagnostic> {
+        let Some(from) = from else {
+            let mut bindings = Vec::new();
+            for import in names {
+                if !self.is_project_import(&import.name)? {
+                    continue;
+                }
+                bindings.push(match &import.alias {
+                    Some(alias) => (alias.clone(), import.name.clone()),
+                    None => {
+                        let top = top_level(&import.name).to_owned();
+                        (top.clone(), top)
+                    }
+                });
+            }
+            return Ok(bindings);
+        };
+
+        let source = self.absolute_module(module, from, level)?;
+        if !self.is_project_import(&source)? {
+            return Ok(vec![]);
+        }
+        let mut bindings = Vec::new();
+        for import in names {
+            if import.name == "*" {
+                let symbols = self.modules.get(&source).map(|m| &m.symbols);
+                bindings.extend(
+                    symbols
+                        .into_iter()
+                        .flatten()
+                        .filter(|(name, _)| !name.starts_with('_'))
+                        .map(|(name, _)| (name.clone(), format!("{source}.{name}"))),
+                );
+                continue;
+            }
+            let target = format!("{source}.{}", import.name);
+            let defined = self
+                .modules
+                .get(&source)
+                .is_some_and(|m| m.symbols.contains_key(&import.name));
+            if !defined && !self.contains_module(&target) {
+                return Err(Diagnostic::error(format!(
+                    "module `{source}` has no `{}`",
+                    import.name
+                )));
+            }
+            let name = import.alias.clone().unwrap_or_else(|| import.name.clone());
+            bindings.push((name, target));
+        }
+        Ok(bindings)
+    }
+
+    /// Whether an imported module is part of the project
+    ///
+    /// Fails for modules that should be but are missing, and for modules
+    /// outside both the project and the standard library.
+    fn is_project_import(&self, name: &str) -> Result<bool, Diagnostic> {
+        if self.contains_module(name) {
+            return Ok(true);
+        }
+        let top = top_level(name);
+        if self.contains_module(top) {
+            return Err(Diagnostic::error(format!("unresolved import `{name}`")));
+        }
+        if STDLIB_MODULES.contains(&top) {
+            return Ok(false);
+        }
+        Err(Diagnostic::warning(format!(
+            "unresolved import `{name}`: module is not part of the project"
+        )))
+    }
+
+    /// Dotted name of the module a `from` import reads from
+    ///
+    /// `from . import x` in `app/models.py` reads from `app`, as does
+    /// `from . import x` in `app/__init__.py`.
+    fn absolute_module(
+        &self,
+        module: &str,
+        from: &str,
+        level: usize,
+    ) -> Result<String, Diagnostic> {
+        if level == 0 {
+            return Ok(from.to_owned());
+        }
+        let is_package = self.modules.get(module).is_some_and(|m| m.is_package);
+        let mut package: Vec<&str> = module.split('.').collect();
+        let strip = if is_package { level - 1 } else { level };
+        if strip >= package.len() {
+            return Err(Diagnostic::error(
+                "relative import beyond the top-level package".to_owned(),
+            ));
+        }
+        package.truncate(package.len() - strip);
+        if !from.is_empty() {
+            package.push(from);
+        }
+        Ok(package.join("."))
+    }
+}
+
+/// First component of a dotted module name
+fn top_level(name: &str) -> &str {
+    name.split('.').next().unwrap_or(name)
+}
+
+/// Imports of the module being lowered
+#[derive(Debug, Default)]
+pub(crate) struct ImportModel {
+    /// Dotted name of the module being lowered
+    module: String,
+    /// Project modules bound to a name, by that name
+    modules: BTreeMap<String, String>,
+}
+
+impl ImportModel {
+    /// A model for code importing no project modules
+    pub(crate) const fn new() -> Self {
+        Self {
+            module: String::new(),
+            modules: BTreeMap::new(),
+        }
+    }
+
+    /// Find the names the module's imports bind to project modules
+    pub(crate) fn analyze(module: &str, symbols: &SymbolTable) -> Self {
+        let modules = symbols
+            .module(module)
+            .into_iter()
+            .flat_map(|m| &m.symbols)
+            .filter_map(|(name, symbol)| match symbol {
+                Symbol::Import(target) if symbols.contains_module(target) => {
+                    Some((name.clone(), target.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        Self {
+            module: module.to_owned(),
+            modules,
+        }
+    }
+
+    /// Rust path of an expression naming a project module: `util` or
+    /// `app.util` becomes `util` or `app::util`
+    pub(crate) fn module_path(&self, node: &PythonHIR) -> Option<String> {
+        match node {
+            PythonHIR::Variable { name, .. } if self.modules.contains_key(name) => {
+                Some(name.clone())
+            }
+            PythonHIR::Attribute { object, attr, .. } => {
+                let path = self.module_path(object)?;
+                Some(format!("{path}::{attr}"))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Unifier {
+    /// Lower an import into one `use` declaration per project module or
+    /// name it imports
+    pub(crate) fn lower_import(&mut self, node: &PythonHIR) -> Result<Vec<UnifiedHIR>> {
+        let PythonHIR::Import {
+            from,
+            level,
+            names,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported(node));
+        };
+        let module = self.imports.module.clone();
+        let bindings = match self
+            .symbols
+            .resolve(&module, from.as_deref(), *level, names)
+        {
+            Ok(bindings) => bindings,
+            // Reported when the project was loaded
+            Err(diagnostic) if !diagnostic.is_error() => return Ok(vec![]),
+            Err(diagnostic) => {
+                return Err(unsupported_in(
+                    &format!("import ({})", diagnostic.message),
+                    node,
+                ))
+            }
+        };
+
+        if names.iter().any(|import| import.name == "*") {
+            let source = self
+                .symbols
+                .absolute_module(&module, from.as_deref().unwrap_or_default(), *level)
+                .ok()
+                .filter(|source| self.symbols.contains_module(source));
+            return Ok(source
+                .map(|source| vec![self.use_declaration(&format!("{source}.*"), None, meta)])
+                .unwrap_or_default());
+        }
+        Ok(bindings
+            .into_iter()
+            .map(|(name, target)| {
+                let renamed = target.rsplit('.').next() != Some(name.as_str());
+                self.use_declaration(&target, renamed.then_some(name), meta)
+            })
+            .collect())
+    }
+
+    /// Lower `util.MAX` or `app.util` naming a module or an item of one
+    /// into a path
+    pub(crate) fn lower_module_access(&mut self, node: &PythonHIR) -> Option<UnifiedHIR> {
+        let PythonHIR::Attribute {
+            inferred_type,
+            meta,
+            ..
+        } = node
+        else {
+            return None;
+        };
+        let path = self.imports.module_path(node)?;
+        Some(UnifiedHIR::Variable {
+            id: self.next_node_id(),
+            name: path,
+            var_type: inferred_type.clone().unwrap_or(Type::Unknown),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// `use crate::<path>` for a dotted name
+    fn use_declaration(
+        &mut self,
+        target: &str,
+        alias: Option<String>,
+        meta: &Metadata,
+    ) -> UnifiedHIR {
+        UnifiedHIR::Use {
+            id: self.next_node_id(),
+            path: target.split('.').map(str::to_owned).collect(),
+            alias,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeId;
+
+    fn import(from: Option<&str>, level: usize, names: &[(&str, Option<&str>)]) -> PythonHIR {
+        PythonHIR::Import {
+            id: NodeId::new(1),
+            from: from.map(str::to_owned),
+            level,
+            names: names
+                .iter()
+                .map(|(name, alias)| ImportName {
+                    name: (*name).to_owned(),
+                    alias: alias.map(str::to_owned),
+                })
+                .collect(),
+            meta: Metadata::new(),
+        }
+    }
+
+    fn function(name: &str) -> PythonHIR {
+        PythonHIR::Function {
+            id: NodeId::new(2),
+            name: name.to_owned(),
+            params: vec![],
+            return_type: None,
+            body: vec![],
+            decorators: vec![],
+            visibility: crate::Visibility::Public,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn project() -> SymbolTable {
+        let mut symbols = SymbolTable::new();
+        symbols.define_module("app", true, &[]);
+        symbols.define_module("app.models", false, &[function("load")]);
+        symbols.define_module("app.util", false, &[function("slugify")]);
+        symbols
+    }
+
+    #[test]
+    fn test_resolve_relative_and_absolute_imports() {
+        let mut symbols = project();
+        let body = vec![
+            import(Some("models"), 1, &[("load", None)]),
+            import(Some(""), 1, &[("util", Some("u"))]),
+            import(None, 0, &[("app.util", None)]),
+        ];
+
+        let diagnostics = symbols.resolve_imports("app.util", &body);
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        let bound = &symbols.module("app.util").map(|m| m.symbols.clone());
+        let bound = bound.as_ref().map(|symbols| {
+            symbols
+                .iter()
+                .map(|(name, symbol)| (name.as_str(), symbol.clone()))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            bound,
+            Some(vec![
+                ("app", Symbol::Import("app".to_owned())),
+                ("load", Symbol::Import("app.models.load".to_owned())),
+                ("slugify", Symbol::Function),
+                ("u", Symbol::Import("app.util".to_owned())),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unresolved_imports_are_reported() {
+        let mut symbols = project();
+        let body = vec![
+            import(None, 0, &[("numpy", Some("np"))]),
+            import(Some("typing"), 0, &[("List", None)]),
+            import(Some("app.models"), 0, &[("save", None)]),
+            import(Some("app.views"), 0, &[("index", None)]),
+        ];
+
+        let diagnostics = symbols.resolve_imports("app.util", &body);
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "warning: unresolved import `numpy`: module is not part of the project",
+                "error: module `app.models` has no `save`",
+                "error: unresolved import `app.views`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relative_import_beyond_top_level() {
+        let mut symbols = project();
+        let body = vec![import(Some("x"), 2, &[("y", None)])];
+
+        let diagnostics = symbols.resolve_imports("app", &body);
+
+        assert_eq!(
+            diagnostics[0].message,
+            "relative import beyond the top-level package"
+        );
+    }
+}
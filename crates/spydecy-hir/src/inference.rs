@@ -0,0 +1,254 @@
+//! Types of unannotated Python code
+//!
+//! The Python converter only types names from annotations. Before a
+//! function is lowered, the types that follow from the code itself are
+//! filled in, so lowering can pick the Rust operation for them:
+//!
+//! - a local first assigned a value of known type has that type, and a
+//!   loop variable the item type of its iterable
+//! - `self.<field>` has the type of the field
+//! - calling a class defined by the module produces an instance of it,
+//!   and calling one of its functions the type the function returns
+//!
+//! Types flow forward through the body in source order. Nested functions
+//! and lambdas keep their own scopes and are left alone.
+
+use crate::{
+    lowering::{expr_type, is_self},
+    python::{PythonHIR, Target},
+    types::{PythonType, Type},
+    unified::UnifiedField,
+    unpacking::item_type,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Names and fields whose types are known at a point of a function body
+struct Inference<'a> {
+    /// Fields of the class whose method is being typed
+    fields: &'a [UnifiedField],
+    /// Classes defined by the module
+    classes: &'a BTreeMap<String, HashSet<String>>,
+    /// Types returned by the module's functions
+    functions: &'a BTreeMap<String, Type>,
+    /// Types of the locals bound so far
+    scope: HashMap<String, Type>,
+}
+
+/// Fill in the types of a function's unannotated locals, `self.<field>`
+/// attributes and calls to the module's classes and functions
+pub(crate) fn infer_types(
+    function: &PythonHIR,
+    fields: &[UnifiedField],
+    classes: &BTreeMap<String, HashSet<String>>,
+    functions: &BTreeMap<String, Type>,
+) -> PythonHIR {
+    let mut function = function.clone();
+    let PythonHIR::Function { params, body, .. } = &mut function else {
+        return function;
+    };
+    let mut inference = Inference {
+        fields,
+        classes,
+        functions,
+        scope: params
+            .iter()
+            .filter_map(|param| Some((param.name.clone(), param.type_annotation.clone()?)))
+            .collect(),
+    };
+    for statement in body {
+        inference.infer(statement);
+    }
+    function
+}
+
+impl Inference<'_> {
+    /// Type a node and its descendants, binding the names it assigns
+    fn infer(&mut self, node: &mut PythonHIR) {
+        match node {
+            PythonHIR::Function { .. } | PythonHIR::Class { .. } | PythonHIR::Lambda { .. } => {
+                return;
+            }
+            // The loop variable is bound before the body runs
+            PythonHIR::For {
+                target,
+                iter,
+                body,
+                orelse,
+                ..
+            } => {
+                self.infer(iter);
+                self.bind(target, &item_type(&expr_type(iter)));
+                for statement in body.iter_mut().chain(orelse) {
+                    self.infer(statement);
+                }
+                return;
+            }
+            _ => {}
+        }
+        for child in node.children_mut() {
+            self.infer(child);
+        }
+
+        match node {
+            PythonHIR::Variable {
+                name,
+                inferred_type: inferred_type @ None,
+                ..
+            } => *inferred_type = self.scope.get(name).cloned(),
+            PythonHIR::Attribute {
+                object,
+                attr,
+                inferred_type: inferred_type @ None,
+                ..
+            } if is_self(object) => {
+                *inferred_type = self
+                    .fields
+                    .iter()
+                    .find(|field| field.name == *attr && field.field_type != Type::Unknown)
+                    .map(|field| field.field_type.clone());
+            }
+            PythonHIR::Call {
+                callee,
+                inferred_type: inferred_type @ None,
+                ..
+            } => {
+                if let PythonHIR::Variable { name, .. } = callee.as_ref() {
+                    if self.classes.contains_key(name) {
+                        *inferred_type = Some(Type::Python(PythonType::Class(name.clone())));
+                    } else {
+                        *inferred_type = self.functions.get(name).cloned();
+                    }
+                }
+            }
+            PythonHIR::Assign {
+                target,
+                value,
+                type_annotation,
+                ..
+            } => {
+                let ty = type_annotation.clone().unwrap_or_else(|| expr_type(value));
+                self.bind(target, &ty);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record the type of the names a target binds, unless already known
+    fn bind(&mut self, target: &Target, ty: &Type) {
+        match (target, ty) {
+            (_, Type::Unknown) => {}
+            (Target::Name(name), ty) => {
+                self.scope.entry(name.clone()).or_insert_with(|| ty.clone());
+            }
+            (Target::Tuple(targets), Type::Python(PythonType::Tuple(types)))
+                if targets.len() == types.len()
+                    && !targets
+                        .iter()
+                        .any(|target| matches!(target, Target::Starred(_))) =>
+            {
+                for (target, ty) in targets.iter().zip(types) {
+                    self.bind(target, ty);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{
+        metadata::Metadata,
+        python::{Parameter, ParameterKind},
+        NodeId, Visibility,
+    };
+
+    fn var(name: &str) -> PythonHIR {
+        PythonHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn assign(target: &str, value: PythonHIR) -> PythonHIR {
+        PythonHIR::Assign {
+            id: NodeId::new(0),
+            target: Target::name(target),
+            value: Box::new(value),
+            type_annotation: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_types_flow_from_iterables_and_constructors() {
+        // def f(xs: list[int]):
+        //     for x in xs: last = x
+        //     cart = Cart()
+        //     return last, cart
+        let int_list = Type::Python(PythonType::List(Box::new(Type::Python(PythonType::Int))));
+        let function = PythonHIR::Function {
+            id: NodeId::new(0),
+            name: "f".to_owned(),
+            params: vec![Parameter {
+                name: "xs".to_owned(),
+                kind: ParameterKind::Positional,
+                type_annotation: Some(int_list),
+                default: None,
+            }],
+            return_type: None,
+            body: vec![
+                PythonHIR::For {
+                    id: NodeId::new(0),
+                    target: Target::name("x"),
+                    iter: Box::new(var("xs")),
+                    body: vec![assign("last", var("x"))],
+                    orelse: vec![],
+                    meta: Metadata::new(),
+                },
+                assign(
+                    "cart",
+                    PythonHIR::Call {
+                        id: NodeId::new(0),
+                        callee: Box::new(var("Cart")),
+                        args: vec![],
+                        kwargs: vec![],
+                        inferred_type: None,
+                        meta: Metadata::new(),
+                    },
+                ),
+                PythonHIR::Return {
+                    id: NodeId::new(0),
+                    value: Some(Box::new(PythonHIR::Tuple {
+                        id: NodeId::new(0),
+                        elements: vec![var("last"), var("cart")],
+                        inferred_type: None,
+                        meta: Metadata::new(),
+                    })),
+                    meta: Metadata::new(),
+                },
+            ],
+            decorators: vec![],
+            visibility: Visibility::Public,
+            meta: Metadata::new(),
+        };
+        let classes = BTreeMap::from([("Cart".to_owned(), HashSet::new())]);
+
+        let PythonHIR::Function { body, .. } =
+            infer_types(&function, &[], &classes, &BTreeMap::new())
+        else {
+            panic!("Expected Function");
+        };
+        let Some(PythonHIR::Return {
+            value: Some(value), ..
+        }) = body.last()
+        else {
+            panic!("Expected a trailing return");
+        };
+        assert_eq!(expr_type(value).to_string(), "tuple[int, Cart]");
+    }
+}
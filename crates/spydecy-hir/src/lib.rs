@@ -51,12 +51,25 @@
 #![allow(clippy::uninlined_format_args)]
 #![allow(clippy::single_match_else)]
 
+pub mod bindings;
 pub mod c;
+pub mod closures;
+pub mod context_managers;
+pub mod dataclasses;
+pub mod diagnostics;
+pub mod enums;
 pub mod error;
+pub mod exceptions;
+pub mod formatting;
+pub mod generators;
+pub mod imports;
+pub mod inference;
+pub mod lowering;
 pub mod metadata;
 pub mod python;
 pub mod types;
 pub mod unified;
+pub mod unpacking;
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -91,21 +104,44 @@ pub struct SourceLocation {
     pub line: usize,
     /// Column number (1-indexed)
     pub column: usize,
+    /// End line number (1-indexed, inclusive)
+    pub end_line: usize,
+    /// End column number (1-indexed, exclusive)
+    pub end_column: usize,
     /// Source language
     pub language: Language,
 }
 
 impl SourceLocation {
     /// Create a new source location
+    ///
+    /// The end position starts out equal to the start; use
+    /// [`SourceLocation::with_end`] to record the full span.
     #[must_use]
     pub const fn new(file: String, line: usize, column: usize, language: Language) -> Self {
         Self {
             file,
             line,
             column,
+            end_line: line,
+            end_column: column,
             language,
         }
     }
+
+    /// Set the end position of the span
+    #[must_use]
+    pub const fn with_end(mut self, end_line: usize, end_column: usize) -> Self {
+        self.end_line = end_line;
+        self.end_column = end_column;
+        self
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
 }
 
 /// Unique identifier for HIR nodes (for cross-referencing)
@@ -148,6 +184,11 @@ mod tests {
         assert_eq!(loc.line, 10);
         assert_eq!(loc.column, 5);
         assert_eq!(loc.language, Language::Python);
+        assert_eq!((loc.end_line, loc.end_column), (10, 5));
+
+        let span = loc.with_end(12, 1);
+        assert_eq!((span.end_line, span.end_column), (12, 1));
+        assert_eq!(span.to_string(), "test.py:10:5");
     }
 
     #[test]
@@ -0,0 +1,2608 @@
+//! Lowering of Python-only code into Unified HIR
+//!
+//! [`Unifier::unify`] pairs a Python call with the C function implementing
+//! it. Code that has no C counterpart - plain functions, classes and their
+//! methods - is lowered directly by [`Unifier::lower_python`].
+//!
+//! Classes become a `Struct` plus an `Impl` block:
+//!
+//! - fields are collected from `self.<name> = ...` assignments, typed from
+//!   annotations, annotated parameters or literal values
+//! - `__init__` becomes a `new` constructor returning `Self`; a class
+//!   without one gets a `new` defaulting every field, and calling a class
+//!   calls its `new`
+//! - methods take `&mut self` when they assign to, delete from or call a
+//!   mutating method on `self.<field>` (directly or through another
+//!   method), and `&self` otherwise
+//!
+//! Exceptions become variants of a generated `Error` enum (see
+//! [`crate::exceptions`]):
+//!
+//! - functions that can raise return `Result<T, Error>`, `raise` becomes
+//!   `return Err(..)` and calls to them propagate with `?`
+//! - `try` runs its body in a `Result`-returning closure and matches the
+//!   error against the `except` clauses
+//! - exception classes carry no fields and only become enum variants
+//!
+//! Functions containing `yield` return an iterator (see
+//! [`crate::generators`]).
+//!
+//! `with` statements become blocks holding RAII guards (see
+//! [`crate::context_managers`]), string formatting becomes `format!` (see
+//! [`crate::formatting`]) and tuples are packed and unpacked with Rust
+//! tuples and destructuring patterns (see [`crate::unpacking`]). Lambdas
+//! and nested functions become closures (see [`crate::closures`]).
+//!
+//! `@dataclass` classes become structs with derived traits (see
+//! [`crate::dataclasses`]) and `Enum` subclasses become fieldless enums
+//! (see [`crate::enums`]). Other decorators, except `@staticmethod`, are
+//! reported.
+//!
+//! Imports of other modules of the project become `use` declarations (see
+//! [`crate::imports`]), and reassigned variables become `mut` locals (see
+//! [`crate::bindings`]). Unannotated locals are typed from the values
+//! assigned to them before a function is lowered (see
+//! [`crate::inference`]).
+
+use crate::{
+    closures::{returned_closure_type, ClosureScope},
+    context_managers::{check_enter, exit_method, is_lock_call, ContextModel, LOCK_TYPE},
+    dataclasses::DataclassModel,
+    diagnostics::Diagnostic,
+    enums::EnumModel,
+    error::UnificationError,
+    exceptions::{exception_name, pops_list, ExceptionModel, EMPTY_POP_MESSAGE, ERROR_TYPE},
+    generators::{first_yield_type, is_generator},
+    imports::ImportModel,
+    inference::infer_types,
+    metadata::Metadata,
+    python::{
+        BinOp as PythonBinOp, Comprehension, Literal as PythonLiteral, Parameter, ParameterKind,
+        PythonHIR, Target, UnaryOp as PythonUnaryOp,
+    },
+    types::{PythonType, RustType, Type},
+    unified::{
+        bind_keyword_args, convert_python_literal, BinOp, CatchClause, ClosureKind,
+        ComprehensionClause, ComprehensionKind, LiteralValue, LoopKind, Receiver, UnaryOp,
+        UnifiedField, UnifiedHIR, UnifiedParameter, Unifier,
+    },
+    unpacking::{item_type, lower_assign_target, lower_loop_target},
+    Language,
+};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+
+/// Python methods that mutate the collection they are called on
+pub(crate) const MUTATING_METHODS: &[&str] = &[
+    "append",
+    "extend",
+    "insert",
+    "pop",
+    "popitem",
+    "remove",
+    "clear",
+    "sort",
+    "reverse",
+    "update",
+    "setdefault",
+    "add",
+    "discard",
+];
+
+/// What lowering knows about the class whose methods are being lowered
+pub(crate) struct ClassInfo {
+    /// Fields, in first-assignment order
+    pub(crate) fields: Vec<UnifiedField>,
+    /// Methods that need `&mut self`
+    pub(crate) mutating: HashSet<String>,
+}
+
+impl Unifier {
+    /// Lower Python code that has no C counterpart
+    ///
+    /// Accepts a module or a single function. Each class in a module becomes
+    /// a `Struct` followed by an `Impl` holding its methods.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnificationError::UnsupportedPython`] for constructs that
+    /// cannot be lowered yet.
+    pub fn lower_python(&mut self, python: &PythonHIR) -> Result<UnifiedHIR> {
+        match python {
+            PythonHIR::Module { name, body, meta } => {
+                self.exceptions = ExceptionModel::analyze(body);
+                self.contexts = ContextModel::analyze(body);
+                self.dataclasses = DataclassModel::analyze(body);
+                self.enums = EnumModel::analyze(body);
+                self.imports = ImportModel::analyze(name, &self.symbols);
+                self.classes = BTreeMap::new();
+                for node in body {
+                    let PythonHIR::Class { name, body, .. } = node else {
+                        continue;
+                    };
+                    if !self.exceptions.is_exception_class(name) && !self.enums.is_enum(name) {
+                        let info = self.class_info(name, &class_methods(body));
+                        self.classes.insert(name.clone(), info.mutating);
+                    }
+                }
+                self.functions = BTreeMap::new();
+                for node in body {
+                    if let PythonHIR::Function { name, .. } = node {
+                        if let Some(returned) = self.function_type(node) {
+                            self.functions.insert(name.clone(), returned);
+                        }
+                    }
+                }
+                let mut declarations = Vec::new();
+                let variants = self.exceptions.variants();
+                if !variants.is_empty() {
+                    declarations.push(UnifiedHIR::ErrorEnum {
+                        id: self.next_node_id(),
+                        name: ERROR_TYPE.to_owned(),
+                        variants,
+                        source_language: Language::Python,
+                        meta: Metadata::new()
+                            .with_docs("Exceptions raised by this module".to_owned()),
+                    });
+                }
+                for node in body {
+                    match node {
+                        PythonHIR::Function { .. } => {
+                            declarations.push(self.lower_function(node, None)?);
+                        }
+                        PythonHIR::Class { name, body, .. }
+                            if self.exceptions.is_exception_class(name) =>
+                        {
+                            // The docstring is kept on the enum variant
+                            if let Some(member) = body.iter().find(|member| {
+                                !matches!(
+                                    member,
+                                    PythonHIR::Literal { .. } | PythonHIR::Pass { .. }
+                                )
+                            }) {
+                                return Err(unsupported_in("exception class", member));
+                            }
+                        }
+                        PythonHIR::Class { name, .. } if self.enums.is_enum(name) => {
+                            declarations.push(self.lower_enum(node)?);
+                        }
+                        PythonHIR::Class { name, .. } if self.dataclasses.is_dataclass(name) => {
+                            declarations.extend(self.lower_dataclass(node)?);
+                        }
+                        PythonHIR::Class { .. } => declarations.extend(self.lower_class(node)?),
+                        PythonHIR::Import { .. } => declarations.extend(self.lower_import(node)?),
+                        PythonHIR::Assign { .. } => declarations.push(self.lower_static(node)?),
+                        // Docstrings and `pass`
+                        PythonHIR::Literal { .. } | PythonHIR::Pass { .. } => {}
+                        other => return Err(unsupported(other)),
+                    }
+                }
+                Ok(UnifiedHIR::Module {
+                    name: name.clone(),
+                    source_language: Language::Python,
+                    declarations,
+                    meta: meta.clone(),
+                })
+            }
+            PythonHIR::Function { .. } => {
+                self.exceptions = ExceptionModel::analyze(std::slice::from_ref(python));
+                self.contexts = ContextModel::new();
+                self.dataclasses = DataclassModel::new();
+                self.enums = EnumModel::new();
+                self.imports = ImportModel::new();
+                self.classes = BTreeMap::new();
+                self.functions = BTreeMap::new();
+                self.lower_function(python, None)
+            }
+            other => Err(unsupported(other)),
+        }
+    }
+
+    /// Type a module-level function returns, if annotated or a generator
+    ///
+    /// A generator's items are inferred from the values it yields.
+    fn function_type(&self, function: &PythonHIR) -> Option<Type> {
+        let PythonHIR::Function {
+            return_type, body, ..
+        } = function
+        else {
+            return None;
+        };
+        match return_type {
+            Some(Type::Python(PythonType::None)) => None,
+            Some(ty) => Some(ty.clone()),
+            None if is_generator(body) => {
+                let PythonHIR::Function { body, .. } =
+                    infer_types(function, &[], &self.classes, &self.functions)
+                else {
+                    return None;
+                };
+                let item = first_yield_type(&body);
+                is_known(&item).then(|| Type::Python(PythonType::Iterator(Box::new(item))))
+            }
+            None => None,
+        }
+    }
+
+    /// Lower a class into a struct and an impl block
+    fn lower_class(&mut self, class: &PythonHIR) -> Result<Vec<UnifiedHIR>> {
+        let PythonHIR::Class {
+            name,
+            body,
+            decorators,
+            meta,
+            ..
+        } = class
+        else {
+            return Err(unsupported(class));
+        };
+        if let Some(decorator) = decorators.first() {
+            return Err(unsupported_in(&format!("decorator `@{decorator}`"), class));
+        }
+
+        let mut methods = Vec::new();
+        for node in body {
+            match node {
+                PythonHIR::Function { .. } => methods.push(node),
+                PythonHIR::Literal { .. } | PythonHIR::Pass { .. } => {}
+                other => return Err(unsupported(other)),
+            }
+        }
+
+        let info = self.class_info(name, &methods);
+        let manager = self.contexts.is_manager(name);
+
+        let mut declarations = vec![UnifiedHIR::Struct {
+            id: self.next_node_id(),
+            name: name.clone(),
+            fields: info.fields.clone(),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }];
+
+        // The class docstring is emitted on the struct
+        let undocumented = Metadata {
+            docs: None,
+            ..meta.clone()
+        };
+        let enclosing = self.exceptions.class.replace(name.clone());
+        let fields = std::mem::replace(&mut self.contexts.fields, info.fields.clone());
+        // Instances are built with `new` even without an `__init__`
+        let constructor = if methods
+            .iter()
+            .any(|method| matches!(method, PythonHIR::Function { name, .. } if name == "__init__"))
+        {
+            None
+        } else {
+            Some(self.lower_constructor(vec![], &[], &info, &undocumented))
+        };
+        let methods = constructor
+            .into_iter()
+            .chain(methods.into_iter().map(|method| {
+                if manager {
+                    self.lower_manager_method(method, &info, name)
+                } else {
+                    self.lower_function(method, Some(&info))
+                }
+            }))
+            .collect::<Result<_>>();
+        self.exceptions.class = enclosing;
+        self.contexts.fields = fields;
+        declarations.push(UnifiedHIR::Impl {
+            id: self.next_node_id(),
+            type_name: name.clone(),
+            methods: methods?,
+            source_language: Language::Python,
+            meta: undocumented,
+        });
+        if manager {
+            declarations.push(self.guard_type(name));
+        }
+        Ok(declarations)
+    }
+
+    /// Fields and mutating methods of a class
+    fn class_info(&self, name: &str, methods: &[&PythonHIR]) -> ClassInfo {
+        let mut fields = collect_fields(methods);
+        self.contexts.type_manager_fields(methods, &mut fields);
+        let mut mutating = mutating_methods(methods, &self.contexts.manager_fields(&fields));
+        if self.contexts.is_manager(name) {
+            mutating.extend(["__enter__".to_owned(), "__exit__".to_owned()]);
+        }
+        ClassInfo { fields, mutating }
+    }
+
+    /// Lower a method of a context manager class
+    ///
+    /// `__enter__` becomes `enter`, returning the guard that calls `exit`
+    /// (the lowered `__exit__`) when dropped.
+    fn lower_manager_method(
+        &mut self,
+        method: &PythonHIR,
+        info: &ClassInfo,
+        class: &str,
+    ) -> Result<UnifiedHIR> {
+        let PythonHIR::Function { name, .. } = method else {
+            return Err(unsupported(method));
+        };
+        match name.as_str() {
+            "__enter__" => {
+                check_enter(method)?;
+                let lowered = self.lower_function(method, Some(info))?;
+                Ok(self.enter_method(lowered, class))
+            }
+            "__exit__" => {
+                if self.exceptions.can_raise(&format!("{class}.__exit__")) {
+                    return Err(unsupported_in("`__exit__` that can raise", method));
+                }
+                let mut lowered = self.lower_function(&exit_method(method)?, Some(info))?;
+                if let UnifiedHIR::Function { name, .. } = &mut lowered {
+                    "exit".clone_into(name);
+                }
+                Ok(lowered)
+            }
+            _ => self.lower_function(method, Some(info)),
+        }
+    }
+
+    /// Lower a function, or a method when `class` is given
+    pub(crate) fn lower_function(
+        &mut self,
+        function: &PythonHIR,
+        class: Option<&ClassInfo>,
+    ) -> Result<UnifiedHIR> {
+        let fields = class.map_or(&[][..], |class| class.fields.as_slice());
+        let function = &infer_types(function, fields, &self.classes, &self.functions);
+        let PythonHIR::Function {
+            name,
+            params,
+            return_type,
+            body,
+            decorators,
+            meta,
+            ..
+        } = function
+        else {
+            return Err(unsupported(function));
+        };
+        if let Some(decorator) = decorators
+            .iter()
+            .find(|decorator| class.is_none() || *decorator != "staticmethod")
+        {
+            return Err(unsupported_in(
+                &format!("decorator `@{decorator}`"),
+                function,
+            ));
+        }
+        let scope = ClosureScope::new(params, body, return_type.as_ref(), None);
+        let enclosing_scope = self.closures.replace(scope);
+
+        let class = class.filter(|_| params.first().is_some_and(|p| p.name == "self"));
+        let params = params
+            .iter()
+            .skip(usize::from(class.is_some()))
+            .map(lower_parameter)
+            .collect();
+
+        let key = self
+            .exceptions
+            .class
+            .as_ref()
+            .map_or_else(|| name.clone(), |class| format!("{class}.{name}"));
+        let fallible = self.exceptions.can_raise(&key);
+        let enclosing = std::mem::replace(&mut self.exceptions.returns_result, fallible);
+        let lowered = if let (Some(class), "__init__") = (class, name.as_str()) {
+            self.lower_constructor(params, body, class, meta)
+        } else {
+            self.lower_method(function, params, class)
+        };
+        self.exceptions.returns_result = enclosing;
+        self.closures = enclosing_scope;
+        lowered
+    }
+
+    /// Lower a function or a method other than `__init__`
+    fn lower_method(
+        &mut self,
+        function: &PythonHIR,
+        params: Vec<UnifiedParameter>,
+        class: Option<&ClassInfo>,
+    ) -> Result<UnifiedHIR> {
+        let PythonHIR::Function {
+            name,
+            return_type,
+            body,
+            meta,
+            ..
+        } = function
+        else {
+            return Err(unsupported(function));
+        };
+
+        let receiver = class.map(|class| {
+            if class.mutating.contains(name) {
+                Receiver::RefMut
+            } else {
+                Receiver::Ref
+            }
+        });
+
+        let (return_type, mut lowered) = if is_generator(body) {
+            // Raising while iterating would need `Iterator<Item = Result<..>>`
+            if self.exceptions.returns_result {
+                return Err(unsupported_in("generator that can raise", function));
+            }
+            let item_type = match return_type {
+                Some(Type::Python(PythonType::Iterator(item))) => item.as_ref().clone(),
+                _ => Type::Unknown,
+            };
+            let generator = self.lower_generator(&params, body, item_type, meta)?;
+            let UnifiedHIR::Generator { item_type, .. } = &generator else {
+                return Err(unsupported(function));
+            };
+            if !is_known(item_type) {
+                return Err(uninferred(&format!("the values `{name}` yields"), function));
+            }
+            (
+                Type::Python(PythonType::Iterator(Box::new(item_type.clone()))),
+                vec![generator],
+            )
+        } else {
+            let return_type = match return_type {
+                Some(Type::Python(PythonType::None)) => Type::Rust(RustType::Unit),
+                Some(ty) => ty.clone(),
+                None if body.iter().any(returns_value) => {
+                    let class = class.and(self.exceptions.class.as_deref());
+                    returned_type(body, class)
+                }
+                None => Type::Rust(RustType::Unit),
+            };
+            let lowered = self.lower_body(body)?;
+            let lowered = self.bind_locals(&params, &[], lowered)?;
+            // A returned closure's type is only known once it is lowered
+            let return_type = returned_closure_type(&return_type, &lowered).unwrap_or(return_type);
+            if !is_known(&return_type) {
+                return Err(uninferred(
+                    &format!("the value returned by `{name}`"),
+                    function,
+                ));
+            }
+            (return_type, lowered)
+        };
+
+        let return_type = if self.exceptions.returns_result {
+            // Falling off the end of a fallible function succeeds
+            if return_type == Type::Rust(RustType::Unit)
+                && !matches!(lowered.last(), Some(UnifiedHIR::Return { .. }))
+            {
+                lowered.push(self.return_ok(None, meta));
+            }
+            result_type(return_type)
+        } else {
+            return_type
+        };
+
+        Ok(UnifiedHIR::Function {
+            id: self.next_node_id(),
+            name: name.clone(),
+            receiver,
+            params,
+            return_type,
+            body: lowered,
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower `__init__` into `fn new(..) -> Self`
+    ///
+    /// `self.<field>` becomes a local of the same name, and the body ends in
+    /// a `Self { .. }` literal built from those locals. Fields only assigned
+    /// by other methods start out as `Default::default()`.
+    fn lower_constructor(
+        &mut self,
+        params: Vec<UnifiedParameter>,
+        body: &[PythonHIR],
+        class: &ClassInfo,
+        meta: &Metadata,
+    ) -> Result<UnifiedHIR> {
+        // The docstring belongs to `new` itself, not the nodes it synthesizes
+        let synthesized = Metadata {
+            docs: None,
+            ..meta.clone()
+        };
+        let mut body = body.to_vec();
+        for statement in &mut body {
+            detach_self(statement);
+        }
+        let assigned: HashSet<String> = body
+            .iter()
+            .flat_map(|statement| match statement {
+                PythonHIR::Assign { target, .. } => target.names(),
+                _ => vec![],
+            })
+            .map(str::to_owned)
+            .collect();
+        // `self.owner = owner` needs no local of its own
+        body.retain(|statement| {
+            !matches!(
+                statement,
+                PythonHIR::Assign { target: Target::Name(target), value, .. }
+                    if matches!(value.as_ref(), PythonHIR::Variable { name, .. } if name == target)
+            )
+        });
+
+        let fields = class
+            .fields
+            .iter()
+            .map(|field| {
+                let value = if assigned.contains(&field.name) {
+                    UnifiedHIR::Variable {
+                        id: self.next_node_id(),
+                        name: field.name.clone(),
+                        var_type: field.field_type.clone(),
+                        source_language: Language::Python,
+                        meta: synthesized.clone(),
+                    }
+                } else {
+                    UnifiedHIR::Call {
+                        id: self.next_node_id(),
+                        target_language: Language::Rust,
+                        callee: "Default::default".to_owned(),
+                        args: vec![],
+                        inferred_type: field.field_type.clone(),
+                        source_language: Language::Python,
+                        cross_mapping: None,
+                        meta: synthesized.clone(),
+                    }
+                };
+                (field.name.clone(), value)
+            })
+            .collect();
+
+        let lowered = self.lower_body(&body)?;
+        let mut lowered = self.bind_locals(&params, &[], lowered)?;
+        let init = UnifiedHIR::StructInit {
+            id: self.next_node_id(),
+            name: "Self".to_owned(),
+            fields,
+            meta: synthesized.clone(),
+        };
+        let mut return_type = Type::Rust(RustType::Custom("Self".to_owned()));
+        if self.exceptions.returns_result {
+            // A validating constructor returns `Result<Self, Error>`
+            lowered.push(self.return_ok(Some(init), &synthesized));
+            return_type = result_type(return_type);
+        } else {
+            lowered.push(init);
+        }
+
+        Ok(UnifiedHIR::Function {
+            id: self.next_node_id(),
+            name: "new".to_owned(),
+            receiver: None,
+            params,
+            return_type,
+            body: lowered,
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower a statement list, dropping `pass`, bare literals (docstrings)
+    /// and `nonlocal` declarations
+    pub(crate) fn lower_body(&mut self, body: &[PythonHIR]) -> Result<Vec<UnifiedHIR>> {
+        body.iter()
+            .filter(|node| {
+                !matches!(
+                    node,
+                    PythonHIR::Pass { .. } | PythonHIR::Literal { .. } | PythonHIR::Nonlocal { .. }
+                )
+            })
+            .map(|node| self.lower_statement(node))
+            .collect()
+    }
+
+    /// Lower a single statement
+    fn lower_statement(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        if let Some(lowered) = self.lower_generator_statement(node)? {
+            return Ok(lowered);
+        }
+        let source_language = Language::Python;
+        Ok(match node {
+            PythonHIR::Assign {
+                target,
+                value,
+                type_annotation,
+                meta,
+                ..
+            } => UnifiedHIR::Assign {
+                id: self.next_node_id(),
+                target: lower_assign_target(target, value, type_annotation.as_ref(), node)?,
+                value: Box::new(self.lower_assigned(value)?),
+                var_type: type_annotation.clone().unwrap_or(Type::Unknown),
+                mutable: vec![],
+                source_language,
+                meta: meta.clone(),
+            },
+            PythonHIR::Store { .. } => self.lower_store(node)?,
+            PythonHIR::AugAssign {
+                target,
+                op,
+                value,
+                meta,
+                ..
+            } => UnifiedHIR::AugAssign {
+                id: self.next_node_id(),
+                target: Box::new(self.lower_expr(target)?),
+                op: lower_bin_op(*op)?,
+                value: Box::new(self.lower_expr(value)?),
+                source_language,
+                meta: meta.clone(),
+            },
+            PythonHIR::Return { value, meta, .. } => {
+                let value = match value {
+                    Some(value) => Some(self.lower_value(value)?),
+                    None => None,
+                };
+                if self.exceptions.returns_result {
+                    self.return_result(value, meta)
+                } else {
+                    UnifiedHIR::Return {
+                        id: self.next_node_id(),
+                        value: value.map(Box::new),
+                        source_language,
+                        meta: meta.clone(),
+                    }
+                }
+            }
+            PythonHIR::Raise { .. } => self.lower_raise(node)?,
+            PythonHIR::Try { .. } => self.lower_try(node)?,
+            PythonHIR::With { .. } => self.lower_with(node)?,
+            PythonHIR::Function { .. } => self.lower_nested_function(node)?,
+            PythonHIR::If {
+                condition,
+                then_branch,
+                else_branch,
+                meta,
+                ..
+            } => UnifiedHIR::If {
+                id: self.next_node_id(),
+                condition: Box::new(self.lower_expr(condition)?),
+                then_branch: self.lower_body(then_branch)?,
+                else_branch: self.lower_body(else_branch)?,
+                source_language,
+                meta: meta.clone(),
+            },
+            PythonHIR::For { orelse, .. } | PythonHIR::While { orelse, .. }
+                if orelse.is_empty() =>
+            {
+                self.lower_loop(node)?
+            }
+            PythonHIR::Break { meta, .. } => UnifiedHIR::Break {
+                id: self.next_node_id(),
+                meta: meta.clone(),
+            },
+            PythonHIR::Continue { meta, .. } => UnifiedHIR::Continue {
+                id: self.next_node_id(),
+                meta: meta.clone(),
+            },
+            // Expression statement
+            other => self.lower_expr(other)?,
+        })
+    }
+
+    /// Lower a store through an attribute or subscript
+    ///
+    /// `HashMap` has no `IndexMut`, so `d[k] = v` becomes `d.insert(k, v)`.
+    fn lower_store(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Store {
+            target,
+            value,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported(node));
+        };
+        Ok(match target.as_ref() {
+            PythonHIR::Subscript { object, index, .. } if is_dict(object) => {
+                let receiver = self.lower_expr(object)?;
+                let key = self.lower_value(index)?;
+                let value = self.lower_value(value)?;
+                self.method_call(receiver, "insert", vec![key, value], meta)
+            }
+            _ => UnifiedHIR::Store {
+                id: self.next_node_id(),
+                target: Box::new(self.lower_expr(target)?),
+                value: Box::new(self.lower_value(value)?),
+                source_language: Language::Python,
+                meta: meta.clone(),
+            },
+        })
+    }
+
+    /// Lower an expression
+    pub(crate) fn lower_expr(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        if let Some(formatted) = self.lower_formatting(node)? {
+            return Ok(formatted);
+        }
+        if let Some(item) = self.lower_tuple_index(node)? {
+            return Ok(item);
+        }
+        if let Some(access) = self.lower_enum_access(node)? {
+            return Ok(access);
+        }
+        if let Some(access) = self.lower_module_access(node) {
+            return Ok(access);
+        }
+        let source_language = Language::Python;
+        Ok(match node {
+            PythonHIR::Variable {
+                name,
+                inferred_type,
+                meta,
+                ..
+            } => UnifiedHIR::Variable {
+                id: self.next_node_id(),
+                name: name.clone(),
+                var_type: inferred_type.clone().unwrap_or(Type::Unknown),
+                source_language,
+                meta: meta.clone(),
+            },
+            PythonHIR::Literal { value, meta, .. } => {
+                let (value, lit_type) = convert_python_literal(value);
+                UnifiedHIR::Literal {
+                    id: self.next_node_id(),
+                    value,
+                    lit_type,
+                    meta: meta.clone(),
+                }
+            }
+            PythonHIR::Attribute {
+                object,
+                attr,
+                inferred_type,
+                meta,
+                ..
+            } => UnifiedHIR::FieldAccess {
+                id: self.next_node_id(),
+                object: Box::new(self.lower_expr(object)?),
+                field: attr.clone(),
+                field_type: inferred_type.clone().unwrap_or(Type::Unknown),
+                source_language,
+                meta: meta.clone(),
+            },
+            PythonHIR::Subscript {
+                object,
+                index,
+                meta,
+                ..
+            } => UnifiedHIR::Index {
+                id: self.next_node_id(),
+                object: Box::new(self.lower_expr(object)?),
+                // A `HashMap` is indexed by a borrowed key
+                index: Box::new(if is_dict(object) {
+                    self.lower_key(index)?
+                } else {
+                    self.lower_expr(index)?
+                }),
+                source_language,
+                meta: meta.clone(),
+            },
+            PythonHIR::Call { .. } => self.lower_call(node)?,
+            PythonHIR::BinOp { .. } => self.lower_binary(node)?,
+            PythonHIR::UnaryOp { .. } => self.lower_unary_op(node)?,
+            PythonHIR::List { .. } | PythonHIR::Dict { .. } => self.lower_collection(node)?,
+            PythonHIR::Tuple { .. } => self.lower_tuple(node)?,
+            PythonHIR::Lambda { .. } => self.lower_lambda(node, &[], false)?,
+            PythonHIR::ListComp { .. }
+            | PythonHIR::SetComp { .. }
+            | PythonHIR::DictComp { .. }
+            | PythonHIR::GeneratorExp { .. } => self.lower_comprehension(node)?,
+            other => return Err(unsupported(other)),
+        })
+    }
+
+    /// Lower a binary operation
+    ///
+    /// `x in xs` becomes `xs.contains(&x)`, or `contains_key` on a dict.
+    /// `+` consumes its left operand, so concatenating onto a string held
+    /// by a variable, field or item clones it first.
+    fn lower_binary(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::BinOp {
+            op,
+            left,
+            right,
+            inferred_type,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported(node));
+        };
+        if let PythonBinOp::In | PythonBinOp::NotIn = op {
+            let method = if is_dict(right) {
+                "contains_key"
+            } else {
+                "contains"
+            };
+            let item = match (expr_type(right), left.as_ref()) {
+                // `Vec<String>::contains` takes a `&String`, not a `&&str`
+                (
+                    Type::Python(PythonType::List(item) | PythonType::Set(item)),
+                    PythonHIR::Literal {
+                        value: PythonLiteral::Str(_),
+                        ..
+                    },
+                ) if *item == Type::Python(PythonType::Str) => {
+                    let owned = self.lower_value(left)?;
+                    self.borrow(owned, meta)
+                }
+                _ => self.lower_key(left)?,
+            };
+            let receiver = self.lower_expr(right)?;
+            let contains = self.method_call(receiver, method, vec![item], meta);
+            if *op == PythonBinOp::In {
+                return Ok(contains);
+            }
+            return Ok(UnifiedHIR::UnaryOp {
+                id: self.next_node_id(),
+                op: UnaryOp::Not,
+                operand: Box::new(contains),
+                source_language: Language::Python,
+                meta: meta.clone(),
+            });
+        }
+
+        // `String + &str` takes an owned left operand and a borrowed right one
+        let str_type = Type::Python(PythonType::Str);
+        let concatenates = *op == PythonBinOp::Add && expr_type(left) == str_type;
+        let mut lowered = if concatenates {
+            self.lower_value(left)?
+        } else {
+            self.lower_expr(left)?
+        };
+        if concatenates
+            && matches!(
+                left.as_ref(),
+                PythonHIR::Variable { .. }
+                    | PythonHIR::Attribute { .. }
+                    | PythonHIR::Subscript { .. }
+            )
+        {
+            lowered = self.method_call(lowered, "clone", vec![], meta);
+        }
+        let mut right_lowered = self.lower_expr(right)?;
+        if concatenates && expr_type(right) == str_type && !is_str_literal(right) {
+            right_lowered = self.borrow(right_lowered, meta);
+        }
+        // Rust has no mixed arithmetic: the `int` operand is converted
+        let (float, int) = (
+            Type::Python(PythonType::Float),
+            Type::Python(PythonType::Int),
+        );
+        let (left_lowered, right_lowered) = match (expr_type(left), expr_type(right)) {
+            (left, right) if left == int && right == float => {
+                (self.cast(lowered, float, meta), right_lowered)
+            }
+            (left, right) if left == float && right == int => {
+                (lowered, self.cast(right_lowered, float, meta))
+            }
+            _ => (lowered, right_lowered),
+        };
+        Ok(UnifiedHIR::BinOp {
+            id: self.next_node_id(),
+            op: lower_bin_op(*op)?,
+            left: Box::new(left_lowered),
+            right: Box::new(right_lowered),
+            result_type: inferred_type.clone().unwrap_or(Type::Unknown),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// `value as <target_type>`
+    fn cast(&mut self, value: UnifiedHIR, target_type: Type, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::Cast {
+            id: self.next_node_id(),
+            value: Box::new(value),
+            target_type,
+            meta: meta.clone(),
+        }
+    }
+
+    /// Lower a key looked up in a collection into `&key`; a string literal
+    /// is already a `&str`
+    fn lower_key(&mut self, key: &PythonHIR) -> Result<UnifiedHIR> {
+        let lowered = self.lower_expr(key)?;
+        if is_str_literal(key) {
+            return Ok(lowered);
+        }
+        Ok(self.borrow(lowered, key.metadata()))
+    }
+
+    /// Lower a value that is stored, passed or returned
+    ///
+    /// Python's `str` is an owned `String`, so a string literal, a `&str`,
+    /// becomes `String::from(..)`.
+    pub(crate) fn lower_value(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let lowered = self.lower_expr(node)?;
+        if !is_str_literal(node) {
+            return Ok(lowered);
+        }
+        Ok(UnifiedHIR::Call {
+            id: self.next_node_id(),
+            target_language: Language::Rust,
+            callee: "String::from".to_owned(),
+            args: vec![lowered],
+            inferred_type: Type::Python(PythonType::Str),
+            source_language: Language::Python,
+            cross_mapping: None,
+            meta: node.metadata().clone(),
+        })
+    }
+
+    /// `&value`
+    fn borrow(&mut self, value: UnifiedHIR, meta: &Metadata) -> UnifiedHIR {
+        UnifiedHIR::UnaryOp {
+            id: self.next_node_id(),
+            op: UnaryOp::Ref,
+            operand: Box::new(value),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+
+    /// Lower a unary operation, dropping unary plus
+    fn lower_unary_op(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::UnaryOp {
+            op, operand, meta, ..
+        } = node
+        else {
+            return Err(unsupported(node));
+        };
+        let op = match op {
+            PythonUnaryOp::Pos => return self.lower_expr(operand),
+            PythonUnaryOp::Not => UnaryOp::Not,
+            PythonUnaryOp::Invert => UnaryOp::BitNot,
+            PythonUnaryOp::Neg => UnaryOp::Neg,
+        };
+        Ok(UnifiedHIR::UnaryOp {
+            id: self.next_node_id(),
+            op,
+            operand: Box::new(self.lower_expr(operand)?),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower a list or dict display
+    fn lower_collection(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        Ok(match node {
+            PythonHIR::List { elements, meta, .. } => UnifiedHIR::List {
+                id: self.next_node_id(),
+                elements: elements
+                    .iter()
+                    .map(|element| self.lower_value(element))
+                    .collect::<Result<_>>()?,
+                list_type: expr_type(node),
+                meta: meta.clone(),
+            },
+            PythonHIR::Dict { entries, meta, .. } => UnifiedHIR::Dict {
+                id: self.next_node_id(),
+                entries: entries
+                    .iter()
+                    .map(|(key, value)| Ok((self.lower_value(key)?, self.lower_value(value)?)))
+                    .collect::<Result<_>>()?,
+                dict_type: expr_type(node),
+                meta: meta.clone(),
+            },
+            other => return Err(unsupported(other)),
+        })
+    }
+
+    /// Lower a comprehension or generator expression
+    fn lower_comprehension(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let (kind, element, value, generators, meta) = match node {
+            PythonHIR::ListComp {
+                element,
+                generators,
+                meta,
+                ..
+            } => (ComprehensionKind::List, element, None, generators, meta),
+            PythonHIR::SetComp {
+                element,
+                generators,
+                meta,
+                ..
+            } => (ComprehensionKind::Set, element, None, generators, meta),
+            PythonHIR::GeneratorExp {
+                element,
+                generators,
+                meta,
+                ..
+            } => (
+                ComprehensionKind::Generator,
+                element,
+                None,
+                generators,
+                meta,
+            ),
+            PythonHIR::DictComp {
+                key,
+                value,
+                generators,
+                meta,
+                ..
+            } => (ComprehensionKind::Dict, key, Some(value), generators, meta),
+            other => return Err(unsupported(other)),
+        };
+
+        let clauses = generators
+            .iter()
+            .map(|generator| {
+                Ok(ComprehensionClause {
+                    target: lower_loop_target(&generator.target, &generator.iter, node)?,
+                    iter: self.lower_expr(&generator.iter)?,
+                    filters: generator
+                        .ifs
+                        .iter()
+                        .map(|filter| self.lower_expr(filter))
+                        .collect::<Result<_>>()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(UnifiedHIR::Comprehension {
+            id: self.next_node_id(),
+            kind,
+            element: Box::new(self.lower_value(element)?),
+            value: match value {
+                Some(value) => Some(Box::new(self.lower_value(value)?)),
+                None => None,
+            },
+            clauses,
+            result_type: expr_type(node),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower a `for` or `while` loop without an `else` clause
+    fn lower_loop(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let (kind, body, meta) = match node {
+            PythonHIR::For {
+                target,
+                iter,
+                body,
+                meta,
+                ..
+            } => (
+                LoopKind::For {
+                    target: lower_loop_target(target, iter, node)?,
+                    iter: Box::new(self.lower_expr(iter)?),
+                },
+                body,
+                meta,
+            ),
+            PythonHIR::While {
+                condition,
+                body,
+                meta,
+                ..
+            } => (
+                LoopKind::While {
+                    condition: Box::new(self.lower_expr(condition)?),
+                },
+                body,
+                meta,
+            ),
+            other => return Err(unsupported(other)),
+        };
+
+        // `break` and `continue` in the loop stay within it
+        if let Some(generator) = &mut self.generator {
+            generator.loop_depth += 1;
+        }
+        let body = self.lower_body(body);
+        if let Some(generator) = &mut self.generator {
+            generator.loop_depth -= 1;
+        }
+        Ok(UnifiedHIR::Loop {
+            id: self.next_node_id(),
+            kind,
+            body: body?,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower `d.get(k)` on a dict into `d.get(&k).copied()`, and
+    /// `d.get(k, default)` into `d.get(&k).copied().unwrap_or(default)`
+    ///
+    /// Values that are not `Copy` are cloned instead. Returns `None` for
+    /// any other call.
+    fn lower_dict_get(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Call {
+            callee,
+            args,
+            kwargs,
+            meta,
+            ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        let PythonHIR::Attribute { object, attr, .. } = callee.as_ref() else {
+            return Ok(None);
+        };
+        let Type::Python(PythonType::Dict { value, .. }) = expr_type(object) else {
+            return Ok(None);
+        };
+        if attr != "get" {
+            return Ok(None);
+        }
+        bind_keyword_args(attr, args, kwargs)?;
+        let (key, default) = match args.as_slice() {
+            [key] => (key, None),
+            [key, default] => (key, Some(default)),
+            _ => return Err(unsupported_in("`dict.get` arguments", node)),
+        };
+
+        let receiver = self.lower_expr(object)?;
+        let key = self.lower_key(key)?;
+        let found = self.method_call(receiver, "get", vec![key], meta);
+        let copy = if is_copy(&value) { "copied" } else { "cloned" };
+        let found = self.method_call(found, copy, vec![], meta);
+        Ok(Some(match default {
+            Some(default) => {
+                let default = self.lower_value(default)?;
+                self.method_call(found, "unwrap_or", vec![default], meta)
+            }
+            None => found,
+        }))
+    }
+
+    /// Lower a call to a Python builtin with a Rust counterpart
+    ///
+    /// - `len(xs)` becomes `xs.len() as i64`, Python's `int` rather than
+    ///   `usize`, and `str(x)` becomes `x.to_string()`
+    /// - `min(xs)` becomes `xs.iter().min().cloned()`, panicking on an empty
+    ///   sequence as Python raises, and `min(a, b)` becomes `a.min(b)`;
+    ///   likewise `max`
+    /// - `print(a, b)` becomes `println!("{} {}", a, b)`, printing lists and
+    ///   tuples with `{:?}`, which matches Python for numbers
+    ///
+    /// Returns `None` for any other call.
+    fn lower_builtin_call(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Call {
+            callee,
+            args,
+            kwargs,
+            meta,
+            ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        let (PythonHIR::Variable { name, .. }, []) = (callee.as_ref(), kwargs.as_slice()) else {
+            return Ok(None);
+        };
+        Ok(Some(match (name.as_str(), args.as_slice()) {
+            ("len", [arg]) => {
+                let receiver = self.lower_expr(arg)?;
+                let len = self.method_call(receiver, "len", vec![], meta);
+                self.cast(len, Type::Python(PythonType::Int), meta)
+            }
+            ("str", [arg]) => {
+                let receiver = self.lower_expr(arg)?;
+                self.method_call(receiver, "to_string", vec![], meta)
+            }
+            ("min" | "max", [items]) => {
+                if item_type(&expr_type(items)) == Type::Python(PythonType::Float) {
+                    return Err(unsupported_in(&format!("`{name}` of floats"), node));
+                }
+                let mut items = self.lower_expr(items)?;
+                // A generator expression is already an iterator of values
+                if matches!(args[0], PythonHIR::GeneratorExp { .. }) {
+                    items = self.method_call(items, name, vec![], meta);
+                } else {
+                    items = self.method_call(items, "iter", vec![], meta);
+                    items = self.method_call(items, name, vec![], meta);
+                    items = self.method_call(items, "cloned", vec![], meta);
+                }
+                let message = UnifiedHIR::Literal {
+                    id: self.next_node_id(),
+                    value: LiteralValue::Str(format!("{name}() iterable argument is empty")),
+                    lit_type: Type::Rust(RustType::Str),
+                    meta: meta.clone(),
+                };
+                self.method_call(items, "expect", vec![message], meta)
+            }
+            ("min" | "max", [first, rest @ ..]) => {
+                let mut extremum = self.lower_expr(first)?;
+                for arg in rest {
+                    let arg = self.lower_expr(arg)?;
+                    extremum = self.method_call(extremum, name, vec![arg], meta);
+                }
+                extremum
+            }
+            ("print", args) => {
+                let mut lowered = Vec::new();
+                let mut fields = Vec::new();
+                for arg in args {
+                    fields.push(match expr_type(arg) {
+                        Type::Python(PythonType::List(_) | PythonType::Tuple(_)) => "{:?}",
+                        _ => "{}",
+                    });
+                    lowered.push(self.lower_expr(arg)?);
+                }
+                let template = UnifiedHIR::Literal {
+                    id: self.next_node_id(),
+                    value: LiteralValue::Str(fields.join(" ")),
+                    lit_type: Type::Rust(RustType::Str),
+                    meta: meta.clone(),
+                };
+                if !args.is_empty() {
+                    lowered.insert(0, template);
+                }
+                UnifiedHIR::Call {
+                    id: self.next_node_id(),
+                    target_language: Language::Rust,
+                    callee: "println!".to_owned(),
+                    args: lowered,
+                    inferred_type: Type::Rust(RustType::Unit),
+                    source_language: Language::Python,
+                    cross_mapping: None,
+                    meta: meta.clone(),
+                }
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Lower a call
+    ///
+    /// Attribute callees become method calls on the attribute's object.
+    /// Keyword arguments have no Rust equivalent without the callee's
+    /// signature and are reported, except for the `key` of a sort and the
+    /// fields of a dataclass.
+    fn lower_call(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Call {
+            callee,
+            args,
+            kwargs,
+            inferred_type,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported(node));
+        };
+        if let Some(call) = self.lower_known_call(node)? {
+            return Ok(call);
+        }
+        if let Some((name, _)) = kwargs.first() {
+            let argument = name.as_ref().map_or_else(
+                || "`**` keyword splat".to_owned(),
+                |name| format!("keyword argument `{name}`"),
+            );
+            return Err(UnificationError::UnsupportedPython {
+                node_kind: format!("{argument} at {}", location(node)),
+            }
+            .into());
+        }
+        let source_language = Language::Python;
+        let pops = pops_list(callee, args);
+        let inferred_type = inferred_type.clone().unwrap_or(Type::Unknown);
+        // `util.slugify(name)` → `util::slugify(name)`, `Cart()` → `Cart::new()`
+        let path = match callee.as_ref() {
+            PythonHIR::Variable { name, .. } if self.classes.contains_key(name) => {
+                Some(format!("{name}::new"))
+            }
+            PythonHIR::Variable { name, .. } => Some(name.clone()),
+            _ => self.imports.module_path(callee),
+        };
+        let owned = path.is_some() || self.takes_strings(callee);
+        let args = args
+            .iter()
+            .map(|arg| {
+                if owned {
+                    self.lower_value(arg)
+                } else {
+                    self.lower_expr(arg)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let call = match (callee.as_ref(), path) {
+            (_, Some(path)) => UnifiedHIR::Call {
+                id: self.next_node_id(),
+                target_language: Language::Rust,
+                callee: path,
+                args,
+                inferred_type,
+                source_language,
+                cross_mapping: None,
+                meta: meta.clone(),
+            },
+            (PythonHIR::Attribute { object, attr, .. }, None) => UnifiedHIR::MethodCall {
+                id: self.next_node_id(),
+                receiver: Box::new(self.lower_expr(object)?),
+                method: rust_method_name(attr).to_owned(),
+                args,
+                inferred_type,
+                source_language,
+                meta: meta.clone(),
+            },
+            (other, None) => return Err(unsupported(other)),
+        };
+
+        // `xs.pop()` raises `IndexError` where Rust returns `None`
+        let on_none = if pops {
+            Some(Box::new(self.error_with_message(
+                "IndexError",
+                Some(EMPTY_POP_MESSAGE),
+                meta,
+            )))
+        } else if self.exceptions.call_can_raise(callee) {
+            None
+        } else {
+            return Ok(call);
+        };
+        Ok(UnifiedHIR::Propagate {
+            id: self.next_node_id(),
+            value: Box::new(call),
+            on_none,
+            source_language,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower a call with a dedicated Rust counterpart: resources, sorts,
+    /// dataclasses, enums, builtins and dict methods
+    ///
+    /// Returns `None` for any other call.
+    fn lower_known_call(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        if let Some(call) = self.lower_resource_call(node)? {
+            return Ok(Some(call));
+        }
+        if let Some(call) = self.lower_items_call(node)? {
+            return Ok(Some(call));
+        }
+        if let Some(call) = self.lower_sort_call(node)? {
+            return Ok(Some(call));
+        }
+        if let Some(call) = self.lower_dataclass_call(node)? {
+            return Ok(Some(call));
+        }
+        if let Some(call) = self.lower_enum_call(node)? {
+            return Ok(Some(call));
+        }
+        if let Some(call) = self.lower_builtin_call(node)? {
+            return Ok(Some(call));
+        }
+        self.lower_dict_get(node)
+    }
+
+    /// Whether a method takes `String` where Python passes `str`: methods
+    /// of the module's classes and the methods adding an item do
+    fn takes_strings(&self, callee: &PythonHIR) -> bool {
+        let PythonHIR::Attribute { object, attr, .. } = callee else {
+            return false;
+        };
+        matches!(attr.as_str(), "append" | "insert" | "add")
+            || matches!(expr_type(object), Type::Python(PythonType::Class(class))
+                if self.classes.contains_key(&class))
+    }
+
+    /// Lower `raise` into `return Err(..)`
+    ///
+    /// A bare `raise` re-raises the error bound by the enclosing handler.
+    fn lower_raise(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Raise {
+            exception,
+            cause,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported(node));
+        };
+        // `from None` only hides the context; a real cause has nowhere to go
+        if let Some(cause) = cause.as_deref() {
+            if !matches!(
+                cause,
+                PythonHIR::Literal {
+                    value: PythonLiteral::None,
+                    ..
+                }
+            ) {
+                return Err(unsupported_in("exception cause", cause));
+            }
+        }
+
+        let error = match exception.as_deref() {
+            None => {
+                let Some(binding) = self.exceptions.handlers.last().cloned() else {
+                    return Err(unsupported_in("bare `raise` outside `except`", node));
+                };
+                UnifiedHIR::Variable {
+                    id: self.next_node_id(),
+                    name: binding,
+                    var_type: error_type(),
+                    source_language: Language::Python,
+                    meta: meta.clone(),
+                }
+            }
+            Some(exception) => {
+                let Some(name) = exception_name(exception) else {
+                    return Err(unsupported(exception));
+                };
+                let message = match exception {
+                    PythonHIR::Call { args, kwargs, .. }
+                        if args.len() > 1 || !kwargs.is_empty() =>
+                    {
+                        return Err(unsupported_in(
+                            "exception with several arguments",
+                            exception,
+                        ));
+                    }
+                    PythonHIR::Call { args, .. } => match args.first() {
+                        Some(message) => Some(self.lower_expr(message)?),
+                        None => None,
+                    },
+                    _ => None,
+                };
+                self.error_value(name, message, meta)
+            }
+        };
+        Ok(self.return_variant("Err", Some(error), meta))
+    }
+
+    /// Lower a `try` statement
+    fn lower_try(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            meta,
+            ..
+        } = node
+        else {
+            return Err(unsupported(node));
+        };
+
+        // The body runs in a closure, and so does everything else but the
+        // finally clause when there is one
+        let mut enclosed: Vec<&PythonHIR> = body.iter().collect();
+        if !finalbody.is_empty() {
+            enclosed.extend(handlers.iter().flat_map(|handler| &handler.body));
+            enclosed.extend(orelse);
+        }
+        if let Some(exit) = enclosed
+            .into_iter()
+            .find_map(|statement| leaving_closure(statement, false))
+        {
+            return Err(unsupported_in("control flow out of `try`", exit));
+        }
+
+        let uncaught = self.exceptions.raised_by(body).iter().any(|exception| {
+            !handlers
+                .iter()
+                .any(|handler| self.exceptions.catches(handler, exception))
+        });
+        let propagates = !self
+            .exceptions
+            .raised_by(std::slice::from_ref(node))
+            .is_empty();
+
+        let lowered_body = self.lower_body(body)?;
+        let mut clauses = Vec::new();
+        for handler in handlers {
+            let variants = self.exceptions.caught_variants(handler);
+            let binding = handler
+                .name
+                .clone()
+                .or_else(|| reraises(&handler.body).then(|| "err".to_owned()));
+            self.exceptions
+                .handlers
+                .push(binding.clone().unwrap_or_default());
+            let body = self.lower_body(&handler.body);
+            self.exceptions.handlers.pop();
+            clauses.push(CatchClause {
+                variants: variants.clone().unwrap_or_default(),
+                binding,
+                body: body?,
+            });
+            // Later handlers could never run
+            if variants.is_none() {
+                break;
+            }
+        }
+        if clauses
+            .last()
+            .map_or(true, |clause| !clause.variants.is_empty())
+        {
+            clauses.push(self.fallback_clause(uncaught, meta));
+        }
+
+        Ok(UnifiedHIR::TryCatch {
+            id: self.next_node_id(),
+            body: lowered_body,
+            handlers: clauses,
+            orelse: self.lower_body(orelse)?,
+            finalbody: self.lower_body(finalbody)?,
+            error_type: ERROR_TYPE.to_owned(),
+            propagates,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// Handler for the errors no `except` clause catches
+    ///
+    /// They are re-raised when the body can raise them and otherwise cannot
+    /// occur, but the match must still cover them.
+    fn fallback_clause(&mut self, uncaught: bool, meta: &Metadata) -> CatchClause {
+        if uncaught {
+            let error = UnifiedHIR::Variable {
+                id: self.next_node_id(),
+                name: "err".to_owned(),
+                var_type: error_type(),
+                source_language: Language::Python,
+                meta: meta.clone(),
+            };
+            CatchClause {
+                variants: vec![],
+                binding: Some("err".to_owned()),
+                body: vec![self.return_variant("Err", Some(error), meta)],
+            }
+        } else {
+            CatchClause {
+                variants: vec![],
+                binding: None,
+                body: vec![UnifiedHIR::Call {
+                    id: self.next_node_id(),
+                    target_language: Language::Rust,
+                    callee: "unreachable!".to_owned(),
+                    args: vec![],
+                    inferred_type: Type::Unknown,
+                    source_language: Language::Python,
+                    cross_mapping: None,
+                    meta: meta.clone(),
+                }],
+            }
+        }
+    }
+
+    /// `return Ok(value)`, with `()` for a missing value
+    fn return_ok(&mut self, value: Option<UnifiedHIR>, meta: &Metadata) -> UnifiedHIR {
+        self.return_variant("Ok", value, meta)
+    }
+
+    /// Return from a fallible function
+    ///
+    /// A propagated error needs no `Ok(..?)` round trip: `return f()?`
+    /// returns the `Result` of `f()` itself, and an empty `pop` the
+    /// `Option` turned into one. Errors of `std` calls still go through
+    /// `?`, which converts them into the module's error type.
+    fn return_result(&mut self, value: Option<UnifiedHIR>, meta: &Metadata) -> UnifiedHIR {
+        let converts = matches!(&value, Some(UnifiedHIR::Propagate { value, .. })
+            if matches!(value.as_ref(), UnifiedHIR::Call { callee, .. } if callee.starts_with("std::")));
+        let (value, on_none) = match value {
+            Some(UnifiedHIR::Propagate { value, on_none, .. }) if !converts => (value, on_none),
+            other => return self.return_ok(other, meta),
+        };
+        let result = match on_none {
+            Some(error) => {
+                let error = UnifiedHIR::Closure {
+                    id: self.next_node_id(),
+                    params: vec![],
+                    return_type: Type::Unknown,
+                    body: vec![UnifiedHIR::Return {
+                        id: self.next_node_id(),
+                        value: Some(error),
+                        source_language: Language::Python,
+                        meta: meta.clone(),
+                    }],
+                    captures: vec![],
+                    kind: ClosureKind::Fn,
+                    moves: false,
+                    source_language: Language::Python,
+                    meta: meta.clone(),
+                };
+                self.method_call(*value, "ok_or_else", vec![error], meta)
+            }
+            None => *value,
+        };
+        UnifiedHIR::Return {
+            id: self.next_node_id(),
+            value: Some(Box::new(result)),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+
+    /// `return Ok(..)` or `return Err(..)`
+    fn return_variant(
+        &mut self,
+        variant: &str,
+        value: Option<UnifiedHIR>,
+        meta: &Metadata,
+    ) -> UnifiedHIR {
+        let value = value.unwrap_or_else(|| UnifiedHIR::Literal {
+            id: self.next_node_id(),
+            value: LiteralValue::Unit,
+            lit_type: Type::Rust(RustType::Unit),
+            meta: meta.clone(),
+        });
+        UnifiedHIR::Return {
+            id: self.next_node_id(),
+            value: Some(Box::new(UnifiedHIR::Call {
+                id: self.next_node_id(),
+                target_language: Language::Rust,
+                callee: variant.to_owned(),
+                args: vec![value],
+                inferred_type: Type::Unknown,
+                source_language: Language::Python,
+                cross_mapping: None,
+                meta: meta.clone(),
+            })),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }
+    }
+}
+
+/// Error for a Python construct that cannot be lowered yet
+pub(crate) fn unsupported(node: &PythonHIR) -> anyhow::Error {
+    let kind = format!("{node:?}");
+    let kind = kind.split([' ', '{', '(']).next().unwrap_or("Unknown");
+    unsupported_in(kind, node)
+}
+
+/// Error for an unsupported use of a construct, described by `what`
+pub(crate) fn unsupported_in(what: &str, node: &PythonHIR) -> anyhow::Error {
+    UnificationError::UnsupportedPython {
+        node_kind: format!("{what} at {}", location(node)),
+    }
+    .into()
+}
+
+/// Error for a type that is neither annotated nor inferred, described by
+/// `what`
+pub(crate) fn uninferred(what: &str, node: &PythonHIR) -> anyhow::Error {
+    Diagnostic::error(format!(
+        "cannot infer the type of {what}; add a type annotation"
+    ))
+    .with_location(node.metadata().source.clone())
+    .into()
+}
+
+/// The generated error enum's type
+fn error_type() -> Type {
+    Type::Rust(RustType::Custom(ERROR_TYPE.to_owned()))
+}
+
+/// `Result<ok, Error>`
+fn result_type(ok: Type) -> Type {
+    Type::Rust(RustType::Result {
+        ok: Box::new(ok),
+        err: Box::new(error_type()),
+    })
+}
+
+/// Where a node came from, for error messages
+fn location(node: &PythonHIR) -> String {
+    node.metadata()
+        .source
+        .as_ref()
+        .map_or_else(|| "<unknown>".to_owned(), ToString::to_string)
+}
+
+/// Lower a function parameter
+pub(crate) fn lower_parameter(param: &Parameter) -> UnifiedParameter {
+    let annotation = param.type_annotation.clone().unwrap_or(Type::Unknown);
+    let param_type = match param.kind {
+        ParameterKind::VarPositional => Type::Python(PythonType::List(Box::new(annotation))),
+        ParameterKind::VarKeyword => Type::Python(PythonType::Dict {
+            key: Box::new(Type::Python(PythonType::Str)),
+            value: Box::new(annotation),
+        }),
+        _ => annotation,
+    };
+    UnifiedParameter {
+        name: param.name.clone(),
+        param_type,
+        source_language: Language::Python,
+    }
+}
+
+/// Map a Python binary operator onto a unified one
+fn lower_bin_op(op: PythonBinOp) -> Result<BinOp> {
+    Ok(match op {
+        PythonBinOp::Add => BinOp::Add,
+        PythonBinOp::Sub => BinOp::Sub,
+        PythonBinOp::Mul => BinOp::Mul,
+        PythonBinOp::Div => BinOp::Div,
+        PythonBinOp::Mod => BinOp::Mod,
+        PythonBinOp::Eq => BinOp::Eq,
+        PythonBinOp::NotEq => BinOp::Ne,
+        PythonBinOp::Lt => BinOp::Lt,
+        PythonBinOp::Le => BinOp::Le,
+        PythonBinOp::Gt => BinOp::Gt,
+        PythonBinOp::Ge => BinOp::Ge,
+        PythonBinOp::And => BinOp::And,
+        PythonBinOp::Or => BinOp::Or,
+        PythonBinOp::BitAnd => BinOp::BitAnd,
+        PythonBinOp::BitOr => BinOp::BitOr,
+        PythonBinOp::BitXor => BinOp::BitXor,
+        PythonBinOp::LShift => BinOp::Shl,
+        PythonBinOp::RShift => BinOp::Shr,
+        other => {
+            return Err(UnificationError::UnsupportedPython {
+                node_kind: format!("operator {other:?}"),
+            }
+            .into())
+        }
+    })
+}
+
+/// Rust name of a Python method
+fn rust_method_name(method: &str) -> &str {
+    match method {
+        "append" => "push",
+        other => other,
+    }
+}
+
+/// The methods among the members of a class
+fn class_methods(body: &[PythonHIR]) -> Vec<&PythonHIR> {
+    body.iter()
+        .filter(|member| matches!(member, PythonHIR::Function { .. }))
+        .collect()
+}
+
+/// Whether a node is a string literal
+fn is_str_literal(node: &PythonHIR) -> bool {
+    matches!(
+        node,
+        PythonHIR::Literal {
+            value: PythonLiteral::Str(_),
+            ..
+        }
+    )
+}
+
+/// Whether an expression is known to be a dict
+fn is_dict(node: &PythonHIR) -> bool {
+    matches!(expr_type(node), Type::Python(PythonType::Dict { .. }))
+}
+
+/// Whether a node is the bare name `self`
+pub(crate) fn is_self(node: &PythonHIR) -> bool {
+    matches!(node, PythonHIR::Variable { name, .. } if name == "self")
+}
+
+/// Whether a node is `self.<field>`, `self.<field>[i]`, ...
+fn is_rooted_at_self(node: &PythonHIR) -> bool {
+    match node {
+        PythonHIR::Attribute { object, .. } | PythonHIR::Subscript { object, .. } => {
+            is_self(object) || is_rooted_at_self(object)
+        }
+        _ => false,
+    }
+}
+
+/// Visit a node and its descendants, without entering nested functions or classes
+pub(crate) fn walk<'a>(node: &'a PythonHIR, visit: &mut impl FnMut(&'a PythonHIR)) {
+    visit(node);
+    if matches!(node, PythonHIR::Function { .. } | PythonHIR::Class { .. }) {
+        return;
+    }
+    for child in node.children() {
+        walk(child, visit);
+    }
+}
+
+/// Visit every statement of a function body and their descendants,
+/// including the bodies of nested functions, which can use `self` too
+fn walk_body<'a>(function: &'a PythonHIR, visit: &mut impl FnMut(&'a PythonHIR)) {
+    if let PythonHIR::Function { body, .. } = function {
+        for statement in body {
+            walk(statement, &mut |node| {
+                visit(node);
+                if matches!(node, PythonHIR::Function { .. }) {
+                    walk_body(node, visit);
+                }
+            });
+        }
+    }
+}
+
+/// The first `return`, `break` or `continue` leaving a closure wrapped
+/// around a statement
+fn leaving_closure(node: &PythonHIR, in_loop: bool) -> Option<&PythonHIR> {
+    match node {
+        PythonHIR::Function { .. } | PythonHIR::Class { .. } => None,
+        PythonHIR::Return { .. } => Some(node),
+        PythonHIR::Break { .. } | PythonHIR::Continue { .. } if !in_loop => Some(node),
+        PythonHIR::For { .. } | PythonHIR::While { .. } => node
+            .children()
+            .into_iter()
+            .find_map(|child| leaving_closure(child, true)),
+        _ => node
+            .children()
+            .into_iter()
+            .find_map(|child| leaving_closure(child, in_loop)),
+    }
+}
+
+/// Whether a handler body contains a bare `raise`
+fn reraises(body: &[PythonHIR]) -> bool {
+    let mut found = false;
+    for statement in body {
+        walk(statement, &mut |node| {
+            found |= matches!(
+                node,
+                PythonHIR::Raise {
+                    exception: None,
+                    ..
+                }
+            );
+        });
+    }
+    found
+}
+
+/// Whether a statement (or anything nested in it) returns a value
+fn returns_value(node: &PythonHIR) -> bool {
+    let mut found = false;
+    walk(node, &mut |node| {
+        found |= matches!(node, PythonHIR::Return { value: Some(_), .. });
+    });
+    found
+}
+
+/// Type of the values an unannotated function returns: that of the first
+/// `return` whose value's type is fully known, or `Type::Unknown`
+///
+/// A method of `class` returning `self` returns the class.
+fn returned_type(body: &[PythonHIR], class: Option<&str>) -> Type {
+    let mut returned = Type::Unknown;
+    for statement in body {
+        walk(statement, &mut |node| {
+            if let PythonHIR::Return {
+                value: Some(value), ..
+            } = node
+            {
+                let ty = match (value.as_ref(), class) {
+                    (PythonHIR::Variable { name, .. }, Some(class)) if name == "self" => {
+                        Type::Python(PythonType::Class(class.to_owned()))
+                    }
+                    _ => expr_type(value),
+                };
+                if !is_known(&returned) && is_known(&ty) {
+                    returned = ty;
+                }
+            }
+        });
+    }
+    returned
+}
+
+/// Whether values of a type are `Copy` in Rust
+pub(crate) const fn is_copy(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::Python(PythonType::Int | PythonType::Float | PythonType::Bool)
+    )
+}
+
+/// Whether a type has no unknown parts left
+pub(crate) fn is_known(ty: &Type) -> bool {
+    match ty {
+        Type::Unknown => false,
+        Type::Python(
+            PythonType::List(item)
+            | PythonType::Set(item)
+            | PythonType::Optional(item)
+            | PythonType::Iterator(item),
+        ) => is_known(item),
+        Type::Python(PythonType::Dict { key, value }) => is_known(key) && is_known(value),
+        Type::Python(PythonType::Tuple(types)) => types.iter().all(is_known),
+        Type::Function {
+            params,
+            return_type,
+        } => params.iter().all(is_known) && is_known(return_type),
+        _ => true,
+    }
+}
+
+/// Collect the struct fields assigned through `self.<field> = ...`
+///
+/// `__init__` is scanned first so its assignments decide the field order.
+fn collect_fields(methods: &[&PythonHIR]) -> Vec<UnifiedField> {
+    let is_init = |method: &&&PythonHIR| matches!(method, PythonHIR::Function { name, .. } if name == "__init__");
+    let ordered = methods
+        .iter()
+        .filter(is_init)
+        .chain(methods.iter().filter(|method| !is_init(method)));
+
+    let mut fields: Vec<UnifiedField> = Vec::new();
+    for method in ordered {
+        walk_body(method, &mut |node| {
+            let (attr, field_type) = match node {
+                PythonHIR::Store {
+                    target,
+                    value,
+                    type_annotation,
+                    ..
+                } => match target.as_ref() {
+                    PythonHIR::Attribute { object, attr, .. } if is_self(object) => (
+                        attr,
+                        type_annotation.clone().unwrap_or_else(|| field_type(value)),
+                    ),
+                    // `self.<field>[key] = value` stores into a dict
+                    PythonHIR::Subscript { object, index, .. } => {
+                        let Some(attr) = self_field(object) else {
+                            return;
+                        };
+                        let dict = PythonType::Dict {
+                            key: Box::new(expr_type(index)),
+                            value: Box::new(expr_type(value)),
+                        };
+                        (attr, Type::Python(dict))
+                    }
+                    _ => return,
+                },
+                // `self.<field>.append(item)` fills in the item type
+                PythonHIR::Call { callee, args, .. } => {
+                    let (PythonHIR::Attribute { object, attr, .. }, [item]) =
+                        (callee.as_ref(), args.as_slice())
+                    else {
+                        return;
+                    };
+                    let Some(field) = self_field(object) else {
+                        return;
+                    };
+                    let item = Box::new(expr_type(item));
+                    match attr.as_str() {
+                        "append" => (field, Type::Python(PythonType::List(item))),
+                        "add" => (field, Type::Python(PythonType::Set(item))),
+                        _ => return,
+                    }
+                }
+                _ => return,
+            };
+            match fields.iter_mut().find(|field| field.name == *attr) {
+                Some(field) => refine_type(&mut field.field_type, &field_type),
+                None => fields.push(UnifiedField {
+                    name: attr.clone(),
+                    field_type,
+                }),
+            }
+        });
+    }
+    fields
+}
+
+/// Name of the field a `self.<field>` expression reads
+fn self_field(node: &PythonHIR) -> Option<&String> {
+    match node {
+        PythonHIR::Attribute { object, attr, .. } if is_self(object) => Some(attr),
+        _ => None,
+    }
+}
+
+/// Fill in the unknown parts of `ty` from a type of the same shape
+///
+/// A `None` first assigned to a field makes it optional, so an optional
+/// type is also filled in from the values it holds.
+fn refine_type(ty: &mut Type, from: &Type) {
+    match (ty, from) {
+        (ty @ Type::Unknown, from) => *ty = from.clone(),
+        (Type::Python(PythonType::List(item)), Type::Python(PythonType::List(from)))
+        | (Type::Python(PythonType::Set(item)), Type::Python(PythonType::Set(from)))
+        | (Type::Python(PythonType::Optional(item)), Type::Python(PythonType::Optional(from))) => {
+            refine_type(item, from);
+        }
+        (Type::Python(PythonType::Optional(item)), from)
+            if *from != Type::Python(PythonType::None) =>
+        {
+            refine_type(item, from);
+        }
+        (
+            Type::Python(PythonType::Dict { key, value }),
+            Type::Python(PythonType::Dict {
+                key: from_key,
+                value: from_value,
+            }),
+        ) => {
+            refine_type(key, from_key);
+            refine_type(value, from_value);
+        }
+        _ => {}
+    }
+}
+
+/// Type of a field from the value first assigned to it
+fn field_type(value: &PythonHIR) -> Type {
+    match expr_type(value) {
+        // `self.head = None` holds an optional value
+        Type::Python(PythonType::None) => {
+            Type::Python(PythonType::Optional(Box::new(Type::Unknown)))
+        }
+        other => other,
+    }
+}
+
+/// Best-effort static type of an expression
+pub(crate) fn expr_type(node: &PythonHIR) -> Type {
+    let first =
+        |elements: &[PythonHIR]| Box::new(elements.first().map_or(Type::Unknown, expr_type));
+    match node {
+        PythonHIR::Variable { inferred_type, .. }
+        | PythonHIR::Attribute { inferred_type, .. }
+        | PythonHIR::Call { inferred_type, .. }
+        | PythonHIR::Subscript { inferred_type, .. }
+        | PythonHIR::BinOp { inferred_type, .. } => {
+            inferred_type.clone().unwrap_or_else(|| match node {
+                PythonHIR::BinOp {
+                    op, left, right, ..
+                } => bin_op_type(*op, &expr_type(left), &expr_type(right)),
+                PythonHIR::Subscript { object, index, .. } => {
+                    subscript_type(&expr_type(object), index)
+                }
+                PythonHIR::Call { .. } if is_lock_call(node) => {
+                    Type::Python(PythonType::Class(LOCK_TYPE.to_owned()))
+                }
+                // `len(x)` and `str(x)` as `lower_builtin_call` lowers them
+                PythonHIR::Call { callee, .. } => match callee.as_ref() {
+                    PythonHIR::Variable { name, .. } if name == "len" => {
+                        Type::Python(PythonType::Int)
+                    }
+                    PythonHIR::Variable { name, .. } if name == "str" => {
+                        Type::Python(PythonType::Str)
+                    }
+                    _ => Type::Unknown,
+                },
+                _ => Type::Unknown,
+            })
+        }
+        PythonHIR::Literal { value, .. } => convert_python_literal(value).1,
+        PythonHIR::FormattedString { .. } => Type::Python(PythonType::Str),
+        PythonHIR::List { elements, .. } => Type::Python(PythonType::List(first(elements))),
+        PythonHIR::Set { elements, .. } => Type::Python(PythonType::Set(first(elements))),
+        PythonHIR::Tuple { elements, .. } => {
+            Type::Python(PythonType::Tuple(elements.iter().map(expr_type).collect()))
+        }
+        PythonHIR::ListComp {
+            element,
+            generators,
+            ..
+        } => Type::Python(PythonType::List(Box::new(comprehension_type(
+            element, generators,
+        )))),
+        PythonHIR::SetComp {
+            element,
+            generators,
+            ..
+        } => Type::Python(PythonType::Set(Box::new(comprehension_type(
+            element, generators,
+        )))),
+        PythonHIR::DictComp {
+            key,
+            value,
+            generators,
+            ..
+        } => Type::Python(PythonType::Dict {
+            key: Box::new(comprehension_type(key, generators)),
+            value: Box::new(comprehension_type(value, generators)),
+        }),
+        PythonHIR::Dict { entries, .. } => {
+            let (key, value) = entries
+                .first()
+                .map_or((Type::Unknown, Type::Unknown), |(key, value)| {
+                    (expr_type(key), expr_type(value))
+                });
+            Type::Python(PythonType::Dict {
+                key: Box::new(key),
+                value: Box::new(value),
+            })
+        }
+        _ => Type::Unknown,
+    }
+}
+
+/// Type of a binary operation on operands of the given types
+///
+/// Comparisons produce a `bool`, and arithmetic mixing `int` and `float`
+/// a `float`; otherwise the result has the left operand's type.
+fn bin_op_type(op: PythonBinOp, left: &Type, right: &Type) -> Type {
+    let float = Type::Python(PythonType::Float);
+    match op {
+        PythonBinOp::Eq
+        | PythonBinOp::NotEq
+        | PythonBinOp::Lt
+        | PythonBinOp::Le
+        | PythonBinOp::Gt
+        | PythonBinOp::Ge
+        | PythonBinOp::Is
+        | PythonBinOp::IsNot
+        | PythonBinOp::In
+        | PythonBinOp::NotIn => Type::Python(PythonType::Bool),
+        PythonBinOp::Add | PythonBinOp::Sub | PythonBinOp::Mul | PythonBinOp::Mod
+            if *right == float && *left == Type::Python(PythonType::Int) =>
+        {
+            float
+        }
+        _ => left.clone(),
+    }
+}
+
+/// Type of `object[index]` for an object of the given type
+fn subscript_type(object: &Type, index: &PythonHIR) -> Type {
+    match (object, index) {
+        (_, PythonHIR::Slice { .. }) => object.clone(),
+        (Type::Python(PythonType::List(item)), _) => item.as_ref().clone(),
+        (Type::Python(PythonType::Dict { value, .. }), _) => value.as_ref().clone(),
+        (Type::Python(PythonType::Str), _) => Type::Python(PythonType::Str),
+        (
+            Type::Python(PythonType::Tuple(types)),
+            PythonHIR::Literal {
+                value: PythonLiteral::Int(index),
+                ..
+            },
+        ) => usize::try_from(*index)
+            .ok()
+            .and_then(|index| types.get(index))
+            .cloned()
+            .unwrap_or(Type::Unknown),
+        _ => Type::Unknown,
+    }
+}
+
+/// Type of a comprehension's element, where each generator's target has
+/// the item type of its iterable
+fn comprehension_type(element: &PythonHIR, generators: &[Comprehension]) -> Type {
+    fn assign_type(node: &mut PythonHIR, target: &str, ty: &Type) {
+        if let PythonHIR::Variable {
+            name,
+            inferred_type: inferred_type @ None,
+            ..
+        } = node
+        {
+            if name == target {
+                *inferred_type = Some(ty.clone());
+            }
+        }
+        for child in node.children_mut() {
+            assign_type(child, target, ty);
+        }
+    }
+
+    let mut element = element.clone();
+    for generator in generators {
+        if let Target::Name(target) = &generator.target {
+            assign_type(
+                &mut element,
+                target,
+                &item_type(&expr_type(&generator.iter)),
+            );
+        }
+    }
+    expr_type(&element)
+}
+
+/// Methods that need `&mut self`
+///
+/// A method mutates when it writes through `self.<field>`, enters a context
+/// manager held in one of the `managers` fields, or calls another method
+/// that does; the latter is resolved to a fixed point.
+pub(crate) fn mutating_methods(
+    methods: &[&PythonHIR],
+    managers: &HashSet<String>,
+) -> HashSet<String> {
+    let method_name = |method: &PythonHIR| match method {
+        PythonHIR::Function { name, .. } => name.clone(),
+        _ => String::new(),
+    };
+
+    let mut mutating: HashSet<String> = methods
+        .iter()
+        .filter(|method| mutates_self(method, managers))
+        .map(|method| method_name(method))
+        .collect();
+
+    loop {
+        let before = mutating.len();
+        for method in methods {
+            if !mutating.contains(&method_name(method)) && calls_any(method, &mutating) {
+                mutating.insert(method_name(method));
+            }
+        }
+        if mutating.len() == before {
+            return mutating;
+        }
+    }
+}
+
+/// Whether a method writes through `self.<field>` or enters a manager field
+fn mutates_self(method: &PythonHIR, managers: &HashSet<String>) -> bool {
+    let mut mutates = false;
+    walk_body(method, &mut |node| {
+        mutates |= match node {
+            PythonHIR::Store { target, .. } | PythonHIR::AugAssign { target, .. } => {
+                is_rooted_at_self(target)
+            }
+            PythonHIR::Delete { targets, .. } => targets.iter().any(is_rooted_at_self),
+            PythonHIR::With { items, .. } => items.iter().any(|item| {
+                matches!(item.context.as_ref(), PythonHIR::Attribute { object, attr, .. }
+                    if is_self(object) && managers.contains(attr))
+            }),
+            PythonHIR::Call { callee, .. } => match callee.as_ref() {
+                PythonHIR::Attribute { object, attr, .. } => {
+                    MUTATING_METHODS.contains(&attr.as_str()) && is_rooted_at_self(object)
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+    });
+    mutates
+}
+
+/// Whether a method calls `self.<name>()` for any of the given names
+fn calls_any(method: &PythonHIR, names: &HashSet<String>) -> bool {
+    let mut calls = false;
+    walk_body(method, &mut |node| {
+        if let PythonHIR::Call { callee, .. } = node {
+            if let PythonHIR::Attribute { object, attr, .. } = callee.as_ref() {
+                calls |= is_self(object) && names.contains(attr);
+            }
+        }
+    });
+    calls
+}
+
+/// Rewrite `self.<field>` into a local named `<field>`
+///
+/// Used for `__init__`, where the fields are built as locals before the
+/// struct exists.
+fn detach_self(node: &mut PythonHIR) {
+    if matches!(node, PythonHIR::Function { .. } | PythonHIR::Class { .. }) {
+        return;
+    }
+    for child in node.children_mut() {
+        detach_self(child);
+    }
+
+    let replacement = match node {
+        PythonHIR::Attribute {
+            id,
+            object,
+            attr,
+            inferred_type,
+            meta,
+        } if is_self(object) => PythonHIR::Variable {
+            id: *id,
+            name: attr.clone(),
+            inferred_type: inferred_type.clone(),
+            meta: meta.clone(),
+        },
+        PythonHIR::Store {
+            id,
+            target,
+            value,
+            type_annotation,
+            meta,
+        } => match target.as_ref() {
+            PythonHIR::Variable { name, .. } => PythonHIR::Assign {
+                id: *id,
+                target: Target::Name(name.clone()),
+                value: value.clone(),
+                type_annotation: type_annotation.clone(),
+                meta: meta.clone(),
+            },
+            _ => return,
+        },
+        _ => return,
+    };
+    *node = replacement;
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::{python::Literal, unified::Pattern, NodeId, Visibility};
+
+    fn var(name: &str) -> PythonHIR {
+        PythonHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn attr(object: PythonHIR, attr: &str) -> PythonHIR {
+        PythonHIR::Attribute {
+            id: NodeId::new(0),
+            object: Box::new(object),
+            attr: attr.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn call(callee: PythonHIR, args: Vec<PythonHIR>) -> PythonHIR {
+        PythonHIR::Call {
+            id: NodeId::new(0),
+            callee: Box::new(callee),
+            args,
+            kwargs: vec![],
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn method(name: &str, params: &[&str], body: Vec<PythonHIR>) -> PythonHIR {
+        PythonHIR::Function {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            params: params
+                .iter()
+                .map(|param| Parameter {
+                    name: (*param).to_owned(),
+                    kind: ParameterKind::Positional,
+                    type_annotation: None,
+                    default: None,
+                })
+                .collect(),
+            return_type: None,
+            body,
+            decorators: vec![],
+            visibility: Visibility::Public,
+            meta: Metadata::new(),
+        }
+    }
+
+    /// ```python
+    /// class Cart:
+    ///     def __init__(self): self.items = []; self.total = 0
+    ///     def add(self, item): self.items.append(item)
+    ///     def add_twice(self, item): self.add(item); self.add(item)
+    ///     def count(self): return len(self.items)
+    /// ```
+    fn cart_class() -> PythonHIR {
+        let store = |field: &str, value: PythonHIR| PythonHIR::Store {
+            id: NodeId::new(0),
+            target: Box::new(attr(var("self"), field)),
+            value: Box::new(value),
+            type_annotation: None,
+            meta: Metadata::new(),
+        };
+        PythonHIR::Class {
+            id: NodeId::new(0),
+            name: "Cart".to_owned(),
+            bases: vec![],
+            body: vec![
+                method(
+                    "__init__",
+                    &["self"],
+                    vec![
+                        store(
+                            "items",
+                            PythonHIR::List {
+                                id: NodeId::new(0),
+                                elements: vec![],
+                                inferred_type: None,
+                                meta: Metadata::new(),
+                            },
+                        ),
+                        store(
+                            "total",
+                            PythonHIR::Literal {
+                                id: NodeId::new(0),
+                                value: Literal::Int(0),
+                                meta: Metadata::new(),
+                            },
+                        ),
+                    ],
+                ),
+                method(
+                    "add",
+                    &["self", "item"],
+                    vec![call(
+                        attr(attr(var("self"), "items"), "append"),
+                        vec![var("item")],
+                    )],
+                ),
+                method(
+                    "add_twice",
+                    &["self", "item"],
+                    vec![
+                        call(attr(var("self"), "add"), vec![var("item")]),
+                        call(attr(var("self"), "add"), vec![var("item")]),
+                    ],
+                ),
+                method(
+                    "count",
+                    &["self"],
+                    vec![PythonHIR::Return {
+                        id: NodeId::new(0),
+                        value: Some(Box::new(call(var("len"), vec![attr(var("self"), "items")]))),
+                        meta: Metadata::new(),
+                    }],
+                ),
+            ],
+            decorators: vec![],
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_lower_class_into_struct_and_impl() {
+        let module = PythonHIR::Module {
+            name: "cart".to_owned(),
+            body: vec![cart_class()],
+            meta: Metadata::new(),
+        };
+        let UnifiedHIR::Module { declarations, .. } = Unifier::new()
+            .lower_python(&module)
+            .expect("Should lower module")
+        else {
+            panic!("Expected Module");
+        };
+
+        let UnifiedHIR::Struct { name, fields, .. } = &declarations[0] else {
+            panic!("Expected Struct");
+        };
+        assert_eq!(name, "Cart");
+        let fields: Vec<(&str, String)> = fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.field_type.to_string()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![("items", "list[?]".to_owned()), ("total", "int".to_owned())]
+        );
+
+        let UnifiedHIR::Impl { methods, .. } = &declarations[1] else {
+            panic!("Expected Impl");
+        };
+        let signatures: Vec<(&str, Option<Receiver>)> = methods
+            .iter()
+            .map(|method| match method {
+                UnifiedHIR::Function { name, receiver, .. } => (name.as_str(), *receiver),
+                other => panic!("Expected Function, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            signatures,
+            vec![
+                ("new", None),
+                ("add", Some(Receiver::RefMut)),
+                ("add_twice", Some(Receiver::RefMut)),
+                ("count", Some(Receiver::Ref)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constructor_builds_self_from_locals() {
+        let class = cart_class();
+        let PythonHIR::Class { body, .. } = &class else {
+            panic!("Expected Class");
+        };
+        let methods: Vec<&PythonHIR> = body.iter().collect();
+        let info = ClassInfo {
+            fields: collect_fields(&methods),
+            mutating: mutating_methods(&methods, &HashSet::new()),
+        };
+
+        let new = Unifier::new()
+            .lower_function(methods[0], Some(&info))
+            .expect("Should lower __init__");
+        let UnifiedHIR::Function { params, body, .. } = new else {
+            panic!("Expected Function");
+        };
+        assert!(params.is_empty(), "self is not a parameter of new()");
+        assert!(
+            matches!(&body[0], UnifiedHIR::Assign { target, .. } if *target == Pattern::name("items"))
+        );
+        let Some(UnifiedHIR::StructInit { name, fields, .. }) = body.last() else {
+            panic!("Expected trailing Self literal");
+        };
+        assert_eq!(name, "Self");
+        assert_eq!(fields.len(), 2);
+    }
+
+    #[test]
+    fn test_pop_makes_function_fallible() {
+        // def last(xs) -> int: return xs.pop()
+        let mut function = method(
+            "last",
+            &["xs"],
+            vec![PythonHIR::Return {
+                id: NodeId::new(0),
+                value: Some(Box::new(call(attr(var("xs"), "pop"), vec![]))),
+                meta: Metadata::new(),
+            }],
+        );
+        if let PythonHIR::Function { return_type, .. } = &mut function {
+            *return_type = Some(Type::Python(PythonType::Int));
+        }
+        let UnifiedHIR::Function {
+            return_type, body, ..
+        } = Unifier::new()
+            .lower_python(&function)
+            .expect("Should lower function")
+        else {
+            panic!("Expected Function");
+        };
+
+        assert_eq!(
+            return_type.to_string(),
+            "Result<int, Error>",
+            "A function that can raise returns Result"
+        );
+        // Popping mutates the parameter, which is rebound `mut` first
+        let [UnifiedHIR::Assign { mutable, .. }, UnifiedHIR::Return {
+            value: Some(value), ..
+        }] = body.as_slice()
+        else {
+            panic!("Expected a rebinding and a return, got {body:?}");
+        };
+        assert_eq!(mutable, &["xs"]);
+        // The `Option` becomes the returned `Result`, not `Ok(..?)`
+        let UnifiedHIR::MethodCall {
+            receiver, method, ..
+        } = value.as_ref()
+        else {
+            panic!("Expected xs.pop().ok_or_else(..), got {value:?}");
+        };
+        assert_eq!(method, "ok_or_else");
+        assert!(matches!(
+            receiver.as_ref(),
+            UnifiedHIR::MethodCall { method, .. } if method == "pop"
+        ));
+    }
+
+    #[test]
+    fn test_bare_raise_outside_except_is_reported() {
+        let function = method(
+            "fail",
+            &[],
+            vec![PythonHIR::Raise {
+                id: NodeId::new(0),
+                exception: None,
+                cause: None,
+                meta: Metadata::new(),
+            }],
+        );
+        let error = Unifier::new()
+            .lower_python(&function)
+            .expect_err("Bare raise needs an enclosing handler");
+        assert!(error.to_string().contains("bare `raise`"), "{error}");
+    }
+
+    #[test]
+    fn test_unsupported_statement_is_reported() {
+        let module = PythonHIR::Module {
+            name: "m".to_owned(),
+            body: vec![var("stray")],
+            meta: Metadata::new(),
+        };
+        let error = Unifier::new()
+            .lower_python(&module)
+            .expect_err("Stray expression should be rejected");
+        assert!(error.to_string().contains("Variable"), "{error}");
+    }
+}
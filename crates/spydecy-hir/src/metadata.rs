@@ -54,6 +54,20 @@ impl Metadata {
         self
     }
 
+    /// Append another node's documentation, separated by a blank line
+    ///
+    /// Used when nodes from both languages are merged into one.
+    pub fn merge_docs(&mut self, other: &Self) {
+        let Some(other) = other.docs.as_deref() else {
+            return;
+        };
+        self.docs = Some(match self.docs.take() {
+            Some(docs) if !docs.contains(other) => format!("{docs}\n\n{other}"),
+            Some(docs) => docs,
+            None => other.to_owned(),
+        });
+    }
+
     /// Add an attribute
     pub fn add_attribute(&mut self, attr: Attribute) {
         self.attributes.push(attr);
@@ -150,6 +164,37 @@ impl CrossRef {
     }
 }
 
+/// Clean up a docstring the way Python's `inspect.cleandoc` does
+///
+/// Leading whitespace is stripped from the first line and the common
+/// indentation of the others removed, as is trailing whitespace and blank
+/// lines at either end. Returns `None` for blank text.
+#[must_use]
+pub fn cleandoc(text: &str) -> Option<String> {
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest: Vec<&str> = lines.collect();
+    // Continuation lines share the indentation of the enclosing block
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut cleaned: Vec<&str> = std::iter::once(first)
+        .chain(
+            rest.iter()
+                .map(|line| line.get(indent..).unwrap_or_default().trim_end()),
+        )
+        .collect();
+    while cleaned.last().is_some_and(|line| line.is_empty()) {
+        cleaned.pop();
+    }
+    let start = cleaned.iter().position(|line| !line.is_empty())?;
+    Some(cleaned[start..].join("\n"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,6 +213,15 @@ mod tests {
         assert_eq!(meta.docs, Some("Test documentation".to_owned()));
     }
 
+    #[test]
+    fn test_merge_docs() {
+        let mut meta = Metadata::new().with_docs("Python docstring".to_owned());
+        meta.merge_docs(&Metadata::new());
+        meta.merge_docs(&Metadata::new().with_docs("C comment".to_owned()));
+        meta.merge_docs(&Metadata::new().with_docs("C comment".to_owned()));
+        assert_eq!(meta.docs.as_deref(), Some("Python docstring\n\nC comment"));
+    }
+
     #[test]
     fn test_attribute_creation() {
         let attr = Attribute::new("staticmethod".to_owned());
@@ -64,8 +64,8 @@ pub enum PythonHIR {
         callee: Box<PythonHIR>,
         /// Arguments
         args: Vec<PythonHIR>,
-        /// Keyword arguments
-        kwargs: Vec<(String, PythonHIR)>,
+        /// Keyword arguments (`None` for a `**mapping` splat)
+        kwargs: Vec<(Option<String>, PythonHIR)>,
         /// Inferred type
         inferred_type: Option<Type>,
         /// Metadata
@@ -88,8 +88,8 @@ pub enum PythonHIR {
     Assign {
         /// Node ID
         id: NodeId,
-        /// Target variable
-        target: String,
+        /// Target variable, or tuple of targets to unpack into
+        target: Target,
         /// Value being assigned
         value: Box<PythonHIR>,
         /// Type annotation
@@ -98,6 +98,46 @@ pub enum PythonHIR {
         meta: Metadata,
     },
 
+    /// Assignment to an attribute or subscript (`obj.attr = v`, `xs[i] = v`)
+    Store {
+        /// Node ID
+        id: NodeId,
+        /// Target expression
+        target: Box<PythonHIR>,
+        /// Value being assigned
+        value: Box<PythonHIR>,
+        /// Type annotation (`self.items: list[int] = []`)
+        type_annotation: Option<Type>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Augmented assignment (`x += v`)
+    AugAssign {
+        /// Node ID
+        id: NodeId,
+        /// Target expression
+        target: Box<PythonHIR>,
+        /// Operator
+        op: BinOp,
+        /// Value
+        value: Box<PythonHIR>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Annotated declaration without a value (`x: int`)
+    Declaration {
+        /// Node ID
+        id: NodeId,
+        /// Declared name
+        name: String,
+        /// Type annotation
+        type_annotation: Type,
+        /// Metadata
+        meta: Metadata,
+    },
+
     /// Return statement
     Return {
         /// Node ID
@@ -126,8 +166,8 @@ pub enum PythonHIR {
     For {
         /// Node ID
         id: NodeId,
-        /// Loop variable
-        target: String,
+        /// Loop variable, or tuple of variables
+        target: Target,
         /// Iterable
         iter: Box<PythonHIR>,
         /// Loop body
@@ -152,6 +192,157 @@ pub enum PythonHIR {
         meta: Metadata,
     },
 
+    /// `break` statement
+    Break {
+        /// Node ID
+        id: NodeId,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `continue` statement
+    Continue {
+        /// Node ID
+        id: NodeId,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `pass` statement
+    Pass {
+        /// Node ID
+        id: NodeId,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `del` statement
+    Delete {
+        /// Node ID
+        id: NodeId,
+        /// Deleted targets
+        targets: Vec<PythonHIR>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `assert` statement
+    Assert {
+        /// Node ID
+        id: NodeId,
+        /// Asserted condition
+        test: Box<PythonHIR>,
+        /// Failure message
+        msg: Option<Box<PythonHIR>>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `global` declaration
+    Global {
+        /// Node ID
+        id: NodeId,
+        /// Declared names
+        names: Vec<String>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `nonlocal` declaration
+    Nonlocal {
+        /// Node ID
+        id: NodeId,
+        /// Declared names
+        names: Vec<String>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `import a.b as c` or `from ..pkg import name as alias`
+    Import {
+        /// Node ID
+        id: NodeId,
+        /// Module of a `from` import as written after the dots (empty for
+        /// `from . import x`); `None` for `import a.b`
+        from: Option<String>,
+        /// Leading dots of a relative `from` import
+        level: usize,
+        /// Imported modules or names
+        names: Vec<ImportName>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `raise` statement
+    Raise {
+        /// Node ID
+        id: NodeId,
+        /// Raised exception (`None` re-raises the one being handled)
+        exception: Option<Box<PythonHIR>>,
+        /// Explicit cause (`raise ... from cause`)
+        cause: Option<Box<PythonHIR>>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `yield` expression
+    Yield {
+        /// Node ID
+        id: NodeId,
+        /// Yielded value (`None` yields `None`)
+        value: Option<Box<PythonHIR>>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `yield from` expression
+    YieldFrom {
+        /// Node ID
+        id: NodeId,
+        /// Iterable delegated to
+        value: Box<PythonHIR>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `try` statement
+    Try {
+        /// Node ID
+        id: NodeId,
+        /// Protected body
+        body: Vec<PythonHIR>,
+        /// `except` clauses
+        handlers: Vec<ExceptHandler>,
+        /// Else clause, run when the body raised nothing
+        orelse: Vec<PythonHIR>,
+        /// Finally clause
+        finalbody: Vec<PythonHIR>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `with` statement
+    With {
+        /// Node ID
+        id: NodeId,
+        /// Context managers, entered in order
+        items: Vec<WithItem>,
+        /// Body
+        body: Vec<PythonHIR>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// f-string (`f"{value:spec}"`)
+    FormattedString {
+        /// Node ID
+        id: NodeId,
+        /// Literal text and replacement fields, in order
+        parts: Vec<FormatPart>,
+        /// Metadata
+        meta: Metadata,
+    },
+
     /// Binary operation
     BinOp {
         /// Node ID
@@ -192,6 +383,82 @@ pub enum PythonHIR {
         meta: Metadata,
     },
 
+    /// Conditional expression (`a if cond else b`)
+    IfExp {
+        /// Node ID
+        id: NodeId,
+        /// Condition
+        condition: Box<PythonHIR>,
+        /// Value when the condition holds
+        body: Box<PythonHIR>,
+        /// Value otherwise
+        orelse: Box<PythonHIR>,
+        /// Inferred type
+        inferred_type: Option<Type>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Lambda expression (`lambda x: x + 1`)
+    Lambda {
+        /// Node ID
+        id: NodeId,
+        /// Parameters
+        params: Vec<Parameter>,
+        /// Body expression
+        body: Box<PythonHIR>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// List display (`[a, b]`)
+    List {
+        /// Node ID
+        id: NodeId,
+        /// Elements
+        elements: Vec<PythonHIR>,
+        /// Inferred type
+        inferred_type: Option<Type>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Tuple display (`(a, b)`)
+    Tuple {
+        /// Node ID
+        id: NodeId,
+        /// Elements
+        elements: Vec<PythonHIR>,
+        /// Inferred type
+        inferred_type: Option<Type>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Set display (`{a, b}`)
+    Set {
+        /// Node ID
+        id: NodeId,
+        /// Elements
+        elements: Vec<PythonHIR>,
+        /// Inferred type
+        inferred_type: Option<Type>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Dict display (`{k: v}`)
+    Dict {
+        /// Node ID
+        id: NodeId,
+        /// Key/value pairs
+        entries: Vec<(PythonHIR, PythonHIR)>,
+        /// Inferred type
+        inferred_type: Option<Type>,
+        /// Metadata
+        meta: Metadata,
+    },
+
     /// List comprehension
     ListComp {
         /// Node ID
@@ -204,6 +471,54 @@ pub enum PythonHIR {
         meta: Metadata,
     },
 
+    /// Set comprehension
+    SetComp {
+        /// Node ID
+        id: NodeId,
+        /// Element expression
+        element: Box<PythonHIR>,
+        /// Generators
+        generators: Vec<Comprehension>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Dict comprehension
+    DictComp {
+        /// Node ID
+        id: NodeId,
+        /// Key expression
+        key: Box<PythonHIR>,
+        /// Value expression
+        value: Box<PythonHIR>,
+        /// Generators
+        generators: Vec<Comprehension>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Generator expression
+    GeneratorExp {
+        /// Node ID
+        id: NodeId,
+        /// Element expression
+        element: Box<PythonHIR>,
+        /// Generators
+        generators: Vec<Comprehension>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Iterable splat (`*args`) in a call or display
+    Starred {
+        /// Node ID
+        id: NodeId,
+        /// Splatted value
+        value: Box<PythonHIR>,
+        /// Metadata
+        meta: Metadata,
+    },
+
     /// Attribute access (obj.attr)
     Attribute {
         /// Node ID
@@ -231,6 +546,20 @@ pub enum PythonHIR {
         /// Metadata
         meta: Metadata,
     },
+
+    /// Slice (`lower:upper:step`) used as a subscript index
+    Slice {
+        /// Node ID
+        id: NodeId,
+        /// Lower bound
+        lower: Option<Box<PythonHIR>>,
+        /// Upper bound
+        upper: Option<Box<PythonHIR>>,
+        /// Step
+        step: Option<Box<PythonHIR>>,
+        /// Metadata
+        meta: Metadata,
+    },
 }
 
 /// Function parameter
@@ -238,12 +567,29 @@ pub enum PythonHIR {
 pub struct Parameter {
     /// Parameter name
     pub name: String,
+    /// Parameter kind
+    pub kind: ParameterKind,
     /// Type annotation
     pub type_annotation: Option<Type>,
-    /// Default value
+    /// Default value (Python source text)
     pub default: Option<String>,
 }
 
+/// How a parameter is bound at the call site
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParameterKind {
+    /// Before `/` - positional only
+    PositionalOnly,
+    /// Positional or keyword
+    Positional,
+    /// After `*` or `*args` - keyword only
+    KeywordOnly,
+    /// `*args`
+    VarPositional,
+    /// `**kwargs`
+    VarKeyword,
+}
+
 /// Binary operator
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BinOp {
@@ -277,6 +623,26 @@ pub enum BinOp {
     And,
     /// or
     Or,
+    /// &
+    BitAnd,
+    /// |
+    BitOr,
+    /// ^
+    BitXor,
+    /// <<
+    LShift,
+    /// >>
+    RShift,
+    /// @
+    MatMult,
+    /// is
+    Is,
+    /// is not
+    IsNot,
+    /// in
+    In,
+    /// not in
+    NotIn,
 }
 
 /// Unary operator
@@ -288,6 +654,8 @@ pub enum UnaryOp {
     Neg,
     /// +
     Pos,
+    /// ~
+    Invert,
 }
 
 /// Literal value
@@ -299,23 +667,159 @@ pub enum Literal {
     Float(f64),
     /// String
     Str(String),
+    /// Bytes
+    Bytes(Vec<u8>),
     /// Boolean
     Bool(bool),
     /// None
     None,
 }
 
-/// List comprehension generator
+/// Target of an assignment, `for` loop or comprehension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target {
+    /// Variable
+    Name(String),
+    /// Tuple or list of targets unpacked in order (`a, (b, c) = ...`)
+    Tuple(Vec<Target>),
+    /// Starred target collecting the remaining items (`*rest`)
+    Starred(String),
+}
+
+impl Target {
+    /// Variable target
+    #[must_use]
+    pub fn name(name: impl Into<String>) -> Self {
+        Self::Name(name.into())
+    }
+
+    /// The variable of a plain `name = ...` target
+    #[must_use]
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            Self::Name(name) => Some(name),
+            Self::Tuple(_) | Self::Starred(_) => None,
+        }
+    }
+
+    /// Variables bound by the target, in order
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Self::Name(name) | Self::Starred(name) => vec![name],
+            Self::Tuple(targets) => targets.iter().flat_map(Self::names).collect(),
+        }
+    }
+}
+
+/// Comprehension generator (`for target in iter if cond`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Comprehension {
-    /// Target variable
-    pub target: String,
+    /// Target variable, or tuple of variables
+    pub target: Target,
     /// Iterable
     pub iter: Box<PythonHIR>,
     /// Filters
     pub ifs: Vec<PythonHIR>,
 }
 
+impl Comprehension {
+    /// The iterable followed by the filters
+    fn children(&self) -> impl Iterator<Item = &PythonHIR> {
+        std::iter::once(self.iter.as_ref()).chain(self.ifs.iter())
+    }
+
+    /// Mutable references to the iterable and the filters
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut PythonHIR> {
+        std::iter::once(self.iter.as_mut()).chain(self.ifs.iter_mut())
+    }
+}
+
+/// `except` clause of a `try` statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExceptHandler {
+    /// Caught exception type, or a tuple of types (`None` catches everything)
+    pub exception_type: Option<Box<PythonHIR>>,
+    /// Name the exception is bound to (`except E as name`)
+    pub name: Option<String>,
+    /// Handler body
+    pub body: Vec<PythonHIR>,
+    /// Metadata
+    pub meta: Metadata,
+}
+
+impl ExceptHandler {
+    /// The exception type followed by the body
+    fn children(&self) -> impl Iterator<Item = &PythonHIR> {
+        self.exception_type
+            .iter()
+            .map(Box::as_ref)
+            .chain(self.body.iter())
+    }
+
+    /// Mutable references to the exception type and the body
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut PythonHIR> {
+        self.exception_type
+            .iter_mut()
+            .map(Box::as_mut)
+            .chain(self.body.iter_mut())
+    }
+}
+
+/// Context manager entered by a `with` statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithItem {
+    /// Expression producing the context manager
+    pub context: Box<PythonHIR>,
+    /// Name the result of `__enter__` is bound to (`with ... as name`)
+    pub target: Option<String>,
+}
+
+/// Module or name of an `import` statement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportName {
+    /// Dotted module path, or the name imported from a module (`*` for all)
+    pub name: String,
+    /// Name it is bound to (`as alias`)
+    pub alias: Option<String>,
+}
+
+/// Piece of an f-string
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FormatPart {
+    /// Literal text
+    Text(String),
+    /// Replacement field (`{value!conversion:spec}`)
+    Field {
+        /// Formatted expression
+        value: Box<PythonHIR>,
+        /// Conversion applied first (`s`, `r` or `a`)
+        conversion: Option<char>,
+        /// Format spec, itself an f-string since it may hold fields
+        spec: Option<Box<PythonHIR>>,
+    },
+}
+
+impl FormatPart {
+    /// The formatted expression followed by the spec
+    fn children(&self) -> impl Iterator<Item = &PythonHIR> {
+        let (value, spec) = match self {
+            Self::Text(_) => (None, None),
+            Self::Field { value, spec, .. } => (Some(value.as_ref()), spec.as_deref()),
+        };
+        value.into_iter().chain(spec)
+    }
+
+    /// Mutable references to the formatted expression and the spec
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut PythonHIR> {
+        let (value, spec) = match self {
+            Self::Text(_) => (None, None),
+            Self::Field { value, spec, .. } => (Some(value.as_mut()), spec.as_deref_mut()),
+        };
+        value.into_iter().chain(spec)
+    }
+}
+
 impl PythonHIR {
     /// Get the node ID if present
     #[must_use]
@@ -327,16 +831,44 @@ impl PythonHIR {
             | Self::Call { id, .. }
             | Self::Variable { id, .. }
             | Self::Assign { id, .. }
+            | Self::Store { id, .. }
+            | Self::AugAssign { id, .. }
             | Self::Return { id, .. }
             | Self::If { id, .. }
             | Self::For { id, .. }
             | Self::While { id, .. }
+            | Self::Break { id, .. }
+            | Self::Continue { id, .. }
+            | Self::Pass { id, .. }
+            | Self::Declaration { id, .. }
+            | Self::Delete { id, .. }
+            | Self::Assert { id, .. }
+            | Self::Global { id, .. }
+            | Self::Nonlocal { id, .. }
+            | Self::Import { id, .. }
+            | Self::Raise { id, .. }
+            | Self::Yield { id, .. }
+            | Self::YieldFrom { id, .. }
+            | Self::Try { id, .. }
+            | Self::With { id, .. }
+            | Self::FormattedString { id, .. }
             | Self::BinOp { id, .. }
             | Self::UnaryOp { id, .. }
             | Self::Literal { id, .. }
+            | Self::IfExp { id, .. }
+            | Self::Lambda { id, .. }
+            | Self::List { id, .. }
+            | Self::Tuple { id, .. }
+            | Self::Set { id, .. }
+            | Self::Dict { id, .. }
             | Self::ListComp { id, .. }
+            | Self::SetComp { id, .. }
+            | Self::DictComp { id, .. }
+            | Self::GeneratorExp { id, .. }
+            | Self::Starred { id, .. }
             | Self::Attribute { id, .. }
-            | Self::Subscript { id, .. } => Some(*id),
+            | Self::Subscript { id, .. }
+            | Self::Slice { id, .. } => Some(*id),
         }
     }
 
@@ -350,16 +882,335 @@ impl PythonHIR {
             | Self::Call { meta, .. }
             | Self::Variable { meta, .. }
             | Self::Assign { meta, .. }
+            | Self::Store { meta, .. }
+            | Self::AugAssign { meta, .. }
             | Self::Return { meta, .. }
             | Self::If { meta, .. }
             | Self::For { meta, .. }
             | Self::While { meta, .. }
+            | Self::Break { meta, .. }
+            | Self::Continue { meta, .. }
+            | Self::Pass { meta, .. }
+            | Self::Declaration { meta, .. }
+            | Self::Delete { meta, .. }
+            | Self::Assert { meta, .. }
+            | Self::Global { meta, .. }
+            | Self::Nonlocal { meta, .. }
+            | Self::Import { meta, .. }
+            | Self::Raise { meta, .. }
+            | Self::Yield { meta, .. }
+            | Self::YieldFrom { meta, .. }
+            | Self::Try { meta, .. }
+            | Self::With { meta, .. }
+            | Self::FormattedString { meta, .. }
             | Self::BinOp { meta, .. }
             | Self::UnaryOp { meta, .. }
             | Self::Literal { meta, .. }
+            | Self::IfExp { meta, .. }
+            | Self::Lambda { meta, .. }
+            | Self::List { meta, .. }
+            | Self::Tuple { meta, .. }
+            | Self::Set { meta, .. }
+            | Self::Dict { meta, .. }
             | Self::ListComp { meta, .. }
+            | Self::SetComp { meta, .. }
+            | Self::DictComp { meta, .. }
+            | Self::GeneratorExp { meta, .. }
+            | Self::Starred { meta, .. }
             | Self::Attribute { meta, .. }
-            | Self::Subscript { meta, .. } => meta,
+            | Self::Subscript { meta, .. }
+            | Self::Slice { meta, .. } => meta,
+        }
+    }
+
+    /// Get the direct child nodes, in source order
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn children(&self) -> Vec<&PythonHIR> {
+        match self {
+            Self::Module { body, .. } | Self::Class { body, .. } | Self::Function { body, .. } => {
+                body.iter().collect()
+            }
+            Self::Call {
+                callee,
+                args,
+                kwargs,
+                ..
+            } => std::iter::once(callee.as_ref())
+                .chain(args.iter())
+                .chain(kwargs.iter().map(|(_, value)| value))
+                .collect(),
+            Self::Assign { value, .. } => vec![value],
+            Self::Store { target, value, .. } | Self::AugAssign { target, value, .. } => {
+                vec![target, value]
+            }
+            Self::Return { value, .. } | Self::Yield { value, .. } => {
+                value.iter().map(Box::as_ref).collect()
+            }
+            Self::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => std::iter::once(condition.as_ref())
+                .chain(then_branch.iter())
+                .chain(else_branch.iter())
+                .collect(),
+            Self::For {
+                iter, body, orelse, ..
+            } => std::iter::once(iter.as_ref())
+                .chain(body.iter())
+                .chain(orelse.iter())
+                .collect(),
+            Self::While {
+                condition,
+                body,
+                orelse,
+                ..
+            } => std::iter::once(condition.as_ref())
+                .chain(body.iter())
+                .chain(orelse.iter())
+                .collect(),
+            Self::Delete {
+                targets: elements, ..
+            }
+            | Self::List { elements, .. }
+            | Self::Tuple { elements, .. }
+            | Self::Set { elements, .. } => elements.iter().collect(),
+            Self::Assert { test, msg, .. } => std::iter::once(test.as_ref())
+                .chain(msg.iter().map(Box::as_ref))
+                .collect(),
+            Self::Raise {
+                exception, cause, ..
+            } => [exception, cause]
+                .into_iter()
+                .flatten()
+                .map(Box::as_ref)
+                .collect(),
+            Self::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => body
+                .iter()
+                .chain(handlers.iter().flat_map(ExceptHandler::children))
+                .chain(orelse.iter())
+                .chain(finalbody.iter())
+                .collect(),
+            Self::With { items, body, .. } => items
+                .iter()
+                .map(|item| item.context.as_ref())
+                .chain(body.iter())
+                .collect(),
+            Self::FormattedString { parts, .. } => {
+                parts.iter().flat_map(FormatPart::children).collect()
+            }
+            Self::BinOp { left, right, .. } => vec![left, right],
+            Self::UnaryOp { operand, .. }
+            | Self::Starred { value: operand, .. }
+            | Self::Lambda { body: operand, .. } => vec![operand],
+            Self::IfExp {
+                condition,
+                body,
+                orelse,
+                ..
+            } => vec![condition, body, orelse],
+            Self::Dict { entries, .. } => entries
+                .iter()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            Self::ListComp {
+                element,
+                generators,
+                ..
+            }
+            | Self::SetComp {
+                element,
+                generators,
+                ..
+            }
+            | Self::GeneratorExp {
+                element,
+                generators,
+                ..
+            } => std::iter::once(element.as_ref())
+                .chain(generators.iter().flat_map(Comprehension::children))
+                .collect(),
+            Self::DictComp {
+                key,
+                value,
+                generators,
+                ..
+            } => [key.as_ref(), value.as_ref()]
+                .into_iter()
+                .chain(generators.iter().flat_map(Comprehension::children))
+                .collect(),
+            Self::Attribute { object, .. } | Self::YieldFrom { value: object, .. } => vec![object],
+            Self::Subscript { object, index, .. } => vec![object, index],
+            Self::Slice {
+                lower, upper, step, ..
+            } => [lower, upper, step]
+                .into_iter()
+                .flatten()
+                .map(Box::as_ref)
+                .collect(),
+            Self::Variable { .. }
+            | Self::Break { .. }
+            | Self::Continue { .. }
+            | Self::Pass { .. }
+            | Self::Declaration { .. }
+            | Self::Global { .. }
+            | Self::Nonlocal { .. }
+            | Self::Import { .. }
+            | Self::Literal { .. } => vec![],
+        }
+    }
+
+    /// Get mutable references to the direct child nodes
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn children_mut(&mut self) -> Vec<&mut PythonHIR> {
+        match self {
+            Self::Module { body, .. } | Self::Class { body, .. } | Self::Function { body, .. } => {
+                body.iter_mut().collect()
+            }
+            Self::Call {
+                callee,
+                args,
+                kwargs,
+                ..
+            } => std::iter::once(callee.as_mut())
+                .chain(args.iter_mut())
+                .chain(kwargs.iter_mut().map(|(_, value)| value))
+                .collect(),
+            Self::Assign { value, .. } => vec![value],
+            Self::Store { target, value, .. } | Self::AugAssign { target, value, .. } => {
+                vec![target, value]
+            }
+            Self::Return { value, .. } | Self::Yield { value, .. } => {
+                value.iter_mut().map(Box::as_mut).collect()
+            }
+            Self::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => std::iter::once(condition.as_mut())
+                .chain(then_branch.iter_mut())
+                .chain(else_branch.iter_mut())
+                .collect(),
+            Self::For {
+                iter, body, orelse, ..
+            } => std::iter::once(iter.as_mut())
+                .chain(body.iter_mut())
+                .chain(orelse.iter_mut())
+                .collect(),
+            Self::While {
+                condition,
+                body,
+                orelse,
+                ..
+            } => std::iter::once(condition.as_mut())
+                .chain(body.iter_mut())
+                .chain(orelse.iter_mut())
+                .collect(),
+            Self::Delete {
+                targets: elements, ..
+            }
+            | Self::List { elements, .. }
+            | Self::Tuple { elements, .. }
+            | Self::Set { elements, .. } => elements.iter_mut().collect(),
+            Self::Assert { test, msg, .. } => std::iter::once(test.as_mut())
+                .chain(msg.iter_mut().map(Box::as_mut))
+                .collect(),
+            Self::Raise {
+                exception, cause, ..
+            } => [exception, cause]
+                .into_iter()
+                .flatten()
+                .map(Box::as_mut)
+                .collect(),
+            Self::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => body
+                .iter_mut()
+                .chain(handlers.iter_mut().flat_map(ExceptHandler::children_mut))
+                .chain(orelse.iter_mut())
+                .chain(finalbody.iter_mut())
+                .collect(),
+            Self::With { items, body, .. } => items
+                .iter_mut()
+                .map(|item| item.context.as_mut())
+                .chain(body.iter_mut())
+                .collect(),
+            Self::FormattedString { parts, .. } => parts
+                .iter_mut()
+                .flat_map(FormatPart::children_mut)
+                .collect(),
+            Self::BinOp { left, right, .. } => vec![left, right],
+            Self::UnaryOp { operand, .. }
+            | Self::Starred { value: operand, .. }
+            | Self::Lambda { body: operand, .. } => vec![operand],
+            Self::IfExp {
+                condition,
+                body,
+                orelse,
+                ..
+            } => vec![condition, body, orelse],
+            Self::Dict { entries, .. } => entries
+                .iter_mut()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            Self::ListComp {
+                element,
+                generators,
+                ..
+            }
+            | Self::SetComp {
+                element,
+                generators,
+                ..
+            }
+            | Self::GeneratorExp {
+                element,
+                generators,
+                ..
+            } => std::iter::once(element.as_mut())
+                .chain(generators.iter_mut().flat_map(Comprehension::children_mut))
+                .collect(),
+            Self::DictComp {
+                key,
+                value,
+                generators,
+                ..
+            } => [key.as_mut(), value.as_mut()]
+                .into_iter()
+                .chain(generators.iter_mut().flat_map(Comprehension::children_mut))
+                .collect(),
+            Self::Attribute { object, .. } | Self::YieldFrom { value: object, .. } => vec![object],
+            Self::Subscript { object, index, .. } => vec![object, index],
+            Self::Slice {
+                lower, upper, step, ..
+            } => [lower, upper, step]
+                .into_iter()
+                .flatten()
+                .map(Box::as_mut)
+                .collect(),
+            Self::Variable { .. }
+            | Self::Break { .. }
+            | Self::Continue { .. }
+            | Self::Pass { .. }
+            | Self::Declaration { .. }
+            | Self::Global { .. }
+            | Self::Nonlocal { .. }
+            | Self::Import { .. }
+            | Self::Literal { .. } => vec![],
         }
     }
 }
@@ -57,6 +57,12 @@ pub enum PythonType {
     Tuple(Vec<Type>),
     /// set[T]
     Set(Box<Type>),
+    /// Optional[T] (`T | None`)
+    Optional(Box<Type>),
+    /// Iterator[T] (also `Iterable[T]` and `Generator[T, ...]`)
+    Iterator(Box<Type>),
+    /// Union[T1, T2, ...]
+    Union(Vec<Type>),
     /// None
     None,
     /// Any (dynamic)
@@ -170,6 +176,16 @@ pub enum RustType {
     Custom(String),
     /// Unit type ()
     Unit,
+    /// `impl Fn(T1, T2) -> R`, or `impl FnMut(..)` for a closure that
+    /// mutates its captures
+    Closure {
+        /// Whether calling the closure mutates it (`FnMut`)
+        mutable: bool,
+        /// Parameter types
+        params: Vec<Type>,
+        /// Return type
+        return_type: Box<Type>,
+    },
 }
 
 /// Integer size
@@ -257,6 +273,17 @@ impl fmt::Display for PythonType {
                 write!(f, "]")
             }
             Self::Set(inner) => write!(f, "set[{inner}]"),
+            Self::Optional(inner) => write!(f, "Optional[{inner}]"),
+            Self::Iterator(inner) => write!(f, "Iterator[{inner}]"),
+            Self::Union(types) => {
+                for (i, t) in types.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{t}")?;
+                }
+                Ok(())
+            }
             Self::None => write!(f, "None"),
             Self::Any => write!(f, "Any"),
             Self::Class(name) => write!(f, "{name}"),
@@ -345,6 +372,20 @@ impl fmt::Display for RustType {
             }
             Self::Custom(name) => write!(f, "{name}"),
             Self::Unit => write!(f, "()"),
+            Self::Closure {
+                mutable,
+                params,
+                return_type,
+            } => {
+                write!(f, "impl {}(", if *mutable { "FnMut" } else { "Fn" })?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{param}")?;
+                }
+                write!(f, ") -> {return_type}")
+            }
         }
     }
 }
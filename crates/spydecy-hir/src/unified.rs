@@ -17,21 +17,32 @@
 //!
 //! The unifier recognizes Python-C patterns:
 //! - `len()` + `list_length()` → `Vec::len()`
-//! - `append()` + `PyList_Append()` → `Vec::push()`
+//! - `list.append()` + `PyList_Append()` → `Vec::push()`
 //! - `dict.get()` + `PyDict_GetItem()` → `HashMap::get()`
 //!
 //! These patterns can be extended via the Pluggable C-API Architecture.
 
 use crate::{
-    c::CHIR,
+    c::{CTypes, CHIR},
+    closures::ClosureScope,
+    context_managers::ContextModel,
+    dataclasses::DataclassModel,
+    enums::EnumModel,
     error::{extract_c_fn_name, extract_python_fn_name, find_similar_patterns, UnificationError},
+    exceptions::{c_error_convention, visit_c, ExceptionModel},
+    generators::GeneratorContext,
+    imports::{ImportModel, SymbolTable},
     metadata::Metadata,
-    python::PythonHIR,
-    types::Type,
+    python::{Literal as PythonLiteral, PythonHIR},
+    types::{CPythonType, CType, IntSize, PythonType, RustType, Type},
     Language, NodeId,
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+};
 
 /// Unified HIR node - combines Python and C into a single representation
 #[allow(clippy::module_name_repetitions)]
@@ -55,6 +66,8 @@ pub enum UnifiedHIR {
         id: NodeId,
         /// Function name
         name: String,
+        /// How a method takes `self` (`None` for free and associated functions)
+        receiver: Option<Receiver>,
         /// Parameters
         params: Vec<UnifiedParameter>,
         /// Return type
@@ -108,17 +121,31 @@ pub enum UnifiedHIR {
         /// Node ID
         id: NodeId,
         /// Target
-        target: String,
+        target: Pattern,
         /// Value
         value: Box<UnifiedHIR>,
         /// Type
         var_type: Type,
+        /// Bound variables that are reassigned or mutated later (`let mut`)
+        mutable: Vec<String>,
         /// Source language
         source_language: Language,
         /// Metadata
         meta: Metadata,
     },
 
+    /// Local declared before the block that first assigns it (`let x;`)
+    Declare {
+        /// Node ID
+        id: NodeId,
+        /// Variable name
+        name: String,
+        /// Whether it can be assigned more than once
+        mutable: bool,
+        /// Metadata
+        meta: Metadata,
+    },
+
     /// Return statement
     Return {
         /// Node ID
@@ -190,6 +217,617 @@ pub enum UnifiedHIR {
         /// Metadata
         meta: Metadata,
     },
+
+    /// Unary operation
+    UnaryOp {
+        /// Node ID
+        id: NodeId,
+        /// Operator
+        op: UnaryOp,
+        /// Operand
+        operand: Box<UnifiedHIR>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Numeric conversion (`value as T`)
+    Cast {
+        /// Node ID
+        id: NodeId,
+        /// Converted value
+        value: Box<UnifiedHIR>,
+        /// Target type
+        target_type: Type,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Struct definition (from a Python class)
+    Struct {
+        /// Node ID
+        id: NodeId,
+        /// Struct name
+        name: String,
+        /// Fields
+        fields: Vec<UnifiedField>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Inherent impl block holding a struct's methods
+    Impl {
+        /// Node ID
+        id: NodeId,
+        /// Implemented type
+        type_name: String,
+        /// Methods (`Function` nodes)
+        methods: Vec<UnifiedHIR>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Enum definition (from an `enum.Enum` subclass)
+    ///
+    /// The enum gets `value`, `name` and `from_value` methods mapping its
+    /// variants to and from the Python members' values.
+    Enum {
+        /// Node ID
+        id: NodeId,
+        /// Enum name
+        name: String,
+        /// Variants, in definition order
+        variants: Vec<EnumVariant>,
+        /// Type of the members' values
+        value_type: Type,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Struct literal (`Name { field: value, .. }`)
+    StructInit {
+        /// Node ID
+        id: NodeId,
+        /// Struct name (or `Self`)
+        name: String,
+        /// Field initialisers, in declaration order
+        fields: Vec<(String, UnifiedHIR)>,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Field access (`object.field`)
+    FieldAccess {
+        /// Node ID
+        id: NodeId,
+        /// Object
+        object: Box<UnifiedHIR>,
+        /// Field name
+        field: String,
+        /// Field type
+        field_type: Type,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Index (`object[index]`)
+    Index {
+        /// Node ID
+        id: NodeId,
+        /// Indexed object
+        object: Box<UnifiedHIR>,
+        /// Index
+        index: Box<UnifiedHIR>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Method call (`receiver.method(args)`)
+    MethodCall {
+        /// Node ID
+        id: NodeId,
+        /// Receiver
+        receiver: Box<UnifiedHIR>,
+        /// Method name (already mapped to its Rust name)
+        method: String,
+        /// Arguments
+        args: Vec<UnifiedHIR>,
+        /// Inferred type
+        inferred_type: Type,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Assignment to a field or index (`object.field = value`)
+    Store {
+        /// Node ID
+        id: NodeId,
+        /// Target (`FieldAccess` or `Index`)
+        target: Box<UnifiedHIR>,
+        /// Value
+        value: Box<UnifiedHIR>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Compound assignment (`target += value`)
+    AugAssign {
+        /// Node ID
+        id: NodeId,
+        /// Target
+        target: Box<UnifiedHIR>,
+        /// Operator
+        op: BinOp,
+        /// Value
+        value: Box<UnifiedHIR>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// List literal
+    List {
+        /// Node ID
+        id: NodeId,
+        /// Elements
+        elements: Vec<UnifiedHIR>,
+        /// List type
+        list_type: Type,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Tuple literal
+    Tuple {
+        /// Node ID
+        id: NodeId,
+        /// Elements
+        elements: Vec<UnifiedHIR>,
+        /// Tuple type
+        tuple_type: Type,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Dict literal
+    Dict {
+        /// Node ID
+        id: NodeId,
+        /// Key/value pairs
+        entries: Vec<(UnifiedHIR, UnifiedHIR)>,
+        /// Dict type
+        dict_type: Type,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Comprehension or generator expression
+    Comprehension {
+        /// Node ID
+        id: NodeId,
+        /// What the comprehension builds
+        kind: ComprehensionKind,
+        /// Element expression (the key, for dict comprehensions)
+        element: Box<UnifiedHIR>,
+        /// Value expression, for dict comprehensions
+        value: Option<Box<UnifiedHIR>>,
+        /// `for` clauses, outermost first
+        clauses: Vec<ComprehensionClause>,
+        /// Result type
+        result_type: Type,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `break`
+    Break {
+        /// Node ID
+        id: NodeId,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `continue`
+    Continue {
+        /// Node ID
+        id: NodeId,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Error enum generated from the exceptions a module raises and catches
+    ErrorEnum {
+        /// Node ID
+        id: NodeId,
+        /// Enum name
+        name: String,
+        /// One variant per exception, carrying its message
+        variants: Vec<ErrorVariant>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `try` statement: the body runs in a `Result`-returning closure whose
+    /// error is matched against the handlers
+    TryCatch {
+        /// Node ID
+        id: NodeId,
+        /// Protected body
+        body: Vec<UnifiedHIR>,
+        /// Handlers, in source order (a catch-all handler comes last)
+        handlers: Vec<CatchClause>,
+        /// Statements run when the body raised nothing
+        orelse: Vec<UnifiedHIR>,
+        /// Statements run however the `try` statement is left
+        finalbody: Vec<UnifiedHIR>,
+        /// Error enum name
+        error_type: String,
+        /// Whether an error can escape the statement
+        propagates: bool,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Error propagation (`value?`)
+    Propagate {
+        /// Node ID
+        id: NodeId,
+        /// `Result`, or `Option` when `on_none` is given
+        value: Box<UnifiedHIR>,
+        /// Error for a `None` value
+        on_none: Option<Box<UnifiedHIR>>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Body of a generator function, producing an iterator
+    ///
+    /// The setup runs when the generator is created; the stages then run
+    /// in order, one loop iteration at a time, as items are requested.
+    Generator {
+        /// Node ID
+        id: NodeId,
+        /// Statements run before the first item is requested
+        setup: Vec<UnifiedHIR>,
+        /// Parameters and setup locals the stages assign to
+        mutable: Vec<String>,
+        /// Stages run on demand
+        stages: Vec<GeneratorStage>,
+        /// Type of the yielded items
+        item_type: Type,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `yield` inside a generator stage
+    Yield {
+        /// Node ID
+        id: NodeId,
+        /// Yielded value, or iterable for `yield from`
+        value: Box<UnifiedHIR>,
+        /// Whether every item of `value` is yielded (`yield from`)
+        delegate: bool,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Move a generator to another stage and resume it
+    ///
+    /// `return` finishes the generator by advancing past the last stage.
+    Advance {
+        /// Node ID
+        id: NodeId,
+        /// Index of the stage to run next
+        stage: usize,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// String formatting (`format!`)
+    Format {
+        /// Node ID
+        id: NodeId,
+        /// Rust format string
+        template: String,
+        /// Arguments referenced by the template
+        args: Vec<UnifiedHIR>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Block holding guards that are dropped when it ends (`with`)
+    Scope {
+        /// Node ID
+        id: NodeId,
+        /// Guards, bound in order and dropped in reverse order
+        guards: Vec<ScopeGuard>,
+        /// Locals assigned by the body but visible after it, as
+        /// `(name, mutable)`; they are declared before the block
+        hoisted: Vec<(String, bool)>,
+        /// Body
+        body: Vec<UnifiedHIR>,
+        /// Value the block evaluates to, when used as an expression
+        value: Option<Box<UnifiedHIR>>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Closure, from a lambda or a nested function
+    Closure {
+        /// Node ID
+        id: NodeId,
+        /// Parameters
+        params: Vec<UnifiedParameter>,
+        /// Return type
+        return_type: Type,
+        /// Body
+        body: Vec<UnifiedHIR>,
+        /// Variables of the enclosing functions the body uses
+        captures: Vec<Capture>,
+        /// Trait the closure implements
+        kind: ClosureKind,
+        /// Whether the closure takes ownership of its captures (`move`),
+        /// because it outlives the enclosing function
+        moves: bool,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Guard type returned by a context manager's `enter` method
+    ///
+    /// The guard mutably borrows the manager, dereferences to it and calls
+    /// its `exit` method when dropped.
+    GuardType {
+        /// Node ID
+        id: NodeId,
+        /// Guard struct name
+        name: String,
+        /// Context manager type
+        target: String,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// Module-level `static` initialized by a constant expression
+    Static {
+        /// Node ID
+        id: NodeId,
+        /// Static name, as written in the source
+        name: String,
+        /// Static type
+        static_type: Type,
+        /// Initializer
+        value: Box<UnifiedHIR>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+
+    /// `use crate::<path> as <alias>;` bringing an item of another module
+    /// into scope (see [`crate::imports`])
+    Use {
+        /// Node ID
+        id: NodeId,
+        /// Path from the crate root; `*` as the last segment imports all
+        path: Vec<String>,
+        /// Name the item is bound to, when not the last path segment
+        alias: Option<String>,
+        /// Source language
+        source_language: Language,
+        /// Metadata
+        meta: Metadata,
+    },
+}
+
+/// What a comprehension builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComprehensionKind {
+    /// `[x for x in xs]` → `Vec`
+    List,
+    /// `{x for x in xs}` → `HashSet`
+    Set,
+    /// `{k: v for k in ks}` → `HashMap`
+    Dict,
+    /// `(x for x in xs)` → lazy iterator
+    Generator,
+}
+
+/// Variable of an enclosing function used by a closure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capture {
+    /// Variable name
+    pub name: String,
+    /// Whether the closure assigns to or mutates the variable
+    pub mutable: bool,
+}
+
+/// Closure trait, from how the closure uses its captures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClosureKind {
+    /// Only reads its captures
+    Fn,
+    /// Mutates a capture, so calling it needs `&mut`
+    FnMut,
+}
+
+/// Binding pattern of an assignment, `for` loop or comprehension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pattern {
+    /// Variable
+    Name(String),
+    /// Tuple destructuring (`(a, (b, c))`)
+    Tuple(Vec<Pattern>),
+    /// Destructuring of a sequence whose length is only known at runtime
+    /// (`[first, rest @ .., last]`)
+    Slice {
+        /// Patterns for the leading items
+        before: Vec<Pattern>,
+        /// Variable collecting the items in between (`*rest`)
+        rest: Option<String>,
+        /// Patterns for the trailing items
+        after: Vec<Pattern>,
+    },
+}
+
+impl Pattern {
+    /// Variable pattern
+    #[must_use]
+    pub fn name(name: impl Into<String>) -> Self {
+        Self::Name(name.into())
+    }
+
+    /// Variables bound by the pattern, in order
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Self::Name(name) => vec![name],
+            Self::Tuple(patterns) => patterns.iter().flat_map(Self::names).collect(),
+            Self::Slice {
+                before,
+                rest,
+                after,
+            } => before
+                .iter()
+                .flat_map(Self::names)
+                .chain(rest.as_deref())
+                .chain(after.iter().flat_map(Self::names))
+                .collect(),
+        }
+    }
+}
+
+/// One `for target in iter if filter` clause of a comprehension
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComprehensionClause {
+    /// Loop variable
+    pub target: Pattern,
+    /// Iterable
+    pub iter: UnifiedHIR,
+    /// `if` filters
+    pub filters: Vec<UnifiedHIR>,
+}
+
+/// Variant of a generated error enum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorVariant {
+    /// Exception name
+    pub name: String,
+    /// Exception class docstring
+    pub docs: Option<String>,
+}
+
+/// Variant of an enum, from a member of an `enum.Enum` subclass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumVariant {
+    /// Variant name (`DarkBlue`)
+    pub name: String,
+    /// Python member name (`DARK_BLUE`)
+    pub member: String,
+    /// Member value
+    pub value: LiteralValue,
+}
+
+/// One stage of a `Generator`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GeneratorStage {
+    /// Statements run once
+    Run(Vec<UnifiedHIR>),
+    /// `for` loop resumed one iteration at a time; the iterable is
+    /// evaluated during setup
+    For {
+        /// Loop variable
+        target: Pattern,
+        /// Iterable
+        iter: UnifiedHIR,
+        /// Loop body
+        body: Vec<UnifiedHIR>,
+    },
+    /// `while` loop resumed one iteration at a time
+    While {
+        /// Loop condition
+        condition: UnifiedHIR,
+        /// Loop body
+        body: Vec<UnifiedHIR>,
+    },
+    /// `yield from iter`, one item at a time; the iterable is evaluated
+    /// during setup
+    Delegate(UnifiedHIR),
+}
+
+/// Local binding a `Scope` holds until it ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScopeGuard {
+    /// Variable name
+    pub name: String,
+    /// Whether the binding is `mut`
+    pub mutable: bool,
+    /// Bound value
+    pub value: UnifiedHIR,
+}
+
+/// One handler of a `TryCatch`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatchClause {
+    /// Error enum variants caught (empty catches every error)
+    pub variants: Vec<String>,
+    /// Name the caught error is bound to
+    pub binding: Option<String>,
+    /// Handler body
+    pub body: Vec<UnifiedHIR>,
+}
+
+/// How a method receives `self`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Receiver {
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+}
+
+/// Struct field
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnifiedField {
+    /// Field name
+    pub name: String,
+    /// Field type
+    pub field_type: Type,
 }
 
 /// Unified parameter (bridges Python and C parameters)
@@ -252,7 +890,7 @@ pub enum LoopKind {
     /// Python for loop / C for loop
     For {
         /// Loop variable
-        target: String,
+        target: Pattern,
         /// Iterable/range
         iter: Box<UnifiedHIR>,
     },
@@ -292,6 +930,31 @@ pub enum BinOp {
     And,
     /// Logical
     Or,
+    /// Bitwise
+    BitAnd,
+    /// Bitwise
+    BitOr,
+    /// Bitwise
+    BitXor,
+    /// Shift
+    Shl,
+    /// Shift
+    Shr,
+}
+
+/// Unary operator (unified)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnaryOp {
+    /// Logical not
+    Not,
+    /// Negation
+    Neg,
+    /// Bitwise not
+    BitNot,
+    /// Shared borrow (`&`)
+    Ref,
+    /// Mutable borrow (`&mut`)
+    RefMut,
 }
 
 /// Literal value (unified)
@@ -303,23 +966,79 @@ pub enum LiteralValue {
     Float(f64),
     /// String
     Str(String),
+    /// Bytes
+    Bytes(Vec<u8>),
     /// Boolean
     Bool(bool),
     /// None/NULL
     None,
+    /// The unit value `()`
+    Unit,
 }
 
 /// Unifier - converts Python + C HIR into Unified HIR
 pub struct Unifier {
     /// Next node ID
     next_id: u64,
+    /// Exceptions of the Python code being lowered
+    pub(crate) exceptions: ExceptionModel,
+    /// Generator whose stages are being lowered
+    pub(crate) generator: Option<GeneratorContext>,
+    /// Context managers of the Python code being lowered
+    pub(crate) contexts: ContextModel,
+    /// Variables closures can capture, inside a function
+    pub(crate) closures: Option<ClosureScope>,
+    /// Dataclasses of the Python code being lowered
+    pub(crate) dataclasses: DataclassModel,
+    /// Enumerations of the Python code being lowered
+    pub(crate) enums: EnumModel,
+    /// Classes of the Python code being lowered, other than exceptions and
+    /// enums, with their methods that need `&mut self`
+    pub(crate) classes: BTreeMap<String, HashSet<String>>,
+    /// Types returned by the functions of the Python code being lowered,
+    /// where annotated or, for generators, inferred
+    pub(crate) functions: BTreeMap<String, Type>,
+    /// Modules of the project being lowered
+    pub(crate) symbols: SymbolTable,
+    /// Imports of the module being lowered
+    pub(crate) imports: ImportModel,
+    /// Types declared by the C code being unified
+    pub(crate) c_types: CTypes,
 }
 
 impl Unifier {
     /// Create a new unifier
     #[must_use]
     pub const fn new() -> Self {
-        Self { next_id: 1 }
+        Self {
+            next_id: 1,
+            exceptions: ExceptionModel::new(),
+            generator: None,
+            contexts: ContextModel::new(),
+            closures: None,
+            dataclasses: DataclassModel::new(),
+            enums: EnumModel::new(),
+            classes: BTreeMap::new(),
+            functions: BTreeMap::new(),
+            symbols: SymbolTable::new(),
+            imports: ImportModel::new(),
+            c_types: CTypes::empty(),
+        }
+    }
+
+    /// Lower modules of a project, whose imports of each other become
+    /// `use` declarations
+    #[must_use]
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Unify with C functions of a translation unit declaring these types
+    #[must_use]
+    pub fn with_c_types(mut self, c_types: CTypes) -> Self {
+        self.c_types = c_types;
+        self
     }
 
     /// Unify a Python HIR node with a C HIR node
@@ -332,6 +1051,61 @@ impl Unifier {
     /// Returns an error if the Python and C HIR nodes cannot be unified
     /// (i.e., no known pattern matches the combination).
     pub fn unify(&mut self, python: &PythonHIR, c: &CHIR) -> Result<UnifiedHIR> {
+        let mut unified = self.unify_pattern(python, c)?;
+        // `pop()` and `get()` produce the C function's value, if any
+        if let UnifiedHIR::Call {
+            inferred_type: Type::Rust(RustType::Option(value_type)),
+            ..
+        } = &mut unified
+        {
+            if **value_type == Type::Unknown {
+                if let Some(ty) = self.c_value_type(c) {
+                    **value_type = ty;
+                }
+            }
+        }
+        if let Some(convention) = c_error_convention(c) {
+            unified = self.propagate_c_error(unified, &convention);
+        }
+        // Reviewers of the generated code see the intent from both sides
+        let meta = unified.metadata_mut();
+        meta.merge_docs(python.metadata());
+        meta.merge_docs(c.metadata());
+        Ok(unified)
+    }
+
+    /// Rust type of the values a C function returns
+    ///
+    /// Typedefs are resolved. A function declared to return a pointer, as
+    /// `PyObject *`, is typed by the struct fields its `return`s read.
+    fn c_value_type(&self, function: &CHIR) -> Option<Type> {
+        let CHIR::Function {
+            return_type,
+            params,
+            body,
+            ..
+        } = function
+        else {
+            return None;
+        };
+        let mut value_type = c_scalar_type(&self.c_types.resolve(return_type));
+        for statement in body {
+            visit_c(statement, &mut |node| {
+                if let CHIR::Return {
+                    value: Some(value), ..
+                } = node
+                {
+                    value_type = value_type
+                        .take()
+                        .or_else(|| c_scalar_type(&self.c_types.expr_type(value, params)?));
+                }
+            });
+        }
+        value_type
+    }
+
+    /// Match a Python HIR node and a C HIR node against the known patterns
+    fn unify_pattern(&mut self, python: &PythonHIR, c: &CHIR) -> Result<UnifiedHIR> {
         // Pattern matching for known Python-C relationships
         match (python, c) {
             // Pattern 1: Python len() → C list_length() → Rust Vec::len()
@@ -340,11 +1114,30 @@ impl Unifier {
                 PythonHIR::Call {
                     callee: py_callee,
                     args: py_args,
+                    kwargs,
                     ..
                 },
                 CHIR::Function { name: c_name, .. },
             ) => {
-                if let PythonHIR::Variable { name: py_name, .. } = py_callee.as_ref() {
+                // `cart.append(item)` is matched with the attribute's object as
+                // the receiver, which the patterns expect as the first argument.
+                let (py_name, py_args): (Option<&str>, Cow<'_, [PythonHIR]>) =
+                    match py_callee.as_ref() {
+                        PythonHIR::Variable { name, .. } => (Some(name), Cow::Borrowed(py_args)),
+                        PythonHIR::Attribute { object, attr, .. } => {
+                            let mut receiver_args = Vec::with_capacity(py_args.len() + 1);
+                            receiver_args.push(object.as_ref().clone());
+                            receiver_args.extend_from_slice(py_args);
+                            (Some(attr), Cow::Owned(receiver_args))
+                        }
+                        _ => (None, Cow::Borrowed(py_args)),
+                    };
+                if let Some(py_name) = py_name {
+                    bind_keyword_args(py_name, &py_args, kwargs)?;
+                }
+                let py_args = py_args.as_ref();
+
+                if let Some(py_name) = py_name {
                     if py_name == "len" && c_name == "list_length" {
                         // VALIDATED PATTERN from Sprint 0!
                         return self.unify_len_pattern(py_args);
@@ -378,11 +1171,11 @@ impl Unifier {
                         return self.unify_extend_pattern(py_args);
                     }
                     // Dict operations
-                    if py_name == "dict_pop" && c_name == "PyDict_DelItem" {
+                    if py_name == "pop" && c_name == "PyDict_DelItem" {
                         // DICT POP PATTERN: Python dict.pop() + C PyDict_DelItem() → Rust HashMap::remove()
                         return self.unify_dict_pop_pattern(py_args);
                     }
-                    if py_name == "dict_clear" && c_name == "PyDict_Clear" {
+                    if py_name == "clear" && c_name == "PyDict_Clear" {
                         // DICT CLEAR PATTERN: Python dict.clear() + C PyDict_Clear() → Rust HashMap::clear()
                         return self.unify_dict_clear_pattern(py_args);
                     }
@@ -393,11 +1186,8 @@ impl Unifier {
                 }
 
                 // No pattern found - generate helpful error message
-                let python_fn = if let PythonHIR::Variable { name, .. } = py_callee.as_ref() {
-                    name.clone()
-                } else {
-                    extract_python_fn_name(python)
-                };
+                let python_fn =
+                    py_name.map_or_else(|| extract_python_fn_name(python), str::to_owned);
                 let c_fn = c_name.clone();
                 let suggestions = find_similar_patterns(&python_fn, &c_fn);
 
@@ -478,7 +1268,12 @@ impl Unifier {
             target_language: Language::Rust,
             callee: "HashMap::get".to_owned(),
             args: self.convert_args(args),
-            inferred_type: Type::Rust(crate::types::RustType::Option(Box::new(Type::Unknown))),
+            // `d.get(k, default)` always produces a value
+            inferred_type: if args.len() > 2 {
+                Type::Unknown
+            } else {
+                Type::Rust(crate::types::RustType::Option(Box::new(Type::Unknown)))
+            },
             source_language: Language::Python,
             cross_mapping: Some(CrossMapping {
                 python_node: None,
@@ -667,7 +1462,7 @@ impl Unifier {
     }
 
     /// Get the next node ID
-    fn next_node_id(&mut self) -> NodeId {
+    pub(crate) fn next_node_id(&mut self) -> NodeId {
         let id = NodeId::new(self.next_id);
         self.next_id += 1;
         id
@@ -682,29 +1477,99 @@ impl Unifier {
     }
 
     /// Convert a single Python HIR node to Unified HIR
-    /// Simplified for Phase 2.1 - handles common cases
+    ///
+    /// Falls back to a placeholder for arguments that cannot be lowered yet.
     #[allow(clippy::unnecessary_wraps)]
     fn convert_python_node(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
-        match node {
-            PythonHIR::Variable { name, .. } => {
-                let id = self.next_node_id();
-                Ok(UnifiedHIR::Variable {
-                    id,
-                    name: name.clone(),
-                    var_type: Type::Unknown, // Type inference to be added later
-                    source_language: Language::Python,
-                    meta: Metadata::new(),
-                })
-            }
-            // For now, just handle variables - expand later
-            _ => Ok(UnifiedHIR::Variable {
+        Ok(self
+            .lower_expr(node)
+            .unwrap_or_else(|_| UnifiedHIR::Variable {
                 id: self.next_node_id(),
                 name: "arg".to_owned(),
                 var_type: Type::Unknown,
                 source_language: Language::Python,
                 meta: Metadata::new(),
-            }),
+            }))
+    }
+}
+
+/// Rust type of a C number type
+fn c_scalar_type(ty: &Type) -> Option<Type> {
+    let int = |bits, signed| Some(Type::Rust(RustType::Int { bits, signed }));
+    match ty {
+        Type::C(CType::Int) => int(IntSize::I32, true),
+        Type::C(CType::Long) => int(IntSize::I64, true),
+        Type::C(CType::SizeT) => int(IntSize::ISize, false),
+        Type::C(CType::CPython(CPythonType::PySsizeT)) => int(IntSize::ISize, true),
+        Type::C(CType::Float) => Some(Type::Rust(RustType::Float { bits: 32 })),
+        Type::C(CType::Double) => Some(Type::Rust(RustType::Float { bits: 64 })),
+        _ => None,
+    }
+}
+
+/// Keyword parameters of the Python functions and methods that are lowered
+///
+/// `dict.get` and `dict.pop` take positional arguments only.
+const KEYWORD_PARAMETERS: &[(&str, &[&str])] = &[
+    ("sort", &["key", "reverse"]),
+    ("sorted", &["key", "reverse"]),
+];
+
+/// Bind the keyword arguments of a call to `method` by parameter name
+///
+/// Splats and keywords the method does not declare cannot be mapped onto a
+/// fixed Rust signature, so they are reported instead of dropped.
+pub(crate) fn bind_keyword_args<'a>(
+    method: &str,
+    args: &[PythonHIR],
+    kwargs: &'a [(Option<String>, PythonHIR)],
+) -> Result<HashMap<&'a str, &'a PythonHIR>> {
+    let unsupported = |what: String| UnificationError::UnsupportedPython {
+        node_kind: format!("{what} in call to {method}()"),
+    };
+    if args
+        .iter()
+        .any(|arg| matches!(arg, PythonHIR::Starred { .. }))
+    {
+        return Err(unsupported("`*` argument splat".to_owned()).into());
+    }
+
+    let params = KEYWORD_PARAMETERS
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map_or(&[][..], |(_, params)| *params);
+    let mut bound = HashMap::new();
+    for (name, value) in kwargs {
+        let Some(name) = name else {
+            return Err(unsupported("`**` keyword splat".to_owned()).into());
+        };
+        if !params.contains(&name.as_str()) || bound.insert(name.as_str(), value).is_some() {
+            return Err(unsupported(format!("keyword argument `{name}`")).into());
+        }
+    }
+    Ok(bound)
+}
+
+/// Map a Python literal onto a unified literal and its type
+pub(crate) fn convert_python_literal(literal: &PythonLiteral) -> (LiteralValue, Type) {
+    match literal {
+        PythonLiteral::Int(value) => (LiteralValue::Int(*value), Type::Python(PythonType::Int)),
+        PythonLiteral::Float(value) => {
+            (LiteralValue::Float(*value), Type::Python(PythonType::Float))
         }
+        PythonLiteral::Str(value) => (
+            LiteralValue::Str(value.clone()),
+            Type::Python(PythonType::Str),
+        ),
+        PythonLiteral::Bytes(value) => (
+            LiteralValue::Bytes(value.clone()),
+            Type::Rust(RustType::Vec(Box::new(Type::Rust(RustType::Int {
+                bits: IntSize::I8,
+                signed: false,
+            })))),
+        ),
+        PythonLiteral::Bool(value) => (LiteralValue::Bool(*value), Type::Python(PythonType::Bool)),
+        PythonLiteral::None => (LiteralValue::None, Type::Python(PythonType::None)),
     }
 }
 
@@ -762,6 +1627,20 @@ impl UnifiedHIR {
                 }
             }
 
+            Self::Propagate {
+                id,
+                value,
+                on_none,
+                source_language,
+                meta,
+            } => Self::Propagate {
+                id,
+                value: Box::new(value.eliminate_boundary()),
+                on_none,
+                source_language,
+                meta,
+            },
+
             // Recursively process other node types
             other => other,
         }
@@ -776,11 +1655,245 @@ impl UnifiedHIR {
             | Self::Call { id, .. }
             | Self::Variable { id, .. }
             | Self::Assign { id, .. }
+            | Self::Declare { id, .. }
             | Self::Return { id, .. }
             | Self::If { id, .. }
             | Self::Loop { id, .. }
             | Self::BinOp { id, .. }
-            | Self::Literal { id, .. } => Some(*id),
+            | Self::Literal { id, .. }
+            | Self::UnaryOp { id, .. }
+            | Self::Cast { id, .. }
+            | Self::Struct { id, .. }
+            | Self::Impl { id, .. }
+            | Self::Enum { id, .. }
+            | Self::StructInit { id, .. }
+            | Self::FieldAccess { id, .. }
+            | Self::Index { id, .. }
+            | Self::MethodCall { id, .. }
+            | Self::Store { id, .. }
+            | Self::AugAssign { id, .. }
+            | Self::List { id, .. }
+            | Self::Tuple { id, .. }
+            | Self::Dict { id, .. }
+            | Self::Comprehension { id, .. }
+            | Self::Break { id, .. }
+            | Self::Continue { id, .. }
+            | Self::ErrorEnum { id, .. }
+            | Self::TryCatch { id, .. }
+            | Self::Propagate { id, .. }
+            | Self::Generator { id, .. }
+            | Self::Yield { id, .. }
+            | Self::Advance { id, .. }
+            | Self::Format { id, .. }
+            | Self::Scope { id, .. }
+            | Self::Closure { id, .. }
+            | Self::GuardType { id, .. }
+            | Self::Static { id, .. }
+            | Self::Use { id, .. } => Some(*id),
+        }
+    }
+
+    /// Get the metadata
+    #[must_use]
+    pub const fn metadata(&self) -> &Metadata {
+        match self {
+            Self::Module { meta, .. }
+            | Self::Function { meta, .. }
+            | Self::Call { meta, .. }
+            | Self::Variable { meta, .. }
+            | Self::Assign { meta, .. }
+            | Self::Declare { meta, .. }
+            | Self::Return { meta, .. }
+            | Self::If { meta, .. }
+            | Self::Loop { meta, .. }
+            | Self::BinOp { meta, .. }
+            | Self::Literal { meta, .. }
+            | Self::UnaryOp { meta, .. }
+            | Self::Cast { meta, .. }
+            | Self::Struct { meta, .. }
+            | Self::Impl { meta, .. }
+            | Self::Enum { meta, .. }
+            | Self::StructInit { meta, .. }
+            | Self::FieldAccess { meta, .. }
+            | Self::Index { meta, .. }
+            | Self::MethodCall { meta, .. }
+            | Self::Store { meta, .. }
+            | Self::AugAssign { meta, .. }
+            | Self::List { meta, .. }
+            | Self::Tuple { meta, .. }
+            | Self::Dict { meta, .. }
+            | Self::Comprehension { meta, .. }
+            | Self::Break { meta, .. }
+            | Self::Continue { meta, .. }
+            | Self::ErrorEnum { meta, .. }
+            | Self::TryCatch { meta, .. }
+            | Self::Propagate { meta, .. }
+            | Self::Generator { meta, .. }
+            | Self::Yield { meta, .. }
+            | Self::Advance { meta, .. }
+            | Self::Format { meta, .. }
+            | Self::Scope { meta, .. }
+            | Self::Closure { meta, .. }
+            | Self::GuardType { meta, .. }
+            | Self::Static { meta, .. }
+            | Self::Use { meta, .. } => meta,
+        }
+    }
+
+    /// Get mutable access to the metadata
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        match self {
+            Self::Module { meta, .. }
+            | Self::Function { meta, .. }
+            | Self::Call { meta, .. }
+            | Self::Variable { meta, .. }
+            | Self::Assign { meta, .. }
+            | Self::Declare { meta, .. }
+            | Self::Return { meta, .. }
+            | Self::If { meta, .. }
+            | Self::Loop { meta, .. }
+            | Self::BinOp { meta, .. }
+            | Self::Literal { meta, .. }
+            | Self::UnaryOp { meta, .. }
+            | Self::Cast { meta, .. }
+            | Self::Struct { meta, .. }
+            | Self::Impl { meta, .. }
+            | Self::Enum { meta, .. }
+            | Self::StructInit { meta, .. }
+            | Self::FieldAccess { meta, .. }
+            | Self::Index { meta, .. }
+            | Self::MethodCall { meta, .. }
+            | Self::Store { meta, .. }
+            | Self::AugAssign { meta, .. }
+            | Self::List { meta, .. }
+            | Self::Tuple { meta, .. }
+            | Self::Dict { meta, .. }
+            | Self::Comprehension { meta, .. }
+            | Self::Break { meta, .. }
+            | Self::Continue { meta, .. }
+            | Self::ErrorEnum { meta, .. }
+            | Self::TryCatch { meta, .. }
+            | Self::Propagate { meta, .. }
+            | Self::Generator { meta, .. }
+            | Self::Yield { meta, .. }
+            | Self::Advance { meta, .. }
+            | Self::Format { meta, .. }
+            | Self::Scope { meta, .. }
+            | Self::Closure { meta, .. }
+            | Self::GuardType { meta, .. }
+            | Self::Static { meta, .. }
+            | Self::Use { meta, .. } => meta,
+        }
+    }
+
+    /// Get the direct child nodes, in evaluation order
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn children(&self) -> Vec<&UnifiedHIR> {
+        match self {
+            Self::Module {
+                declarations: body, ..
+            }
+            | Self::Function { body, .. }
+            | Self::Impl { methods: body, .. }
+            | Self::Closure { body, .. } => body.iter().collect(),
+            Self::Call { args, .. } | Self::Format { args, .. } => args.iter().collect(),
+            Self::Assign { value, .. } | Self::Yield { value, .. } | Self::Static { value, .. } => {
+                vec![value]
+            }
+            Self::Return { value, .. } => value.iter().map(Box::as_ref).collect(),
+            Self::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => std::iter::once(condition.as_ref())
+                .chain(then_branch)
+                .chain(else_branch)
+                .collect(),
+            Self::Loop { kind, body, .. } => match kind {
+                LoopKind::For { iter, .. } => std::iter::once(iter.as_ref()),
+                LoopKind::While { condition } => std::iter::once(condition.as_ref()),
+            }
+            .chain(body)
+            .collect(),
+            Self::BinOp { left, right, .. } => vec![left, right],
+            Self::UnaryOp { operand, .. } | Self::Cast { value: operand, .. } => vec![operand],
+            Self::StructInit { fields, .. } => fields.iter().map(|(_, value)| value).collect(),
+            Self::FieldAccess { object, .. } => vec![object],
+            Self::Index { object, index, .. } => vec![object, index],
+            Self::MethodCall { receiver, args, .. } => {
+                std::iter::once(receiver.as_ref()).chain(args).collect()
+            }
+            Self::Store { target, value, .. } | Self::AugAssign { target, value, .. } => {
+                vec![target, value]
+            }
+            Self::List { elements, .. } | Self::Tuple { elements, .. } => elements.iter().collect(),
+            Self::Dict { entries, .. } => entries
+                .iter()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            Self::Comprehension {
+                element,
+                value,
+                clauses,
+                ..
+            } => clauses
+                .iter()
+                .flat_map(|clause| std::iter::once(&clause.iter).chain(&clause.filters))
+                .chain(std::iter::once(element.as_ref()))
+                .chain(value.iter().map(Box::as_ref))
+                .collect(),
+            Self::TryCatch {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => body
+                .iter()
+                .chain(handlers.iter().flat_map(|handler| &handler.body))
+                .chain(orelse)
+                .chain(finalbody)
+                .collect(),
+            Self::Propagate { value, on_none, .. } => std::iter::once(value.as_ref())
+                .chain(on_none.iter().map(Box::as_ref))
+                .collect(),
+            Self::Generator { setup, stages, .. } => setup
+                .iter()
+                .chain(stages.iter().flat_map(|stage| match stage {
+                    GeneratorStage::Run(body) => body.iter().collect::<Vec<_>>(),
+                    GeneratorStage::For { iter, body, .. } => {
+                        std::iter::once(iter).chain(body).collect()
+                    }
+                    GeneratorStage::While { condition, body } => {
+                        std::iter::once(condition).chain(body).collect()
+                    }
+                    GeneratorStage::Delegate(iter) => vec![iter],
+                }))
+                .collect(),
+            Self::Scope {
+                guards,
+                body,
+                value,
+                ..
+            } => guards
+                .iter()
+                .map(|guard| &guard.value)
+                .chain(body)
+                .chain(value.iter().map(Box::as_ref))
+                .collect(),
+            Self::Variable { .. }
+            | Self::Declare { .. }
+            | Self::Literal { .. }
+            | Self::Struct { .. }
+            | Self::Enum { .. }
+            | Self::Break { .. }
+            | Self::Continue { .. }
+            | Self::ErrorEnum { .. }
+            | Self::Advance { .. }
+            | Self::GuardType { .. }
+            | Self::Use { .. } => vec![],
         }
     }
 }
@@ -902,6 +2015,298 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unifier_pop_raises_index_error() {
+        // list_pop() sets IndexError and returns NULL on an empty list
+        let mut unifier = Unifier::new();
+        let c_var = |name: &str| CHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type: None,
+            meta: Metadata::new(),
+        };
+
+        let python_call = PythonHIR::Call {
+            id: NodeId::new(1),
+            callee: Box::new(PythonHIR::Variable {
+                id: NodeId::new(2),
+                name: "pop".to_owned(),
+                inferred_type: None,
+                meta: Metadata::new(),
+            }),
+            args: vec![],
+            kwargs: vec![],
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+
+        let c_function = CHIR::Function {
+            id: NodeId::new(3),
+            name: "list_pop".to_owned(),
+            return_type: Type::Unknown,
+            params: vec![],
+            body: vec![CHIR::If {
+                id: NodeId::new(4),
+                condition: Box::new(c_var("empty")),
+                then_branch: vec![
+                    CHIR::Call {
+                        id: NodeId::new(5),
+                        callee: Box::new(c_var("PyErr_SetString")),
+                        args: vec![
+                            c_var("PyExc_IndexError"),
+                            CHIR::Literal {
+                                id: NodeId::new(6),
+                                value: crate::c::Literal::Str("pop from empty list".to_owned()),
+                                meta: Metadata::new(),
+                            },
+                        ],
+                        inferred_type: None,
+                        meta: Metadata::new(),
+                    },
+                    CHIR::Return {
+                        id: NodeId::new(7),
+                        value: Some(Box::new(c_var("NULL"))),
+                        meta: Metadata::new(),
+                    },
+                ],
+                else_branch: vec![],
+                meta: Metadata::new(),
+            }],
+            storage_class: crate::c::StorageClass::Static,
+            visibility: crate::Visibility::Private,
+            meta: Metadata::new(),
+        };
+
+        let unified = unifier
+            .unify(&python_call, &c_function)
+            .expect("Unification should succeed");
+
+        let UnifiedHIR::Propagate {
+            value,
+            on_none: Some(error),
+            ..
+        } = unified
+        else {
+            panic!("Expected the NULL return to propagate, got {unified:?}");
+        };
+        assert!(matches!(*value, UnifiedHIR::Call { ref callee, .. } if callee == "Vec::pop"));
+        let UnifiedHIR::Call { callee, .. } = *error else {
+            panic!("Expected an error constructor");
+        };
+        assert_eq!(callee, "Error::IndexError");
+    }
+
+    fn prices_translation_unit() -> CHIR {
+        // typedef double price_t;
+        // struct prices { price_t *items; size_t size; };
+        // typedef struct prices Prices;
+        let c = |ty: CType| Type::C(ty);
+        let typedef = |name: &str, var_type: Type| CHIR::VarDecl {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            var_type,
+            init: None,
+            storage_class: crate::c::StorageClass::Typedef,
+            meta: Metadata::new(),
+        };
+        CHIR::TranslationUnit {
+            name: "prices.c".to_owned(),
+            declarations: vec![
+                typedef("price_t", c(CType::Double)),
+                CHIR::Struct {
+                    id: NodeId::new(0),
+                    name: "prices".to_owned(),
+                    fields: vec![
+                        crate::c::Field {
+                            name: "items".to_owned(),
+                            field_type: c(CType::Pointer(Box::new(CType::Typedef(
+                                "price_t".to_owned(),
+                            )))),
+                        },
+                        crate::c::Field {
+                            name: "size".to_owned(),
+                            field_type: c(CType::SizeT),
+                        },
+                    ],
+                    meta: Metadata::new(),
+                },
+                typedef("Prices", c(CType::Struct("prices".to_owned()))),
+            ],
+            meta: Metadata::new(),
+        }
+    }
+
+    #[test]
+    fn test_unifier_types_pop_through_c_fields_and_typedefs() {
+        // static void *list_pop(Prices *list) { return list->items[--list->size]; }
+        let c = |ty: CType| Type::C(ty);
+        let list = || {
+            Box::new(CHIR::Variable {
+                id: NodeId::new(0),
+                name: "list".to_owned(),
+                var_type: None,
+                meta: Metadata::new(),
+            })
+        };
+        let field = |field: &str| CHIR::FieldAccess {
+            id: NodeId::new(0),
+            object: list(),
+            field: field.to_owned(),
+            is_pointer: true,
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+        let c_function = CHIR::Function {
+            id: NodeId::new(0),
+            name: "list_pop".to_owned(),
+            return_type: c(CType::Pointer(Box::new(CType::Void))),
+            params: vec![crate::c::Parameter {
+                name: "list".to_owned(),
+                param_type: c(CType::Pointer(Box::new(CType::Typedef(
+                    "Prices".to_owned(),
+                )))),
+            }],
+            body: vec![CHIR::Return {
+                id: NodeId::new(0),
+                value: Some(Box::new(CHIR::ArraySubscript {
+                    id: NodeId::new(0),
+                    array: Box::new(field("items")),
+                    index: Box::new(field("size")),
+                    inferred_type: None,
+                    meta: Metadata::new(),
+                })),
+                meta: Metadata::new(),
+            }],
+            storage_class: crate::c::StorageClass::Static,
+            visibility: crate::Visibility::Private,
+            meta: Metadata::new(),
+        };
+        let python_call = PythonHIR::Call {
+            id: NodeId::new(1),
+            callee: Box::new(PythonHIR::Variable {
+                id: NodeId::new(2),
+                name: "pop".to_owned(),
+                inferred_type: None,
+                meta: Metadata::new(),
+            }),
+            args: vec![],
+            kwargs: vec![],
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+
+        let unified = Unifier::new()
+            .with_c_types(CTypes::new(&prices_translation_unit()))
+            .unify(&python_call, &c_function)
+            .expect("Unification should succeed");
+
+        let UnifiedHIR::Call { inferred_type, .. } = unified else {
+            panic!("Expected UnifiedHIR::Call, got {unified:?}");
+        };
+        assert_eq!(
+            inferred_type,
+            Type::Rust(RustType::Option(Box::new(Type::Rust(RustType::Float {
+                bits: 64
+            }))))
+        );
+
+        // Without the declarations the popped value stays unknown
+        let unified = Unifier::new()
+            .unify(&python_call, &c_function)
+            .expect("Unification should succeed");
+        assert!(matches!(
+            unified,
+            UnifiedHIR::Call {
+                inferred_type: Type::Rust(RustType::Option(ref value)),
+                ..
+            } if **value == Type::Unknown
+        ));
+    }
+
+    #[test]
+    fn test_unifier_method_call_receiver() {
+        let mut unifier = Unifier::new();
+        let variable = |id, name: &str| PythonHIR::Variable {
+            id: NodeId::new(id),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+
+        // d.clear() with the attribute's object as the receiver
+        let python_call = PythonHIR::Call {
+            id: NodeId::new(1),
+            callee: Box::new(PythonHIR::Attribute {
+                id: NodeId::new(2),
+                object: Box::new(variable(3, "d")),
+                attr: "clear".to_owned(),
+                inferred_type: None,
+                meta: Metadata::new(),
+            }),
+            args: vec![],
+            kwargs: vec![],
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+        let c_function = CHIR::Function {
+            id: NodeId::new(4),
+            name: "PyDict_Clear".to_owned(),
+            return_type: Type::C(CType::Void),
+            params: vec![],
+            body: vec![],
+            storage_class: crate::c::StorageClass::None,
+            visibility: crate::Visibility::Public,
+            meta: Metadata::new(),
+        };
+
+        let unified = unifier
+            .unify(&python_call, &c_function)
+            .expect("Unification should succeed");
+
+        let UnifiedHIR::Call {
+            callee,
+            args,
+            cross_mapping: Some(mapping),
+            ..
+        } = unified
+        else {
+            panic!("Expected UnifiedHIR::Call with a cross mapping");
+        };
+        assert_eq!(callee, "HashMap::clear");
+        assert_eq!(mapping.pattern, UnificationPattern::DictClearPattern);
+        assert!(matches!(&args[..], [UnifiedHIR::Variable { name, .. }] if name == "d"));
+    }
+
+    #[test]
+    fn test_bind_keyword_args() {
+        let literal = |value: PythonLiteral| PythonHIR::Literal {
+            id: NodeId::new(0),
+            value,
+            meta: Metadata::new(),
+        };
+        let kwargs = vec![
+            (
+                Some("reverse".to_owned()),
+                literal(PythonLiteral::Bool(true)),
+            ),
+            (Some("key".to_owned()), literal(PythonLiteral::None)),
+        ];
+        let bound = bind_keyword_args("sorted", &[], &kwargs).expect("Should bind keywords");
+        assert!(matches!(
+            bound.get("reverse"),
+            Some(PythonHIR::Literal {
+                value: PythonLiteral::Bool(true),
+                ..
+            })
+        ));
+        assert!(bound.contains_key("key"));
+
+        // `dict.get(key, default, /)` takes no keywords
+        let kwargs = vec![(Some("default".to_owned()), literal(PythonLiteral::Int(0)))];
+        let error = bind_keyword_args("get", &[], &kwargs).expect_err("Positional only");
+        assert!(error.to_string().contains("`default`"), "{error}");
+    }
+
     #[test]
     fn test_unifier_dict_get_pattern() {
         // Test dict.get() pattern: Python dict.get() + C PyDict_GetItem → Rust HashMap::get()
@@ -960,6 +2365,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unifier_preserves_literal_args() {
+        let mut unifier = Unifier::new();
+
+        let python_call = PythonHIR::Call {
+            id: NodeId::new(1),
+            callee: Box::new(PythonHIR::Variable {
+                id: NodeId::new(2),
+                name: "insert".to_owned(),
+                inferred_type: None,
+                meta: Metadata::new(),
+            }),
+            args: vec![
+                PythonHIR::Variable {
+                    id: NodeId::new(3),
+                    name: "xs".to_owned(),
+                    inferred_type: Some(Type::Python(PythonType::List(Box::new(Type::Python(
+                        PythonType::Int,
+                    ))))),
+                    meta: Metadata::new(),
+                },
+                PythonHIR::Literal {
+                    id: NodeId::new(4),
+                    value: PythonLiteral::Int(0),
+                    meta: Metadata::new(),
+                },
+                PythonHIR::Literal {
+                    id: NodeId::new(5),
+                    value: PythonLiteral::Int(42),
+                    meta: Metadata::new(),
+                },
+            ],
+            kwargs: vec![],
+            inferred_type: None,
+            meta: Metadata::new(),
+        };
+
+        let c_function = CHIR::Function {
+            id: NodeId::new(6),
+            name: "list_insert".to_owned(),
+            return_type: Type::C(CType::Int),
+            params: vec![],
+            body: vec![],
+            storage_class: crate::c::StorageClass::Static,
+            visibility: crate::Visibility::Private,
+            meta: Metadata::new(),
+        };
+
+        let unified = unifier
+            .unify(&python_call, &c_function)
+            .expect("Unification should succeed");
+
+        let UnifiedHIR::Call { args, .. } = unified else {
+            panic!("Expected UnifiedHIR::Call");
+        };
+        let UnifiedHIR::Variable { var_type, .. } = &args[0] else {
+            panic!("Expected receiver Variable");
+        };
+        assert_eq!(var_type.to_string(), "list[int]");
+        assert!(matches!(
+            &args[2],
+            UnifiedHIR::Literal {
+                value: LiteralValue::Int(42),
+                lit_type: Type::Python(PythonType::Int),
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_boundary_elimination() {
         // Test boundary elimination (from Sprint 0)
@@ -0,0 +1,422 @@
+//! Tuple packing and unpacking
+//!
+//! Tuple displays (`return total, count`) become Rust tuples, and
+//! unpacking targets become destructuring patterns:
+//!
+//! ```text
+//! a, (b, c) = pair          →  let (a, (b, c)) = pair;
+//! first, *rest = xs         →  let (first, rest) = match &xs[..] {
+//!                                  [first, rest @ ..] => (first.clone(), rest.to_vec()),
+//!                                  _ => panic!("not enough values to unpack (expected at least 1)"),
+//!                              };
+//! for k, v in d.items():    →  for (k, v) in d.iter().map(|entry| (entry.0.clone(), entry.1.clone())) {
+//! pair[1]                   →  pair.1
+//! ```
+//!
+//! A tuple target over a tuple, or over a value of unknown type such as a
+//! call's result, is a tuple pattern checked by the compiler. A list's
+//! length is only known at runtime, so unpacking a list or using a starred
+//! target matches a slice pattern and panics on a length mismatch, where
+//! Python raises `ValueError`. Slice patterns are refutable and are
+//! reported in `for` loops and comprehensions.
+
+use crate::{
+    error::UnificationError,
+    lowering::{expr_type, unsupported_in},
+    metadata::Metadata,
+    python::{Literal, PythonHIR, Target},
+    types::{PythonType, Type},
+    unified::{ClosureKind, Pattern, UnifiedHIR, UnifiedParameter, Unifier},
+    Language,
+};
+use anyhow::Result;
+
+impl Unifier {
+    /// Lower a tuple display
+    pub(crate) fn lower_tuple(&mut self, node: &PythonHIR) -> Result<UnifiedHIR> {
+        let PythonHIR::Tuple { elements, meta, .. } = node else {
+            return Err(unsupported_in("tuple", node));
+        };
+        Ok(UnifiedHIR::Tuple {
+            id: self.next_node_id(),
+            elements: elements
+                .iter()
+                .map(|element| self.lower_value(element))
+                .collect::<Result<_>>()?,
+            tuple_type: expr_type(node),
+            meta: meta.clone(),
+        })
+    }
+
+    /// Lower `d.items()` to an iterator over `(key, value)` tuples
+    ///
+    /// The dict is borrowed and each entry cloned, leaving the dict usable.
+    /// Returns `None` for any other call.
+    pub(crate) fn lower_items_call(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Call {
+            callee,
+            args,
+            kwargs,
+            meta,
+            ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        let PythonHIR::Attribute { object, attr, .. } = callee.as_ref() else {
+            return Ok(None);
+        };
+        if attr != "items" || !args.is_empty() || !kwargs.is_empty() {
+            return Ok(None);
+        }
+
+        let (key_type, value_type) = match expr_type(object) {
+            Type::Python(PythonType::Dict { key, value }) => (*key, *value),
+            _ => (Type::Unknown, Type::Unknown),
+        };
+        let entry_type = Type::Python(PythonType::Tuple(vec![key_type, value_type]));
+        let mut owned = Vec::with_capacity(2);
+        for index in 0..2 {
+            let entry = UnifiedHIR::Variable {
+                id: self.next_node_id(),
+                name: "entry".to_owned(),
+                var_type: Type::Unknown,
+                source_language: Language::Python,
+                meta: meta.clone(),
+            };
+            let field = UnifiedHIR::FieldAccess {
+                id: self.next_node_id(),
+                object: Box::new(entry),
+                field: index.to_string(),
+                field_type: Type::Unknown,
+                source_language: Language::Python,
+                meta: meta.clone(),
+            };
+            owned.push(self.method_call(field, "clone", vec![], meta));
+        }
+        let clone_entry = UnifiedHIR::Closure {
+            id: self.next_node_id(),
+            params: vec![UnifiedParameter {
+                name: "entry".to_owned(),
+                param_type: Type::Unknown,
+                source_language: Language::Python,
+            }],
+            return_type: entry_type.clone(),
+            body: vec![UnifiedHIR::Return {
+                id: self.next_node_id(),
+                value: Some(Box::new(UnifiedHIR::Tuple {
+                    id: self.next_node_id(),
+                    elements: owned,
+                    tuple_type: entry_type,
+                    meta: meta.clone(),
+                })),
+                source_language: Language::Python,
+                meta: meta.clone(),
+            }],
+            captures: vec![],
+            kind: ClosureKind::Fn,
+            moves: false,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        };
+        let dict = self.lower_expr(object)?;
+        let entries = self.method_call(dict, "iter", vec![], meta);
+        Ok(Some(self.method_call(
+            entries,
+            "map",
+            vec![clone_entry],
+            meta,
+        )))
+    }
+
+    /// Lower `pair[1]` on a tuple to the field access `pair.1`
+    ///
+    /// Returns `None` for any other node, and for indices that are not
+    /// in-range integer literals.
+    pub(crate) fn lower_tuple_index(&mut self, node: &PythonHIR) -> Result<Option<UnifiedHIR>> {
+        let PythonHIR::Subscript {
+            object,
+            index,
+            meta,
+            ..
+        } = node
+        else {
+            return Ok(None);
+        };
+        let (
+            Type::Python(PythonType::Tuple(types)),
+            PythonHIR::Literal {
+                value: Literal::Int(index),
+                ..
+            },
+        ) = (expr_type(object), index.as_ref())
+        else {
+            return Ok(None);
+        };
+        let Some((index, field_type)) = usize::try_from(*index)
+            .ok()
+            .and_then(|index| Some((index, types.get(index)?.clone())))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(UnifiedHIR::FieldAccess {
+            id: self.next_node_id(),
+            object: Box::new(self.lower_expr(object)?),
+            field: index.to_string(),
+            field_type,
+            source_language: Language::Python,
+            meta: meta.clone(),
+        }))
+    }
+
+    /// Assign to variables declared earlier: `x = value;`, `(a, b) = value;`
+    pub(crate) fn reassign(
+        &mut self,
+        target: &Pattern,
+        value: UnifiedHIR,
+        meta: &Metadata,
+    ) -> Result<UnifiedHIR> {
+        Ok(UnifiedHIR::Store {
+            id: self.next_node_id(),
+            target: Box::new(self.assignee(target, meta)?),
+            value: Box::new(value),
+            source_language: Language::Python,
+            meta: meta.clone(),
+        })
+    }
+
+    /// The place expression a destructuring assignment writes to
+    fn assignee(&mut self, target: &Pattern, meta: &Metadata) -> Result<UnifiedHIR> {
+        Ok(match target {
+            Pattern::Name(name) => UnifiedHIR::Variable {
+                id: self.next_node_id(),
+                name: name.clone(),
+                var_type: Type::Unknown,
+                source_language: Language::Python,
+                meta: meta.clone(),
+            },
+            Pattern::Tuple(patterns) => UnifiedHIR::Tuple {
+                id: self.next_node_id(),
+                elements: patterns
+                    .iter()
+                    .map(|pattern| self.assignee(pattern, meta))
+                    .collect::<Result<_>>()?,
+                tuple_type: Type::Unknown,
+                meta: meta.clone(),
+            },
+            Pattern::Slice { .. } => {
+                let location = meta
+                    .source
+                    .as_ref()
+                    .map_or_else(|| "<unknown>".to_owned(), ToString::to_string);
+                return Err(UnificationError::UnsupportedPython {
+                    node_kind: format!(
+                        "list or starred unpacking into variables declared earlier at {location}"
+                    ),
+                }
+                .into());
+            }
+        })
+    }
+}
+
+/// Lower the target of an assignment
+///
+/// The value's type picks the pattern: the annotation if there is one,
+/// else the type of the `value` expression.
+pub(crate) fn lower_assign_target(
+    target: &Target,
+    value: &PythonHIR,
+    type_annotation: Option<&Type>,
+    node: &PythonHIR,
+) -> Result<Pattern> {
+    let value_type = type_annotation.cloned().unwrap_or_else(|| expr_type(value));
+    lower_target(target, &value_type, node)
+}
+
+/// Lower the target of a `for` loop or comprehension over `iter`
+pub(crate) fn lower_loop_target(
+    target: &Target,
+    iter: &PythonHIR,
+    node: &PythonHIR,
+) -> Result<Pattern> {
+    let pattern = lower_target(target, &item_type(&expr_type(iter)), node)?;
+    if is_refutable(&pattern) {
+        return Err(unsupported_in(
+            "list or starred unpacking in a loop target",
+            node,
+        ));
+    }
+    Ok(pattern)
+}
+
+/// Lower a target bound to a value of type `value_type`
+fn lower_target(target: &Target, value_type: &Type, node: &PythonHIR) -> Result<Pattern> {
+    let Target::Tuple(targets) = target else {
+        return match target {
+            Target::Name(name) => Ok(Pattern::Name(name.clone())),
+            _ => Err(unsupported_in("starred assignment target", node)),
+        };
+    };
+    let starred = targets
+        .iter()
+        .position(|target| matches!(target, Target::Starred(_)));
+    match (value_type, starred) {
+        (Type::Python(PythonType::Tuple(types)), Some(_)) if !types.is_empty() => {
+            Err(unsupported_in("starred unpacking of a tuple", node))
+        }
+        (Type::Python(PythonType::Tuple(types)), None) if !types.is_empty() => {
+            if types.len() != targets.len() {
+                return Err(unsupported_in(
+                    &format!(
+                        "unpacking {} values into {} targets",
+                        types.len(),
+                        targets.len()
+                    ),
+                    node,
+                ));
+            }
+            Ok(Pattern::Tuple(
+                targets
+                    .iter()
+                    .zip(types)
+                    .map(|(target, ty)| lower_target(target, ty, node))
+                    .collect::<Result<_>>()?,
+            ))
+        }
+        (Type::Python(PythonType::List(item)), _) => lower_slice(targets, starred, item, node),
+        (_, Some(_)) => lower_slice(targets, starred, &Type::Unknown, node),
+        (_, None) => Ok(Pattern::Tuple(
+            targets
+                .iter()
+                .map(|target| lower_target(target, &Type::Unknown, node))
+                .collect::<Result<_>>()?,
+        )),
+    }
+}
+
+/// Lower a target unpacking a list whose items have type `item`
+fn lower_slice(
+    targets: &[Target],
+    starred: Option<usize>,
+    item: &Type,
+    node: &PythonHIR,
+) -> Result<Pattern> {
+    let (before, rest, after) = match starred {
+        Some(index) => {
+            let Target::Starred(rest) = &targets[index] else {
+                return Err(unsupported_in("starred assignment target", node));
+            };
+            (&targets[..index], Some(rest.clone()), &targets[index + 1..])
+        }
+        None => (targets, None, &[][..]),
+    };
+    let lower = |targets: &[Target]| -> Result<Vec<Pattern>> {
+        targets
+            .iter()
+            .map(|target| {
+                let pattern = lower_target(target, item, node)?;
+                if is_refutable(&pattern) {
+                    return Err(unsupported_in("unpacking nested lists", node));
+                }
+                Ok(pattern)
+            })
+            .collect()
+    };
+    Ok(Pattern::Slice {
+        before: lower(before)?,
+        rest,
+        after: lower(after)?,
+    })
+}
+
+/// Whether a pattern can fail to match, which only slice patterns can
+fn is_refutable(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Name(_) => false,
+        Pattern::Tuple(patterns) => patterns.iter().any(is_refutable),
+        Pattern::Slice { .. } => true,
+    }
+}
+
+/// Type of the items produced by iterating over a value of type `ty`
+pub(crate) fn item_type(ty: &Type) -> Type {
+    match ty {
+        Type::Python(
+            PythonType::List(item) | PythonType::Set(item) | PythonType::Iterator(item),
+        ) => item.as_ref().clone(),
+        Type::Python(PythonType::Dict { key, .. }) => key.as_ref().clone(),
+        _ => Type::Unknown,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::NodeId;
+
+    fn var(name: &str) -> PythonHIR {
+        PythonHIR::Variable {
+            id: NodeId::new(0),
+            name: name.to_owned(),
+            inferred_type: None,
+            meta: Metadata::new(),
+        }
+    }
+
+    fn tuple(targets: &[&str]) -> Target {
+        Target::Tuple(
+            targets
+                .iter()
+                .map(|name| match name.strip_prefix('*') {
+                    Some(rest) => Target::Starred(rest.to_owned()),
+                    None => Target::name(*name),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_tuple_target_over_unknown_value_is_tuple_pattern() {
+        let pattern = lower_target(&tuple(&["a", "b"]), &Type::Unknown, &var("pair"))
+            .expect("Should lower tuple target");
+
+        assert_eq!(
+            pattern,
+            Pattern::Tuple(vec![Pattern::name("a"), Pattern::name("b")])
+        );
+    }
+
+    #[test]
+    fn test_starred_target_over_list_is_slice_pattern() {
+        let list = Type::Python(PythonType::List(Box::new(Type::Python(PythonType::Int))));
+        let pattern = lower_target(&tuple(&["first", "*middle", "last"]), &list, &var("xs"))
+            .expect("Should lower starred target");
+
+        assert_eq!(
+            pattern,
+            Pattern::Slice {
+                before: vec![Pattern::name("first")],
+                rest: Some("middle".to_owned()),
+                after: vec![Pattern::name("last")],
+            }
+        );
+        assert_eq!(pattern.names(), vec!["first", "middle", "last"]);
+    }
+
+    #[test]
+    fn test_starred_unpacking_of_tuple_is_reported() {
+        let pair = Type::Python(PythonType::Tuple(vec![
+            Type::Python(PythonType::Int),
+            Type::Python(PythonType::Str),
+        ]));
+
+        let error = lower_target(&tuple(&["a", "*rest"]), &pair, &var("pair"))
+            .expect_err("Should reject starred tuple unpacking");
+        assert!(
+            error.to_string().contains("starred unpacking of a tuple"),
+            "{error}"
+        );
+    }
+}
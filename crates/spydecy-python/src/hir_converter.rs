@@ -1,149 +1,1365 @@
 //! Python AST to HIR converter
 //!
 //! This module converts Python AST nodes into Spydecy's Python HIR.
+//!
+//! Values without a HIR representation, such as integer literals beyond 64
+//! bits, do not fail the conversion: the statement containing them is
+//! skipped and reported in [`Conversion::diagnostics`].
 
-use crate::parser::PythonAST;
-use anyhow::{bail, Result};
+use crate::{parser::PythonAST, type_extractor::annotation_to_type};
+use anyhow::{bail, Context, Result};
 use spydecy_hir::{
-    metadata::Metadata,
-    python::{Literal, PythonHIR},
-    NodeId, Visibility,
+    diagnostics::Diagnostic,
+    metadata::{cleandoc, Attribute, Metadata},
+    python::{
+        BinOp, Comprehension, ExceptHandler, FormatPart, ImportName, Literal, Parameter,
+        ParameterKind, PythonHIR, Target, UnaryOp, WithItem,
+    },
+    types::{PythonType, Type},
+    Language, NodeId, SourceLocation, Visibility,
 };
+use std::{collections::HashMap, fmt, path::Path};
+
+/// Python HIR together with the statements that had to be skipped
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    /// Converted node
+    pub hir: PythonHIR,
+    /// Skipped statements
+    pub diagnostics: Vec<Diagnostic>,
+}
 
 /// Convert Python AST to HIR
 ///
+/// A module is named after its file (`util.py` becomes `util`), or `main`
+/// when the file name is not an identifier. Skipped statements are logged
+/// as warnings; use [`convert`] to collect them instead.
+///
 /// # Errors
 ///
 /// Returns an error if the AST cannot be converted to HIR
 pub fn convert_to_hir(ast: &PythonAST) -> Result<PythonHIR> {
-    let mut id_counter = 1;
-    convert_node(ast, &mut id_counter)
+    Ok(logged(convert(ast)?))
+}
+
+/// Convert Python AST to HIR, collecting the statements that had to be
+/// skipped
+///
+/// # Errors
+///
+/// Returns an error if the AST cannot be converted to HIR
+pub fn convert(ast: &PythonAST) -> Result<Conversion> {
+    let file = ast.attributes.get("filename").map_or("", String::as_str);
+    let module = Path::new(file)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| is_identifier(stem))
+        .unwrap_or("main");
+    convert_project_module(ast, module)
+}
+
+/// Convert the AST of a project module to HIR, naming it by its dotted path
+///
+/// Skipped statements are logged as warnings; use
+/// [`convert_project_module`] to collect them instead.
+///
+/// # Errors
+///
+/// Returns an error if the AST cannot be converted to HIR
+pub fn convert_module_to_hir(ast: &PythonAST, module: &str) -> Result<PythonHIR> {
+    Ok(logged(convert_project_module(ast, module)?))
+}
+
+/// Convert the AST of a project module to HIR, naming it by its dotted
+/// path and collecting the statements that had to be skipped
+///
+/// # Errors
+///
+/// Returns an error if the AST cannot be converted to HIR
+pub fn convert_project_module(ast: &PythonAST, module: &str) -> Result<Conversion> {
+    let file = ast
+        .attributes
+        .get("filename")
+        .cloned()
+        .unwrap_or_else(|| "<unknown>".to_string());
+    let mut cx = ConversionContext {
+        next_id: 1,
+        file,
+        module: module.to_string(),
+        diagnostics: Vec::new(),
+    };
+    let hir = convert_node(ast, &mut cx)?;
+    Ok(Conversion {
+        hir,
+        diagnostics: cx.diagnostics,
+    })
+}
+
+/// The HIR of a conversion, after logging its diagnostics
+fn logged(conversion: Conversion) -> PythonHIR {
+    for diagnostic in &conversion.diagnostics {
+        tracing::warn!("{diagnostic}");
+    }
+    conversion.hir
+}
+
+/// Whether a name is a valid Python identifier
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+/// State shared by the converter functions
+struct ConversionContext {
+    /// Next node ID to hand out
+    next_id: u64,
+    /// Source file the AST was parsed from
+    file: String,
+    /// Dotted name of the module being converted
+    module: String,
+    /// Skipped statements
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Error of a statement that was skipped and reported in
+/// [`ConversionContext::diagnostics`]
+#[derive(Debug)]
+struct Skipped;
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "statement skipped")
+    }
+}
+
+impl std::error::Error for Skipped {}
+
+impl ConversionContext {
+    fn next_id(&mut self) -> NodeId {
+        let id = NodeId::new(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Report a statement containing `ast` as skipped, returning the error
+    /// that skips it
+    fn skip(&mut self, ast: &PythonAST, what: &str) -> anyhow::Error {
+        let diagnostic =
+            Diagnostic::warning(format!("{what}; the statement containing it was skipped"));
+        self.diagnostics
+            .push(diagnostic.with_location(self.meta(ast).source));
+        Skipped.into()
+    }
+
+    /// Metadata carrying the source span of an AST node
+    ///
+    /// Nodes without a position (the module itself) point at the start of
+    /// the file.
+    fn meta(&self, ast: &PythonAST) -> Metadata {
+        let line = ast.lineno.unwrap_or(1);
+        let column = ast.col_offset.map_or(1, |col| col + 1);
+        let end_line = ast.end_lineno.unwrap_or(line);
+        let end_column = ast.end_col_offset.map_or(column, |col| col + 1);
+        Metadata::with_source(
+            SourceLocation::new(self.file.clone(), line, column, Language::Python)
+                .with_end(end_line, end_column),
+        )
+    }
 }
 
-fn convert_node(ast: &PythonAST, id_counter: &mut u64) -> Result<PythonHIR> {
+fn convert_node(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
     match ast.node_type.as_str() {
-        "Module" => convert_module(ast, id_counter),
-        "FunctionDef" => convert_function_def(ast, id_counter),
-        "Return" => convert_return(ast, id_counter),
-        "Call" => convert_call(ast, id_counter),
-        "Name" => convert_name(ast, id_counter),
-        "Constant" => convert_constant(id_counter),
+        "Module" => convert_module(ast, cx),
+        "FunctionDef" => convert_function_def(ast, cx),
+        "ClassDef" => convert_class_def(ast, cx),
+        "Return" => convert_return(ast, cx),
+        "Expr" => convert_expr_statement(ast, cx),
+        "Assign" => convert_assign(ast, cx),
+        "AnnAssign" => convert_ann_assign(ast, cx),
+        "AugAssign" => convert_aug_assign(ast, cx),
+        "If" => convert_if(ast, cx),
+        "For" => convert_for(ast, cx),
+        "While" => convert_while(ast, cx),
+        "Break" | "Continue" | "Pass" => Ok(convert_simple_statement(ast, cx)),
+        "Delete" => convert_delete(ast, cx),
+        "Assert" => convert_assert(ast, cx),
+        "Global" | "Nonlocal" => Ok(convert_scope_declaration(ast, cx)),
+        "Import" | "ImportFrom" => Ok(convert_import(ast, cx)),
+        "Raise" => convert_raise(ast, cx),
+        "Try" => convert_try(ast, cx),
+        "With" => convert_with(ast, cx),
+        "Yield" => convert_yield(ast, cx),
+        "YieldFrom" => convert_yield_from(ast, cx),
+        "Call" => convert_call(ast, cx),
+        "Name" => convert_name(ast, cx),
+        "Constant" => convert_constant(ast, cx),
+        "JoinedStr" => convert_joined_str(ast, cx),
+        "BinOp" => convert_bin_op(ast, cx),
+        "BoolOp" => convert_bool_op(ast, cx),
+        "Compare" => convert_compare(ast, cx),
+        "UnaryOp" => convert_unary_op(ast, cx),
+        "IfExp" => convert_if_exp(ast, cx),
+        "Lambda" => convert_lambda(ast, cx),
+        "Attribute" => convert_attribute(ast, cx),
+        "Subscript" => convert_subscript(ast, cx),
+        "Starred" => convert_starred(ast, cx),
+        "Slice" => convert_slice(ast, cx),
+        "List" | "Tuple" | "Set" => convert_sequence(ast, cx),
+        "Dict" => convert_dict(ast, cx),
+        "ListComp" | "SetComp" | "DictComp" | "GeneratorExp" => convert_comprehension(ast, cx),
         _ => bail!("Unsupported Python AST node type: {}", ast.node_type),
     }
 }
 
 /// Convert Module node
-fn convert_module(ast: &PythonAST, id_counter: &mut u64) -> Result<PythonHIR> {
-    let mut body = Vec::new();
-    for child in &ast.children {
-        body.push(convert_node(child, id_counter)?);
-    }
+fn convert_module(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let mut body = convert_body(ast, "body", cx)?;
+    annotate_variable_types(&mut body, HashMap::new());
+    let mut meta = cx.meta(ast);
+    meta.docs = docstring(&body);
     Ok(PythonHIR::Module {
-        name: "main".to_string(),
+        name: cx.module.clone(),
         body,
-        meta: Metadata::new(),
+        meta,
     })
 }
 
 /// Convert FunctionDef node
-fn convert_function_def(ast: &PythonAST, id_counter: &mut u64) -> Result<PythonHIR> {
+fn convert_function_def(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
     let name = ast
         .attributes
         .get("name")
         .cloned()
         .unwrap_or_else(|| "unknown".to_string());
 
-    let mut body = Vec::new();
-    for child in &ast.children {
-        body.push(convert_node(child, id_counter)?);
-    }
+    let params = match ast.child("args") {
+        Some(arguments) => convert_parameters(arguments)?,
+        None => vec![],
+    };
+    let return_type = ast.child("returns").map(annotation_to_type);
+    let mut body = convert_body(ast, "body", cx)?;
+    annotate_variable_types(&mut body, parameter_types(&params));
 
-    let id = next_id(id_counter);
+    let (decorators, attributes) = convert_decorators(ast)?;
+
+    let id = cx.next_id();
+    let mut meta = cx.meta(ast);
+    meta.docs = docstring(&body);
+    meta.attributes = attributes;
     Ok(PythonHIR::Function {
         id,
         name,
-        params: vec![],
-        return_type: None,
+        params,
+        return_type,
         body,
-        decorators: vec![],
+        decorators,
         visibility: Visibility::Public,
-        meta: Metadata::new(),
+        meta,
     })
 }
 
-/// Convert Return node
-fn convert_return(ast: &PythonAST, id_counter: &mut u64) -> Result<PythonHIR> {
-    let value = if ast.children.is_empty() {
-        None
-    } else {
-        Some(Box::new(convert_node(&ast.children[0], id_counter)?))
+/// Convert ClassDef node
+fn convert_class_def(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let name = ast
+        .attributes
+        .get("name")
+        .cloned()
+        .unwrap_or_else(|| "unknown".to_string());
+    let bases = ast
+        .children_in("bases")
+        .map(dotted_name)
+        .collect::<Result<_>>()?;
+    let body = convert_body(ast, "body", cx)?;
+    let (decorators, attributes) = convert_decorators(ast)?;
+
+    let id = cx.next_id();
+    let mut meta = cx.meta(ast);
+    meta.docs = docstring(&body);
+    meta.attributes = attributes;
+    Ok(PythonHIR::Class {
+        id,
+        name,
+        bases,
+        body,
+        decorators,
+        meta,
+    })
+}
+
+/// Convert the decorators of a function or class
+///
+/// Returns the decorator names (`dataclass`, `functools.cache`) and one
+/// attribute per decorator, named `@<name>`, whose arguments are the call's
+/// arguments as source text (`order=True`).
+fn convert_decorators(ast: &PythonAST) -> Result<(Vec<String>, Vec<Attribute>)> {
+    let mut names = Vec::new();
+    let mut attributes = Vec::new();
+    for decorator in ast.children_in("decorator_list") {
+        let (name, args) = if decorator.node_type == "Call" {
+            let positional = decorator.children_in("args").map(argument_text);
+            let keywords = decorator.children_in("keywords").map(|keyword| {
+                let name = keyword
+                    .attributes
+                    .get("arg")
+                    .with_context(|| format!("`**` in decorator at {}", location_of(keyword)))?;
+                Ok(format!(
+                    "{name}={}",
+                    argument_text(required_child(keyword, "value")?)?
+                ))
+            });
+            (
+                dotted_name(required_child(decorator, "func")?)?,
+                positional.chain(keywords).collect::<Result<_>>()?,
+            )
+        } else {
+            (dotted_name(decorator)?, vec![])
+        };
+        attributes.push(Attribute::with_args(format!("@{name}"), args));
+        names.push(name);
+    }
+    Ok((names, attributes))
+}
+
+/// Source text of a constant or (dotted) name passed to a decorator
+fn argument_text(ast: &PythonAST) -> Result<String> {
+    match ast.node_type.as_str() {
+        "Constant" => {
+            let text = ast.attributes.get("value").cloned().unwrap_or_default();
+            Ok(match ast.attributes.get("value_type").map(String::as_str) {
+                Some("str") => format!("{text:?}"),
+                Some("None") => "None".to_owned(),
+                _ => text,
+            })
+        }
+        _ => dotted_name(ast)
+            .with_context(|| format!("Unsupported decorator argument at {}", location_of(ast))),
+    }
+}
+
+/// The docstring of a module, class or function body, cleaned up the way
+/// `inspect.cleandoc` does
+fn docstring(body: &[PythonHIR]) -> Option<String> {
+    let Some(PythonHIR::Literal {
+        value: Literal::Str(text),
+        ..
+    }) = body.first()
+    else {
+        return None;
     };
 
-    let id = next_id(id_counter);
+    cleandoc(text)
+}
+
+/// Render a `Name` or dotted `Attribute` expression (`abc.ABC`)
+fn dotted_name(ast: &PythonAST) -> Result<String> {
+    match ast.node_type.as_str() {
+        "Name" => Ok(name_of(ast)),
+        "Attribute" => {
+            let object = dotted_name(required_child(ast, "value")?)?;
+            let attr = ast
+                .attributes
+                .get("attr")
+                .context("Attribute has no name")?;
+            Ok(format!("{object}.{attr}"))
+        }
+        other => bail!("Unsupported base class expression: {other}"),
+    }
+}
+
+/// Convert an `arguments` node into the function's parameter list
+fn convert_parameters(arguments: &PythonAST) -> Result<Vec<Parameter>> {
+    arguments
+        .children
+        .iter()
+        .map(|arg| {
+            let kind = match arg.field.as_deref() {
+                Some("posonlyargs") => ParameterKind::PositionalOnly,
+                Some("args") => ParameterKind::Positional,
+                Some("vararg") => ParameterKind::VarPositional,
+                Some("kwonlyargs") => ParameterKind::KeywordOnly,
+                Some("kwarg") => ParameterKind::VarKeyword,
+                other => bail!("Unexpected parameter field: {other:?}"),
+            };
+            let name = arg
+                .attributes
+                .get("arg")
+                .cloned()
+                .context("Parameter has no name")?;
+            Ok(Parameter {
+                name,
+                kind,
+                type_annotation: arg.child("annotation").map(annotation_to_type),
+                default: arg.attributes.get("default").cloned(),
+            })
+        })
+        .collect()
+}
+
+/// The types that parameter names have inside the function body
+fn parameter_types(params: &[Parameter]) -> HashMap<String, Type> {
+    params
+        .iter()
+        .filter_map(|param| {
+            let annotation = param.type_annotation.clone()?;
+            let ty = match param.kind {
+                // `*args: T` is typed as a list of T, which becomes a `Vec<T>`
+                // parameter, and `**kwargs: T` as a dict of str to T
+                ParameterKind::VarPositional => {
+                    Type::Python(PythonType::List(Box::new(annotation)))
+                }
+                ParameterKind::VarKeyword => Type::Python(PythonType::Dict {
+                    key: Box::new(Type::Python(PythonType::Str)),
+                    value: Box::new(annotation),
+                }),
+                _ => annotation,
+            };
+            Some((param.name.clone(), ty))
+        })
+        .collect()
+}
+
+/// Fill in `Variable::inferred_type` from annotated parameters and assignments
+///
+/// Nested functions and classes are skipped; they were annotated with their
+/// own scope when they were converted.
+fn annotate_variable_types(body: &mut [PythonHIR], mut scope: HashMap<String, Type>) {
+    fn collect(node: &PythonHIR, scope: &mut HashMap<String, Type>) {
+        match node {
+            PythonHIR::Function { .. } | PythonHIR::Class { .. } => return,
+            PythonHIR::Assign {
+                target: Target::Name(name),
+                type_annotation: Some(ty),
+                ..
+            } => {
+                scope.insert(name.clone(), ty.clone());
+            }
+            _ => {}
+        }
+        for child in node.children() {
+            collect(child, scope);
+        }
+    }
+
+    fn apply(node: &mut PythonHIR, scope: &HashMap<String, Type>) {
+        match node {
+            PythonHIR::Function { .. } | PythonHIR::Class { .. } => return,
+            PythonHIR::Variable {
+                name,
+                inferred_type: inferred_type @ None,
+                ..
+            } => *inferred_type = scope.get(name).cloned(),
+            _ => {}
+        }
+        for child in node.children_mut() {
+            apply(child, scope);
+        }
+    }
+
+    for node in body.iter() {
+        collect(node, &mut scope);
+    }
+    if scope.is_empty() {
+        return;
+    }
+    for node in body {
+        apply(node, &scope);
+    }
+}
+
+/// Convert Return node
+fn convert_return(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let value = convert_optional(ast, "value", cx)?;
+
+    let id = cx.next_id();
     Ok(PythonHIR::Return {
         id,
         value,
-        meta: Metadata::new(),
+        meta: cx.meta(ast),
     })
 }
 
-/// Convert Call node
-fn convert_call(ast: &PythonAST, id_counter: &mut u64) -> Result<PythonHIR> {
-    if ast.children.is_empty() {
-        bail!("Call node must have at least one child (the callee)");
+/// Convert Assign node
+fn convert_assign(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let targets: Vec<&PythonAST> = ast.children_in("targets").collect();
+    if targets.len() != 1 {
+        bail!("Chained assignment (`a = b = value`) is not supported");
     }
+    let value = convert_node(required_child(ast, "value")?, cx)?;
+    convert_assignment_target(ast, targets[0], value, cx)
+}
 
-    let callee = Box::new(convert_node(&ast.children[0], id_counter)?);
+/// Convert AnnAssign node (`x: int = value`)
+fn convert_ann_assign(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let Some(value) = ast.child("value") else {
+        return convert_declaration(ast, cx);
+    };
+    let value = convert_node(value, cx)?;
+    let mut assignment = convert_assignment_target(ast, required_child(ast, "target")?, value, cx)?;
+    if let PythonHIR::Assign {
+        type_annotation, ..
+    }
+    | PythonHIR::Store {
+        type_annotation, ..
+    } = &mut assignment
+    {
+        *type_annotation = ast.child("annotation").map(annotation_to_type);
+    }
+    Ok(assignment)
+}
 
-    let mut args = Vec::new();
-    for child in &ast.children[1..] {
-        args.push(convert_node(child, id_counter)?);
+/// Convert an AnnAssign without a value (`x: int`), which only declares a name
+fn convert_declaration(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let target = required_child(ast, "target")?;
+    if target.node_type != "Name" {
+        bail!(
+            "Annotated declaration of a {} at {} is not supported",
+            target.node_type,
+            location_of(ast)
+        );
     }
+    let id = cx.next_id();
+    Ok(PythonHIR::Declaration {
+        id,
+        name: name_of(target),
+        type_annotation: annotation_to_type(required_child(ast, "annotation")?),
+        meta: cx.meta(ast),
+    })
+}
+
+/// Build an assignment to a name, attribute, subscript or unpacking target
+///
+/// The assignment node takes its location from the whole `statement`.
+fn convert_assignment_target(
+    statement: &PythonAST,
+    target: &PythonAST,
+    value: PythonHIR,
+    cx: &mut ConversionContext,
+) -> Result<PythonHIR> {
+    match target.node_type.as_str() {
+        "Name" | "Tuple" | "List" => {
+            let target = convert_target(target)?;
+            let id = cx.next_id();
+            Ok(PythonHIR::Assign {
+                id,
+                target,
+                value: Box::new(value),
+                type_annotation: None,
+                meta: cx.meta(statement),
+            })
+        }
+        "Attribute" | "Subscript" => {
+            let target = Box::new(convert_node(target, cx)?);
+            let id = cx.next_id();
+            Ok(PythonHIR::Store {
+                id,
+                target,
+                value: Box::new(value),
+                type_annotation: None,
+                meta: cx.meta(statement),
+            })
+        }
+        other => bail!("Unsupported assignment target: {other}"),
+    }
+}
+
+/// Convert a variable or (nested) unpacking target (`k, (a, *rest)`)
+fn convert_target(target: &PythonAST) -> Result<Target> {
+    match target.node_type.as_str() {
+        "Name" => Ok(Target::Name(name_of(target))),
+        "Tuple" | "List" => Ok(Target::Tuple(
+            target
+                .children_in("elts")
+                .map(convert_target)
+                .collect::<Result<_>>()?,
+        )),
+        "Starred" => match target.child("value") {
+            Some(value) if value.node_type == "Name" => Ok(Target::Starred(name_of(value))),
+            _ => bail!("Unsupported starred target ({})", location_of(target)),
+        },
+        other => bail!(
+            "Unsupported unpacking target: {other} ({})",
+            location_of(target)
+        ),
+    }
+}
+
+/// Convert AugAssign node (`x += value`)
+fn convert_aug_assign(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let op = convert_bin_operator(operator_name(ast, "op")?)?;
+    let target = Box::new(convert_node(required_child(ast, "target")?, cx)?);
+    let value = Box::new(convert_node(required_child(ast, "value")?, cx)?);
+
+    let id = cx.next_id();
+    Ok(PythonHIR::AugAssign {
+        id,
+        target,
+        op,
+        value,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert If node
+fn convert_if(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let condition = Box::new(convert_node(required_child(ast, "test")?, cx)?);
+    let then_branch = convert_body(ast, "body", cx)?;
+    let else_branch = convert_body(ast, "orelse", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::If {
+        id,
+        condition,
+        then_branch,
+        else_branch,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert For node
+fn convert_for(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let target = convert_target(required_child(ast, "target")?)?;
+    let iter = Box::new(convert_node(required_child(ast, "iter")?, cx)?);
+    let body = convert_body(ast, "body", cx)?;
+    let orelse = convert_body(ast, "orelse", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::For {
+        id,
+        target,
+        iter,
+        body,
+        orelse,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert While node
+fn convert_while(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let condition = Box::new(convert_node(required_child(ast, "test")?, cx)?);
+    let body = convert_body(ast, "body", cx)?;
+    let orelse = convert_body(ast, "orelse", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::While {
+        id,
+        condition,
+        body,
+        orelse,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Break, Continue and Pass nodes
+fn convert_simple_statement(ast: &PythonAST, cx: &mut ConversionContext) -> PythonHIR {
+    let id = cx.next_id();
+    let meta = cx.meta(ast);
+    match ast.node_type.as_str() {
+        "Break" => PythonHIR::Break { id, meta },
+        "Continue" => PythonHIR::Continue { id, meta },
+        _ => PythonHIR::Pass { id, meta },
+    }
+}
+
+/// Convert Expr node, an expression used as a statement
+///
+/// A lone `...`, the body of a stub, does nothing, like `pass`.
+fn convert_expr_statement(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let value = required_child(ast, "value")?;
+    if value.node_type == "Constant"
+        && value.attributes.get("value_type").map(String::as_str) == Some("ellipsis")
+    {
+        return Ok(convert_simple_statement(ast, cx));
+    }
+    convert_node(value, cx)
+}
+
+/// Convert Delete node
+fn convert_delete(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let targets = convert_body(ast, "targets", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Delete {
+        id,
+        targets,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Assert node
+fn convert_assert(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let test = Box::new(convert_node(required_child(ast, "test")?, cx)?);
+    let msg = convert_optional(ast, "msg", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Assert {
+        id,
+        test,
+        msg,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Raise node
+fn convert_raise(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let exception = convert_optional(ast, "exc", cx)?;
+    let cause = convert_optional(ast, "cause", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Raise {
+        id,
+        exception,
+        cause,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Yield node
+fn convert_yield(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let value = convert_optional(ast, "value", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Yield {
+        id,
+        value,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert YieldFrom node
+fn convert_yield_from(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let value = Box::new(convert_node(required_child(ast, "value")?, cx)?);
+
+    let id = cx.next_id();
+    Ok(PythonHIR::YieldFrom {
+        id,
+        value,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Try node
+fn convert_try(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let body = convert_body(ast, "body", cx)?;
+    let handlers = ast
+        .children_in("handlers")
+        .map(|handler| {
+            Ok(ExceptHandler {
+                exception_type: convert_optional(handler, "type", cx)?,
+                name: handler.attributes.get("name").cloned(),
+                body: convert_body(handler, "body", cx)?,
+                meta: cx.meta(handler),
+            })
+        })
+        .collect::<Result<_>>()?;
+    let orelse = convert_body(ast, "orelse", cx)?;
+    let finalbody = convert_body(ast, "finalbody", cx)?;
 
-    let id = next_id(id_counter);
+    let id = cx.next_id();
+    Ok(PythonHIR::Try {
+        id,
+        body,
+        handlers,
+        orelse,
+        finalbody,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert With node
+fn convert_with(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let items = ast
+        .children_in("items")
+        .map(|item| {
+            let target = match item.child("optional_vars") {
+                Some(target) if target.node_type == "Name" => Some(name_of(target)),
+                Some(target) => bail!("Unsupported with-statement target: {}", target.node_type),
+                None => None,
+            };
+            Ok(WithItem {
+                context: Box::new(convert_node(required_child(item, "context_expr")?, cx)?),
+                target,
+            })
+        })
+        .collect::<Result<_>>()?;
+    let body = convert_body(ast, "body", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::With {
+        id,
+        items,
+        body,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Global and Nonlocal nodes
+fn convert_scope_declaration(ast: &PythonAST, cx: &mut ConversionContext) -> PythonHIR {
+    let names = ast
+        .attributes
+        .get("names")
+        .map(|names| names.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let id = cx.next_id();
+    let meta = cx.meta(ast);
+    if ast.node_type == "Global" {
+        PythonHIR::Global { id, names, meta }
+    } else {
+        PythonHIR::Nonlocal { id, names, meta }
+    }
+}
+
+/// Convert Import and ImportFrom nodes
+fn convert_import(ast: &PythonAST, cx: &mut ConversionContext) -> PythonHIR {
+    let names = ast
+        .children_in("names")
+        .map(|alias| ImportName {
+            name: alias.attributes.get("name").cloned().unwrap_or_default(),
+            alias: alias.attributes.get("asname").cloned(),
+        })
+        .collect();
+    // `from . import x` has no `module`
+    let from = (ast.node_type == "ImportFrom")
+        .then(|| ast.attributes.get("module").cloned().unwrap_or_default());
+    let level = ast
+        .attributes
+        .get("level")
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(0);
+
+    let id = cx.next_id();
+    PythonHIR::Import {
+        id,
+        from,
+        level,
+        names,
+        meta: cx.meta(ast),
+    }
+}
+
+/// Convert Call node
+fn convert_call(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let Some(func) = ast.child("func") else {
+        bail!("Call node must have a callee");
+    };
+
+    let callee = Box::new(convert_node(func, cx)?);
+    let args = convert_body(ast, "args", cx)?;
+    // `keyword` nodes without an `arg` are `**mapping` splats
+    let kwargs = ast
+        .children_in("keywords")
+        .map(|keyword| {
+            let value = convert_node(required_child(keyword, "value")?, cx)?;
+            Ok((keyword.attributes.get("arg").cloned(), value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let id = cx.next_id();
     Ok(PythonHIR::Call {
         id,
         callee,
         args,
-        kwargs: vec![],
+        kwargs,
         inferred_type: None,
-        meta: Metadata::new(),
+        meta: cx.meta(ast),
     })
 }
 
 /// Convert Name node
 #[allow(clippy::unnecessary_wraps)]
-fn convert_name(ast: &PythonAST, id_counter: &mut u64) -> Result<PythonHIR> {
-    let name = ast
-        .attributes
-        .get("id")
-        .cloned()
-        .unwrap_or_else(|| "unknown".to_string());
+fn convert_name(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let name = name_of(ast);
 
-    let id = next_id(id_counter);
+    let id = cx.next_id();
     Ok(PythonHIR::Variable {
         id,
         name,
         inferred_type: None,
-        meta: Metadata::new(),
+        meta: cx.meta(ast),
     })
 }
 
 /// Convert Constant node
-#[allow(clippy::unnecessary_wraps)]
-fn convert_constant(id_counter: &mut u64) -> Result<PythonHIR> {
-    let id = next_id(id_counter);
+fn convert_constant(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let value_type = ast
+        .attributes
+        .get("value_type")
+        .map_or("None", String::as_str);
+    let text = ast.attributes.get("value").map_or("", String::as_str);
+
+    let value = match value_type {
+        "int" => match text.parse::<i64>() {
+            Ok(value) => Literal::Int(value),
+            Err(_) => {
+                return Err(cx.skip(
+                    ast,
+                    &format!("integer literal {text} does not fit in a 64-bit integer"),
+                ))
+            }
+        },
+        "float" => Literal::Float(
+            text.parse()
+                .with_context(|| format!("Invalid float literal {text}"))?,
+        ),
+        "str" => Literal::Str(text.to_string()),
+        "bytes" => Literal::Bytes(decode_hex(text)?),
+        "bool" => Literal::Bool(text == "True"),
+        "None" => Literal::None,
+        other => bail!(
+            "Unsupported constant {text} of type {other} at {}",
+            location_of(ast)
+        ),
+    };
+
+    let id = cx.next_id();
     Ok(PythonHIR::Literal {
         id,
-        value: Literal::None, // Placeholder
-        meta: Metadata::new(),
+        value,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert JoinedStr node (an f-string or the format spec of one of its fields)
+fn convert_joined_str(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let parts = ast
+        .children_in("values")
+        .map(|value| match value.node_type.as_str() {
+            "Constant" => Ok(FormatPart::Text(
+                value.attributes.get("value").cloned().unwrap_or_default(),
+            )),
+            "FormattedValue" => {
+                // `ast.FormattedValue.conversion` is a character code, -1 for none
+                let conversion = value
+                    .attributes
+                    .get("conversion")
+                    .and_then(|code| code.parse::<u32>().ok())
+                    .and_then(char::from_u32);
+                let spec = match value.child("format_spec") {
+                    Some(spec) => Some(Box::new(convert_node(spec, cx)?)),
+                    None => None,
+                };
+                Ok(FormatPart::Field {
+                    value: Box::new(convert_node(required_child(value, "value")?, cx)?),
+                    conversion,
+                    spec,
+                })
+            }
+            other => bail!("Unsupported f-string part: {other}"),
+        })
+        .collect::<Result<_>>()?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::FormattedString {
+        id,
+        parts,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Decode the hex encoding the parser uses for bytes constants
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            text.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .with_context(|| format!("Invalid bytes constant encoding: {text}"))
+        })
+        .collect()
+}
+
+/// Describe a node's position for error messages
+fn location_of(ast: &PythonAST) -> String {
+    match (ast.lineno, ast.col_offset) {
+        (Some(line), Some(col)) => format!("line {line}, column {}", col + 1),
+        (Some(line), None) => format!("line {line}"),
+        _ => "unknown location".to_string(),
+    }
+}
+
+/// Convert BinOp node
+fn convert_bin_op(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let op = convert_bin_operator(operator_name(ast, "op")?)?;
+    let left = convert_node(required_child(ast, "left")?, cx)?;
+    let right = convert_node(required_child(ast, "right")?, cx)?;
+    Ok(make_bin_op(op, left, right, ast, cx))
+}
+
+/// Convert BoolOp node (`a and b and c` folds left into nested `BinOp`s)
+fn convert_bool_op(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let op = match operator_name(ast, "op")? {
+        "And" => BinOp::And,
+        "Or" => BinOp::Or,
+        other => bail!("Unsupported boolean operator: {other}"),
+    };
+
+    let mut values = ast.children_in("values");
+    let Some(first) = values.next() else {
+        bail!("BoolOp node must have at least one value");
+    };
+    let mut result = convert_node(first, cx)?;
+    for value in values {
+        let right = convert_node(value, cx)?;
+        result = make_bin_op(op, result, right, ast, cx);
+    }
+    Ok(result)
+}
+
+/// Convert Compare node
+///
+/// Chained comparisons (`a < b < c`) become `(a < b) and (b < c)`.
+fn convert_compare(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let ops = operator_name(ast, "ops")?
+        .split(',')
+        .map(convert_bin_operator)
+        .collect::<Result<Vec<_>>>()?;
+    let comparators: Vec<&PythonAST> = ast.children_in("comparators").collect();
+    if ops.len() != comparators.len() {
+        bail!("Compare node has mismatched operators and comparators");
+    }
+
+    let mut left_ast = required_child(ast, "left")?;
+    let mut result: Option<PythonHIR> = None;
+    for (op, right_ast) in ops.into_iter().zip(comparators) {
+        let left = convert_node(left_ast, cx)?;
+        let right = convert_node(right_ast, cx)?;
+        let comparison = make_bin_op(op, left, right, ast, cx);
+        result = Some(match result {
+            Some(previous) => make_bin_op(BinOp::And, previous, comparison, ast, cx),
+            None => comparison,
+        });
+        left_ast = right_ast;
+    }
+    result.context("Compare node must have at least one comparison")
+}
+
+/// Convert UnaryOp node
+fn convert_unary_op(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let op = match operator_name(ast, "op")? {
+        "Not" => UnaryOp::Not,
+        "USub" => UnaryOp::Neg,
+        "UAdd" => UnaryOp::Pos,
+        "Invert" => UnaryOp::Invert,
+        other => bail!("Unsupported unary operator: {other}"),
+    };
+    let operand = Box::new(convert_node(required_child(ast, "operand")?, cx)?);
+
+    let id = cx.next_id();
+    Ok(PythonHIR::UnaryOp {
+        id,
+        op,
+        operand,
+        inferred_type: None,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert IfExp node (`body if test else orelse`)
+fn convert_if_exp(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let condition = Box::new(convert_node(required_child(ast, "test")?, cx)?);
+    let body = Box::new(convert_node(required_child(ast, "body")?, cx)?);
+    let orelse = Box::new(convert_node(required_child(ast, "orelse")?, cx)?);
+
+    let id = cx.next_id();
+    Ok(PythonHIR::IfExp {
+        id,
+        condition,
+        body,
+        orelse,
+        inferred_type: None,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Lambda node
+fn convert_lambda(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let params = convert_parameters(required_child(ast, "args")?)?;
+    let body = Box::new(convert_node(required_child(ast, "body")?, cx)?);
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Lambda {
+        id,
+        params,
+        body,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Attribute node
+fn convert_attribute(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let object = Box::new(convert_node(required_child(ast, "value")?, cx)?);
+    let attr = ast
+        .attributes
+        .get("attr")
+        .cloned()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Attribute {
+        id,
+        object,
+        attr,
+        inferred_type: None,
+        meta: cx.meta(ast),
     })
 }
 
-fn next_id(counter: &mut u64) -> NodeId {
-    let id = NodeId::new(*counter);
-    *counter += 1;
-    id
+/// Convert Subscript node
+fn convert_subscript(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let object = Box::new(convert_node(required_child(ast, "value")?, cx)?);
+    let index = Box::new(convert_node(required_child(ast, "slice")?, cx)?);
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Subscript {
+        id,
+        object,
+        index,
+        inferred_type: None,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Starred node (`*value`)
+fn convert_starred(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let value = Box::new(convert_node(required_child(ast, "value")?, cx)?);
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Starred {
+        id,
+        value,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert Slice node
+fn convert_slice(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let lower = convert_optional(ast, "lower", cx)?;
+    let upper = convert_optional(ast, "upper", cx)?;
+    let step = convert_optional(ast, "step", cx)?;
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Slice {
+        id,
+        lower,
+        upper,
+        step,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert List, Tuple and Set displays
+fn convert_sequence(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let elements = convert_body(ast, "elts", cx)?;
+
+    let id = cx.next_id();
+    let meta = cx.meta(ast);
+    Ok(match ast.node_type.as_str() {
+        "List" => PythonHIR::List {
+            id,
+            elements,
+            inferred_type: None,
+            meta,
+        },
+        "Tuple" => PythonHIR::Tuple {
+            id,
+            elements,
+            inferred_type: None,
+            meta,
+        },
+        _ => PythonHIR::Set {
+            id,
+            elements,
+            inferred_type: None,
+            meta,
+        },
+    })
+}
+
+/// Convert Dict display
+fn convert_dict(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let keys = convert_body(ast, "keys", cx)?;
+    let values = convert_body(ast, "values", cx)?;
+    if keys.len() != values.len() {
+        bail!("Dict unpacking (`{{**mapping}}`) is not supported");
+    }
+
+    let id = cx.next_id();
+    Ok(PythonHIR::Dict {
+        id,
+        entries: keys.into_iter().zip(values).collect(),
+        inferred_type: None,
+        meta: cx.meta(ast),
+    })
+}
+
+/// Convert ListComp, SetComp, DictComp and GeneratorExp nodes
+fn convert_comprehension(ast: &PythonAST, cx: &mut ConversionContext) -> Result<PythonHIR> {
+    let (element, value) = if ast.node_type == "DictComp" {
+        let key = convert_node(required_child(ast, "key")?, cx)?;
+        let value = convert_node(required_child(ast, "value")?, cx)?;
+        (Box::new(key), Some(Box::new(value)))
+    } else {
+        (
+            Box::new(convert_node(required_child(ast, "elt")?, cx)?),
+            None,
+        )
+    };
+    let generators = ast
+        .children_in("generators")
+        .map(|generator| convert_generator(generator, cx))
+        .collect::<Result<Vec<_>>>()?;
+
+    let id = cx.next_id();
+    let meta = cx.meta(ast);
+    Ok(match (ast.node_type.as_str(), value) {
+        ("DictComp", Some(value)) => PythonHIR::DictComp {
+            id,
+            key: element,
+            value,
+            generators,
+            meta,
+        },
+        ("SetComp", _) => PythonHIR::SetComp {
+            id,
+            element,
+            generators,
+            meta,
+        },
+        ("GeneratorExp", _) => PythonHIR::GeneratorExp {
+            id,
+            element,
+            generators,
+            meta,
+        },
+        _ => PythonHIR::ListComp {
+            id,
+            element,
+            generators,
+            meta,
+        },
+    })
+}
+
+/// Convert a comprehension clause (`for target in iter if cond`)
+fn convert_generator(ast: &PythonAST, cx: &mut ConversionContext) -> Result<Comprehension> {
+    if ast
+        .attributes
+        .get("is_async")
+        .is_some_and(|flag| flag != "0")
+    {
+        bail!(
+            "Async comprehensions are not supported ({})",
+            location_of(ast)
+        );
+    }
+    Ok(Comprehension {
+        target: convert_target(required_child(ast, "target")?)?,
+        iter: Box::new(convert_node(required_child(ast, "iter")?, cx)?),
+        ifs: convert_body(ast, "ifs", cx)?,
+    })
+}
+
+/// Build a `BinOp` node from already-converted operands
+fn make_bin_op(
+    op: BinOp,
+    left: PythonHIR,
+    right: PythonHIR,
+    ast: &PythonAST,
+    cx: &mut ConversionContext,
+) -> PythonHIR {
+    let id = cx.next_id();
+    PythonHIR::BinOp {
+        id,
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+        inferred_type: None,
+        meta: cx.meta(ast),
+    }
+}
+
+/// Map a Python `ast` operator class name onto a HIR binary operator
+fn convert_bin_operator(name: &str) -> Result<BinOp> {
+    Ok(match name {
+        "Add" => BinOp::Add,
+        "Sub" => BinOp::Sub,
+        "Mult" => BinOp::Mul,
+        "Div" => BinOp::Div,
+        "FloorDiv" => BinOp::FloorDiv,
+        "Mod" => BinOp::Mod,
+        "Pow" => BinOp::Pow,
+        "LShift" => BinOp::LShift,
+        "RShift" => BinOp::RShift,
+        "BitAnd" => BinOp::BitAnd,
+        "BitOr" => BinOp::BitOr,
+        "BitXor" => BinOp::BitXor,
+        "MatMult" => BinOp::MatMult,
+        "Eq" => BinOp::Eq,
+        "NotEq" => BinOp::NotEq,
+        "Lt" => BinOp::Lt,
+        "LtE" => BinOp::Le,
+        "Gt" => BinOp::Gt,
+        "GtE" => BinOp::Ge,
+        "Is" => BinOp::Is,
+        "IsNot" => BinOp::IsNot,
+        "In" => BinOp::In,
+        "NotIn" => BinOp::NotIn,
+        other => bail!("Unsupported binary operator: {other}"),
+    })
+}
+
+/// Get the operator class name stored in an attribute
+fn operator_name<'a>(ast: &'a PythonAST, attr: &str) -> Result<&'a str> {
+    ast.attributes
+        .get(attr)
+        .map(String::as_str)
+        .with_context(|| format!("{} node is missing its `{attr}` operator", ast.node_type))
+}
+
+/// Get the identifier of a Name node
+fn name_of(ast: &PythonAST) -> String {
+    ast.attributes
+        .get("id")
+        .cloned()
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Get the child stored in a required field
+fn required_child<'a>(ast: &'a PythonAST, field: &str) -> Result<&'a PythonAST> {
+    ast.child(field)
+        .with_context(|| format!("{} node is missing its `{field}` field", ast.node_type))
+}
+
+/// Convert the child stored in an optional field
+fn convert_optional(
+    ast: &PythonAST,
+    field: &str,
+    cx: &mut ConversionContext,
+) -> Result<Option<Box<PythonHIR>>> {
+    ast.child(field)
+        .map(|child| convert_node(child, cx).map(Box::new))
+        .transpose()
+}
+
+/// Convert every child stored in a list field, leaving out the skipped
+/// statements
+fn convert_body(
+    ast: &PythonAST,
+    field: &str,
+    cx: &mut ConversionContext,
+) -> Result<Vec<PythonHIR>> {
+    let mut body = Vec::new();
+    for child in ast.children_in(field) {
+        match convert_node(child, cx) {
+            Ok(node) => body.push(node),
+            Err(error) if error.is::<Skipped>() => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(body)
 }
 
 #[cfg(test)]
@@ -156,7 +1372,7 @@ mod tests {
         let mut func = PythonAST::new("FunctionDef".to_string());
         func.attributes
             .insert("name".to_string(), "my_len".to_string());
-        ast.children.push(func);
+        ast.push_child("body", func);
 
         let hir = convert_to_hir(&ast).unwrap();
 
@@ -175,10 +1391,787 @@ mod tests {
             .insert("name".to_string(), "test".to_string());
 
         let ret = PythonAST::new("Return".to_string());
-        func.children.push(ret);
-        module.children.push(func);
+        func.push_child("body", ret);
+        module.push_child("body", func);
 
         let hir = convert_to_hir(&module).unwrap();
         assert!(matches!(hir, PythonHIR::Module { .. }));
     }
+
+    fn convert_function_body(source: &str) -> Vec<PythonHIR> {
+        let ast = crate::parser::parse(source, "test.py").unwrap();
+        let PythonHIR::Module { mut body, .. } = convert_to_hir(&ast).unwrap() else {
+            panic!("Expected Module");
+        };
+        let Some(PythonHIR::Function { body, .. }) = body.pop() else {
+            panic!("Expected Function");
+        };
+        body
+    }
+
+    #[test]
+    fn test_convert_function_signature() {
+        let ast = crate::parser::parse(
+            "def f(a: int, /, b=2, *rest, key: str = 'x', **opts) -> bool:\n    pass\n",
+            "test.py",
+        )
+        .unwrap();
+        let PythonHIR::Module { body, .. } = convert_to_hir(&ast).unwrap() else {
+            panic!("Expected Module");
+        };
+        let PythonHIR::Function {
+            params,
+            return_type,
+            ..
+        } = &body[0]
+        else {
+            panic!("Expected Function");
+        };
+
+        let summary: Vec<(&str, ParameterKind, Option<&str>)> = params
+            .iter()
+            .map(|p| (p.name.as_str(), p.kind, p.default.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a", ParameterKind::PositionalOnly, None),
+                ("b", ParameterKind::Positional, Some("2")),
+                ("rest", ParameterKind::VarPositional, None),
+                ("key", ParameterKind::KeywordOnly, Some("'x'")),
+                ("opts", ParameterKind::VarKeyword, None),
+            ]
+        );
+        assert_eq!(
+            params[0].type_annotation,
+            Some(Type::Python(PythonType::Int))
+        );
+        assert_eq!(params[1].type_annotation, None);
+        assert_eq!(
+            params[3].type_annotation,
+            Some(Type::Python(PythonType::Str))
+        );
+        assert_eq!(return_type, &Some(Type::Python(PythonType::Bool)));
+    }
+
+    #[test]
+    fn test_convert_propagates_variable_types() {
+        let body = convert_function_body(
+            r"
+def f(xs: list[int], *rest: str):
+    total: float = 0.0
+    return xs, rest, total
+",
+        );
+
+        assert!(matches!(
+            &body[0],
+            PythonHIR::Assign {
+                type_annotation: Some(Type::Python(PythonType::Float)),
+                ..
+            }
+        ));
+        let PythonHIR::Return {
+            value: Some(value), ..
+        } = &body[1]
+        else {
+            panic!("Expected Return");
+        };
+        let PythonHIR::Tuple { elements, .. } = value.as_ref() else {
+            panic!("Expected Tuple");
+        };
+        let types: Vec<String> = elements
+            .iter()
+            .map(|element| match element {
+                PythonHIR::Variable {
+                    inferred_type: Some(ty),
+                    ..
+                } => ty.to_string(),
+                other => panic!("Expected typed Variable, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(types, vec!["list[int]", "list[str]", "float"]);
+    }
+
+    #[test]
+    fn test_convert_records_source_spans() {
+        let ast = crate::parser::parse("def f(x):\n    return x + 1\n", "spans.py").unwrap();
+        let hir = convert_to_hir(&ast).unwrap();
+        let PythonHIR::Module { body, meta, .. } = &hir else {
+            panic!("Expected Module");
+        };
+        assert_eq!(meta.source.as_ref().unwrap().file, "spans.py");
+
+        let function = body[0].metadata().source.as_ref().unwrap();
+        assert_eq!((function.line, function.column), (1, 1));
+        assert_eq!((function.end_line, function.end_column), (2, 17));
+        assert_eq!(function.language, Language::Python);
+
+        let PythonHIR::Function { body, .. } = &body[0] else {
+            panic!("Expected Function");
+        };
+        let PythonHIR::Return {
+            value: Some(sum), ..
+        } = &body[0]
+        else {
+            panic!("Expected Return");
+        };
+        let sum = sum.metadata().source.as_ref().unwrap();
+        assert_eq!(sum.to_string(), "spans.py:2:12");
+        assert_eq!((sum.end_line, sum.end_column), (2, 17));
+    }
+
+    #[test]
+    fn test_convert_class_def() {
+        let ast = crate::parser::parse(
+            r"
+class Cart(abc.ABC, Base):
+    def __init__(self):
+        self.items: list[str] = []
+
+    def add(self, item):
+        self.items.append(item)
+",
+            "test.py",
+        )
+        .unwrap();
+        let PythonHIR::Module { body, .. } = convert_to_hir(&ast).unwrap() else {
+            panic!("Expected Module");
+        };
+        let PythonHIR::Class {
+            name, bases, body, ..
+        } = &body[0]
+        else {
+            panic!("Expected Class");
+        };
+        assert_eq!(name, "Cart");
+        assert_eq!(bases, &vec!["abc.ABC".to_string(), "Base".to_string()]);
+        assert_eq!(body.len(), 2);
+
+        let PythonHIR::Function { body: init, .. } = &body[0] else {
+            panic!("Expected __init__");
+        };
+        assert!(matches!(
+            &init[0],
+            PythonHIR::Store {
+                type_annotation: Some(Type::Python(PythonType::List(_))),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_convert_control_flow() {
+        let body = convert_function_body(
+            r"
+def total(items):
+    count = 0
+    for item in items:
+        if item > 0 and not item == 3:
+            count += item
+        else:
+            continue
+    while count > 100:
+        count -= 1
+    return count
+",
+        );
+
+        assert!(
+            matches!(&body[0], PythonHIR::Assign { target, .. } if target.as_name() == Some("count"))
+        );
+        let PythonHIR::For {
+            target,
+            body: loop_body,
+            ..
+        } = &body[1]
+        else {
+            panic!("Expected For, got {:?}", body[1]);
+        };
+        assert_eq!(target, &Target::name("item"));
+        let PythonHIR::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } = &loop_body[0]
+        else {
+            panic!("Expected If");
+        };
+        assert!(matches!(
+            condition.as_ref(),
+            PythonHIR::BinOp { op: BinOp::And, .. }
+        ));
+        assert!(matches!(
+            &then_branch[0],
+            PythonHIR::AugAssign { op: BinOp::Add, .. }
+        ));
+        assert!(matches!(&else_branch[0], PythonHIR::Continue { .. }));
+        assert!(matches!(&body[2], PythonHIR::While { .. }));
+        assert!(matches!(&body[3], PythonHIR::Return { value: Some(_), .. }));
+    }
+
+    #[test]
+    fn test_convert_chained_comparison() {
+        let body = convert_function_body(
+            r"
+def in_range(x):
+    return 0 <= x < 10
+",
+        );
+
+        let PythonHIR::Return {
+            value: Some(value), ..
+        } = &body[0]
+        else {
+            panic!("Expected Return");
+        };
+        let PythonHIR::BinOp {
+            op: BinOp::And,
+            left,
+            right,
+            ..
+        } = value.as_ref()
+        else {
+            panic!("Expected chained comparison to become `and`");
+        };
+        assert!(matches!(
+            left.as_ref(),
+            PythonHIR::BinOp { op: BinOp::Le, .. }
+        ));
+        assert!(matches!(
+            right.as_ref(),
+            PythonHIR::BinOp { op: BinOp::Lt, .. }
+        ));
+    }
+
+    #[test]
+    fn test_convert_attribute_and_subscript_targets() {
+        let body = convert_function_body(
+            r"
+def update(self, xs):
+    self.total = xs[1:]
+    xs[0] = -xs[0]
+",
+        );
+
+        let PythonHIR::Store { target, value, .. } = &body[0] else {
+            panic!("Expected Store");
+        };
+        assert!(matches!(target.as_ref(), PythonHIR::Attribute { attr, .. } if attr == "total"));
+        let PythonHIR::Subscript { index, .. } = value.as_ref() else {
+            panic!("Expected Subscript");
+        };
+        assert!(matches!(
+            index.as_ref(),
+            PythonHIR::Slice { upper: None, .. }
+        ));
+        assert!(matches!(
+            &body[1],
+            PythonHIR::Store { value, .. } if matches!(value.as_ref(), PythonHIR::UnaryOp { op: UnaryOp::Neg, .. })
+        ));
+    }
+
+    #[test]
+    fn test_convert_unpacking_targets() {
+        let body = convert_function_body(
+            r"
+def split(pairs, d):
+    first, (second, *rest) = pairs
+    for k, v in d.items():
+        pass
+    return [a for a, _ in pairs]
+",
+        );
+
+        let PythonHIR::Assign { target, .. } = &body[0] else {
+            panic!("Expected Assign, got {:?}", body[0]);
+        };
+        assert_eq!(
+            target,
+            &Target::Tuple(vec![
+                Target::name("first"),
+                Target::Tuple(vec![
+                    Target::name("second"),
+                    Target::Starred("rest".to_owned())
+                ]),
+            ])
+        );
+        assert_eq!(target.names(), vec!["first", "second", "rest"]);
+        let PythonHIR::For { target, .. } = &body[1] else {
+            panic!("Expected For, got {:?}", body[1]);
+        };
+        assert_eq!(target.names(), vec!["k", "v"]);
+        let Some(PythonHIR::ListComp { generators, .. }) = (match &body[2] {
+            PythonHIR::Return { value, .. } => value.as_deref(),
+            _ => None,
+        }) else {
+            panic!("Expected a returned ListComp, got {:?}", body[2]);
+        };
+        assert_eq!(generators[0].target.names(), vec!["a", "_"]);
+    }
+
+    #[test]
+    fn test_convert_lambda() {
+        let body = convert_function_body(
+            r"
+def ranked(rows):
+    return sorted(rows, key=lambda row, weight=2: row[1] * weight)
+",
+        );
+
+        let Some(PythonHIR::Call { kwargs, .. }) = (match &body[0] {
+            PythonHIR::Return { value, .. } => value.as_deref(),
+            _ => None,
+        }) else {
+            panic!("Expected a returned Call, got {:?}", body[0]);
+        };
+        let (Some(keyword), PythonHIR::Lambda { params, body, .. }) = &kwargs[0] else {
+            panic!("Expected a key lambda, got {:?}", kwargs[0]);
+        };
+        assert_eq!(keyword, "key");
+        let params: Vec<_> = params
+            .iter()
+            .map(|param| (param.name.as_str(), param.default.as_deref()))
+            .collect();
+        assert_eq!(params, vec![("row", None), ("weight", Some("2"))]);
+        assert!(
+            matches!(body.as_ref(), PythonHIR::BinOp { .. }),
+            "Expected the lambda body expression, got {body:?}"
+        );
+    }
+
+    #[test]
+    fn test_convert_class_decorators() {
+        let ast = crate::parser::parse(
+            r#"
+@dataclasses.dataclass(order=True, eq=False)
+class Point:
+    x: float
+    label: str = "origin"
+"#,
+            "test.py",
+        )
+        .unwrap();
+        let PythonHIR::Module { body, .. } = convert_to_hir(&ast).unwrap() else {
+            panic!("Expected Module");
+        };
+
+        let PythonHIR::Class {
+            decorators,
+            body,
+            meta,
+            ..
+        } = &body[0]
+        else {
+            panic!("Expected Class, got {:?}", body[0]);
+        };
+        assert_eq!(decorators, &vec!["dataclasses.dataclass".to_string()]);
+        assert_eq!(meta.attributes[0].name, "@dataclasses.dataclass");
+        assert_eq!(meta.attributes[0].args, vec!["order=True", "eq=False"]);
+        assert!(
+            matches!(&body[0], PythonHIR::Declaration { name, .. } if name == "x"),
+            "Expected a bare declaration, got {:?}",
+            body[0]
+        );
+        assert!(
+            matches!(
+                &body[1],
+                PythonHIR::Assign {
+                    type_annotation: Some(_),
+                    ..
+                }
+            ),
+            "Expected an annotated assignment, got {:?}",
+            body[1]
+        );
+    }
+
+    #[test]
+    fn test_convert_literal_values() {
+        let body = convert_function_body(
+            r#"
+def defaults():
+    return [42, -1.5, "text", b"ab", False, None]
+"#,
+        );
+
+        let PythonHIR::Return {
+            value: Some(value), ..
+        } = &body[0]
+        else {
+            panic!("Expected Return");
+        };
+        let PythonHIR::List { elements, .. } = value.as_ref() else {
+            panic!("Expected List");
+        };
+        let literals: Vec<&Literal> = elements
+            .iter()
+            .filter_map(|element| match element {
+                PythonHIR::Literal { value, .. } => Some(value),
+                PythonHIR::UnaryOp { operand, .. } => match operand.as_ref() {
+                    PythonHIR::Literal { value, .. } => Some(value),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            literals,
+            vec![
+                &Literal::Int(42),
+                &Literal::Float(1.5),
+                &Literal::Str("text".to_string()),
+                &Literal::Bytes(b"ab".to_vec()),
+                &Literal::Bool(false),
+                &Literal::None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_comprehensions() {
+        let body = convert_function_body(
+            r"
+def f(xs, ys):
+    pairs = [x * y for x in xs if x > 0 for y in ys]
+    index = {x: i for i in range(3) if i for x in xs}
+    unique = {x for x in xs}
+    return sum(x for x in xs)
+",
+        );
+
+        let PythonHIR::Assign { value, .. } = &body[0] else {
+            panic!("Expected Assign");
+        };
+        let PythonHIR::ListComp {
+            element,
+            generators,
+            ..
+        } = value.as_ref()
+        else {
+            panic!("Expected ListComp");
+        };
+        assert!(matches!(
+            element.as_ref(),
+            PythonHIR::BinOp { op: BinOp::Mul, .. }
+        ));
+        let clauses: Vec<(Option<&str>, usize)> = generators
+            .iter()
+            .map(|generator| (generator.target.as_name(), generator.ifs.len()))
+            .collect();
+        assert_eq!(clauses, vec![(Some("x"), 1), (Some("y"), 0)]);
+
+        let PythonHIR::Assign { value, .. } = &body[1] else {
+            panic!("Expected Assign");
+        };
+        assert!(
+            matches!(value.as_ref(), PythonHIR::DictComp { generators, .. } if generators.len() == 2)
+        );
+        let PythonHIR::Assign { value, .. } = &body[2] else {
+            panic!("Expected Assign");
+        };
+        assert!(matches!(value.as_ref(), PythonHIR::SetComp { .. }));
+        let PythonHIR::Return {
+            value: Some(value), ..
+        } = &body[3]
+        else {
+            panic!("Expected Return");
+        };
+        let PythonHIR::Call { args, .. } = value.as_ref() else {
+            panic!("Expected Call");
+        };
+        assert!(matches!(&args[0], PythonHIR::GeneratorExp { .. }));
+    }
+
+    #[test]
+    fn test_convert_keyword_and_star_arguments() {
+        let body = convert_function_body(
+            r"
+def f(xs, extra, opts):
+    ys = sorted(xs, *extra, key=len, reverse=True, **opts)
+    return min(xs, default=0)
+",
+        );
+
+        let PythonHIR::Assign { value, .. } = &body[0] else {
+            panic!("Expected Assign");
+        };
+        let PythonHIR::Call { args, kwargs, .. } = value.as_ref() else {
+            panic!("Expected Call");
+        };
+        assert!(matches!(&args[1], PythonHIR::Starred { value, .. }
+            if matches!(value.as_ref(), PythonHIR::Variable { name, .. } if name == "extra")));
+        let names: Vec<Option<&str>> = kwargs.iter().map(|(name, _)| name.as_deref()).collect();
+        assert_eq!(names, vec![Some("key"), Some("reverse"), None]);
+        assert!(matches!(
+            &kwargs[1].1,
+            PythonHIR::Literal {
+                value: Literal::Bool(true),
+                ..
+            }
+        ));
+
+        let PythonHIR::Return {
+            value: Some(value), ..
+        } = &body[1]
+        else {
+            panic!("Expected Return");
+        };
+        let PythonHIR::Call { kwargs, .. } = value.as_ref() else {
+            panic!("Expected Call");
+        };
+        assert_eq!(kwargs[0].0.as_deref(), Some("default"));
+    }
+
+    #[test]
+    fn test_convert_docstrings() {
+        let ast = crate::parser::parse(
+            r#""""Shopping cart helpers."""
+
+class Cart:
+    """A cart.
+
+    Holds items.
+    """
+
+    def total(self):
+        """
+        Sum of the prices.
+        """
+        return 0
+
+def undocumented():
+    x = "not a docstring"
+"#,
+            "cart.py",
+        )
+        .unwrap();
+        let hir = convert_to_hir(&ast).unwrap();
+        assert_eq!(
+            hir.metadata().docs.as_deref(),
+            Some("Shopping cart helpers.")
+        );
+
+        let PythonHIR::Module { body, .. } = &hir else {
+            panic!("Expected Module");
+        };
+        let PythonHIR::Class {
+            body: class_body,
+            meta,
+            ..
+        } = &body[1]
+        else {
+            panic!("Expected Class");
+        };
+        assert_eq!(meta.docs.as_deref(), Some("A cart.\n\nHolds items."));
+        assert_eq!(
+            class_body[1].metadata().docs.as_deref(),
+            Some("Sum of the prices.")
+        );
+        assert_eq!(body[2].metadata().docs, None);
+    }
+
+    #[test]
+    fn test_convert_try_and_raise() {
+        let ast = crate::parser::parse(
+            "try:\n    x = items.pop()\nexcept (IndexError, KeyError) as e:\n    raise ValueError(\"empty\") from e\nexcept:\n    raise\nelse:\n    pass\nfinally:\n    done()\n",
+            "test.py",
+        )
+        .unwrap();
+        let hir = convert_to_hir(&ast).unwrap();
+        let PythonHIR::Module { body, .. } = &hir else {
+            panic!("Expected Module");
+        };
+        let PythonHIR::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        } = &body[0]
+        else {
+            panic!("Expected Try, got {:?}", body[0]);
+        };
+        assert_eq!((body.len(), orelse.len(), finalbody.len()), (1, 1, 1));
+        assert_eq!(handlers.len(), 2);
+
+        assert!(matches!(
+            handlers[0].exception_type.as_deref(),
+            Some(PythonHIR::Tuple { elements, .. }) if elements.len() == 2
+        ));
+        assert_eq!(handlers[0].name.as_deref(), Some("e"));
+        assert!(matches!(
+            &handlers[0].body[0],
+            PythonHIR::Raise {
+                exception: Some(_),
+                cause: Some(_),
+                ..
+            }
+        ));
+
+        assert!(handlers[1].exception_type.is_none());
+        assert!(matches!(
+            &handlers[1].body[0],
+            PythonHIR::Raise {
+                exception: None,
+                cause: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_convert_yield_and_yield_from() {
+        let ast = crate::parser::parse(
+            "def gen(items):\n    yield\n    yield 1\n    yield from items\n",
+            "test.py",
+        )
+        .unwrap();
+        let hir = convert_to_hir(&ast).unwrap();
+        let PythonHIR::Module { body, .. } = &hir else {
+            panic!("Expected Module");
+        };
+        let PythonHIR::Function { body, .. } = &body[0] else {
+            panic!("Expected Function");
+        };
+        assert!(matches!(&body[0], PythonHIR::Yield { value: None, .. }));
+        assert!(matches!(&body[1], PythonHIR::Yield { value: Some(_), .. }));
+        assert!(matches!(
+            &body[2],
+            PythonHIR::YieldFrom { value, .. }
+                if matches!(value.as_ref(), PythonHIR::Variable { name, .. } if name == "items")
+        ));
+    }
+
+    #[test]
+    fn test_convert_with() {
+        let body =
+            convert_function_body("def f(lock):\n    with open('a') as f, lock:\n        pass\n");
+        let PythonHIR::With { items, body, .. } = &body[0] else {
+            panic!("Expected With");
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].target.as_deref(), Some("f"));
+        assert!(matches!(items[0].context.as_ref(), PythonHIR::Call { .. }));
+        assert_eq!(items[1].target, None);
+        assert!(matches!(body[0], PythonHIR::Pass { .. }));
+    }
+
+    #[test]
+    fn test_convert_imports() {
+        let ast = crate::parser::parse(
+            "import os.path as p, sys\nfrom ..models import User as U\nfrom . import *\n",
+            "app/views.py",
+        )
+        .unwrap();
+        let PythonHIR::Module { name, body, .. } = convert_to_hir(&ast).unwrap() else {
+            panic!("Expected Module");
+        };
+        assert_eq!(name, "views");
+        let PythonHIR::Import {
+            from: None,
+            level: 0,
+            names,
+            ..
+        } = &body[0]
+        else {
+            panic!("Expected Import");
+        };
+        assert_eq!(names[0].name, "os.path");
+        assert_eq!(names[0].alias.as_deref(), Some("p"));
+        assert_eq!(names[1].alias, None);
+        let PythonHIR::Import {
+            from: Some(from),
+            level: 2,
+            names,
+            ..
+        } = &body[1]
+        else {
+            panic!("Expected ImportFrom");
+        };
+        assert_eq!(from, "models");
+        assert_eq!(names[0].alias.as_deref(), Some("U"));
+        assert!(
+            matches!(&body[2], PythonHIR::Import { from: Some(from), level: 1, names, .. }
+            if from.is_empty() && names[0].name == "*")
+        );
+    }
+
+    #[test]
+    fn test_convert_f_string() {
+        let body = convert_function_body("def f(x):\n    return f'x={x!r:>8}!'\n");
+        let PythonHIR::Return {
+            value: Some(value), ..
+        } = &body[0]
+        else {
+            panic!("Expected Return");
+        };
+        let PythonHIR::FormattedString { parts, .. } = value.as_ref() else {
+            panic!("Expected FormattedString");
+        };
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], FormatPart::Text("x=".to_owned()));
+        let FormatPart::Field {
+            conversion, spec, ..
+        } = &parts[1]
+        else {
+            panic!("Expected Field");
+        };
+        assert_eq!(*conversion, Some('r'));
+        let Some(PythonHIR::FormattedString { parts: spec, .. }) = spec.as_deref() else {
+            panic!("Expected a format spec");
+        };
+        assert_eq!(spec, &[FormatPart::Text(">8".to_owned())]);
+    }
+
+    #[test]
+    fn test_integer_overflow_skips_statement() {
+        let ast =
+            crate::parser::parse("x = 123456789012345678901234567890\ny = 1\n", "test.py").unwrap();
+        let conversion = convert(&ast).unwrap();
+
+        let PythonHIR::Module { body, .. } = &conversion.hir else {
+            panic!("Expected Module");
+        };
+        assert!(
+            matches!(body.as_slice(), [PythonHIR::Assign { target: Target::Name(name), .. }] if name == "y"),
+            "{body:?}"
+        );
+        let [diagnostic] = conversion.diagnostics.as_slice() else {
+            panic!("Expected one diagnostic, got {:?}", conversion.diagnostics);
+        };
+        assert_eq!(
+            diagnostic.message,
+            "integer literal 123456789012345678901234567890 does not fit in a 64-bit integer; \
+             the statement containing it was skipped"
+        );
+        let location = diagnostic.location.as_ref().unwrap();
+        assert_eq!((location.line, location.column), (1, 5));
+    }
+
+    #[test]
+    fn test_ellipsis_body_is_a_no_op() {
+        let ast = crate::parser::parse("def stub(x: int) -> None:\n    ...\n", "test.py").unwrap();
+        let hir = convert_to_hir(&ast).unwrap();
+
+        let PythonHIR::Module { body, .. } = &hir else {
+            panic!("Expected Module");
+        };
+        let [PythonHIR::Function { body, .. }] = body.as_slice() else {
+            panic!("Expected a function, got {body:?}");
+        };
+        assert!(
+            matches!(body.as_slice(), [PythonHIR::Pass { .. }]),
+            "{body:?}"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_node_is_reported() {
+        let ast = crate::parser::parse("async def f():\n    pass\n", "test.py").unwrap();
+        let err = convert_to_hir(&ast).unwrap_err();
+        assert!(err.to_string().contains("AsyncFunctionDef"));
+    }
 }
@@ -1,7 +1,8 @@
 //! Python transpiler - converts Python AST to Spydecy HIR
 //!
-//! This module uses PyO3 to parse Python code into AST, then converts
-//! it to Spydecy's Unified HIR for cross-layer optimization.
+//! This module parses Python code into AST, with CPython's `ast` module via
+//! PyO3 or with a pure-Rust parser (see [`parser::ParserBackend`]), then
+//! converts it to Spydecy's Unified HIR for cross-layer optimization.
 //!
 //! # Sprint 2 Deliverables
 //!
@@ -23,10 +24,13 @@
 )]
 
 pub mod hir_converter;
+pub mod loader;
 pub mod parser;
 pub mod type_extractor;
+pub mod version;
 
 use anyhow::Result;
+use parser::ParseOptions;
 use spydecy_hir::python::PythonHIR;
 
 /// Parse Python source code into HIR
@@ -39,6 +43,22 @@ pub fn parse_python(source: &str, filename: &str) -> Result<PythonHIR> {
     hir_converter::convert_to_hir(&ast)
 }
 
+/// Parse Python source code into HIR with the given parser options (or
+/// backend)
+///
+/// # Errors
+///
+/// Returns an error if the backend is not available, or the Python code
+/// cannot be parsed, is newer than the target or cannot be converted to HIR
+pub fn parse_python_with(
+    options: impl Into<ParseOptions>,
+    source: &str,
+    filename: &str,
+) -> Result<PythonHIR> {
+    let ast = parser::parse_with(options, source, filename)?;
+    hir_converter::convert_to_hir(&ast)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,208 @@
+//! Multi-module project loader
+//!
+//! [`load_project`] walks a directory, converts every `.py` file into a
+//! module named by its dotted path and resolves the imports between them
+//! into a [`SymbolTable`]:
+//!
+//! ```text
+//! src/app/__init__.py   →  app         (package)
+//! src/app/models.py     →  app.models
+//! src/main.py           →  main
+//! ```
+//!
+//! When the directory is itself a package (holds an `__init__.py`), its
+//! name is the first component of every module name. Hidden directories,
+//! `__pycache__` and files whose names are not identifiers are skipped.
+
+use crate::{
+    hir_converter::{self, is_identifier},
+    parser,
+};
+use anyhow::{Context, Result};
+use spydecy_hir::{diagnostics::Diagnostic, imports::SymbolTable, python::PythonHIR};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Python module of a project
+#[derive(Debug, Clone)]
+pub struct ProjectModule {
+    /// Dotted module name
+    pub name: String,
+    /// Source file
+    pub path: PathBuf,
+    /// Whether the module is a package (`__init__.py`)
+    pub is_package: bool,
+    /// Converted module
+    pub hir: PythonHIR,
+}
+
+/// Python project loaded from a directory
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Modules, sorted by name
+    pub modules: Vec<ProjectModule>,
+    /// Names bound by each module, with imports resolved
+    pub symbols: SymbolTable,
+    /// Skipped statements and unresolved imports
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Load every module of the Python project rooted at `root`
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read or a module cannot be
+/// parsed or converted to HIR. Skipped statements and unresolved imports
+/// are not errors; they are collected in [`Project::diagnostics`].
+pub fn load_project(root: &Path) -> Result<Project> {
+    let prefix = if root.join("__init__.py").is_file() {
+        let name = root
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", root.display()))?
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(str::to_string)
+            .with_context(|| format!("Package {} has no name", root.display()))?;
+        vec![name]
+    } else {
+        vec![]
+    };
+
+    let mut modules = Vec::new();
+    let mut diagnostics = Vec::new();
+    collect_modules(root, &prefix, &mut modules, &mut diagnostics)?;
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut symbols = SymbolTable::new();
+    for module in &modules {
+        symbols.define_module(&module.name, module.is_package, module_body(&module.hir));
+    }
+    for module in &modules {
+        diagnostics.extend(symbols.resolve_imports(&module.name, module_body(&module.hir)));
+    }
+
+    Ok(Project {
+        modules,
+        symbols,
+        diagnostics,
+    })
+}
+
+/// Convert the modules of a directory and its subdirectories
+fn collect_modules(
+    dir: &Path,
+    package: &[String],
+    modules: &mut Vec<ProjectModule>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if path.is_dir() {
+            if is_identifier(stem) && stem != "__pycache__" {
+                let mut subpackage = package.to_vec();
+                subpackage.push(stem.to_string());
+                collect_modules(&path, &subpackage, modules, diagnostics)?;
+            }
+            continue;
+        }
+        if path.extension().and_then(|ext| ext.to_str()) != Some("py") || !is_identifier(stem) {
+            continue;
+        }
+
+        let is_package = stem == "__init__";
+        let mut name = package.to_vec();
+        if !is_package {
+            name.push(stem.to_string());
+        }
+        if name.is_empty() {
+            // `__init__.py` of a directory that is not a package
+            continue;
+        }
+        let name = name.join(".");
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let ast = parser::parse(&source, &path.to_string_lossy())?;
+        let conversion = hir_converter::convert_project_module(&ast, &name)
+            .with_context(|| format!("Failed to convert module {name}"))?;
+        diagnostics.extend(conversion.diagnostics);
+        let hir = conversion.hir;
+        modules.push(ProjectModule {
+            name,
+            path,
+            is_package,
+            hir,
+        });
+    }
+    Ok(())
+}
+
+/// Top-level statements of a converted module
+fn module_body(hir: &PythonHIR) -> &[PythonHIR] {
+    match hir {
+        PythonHIR::Module { body, .. } => body,
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, file: &str, source: &str) {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+
+    #[test]
+    fn test_load_project_names_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "main.py", "from app.util import slugify\n");
+        write(dir.path(), "app/__init__.py", "");
+        write(dir.path(), "app/util.py", "def slugify(s):\n    return s\n");
+        write(dir.path(), "app/__pycache__/util.py", "");
+        write(dir.path(), "not-a-module.py", "");
+
+        let project = load_project(dir.path()).unwrap();
+
+        let names: Vec<_> = project
+            .modules
+            .iter()
+            .map(|module| (module.name.as_str(), module.is_package))
+            .collect();
+        assert_eq!(names, [("app", true), ("app.util", false), ("main", false)]);
+        assert!(project.diagnostics.is_empty(), "{:?}", project.diagnostics);
+        assert!(
+            matches!(&project.modules[2].hir, PythonHIR::Module { name, .. } if name == "main")
+        );
+    }
+
+    #[test]
+    fn test_load_package_root_reports_third_party_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("shop");
+        write(&root, "__init__.py", "");
+        write(&root, "cart.py", "import requests\nfrom . import prices\n");
+        write(&root, "prices.py", "");
+
+        let project = load_project(&root).unwrap();
+
+        let names: Vec<_> = project.symbols.module_names().collect();
+        assert_eq!(names, ["shop", "shop.cart", "shop.prices"]);
+        assert_eq!(project.diagnostics.len(), 1);
+        let diagnostic = &project.diagnostics[0];
+        assert_eq!(
+            diagnostic.message,
+            "unresolved import `requests`: module is not part of the project"
+        );
+        assert_eq!(diagnostic.location.as_ref().map(|l| l.line), Some(1));
+    }
+}
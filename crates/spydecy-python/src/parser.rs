@@ -1,14 +1,38 @@
-//! Python AST parser using PyO3
+//! Python AST parser
 //!
-//! This module uses PyO3 to invoke Python's `ast` module for parsing.
+//! Two backends produce the same [`PythonAST`]:
+//!
+//! - **PyO3** (`pyo3-parser` feature, on by default) runs CPython's own
+//!   `ast` module in an embedded interpreter.
+//! - **Native** (`native-parser` feature) is a pure-Rust tokenizer and
+//!   parser following CPython 3.11's grammar, for builds and deployments
+//!   without a Python installation.
+//!
+//! Whatever produced it, the tree follows one schema, that of Python 3.11's
+//! `ast` module: the PyO3 backend rewrites the shapes of older interpreters
+//! (`Num`/`Str` literals, `Index` subscripts) as it extracts them. Syntax
+//! newer than the [target version](crate::version) is then rejected.
+//!
+//! [`parse_with`] takes its [`ParseOptions`] per call; [`parse`] reads them
+//! from the `SPYDECY_PYTHON_PARSER` (`pyo3` or `native`) and
+//! `SPYDECY_PYTHON_TARGET` (e.g. `3.10`) environment variables.
+
+#[cfg(not(any(feature = "pyo3-parser", feature = "native-parser")))]
+compile_error!("spydecy-python needs the `pyo3-parser` or `native-parser` feature");
+
+#[cfg(feature = "pyo3-parser")]
+mod cpython;
+#[cfg(feature = "native-parser")]
+mod native;
+mod unparse;
 
-use anyhow::{Context, Result};
-use pyo3::prelude::*;
-use pyo3::types::PyModule;
+use crate::version::{self, PythonVersion};
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 
 /// Python AST node (simplified representation)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PythonAST {
     /// Node type (e.g., "Module", "FunctionDef", "Call")
     pub node_type: String,
@@ -16,6 +40,15 @@ pub struct PythonAST {
     pub lineno: Option<usize>,
     /// Column offset
     pub col_offset: Option<usize>,
+    /// End line number
+    #[serde(default)]
+    pub end_lineno: Option<usize>,
+    /// End column offset
+    #[serde(default)]
+    pub end_col_offset: Option<usize>,
+    /// Name of the parent field this node was found in (e.g., "body", "test")
+    #[serde(default)]
+    pub field: Option<String>,
     /// Child nodes
     pub children: Vec<PythonAST>,
     /// Node attributes (name, value, etc.)
@@ -30,150 +63,194 @@ impl PythonAST {
             node_type,
             lineno: None,
             col_offset: None,
+            end_lineno: None,
+            end_col_offset: None,
+            field: None,
             children: Vec::new(),
             attributes: std::collections::HashMap::new(),
         }
     }
-}
-
-/// Parse Python source code into AST
-///
-/// # Errors
-///
-/// Returns an error if the Python code cannot be parsed
-pub fn parse(source: &str, filename: &str) -> Result<PythonAST> {
-    Python::with_gil(|py| parse_with_python(py, source, filename))
-}
 
-/// Parse Python source using Python's ast module
-fn parse_with_python(py: Python<'_>, source: &str, filename: &str) -> Result<PythonAST> {
-    // Import Python's ast module
-    let ast_module =
-        PyModule::import_bound(py, "ast").context("Failed to import Python ast module")?;
+    /// Append a child node found in the given parent field
+    pub fn push_child(&mut self, field: &str, mut child: PythonAST) {
+        child.field = Some(field.to_string());
+        self.children.push(child);
+    }
 
-    // Parse the source code
-    let ast_obj = ast_module
-        .call_method1("parse", (source, filename))
-        .context("Failed to parse Python source code")?;
+    /// Iterate over the children found in the given field
+    pub fn children_in<'a>(&'a self, field: &'a str) -> impl Iterator<Item = &'a PythonAST> {
+        self.children
+            .iter()
+            .filter(move |child| child.field.as_deref() == Some(field))
+    }
 
-    // Convert Python AST to our simplified AST representation
-    extract_ast_node(&ast_obj)
+    /// Get the first child found in the given field
+    #[must_use]
+    pub fn child(&self, field: &str) -> Option<&PythonAST> {
+        self.children
+            .iter()
+            .find(|child| child.field.as_deref() == Some(field))
+    }
 }
 
-/// Extract AST node information from Python object
-fn extract_ast_node(obj: &Bound<'_, PyAny>) -> Result<PythonAST> {
-    let node_type = obj
-        .getattr("__class__")?
-        .getattr("__name__")?
-        .extract::<String>()?;
+/// Parser implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParserBackend {
+    /// CPython's `ast` module, through PyO3
+    Pyo3,
+    /// Pure-Rust parser
+    Native,
+}
 
-    let mut ast = PythonAST::new(node_type.clone());
+impl ParserBackend {
+    /// Environment variable naming the backend used by [`parse`]
+    pub const ENV_VAR: &'static str = "SPYDECY_PYTHON_PARSER";
 
-    // Extract line number and column offset
-    extract_location_info(obj, &mut ast);
+    /// Cargo feature that compiles the backend in
+    #[must_use]
+    pub const fn feature(self) -> &'static str {
+        match self {
+            Self::Pyo3 => "pyo3-parser",
+            Self::Native => "native-parser",
+        }
+    }
 
-    // Extract node-specific attributes
-    extract_node_attributes(obj, &node_type, &mut ast)?;
+    /// Whether the backend was compiled in
+    #[must_use]
+    pub const fn is_available(self) -> bool {
+        match self {
+            Self::Pyo3 => cfg!(feature = "pyo3-parser"),
+            Self::Native => cfg!(feature = "native-parser"),
+        }
+    }
 
-    Ok(ast)
+    /// Backend named by [`Self::ENV_VAR`], or the default when it is unset
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable names an unknown backend
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(Self::ENV_VAR) {
+            Ok(name) if !name.is_empty() => name.parse(),
+            _ => Ok(Self::default()),
+        }
+    }
 }
 
-/// Extract location information (line number and column offset)
-fn extract_location_info(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) {
-    if let Ok(lineno) = obj.getattr("lineno") {
-        ast.lineno = lineno.extract().ok();
-    }
-    if let Ok(col_offset) = obj.getattr("col_offset") {
-        ast.col_offset = col_offset.extract().ok();
+impl Default for ParserBackend {
+    /// PyO3 when it is compiled in, the native parser otherwise
+    fn default() -> Self {
+        if cfg!(feature = "pyo3-parser") {
+            Self::Pyo3
+        } else {
+            Self::Native
+        }
     }
 }
 
-/// Extract node-specific attributes based on node type
-fn extract_node_attributes(
-    obj: &Bound<'_, PyAny>,
-    node_type: &str,
-    ast: &mut PythonAST,
-) -> Result<()> {
-    match node_type {
-        "Module" => extract_module_attrs(obj, ast)?,
-        "FunctionDef" => extract_function_def_attrs(obj, ast)?,
-        "Return" => extract_return_attrs(obj, ast)?,
-        "Call" => extract_call_attrs(obj, ast)?,
-        "Name" => extract_name_attrs(obj, ast)?,
-        _ => extract_default_attrs(obj, ast)?,
-    }
-    Ok(())
-}
+impl FromStr for ParserBackend {
+    type Err = anyhow::Error;
 
-/// Extract Module node attributes
-fn extract_module_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
-    if let Ok(body) = obj.getattr("body") {
-        ast.children = extract_list(&body)?;
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "pyo3" | "cpython" => Ok(Self::Pyo3),
+            "native" | "rust" => Ok(Self::Native),
+            _ => bail!("Unknown Python parser `{name}` (expected `pyo3` or `native`)"),
+        }
     }
-    Ok(())
 }
 
-/// Extract FunctionDef node attributes
-fn extract_function_def_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
-    if let Ok(name) = obj.getattr("name") {
-        ast.attributes.insert("name".to_string(), name.extract()?);
-    }
-    if let Ok(body) = obj.getattr("body") {
-        ast.children = extract_list(&body)?;
+impl fmt::Display for ParserBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pyo3 => "pyo3",
+            Self::Native => "native",
+        })
     }
-    Ok(())
 }
 
-/// Extract Return node attributes
-fn extract_return_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
-    if let Ok(value) = obj.getattr("value") {
-        if !value.is_none() {
-            ast.children.push(extract_ast_node(&value)?);
-        }
-    }
-    Ok(())
+/// How to parse: which backend, for which Python version
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Parser implementation
+    pub backend: ParserBackend,
+    /// Newest Python version whose syntax is accepted
+    pub target: PythonVersion,
 }
 
-/// Extract Call node attributes
-fn extract_call_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
-    if let Ok(func) = obj.getattr("func") {
-        ast.children.push(extract_ast_node(&func)?);
+impl ParseOptions {
+    /// Options named by [`ParserBackend::ENV_VAR`] and
+    /// [`PythonVersion::ENV_VAR`], with defaults for unset variables
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable names an unknown backend or an
+    /// unsupported version
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            backend: ParserBackend::from_env()?,
+            target: PythonVersion::from_env()?,
+        })
+    }
+
+    /// Use the given backend
+    #[must_use]
+    pub const fn with_backend(mut self, backend: ParserBackend) -> Self {
+        self.backend = backend;
+        self
     }
-    if let Ok(args) = obj.getattr("args") {
-        ast.children.extend(extract_list(&args)?);
+
+    /// Accept syntax up to the given version
+    #[must_use]
+    pub const fn with_target(mut self, target: PythonVersion) -> Self {
+        self.target = target;
+        self
     }
-    Ok(())
 }
 
-/// Extract Name node attributes
-fn extract_name_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
-    if let Ok(id) = obj.getattr("id") {
-        ast.attributes.insert("id".to_string(), id.extract()?);
+impl From<ParserBackend> for ParseOptions {
+    fn from(backend: ParserBackend) -> Self {
+        Self::default().with_backend(backend)
     }
-    Ok(())
 }
 
-/// Extract default attributes for unknown node types
-#[allow(clippy::unnecessary_wraps)]
-fn extract_default_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
-    if let Ok(value) = obj.getattr("value") {
-        if !value.is_none() {
-            if let Ok(child) = extract_ast_node(&value) {
-                ast.children.push(child);
-            }
-        }
-    }
-    Ok(())
+/// Parse Python source code into AST
+///
+/// The options come from [`ParseOptions::from_env`].
+///
+/// # Errors
+///
+/// Returns an error if the Python code cannot be parsed, uses syntax newer
+/// than the target, or the environment names an unknown backend or version
+pub fn parse(source: &str, filename: &str) -> Result<PythonAST> {
+    parse_with(ParseOptions::from_env()?, source, filename)
 }
 
-/// Extract a list of AST nodes
-fn extract_list(list: &Bound<'_, PyAny>) -> Result<Vec<PythonAST>> {
-    let mut nodes = Vec::new();
-    for item in list.iter()? {
-        let item = item?;
-        nodes.push(extract_ast_node(&item)?);
-    }
-    Ok(nodes)
+/// Parse Python source code into AST with the given options (or backend)
+///
+/// # Errors
+///
+/// Returns an error if the Python code cannot be parsed, uses syntax newer
+/// than the target, or the backend was not compiled in
+pub fn parse_with(
+    options: impl Into<ParseOptions>,
+    source: &str,
+    filename: &str,
+) -> Result<PythonAST> {
+    let ParseOptions { backend, target } = options.into();
+    let ast = match backend {
+        #[cfg(feature = "pyo3-parser")]
+        ParserBackend::Pyo3 => cpython::parse(source, filename)?,
+        #[cfg(feature = "native-parser")]
+        ParserBackend::Native => native::parse(source, filename)?,
+        #[allow(unreachable_patterns)]
+        _ => bail!(
+            "The {backend} Python parser is not available; enable the `{}` feature of spydecy-python",
+            backend.feature()
+        ),
+    };
+    version::check_syntax(&ast, target)?;
+    Ok(ast)
 }
 
 #[cfg(test)]
@@ -201,6 +278,93 @@ def my_len(x: list) -> int:
         assert_eq!(ast.node_type, "Module");
     }
 
+    #[test]
+    fn test_parse_records_fields_and_operators() {
+        let source = r"
+if a < b <= c:
+    x += 1
+else:
+    pass
+";
+        let ast = parse(source, "test.py").unwrap();
+        let if_node = ast.child("body").unwrap();
+        assert_eq!(if_node.node_type, "If");
+
+        let test = if_node.child("test").unwrap();
+        assert_eq!(test.node_type, "Compare");
+        assert_eq!(test.attributes.get("ops").unwrap(), "Lt,LtE");
+        assert_eq!(test.children_in("comparators").count(), 2);
+
+        let aug = if_node.child("body").unwrap();
+        assert_eq!(aug.attributes.get("op").unwrap(), "Add");
+        assert_eq!(if_node.child("orelse").unwrap().node_type, "Pass");
+    }
+
+    #[test]
+    fn test_parse_constant_values() {
+        let source = r#"
+values = [42, 2.5, "hi", b"\x00a", True, None, 10**100]
+"#;
+        let ast = parse(source, "test.py").unwrap();
+        let list = ast.child("body").unwrap().child("value").unwrap();
+        let constants: Vec<(&str, &str)> = list
+            .children_in("elts")
+            .filter(|node| node.node_type == "Constant")
+            .map(|node| {
+                (
+                    node.attributes["value_type"].as_str(),
+                    node.attributes["value"].as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            constants,
+            vec![
+                ("int", "42"),
+                ("float", "2.5"),
+                ("str", "hi"),
+                ("bytes", "0061"),
+                ("bool", "True"),
+                ("None", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_arguments_with_defaults() {
+        let source = r"
+def f(a, /, b, c=1, *args, d, e=[], **kwargs):
+    pass
+";
+        let ast = parse(source, "test.py").unwrap();
+        let arguments = ast.child("body").unwrap().child("args").unwrap();
+        let params: Vec<(&str, &str, Option<&str>)> = arguments
+            .children
+            .iter()
+            .map(|arg| {
+                (
+                    arg.field.as_deref().unwrap(),
+                    arg.attributes["arg"].as_str(),
+                    arg.attributes.get("default").map(String::as_str),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            params,
+            vec![
+                ("posonlyargs", "a", None),
+                ("args", "b", None),
+                ("args", "c", Some("1")),
+                ("vararg", "args", None),
+                ("kwonlyargs", "d", None),
+                ("kwonlyargs", "e", Some("[]")),
+                ("kwarg", "kwargs", None),
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_invalid_syntax() {
         let source = "def invalid syntax here";
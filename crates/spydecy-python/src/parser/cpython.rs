@@ -0,0 +1,330 @@
+//! PyO3 parser backend
+//!
+//! Runs CPython's own `ast` module in an embedded interpreter and converts
+//! the resulting tree into a [`PythonAST`].
+//!
+//! The schema is that of Python 3.11. Other interpreters build some nodes
+//! differently, and extraction rewrites them so the schema does not depend
+//! on the linked Python:
+//!
+//! | Python | Produces                                    | Extracted as          |
+//! |--------|---------------------------------------------|-----------------------|
+//! | ≤ 3.7  | `Num`, `Str`, `Bytes`, `NameConstant`, `Ellipsis` | `Constant`      |
+//! | ≤ 3.8  | `Index(value)` as a subscript's slice       | `value`               |
+//! | ≤ 3.8  | `ExtSlice(dims)` as a subscript's slice     | `Tuple(elts=dims)`    |
+//! | ≤ 3.7  | `arguments` without `posonlyargs`           | no positional-only args |
+//! | ≥ 3.12 | f-string parts with their own spans, empty literals | 3.11 parts  |
+//!
+//! Positions cannot be recovered the same way: before 3.8 nodes have no end
+//! positions, and before 3.10 `alias` nodes have no positions at all.
+
+use super::{unparse::unparse, PythonAST};
+use anyhow::{Context, Result};
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyBool, PyBytes, PyFloat, PyInt, PyList, PyModule, PyString, PyTuple};
+
+/// Parse Python source with CPython's `ast` module
+pub(super) fn parse(source: &str, filename: &str) -> Result<PythonAST> {
+    Python::with_gil(|py| parse_with_python(py, source, filename))
+}
+
+/// Parse Python source using Python's ast module
+fn parse_with_python(py: Python<'_>, source: &str, filename: &str) -> Result<PythonAST> {
+    // Import Python's ast module
+    let ast_module =
+        PyModule::import_bound(py, "ast").context("Failed to import Python ast module")?;
+
+    // Parse the source code
+    let ast_obj = ast_module
+        .call_method1("parse", (source, filename))
+        .context("Failed to parse Python source code")?;
+
+    // Convert Python AST to our simplified AST representation
+    let mut ast = extract_ast_node(&ast_obj)?;
+    if py.version_info() >= (3, 12) {
+        normalize_fstrings(&mut ast);
+    }
+    ast.attributes
+        .insert("filename".to_string(), filename.to_string());
+    Ok(ast)
+}
+
+/// Rewrite f-strings parsed by Python 3.12 or newer the way 3.11 reports
+/// them
+///
+/// Since PEP 701, every part of an f-string has its own span and a format
+/// spec ending in a nested field gets an empty trailing literal. In 3.11
+/// there are no empty literals and every part spans the whole f-string
+/// (format specs narrow that to their own string token, which only differs
+/// when literals are concatenated).
+fn normalize_fstrings(node: &mut PythonAST) {
+    if node.node_type == "JoinedStr" {
+        let span = (
+            node.lineno,
+            node.col_offset,
+            node.end_lineno,
+            node.end_col_offset,
+        );
+        respan_fstring_parts(node, span);
+    } else {
+        node.children.iter_mut().for_each(normalize_fstrings);
+    }
+}
+
+type Span = (Option<usize>, Option<usize>, Option<usize>, Option<usize>);
+
+fn respan_fstring_parts(joined: &mut PythonAST, span: Span) {
+    joined.children.retain(|part| {
+        part.node_type != "Constant" || part.attributes.get("value").is_some_and(|v| !v.is_empty())
+    });
+    for part in &mut joined.children {
+        (
+            part.lineno,
+            part.col_offset,
+            part.end_lineno,
+            part.end_col_offset,
+        ) = span;
+        for child in &mut part.children {
+            if child.field.as_deref() == Some("format_spec") {
+                (
+                    child.lineno,
+                    child.col_offset,
+                    child.end_lineno,
+                    child.end_col_offset,
+                ) = span;
+                respan_fstring_parts(child, span);
+            } else {
+                normalize_fstrings(child);
+            }
+        }
+    }
+}
+
+/// Extract AST node information from Python object
+fn extract_ast_node(obj: &Bound<'_, PyAny>) -> Result<PythonAST> {
+    let node_type = match class_name(obj)?.as_str() {
+        "Index" => return extract_ast_node(&obj.getattr("value")?),
+        "ExtSlice" => return extract_ext_slice(obj),
+        "Num" | "Str" | "Bytes" | "NameConstant" | "Ellipsis" => "Constant".to_string(),
+        name => name.to_string(),
+    };
+
+    let mut ast = PythonAST::new(node_type);
+
+    // Extract line number and column offset
+    extract_location_info(obj, &mut ast);
+
+    // Extract node-specific attributes
+    extract_node_attributes(obj, &mut ast)?;
+
+    Ok(ast)
+}
+
+/// Extract a Python 3.8 `ExtSlice` (`a[i, j:k]`) as the `Tuple` later
+/// versions produce, spanning its dimensions
+fn extract_ext_slice(obj: &Bound<'_, PyAny>) -> Result<PythonAST> {
+    let mut tuple = PythonAST::new("Tuple".to_string());
+    for dim in obj.getattr("dims")?.iter()? {
+        tuple.push_child("elts", extract_ast_node(&dim?)?);
+    }
+    if let (Some(first), Some(last)) = (tuple.children.first(), tuple.children.last()) {
+        tuple.lineno = first.lineno;
+        tuple.col_offset = first.col_offset;
+        tuple.end_lineno = last.end_lineno;
+        tuple.end_col_offset = last.end_col_offset;
+    }
+    Ok(tuple)
+}
+
+/// Extract location information (start and end line numbers and column offsets)
+fn extract_location_info(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) {
+    if let Ok(lineno) = obj.getattr("lineno") {
+        ast.lineno = lineno.extract().ok();
+    }
+    if let Ok(col_offset) = obj.getattr("col_offset") {
+        ast.col_offset = col_offset.extract().ok();
+    }
+    if let Ok(end_lineno) = obj.getattr("end_lineno") {
+        ast.end_lineno = end_lineno.extract().ok();
+    }
+    if let Ok(end_col_offset) = obj.getattr("end_col_offset") {
+        ast.end_col_offset = end_col_offset.extract().ok();
+    }
+}
+
+/// Extract node-specific attributes and children
+///
+/// Every node is walked through its `_fields`, so the full statement and
+/// expression grammar is captured without a per-node extractor.
+fn extract_node_attributes(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
+    match ast.node_type.as_str() {
+        "Constant" => return extract_constant_attrs(obj, ast),
+        "arguments" => return extract_arguments_attrs(obj, ast),
+        _ => {}
+    }
+
+    let fields = obj.getattr("_fields")?;
+    for field in fields.iter()? {
+        let field: String = field?.extract()?;
+        // Load/Store/Del contexts carry no information we need
+        if field == "ctx" {
+            continue;
+        }
+        if let Ok(value) = obj.getattr(field.as_str()) {
+            extract_field(&field, &value, ast)?;
+        }
+    }
+    Ok(())
+}
+
+/// Extract a Constant's value and its Python type
+///
+/// Values are kept as text so big integers survive until conversion, where
+/// overflow can be reported with the literal's location. Bytes are hex-encoded.
+fn extract_constant_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
+    // Python 3.7's literal nodes keep their value in `n` (`Num`) or `s`
+    // (`Str`, `Bytes`), and `Ellipsis` has none
+    let value = ["value", "n", "s"]
+        .into_iter()
+        .find_map(|field| obj.getattr(field).ok())
+        .unwrap_or_else(|| obj.py().Ellipsis().into_bound(obj.py()));
+    let (value_type, text) = if value.is_none() {
+        ("None".to_string(), String::new())
+    } else if value.is_instance_of::<PyBool>() || value.is_instance_of::<PyInt>() {
+        let value_type = if value.is_instance_of::<PyBool>() {
+            "bool"
+        } else {
+            "int"
+        };
+        (value_type.to_string(), value.str()?.to_string())
+    } else if value.is_instance_of::<PyFloat>() {
+        ("float".to_string(), value.repr()?.to_string())
+    } else if value.is_instance_of::<PyString>() {
+        ("str".to_string(), value.extract()?)
+    } else if value.is_instance_of::<PyBytes>() {
+        ("bytes".to_string(), value.call_method0("hex")?.extract()?)
+    } else {
+        (class_name(&value)?, value.repr()?.to_string())
+    };
+    ast.attributes.insert("value_type".to_string(), value_type);
+    ast.attributes.insert("value".to_string(), text);
+    Ok(())
+}
+
+/// Extract a function's `arguments` node
+///
+/// Defaults are stored on the `arg` they belong to (as Python source text),
+/// since `defaults` only lines up with the tail of the positional parameters
+/// and `kw_defaults` uses `None` for keyword-only parameters without one.
+fn extract_arguments_attrs(obj: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
+    // Positional-only parameters only exist since Python 3.8
+    let posonlyargs: Vec<_> = match obj.getattr("posonlyargs") {
+        Ok(posonlyargs) => posonlyargs.iter()?.collect::<PyResult<_>>()?,
+        Err(_) => Vec::new(),
+    };
+    let args: Vec<_> = obj.getattr("args")?.iter()?.collect::<PyResult<_>>()?;
+    let defaults: Vec<_> = obj.getattr("defaults")?.iter()?.collect::<PyResult<_>>()?;
+
+    let positional = posonlyargs
+        .iter()
+        .map(|arg| ("posonlyargs", arg))
+        .chain(args.iter().map(|arg| ("args", arg)));
+    let first_default = posonlyargs.len() + args.len() - defaults.len();
+    for (i, (field, arg)) in positional.enumerate() {
+        let default = i.checked_sub(first_default).map(|d| &defaults[d]);
+        ast.push_child(field, extract_arg(arg, default)?);
+    }
+
+    let vararg = obj.getattr("vararg")?;
+    if !vararg.is_none() {
+        ast.push_child("vararg", extract_arg(&vararg, None)?);
+    }
+
+    let kw_defaults = obj.getattr("kw_defaults")?;
+    for (arg, default) in obj.getattr("kwonlyargs")?.iter()?.zip(kw_defaults.iter()?) {
+        let default = default?;
+        let default = (!default.is_none()).then_some(&default);
+        ast.push_child("kwonlyargs", extract_arg(&arg?, default)?);
+    }
+
+    let kwarg = obj.getattr("kwarg")?;
+    if !kwarg.is_none() {
+        ast.push_child("kwarg", extract_arg(&kwarg, None)?);
+    }
+    Ok(())
+}
+
+/// Extract a single `arg` node, recording its default value's source text
+fn extract_arg(arg: &Bound<'_, PyAny>, default: Option<&Bound<'_, PyAny>>) -> Result<PythonAST> {
+    let mut node = extract_ast_node(arg)?;
+    if let Some(default) = default {
+        let source = unparse(&extract_ast_node(default)?);
+        node.attributes.insert("default".to_string(), source);
+    }
+    Ok(node)
+}
+
+/// Extract a single field value into children or attributes
+fn extract_field(field: &str, value: &Bound<'_, PyAny>, ast: &mut PythonAST) -> Result<()> {
+    if value.is_none() {
+        return Ok(());
+    }
+
+    if value.is_instance_of::<PyList>() {
+        let mut operators = Vec::new();
+        let mut names = Vec::new();
+        for item in value.iter()? {
+            let item = item?;
+            if item.is_none() {
+                continue;
+            }
+            if is_operator(&item)? {
+                operators.push(class_name(&item)?);
+            } else if let Ok(name) = item.extract::<String>() {
+                names.push(name);
+            } else {
+                ast.push_child(field, extract_ast_node(&item)?);
+            }
+        }
+        if !operators.is_empty() {
+            ast.attributes
+                .insert(field.to_string(), operators.join(","));
+        } else if !names.is_empty() {
+            ast.attributes.insert(field.to_string(), names.join(","));
+        }
+    } else if is_operator(value)? {
+        ast.attributes.insert(field.to_string(), class_name(value)?);
+    } else if value.hasattr("_fields")? {
+        ast.push_child(field, extract_ast_node(value)?);
+    } else if let Ok(text) = value.extract::<String>() {
+        ast.attributes.insert(field.to_string(), text);
+    } else if value.is_instance_of::<PyBool>() || value.is_instance_of::<PyInt>() {
+        ast.attributes
+            .insert(field.to_string(), value.str()?.to_string());
+    }
+    Ok(())
+}
+
+/// `(ast.operator, ast.unaryop, ast.cmpop, ast.boolop)`, looked up on first
+/// use rather than for every field of every node
+static OPERATOR_CLASSES: GILOnceCell<Py<PyTuple>> = GILOnceCell::new();
+
+/// Check whether a node is an operator token (`ast.Add`, `ast.Lt`, `ast.Not`, ...)
+fn is_operator(obj: &Bound<'_, PyAny>) -> Result<bool> {
+    let py = obj.py();
+    let classes = OPERATOR_CLASSES.get_or_try_init(py, || -> PyResult<_> {
+        let ast_module = PyModule::import_bound(py, "ast")?;
+        let classes = ["operator", "unaryop", "cmpop", "boolop"]
+            .into_iter()
+            .map(|class| ast_module.getattr(class))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PyTuple::new_bound(py, classes).unbind())
+    })?;
+    Ok(obj.is_instance(classes.bind(py))?)
+}
+
+/// Get the Python class name of an object
+fn class_name(obj: &Bound<'_, PyAny>) -> Result<String> {
+    Ok(obj.getattr("__class__")?.getattr("__name__")?.extract()?)
+}
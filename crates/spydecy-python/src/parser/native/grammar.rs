@@ -0,0 +1,2034 @@
+//! Recursive-descent parser
+//!
+//! Follows the structure of CPython's PEG grammar closely enough to build
+//! the same nodes with the same spans: a node starts at the first token of
+//! the rule that built it and ends at the last non-layout token it
+//! consumed. That is why `(a).b` starts at the parenthesis while `(a)` on
+//! its own is just the name.
+
+mod patterns;
+
+use super::lexer::{tokenize, Token, TokenKind};
+use super::literals::{self, Number};
+use super::{Pos, SyntaxError};
+use crate::parser::unparse::unparse;
+use crate::parser::PythonAST;
+
+type PResult<T> = Result<T, SyntaxError>;
+
+/// Hard keywords, which can never be names
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+const AUGMENTED_ASSIGNMENTS: &[(&str, &str)] = &[
+    ("+=", "Add"),
+    ("-=", "Sub"),
+    ("*=", "Mult"),
+    ("@=", "MatMult"),
+    ("/=", "Div"),
+    ("%=", "Mod"),
+    ("&=", "BitAnd"),
+    ("|=", "BitOr"),
+    ("^=", "BitXor"),
+    ("<<=", "LShift"),
+    (">>=", "RShift"),
+    ("**=", "Pow"),
+    ("//=", "FloorDiv"),
+];
+
+/// Parse a module from its tokens
+pub(super) fn parse_module(tokens: Vec<Token>) -> PResult<PythonAST> {
+    let mut parser = Parser::new(tokens);
+    let mut module = PythonAST::new("Module".to_string());
+    while parser.peek().kind != TokenKind::EndMarker {
+        for statement in parser.statement()? {
+            module.push_child("body", statement);
+        }
+    }
+    Ok(module)
+}
+
+/// Build a node spanning `start..end`
+fn located(kind: &str, start: Pos, end: Pos) -> PythonAST {
+    let mut node = PythonAST::new(kind.to_string());
+    node.lineno = Some(start.line);
+    node.col_offset = Some(start.col);
+    node.end_lineno = Some(end.line);
+    node.end_col_offset = Some(end.col);
+    node
+}
+
+fn set_attr(node: &mut PythonAST, key: &str, value: impl Into<String>) {
+    node.attributes.insert(key.to_string(), value.into());
+}
+
+fn push_all(node: &mut PythonAST, field: &str, children: Vec<PythonAST>) {
+    for child in children {
+        node.push_child(field, child);
+    }
+}
+
+/// Part of an f-string before the parts are turned into nodes
+enum Part {
+    Literal(String),
+    Field(PythonAST),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    /// End of the last consumed token that is not layout
+    last_end: Pos,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            index: 0,
+            last_end: Pos::default(),
+        }
+    }
+
+    // ----- Token helpers -----
+
+    fn peek(&self) -> &Token {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.index + n).min(last)]
+    }
+
+    fn start(&self) -> Pos {
+        self.peek().start
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.kind != TokenKind::EndMarker {
+            self.index += 1;
+        }
+        if !token.is_layout() {
+            self.last_end = token.end;
+        }
+        token
+    }
+
+    fn at_op(&self, op: &str) -> bool {
+        let token = self.peek();
+        token.kind == TokenKind::Op && token.text == op
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        let token = self.peek();
+        token.kind == TokenKind::Name && token.text == keyword
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let found = self.at_op(op);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_op(&mut self, op: &str) -> PResult<()> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("expected '{op}'")))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> PResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("expected '{keyword}'")))
+        }
+    }
+
+    fn expect_name(&mut self) -> PResult<String> {
+        let token = self.peek();
+        if token.kind == TokenKind::Name && !KEYWORDS.contains(&token.text.as_str()) {
+            Ok(self.advance().text)
+        } else {
+            Err(self.unexpected("expected a name"))
+        }
+    }
+
+    fn expect_newline(&mut self) -> PResult<()> {
+        if self.peek().kind == TokenKind::Newline {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.unexpected("invalid syntax"))
+        }
+    }
+
+    fn at_statement_end(&self) -> bool {
+        matches!(self.peek().kind, TokenKind::Newline | TokenKind::EndMarker) || self.at_op(";")
+    }
+
+    /// Whether the next token can start an expression
+    fn starts_expression(&self) -> bool {
+        let token = self.peek();
+        match token.kind {
+            TokenKind::Name => {
+                !KEYWORDS.contains(&token.text.as_str())
+                    || matches!(
+                        token.text.as_str(),
+                        "None" | "True" | "False" | "lambda" | "not" | "await"
+                    )
+            }
+            TokenKind::Number | TokenKind::String => true,
+            TokenKind::Op => matches!(
+                token.text.as_str(),
+                "(" | "[" | "{" | "-" | "+" | "~" | "*" | "..."
+            ),
+            _ => false,
+        }
+    }
+
+    fn unexpected(&self, message: &str) -> SyntaxError {
+        let token = self.peek();
+        let message = match token.kind {
+            TokenKind::Indent => "unexpected indent",
+            TokenKind::Dedent => "unindent does not match any outer indentation level",
+            TokenKind::EndMarker => "unexpected EOF while parsing",
+            _ => message,
+        };
+        SyntaxError::new(token.start, message)
+    }
+
+    fn node(&self, kind: &str, start: Pos) -> PythonAST {
+        located(kind, start, self.last_end)
+    }
+
+    // ----- Statements -----
+
+    fn statement(&mut self) -> PResult<Vec<PythonAST>> {
+        let token = self.peek();
+        if token.kind == TokenKind::Indent {
+            return Err(self.unexpected("unexpected indent"));
+        }
+        let compound = match (token.kind, token.text.as_str()) {
+            (TokenKind::Name, "if" | "while" | "for" | "try" | "with" | "def" | "class")
+            | (TokenKind::Op, "@") => true,
+            (TokenKind::Name, "async") => {
+                matches!(self.peek_nth(1).text.as_str(), "def" | "for" | "with")
+            }
+            (TokenKind::Name, "match") if self.is_match_statement() => {
+                return Ok(vec![self.match_statement()?]);
+            }
+            _ => false,
+        };
+        if compound {
+            Ok(vec![self.compound_statement()?])
+        } else {
+            self.simple_statements()
+        }
+    }
+
+    /// `match` is a soft keyword: it starts a statement only when the line
+    /// ends with `:` and opens a block
+    fn is_match_statement(&self) -> bool {
+        let next = self.peek_nth(1);
+        if next.kind == TokenKind::Newline
+            || (next.kind == TokenKind::Op
+                && !matches!(
+                    next.text.as_str(),
+                    "(" | "[" | "{" | "-" | "+" | "*" | "~" | "..."
+                ))
+        {
+            return false;
+        }
+        let mut end = self.index;
+        while !matches!(
+            self.tokens[end].kind,
+            TokenKind::Newline | TokenKind::EndMarker
+        ) {
+            end += 1;
+        }
+        let last = &self.tokens[end - 1];
+        last.kind == TokenKind::Op
+            && last.text == ":"
+            && self
+                .tokens
+                .get(end + 1)
+                .is_some_and(|token| token.kind == TokenKind::Indent)
+    }
+
+    fn simple_statements(&mut self) -> PResult<Vec<PythonAST>> {
+        let mut statements = vec![self.simple_statement()?];
+        while self.eat_op(";") {
+            if self.peek().kind == TokenKind::Newline {
+                break;
+            }
+            statements.push(self.simple_statement()?);
+        }
+        self.expect_newline()?;
+        Ok(statements)
+    }
+
+    fn simple_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let keyword = if self.peek().kind == TokenKind::Name {
+            self.peek().text.clone()
+        } else {
+            String::new()
+        };
+        match keyword.as_str() {
+            "pass" | "break" | "continue" => {
+                self.advance();
+                let kind = match keyword.as_str() {
+                    "pass" => "Pass",
+                    "break" => "Break",
+                    _ => "Continue",
+                };
+                Ok(self.node(kind, start))
+            }
+            "return" => {
+                self.advance();
+                let value = if self.at_statement_end() {
+                    None
+                } else {
+                    Some(self.star_expressions()?)
+                };
+                let mut node = self.node("Return", start);
+                push_all(&mut node, "value", value.into_iter().collect());
+                Ok(node)
+            }
+            "raise" => self.raise_statement(),
+            "global" | "nonlocal" => {
+                self.advance();
+                let mut names = vec![self.expect_name()?];
+                while self.eat_op(",") {
+                    names.push(self.expect_name()?);
+                }
+                let kind = if keyword == "global" {
+                    "Global"
+                } else {
+                    "Nonlocal"
+                };
+                let mut node = self.node(kind, start);
+                set_attr(&mut node, "names", names.join(","));
+                Ok(node)
+            }
+            "del" => {
+                self.advance();
+                let mut targets = vec![self.bitwise_or()?];
+                while self.eat_op(",") {
+                    if self.at_statement_end() {
+                        break;
+                    }
+                    targets.push(self.bitwise_or()?);
+                }
+                let mut node = self.node("Delete", start);
+                push_all(&mut node, "targets", targets);
+                Ok(node)
+            }
+            "assert" => {
+                self.advance();
+                let test = self.expression()?;
+                let msg = if self.eat_op(",") {
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+                let mut node = self.node("Assert", start);
+                node.push_child("test", test);
+                push_all(&mut node, "msg", msg.into_iter().collect());
+                Ok(node)
+            }
+            "import" => self.import_statement(),
+            "from" => self.import_from_statement(),
+            _ => self.expression_statement(),
+        }
+    }
+
+    fn raise_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let mut exc = None;
+        let mut cause = None;
+        if !self.at_statement_end() {
+            exc = Some(self.expression()?);
+            if self.eat_keyword("from") {
+                cause = Some(self.expression()?);
+            }
+        }
+        let mut node = self.node("Raise", start);
+        push_all(&mut node, "exc", exc.into_iter().collect());
+        push_all(&mut node, "cause", cause.into_iter().collect());
+        Ok(node)
+    }
+
+    fn dotted_name(&mut self) -> PResult<String> {
+        let mut name = self.expect_name()?;
+        while self.eat_op(".") {
+            name.push('.');
+            name.push_str(&self.expect_name()?);
+        }
+        Ok(name)
+    }
+
+    /// `name [as asname]`, where the name may be dotted
+    fn alias(&mut self, dotted: bool) -> PResult<PythonAST> {
+        let start = self.start();
+        let name = if dotted {
+            self.dotted_name()?
+        } else {
+            self.expect_name()?
+        };
+        let asname = if self.eat_keyword("as") {
+            Some(self.expect_name()?)
+        } else {
+            None
+        };
+        let mut node = self.node("alias", start);
+        set_attr(&mut node, "name", name);
+        if let Some(asname) = asname {
+            set_attr(&mut node, "asname", asname);
+        }
+        Ok(node)
+    }
+
+    fn import_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let mut names = vec![self.alias(true)?];
+        while self.eat_op(",") {
+            names.push(self.alias(true)?);
+        }
+        let mut node = self.node("Import", start);
+        push_all(&mut node, "names", names);
+        Ok(node)
+    }
+
+    fn import_from_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let mut level = 0;
+        loop {
+            if self.eat_op(".") {
+                level += 1;
+            } else if self.eat_op("...") {
+                level += 3;
+            } else {
+                break;
+            }
+        }
+        let module = if level > 0 && self.at_keyword("import") {
+            None
+        } else {
+            Some(self.dotted_name()?)
+        };
+        self.expect_keyword("import")?;
+
+        let mut names = Vec::new();
+        if self.at_op("*") {
+            let star = self.start();
+            self.advance();
+            let mut alias = self.node("alias", star);
+            set_attr(&mut alias, "name", "*");
+            names.push(alias);
+        } else if self.eat_op("(") {
+            names.push(self.alias(false)?);
+            while self.eat_op(",") {
+                if self.at_op(")") {
+                    break;
+                }
+                names.push(self.alias(false)?);
+            }
+            self.expect_op(")")?;
+        } else {
+            names.push(self.alias(false)?);
+            while self.eat_op(",") {
+                names.push(self.alias(false)?);
+            }
+        }
+
+        let mut node = self.node("ImportFrom", start);
+        if let Some(module) = module {
+            set_attr(&mut node, "module", module);
+        }
+        push_all(&mut node, "names", names);
+        set_attr(&mut node, "level", level.to_string());
+        Ok(node)
+    }
+
+    /// Right-hand side of an assignment
+    fn assignment_value(&mut self) -> PResult<PythonAST> {
+        if self.at_keyword("yield") {
+            self.yield_expression()
+        } else {
+            self.star_expressions()
+        }
+    }
+
+    fn expression_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let parenthesized = self.at_op("(");
+        let first = self.assignment_value()?;
+
+        if self.eat_op(":") {
+            if !matches!(first.node_type.as_str(), "Name" | "Attribute" | "Subscript") {
+                return Err(SyntaxError::new(
+                    start,
+                    "only single target (not tuple) can be annotated",
+                ));
+            }
+            let simple = first.node_type == "Name" && !parenthesized;
+            let annotation = self.expression()?;
+            let value = if self.eat_op("=") {
+                Some(self.assignment_value()?)
+            } else {
+                None
+            };
+            let mut node = self.node("AnnAssign", start);
+            node.push_child("target", first);
+            node.push_child("annotation", annotation);
+            push_all(&mut node, "value", value.into_iter().collect());
+            set_attr(&mut node, "simple", if simple { "1" } else { "0" });
+            return Ok(node);
+        }
+
+        let token = self.peek();
+        let augmented = AUGMENTED_ASSIGNMENTS
+            .iter()
+            .find(|(op, _)| token.kind == TokenKind::Op && token.text == *op);
+        if let Some((_, op)) = augmented {
+            if !matches!(first.node_type.as_str(), "Name" | "Attribute" | "Subscript") {
+                return Err(SyntaxError::new(
+                    start,
+                    "illegal expression for augmented assignment",
+                ));
+            }
+            self.advance();
+            let value = self.assignment_value()?;
+            let mut node = self.node("AugAssign", start);
+            node.push_child("target", first);
+            set_attr(&mut node, "op", *op);
+            node.push_child("value", value);
+            return Ok(node);
+        }
+
+        if !self.at_op("=") {
+            let mut node = self.node("Expr", start);
+            node.push_child("value", first);
+            return Ok(node);
+        }
+        let mut targets = vec![first];
+        while self.eat_op("=") {
+            targets.push(self.assignment_value()?);
+        }
+        let value = targets
+            .pop()
+            .unwrap_or_else(|| PythonAST::new(String::new()));
+        for target in &targets {
+            check_target(target)?;
+        }
+        let mut node = self.node("Assign", start);
+        push_all(&mut node, "targets", targets);
+        node.push_child("value", value);
+        Ok(node)
+    }
+
+    fn compound_statement(&mut self) -> PResult<PythonAST> {
+        let mut decorators = Vec::new();
+        while self.eat_op("@") {
+            decorators.push(self.named_expression()?);
+            self.expect_newline()?;
+        }
+        let keyword = if self.at_keyword("async") {
+            self.peek_nth(1).text.clone()
+        } else {
+            self.peek().text.clone()
+        };
+        if !decorators.is_empty() && !matches!(keyword.as_str(), "def" | "class") {
+            return Err(self.unexpected("invalid syntax"));
+        }
+        match keyword.as_str() {
+            "def" => self.function_def(decorators),
+            "class" => self.class_def(decorators),
+            "if" => self.if_statement(),
+            "while" => self.while_statement(),
+            "for" => self.for_statement(),
+            "try" => self.try_statement(),
+            "with" => self.with_statement(),
+            _ => Err(self.unexpected("invalid syntax")),
+        }
+    }
+
+    /// Indented block, or simple statements on the same line
+    fn block(&mut self) -> PResult<Vec<PythonAST>> {
+        if self.peek().kind != TokenKind::Newline {
+            return self.simple_statements();
+        }
+        self.advance();
+        if self.peek().kind != TokenKind::Indent {
+            return Err(self.unexpected("expected an indented block"));
+        }
+        self.advance();
+        let mut body = Vec::new();
+        while !matches!(self.peek().kind, TokenKind::Dedent | TokenKind::EndMarker) {
+            body.extend(self.statement()?);
+        }
+        self.advance();
+        Ok(body)
+    }
+
+    /// `else: block`, if present
+    fn else_block(&mut self) -> PResult<Vec<PythonAST>> {
+        if !self.eat_keyword("else") {
+            return Ok(Vec::new());
+        }
+        self.expect_op(":")?;
+        self.block()
+    }
+
+    fn function_def(&mut self, decorators: Vec<PythonAST>) -> PResult<PythonAST> {
+        let start = self.start();
+        let kind = if self.eat_keyword("async") {
+            "AsyncFunctionDef"
+        } else {
+            "FunctionDef"
+        };
+        self.expect_keyword("def")?;
+        let name = self.expect_name()?;
+        self.expect_op("(")?;
+        let args = self.parameters(")", true)?;
+        self.expect_op(")")?;
+        let returns = if self.eat_op("->") {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.expect_op(":")?;
+        let body = self.block()?;
+
+        let mut node = self.node(kind, start);
+        set_attr(&mut node, "name", name);
+        node.push_child("args", args);
+        push_all(&mut node, "body", body);
+        push_all(&mut node, "decorator_list", decorators);
+        push_all(&mut node, "returns", returns.into_iter().collect());
+        Ok(node)
+    }
+
+    fn class_def(&mut self, decorators: Vec<PythonAST>) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let name = self.expect_name()?;
+        let (bases, keywords) = if self.at_op("(") {
+            let open = self.start();
+            self.advance();
+            self.call_arguments(open)?
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        self.expect_op(":")?;
+        let body = self.block()?;
+
+        let mut node = self.node("ClassDef", start);
+        set_attr(&mut node, "name", name);
+        push_all(&mut node, "bases", bases);
+        push_all(&mut node, "keywords", keywords);
+        push_all(&mut node, "body", body);
+        push_all(&mut node, "decorator_list", decorators);
+        Ok(node)
+    }
+
+    /// Parameters up to (not including) `closing`, as an `arguments` node
+    ///
+    /// Defaults are stored on their `arg` as unparsed source text, the way
+    /// the `ast` backend records them.
+    fn parameters(&mut self, closing: &str, annotated: bool) -> PResult<PythonAST> {
+        let mut positional = Vec::new();
+        let mut posonly = 0;
+        let mut vararg = None;
+        let mut kwonly = Vec::new();
+        let mut kwarg = None;
+        let mut star = false;
+        let mut default_seen = false;
+
+        while !self.at_op(closing) {
+            if kwarg.is_some() {
+                return Err(self.unexpected("arguments cannot follow var-keyword argument"));
+            }
+            if self.eat_op("/") {
+                if star || posonly > 0 || positional.is_empty() {
+                    return Err(self.unexpected("invalid syntax"));
+                }
+                posonly = positional.len();
+            } else if self.eat_op("**") {
+                kwarg = Some(self.parameter(annotated, false)?);
+            } else if self.eat_op("*") {
+                if star {
+                    return Err(self.unexpected("* argument may appear only once"));
+                }
+                star = true;
+                if !self.at_op(",") && !self.at_op(closing) {
+                    vararg = Some(self.parameter(annotated, true)?);
+                }
+            } else {
+                let mut param = self.parameter(annotated, false)?;
+                if self.eat_op("=") {
+                    let default = self.expression()?;
+                    set_attr(&mut param, "default", unparse(&default));
+                    default_seen |= !star;
+                } else if default_seen && !star {
+                    return Err(self.unexpected("non-default argument follows default argument"));
+                }
+                if star {
+                    kwonly.push(param);
+                } else {
+                    positional.push(param);
+                }
+            }
+            if !self.eat_op(",") {
+                break;
+            }
+        }
+        if star && vararg.is_none() && kwonly.is_empty() {
+            return Err(self.unexpected("named arguments must follow bare *"));
+        }
+
+        let mut arguments = PythonAST::new("arguments".to_string());
+        let args = positional.split_off(posonly);
+        push_all(&mut arguments, "posonlyargs", positional);
+        push_all(&mut arguments, "args", args);
+        push_all(&mut arguments, "vararg", vararg.into_iter().collect());
+        push_all(&mut arguments, "kwonlyargs", kwonly);
+        push_all(&mut arguments, "kwarg", kwarg.into_iter().collect());
+        Ok(arguments)
+    }
+
+    /// Parameter name and annotation; only `*args` may have a starred
+    /// annotation (`*args: *Ts`)
+    fn parameter(&mut self, annotated: bool, vararg: bool) -> PResult<PythonAST> {
+        let start = self.start();
+        let name = self.expect_name()?;
+        let annotation = if annotated && self.eat_op(":") {
+            Some(if vararg {
+                self.star_expression()?
+            } else {
+                self.expression()?
+            })
+        } else {
+            None
+        };
+        let mut node = self.node("arg", start);
+        set_attr(&mut node, "arg", name);
+        push_all(&mut node, "annotation", annotation.into_iter().collect());
+        Ok(node)
+    }
+
+    /// `if`/`elif` chain; an `elif` becomes a nested `If` in `orelse`
+    fn if_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let test = self.named_expression()?;
+        self.expect_op(":")?;
+        let body = self.block()?;
+        let orelse = if self.at_keyword("elif") {
+            vec![self.if_statement()?]
+        } else {
+            self.else_block()?
+        };
+        let mut node = self.node("If", start);
+        node.push_child("test", test);
+        push_all(&mut node, "body", body);
+        push_all(&mut node, "orelse", orelse);
+        Ok(node)
+    }
+
+    fn while_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let test = self.named_expression()?;
+        self.expect_op(":")?;
+        let body = self.block()?;
+        let orelse = self.else_block()?;
+        let mut node = self.node("While", start);
+        node.push_child("test", test);
+        push_all(&mut node, "body", body);
+        push_all(&mut node, "orelse", orelse);
+        Ok(node)
+    }
+
+    fn for_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let kind = if self.eat_keyword("async") {
+            "AsyncFor"
+        } else {
+            "For"
+        };
+        self.expect_keyword("for")?;
+        let target = self.star_targets()?;
+        self.expect_keyword("in")?;
+        let iter = self.star_expressions()?;
+        self.expect_op(":")?;
+        let body = self.block()?;
+        let orelse = self.else_block()?;
+        let mut node = self.node(kind, start);
+        node.push_child("target", target);
+        node.push_child("iter", iter);
+        push_all(&mut node, "body", body);
+        push_all(&mut node, "orelse", orelse);
+        Ok(node)
+    }
+
+    fn try_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        self.expect_op(":")?;
+        let body = self.block()?;
+
+        let mut handlers = Vec::new();
+        let mut star = false;
+        while self.at_keyword("except") {
+            let handler_start = self.start();
+            self.advance();
+            star |= self.eat_op("*");
+            let mut kind = None;
+            let mut name = None;
+            if !self.at_op(":") {
+                kind = Some(self.expression()?);
+                if self.eat_keyword("as") {
+                    name = Some(self.expect_name()?);
+                }
+            }
+            self.expect_op(":")?;
+            let handler_body = self.block()?;
+            let mut handler = self.node("ExceptHandler", handler_start);
+            push_all(&mut handler, "type", kind.into_iter().collect());
+            if let Some(name) = name {
+                set_attr(&mut handler, "name", name);
+            }
+            push_all(&mut handler, "body", handler_body);
+            handlers.push(handler);
+        }
+        let orelse = self.else_block()?;
+        let finalbody = if self.eat_keyword("finally") {
+            self.expect_op(":")?;
+            self.block()?
+        } else {
+            Vec::new()
+        };
+        if handlers.is_empty() && finalbody.is_empty() {
+            return Err(self.unexpected("expected 'except' or 'finally' block"));
+        }
+
+        let mut node = self.node(if star { "TryStar" } else { "Try" }, start);
+        push_all(&mut node, "body", body);
+        push_all(&mut node, "handlers", handlers);
+        push_all(&mut node, "orelse", orelse);
+        push_all(&mut node, "finalbody", finalbody);
+        Ok(node)
+    }
+
+    fn with_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let kind = if self.eat_keyword("async") {
+            "AsyncWith"
+        } else {
+            "With"
+        };
+        self.expect_keyword("with")?;
+
+        // `with (a as b, c):` groups items; `with (a, b) as c:` is a tuple
+        let mut items = None;
+        if self.at_op("(") {
+            let (index, last_end) = (self.index, self.last_end);
+            match self.parenthesized_with_items() {
+                Ok(grouped) if self.at_op(":") => items = Some(grouped),
+                _ => (self.index, self.last_end) = (index, last_end),
+            }
+        }
+        let items = match items {
+            Some(items) => items,
+            None => {
+                let mut items = vec![self.with_item()?];
+                while self.eat_op(",") {
+                    items.push(self.with_item()?);
+                }
+                items
+            }
+        };
+        self.expect_op(":")?;
+        let body = self.block()?;
+
+        let mut node = self.node(kind, start);
+        push_all(&mut node, "items", items);
+        push_all(&mut node, "body", body);
+        Ok(node)
+    }
+
+    fn parenthesized_with_items(&mut self) -> PResult<Vec<PythonAST>> {
+        self.expect_op("(")?;
+        let mut items = vec![self.with_item()?];
+        while self.eat_op(",") {
+            if self.at_op(")") {
+                break;
+            }
+            items.push(self.with_item()?);
+        }
+        self.expect_op(")")?;
+        Ok(items)
+    }
+
+    fn with_item(&mut self) -> PResult<PythonAST> {
+        let context = self.expression()?;
+        let mut item = PythonAST::new("withitem".to_string());
+        item.push_child("context_expr", context);
+        if self.eat_keyword("as") {
+            let target = self.target()?;
+            item.push_child("optional_vars", target);
+        }
+        Ok(item)
+    }
+
+    // ----- Targets -----
+
+    /// Single assignment target: `*x`, `x`, `x.y`, `x[i]`, `(a, b)`, `[a, b]`
+    fn target(&mut self) -> PResult<PythonAST> {
+        if self.at_op("*") {
+            let start = self.start();
+            self.advance();
+            let value = self.target()?;
+            let mut node = self.node("Starred", start);
+            node.push_child("value", value);
+            return Ok(node);
+        }
+        self.primary()
+    }
+
+    /// Comma-separated targets of `for` loops and comprehensions
+    fn star_targets(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let first = self.target()?;
+        if !self.at_op(",") {
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(",") {
+            if !self.starts_expression() {
+                break;
+            }
+            elts.push(self.target()?);
+        }
+        Ok(self.sequence("Tuple", start, elts))
+    }
+
+    // ----- Expressions -----
+
+    fn sequence(&self, kind: &str, start: Pos, elts: Vec<PythonAST>) -> PythonAST {
+        let mut node = self.node(kind, start);
+        push_all(&mut node, "elts", elts);
+        node
+    }
+
+    /// Comma-separated expressions, a tuple when there is a comma
+    fn star_expressions(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let first = self.star_expression()?;
+        if !self.at_op(",") {
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(",") {
+            if !self.starts_expression() {
+                break;
+            }
+            elts.push(self.star_expression()?);
+        }
+        Ok(self.sequence("Tuple", start, elts))
+    }
+
+    fn starred(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let value = self.bitwise_or()?;
+        let mut node = self.node("Starred", start);
+        node.push_child("value", value);
+        Ok(node)
+    }
+
+    fn star_expression(&mut self) -> PResult<PythonAST> {
+        if self.at_op("*") {
+            self.starred()
+        } else {
+            self.expression()
+        }
+    }
+
+    fn star_named_expression(&mut self) -> PResult<PythonAST> {
+        if self.at_op("*") {
+            self.starred()
+        } else {
+            self.named_expression()
+        }
+    }
+
+    /// Expression that may be an assignment expression (`x := value`)
+    fn named_expression(&mut self) -> PResult<PythonAST> {
+        let token = self.peek();
+        let next = self.peek_nth(1);
+        if token.kind != TokenKind::Name || next.kind != TokenKind::Op || next.text != ":=" {
+            return self.expression();
+        }
+        let start = self.start();
+        let name = self.expect_name()?;
+        let mut target = self.node("Name", start);
+        set_attr(&mut target, "id", name);
+        self.advance();
+        let value = self.expression()?;
+        let mut node = self.node("NamedExpr", start);
+        node.push_child("target", target);
+        node.push_child("value", value);
+        Ok(node)
+    }
+
+    fn expression(&mut self) -> PResult<PythonAST> {
+        if self.at_keyword("lambda") {
+            return self.lambda();
+        }
+        let start = self.start();
+        let body = self.disjunction()?;
+        if !self.eat_keyword("if") {
+            return Ok(body);
+        }
+        let test = self.disjunction()?;
+        self.expect_keyword("else")?;
+        let orelse = self.expression()?;
+        let mut node = self.node("IfExp", start);
+        node.push_child("test", test);
+        node.push_child("body", body);
+        node.push_child("orelse", orelse);
+        Ok(node)
+    }
+
+    fn lambda(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let args = self.parameters(":", false)?;
+        self.expect_op(":")?;
+        let body = self.expression()?;
+        let mut node = self.node("Lambda", start);
+        node.push_child("args", args);
+        node.push_child("body", body);
+        Ok(node)
+    }
+
+    fn yield_expression(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        if self.eat_keyword("from") {
+            let value = self.expression()?;
+            let mut node = self.node("YieldFrom", start);
+            node.push_child("value", value);
+            return Ok(node);
+        }
+        let value = if self.starts_expression() {
+            Some(self.star_expressions()?)
+        } else {
+            None
+        };
+        let mut node = self.node("Yield", start);
+        push_all(&mut node, "value", value.into_iter().collect());
+        Ok(node)
+    }
+
+    /// `a or b`, `a and b`: one `BoolOp` per run of the same operator
+    fn bool_op(
+        &mut self,
+        keyword: &str,
+        op: &str,
+        operand: fn(&mut Self) -> PResult<PythonAST>,
+    ) -> PResult<PythonAST> {
+        let start = self.start();
+        let first = operand(self)?;
+        if !self.at_keyword(keyword) {
+            return Ok(first);
+        }
+        let mut values = vec![first];
+        while self.eat_keyword(keyword) {
+            values.push(operand(self)?);
+        }
+        let mut node = self.node("BoolOp", start);
+        set_attr(&mut node, "op", op);
+        push_all(&mut node, "values", values);
+        Ok(node)
+    }
+
+    fn disjunction(&mut self) -> PResult<PythonAST> {
+        self.bool_op("or", "Or", Self::conjunction)
+    }
+
+    fn conjunction(&mut self) -> PResult<PythonAST> {
+        self.bool_op("and", "And", Self::inversion)
+    }
+
+    fn inversion(&mut self) -> PResult<PythonAST> {
+        if !self.at_keyword("not") {
+            return self.comparison();
+        }
+        let start = self.start();
+        self.advance();
+        let operand = self.inversion()?;
+        Ok(self.unary(start, "Not", operand))
+    }
+
+    fn unary(&self, start: Pos, op: &str, operand: PythonAST) -> PythonAST {
+        let mut node = self.node("UnaryOp", start);
+        set_attr(&mut node, "op", op);
+        node.push_child("operand", operand);
+        node
+    }
+
+    fn comparison_operator(&self) -> Option<(&'static str, usize)> {
+        let token = self.peek();
+        let op = match (token.kind, token.text.as_str()) {
+            (TokenKind::Op, "==") => "Eq",
+            (TokenKind::Op, "!=") => "NotEq",
+            (TokenKind::Op, "<") => "Lt",
+            (TokenKind::Op, "<=") => "LtE",
+            (TokenKind::Op, ">") => "Gt",
+            (TokenKind::Op, ">=") => "GtE",
+            (TokenKind::Name, "in") => "In",
+            (TokenKind::Name, "not") if self.peek_nth(1).text == "in" => return Some(("NotIn", 2)),
+            (TokenKind::Name, "is") if self.peek_nth(1).text == "not" => return Some(("IsNot", 2)),
+            (TokenKind::Name, "is") => "Is",
+            _ => return None,
+        };
+        Some((op, 1))
+    }
+
+    fn comparison(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let left = self.bitwise_or()?;
+        let mut ops = Vec::new();
+        let mut comparators = Vec::new();
+        while let Some((op, tokens)) = self.comparison_operator() {
+            for _ in 0..tokens {
+                self.advance();
+            }
+            ops.push(op);
+            comparators.push(self.bitwise_or()?);
+        }
+        if ops.is_empty() {
+            return Ok(left);
+        }
+        let mut node = self.node("Compare", start);
+        node.push_child("left", left);
+        set_attr(&mut node, "ops", ops.join(","));
+        push_all(&mut node, "comparators", comparators);
+        Ok(node)
+    }
+
+    /// Left-associative binary operators of one precedence level
+    fn binary(
+        &mut self,
+        ops: &[(&str, &str)],
+        operand: fn(&mut Self) -> PResult<PythonAST>,
+    ) -> PResult<PythonAST> {
+        let start = self.start();
+        let mut left = operand(self)?;
+        loop {
+            let token = self.peek();
+            let Some((_, op)) = ops
+                .iter()
+                .find(|(text, _)| token.kind == TokenKind::Op && token.text == *text)
+            else {
+                return Ok(left);
+            };
+            self.advance();
+            let right = operand(self)?;
+            left = self.binop(start, left, op, right);
+        }
+    }
+
+    fn binop(&self, start: Pos, left: PythonAST, op: &str, right: PythonAST) -> PythonAST {
+        let mut node = self.node("BinOp", start);
+        node.push_child("left", left);
+        set_attr(&mut node, "op", op);
+        node.push_child("right", right);
+        node
+    }
+
+    fn bitwise_or(&mut self) -> PResult<PythonAST> {
+        self.binary(&[("|", "BitOr")], Self::bitwise_xor)
+    }
+
+    fn bitwise_xor(&mut self) -> PResult<PythonAST> {
+        self.binary(&[("^", "BitXor")], Self::bitwise_and)
+    }
+
+    fn bitwise_and(&mut self) -> PResult<PythonAST> {
+        self.binary(&[("&", "BitAnd")], Self::shift)
+    }
+
+    fn shift(&mut self) -> PResult<PythonAST> {
+        self.binary(&[("<<", "LShift"), (">>", "RShift")], Self::sum)
+    }
+
+    fn sum(&mut self) -> PResult<PythonAST> {
+        self.binary(&[("+", "Add"), ("-", "Sub")], Self::term)
+    }
+
+    fn term(&mut self) -> PResult<PythonAST> {
+        self.binary(
+            &[
+                ("*", "Mult"),
+                ("/", "Div"),
+                ("//", "FloorDiv"),
+                ("%", "Mod"),
+                ("@", "MatMult"),
+            ],
+            Self::factor,
+        )
+    }
+
+    fn factor(&mut self) -> PResult<PythonAST> {
+        let op = match self.peek().text.as_str() {
+            "+" => "UAdd",
+            "-" => "USub",
+            "~" => "Invert",
+            _ => return self.power(),
+        };
+        if self.peek().kind != TokenKind::Op {
+            return self.power();
+        }
+        let start = self.start();
+        self.advance();
+        let operand = self.factor()?;
+        Ok(self.unary(start, op, operand))
+    }
+
+    fn power(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let base = if self.at_keyword("await") {
+            self.advance();
+            let value = self.primary()?;
+            let mut node = self.node("Await", start);
+            node.push_child("value", value);
+            node
+        } else {
+            self.primary()?
+        };
+        if !self.eat_op("**") {
+            return Ok(base);
+        }
+        let exponent = self.factor()?;
+        Ok(self.binop(start, base, "Pow", exponent))
+    }
+
+    /// Atom followed by attribute, call and subscript trailers
+    fn primary(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let mut node = self.atom()?;
+        loop {
+            if self.eat_op(".") {
+                let attr = self.expect_name()?;
+                let mut attribute = self.node("Attribute", start);
+                attribute.push_child("value", node);
+                set_attr(&mut attribute, "attr", attr);
+                node = attribute;
+            } else if self.at_op("(") {
+                let open = self.start();
+                self.advance();
+                let (args, keywords) = self.call_arguments(open)?;
+                let mut call = self.node("Call", start);
+                call.push_child("func", node);
+                push_all(&mut call, "args", args);
+                push_all(&mut call, "keywords", keywords);
+                node = call;
+            } else if self.eat_op("[") {
+                let slice = self.slices()?;
+                self.expect_op("]")?;
+                let mut subscript = self.node("Subscript", start);
+                subscript.push_child("value", node);
+                subscript.push_child("slice", slice);
+                node = subscript;
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    /// Arguments of a call or class definition, through the closing `)`
+    ///
+    /// A generator expression as the only argument spans the call's
+    /// parentheses, which start at `open`.
+    fn call_arguments(&mut self, open: Pos) -> PResult<(Vec<PythonAST>, Vec<PythonAST>)> {
+        let mut args = Vec::new();
+        let mut keywords: Vec<PythonAST> = Vec::new();
+        while !self.at_op(")") {
+            let start = self.start();
+            let next = self.peek_nth(1);
+            let is_keyword = self.peek().kind == TokenKind::Name
+                && next.kind == TokenKind::Op
+                && next.text == "=";
+            if self.at_op("*") {
+                self.advance();
+                let value = self.expression()?;
+                let mut node = self.node("Starred", start);
+                node.push_child("value", value);
+                args.push(node);
+            } else if self.eat_op("**") {
+                let value = self.expression()?;
+                let mut node = self.node("keyword", start);
+                node.push_child("value", value);
+                keywords.push(node);
+            } else if is_keyword {
+                let name = self.expect_name()?;
+                self.advance();
+                let value = self.expression()?;
+                let mut node = self.node("keyword", start);
+                set_attr(&mut node, "arg", name);
+                node.push_child("value", value);
+                keywords.push(node);
+            } else {
+                let value = self.named_expression()?;
+                if self.at_keyword("for") || self.at_keyword("async") {
+                    let generators = self.comprehension_clauses()?;
+                    if !args.is_empty() || !keywords.is_empty() || !self.at_op(")") {
+                        return Err(SyntaxError::new(
+                            start,
+                            "Generator expression must be parenthesized",
+                        ));
+                    }
+                    self.advance();
+                    let mut genexp = self.node("GeneratorExp", open);
+                    genexp.push_child("elt", value);
+                    push_all(&mut genexp, "generators", generators);
+                    return Ok((vec![genexp], keywords));
+                }
+                if let Some(keyword) = keywords.last() {
+                    let message = if keyword.attributes.contains_key("arg") {
+                        "positional argument follows keyword argument"
+                    } else {
+                        "positional argument follows keyword argument unpacking"
+                    };
+                    return Err(SyntaxError::new(start, message));
+                }
+                args.push(value);
+            }
+            if !self.eat_op(",") {
+                break;
+            }
+        }
+        self.expect_op(")")?;
+        Ok((args, keywords))
+    }
+
+    /// Subscript contents; a starred element (`a[*b]`) always makes a tuple
+    fn slices(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let starred = self.at_op("*");
+        let first = self.slice_or_starred()?;
+        if !starred && !self.at_op(",") {
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(",") {
+            if self.at_op("]") {
+                break;
+            }
+            elts.push(self.slice_or_starred()?);
+        }
+        Ok(self.sequence("Tuple", start, elts))
+    }
+
+    fn slice_or_starred(&mut self) -> PResult<PythonAST> {
+        if self.at_op("*") {
+            self.starred()
+        } else {
+            self.slice()
+        }
+    }
+
+    fn slice(&mut self) -> PResult<PythonAST> {
+        if self.at_op("*") {
+            return self.starred();
+        }
+        let start = self.start();
+        let lower = if self.at_op(":") {
+            None
+        } else {
+            let lower = self.named_expression()?;
+            if !self.at_op(":") {
+                return Ok(lower);
+            }
+            Some(lower)
+        };
+        self.advance();
+        let bound = |parser: &mut Self| {
+            if parser.at_op(":") || parser.at_op(",") || parser.at_op("]") {
+                Ok(None)
+            } else {
+                parser.expression().map(Some)
+            }
+        };
+        let upper = bound(self)?;
+        let step = if self.eat_op(":") { bound(self)? } else { None };
+        let mut node = self.node("Slice", start);
+        push_all(&mut node, "lower", lower.into_iter().collect());
+        push_all(&mut node, "upper", upper.into_iter().collect());
+        push_all(&mut node, "step", step.into_iter().collect());
+        Ok(node)
+    }
+
+    fn constant(&self, start: Pos, value_type: &str, value: impl Into<String>) -> PythonAST {
+        let mut node = self.node("Constant", start);
+        set_attr(&mut node, "value_type", value_type);
+        set_attr(&mut node, "value", value);
+        node
+    }
+
+    fn atom(&mut self) -> PResult<PythonAST> {
+        let token = self.peek().clone();
+        let start = token.start;
+        match (token.kind, token.text.as_str()) {
+            (TokenKind::Name, "None") => {
+                self.advance();
+                Ok(self.constant(start, "None", ""))
+            }
+            (TokenKind::Name, "True" | "False") => {
+                self.advance();
+                Ok(self.constant(start, "bool", token.text))
+            }
+            (TokenKind::Name, name) if !KEYWORDS.contains(&name) => {
+                self.advance();
+                let mut node = self.node("Name", start);
+                set_attr(&mut node, "id", name);
+                Ok(node)
+            }
+            (TokenKind::Number, text) => {
+                let number = literals::parse_number(text)
+                    .map_err(|message| SyntaxError::new(start, message))?;
+                self.advance();
+                Ok(match number {
+                    Number::Int(digits) => self.constant(start, "int", digits),
+                    Number::Float(value) => {
+                        self.constant(start, "float", literals::float_repr(value))
+                    }
+                    Number::Imaginary(value) => {
+                        self.constant(start, "complex", literals::imaginary_repr(value))
+                    }
+                })
+            }
+            (TokenKind::String, _) => self.strings(),
+            (TokenKind::Op, "(") => self.parenthesized(),
+            (TokenKind::Op, "[") => self.list(),
+            (TokenKind::Op, "{") => self.braces(),
+            (TokenKind::Op, "...") => {
+                self.advance();
+                Ok(self.constant(start, "ellipsis", "Ellipsis"))
+            }
+            _ => Err(self.unexpected("invalid syntax")),
+        }
+    }
+
+    /// Group, tuple or generator expression
+    fn parenthesized(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        if self.eat_op(")") {
+            return Ok(self.sequence("Tuple", start, Vec::new()));
+        }
+        if self.at_keyword("yield") {
+            let value = self.yield_expression()?;
+            self.expect_op(")")?;
+            return Ok(value);
+        }
+        let first = self.star_named_expression()?;
+        if self.at_keyword("for") || self.at_keyword("async") {
+            let generators = self.comprehension_clauses()?;
+            self.expect_op(")")?;
+            let mut node = self.node("GeneratorExp", start);
+            node.push_child("elt", first);
+            push_all(&mut node, "generators", generators);
+            return Ok(node);
+        }
+        if self.eat_op(")") {
+            if first.node_type == "Starred" {
+                return Err(SyntaxError::new(
+                    start,
+                    "cannot use starred expression here",
+                ));
+            }
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(",") {
+            if self.at_op(")") {
+                break;
+            }
+            elts.push(self.star_named_expression()?);
+        }
+        self.expect_op(")")?;
+        Ok(self.sequence("Tuple", start, elts))
+    }
+
+    /// List display or comprehension
+    fn list(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        if self.eat_op("]") {
+            return Ok(self.sequence("List", start, Vec::new()));
+        }
+        let first = self.star_named_expression()?;
+        if self.at_keyword("for") || self.at_keyword("async") {
+            let generators = self.comprehension_clauses()?;
+            self.expect_op("]")?;
+            let mut node = self.node("ListComp", start);
+            node.push_child("elt", first);
+            push_all(&mut node, "generators", generators);
+            return Ok(node);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(",") {
+            if self.at_op("]") {
+                break;
+            }
+            elts.push(self.star_named_expression()?);
+        }
+        self.expect_op("]")?;
+        Ok(self.sequence("List", start, elts))
+    }
+
+    /// Dict or set display or comprehension
+    fn braces(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        if self.eat_op("}") {
+            return Ok(self.node("Dict", start));
+        }
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        if self.eat_op("**") {
+            values.push(self.bitwise_or()?);
+        } else {
+            let first = self.star_named_expression()?;
+            if !self.eat_op(":") {
+                return self.set(start, first);
+            }
+            let value = self.expression()?;
+            if self.at_keyword("for") || self.at_keyword("async") {
+                let generators = self.comprehension_clauses()?;
+                self.expect_op("}")?;
+                let mut node = self.node("DictComp", start);
+                node.push_child("key", first);
+                node.push_child("value", value);
+                push_all(&mut node, "generators", generators);
+                return Ok(node);
+            }
+            keys.push(first);
+            values.push(value);
+        }
+        while self.eat_op(",") {
+            if self.at_op("}") {
+                break;
+            }
+            if self.eat_op("**") {
+                values.push(self.bitwise_or()?);
+            } else {
+                keys.push(self.expression()?);
+                self.expect_op(":")?;
+                values.push(self.expression()?);
+            }
+        }
+        self.expect_op("}")?;
+        let mut node = self.node("Dict", start);
+        push_all(&mut node, "keys", keys);
+        push_all(&mut node, "values", values);
+        Ok(node)
+    }
+
+    fn set(&mut self, start: Pos, first: PythonAST) -> PResult<PythonAST> {
+        if self.at_keyword("for") || self.at_keyword("async") {
+            let generators = self.comprehension_clauses()?;
+            self.expect_op("}")?;
+            let mut node = self.node("SetComp", start);
+            node.push_child("elt", first);
+            push_all(&mut node, "generators", generators);
+            return Ok(node);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(",") {
+            if self.at_op("}") {
+                break;
+            }
+            elts.push(self.star_named_expression()?);
+        }
+        self.expect_op("}")?;
+        Ok(self.sequence("Set", start, elts))
+    }
+
+    fn comprehension_clauses(&mut self) -> PResult<Vec<PythonAST>> {
+        let mut generators = Vec::new();
+        while self.at_keyword("for") || self.at_keyword("async") {
+            let is_async = self.eat_keyword("async");
+            self.expect_keyword("for")?;
+            let target = self.star_targets()?;
+            self.expect_keyword("in")?;
+            let iter = self.disjunction()?;
+            let mut node = PythonAST::new("comprehension".to_string());
+            node.push_child("target", target);
+            node.push_child("iter", iter);
+            while self.eat_keyword("if") {
+                let condition = self.disjunction()?;
+                node.push_child("ifs", condition);
+            }
+            set_attr(&mut node, "is_async", if is_async { "1" } else { "0" });
+            generators.push(node);
+        }
+        Ok(generators)
+    }
+
+    // ----- Strings -----
+
+    /// Adjacent string literals, concatenated into a `Constant` or, when
+    /// any of them is an f-string, a `JoinedStr`
+    fn strings(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let mut tokens = Vec::new();
+        while self.peek().kind == TokenKind::String {
+            tokens.push(self.advance());
+        }
+        let span = (start, self.last_end);
+
+        let prefixes: Vec<_> = tokens
+            .iter()
+            .map(|token| literals::split_string_token(&token.text).0)
+            .collect();
+        let bytes = prefixes[0].bytes;
+        if prefixes.iter().any(|prefix| prefix.bytes != bytes) {
+            return Err(SyntaxError::new(
+                start,
+                "cannot mix bytes and nonbytes literals",
+            ));
+        }
+
+        if !prefixes.iter().any(|prefix| prefix.formatted) {
+            let mut value = String::new();
+            for token in &tokens {
+                let (prefix, body) = literals::split_string_token(&token.text);
+                let body = &token.text[body];
+                value.push_str(
+                    &literals::decode_escapes(body, prefix)
+                        .map_err(|message| SyntaxError::new(token.start, message))?,
+                );
+            }
+            return Ok(if bytes {
+                self.constant(start, "bytes", literals::bytes_hex(&value))
+            } else {
+                self.constant(start, "str", value)
+            });
+        }
+
+        let mut parts = Vec::new();
+        for token in &tokens {
+            let (prefix, body) = literals::split_string_token(&token.text);
+            if prefix.formatted {
+                let mut reader = FStringReader {
+                    token,
+                    body: &token.text[body.clone()],
+                    body_offset: body.start,
+                    raw: prefix.raw,
+                    span,
+                    index: 0,
+                };
+                reader.parts(false, &mut parts)?;
+            } else {
+                let value = literals::decode_escapes(&token.text[body], prefix)
+                    .map_err(|message| SyntaxError::new(token.start, message))?;
+                push_literal(&mut parts, &value);
+            }
+        }
+        Ok(joined_str(parts, span, span))
+    }
+}
+
+/// Reject assignments to expressions that cannot be targets
+fn check_target(target: &PythonAST) -> PResult<()> {
+    match target.node_type.as_str() {
+        "Name" | "Attribute" | "Subscript" => Ok(()),
+        "Starred" => target.children.iter().try_for_each(check_target),
+        "Tuple" | "List" => target.children_in("elts").try_for_each(check_target),
+        other => {
+            let pos = Pos {
+                line: target.lineno.unwrap_or(0),
+                col: target.col_offset.unwrap_or(0),
+            };
+            let what = match other {
+                "Call" => "function call",
+                "Constant" => "literal",
+                _ => "expression",
+            };
+            Err(SyntaxError::new(pos, format!("cannot assign to {what}")))
+        }
+    }
+}
+
+fn push_literal(parts: &mut Vec<Part>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(Part::Literal(last)) = parts.last_mut() {
+        last.push_str(text);
+    } else {
+        parts.push(Part::Literal(text.to_string()));
+    }
+}
+
+/// `JoinedStr` of f-string parts, with CPython 3.11's spans
+///
+/// Every part carries the span of the whole (possibly concatenated)
+/// literal, except that the `JoinedStr` itself and a trailing literal get
+/// `finish`: the same span at the top level, but just the enclosing token
+/// for a format spec.
+fn joined_str(parts: Vec<Part>, whole: (Pos, Pos), finish: (Pos, Pos)) -> PythonAST {
+    let mut node = located("JoinedStr", finish.0, finish.1);
+    let count = parts.len();
+    for (i, part) in parts.into_iter().enumerate() {
+        let child = match part {
+            Part::Literal(text) => {
+                let (start, end) = if i + 1 == count { finish } else { whole };
+                let mut constant = located("Constant", start, end);
+                set_attr(&mut constant, "value_type", "str");
+                set_attr(&mut constant, "value", text);
+                constant
+            }
+            Part::Field(field) => field,
+        };
+        node.push_child("values", child);
+    }
+    node
+}
+
+/// Reader over the body of one f-string token
+struct FStringReader<'a> {
+    token: &'a Token,
+    body: &'a str,
+    /// Offset of `body` in the token text
+    body_offset: usize,
+    raw: bool,
+    /// Span of the whole (possibly concatenated) literal
+    span: (Pos, Pos),
+    index: usize,
+}
+
+impl FStringReader<'_> {
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError::new(self.token.start, format!("f-string: {message}"))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.body[self.index..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.body[self.index..].chars().nth(1)
+    }
+
+    /// Read literal text and replacement fields into `parts`
+    ///
+    /// In a format spec, reading stops before the `}` closing the field.
+    fn parts(&mut self, in_spec: bool, parts: &mut Vec<Part>) -> PResult<()> {
+        let mut literal = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '{' if !in_spec && self.peek_second() == Some('{') => {
+                    literal.push('{');
+                    self.index += 2;
+                }
+                '{' => {
+                    self.flush(&mut literal, parts)?;
+                    self.field(parts)?;
+                }
+                '}' if in_spec => break,
+                '}' if self.peek_second() == Some('}') => {
+                    literal.push('}');
+                    self.index += 2;
+                }
+                '}' => return Err(self.error("single '}' is not allowed")),
+                '\\' if !self.raw => {
+                    literal.push('\\');
+                    self.index += 1;
+                    if let Some(next) = self.peek().filter(|next| !matches!(next, '{' | '}')) {
+                        literal.push(next);
+                        self.index += next.len_utf8();
+                    }
+                }
+                c => {
+                    literal.push(c);
+                    self.index += c.len_utf8();
+                }
+            }
+        }
+        if in_spec && self.peek().is_none() {
+            return Err(self.error("expecting '}'"));
+        }
+        self.flush(&mut literal, parts)
+    }
+
+    fn flush(&self, literal: &mut String, parts: &mut Vec<Part>) -> PResult<()> {
+        let prefix = literals::StringPrefix {
+            raw: self.raw,
+            ..literals::StringPrefix::default()
+        };
+        let value =
+            literals::decode_escapes(literal, prefix).map_err(|message| self.error(&message))?;
+        push_literal(parts, &value);
+        literal.clear();
+        Ok(())
+    }
+
+    /// Replacement field starting at `{`, through its closing `}`
+    ///
+    /// A self-documenting field (`{x=}`) adds its text as a literal first.
+    fn field(&mut self, parts: &mut Vec<Part>) -> PResult<()> {
+        let open = self.index;
+        self.index += 1;
+        let end = self.expression_end()?;
+        let expression = &self.body[open + 1..end];
+        if expression.trim().is_empty() {
+            return Err(self.error("empty expression not allowed"));
+        }
+        let value = self.parse_expression(open, expression)?;
+        self.index = end;
+
+        let debug = self.peek() == Some('=');
+        if debug {
+            self.index += 1;
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.index += 1;
+            }
+            push_literal(parts, &self.body[open + 1..self.index]);
+        }
+        let mut conversion = -1;
+        if self.peek() == Some('!') {
+            conversion = match self.peek_second() {
+                Some('s') => 115,
+                Some('r') => 114,
+                Some('a') => 97,
+                _ => {
+                    return Err(
+                        self.error("invalid conversion character: expected 's', 'r', or 'a'")
+                    )
+                }
+            };
+            self.index += 2;
+        }
+        let format_spec = if self.peek() == Some(':') {
+            self.index += 1;
+            let mut spec = Vec::new();
+            self.parts(true, &mut spec)?;
+            Some(joined_str(
+                spec,
+                self.span,
+                (self.token.start, self.token.end),
+            ))
+        } else {
+            None
+        };
+        if self.peek() != Some('}') {
+            return Err(self.error("expecting '}'"));
+        }
+        self.index += 1;
+        if debug && conversion == -1 && format_spec.is_none() {
+            conversion = 114;
+        }
+
+        let mut field = located("FormattedValue", self.span.0, self.span.1);
+        field.push_child("value", value);
+        set_attr(&mut field, "conversion", conversion.to_string());
+        push_all(&mut field, "format_spec", format_spec.into_iter().collect());
+        parts.push(Part::Field(field));
+        Ok(())
+    }
+
+    /// Byte index where the expression of the current field ends
+    fn expression_end(&self) -> PResult<usize> {
+        let bytes = self.body.as_bytes();
+        let mut depth = 0usize;
+        let mut index = self.index;
+        let mut quote = None;
+        while index < bytes.len() {
+            let c = bytes[index];
+            if let Some(open) = quote {
+                if c == open {
+                    quote = None;
+                }
+                index += 1;
+                continue;
+            }
+            let next = bytes.get(index + 1).copied();
+            let previous = if index > 0 { bytes[index - 1] } else { 0 };
+            match c {
+                b'\'' | b'"' => quote = Some(c),
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' if depth > 0 => depth -= 1,
+                b'}' => return Ok(index),
+                b'!' if depth == 0 && next != Some(b'=') => return Ok(index),
+                b':' if depth == 0 => return Ok(index),
+                b'=' if depth == 0
+                    && next != Some(b'=')
+                    && !matches!(previous, b'=' | b'!' | b'<' | b'>') =>
+                {
+                    return Ok(index)
+                }
+                b'#' => return Err(self.error("expression part cannot include '#'")),
+                b'\\' => return Err(self.error("expression part cannot include a backslash")),
+                _ => {}
+            }
+            index += 1;
+        }
+        Err(self.error("expecting '}'"))
+    }
+
+    /// Parse the expression of a field whose `{` is at body index `open`
+    ///
+    /// CPython parses `(expression)` with the parenthesis standing in for
+    /// the brace, so nodes get their position in the file.
+    fn parse_expression(&self, open: usize, expression: &str) -> PResult<PythonAST> {
+        let before = &self.token.text[..self.body_offset + open];
+        let origin = match before.rfind('\n') {
+            Some(newline) => Pos {
+                line: self.token.start.line + before.matches('\n').count(),
+                col: before.len() - newline - 1,
+            },
+            None => Pos {
+                line: self.token.start.line,
+                col: self.token.start.col + before.len(),
+            },
+        };
+        let source = format!("({expression})");
+        let tokens = tokenize(&source, origin).map_err(|error| self.error(&error.message))?;
+        let mut parser = Parser::new(tokens);
+        let value = parser
+            .star_expressions()
+            .map_err(|error| self.error(&error.message))?;
+        if parser.peek().kind != TokenKind::Newline {
+            return Err(self.error("invalid syntax"));
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> PythonAST {
+        parse_module(tokenize(source, Pos { line: 1, col: 0 }).unwrap()).unwrap()
+    }
+
+    fn span(node: &PythonAST) -> (usize, usize, usize, usize) {
+        (
+            node.lineno.unwrap(),
+            node.col_offset.unwrap(),
+            node.end_lineno.unwrap(),
+            node.end_col_offset.unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_spans_follow_rules_not_children() {
+        let module = parse("x = (a).b + (c)\n");
+        let assign = module.child("body").unwrap();
+        let binop = assign.child("value").unwrap();
+        assert_eq!(span(binop), (1, 4, 1, 15));
+        let attribute = binop.child("left").unwrap();
+        assert_eq!(span(attribute), (1, 4, 1, 9));
+        assert_eq!(span(attribute.child("value").unwrap()), (1, 5, 1, 6));
+        assert_eq!(span(binop.child("right").unwrap()), (1, 13, 1, 14));
+    }
+
+    #[test]
+    fn test_elif_nests_in_orelse() {
+        let module = parse("if a:\n    pass\nelif b:\n    pass\nelse:\n    x = 1\n");
+        let outer = module.child("body").unwrap();
+        let inner = outer.child("orelse").unwrap();
+        assert_eq!(inner.node_type, "If");
+        assert_eq!(span(outer), (1, 0, 6, 9));
+        assert_eq!(span(inner), (3, 0, 6, 9));
+    }
+
+    #[test]
+    fn test_fstring_parts_share_the_literal_span() {
+        let module = parse("s = f'a={x!r:>{y}}b'\n");
+        let joined = module.child("body").unwrap().child("value").unwrap();
+        let kinds: Vec<_> = joined
+            .children
+            .iter()
+            .map(|part| (part.node_type.as_str(), span(part)))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("Constant", (1, 4, 1, 20)),
+                ("FormattedValue", (1, 4, 1, 20)),
+                ("Constant", (1, 4, 1, 20)),
+            ]
+        );
+        let field = &joined.children[1];
+        assert_eq!(field.attributes["conversion"], "114");
+        assert_eq!(span(field.child("value").unwrap()), (1, 9, 1, 10));
+        let spec = field.child("format_spec").unwrap();
+        assert_eq!(
+            spec.children[1].child("value").unwrap().attributes["id"],
+            "y"
+        );
+    }
+
+    #[test]
+    fn test_match_patterns() {
+        let module = parse(
+            "match p:\n    case Point(x=0) | [1, *rest] as q if q:\n        pass\n    case _:\n        pass\n",
+        );
+        let statement = module.child("body").unwrap();
+        assert_eq!(statement.node_type, "Match");
+        assert_eq!(span(statement), (1, 0, 5, 12));
+        let case = statement.child("cases").unwrap();
+        let capture = case.child("pattern").unwrap();
+        assert_eq!(capture.node_type, "MatchAs");
+        assert_eq!(capture.attributes["name"], "q");
+        let alternatives: Vec<_> = capture
+            .child("pattern")
+            .unwrap()
+            .children
+            .iter()
+            .map(|pattern| pattern.node_type.as_str())
+            .collect();
+        assert_eq!(alternatives, ["MatchClass", "MatchSequence"]);
+        assert_eq!(case.child("guard").unwrap().attributes["id"], "q");
+        let wildcard = statement.children[2].child("pattern").unwrap();
+        assert!(!wildcard.attributes.contains_key("name"));
+    }
+
+    #[test]
+    fn test_invalid_targets_are_rejected() {
+        let tokens = tokenize("f() = 1\n", Pos { line: 1, col: 0 }).unwrap();
+        let error = parse_module(tokens).unwrap_err();
+        assert_eq!(error.message, "cannot assign to function call");
+    }
+}
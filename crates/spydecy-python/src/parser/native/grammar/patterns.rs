@@ -0,0 +1,347 @@
+//! `match` statements and their patterns
+//!
+//! Mirrors the `match_stmt` and `*_pattern` rules of CPython's grammar,
+//! including the soft keywords `match`, `case` and `_`.
+
+use super::{push_all, set_attr, PResult, Parser, KEYWORDS};
+use crate::parser::native::lexer::TokenKind;
+use crate::parser::native::Pos;
+use crate::parser::native::SyntaxError;
+use crate::parser::PythonAST;
+
+impl Parser {
+    pub(super) fn match_statement(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let subject = self.subject()?;
+        self.expect_op(":")?;
+        self.expect_newline()?;
+        if self.peek().kind != TokenKind::Indent {
+            return Err(self.unexpected("expected an indented block"));
+        }
+        self.advance();
+        let mut cases = Vec::new();
+        while self.at_keyword("case") {
+            cases.push(self.case_block()?);
+        }
+        if cases.is_empty() || self.peek().kind != TokenKind::Dedent {
+            return Err(self.unexpected("expected 'case' block"));
+        }
+        self.advance();
+        let mut node = self.node("Match", start);
+        node.push_child("subject", subject);
+        push_all(&mut node, "cases", cases);
+        Ok(node)
+    }
+
+    /// Subject of a `match`, a tuple when there is a comma
+    fn subject(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let first = self.star_named_expression()?;
+        if !self.at_op(",") {
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(",") {
+            if self.at_op(":") {
+                break;
+            }
+            elts.push(self.star_named_expression()?);
+        }
+        Ok(self.sequence("Tuple", start, elts))
+    }
+
+    /// `case patterns [if guard]: block`; `match_case` has no location
+    fn case_block(&mut self) -> PResult<PythonAST> {
+        self.advance();
+        let pattern = self.top_level_pattern()?;
+        let guard = if self.eat_keyword("if") {
+            Some(self.named_expression()?)
+        } else {
+            None
+        };
+        self.expect_op(":")?;
+        let body = self.block()?;
+        let mut node = PythonAST::new("match_case".to_string());
+        node.push_child("pattern", pattern);
+        if let Some(guard) = guard {
+            node.push_child("guard", guard);
+        }
+        push_all(&mut node, "body", body);
+        Ok(node)
+    }
+
+    /// Pattern after `case`, where an open sequence needs no brackets
+    fn top_level_pattern(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let first = self.maybe_star_pattern()?;
+        if !self.at_op(",") {
+            if first.node_type == "MatchStar" {
+                return Err(SyntaxError::new(start, "star pattern outside a sequence"));
+            }
+            return Ok(first);
+        }
+        let mut patterns = vec![first];
+        while self.eat_op(",") {
+            if self.at_op(":") || self.at_keyword("if") {
+                break;
+            }
+            patterns.push(self.maybe_star_pattern()?);
+        }
+        Ok(self.match_sequence(start, patterns))
+    }
+
+    fn match_sequence(&self, start: Pos, patterns: Vec<PythonAST>) -> PythonAST {
+        let mut node = self.node("MatchSequence", start);
+        push_all(&mut node, "patterns", patterns);
+        node
+    }
+
+    fn maybe_star_pattern(&mut self) -> PResult<PythonAST> {
+        if !self.at_op("*") {
+            return self.pattern();
+        }
+        let start = self.start();
+        self.advance();
+        let name = self.expect_name()?;
+        let mut node = self.node("MatchStar", start);
+        if name != "_" {
+            set_attr(&mut node, "name", name);
+        }
+        Ok(node)
+    }
+
+    /// `or_pattern [as name]`
+    fn pattern(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let pattern = self.or_pattern()?;
+        if !self.eat_keyword("as") {
+            return Ok(pattern);
+        }
+        let name_start = self.start();
+        let name = self.expect_name()?;
+        if name == "_" {
+            return Err(SyntaxError::new(name_start, "cannot use '_' as a target"));
+        }
+        let mut node = self.node("MatchAs", start);
+        node.push_child("pattern", pattern);
+        set_attr(&mut node, "name", name);
+        Ok(node)
+    }
+
+    fn or_pattern(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        let first = self.closed_pattern()?;
+        if !self.at_op("|") {
+            return Ok(first);
+        }
+        let mut patterns = vec![first];
+        while self.eat_op("|") {
+            patterns.push(self.closed_pattern()?);
+        }
+        let mut node = self.node("MatchOr", start);
+        push_all(&mut node, "patterns", patterns);
+        Ok(node)
+    }
+
+    fn closed_pattern(&mut self) -> PResult<PythonAST> {
+        let token = self.peek().clone();
+        let start = token.start;
+        match (token.kind, token.text.as_str()) {
+            (TokenKind::Number | TokenKind::String, _) | (TokenKind::Op, "-") => {
+                let value = self.literal_expression()?;
+                Ok(self.match_value(start, value))
+            }
+            (TokenKind::Name, "None") => {
+                self.advance();
+                Ok(self.node("MatchSingleton", start))
+            }
+            (TokenKind::Name, "True" | "False") => {
+                self.advance();
+                let mut node = self.node("MatchSingleton", start);
+                set_attr(&mut node, "value", token.text);
+                Ok(node)
+            }
+            (TokenKind::Op, "(") => {
+                self.advance();
+                if self.eat_op(")") {
+                    return Ok(self.match_sequence(start, Vec::new()));
+                }
+                let first = self.maybe_star_pattern()?;
+                if self.eat_op(")") {
+                    if first.node_type == "MatchStar" {
+                        return Err(SyntaxError::new(start, "star pattern outside a sequence"));
+                    }
+                    return Ok(first);
+                }
+                let mut patterns = vec![first];
+                while self.eat_op(",") && !self.at_op(")") {
+                    patterns.push(self.maybe_star_pattern()?);
+                }
+                self.expect_op(")")?;
+                Ok(self.match_sequence(start, patterns))
+            }
+            (TokenKind::Op, "[") => {
+                self.advance();
+                let mut patterns = Vec::new();
+                while !self.at_op("]") {
+                    patterns.push(self.maybe_star_pattern()?);
+                    if !self.eat_op(",") {
+                        break;
+                    }
+                }
+                self.expect_op("]")?;
+                Ok(self.match_sequence(start, patterns))
+            }
+            (TokenKind::Op, "{") => self.mapping_pattern(),
+            (TokenKind::Name, name) if !KEYWORDS.contains(&name) => {
+                let (value, dotted) = self.name_or_attribute()?;
+                if self.at_op("(") {
+                    self.class_pattern(start, value)
+                } else if dotted {
+                    Ok(self.match_value(start, value))
+                } else {
+                    let mut node = self.node("MatchAs", start);
+                    if name != "_" {
+                        set_attr(&mut node, "name", name);
+                    }
+                    Ok(node)
+                }
+            }
+            _ => Err(self.unexpected("invalid pattern")),
+        }
+    }
+
+    fn match_value(&self, start: Pos, value: PythonAST) -> PythonAST {
+        let mut node = self.node("MatchValue", start);
+        node.push_child("value", value);
+        node
+    }
+
+    /// Number, signed number, `real ± imaginary` or string literal
+    fn literal_expression(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        if self.peek().kind == TokenKind::String {
+            return self.strings();
+        }
+        let mut value = if self.eat_op("-") {
+            let number = self.number()?;
+            self.unary(start, "USub", number)
+        } else {
+            self.number()?
+        };
+        if self.at_op("+") || self.at_op("-") {
+            let op = if self.at_op("+") { "Add" } else { "Sub" };
+            self.advance();
+            let imaginary = self.number()?;
+            if imaginary.attributes.get("value_type").map(String::as_str) != Some("complex") {
+                return Err(SyntaxError::new(
+                    start,
+                    "imaginary number required in complex literal",
+                ));
+            }
+            value = self.binop(start, value, op, imaginary);
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> PResult<PythonAST> {
+        if self.peek().kind == TokenKind::Number {
+            self.atom()
+        } else {
+            Err(self.unexpected("expected a number"))
+        }
+    }
+
+    /// `name(.attribute)*`, and whether there was at least one dot
+    fn name_or_attribute(&mut self) -> PResult<(PythonAST, bool)> {
+        let start = self.start();
+        let mut value = self.atom()?;
+        let mut dotted = false;
+        while self.eat_op(".") {
+            let attr = self.expect_name()?;
+            let mut node = self.node("Attribute", start);
+            node.push_child("value", value);
+            set_attr(&mut node, "attr", attr);
+            value = node;
+            dotted = true;
+        }
+        Ok((value, dotted))
+    }
+
+    fn mapping_pattern(&mut self) -> PResult<PythonAST> {
+        let start = self.start();
+        self.advance();
+        let mut keys = Vec::new();
+        let mut patterns = Vec::new();
+        let mut rest = None;
+        while !self.at_op("}") {
+            if self.eat_op("**") {
+                rest = Some(self.expect_name()?);
+                self.eat_op(",");
+                break;
+            }
+            let token = self.peek();
+            let key_start = token.start;
+            let key =
+                match (token.kind, token.text.as_str()) {
+                    (TokenKind::Name, "None" | "True" | "False") => self.atom()?,
+                    (TokenKind::Name, _) => match self.name_or_attribute()? {
+                        (key, true) => key,
+                        (_, false) => return Err(SyntaxError::new(
+                            key_start,
+                            "mapping pattern keys may only match literals and attribute lookups",
+                        )),
+                    },
+                    _ => self.literal_expression()?,
+                };
+            self.expect_op(":")?;
+            keys.push(key);
+            patterns.push(self.pattern()?);
+            if !self.eat_op(",") {
+                break;
+            }
+        }
+        self.expect_op("}")?;
+        let mut node = self.node("MatchMapping", start);
+        push_all(&mut node, "keys", keys);
+        push_all(&mut node, "patterns", patterns);
+        if let Some(rest) = rest {
+            set_attr(&mut node, "rest", rest);
+        }
+        Ok(node)
+    }
+
+    fn class_pattern(&mut self, start: Pos, cls: PythonAST) -> PResult<PythonAST> {
+        self.advance();
+        let mut patterns = Vec::new();
+        let mut kwd_attrs = Vec::new();
+        let mut kwd_patterns = Vec::new();
+        while !self.at_op(")") {
+            let keyword = self.peek().kind == TokenKind::Name
+                && self.peek_nth(1).kind == TokenKind::Op
+                && self.peek_nth(1).text == "=";
+            if keyword {
+                kwd_attrs.push(self.expect_name()?);
+                self.advance();
+                kwd_patterns.push(self.pattern()?);
+            } else if kwd_attrs.is_empty() {
+                patterns.push(self.pattern()?);
+            } else {
+                return Err(self.unexpected("positional patterns follow keyword patterns"));
+            }
+            if !self.eat_op(",") {
+                break;
+            }
+        }
+        self.expect_op(")")?;
+        let mut node = self.node("MatchClass", start);
+        node.push_child("cls", cls);
+        push_all(&mut node, "patterns", patterns);
+        if !kwd_attrs.is_empty() {
+            set_attr(&mut node, "kwd_attrs", kwd_attrs.join(","));
+        }
+        push_all(&mut node, "kwd_patterns", kwd_patterns);
+        Ok(node)
+    }
+}
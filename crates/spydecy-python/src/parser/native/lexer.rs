@@ -0,0 +1,480 @@
+//! Tokenizer
+//!
+//! Turns source text into the token stream of CPython's tokenizer: names,
+//! numbers, strings and operators, plus `NEWLINE`, `INDENT` and `DEDENT`
+//! for the layout. Comments, blank lines and newlines inside brackets
+//! produce no tokens. Columns are byte offsets, like `ast` reports them.
+
+use super::{Pos, SyntaxError};
+
+/// Token category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TokenKind {
+    Name,
+    Number,
+    String,
+    Op,
+    Newline,
+    Indent,
+    Dedent,
+    EndMarker,
+}
+
+/// Token with its source span
+#[derive(Debug, Clone)]
+pub(super) struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub start: Pos,
+    pub end: Pos,
+}
+
+impl Token {
+    /// Whether the token is layout only (never part of a node's span)
+    pub fn is_layout(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent | TokenKind::EndMarker
+        )
+    }
+}
+
+/// Operators, longest first so that the first match wins
+const OPERATORS: &[&str] = &[
+    "**=", "//=", ">>=", "<<=", "...", "->", ":=", "**", "//", "<<", ">>", "<=", ">=", "==", "!=",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "@=", "(", ")", "[", "]", "{", "}", ",", ":",
+    ";", ".", "+", "-", "*", "/", "%", "&", "|", "^", "~", "<", ">", "=", "@", "!",
+];
+
+/// Tokenize `source`
+///
+/// `origin` is the position of the first byte. Columns on that first line
+/// are shifted by its column, which places the tokens of an f-string
+/// replacement field at their position in the enclosing file.
+pub(super) fn tokenize(source: &str, origin: Pos) -> Result<Vec<Token>, SyntaxError> {
+    Lexer {
+        source,
+        offset: 0,
+        line: 0,
+        line_start: 0,
+        origin,
+        depth: 0,
+        indents: vec![0],
+        tokens: Vec::new(),
+    }
+    .run()
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    offset: usize,
+    /// Line of `offset`, counted from 0 relative to `origin`
+    line: usize,
+    line_start: usize,
+    origin: Pos,
+    /// Bracket nesting, inside which newlines and indentation are ignored
+    depth: usize,
+    indents: Vec<usize>,
+    tokens: Vec<Token>,
+}
+
+impl Lexer<'_> {
+    fn run(mut self) -> Result<Vec<Token>, SyntaxError> {
+        let mut at_line_start = true;
+        loop {
+            if at_line_start && self.depth == 0 {
+                if !self.indentation()? {
+                    break;
+                }
+                at_line_start = false;
+            }
+            self.skip_whitespace();
+            let Some(c) = self.peek() else { break };
+            match c {
+                '#' => self.skip_comment(),
+                '\\' => {
+                    self.offset += 1;
+                    if !self.eat_newline() {
+                        return Err(
+                            self.error("unexpected character after line continuation character")
+                        );
+                    }
+                    if self.peek().is_none() {
+                        return Err(self.error("unexpected EOF while parsing"));
+                    }
+                }
+                '\n' | '\r' => {
+                    let start = self.pos();
+                    self.eat_newline();
+                    if self.depth == 0 {
+                        let end = Pos {
+                            line: start.line,
+                            col: start.col + 1,
+                        };
+                        self.push(TokenKind::Newline, String::new(), start, end);
+                        at_line_start = true;
+                    }
+                }
+                c if c.is_ascii_digit() => self.number()?,
+                '.' if self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) => self.number()?,
+                '"' | '\'' => self.string(self.offset)?,
+                c if is_identifier_start(c) => self.name()?,
+                _ => self.operator()?,
+            }
+        }
+
+        if self.depth > 0 {
+            return Err(self.error("unexpected EOF while parsing"));
+        }
+        let end = self.pos();
+        if self
+            .tokens
+            .last()
+            .is_some_and(|token| token.kind != TokenKind::Newline)
+        {
+            self.push(TokenKind::Newline, String::new(), end, end);
+        }
+        for _ in 1..self.indents.len() {
+            self.push(TokenKind::Dedent, String::new(), end, end);
+        }
+        self.push(TokenKind::EndMarker, String::new(), end, end);
+        Ok(self.tokens)
+    }
+
+    /// Measure the indentation of a logical line and emit `INDENT`/`DEDENT`
+    ///
+    /// Blank and comment-only lines are consumed. Returns `false` at the end
+    /// of the input.
+    fn indentation(&mut self) -> Result<bool, SyntaxError> {
+        loop {
+            let mut width = 0;
+            while let Some(c) = self.peek() {
+                match c {
+                    ' ' => width += 1,
+                    '\t' => width = (width / 8 + 1) * 8,
+                    '\x0c' => width = 0,
+                    _ => break,
+                }
+                self.offset += 1;
+            }
+            match self.peek() {
+                None => return Ok(false),
+                Some('#') => {
+                    self.skip_comment();
+                    if !self.eat_newline() {
+                        return Ok(false);
+                    }
+                }
+                Some('\n' | '\r') => {
+                    self.eat_newline();
+                }
+                Some('\\') if self.tokens.is_empty() => {
+                    return Err(self.error("unexpected line continuation"));
+                }
+                Some(_) => {
+                    let current = self.indents.last().copied().unwrap_or(0);
+                    let pos = self.pos();
+                    if width > current {
+                        self.indents.push(width);
+                        self.push(TokenKind::Indent, String::new(), pos, pos);
+                    } else {
+                        while width < self.indents.last().copied().unwrap_or(0) {
+                            self.indents.pop();
+                            self.push(TokenKind::Dedent, String::new(), pos, pos);
+                        }
+                        if self.indents.last() != Some(&width) {
+                            return Err(
+                                self.error("unindent does not match any outer indentation level")
+                            );
+                        }
+                    }
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<(), SyntaxError> {
+        let start = self.offset;
+        while self.peek().is_some_and(is_identifier_char) {
+            self.bump();
+        }
+        let text = &self.source[start..self.offset];
+        if matches!(self.peek(), Some('"' | '\'')) && is_string_prefix(text) {
+            return self.string(start);
+        }
+        let text = text.to_string();
+        self.push_from(TokenKind::Name, text, start);
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<(), SyntaxError> {
+        let start = self.offset;
+        let radix_prefix = self.peek() == Some('0')
+            && matches!(self.peek_nth(1), Some('x' | 'X' | 'o' | 'O' | 'b' | 'B'));
+        if radix_prefix {
+            self.offset += 2;
+            while self
+                .peek()
+                .is_some_and(|c| c.is_ascii_hexdigit() || c == '_')
+            {
+                self.offset += 1;
+            }
+        } else {
+            self.digits();
+            if self.peek() == Some('.') {
+                self.offset += 1;
+                self.digits();
+            }
+            if matches!(self.peek(), Some('e' | 'E')) {
+                let sign = usize::from(matches!(self.peek_nth(1), Some('+' | '-')));
+                if self.peek_nth(1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                    self.offset += 1 + sign;
+                    self.digits();
+                }
+            }
+            if matches!(self.peek(), Some('j' | 'J')) {
+                self.offset += 1;
+            }
+        }
+        if self.peek().is_some_and(is_identifier_char) {
+            return Err(self.error("invalid decimal literal"));
+        }
+        let text = self.source[start..self.offset].to_string();
+        self.push_from(TokenKind::Number, text, start);
+        Ok(())
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '_') {
+            self.offset += 1;
+        }
+    }
+
+    /// Lex a string literal whose prefix starts at `start`
+    fn string(&mut self, start: usize) -> Result<(), SyntaxError> {
+        let start_pos = self.pos_of(start);
+        let quote = self.peek().unwrap_or('"');
+        let triple = self.peek_nth(1) == Some(quote) && self.peek_nth(2) == Some(quote);
+        self.offset += if triple { 3 } else { 1 };
+
+        loop {
+            let Some(c) = self.peek() else {
+                let message = if triple {
+                    "unterminated triple-quoted string literal"
+                } else {
+                    "unterminated string literal"
+                };
+                return Err(SyntaxError::new(start_pos, message));
+            };
+            match c {
+                '\\' => {
+                    self.offset += 1;
+                    if !self.eat_newline() {
+                        self.bump();
+                    }
+                }
+                '\n' | '\r' if !triple => {
+                    return Err(SyntaxError::new(start_pos, "unterminated string literal"));
+                }
+                '\n' | '\r' => {
+                    self.eat_newline();
+                }
+                c if c == quote => {
+                    if !triple {
+                        self.offset += 1;
+                        break;
+                    }
+                    if self.peek_nth(1) == Some(quote) && self.peek_nth(2) == Some(quote) {
+                        self.offset += 3;
+                        break;
+                    }
+                    self.offset += 1;
+                }
+                _ => self.bump(),
+            }
+        }
+
+        let text = self.source[start..self.offset].to_string();
+        let end = self.pos();
+        self.push(TokenKind::String, text, start_pos, end);
+        Ok(())
+    }
+
+    fn operator(&mut self) -> Result<(), SyntaxError> {
+        let rest = &self.source[self.offset..];
+        let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) else {
+            let c = self.peek().unwrap_or(' ');
+            return Err(self.error(&format!("invalid character '{c}' (U+{:04X})", u32::from(c))));
+        };
+        match *op {
+            "(" | "[" | "{" => self.depth += 1,
+            ")" | "]" | "}" => {
+                if self.depth == 0 {
+                    return Err(self.error(&format!("unmatched '{op}'")));
+                }
+                self.depth -= 1;
+            }
+            _ => {}
+        }
+        let start = self.offset;
+        self.offset += op.len();
+        self.push_from(TokenKind::Op, (*op).to_string(), start);
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\x0c')) {
+            self.offset += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n' && c != '\r') {
+            self.bump();
+        }
+    }
+
+    /// Consume a `\n`, `\r\n` or `\r` line break
+    fn eat_newline(&mut self) -> bool {
+        match self.peek() {
+            Some('\n') => self.offset += 1,
+            Some('\r') => {
+                self.offset += 1;
+                if self.peek() == Some('\n') {
+                    self.offset += 1;
+                }
+            }
+            _ => return false,
+        }
+        self.line += 1;
+        self.line_start = self.offset;
+        true
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.source[self.offset..].chars().nth(n)
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.offset += c.len_utf8();
+        }
+    }
+
+    fn pos(&self) -> Pos {
+        self.pos_of(self.offset)
+    }
+
+    /// Position of a byte offset on the current line
+    fn pos_of(&self, offset: usize) -> Pos {
+        let col = offset - self.line_start;
+        if self.line == 0 {
+            Pos {
+                line: self.origin.line,
+                col: self.origin.col + col,
+            }
+        } else {
+            Pos {
+                line: self.origin.line + self.line,
+                col,
+            }
+        }
+    }
+
+    fn push(&mut self, kind: TokenKind, text: String, start: Pos, end: Pos) {
+        self.tokens.push(Token {
+            kind,
+            text,
+            start,
+            end,
+        });
+    }
+
+    /// Push a single-line token that started at byte `start`
+    fn push_from(&mut self, kind: TokenKind, text: String, start: usize) {
+        let (start, end) = (self.pos_of(start), self.pos());
+        self.push(kind, text, start, end);
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError::new(self.pos(), message)
+    }
+}
+
+/// Whether `text` is a valid string prefix (`r`, `b`, `f`, `u`, `rb`, `fr`, ...)
+fn is_string_prefix(text: &str) -> bool {
+    matches!(
+        text.to_ascii_lowercase().as_str(),
+        "r" | "u" | "b" | "f" | "br" | "rb" | "fr" | "rf"
+    )
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(TokenKind, String)> {
+        tokenize(source, Pos { line: 1, col: 0 })
+            .unwrap()
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_layout_tokens() {
+        use TokenKind::{Dedent, EndMarker, Indent, Name, Newline, Op};
+        let tokens = kinds("if x:\n    # note\n\n    y = (1,\n  2)\nz\n");
+        let layout: Vec<_> = tokens.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            layout,
+            [
+                Name,
+                Name,
+                Op,
+                Newline,
+                Indent,
+                Name,
+                Op,
+                Op,
+                TokenKind::Number,
+                Op,
+                TokenKind::Number,
+                Op,
+                Newline,
+                Dedent,
+                Name,
+                Newline,
+                EndMarker
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_prefixes_and_positions() {
+        let tokens = tokenize("x = rb'\\'' + '''a\nb'''", Pos { line: 1, col: 0 }).unwrap();
+        assert_eq!(tokens[2].text, "rb'\\''");
+        assert_eq!((tokens[2].start.col, tokens[2].end.col), (4, 10));
+        assert_eq!(tokens[4].text, "'''a\nb'''");
+        assert_eq!((tokens[4].end.line, tokens[4].end.col), (2, 4));
+    }
+
+    #[test]
+    fn test_inconsistent_dedent_is_an_error() {
+        let error = tokenize("if x:\n    y\n  z\n", Pos { line: 1, col: 0 }).unwrap_err();
+        assert_eq!(error.pos.line, 3);
+    }
+}
@@ -0,0 +1,318 @@
+//! Literal decoding
+//!
+//! Constants are stored the way the `ast` backend extracts them: integers
+//! as decimal text of arbitrary size, floats as their `repr`, strings
+//! decoded and bytes hex-encoded.
+
+use std::{fmt::Write, ops::Range};
+
+/// Value of a numeric literal
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Number {
+    /// Decimal digits, without sign or leading zeros
+    Int(String),
+    Float(f64),
+    Imaginary(f64),
+}
+
+/// Decode a number token
+pub(super) fn parse_number(text: &str) -> Result<Number, String> {
+    let invalid = || format!("invalid number literal '{text}'");
+    if text.contains("__") || text.ends_with('_') {
+        return Err(invalid());
+    }
+    let digits = text.replace('_', "");
+    let lower = digits.to_ascii_lowercase();
+
+    let radix = match lower.get(..2) {
+        Some("0x") => Some(16),
+        Some("0o") => Some(8),
+        Some("0b") => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        return int_to_decimal(&lower[2..], radix)
+            .map(Number::Int)
+            .ok_or_else(invalid);
+    }
+
+    if let Some(imaginary) = lower.strip_suffix('j') {
+        return parse_float(imaginary)
+            .map(Number::Imaginary)
+            .ok_or_else(invalid);
+    }
+    if lower.contains(['.', 'e']) {
+        return parse_float(&lower).map(Number::Float).ok_or_else(invalid);
+    }
+    let trimmed = lower.trim_start_matches('0');
+    if trimmed.len() < lower.len() && !trimmed.is_empty() {
+        return Err(
+            "leading zeros in decimal integer literals are not permitted; use an 0o prefix for octal integers"
+                .to_string(),
+        );
+    }
+    int_to_decimal(&lower, 10)
+        .map(Number::Int)
+        .ok_or_else(invalid)
+}
+
+fn parse_float(text: &str) -> Option<f64> {
+    if text.is_empty() || text.starts_with('e') {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Convert digits in `radix` to decimal text of any size
+fn int_to_decimal(digits: &str, radix: u32) -> Option<String> {
+    // Little-endian limbs of 10^9
+    const BASE: u64 = 1_000_000_000;
+    if digits.is_empty() {
+        return None;
+    }
+    let mut limbs: Vec<u64> = vec![0];
+    for c in digits.chars() {
+        let mut carry = u64::from(c.to_digit(radix)?);
+        for limb in &mut limbs {
+            let value = *limb * u64::from(radix) + carry;
+            *limb = value % BASE;
+            carry = value / BASE;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    }
+    let mut text = limbs.last().map_or_else(String::new, u64::to_string);
+    for limb in limbs.iter().rev().skip(1) {
+        let _ = write!(text, "{limb:09}");
+    }
+    Some(text)
+}
+
+/// Python's `repr` of a float
+pub(super) fn float_repr(value: f64) -> String {
+    format_float(value, true)
+}
+
+/// Python's `repr` of an imaginary number (`1j`, `2.5j`)
+pub(super) fn imaginary_repr(value: f64) -> String {
+    format!("{}j", format_float(value, false))
+}
+
+/// Shortest round-tripping representation, in Python's layout
+///
+/// Python switches to exponent notation when the decimal point would fall
+/// more than 16 digits right or 4 digits left of the first digit.
+fn format_float(value: f64, add_dot_zero: bool) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    let decimal_point = exponent.parse::<i64>().unwrap_or(0) + 1;
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+    let ndigits = i64::try_from(digits.len()).unwrap_or(i64::MAX);
+
+    if !(-4 < decimal_point && decimal_point <= 16) {
+        let (first, rest) = digits.split_at(1);
+        let exponent = decimal_point - 1;
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        return format!(
+            "{sign}{first}{fraction}e{exponent_sign}{:02}",
+            exponent.abs()
+        );
+    }
+    let body = if decimal_point <= 0 {
+        let zeros = "0".repeat(usize::try_from(-decimal_point).unwrap_or(0));
+        format!("0.{zeros}{digits}")
+    } else if decimal_point >= ndigits {
+        let zeros = "0".repeat(usize::try_from(decimal_point - ndigits).unwrap_or(0));
+        if add_dot_zero {
+            format!("{digits}{zeros}.0")
+        } else {
+            format!("{digits}{zeros}")
+        }
+    } else {
+        let (whole, fraction) = digits.split_at(usize::try_from(decimal_point).unwrap_or(0));
+        format!("{whole}.{fraction}")
+    };
+    format!("{sign}{body}")
+}
+
+/// Prefix flags of a string literal
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct StringPrefix {
+    pub raw: bool,
+    pub bytes: bool,
+    pub formatted: bool,
+}
+
+/// Split a string token into its prefix and the range of the text between
+/// the quotes
+pub(super) fn split_string_token(token: &str) -> (StringPrefix, Range<usize>) {
+    let quote_at = token.find(['\'', '"']).unwrap_or(0);
+    let mut prefix = StringPrefix::default();
+    for c in token[..quote_at].chars() {
+        match c.to_ascii_lowercase() {
+            'r' => prefix.raw = true,
+            'b' => prefix.bytes = true,
+            'f' => prefix.formatted = true,
+            _ => {}
+        }
+    }
+    let quoted = &token[quote_at..];
+    let quote_len = if quoted.starts_with("'''") || quoted.starts_with("\"\"\"") {
+        3
+    } else {
+        1
+    };
+    let end = token
+        .len()
+        .saturating_sub(quote_len)
+        .max(quote_at + quote_len);
+    (prefix, quote_at + quote_len..end)
+}
+
+/// Decode the escape sequences of a string or bytes literal body
+///
+/// Bytes are returned as the chars `U+0000..=U+00FF`.
+pub(super) fn decode_escapes(body: &str, prefix: StringPrefix) -> Result<String, String> {
+    // Newlines in the source are normalized to `\n`
+    let body = body.replace("\r\n", "\n").replace('\r', "\n");
+    if prefix.bytes && !body.is_ascii() {
+        return Err("bytes can only contain ASCII literal characters".to_string());
+    }
+    if prefix.raw {
+        return Ok(body);
+    }
+
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            out.push('\\');
+            break;
+        };
+        match escape {
+            '\n' => {}
+            '\\' | '\'' | '"' => out.push(escape),
+            'a' => out.push('\x07'),
+            'b' => out.push('\x08'),
+            'f' => out.push('\x0c'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\x0b'),
+            '0'..='7' => {
+                let mut value = escape.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                if prefix.bytes {
+                    value &= 0xff;
+                }
+                out.push(char::from_u32(value).unwrap_or('\u{fffd}'));
+            }
+            'x' => out.push(hex_escape(&mut chars, 2, "\\xXX")?),
+            'u' if !prefix.bytes => out.push(hex_escape(&mut chars, 4, "\\uXXXX")?),
+            'U' if !prefix.bytes => out.push(hex_escape(&mut chars, 8, "\\UXXXXXXXX")?),
+            'N' if !prefix.bytes => {
+                return Err("\\N{...} escapes are not supported by the native parser".to_string())
+            }
+            other => {
+                out.push('\\');
+                out.push(other);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn hex_escape(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    len: usize,
+    form: &str,
+) -> Result<char, String> {
+    let mut value = 0;
+    for _ in 0..len {
+        let digit = chars
+            .next()
+            .and_then(|c| c.to_digit(16))
+            .ok_or_else(|| format!("truncated {form} escape"))?;
+        value = value * 16 + digit;
+    }
+    char::from_u32(value).ok_or_else(|| "illegal Unicode character".to_string())
+}
+
+/// Hex encoding of a decoded bytes literal, as `bytes.hex()` produces it
+pub(super) fn bytes_hex(value: &str) -> String {
+    value.chars().fold(String::new(), |mut hex, c| {
+        let _ = write!(hex, "{:02x}", u32::from(c));
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(parse_number("1_000"), Ok(Number::Int("1000".to_string())));
+        assert_eq!(parse_number("0xFF"), Ok(Number::Int("255".to_string())));
+        assert_eq!(
+            parse_number("0x1_0000_0000_0000_0000"),
+            Ok(Number::Int("18446744073709551616".to_string()))
+        );
+        assert_eq!(parse_number("00"), Ok(Number::Int("0".to_string())));
+        assert_eq!(parse_number("1e3"), Ok(Number::Float(1000.0)));
+        assert_eq!(parse_number(".5j"), Ok(Number::Imaginary(0.5)));
+        assert!(parse_number("012").is_err());
+    }
+
+    #[test]
+    fn test_float_repr_matches_python() {
+        assert_eq!(float_repr(2.5), "2.5");
+        assert_eq!(float_repr(1.0), "1.0");
+        assert_eq!(float_repr(0.1), "0.1");
+        assert_eq!(float_repr(1e16), "1e+16");
+        assert_eq!(float_repr(1e15), "1000000000000000.0");
+        assert_eq!(float_repr(0.0001), "0.0001");
+        assert_eq!(float_repr(0.00001), "1e-05");
+        assert_eq!(float_repr(1.5e-7), "1.5e-07");
+        assert_eq!(imaginary_repr(1.0), "1j");
+        assert_eq!(imaginary_repr(1e20), "1e+20j");
+    }
+
+    #[test]
+    fn test_decode_escapes() {
+        let plain = StringPrefix::default();
+        assert_eq!(decode_escapes(r"a\n\x41\101\q", plain).unwrap(), "a\nAA\\q");
+        let raw = StringPrefix { raw: true, ..plain };
+        assert_eq!(decode_escapes(r"a\n", raw).unwrap(), "a\\n");
+        let bytes = StringPrefix {
+            bytes: true,
+            ..plain
+        };
+        assert_eq!(bytes_hex(&decode_escapes(r"\x00a", bytes).unwrap()), "0061");
+    }
+}
@@ -0,0 +1,69 @@
+//! Pure-Rust parser backend
+//!
+//! Parses Python 3.11 source without an interpreter and builds the same
+//! [`PythonAST`] the PyO3 backend extracts from `ast.parse`: node types,
+//! field order, attribute encoding and source spans all match, so the HIR
+//! converter cannot tell the backends apart.
+//!
+//! ```text
+//! source → lexer (tokens + INDENT/DEDENT) → grammar (PythonAST)
+//! ```
+//!
+//! Without Unicode tables, `\N{...}` escapes are rejected and non-ASCII
+//! identifiers are kept as written rather than NFKC-normalized.
+
+mod grammar;
+mod lexer;
+mod literals;
+
+use super::PythonAST;
+use anyhow::{anyhow, Context, Result};
+use std::fmt;
+
+/// Position in the source: 1-based line, 0-based byte column
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Pos {
+    line: usize,
+    col: usize,
+}
+
+/// Syntax error at a position
+#[derive(Debug, Clone)]
+struct SyntaxError {
+    pos: Pos,
+    message: String,
+}
+
+impl SyntaxError {
+    fn new(pos: Pos, message: impl Into<String>) -> Self {
+        Self {
+            pos,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (line {}, column {})",
+            self.message,
+            self.pos.line,
+            self.pos.col + 1
+        )
+    }
+}
+
+/// Parse Python source into a `Module` node
+pub(super) fn parse(source: &str, filename: &str) -> Result<PythonAST> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let mut module = lexer::tokenize(source, Pos { line: 1, col: 0 })
+        .and_then(grammar::parse_module)
+        .map_err(|error| anyhow!("{error} in {filename}"))
+        .context("Failed to parse Python source code")?;
+    module
+        .attributes
+        .insert("filename".to_string(), filename.to_string());
+    Ok(module)
+}
@@ -0,0 +1,618 @@
+//! Expression unparser
+//!
+//! A port of the expression half of CPython 3.11's `ast.unparse`, used by
+//! both backends for the `default` attribute of parameters: `"a"` becomes
+//! `'a'`, `0x10` becomes `16` and parentheses appear exactly where operator
+//! precedence needs them. Doing it in Rust keeps the text identical across
+//! interpreters, including those older than 3.9 that have no `ast.unparse`.
+
+use super::PythonAST;
+use std::fmt::Write;
+
+// Precedence levels of `ast._Precedence`, loosest first
+const NAMED_EXPR: u8 = 1;
+const TUPLE: u8 = 2;
+const YIELD: u8 = 3;
+const TEST: u8 = 4;
+const OR: u8 = 5;
+const AND: u8 = 6;
+const NOT: u8 = 7;
+const CMP: u8 = 8;
+const EXPR: u8 = 9;
+const BXOR: u8 = 10;
+const BAND: u8 = 11;
+const SHIFT: u8 = 12;
+const ARITH: u8 = 13;
+const TERM: u8 = 14;
+const FACTOR: u8 = 15;
+const POWER: u8 = 16;
+const AWAIT: u8 = 17;
+const ATOM: u8 = 18;
+
+/// Large enough to overflow a float, as `ast.unparse` writes infinity
+const INFINITY: &str = "1e309";
+
+/// Unparse an expression the way `ast.unparse` does
+pub(super) fn unparse(node: &PythonAST) -> String {
+    let mut out = String::new();
+    write(node, TEST, &mut out);
+    out
+}
+
+fn attr<'a>(node: &'a PythonAST, key: &str) -> &'a str {
+    node.attributes.get(key).map_or("", String::as_str)
+}
+
+fn child<'a>(node: &'a PythonAST, field: &str) -> Option<&'a PythonAST> {
+    node.child(field)
+}
+
+/// Write `node` where the surrounding context binds with `precedence`
+#[allow(clippy::too_many_lines)]
+fn write(node: &PythonAST, precedence: u8, out: &mut String) {
+    match node.node_type.as_str() {
+        "Name" => out.push_str(attr(node, "id")),
+        "Constant" => write_constant(node, out),
+        "JoinedStr" => write_joined_str(node, out),
+        "Tuple" => {
+            let elts: Vec<_> = node.children_in("elts").collect();
+            let parens = elts.is_empty() || precedence > TUPLE;
+            delimit_if(parens, "(", ")", out, |out| items(&elts, out));
+        }
+        "List" => delimit("[", "]", out, |out| {
+            interleave(node.children_in("elts"), TEST, out);
+        }),
+        "Set" => {
+            if node.children.is_empty() {
+                out.push_str("{*()}");
+            } else {
+                delimit("{", "}", out, |out| {
+                    interleave(node.children_in("elts"), TEST, out);
+                });
+            }
+        }
+        "Dict" => write_dict(node, out),
+        "ListComp" | "SetComp" | "GeneratorExp" => {
+            let (open, close) = match node.node_type.as_str() {
+                "ListComp" => ("[", "]"),
+                "SetComp" => ("{", "}"),
+                _ => ("(", ")"),
+            };
+            delimit(open, close, out, |out| {
+                if let Some(elt) = child(node, "elt") {
+                    write(elt, TEST, out);
+                }
+                write_generators(node, out);
+            });
+        }
+        "DictComp" => delimit("{", "}", out, |out| {
+            if let (Some(key), Some(value)) = (child(node, "key"), child(node, "value")) {
+                write(key, TEST, out);
+                out.push_str(": ");
+                write(value, TEST, out);
+            }
+            write_generators(node, out);
+        }),
+        "BinOp" => write_binop(node, precedence, out),
+        "UnaryOp" => {
+            let (op, op_precedence) = match attr(node, "op") {
+                "Not" => ("not ", NOT),
+                "Invert" => ("~", FACTOR),
+                "UAdd" => ("+", FACTOR),
+                _ => ("-", FACTOR),
+            };
+            delimit_if(precedence > op_precedence, "(", ")", out, |out| {
+                out.push_str(op);
+                if let Some(operand) = child(node, "operand") {
+                    write(operand, op_precedence, out);
+                }
+            });
+        }
+        "BoolOp" => {
+            let (op, mut op_precedence) = if attr(node, "op") == "And" {
+                (" and ", AND)
+            } else {
+                (" or ", OR)
+            };
+            delimit_if(precedence > op_precedence, "(", ")", out, |out| {
+                for (i, value) in node.children_in("values").enumerate() {
+                    if i > 0 {
+                        out.push_str(op);
+                    }
+                    // Each operand binds one level tighter than the last
+                    op_precedence += 1;
+                    write(value, op_precedence, out);
+                }
+            });
+        }
+        "Compare" => delimit_if(precedence > CMP, "(", ")", out, |out| {
+            if let Some(left) = child(node, "left") {
+                write(left, CMP + 1, out);
+            }
+            let ops = attr(node, "ops").split(',');
+            for (op, comparator) in ops.zip(node.children_in("comparators")) {
+                out.push(' ');
+                out.push_str(comparison_operator(op));
+                out.push(' ');
+                write(comparator, CMP + 1, out);
+            }
+        }),
+        "Attribute" => {
+            if let Some(value) = child(node, "value") {
+                write(value, ATOM, out);
+                // `3.real` would lex as a float
+                if value.node_type == "Constant" && attr(value, "value_type") == "int" {
+                    out.push(' ');
+                }
+            }
+            out.push('.');
+            out.push_str(attr(node, "attr"));
+        }
+        "Call" => {
+            if let Some(func) = child(node, "func") {
+                write(func, ATOM, out);
+            }
+            delimit("(", ")", out, |out| {
+                let arguments = node.children_in("args").chain(node.children_in("keywords"));
+                interleave(arguments, TEST, out);
+            });
+        }
+        "keyword" => {
+            match node.attributes.get("arg") {
+                Some(arg) => {
+                    out.push_str(arg);
+                    out.push('=');
+                }
+                None => out.push_str("**"),
+            }
+            if let Some(value) = child(node, "value") {
+                write(value, TEST, out);
+            }
+        }
+        "Starred" => {
+            out.push('*');
+            if let Some(value) = child(node, "value") {
+                write(value, EXPR, out);
+            }
+        }
+        "Subscript" => {
+            if let Some(value) = child(node, "value") {
+                write(value, ATOM, out);
+            }
+            delimit("[", "]", out, |out| match child(node, "slice") {
+                Some(slice) if slice.node_type == "Tuple" && !slice.children.is_empty() => {
+                    items(&slice.children_in("elts").collect::<Vec<_>>(), out);
+                }
+                Some(slice) => write(slice, TEST, out),
+                None => {}
+            });
+        }
+        "Slice" => {
+            if let Some(lower) = child(node, "lower") {
+                write(lower, TEST, out);
+            }
+            out.push(':');
+            if let Some(upper) = child(node, "upper") {
+                write(upper, TEST, out);
+            }
+            if let Some(step) = child(node, "step") {
+                out.push(':');
+                write(step, TEST, out);
+            }
+        }
+        "IfExp" => delimit_if(precedence > TEST, "(", ")", out, |out| {
+            if let (Some(body), Some(test), Some(orelse)) = (
+                child(node, "body"),
+                child(node, "test"),
+                child(node, "orelse"),
+            ) {
+                write(body, TEST + 1, out);
+                out.push_str(" if ");
+                write(test, TEST + 1, out);
+                out.push_str(" else ");
+                write(orelse, TEST, out);
+            }
+        }),
+        "Lambda" => delimit_if(precedence > TEST, "(", ")", out, |out| {
+            out.push_str("lambda");
+            if let Some(args) = child(node, "args") {
+                let params = parameters(args);
+                if !params.is_empty() {
+                    out.push(' ');
+                    out.push_str(&params);
+                }
+            }
+            out.push_str(": ");
+            if let Some(body) = child(node, "body") {
+                write(body, TEST, out);
+            }
+        }),
+        "NamedExpr" => delimit_if(precedence > NAMED_EXPR, "(", ")", out, |out| {
+            if let (Some(target), Some(value)) = (child(node, "target"), child(node, "value")) {
+                write(target, ATOM, out);
+                out.push_str(" := ");
+                write(value, ATOM, out);
+            }
+        }),
+        "Yield" | "YieldFrom" => delimit_if(precedence > YIELD, "(", ")", out, |out| {
+            out.push_str(if node.node_type == "Yield" {
+                "yield"
+            } else {
+                "yield from"
+            });
+            if let Some(value) = child(node, "value") {
+                out.push(' ');
+                write(value, YIELD, out);
+            }
+        }),
+        "Await" => delimit_if(precedence > AWAIT, "(", ")", out, |out| {
+            out.push_str("await");
+            if let Some(value) = child(node, "value") {
+                out.push(' ');
+                write(value, ATOM, out);
+            }
+        }),
+        _ => {}
+    }
+}
+
+fn delimit(open: &str, close: &str, out: &mut String, body: impl FnOnce(&mut String)) {
+    delimit_if(true, open, close, out, body);
+}
+
+fn delimit_if(
+    condition: bool,
+    open: &str,
+    close: &str,
+    out: &mut String,
+    body: impl FnOnce(&mut String),
+) {
+    if condition {
+        out.push_str(open);
+    }
+    body(out);
+    if condition {
+        out.push_str(close);
+    }
+}
+
+fn interleave<'a>(nodes: impl Iterator<Item = &'a PythonAST>, precedence: u8, out: &mut String) {
+    for (i, node) in nodes.enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write(node, precedence, out);
+    }
+}
+
+/// Tuple items: a lone item keeps its trailing comma
+fn items(nodes: &[&PythonAST], out: &mut String) {
+    if let [single] = nodes {
+        write(single, TEST, out);
+        out.push(',');
+    } else {
+        interleave(nodes.iter().copied(), TEST, out);
+    }
+}
+
+fn write_constant(node: &PythonAST, out: &mut String) {
+    let value = attr(node, "value");
+    match attr(node, "value_type") {
+        "None" => out.push_str("None"),
+        "str" => out.push_str(&str_repr(value)),
+        "bytes" => out.push_str(&bytes_repr(value)),
+        "ellipsis" => out.push_str("..."),
+        "float" | "complex" => {
+            let infinity = format!("({INFINITY}-{INFINITY})");
+            out.push_str(&value.replace("inf", INFINITY).replace("nan", &infinity));
+        }
+        _ => out.push_str(value),
+    }
+}
+
+fn write_dict(node: &PythonAST, out: &mut String) {
+    // `**mapping` entries have no key; the `ast` backend drops the `None`
+    // keys, so they are assumed to come first
+    let keys: Vec<_> = node.children_in("keys").collect();
+    let values: Vec<_> = node.children_in("values").collect();
+    let unpacked = values.len().saturating_sub(keys.len());
+    delimit("{", "}", out, |out| {
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            match i.checked_sub(unpacked).map(|k| keys[k]) {
+                Some(key) => {
+                    write(key, TEST, out);
+                    out.push_str(": ");
+                    write(value, TEST, out);
+                }
+                None => {
+                    out.push_str("**");
+                    write(value, EXPR, out);
+                }
+            }
+        }
+    });
+}
+
+fn write_generators(node: &PythonAST, out: &mut String) {
+    for generator in node.children_in("generators") {
+        out.push_str(if attr(generator, "is_async") == "1" {
+            " async for "
+        } else {
+            " for "
+        });
+        if let Some(target) = child(generator, "target") {
+            write(target, TUPLE, out);
+        }
+        out.push_str(" in ");
+        if let Some(iter) = child(generator, "iter") {
+            write(iter, TEST + 1, out);
+        }
+        for condition in generator.children_in("ifs") {
+            out.push_str(" if ");
+            write(condition, TEST + 1, out);
+        }
+    }
+}
+
+fn write_binop(node: &PythonAST, precedence: u8, out: &mut String) {
+    let (op, op_precedence) = match attr(node, "op") {
+        "Add" => ("+", ARITH),
+        "Sub" => ("-", ARITH),
+        "Mult" => ("*", TERM),
+        "MatMult" => ("@", TERM),
+        "Div" => ("/", TERM),
+        "Mod" => ("%", TERM),
+        "FloorDiv" => ("//", TERM),
+        "LShift" => ("<<", SHIFT),
+        "RShift" => (">>", SHIFT),
+        "BitOr" => ("|", EXPR),
+        "BitXor" => ("^", BXOR),
+        "BitAnd" => ("&", BAND),
+        _ => ("**", POWER),
+    };
+    // `**` is right-associative, everything else left-associative
+    let (left_precedence, right_precedence) = if op == "**" {
+        (op_precedence + 1, op_precedence)
+    } else {
+        (op_precedence, op_precedence + 1)
+    };
+    delimit_if(precedence > op_precedence, "(", ")", out, |out| {
+        if let Some(left) = child(node, "left") {
+            write(left, left_precedence, out);
+        }
+        out.push(' ');
+        out.push_str(op);
+        out.push(' ');
+        if let Some(right) = child(node, "right") {
+            write(right, right_precedence, out);
+        }
+    });
+}
+
+fn comparison_operator(op: &str) -> &'static str {
+    match op {
+        "Eq" => "==",
+        "NotEq" => "!=",
+        "Lt" => "<",
+        "LtE" => "<=",
+        "Gt" => ">",
+        "GtE" => ">=",
+        "Is" => "is",
+        "IsNot" => "is not",
+        "In" => "in",
+        _ => "not in",
+    }
+}
+
+/// Parameter list of a lambda's `arguments` node
+fn parameters(arguments: &PythonAST) -> String {
+    let mut params = Vec::new();
+    let posonly = arguments.children_in("posonlyargs").count();
+    let positional = arguments
+        .children_in("posonlyargs")
+        .chain(arguments.children_in("args"));
+    for (i, arg) in positional.enumerate() {
+        params.push(parameter(arg, ""));
+        if i + 1 == posonly {
+            params.push("/".to_string());
+        }
+    }
+    match arguments.child("vararg") {
+        Some(vararg) => params.push(parameter(vararg, "*")),
+        None if arguments.child("kwonlyargs").is_some() => params.push("*".to_string()),
+        None => {}
+    }
+    params.extend(
+        arguments
+            .children_in("kwonlyargs")
+            .map(|arg| parameter(arg, "")),
+    );
+    if let Some(kwarg) = arguments.child("kwarg") {
+        params.push(parameter(kwarg, "**"));
+    }
+    params.join(", ")
+}
+
+fn parameter(arg: &PythonAST, prefix: &str) -> String {
+    let mut text = format!("{prefix}{}", attr(arg, "arg"));
+    if let Some(annotation) = arg.child("annotation") {
+        text.push_str(": ");
+        text.push_str(&unparse(annotation));
+    }
+    if let Some(default) = arg.attributes.get("default") {
+        text.push('=');
+        text.push_str(default);
+    }
+    text
+}
+
+fn write_joined_str(node: &PythonAST, out: &mut String) {
+    let mut inner = String::new();
+    write_fstring_inner(node, &mut inner);
+    let quote = if inner.contains('\'') && !inner.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    out.push('f');
+    out.push(quote);
+    out.push_str(&inner);
+    out.push(quote);
+}
+
+fn write_fstring_inner(node: &PythonAST, out: &mut String) {
+    match node.node_type.as_str() {
+        "JoinedStr" => {
+            for value in node.children_in("values") {
+                write_fstring_inner(value, out);
+            }
+        }
+        "Constant" => out.push_str(&attr(node, "value").replace('{', "{{").replace('}', "}}")),
+        "FormattedValue" => {
+            out.push('{');
+            if let Some(value) = child(node, "value") {
+                let mut expression = String::new();
+                write(value, TEST + 1, &mut expression);
+                if expression.starts_with('{') {
+                    out.push(' ');
+                }
+                out.push_str(&expression);
+            }
+            let conversion = attr(node, "conversion").parse::<u8>().ok().map(char::from);
+            if let Some(conversion) = conversion {
+                out.push('!');
+                out.push(conversion);
+            }
+            if let Some(spec) = child(node, "format_spec") {
+                out.push(':');
+                write_fstring_inner(spec, out);
+            }
+            out.push('}');
+        }
+        _ => {}
+    }
+}
+
+/// Python's `repr` of a string
+fn str_repr(value: &str) -> String {
+    let quote = if value.contains('\'') && !value.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push(quote);
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if is_printable(c) => out.push(c),
+            c => {
+                let code = u32::from(c);
+                let _ = if code <= 0xff {
+                    write!(out, "\\x{code:02x}")
+                } else if code <= 0xffff {
+                    write!(out, "\\u{code:04x}")
+                } else {
+                    write!(out, "\\U{code:08x}")
+                };
+            }
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Python's `repr` of bytes given as hex text
+fn bytes_repr(hex: &str) -> String {
+    let bytes: Vec<u8> = (0..hex.len() / 2)
+        .filter_map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok())
+        .collect();
+    let quote = if bytes.contains(&b'\'') && !bytes.contains(&b'"') {
+        b'"'
+    } else {
+        b'\''
+    };
+    let mut out = String::from("b");
+    out.push(char::from(quote));
+    for byte in bytes {
+        match byte {
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            byte if byte == quote => {
+                out.push('\\');
+                out.push(char::from(byte));
+            }
+            0x20..=0x7e => out.push(char::from(byte)),
+            byte => {
+                let _ = write!(out, "\\x{byte:02x}");
+            }
+        }
+    }
+    out.push(char::from(quote));
+    out
+}
+
+/// Approximation of `str.isprintable` for a single character
+fn is_printable(c: char) -> bool {
+    if c == ' ' {
+        return true;
+    }
+    !(c.is_control()
+        || c.is_whitespace()
+        || matches!(
+            c,
+            '\u{ad}'
+                | '\u{200b}'..='\u{200f}'
+                | '\u{2028}'..='\u{202e}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{feff}'
+                | '\u{e000}'..='\u{f8ff}'
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse;
+    use super::*;
+
+    fn roundtrip(source: &str) -> String {
+        let module = parse(&format!("x = {source}\n"), "test.py").unwrap();
+        unparse(module.child("body").unwrap().child("value").unwrap())
+    }
+
+    #[test]
+    fn test_unparse_matches_ast_unparse() {
+        assert_eq!(roundtrip("\"a\""), "'a'");
+        assert_eq!(roundtrip("0x10"), "16");
+        assert_eq!(roundtrip("-1"), "-1");
+        assert_eq!(roundtrip("(1, 2)"), "(1, 2)");
+        assert_eq!(roundtrip("1,"), "(1,)");
+        assert_eq!(roundtrip("(a + b) * c"), "(a + b) * c");
+        assert_eq!(roundtrip("a - (b - c)"), "a - (b - c)");
+        assert_eq!(roundtrip("2 ** -1"), "2 ** (-1)");
+        assert_eq!(roundtrip("{'k': [1.5]}"), "{'k': [1.5]}");
+        assert_eq!(roundtrip("f(x, *a, k=1, **kw)"), "f(x, *a, k=1, **kw)");
+        assert_eq!(roundtrip("lambda a, *b, c=1: a"), "lambda a, *b, c=1: a");
+        assert_eq!(roundtrip("not a or b and c"), "not a or (b and c)");
+        assert_eq!(roundtrip("x[1:2, ::3]"), "x[1:2, ::3]");
+    }
+
+    #[test]
+    fn test_reprs() {
+        assert_eq!(str_repr("it's"), "\"it's\"");
+        assert_eq!(str_repr("a\n'\""), "'a\\n\\'\"'");
+        assert_eq!(bytes_repr("00ff41"), "b'\\x00\\xffA'");
+    }
+}
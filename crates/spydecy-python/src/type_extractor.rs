@@ -5,10 +5,14 @@
 
 use crate::parser::PythonAST;
 use anyhow::Result;
-use spydecy_hir::types::Type;
+use spydecy_hir::types::{PythonType, Type};
 
 /// Extract type hints from Python AST
 ///
+/// Returns a `(name, type)` pair for every annotated parameter and annotated
+/// assignment target, plus each annotated function's return type keyed by
+/// the function name.
+///
 /// # Errors
 ///
 /// Returns an error if type hints cannot be extracted
@@ -16,18 +20,208 @@ pub fn extract_type_hints(ast: &PythonAST) -> Result<Vec<(String, Type)>> {
     let mut type_hints = Vec::new();
 
     // Walk the AST and extract type annotations
-    extract_type_hints_recursive(ast, &mut type_hints)?;
+    extract_type_hints_recursive(ast, &mut type_hints);
 
     Ok(type_hints)
 }
 
-#[allow(clippy::unnecessary_wraps)]
-fn extract_type_hints_recursive(
-    _ast: &PythonAST,
-    _type_hints: &mut Vec<(String, Type)>,
-) -> Result<()> {
-    // Implementation will come in Sprint 2
-    Ok(())
+fn extract_type_hints_recursive(ast: &PythonAST, type_hints: &mut Vec<(String, Type)>) {
+    match ast.node_type.as_str() {
+        "FunctionDef" | "AsyncFunctionDef" => {
+            if let (Some(name), Some(returns)) = (ast.attributes.get("name"), ast.child("returns"))
+            {
+                type_hints.push((name.clone(), annotation_to_type(returns)));
+            }
+        }
+        "arg" => {
+            if let (Some(name), Some(annotation)) =
+                (ast.attributes.get("arg"), ast.child("annotation"))
+            {
+                type_hints.push((name.clone(), annotation_to_type(annotation)));
+            }
+        }
+        "AnnAssign" => {
+            let target = ast.child("target").and_then(|t| t.attributes.get("id"));
+            if let (Some(name), Some(annotation)) = (target, ast.child("annotation")) {
+                type_hints.push((name.clone(), annotation_to_type(annotation)));
+            }
+        }
+        _ => {}
+    }
+
+    for child in &ast.children {
+        extract_type_hints_recursive(child, type_hints);
+    }
+}
+
+/// Convert an annotation expression into a HIR type
+///
+/// Handles builtin and `typing` names, subscripted generics (`list[int]`,
+/// `Dict[str, int]`, `Optional[T]`, `Union[A, B]`), PEP 604 unions
+/// (`int | None`) and string forward references. Unrecognised annotations
+/// map to [`Type::Unknown`].
+#[must_use]
+pub fn annotation_to_type(annotation: &PythonAST) -> Type {
+    match annotation.node_type.as_str() {
+        "Name" => annotation
+            .attributes
+            .get("id")
+            .map_or(Type::Unknown, |name| name_to_type(name)),
+        // `typing.List`, `t.Optional`, ...
+        "Attribute" => annotation
+            .attributes
+            .get("attr")
+            .map_or(Type::Unknown, |name| name_to_type(name)),
+        "Constant" => match annotation.attributes.get("value_type").map(String::as_str) {
+            Some("None") => Type::Python(PythonType::None),
+            Some("str") => annotation
+                .attributes
+                .get("value")
+                .map_or(Type::Unknown, |text| forward_reference_to_type(text)),
+            _ => Type::Unknown,
+        },
+        "Subscript" => subscript_to_type(annotation),
+        "BinOp" if annotation.attributes.get("op").map(String::as_str) == Some("BitOr") => {
+            let mut members = Vec::new();
+            flatten_union(annotation, &mut members);
+            make_union(members)
+        }
+        _ => Type::Unknown,
+    }
+}
+
+/// Convert a subscripted generic such as `list[int]` or `Optional[str]`
+fn subscript_to_type(annotation: &PythonAST) -> Type {
+    let Some(base) = annotation.child("value").map(annotation_to_type) else {
+        return Type::Unknown;
+    };
+    // `Callable[[A, B], R]`
+    if let Type::Function { .. } = base {
+        let mut parts = annotation
+            .child("slice")
+            .into_iter()
+            .flat_map(|slice| slice.children_in("elts"));
+        let params = parts.next().map_or_else(Vec::new, |params| {
+            params.children_in("elts").map(annotation_to_type).collect()
+        });
+        return Type::Function {
+            params,
+            return_type: Box::new(parts.next().map_or(Type::Unknown, annotation_to_type)),
+        };
+    }
+    let args: Vec<Type> = match annotation.child("slice") {
+        Some(slice) if slice.node_type == "Tuple" => {
+            slice.children_in("elts").map(annotation_to_type).collect()
+        }
+        Some(slice) => vec![annotation_to_type(slice)],
+        None => vec![],
+    };
+    let arg = |i: usize| Box::new(args.get(i).cloned().unwrap_or(Type::Unknown));
+
+    let Type::Python(base) = base else {
+        return Type::Unknown;
+    };
+    Type::Python(match base {
+        PythonType::List(_) => PythonType::List(arg(0)),
+        PythonType::Set(_) => PythonType::Set(arg(0)),
+        PythonType::Dict { .. } => PythonType::Dict {
+            key: arg(0),
+            value: arg(1),
+        },
+        // `tuple[int, ...]` is a variable-length homogeneous sequence
+        PythonType::Tuple(_) if is_variadic_tuple(annotation) => PythonType::List(arg(0)),
+        PythonType::Tuple(_) => PythonType::Tuple(args),
+        PythonType::Optional(_) => PythonType::Optional(arg(0)),
+        // `Generator[Yield, Send, Return]` is an iterator over its yields
+        PythonType::Iterator(_) => PythonType::Iterator(arg(0)),
+        PythonType::Union(_) => return make_union(args),
+        // User generics (`Box[int]`) keep their class name
+        other => other,
+    })
+}
+
+/// Whether a `tuple[...]` subscript ends in `...`
+fn is_variadic_tuple(annotation: &PythonAST) -> bool {
+    annotation
+        .child("slice")
+        .and_then(|slice| slice.children_in("elts").last())
+        .is_some_and(|last| {
+            last.attributes.get("value_type").map(String::as_str) == Some("ellipsis")
+        })
+}
+
+/// Collect the members of an `A | B | C` union
+fn flatten_union(annotation: &PythonAST, members: &mut Vec<Type>) {
+    if annotation.node_type == "BinOp"
+        && annotation.attributes.get("op").map(String::as_str) == Some("BitOr")
+    {
+        for side in ["left", "right"] {
+            if let Some(operand) = annotation.child(side) {
+                flatten_union(operand, members);
+            }
+        }
+    } else {
+        members.push(annotation_to_type(annotation));
+    }
+}
+
+/// Build a union type, collapsing `T | None` into `Optional[T]`
+fn make_union(members: Vec<Type>) -> Type {
+    let none = Type::Python(PythonType::None);
+    let has_none = members.contains(&none);
+    let mut rest: Vec<Type> = members.into_iter().filter(|t| *t != none).collect();
+
+    let inner = match rest.len() {
+        0 => return none,
+        1 => rest.remove(0),
+        _ => Type::Python(PythonType::Union(rest)),
+    };
+    if has_none {
+        Type::Python(PythonType::Optional(Box::new(inner)))
+    } else {
+        inner
+    }
+}
+
+/// Resolve a string annotation (`"Point"`, `"list[Node]"`)
+fn forward_reference_to_type(text: &str) -> Type {
+    crate::parser::parse(text, "<annotation>")
+        .ok()
+        .and_then(|module| {
+            let statement = module.child("body")?;
+            statement.child("value").map(annotation_to_type)
+        })
+        .unwrap_or(Type::Unknown)
+}
+
+/// Map a bare type name onto a HIR type
+fn name_to_type(name: &str) -> Type {
+    let unknown = || Box::new(Type::Unknown);
+    if name == "Callable" {
+        return Type::Function {
+            params: vec![],
+            return_type: unknown(),
+        };
+    }
+    Type::Python(match name {
+        "int" => PythonType::Int,
+        "float" => PythonType::Float,
+        "str" => PythonType::Str,
+        "bool" => PythonType::Bool,
+        "None" | "NoneType" => PythonType::None,
+        "Any" | "object" => PythonType::Any,
+        "list" | "List" | "Sequence" | "MutableSequence" => PythonType::List(unknown()),
+        "dict" | "Dict" | "Mapping" | "MutableMapping" => PythonType::Dict {
+            key: unknown(),
+            value: unknown(),
+        },
+        "tuple" | "Tuple" => PythonType::Tuple(vec![]),
+        "set" | "Set" | "frozenset" | "FrozenSet" => PythonType::Set(unknown()),
+        "Optional" => PythonType::Optional(unknown()),
+        "Iterator" | "Iterable" | "Generator" => PythonType::Iterator(unknown()),
+        "Union" => PythonType::Union(vec![]),
+        _ => PythonType::Class(name.to_string()),
+    })
 }
 
 #[cfg(test)]
@@ -40,4 +234,76 @@ mod tests {
         let result = extract_type_hints(&ast);
         assert!(result.is_ok());
     }
+
+    fn hint_types(source: &str) -> Vec<(String, String)> {
+        let ast = crate::parser::parse(source, "test.py").unwrap();
+        extract_type_hints(&ast)
+            .unwrap()
+            .into_iter()
+            .map(|(name, ty)| (name, ty.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_generic_type_hints() {
+        let hints = hint_types(
+            r"
+from typing import Dict, List, Optional, Union
+
+def f(a: int, b: list[int], c: List[str], d: dict[str, int], e: Dict[str, List[int]]) -> None:
+    pass
+
+def g(a: Optional[int], b: int | None, c: Union[int, str], d: tuple[int, str], e: set[float]):
+    pass
+
+def h(a: tuple[int, ...], b: 'Node', c: Node):
+    total: float = 0.0
+
+def i(a: Iterable[str]) -> Generator[int, None, None]:
+    yield 1
+
+def j(key: Callable[[int, str], bool]):
+    pass
+",
+        );
+        let expected = [
+            ("f", "None"),
+            ("a", "int"),
+            ("b", "list[int]"),
+            ("c", "list[str]"),
+            ("d", "dict[str, int]"),
+            ("e", "dict[str, list[int]]"),
+            ("a", "Optional[int]"),
+            ("b", "Optional[int]"),
+            ("c", "int | str"),
+            ("d", "tuple[int, str]"),
+            ("e", "set[float]"),
+            ("a", "list[int]"),
+            ("b", "Node"),
+            ("c", "Node"),
+            ("total", "float"),
+            ("i", "Iterator[int]"),
+            ("a", "Iterator[str]"),
+            ("key", "fn(int, str) -> bool"),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(name, ty)| ((*name).to_string(), (*ty).to_string()))
+            .collect();
+        assert_eq!(hints, expected);
+    }
+
+    #[test]
+    fn test_annotation_to_type() {
+        let mut name = PythonAST::new("Name".to_string());
+        name.attributes.insert("id".to_string(), "int".to_string());
+        assert_eq!(annotation_to_type(&name), Type::Python(PythonType::Int));
+
+        name.attributes
+            .insert("id".to_string(), "Point".to_string());
+        assert_eq!(
+            annotation_to_type(&name),
+            Type::Python(PythonType::Class("Point".to_string()))
+        );
+    }
 }
@@ -0,0 +1,336 @@
+//! Target Python version
+//!
+//! Source is parsed by whatever interpreter PyO3 links against (or by the
+//! native parser, which follows 3.11), and both produce the same
+//! [`PythonAST`] schema whatever version did the parsing. The *target* is
+//! the version the code is written for: [`check_syntax`] rejects
+//! constructs that are newer than it, so code relying on 3.12 syntax is not
+//! silently accepted for a 3.9 project.
+//!
+//! Only constructs visible in the AST are checked. Parenthesized context
+//! managers or nested f-string quotes, for example, parse to the same nodes
+//! as their older spellings.
+
+use crate::parser::PythonAST;
+use anyhow::{bail, Context, Result};
+use std::{fmt, str::FromStr};
+
+/// Python 3 release, ignoring the patch level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PythonVersion {
+    /// Major version, always 3
+    pub major: u8,
+    /// Minor version
+    pub minor: u8,
+}
+
+impl PythonVersion {
+    /// Environment variable naming the target used by [`crate::parser::parse`]
+    pub const ENV_VAR: &'static str = "SPYDECY_PYTHON_TARGET";
+
+    /// Oldest supported target
+    pub const OLDEST: Self = Self::new(3, 7);
+
+    /// Newest supported target
+    pub const NEWEST: Self = Self::new(3, 13);
+
+    /// Create a version
+    #[must_use]
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// Version named by [`Self::ENV_VAR`], or the default when it is unset
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable is not a supported version
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(Self::ENV_VAR) {
+            Ok(version) if !version.is_empty() => version
+                .parse()
+                .with_context(|| format!("Invalid {}", Self::ENV_VAR)),
+            _ => Ok(Self::default()),
+        }
+    }
+}
+
+impl Default for PythonVersion {
+    /// 3.11, the grammar the native parser follows
+    fn default() -> Self {
+        Self::new(3, 11)
+    }
+}
+
+impl FromStr for PythonVersion {
+    type Err = anyhow::Error;
+
+    /// Parse `3.11`; a patch level (`3.11.4`) is accepted and ignored
+    fn from_str(text: &str) -> Result<Self> {
+        let mut parts = text.trim().split('.');
+        let (Some(major), Some(minor)) = (parts.next(), parts.next()) else {
+            bail!("Invalid Python version `{text}` (expected e.g. `3.11`)");
+        };
+        let (Ok(major), Ok(minor)) = (major.parse(), minor.parse()) else {
+            bail!("Invalid Python version `{text}` (expected e.g. `3.11`)");
+        };
+        if parts
+            .next()
+            .is_some_and(|patch| patch.parse::<u8>().is_err())
+        {
+            bail!("Invalid Python version `{text}` (expected e.g. `3.11`)");
+        }
+        let version = Self::new(major, minor);
+        if !(Self::OLDEST..=Self::NEWEST).contains(&version) {
+            bail!(
+                "Python {version} is not a supported target (expected {} to {})",
+                Self::OLDEST,
+                Self::NEWEST
+            );
+        }
+        Ok(version)
+    }
+}
+
+impl fmt::Display for PythonVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Construct that needs a newer Python than the target
+struct Newer<'a> {
+    node: &'a PythonAST,
+    description: &'static str,
+    since: PythonVersion,
+}
+
+/// Reject syntax that is newer than the target version
+///
+/// # Errors
+///
+/// Returns an error naming the first construct (in source order) that the
+/// target cannot run, with its location
+pub fn check_syntax(module: &PythonAST, target: PythonVersion) -> Result<()> {
+    let walk = Walk {
+        target,
+        postponed_annotations: has_future_annotations(module),
+    };
+    let Some(newer) = walk.node(module, false) else {
+        return Ok(());
+    };
+    let location = match (newer.node.lineno, newer.node.col_offset) {
+        (Some(line), Some(col)) => format!("line {line}, column {}", col + 1),
+        _ => "unknown location".to_string(),
+    };
+    let filename = module
+        .attributes
+        .get("filename")
+        .map_or("<unknown>", String::as_str);
+    bail!(
+        "{} require Python {} or newer, but the target is Python {target} ({location} in {filename})",
+        newer.description,
+        newer.since
+    )
+}
+
+/// `from __future__ import annotations`, which stops annotations from
+/// being evaluated and so allows newer typing syntax in them
+fn has_future_annotations(module: &PythonAST) -> bool {
+    module.children_in("body").any(|statement| {
+        statement.node_type == "ImportFrom"
+            && statement.attributes.get("module").map(String::as_str) == Some("__future__")
+            && statement.children_in("names").any(|alias| {
+                alias.attributes.get("name").map(String::as_str) == Some("annotations")
+            })
+    })
+}
+
+struct Walk {
+    target: PythonVersion,
+    postponed_annotations: bool,
+}
+
+impl Walk {
+    /// First construct in `node` or below that is newer than the target
+    fn node<'a>(&self, node: &'a PythonAST, in_annotation: bool) -> Option<Newer<'a>> {
+        if let Some(newer) = self.construct(node, in_annotation) {
+            return Some(newer);
+        }
+        node.children.iter().find_map(|child| {
+            let annotation = matches!(child.field.as_deref(), Some("annotation" | "returns"));
+            self.node(child, in_annotation || annotation)
+        })
+    }
+
+    /// `node` itself, if it is newer than the target
+    fn construct<'a>(&self, node: &'a PythonAST, in_annotation: bool) -> Option<Newer<'a>> {
+        let typing = in_annotation && !self.postponed_annotations;
+        let (culprit, description, since) = match node.node_type.as_str() {
+            "NamedExpr" => (node, "assignment expressions (`:=`)", (3, 8)),
+            "arguments" => (
+                node.children_in("posonlyargs").next()?,
+                "positional-only parameters",
+                (3, 8),
+            ),
+            "FunctionDef" | "AsyncFunctionDef" | "ClassDef" => {
+                if let Some(params) = node.children_in("type_params").next() {
+                    (params, "type parameter lists", (3, 12))
+                } else {
+                    let decorator = node
+                        .children_in("decorator_list")
+                        .find(|decorator| !is_classic_decorator(decorator))?;
+                    (decorator, "arbitrary decorator expressions", (3, 9))
+                }
+            }
+            "Subscript" if has_starred_slice(node) => {
+                (node, "starred expressions in subscripts", (3, 11))
+            }
+            "Subscript" if typing && is_builtin_generic(node) => {
+                (node, "built-in generic types in annotations", (3, 9))
+            }
+            "Match" => (node, "`match` statements", (3, 10)),
+            "BinOp" if typing && node.attributes.get("op").map(String::as_str) == Some("BitOr") => {
+                (node, "`X | Y` union types in annotations", (3, 10))
+            }
+            "TryStar" => (node, "`except*` clauses", (3, 11)),
+            "arg" => (
+                node.child("annotation")
+                    .filter(|annotation| annotation.node_type == "Starred")?,
+                "starred annotations on `*args`",
+                (3, 11),
+            ),
+            "TypeAlias" => (node, "`type` statements", (3, 12)),
+            "TypeVar" | "ParamSpec" | "TypeVarTuple" => (
+                node.child("default_value")?,
+                "type parameter defaults",
+                (3, 13),
+            ),
+            _ => return None,
+        };
+        let since = PythonVersion::new(since.0, since.1);
+        (since > self.target).then_some(Newer {
+            node: culprit,
+            description,
+            since,
+        })
+    }
+}
+
+/// Whether a decorator fits the pre-3.9 grammar: a dotted name, optionally
+/// called
+fn is_classic_decorator(decorator: &PythonAST) -> bool {
+    let callee = if decorator.node_type == "Call" {
+        match decorator.child("func") {
+            Some(func) => func,
+            None => return false,
+        }
+    } else {
+        decorator
+    };
+    is_dotted_name(callee)
+}
+
+fn is_dotted_name(node: &PythonAST) -> bool {
+    match node.node_type.as_str() {
+        "Name" => true,
+        "Attribute" => node.child("value").is_some_and(is_dotted_name),
+        _ => false,
+    }
+}
+
+/// `list[int]` and friends (PEP 585)
+fn is_builtin_generic(subscript: &PythonAST) -> bool {
+    subscript.child("value").is_some_and(|value| {
+        value.node_type == "Name"
+            && matches!(
+                value.attributes.get("id").map(String::as_str),
+                Some("list" | "dict" | "set" | "frozenset" | "tuple" | "type")
+            )
+    })
+}
+
+/// `a[*b]` or `a[x, *b]` (PEP 646)
+fn has_starred_slice(subscript: &PythonAST) -> bool {
+    subscript.child("slice").is_some_and(|slice| {
+        slice.node_type == "Starred"
+            || (slice.node_type == "Tuple"
+                && slice
+                    .children_in("elts")
+                    .any(|elt| elt.node_type == "Starred"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_with, ParseOptions};
+
+    fn check(source: &str, target: &str) -> Result<()> {
+        let ast = parse_with(
+            ParseOptions::default().with_target(PythonVersion::NEWEST),
+            source,
+            "test.py",
+        )
+        .unwrap();
+        check_syntax(&ast, target.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!(
+            "3.9".parse::<PythonVersion>().unwrap(),
+            PythonVersion::new(3, 9)
+        );
+        assert_eq!(
+            "3.10.4".parse::<PythonVersion>().unwrap(),
+            PythonVersion::new(3, 10)
+        );
+        assert!("3".parse::<PythonVersion>().is_err());
+        assert!("3.x".parse::<PythonVersion>().is_err());
+        let error = "2.7".parse::<PythonVersion>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Python 2.7 is not a supported target (expected 3.7 to 3.13)"
+        );
+        assert!(PythonVersion::new(3, 9) < PythonVersion::new(3, 10));
+    }
+
+    #[test]
+    fn test_match_needs_3_10() {
+        let source = "def f(x):\n    match x:\n        case _:\n            pass\n";
+        assert!(check(source, "3.10").is_ok());
+        let error = check(source, "3.9").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`match` statements require Python 3.10 or newer, but the target is Python 3.9 \
+             (line 2, column 5 in test.py)"
+        );
+    }
+
+    #[test]
+    fn test_union_annotations_need_3_10_unless_postponed() {
+        let source = "def f(x: int | None) -> list[int]:\n    return [x or 0]\n";
+        let error = check(source, "3.9").unwrap_err();
+        assert!(
+            error.to_string().starts_with("`X | Y` union types"),
+            "{error}"
+        );
+        assert!(check(
+            &format!("from __future__ import annotations\n{source}"),
+            "3.8"
+        )
+        .is_ok());
+        assert!(check("y = 1 | 2\n", "3.7").is_ok());
+    }
+
+    #[test]
+    fn test_older_features() {
+        assert!(check("if (n := 1):\n    pass\n", "3.7").is_err());
+        assert!(check("def f(a, /):\n    pass\n", "3.7").is_err());
+        assert!(check("@buttons[0].clicked.connect\ndef f():\n    pass\n", "3.8").is_err());
+        assert!(check("@app.route('/')\ndef f():\n    pass\n", "3.7").is_ok());
+        assert!(check("x = a[*b]\n", "3.10").is_err());
+        assert!(check("try:\n    pass\nexcept* ValueError:\n    pass\n", "3.10").is_err());
+    }
+}
@@ -6,7 +6,9 @@
 #![deny(unsafe_code)]
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use spydecy_c::{config::CParserConfig, hir_converter::Conversion};
+use spydecy_hir::diagnostics::Diagnostic;
 use std::path::{Path, PathBuf};
 
 /// Spydecy CLI
@@ -40,6 +42,10 @@ enum Commands {
         /// Enable verbose output
         #[arg(short, long)]
         verbose: bool,
+
+        /// Flags the C source is parsed with
+        #[command(flatten)]
+        c_flags: CFlags,
     },
 
     /// Start interactive debugger
@@ -60,6 +66,10 @@ enum DebugMode {
     Visualize {
         /// Source file to visualize
         file: PathBuf,
+
+        /// Flags a C source is parsed with
+        #[command(flatten)]
+        c_flags: CFlags,
     },
 
     /// Step through transpilation interactively
@@ -71,9 +81,49 @@ enum DebugMode {
         /// C source file
         #[arg(long)]
         c: PathBuf,
+
+        /// Flags the C source is parsed with
+        #[command(flatten)]
+        c_flags: CFlags,
     },
 }
 
+/// Clang flags for parsing C sources
+#[derive(Args, Debug, Default)]
+struct CFlags {
+    /// Add a directory to the C include search path
+    #[arg(short = 'I', long = "include-dir", value_name = "DIR")]
+    include_dirs: Vec<PathBuf>,
+
+    /// Define a C macro
+    #[arg(short = 'D', long = "define", value_name = "NAME[=VALUE]")]
+    defines: Vec<String>,
+
+    /// C language standard, e.g. c11
+    #[arg(long)]
+    std: Option<String>,
+
+    /// Target triple to parse C for
+    #[arg(long)]
+    target: Option<String>,
+
+    /// Compilation database to take the C source's flags from
+    #[arg(long, value_name = "FILE")]
+    compile_commands: Option<PathBuf>,
+}
+
+impl From<CFlags> for CParserConfig {
+    fn from(flags: CFlags) -> Self {
+        Self {
+            include_dirs: flags.include_dirs,
+            defines: flags.defines,
+            std: flags.std,
+            target: flags.target,
+            compile_commands: flags.compile_commands,
+        }
+    }
+}
+
 fn main() {
     // Initialize tracing
     tracing_subscriber::fmt()
@@ -91,10 +141,13 @@ fn main() {
             c,
             output,
             verbose,
-        } => compile_command(&python, &c, &output, verbose),
+            c_flags,
+        } => compile_command(&python, &c, &output, verbose, &c_flags.into()),
         Commands::Debug { mode } => match mode {
-            DebugMode::Visualize { file } => debug_visualize_command(&file),
-            DebugMode::Step { python, c } => debug_step_command(python, c),
+            DebugMode::Visualize { file, c_flags } => {
+                debug_visualize_command(&file, &c_flags.into())
+            }
+            DebugMode::Step { python, c, c_flags } => debug_step_command(python, c, c_flags.into()),
         },
         Commands::Info => {
             info_command();
@@ -138,10 +191,11 @@ fn extract_c_function(c_hir_module: spydecy_hir::c::CHIR) -> Result<spydecy_hir:
     use spydecy_hir::c::CHIR;
 
     if let CHIR::TranslationUnit { declarations, .. } = c_hir_module {
+        // Type declarations come before the functions using them
         declarations
-            .first()
-            .context("C file has no declarations")
-            .cloned()
+            .into_iter()
+            .find(|declaration| matches!(declaration, CHIR::Function { .. }))
+            .context("C file has no functions")
     } else {
         anyhow::bail!("Expected C TranslationUnit")
     }
@@ -158,14 +212,16 @@ fn parse_python_file(path: &Path) -> Result<spydecy_hir::python::PythonHIR> {
         .context("Failed to parse Python source")
 }
 
-/// Parse C file to HIR
-fn parse_c_file(path: &Path) -> Result<spydecy_hir::c::CHIR> {
-    use spydecy_c::parse_c;
+/// Parse C file to HIR, along with clang's diagnostics and the skipped
+/// constructs
+fn parse_c_file(path: &Path, config: &CParserConfig) -> Result<Conversion> {
+    use spydecy_c::parse_c_with_diagnostics;
 
     let c_source = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read C file: {}", path.display()))?;
 
-    parse_c(&c_source, path.to_str().unwrap_or("input.c")).context("Failed to parse C source")
+    parse_c_with_diagnostics(config, &c_source, path.to_str().unwrap_or("input.c"))
+        .context("Failed to parse C source")
 }
 
 /// Helper for verbose logging
@@ -197,6 +253,12 @@ impl VerboseLogger {
         self.log(&format!("   ✅ {msg}"));
     }
 
+    fn diagnostic(&self, diagnostic: &Diagnostic) {
+        for line in diagnostic.to_string().lines() {
+            self.log(&format!("   {line}"));
+        }
+    }
+
     fn input(&self, path: &Path) {
         self.log(&format!("   Input: {}", path.display()));
     }
@@ -213,9 +275,15 @@ impl VerboseLogger {
 }
 
 /// Compile Python + C to Rust using the full pipeline
-fn compile_command(python: &Path, c: &Path, output: &Path, verbose: bool) -> Result<()> {
+fn compile_command(
+    python: &Path,
+    c: &Path,
+    output: &Path,
+    verbose: bool,
+    c_config: &CParserConfig,
+) -> Result<()> {
     use spydecy_codegen::generate_rust;
-    use spydecy_hir::unified::Unifier;
+    use spydecy_hir::{c::CTypes, unified::Unifier};
     use spydecy_optimizer::OptimizationPipeline;
 
     let log = VerboseLogger::new(verbose);
@@ -233,8 +301,12 @@ fn compile_command(python: &Path, c: &Path, output: &Path, verbose: bool) -> Res
     log.step(2, "Parsing C source...");
     log.input(c);
 
-    let c_hir_module = parse_c_file(c)?;
-    let c_hir = extract_c_function(c_hir_module)?;
+    let conversion = parse_c_file(c, c_config)?;
+    for diagnostic in &conversion.diagnostics {
+        log.diagnostic(diagnostic);
+    }
+    let c_types = CTypes::new(&conversion.hir);
+    let c_hir = extract_c_function(conversion.hir)?;
 
     log.success("C HIR created");
 
@@ -242,7 +314,7 @@ fn compile_command(python: &Path, c: &Path, output: &Path, verbose: bool) -> Res
     log.step(3, "Unifying Python + C...");
 
     let python_call = extract_python_call(python_hir)?;
-    let mut unifier = Unifier::new();
+    let mut unifier = Unifier::new().with_c_types(c_types);
     let unified_hir = unifier
         .unify(&python_call, &c_hir)
         .context("Failed to unify Python and C")?;
@@ -290,7 +362,7 @@ fn compile_command(python: &Path, c: &Path, output: &Path, verbose: bool) -> Res
 }
 
 /// Debug visualize command - visualize AST
-fn debug_visualize_command(file: &Path) -> Result<()> {
+fn debug_visualize_command(file: &Path, c_config: &CParserConfig) -> Result<()> {
     tracing::info!("Visualizing: {}", file.display());
 
     // Determine file type by extension
@@ -304,7 +376,8 @@ fn debug_visualize_command(file: &Path) -> Result<()> {
         }
         "c" | "h" => {
             // Visualize C AST with CPython annotations
-            spydecy_debugger::visualize_c_ast(file).context("Failed to visualize C AST")?
+            spydecy_debugger::visualize_c_ast(file, c_config)
+                .context("Failed to visualize C AST")?
         }
         _ => {
             anyhow::bail!("Unsupported file extension: '{extension}'. Supported: .py, .c, .h");
@@ -316,7 +389,7 @@ fn debug_visualize_command(file: &Path) -> Result<()> {
 }
 
 /// Debug step command - interactive step-through debugging
-fn debug_step_command(python: PathBuf, c: PathBuf) -> Result<()> {
+fn debug_step_command(python: PathBuf, c: PathBuf, c_config: CParserConfig) -> Result<()> {
     tracing::info!(
         "Starting interactive debugger: {} + {}",
         python.display(),
@@ -327,7 +400,7 @@ fn debug_step_command(python: PathBuf, c: PathBuf) -> Result<()> {
     println!("   Python: {}", python.display());
     println!("   C:      {}", c.display());
 
-    spydecy_debugger::start_interactive_debugger(python, c)
+    spydecy_debugger::start_interactive_debugger(python, c, c_config)
 }
 
 /// Info command - display project status
@@ -398,4 +471,39 @@ mod tests {
         let cli = Cli::parse_from(["spydecy", "info"]);
         assert!(matches!(cli.command, Commands::Info));
     }
+
+    #[test]
+    fn test_c_flags_parsing() {
+        let cli = Cli::parse_from([
+            "spydecy",
+            "compile",
+            "--python",
+            "list.py",
+            "--c",
+            "listobject.c",
+            "-o",
+            "list.rs",
+            "-I",
+            "Include",
+            "-IInclude/internal",
+            "-DPy_BUILD_CORE",
+            "--std",
+            "c11",
+            "--compile-commands",
+            "build/compile_commands.json",
+        ]);
+        let config = match cli.command {
+            Commands::Compile { c_flags, .. } => CParserConfig::from(c_flags),
+            _ => CParserConfig::default(),
+        };
+        assert_eq!(
+            config,
+            CParserConfig::default()
+                .with_include_dir("Include")
+                .with_include_dir("Include/internal")
+                .with_define("Py_BUILD_CORE")
+                .with_std("c11")
+                .with_compile_commands("build/compile_commands.json")
+        );
+    }
 }
@@ -0,0 +1,34 @@
+//! Helpers shared by the end-to-end tests
+
+use std::process::Command;
+
+/// Check that generated Rust code compiles, failing with rustc's errors
+///
+/// The code is built as a library and only type-checked, so it needs no
+/// `main` and produces no binary.
+pub fn assert_compiles(rust_code: &str) {
+    let dir = tempfile::tempdir().expect("Should create a temporary directory");
+    let source = dir.path().join("generated.rs");
+    std::fs::write(&source, rust_code).expect("Should write the generated code");
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let output = Command::new(rustc)
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "lib",
+            "--emit=metadata",
+        ])
+        .arg("--out-dir")
+        .arg(dir.path())
+        .arg(&source)
+        .output()
+        .expect("Should run rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,205 @@
+//! End-to-end class lowering
+//!
+//! Python classes have no C counterpart: they are lowered straight from
+//! Python HIR into a Rust `struct` plus an `impl` block.
+//!
+//! Parse → Lower → Generate
+
+mod common;
+
+use common::assert_compiles;
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_class_becomes_struct_and_impl() {
+    let python_source = r#"
+class ShoppingCart:
+    """A cart of items"""
+
+    def __init__(self, owner: str):
+        self.owner = owner
+        self.items: list[str] = []
+
+    def add(self, item: str) -> None:
+        self.items.append(item)
+
+    def size(self) -> int:
+        return len(self.items)
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower class");
+
+    assert!(
+        rust_code.contains("pub struct ShoppingCart {"),
+        "Should generate a struct. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub owner: String,") && rust_code.contains("pub items: Vec<String>,"),
+        "Fields should come from __init__ assignments. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("impl ShoppingCart {"),
+        "Should generate an impl block. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub fn new(owner: String) -> Self {")
+            && rust_code.contains("Self { owner, items }"),
+        "__init__ should become a constructor. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub fn add(&mut self, item: String) {")
+            && rust_code.contains("self.items.push(item);"),
+        "A mutating method should take &mut self. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub fn size(&self) -> i64 {")
+            && rust_code.contains("return self.items.len() as i64;"),
+        "A read-only method should take &self. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_mutation_through_helper_method() {
+    let python_source = r#"
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+    def bump(self):
+        self.count += 1
+
+    def bump_twice(self):
+        self.bump()
+        self.bump()
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower class");
+
+    assert!(
+        rust_code.contains("pub fn bump_twice(&mut self) {"),
+        "Calling a mutating method should make the caller mutate too. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_docstrings_become_doc_comments() {
+    let python_source = r#""""Inventory tracking."""
+
+class Stock:
+    """Units on hand.
+
+    Never negative.
+    """
+
+    def __init__(self):
+        self.units = 0
+
+    def restock(self, amount: int) -> None:
+        """Add delivered units."""
+        self.units += amount
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower class");
+
+    assert!(
+        rust_code.contains("//! Inventory tracking.\n#![allow(dead_code)]"),
+        "Module docstring should become an inner doc comment. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("/// Units on hand.\n///\n/// Never negative.\npub struct Stock {"),
+        "Class docstring should document the struct. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code
+            .contains("    /// Add delivered units.\n    pub fn restock(&mut self, amount: i64) {"),
+        "Method docstring should document the method. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_instances_are_built_with_new() {
+    let python_source = r#"
+class Inventory:
+    def __init__(self, name: str):
+        self.name = name
+        self.items: dict[str, int] = {}
+
+    def add(self, item: str, qty: int) -> None:
+        self.items[item] = qty
+
+    def label(self) -> str:
+        return self.name + "!"
+
+    def has(self, item: str) -> bool:
+        return item in self.items
+
+    def lacks(self, item: str) -> bool:
+        return item not in self.items
+
+    def size(self) -> int:
+        return len(self.items)
+
+
+class Tally:
+    def reset(self) -> None:
+        self.total = 0
+
+
+def stock(name: str, item: str) -> int:
+    inventory = Inventory(name)
+    inventory.add(item, 3)
+    tally = Tally()
+    tally.reset()
+    return inventory.size()
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower classes");
+
+    assert!(
+        rust_code.contains("let mut inventory = Inventory::new(name);")
+            && rust_code.contains("let mut tally = Tally::new();"),
+        "Calling a class should call its constructor. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub fn new() -> Self {"),
+        "A class without __init__ should still get a constructor. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("self.items.insert(item, qty);"),
+        "Storing into a dict field should insert. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("return self.name.clone() + \"!\";"),
+        "Concatenating onto a field should not move out of self. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("return self.items.contains_key(&item);")
+            && rust_code.contains("return !self.items.contains_key(&item);"),
+        "Membership tests should become contains_key. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
@@ -0,0 +1,196 @@
+//! End-to-end lambdas and nested functions
+//!
+//! Lambdas and nested functions become Rust closures. Capture analysis
+//! decides between borrowing `Fn` closures, `FnMut` closures for those
+//! mutating a captured variable, and `move` closures for those returned
+//! by the enclosing function.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_sorted_with_key_lambda() {
+    let python_source = r"
+def by_score(rows: list[tuple[str, int]]) -> list[tuple[str, int]]:
+    return sorted(rows, key=lambda row: row[1])
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower sorted key");
+
+    assert!(
+        rust_code.contains("let mut sorted = rows.clone();")
+            && rust_code.contains("sorted.sort_by_key(|row| row.1);"),
+        "sorted() should sort a copy by the key. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_sorted_by_string_key_clones_it() {
+    let python_source = r"
+def by_name(rows: list[tuple[str, int]]) -> list[tuple[str, int]]:
+    return sorted(rows, key=lambda row: row[0])
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower sorted key");
+
+    assert!(
+        rust_code.contains("sorted.sort_by_key(|row| row.0.clone());"),
+        "A key that is not Copy should be cloned out of the item. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_nested_helper_borrows_captures() {
+    let python_source = r"
+def scaled(values: list[int], factor: int) -> list[int]:
+    def scale(x: int) -> int:
+        return x * factor
+
+    return [scale(v) for v in values]
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower nested function");
+
+    assert!(
+        rust_code.contains("let scale = |x: i64| x * factor;"),
+        "A reading helper should be a plain closure. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_nonlocal_counter_is_fn_mut() {
+    let python_source = r"
+def count_evens(values: list[int]) -> int:
+    count = 0
+
+    def visit(x: int):
+        nonlocal count
+        if x % 2 == 0:
+            count += 1
+
+    for v in values:
+        visit(v)
+    return count
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower nonlocal counter");
+
+    assert!(
+        rust_code.contains("let mut count = 0;")
+            && !rust_code.contains("let mut count = count;")
+            && rust_code.contains("let mut visit = |x: i64| {")
+            && rust_code.contains("count += 1;"),
+        "Mutating a capture should make an FnMut closure. Got: {}",
+        rust_code
+    );
+    assert!(
+        !rust_code.contains("nonlocal"),
+        "The declaration should be dropped. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_returned_lambda_moves_captures() {
+    let python_source = r"
+def make_adder(n: int) -> Callable[[int], int]:
+    return lambda x: x + n
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower returned lambda");
+
+    assert!(
+        rust_code.contains("pub fn make_adder(n: i64) -> impl Fn(i64) -> i64 {")
+            && rust_code.contains("return move |x| x + n;"),
+        "A returned closure should move its captures. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_returned_counter_is_impl_fn_mut() {
+    let python_source = r"
+def make_counter(start: int):
+    total = start
+
+    def step() -> int:
+        nonlocal total
+        total += 1
+        return total
+
+    return step
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower returned function");
+
+    assert!(
+        rust_code.contains("pub fn make_counter(start: i64) -> impl FnMut() -> i64 {")
+            && rust_code.contains("let mut step = move || -> i64 {"),
+        "The closure type should come from the nested function. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_recursive_nested_function_is_reported() {
+    let python_source = r"
+def total(n: int) -> int:
+    def go(k: int) -> int:
+        if k == 0:
+            return 0
+        return k + go(k - 1)
+
+    return go(n)
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject recursion");
+
+    assert!(
+        error.to_string().contains("recursive nested function"),
+        "Should name the unsupported construct. Got: {}",
+        error
+    );
+}
+
+#[test]
+fn test_nested_helper_mutating_self_needs_mut_receiver() {
+    let python_source = r"
+class Ledger:
+    def __init__(self):
+        self.entries: list[int] = []
+
+    def add_all(self, values: list[int]):
+        def add(v: int):
+            self.entries.append(v)
+
+        for v in values:
+            add(v)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower method helper");
+
+    assert!(
+        rust_code.contains("pub fn add_all(&mut self, values: Vec<i64>) {")
+            && rust_code.contains("let mut add = |v: i64| {"),
+        "The helper mutates self through the method's receiver. Got: {}",
+        rust_code
+    );
+}
@@ -0,0 +1,111 @@
+//! End-to-end comprehension lowering
+//!
+//! List, set and dict comprehensions and generator expressions become Rust
+//! iterator chains.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_list_comprehension_with_filter() {
+    let python_source = r#"
+def positive_doubles(numbers: list[int]) -> list[int]:
+    return [n * 2 for n in numbers if n > 0]
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower comprehension");
+
+    assert!(
+        rust_code
+            .contains("numbers.iter().filter(|&&n| n > 0).map(|&n| n * 2).collect::<Vec<_>>()"),
+        "Should become an iterator chain. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_nested_clauses_use_flat_map() {
+    let python_source = r#"
+def products(xs: list[int], ys: list[int]):
+    return {x * y for x in xs for y in ys if y > x}
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower comprehension");
+
+    assert!(
+        rust_code.contains(
+            "xs.iter().flat_map(|&x| ys.iter().filter(move |&&y| y > x).map(move |&y| x * y))"
+        ) && rust_code.contains(".collect::<std::collections::HashSet<_>>()"),
+        "Nested clauses should use flat_map. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_dict_comprehension_and_generator() {
+    let python_source = r#"
+def squares(n: int) -> int:
+    table = {i: i * i for i in range(1, n)}
+    return total(i for i in range(n))
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower comprehensions");
+
+    assert!(
+        rust_code
+            .contains("(1..n).map(|i| (i, i * i)).collect::<std::collections::HashMap<_, _>>()"),
+        "Dict comprehension should collect into a HashMap. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("total((0..n).map(|i| i))"),
+        "A generator expression should stay a lazy iterator. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_unannotated_return_type_is_inferred_from_comprehension() {
+    let python_source = r#"
+def doubled(xs: list[int]):
+    return [x * 2 for x in xs]
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should infer return type");
+
+    assert!(
+        rust_code.contains("pub fn doubled(xs: Vec<i64>) -> Vec<i64> {"),
+        "The return type should come from the returned comprehension. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_uninferable_return_type_is_reported() {
+    let python_source = r#"
+def first(xs):
+    return [x for x in xs]
+"#;
+
+    let error = lower_and_generate(python_source).expect_err("Should reject unknown return type");
+
+    assert_eq!(
+        error.to_string(),
+        "error: cannot infer the type of the value returned by `first`; add a type annotation\n  \
+         --> test.py:2:1",
+        "Should name the function and point at it"
+    );
+}
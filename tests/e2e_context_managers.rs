@@ -0,0 +1,239 @@
+//! End-to-end context manager lowering
+//!
+//! `with` statements become blocks binding one RAII guard per context
+//! manager: files opened with `open` are `std::fs::File`s, locks are
+//! `std::sync::Mutex` guards, and classes defining `__enter__` and
+//! `__exit__` get a guard struct whose `Drop` impl calls `exit`.
+//!
+//! Parse → Lower → Generate
+
+mod common;
+
+use common::assert_compiles;
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_with_open_reads_and_writes_files() {
+    let python_source = r"
+def copy(src: str, dst: str) -> int:
+    with open(src) as f, open(dst, 'w') as out:
+        text = f.read()
+        out.write(text)
+    return len(text)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower with open");
+
+    assert!(
+        rust_code.contains("pub fn copy(src: String, dst: String) -> Result<i64, Error> {"),
+        "Opening a file can raise OSError. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("impl From<std::io::Error> for Error {"),
+        "I/O errors should convert into OSError. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("let mut f = std::fs::File::open(src)?;")
+            && rust_code.contains("let mut out = std::fs::File::create(dst)?;"),
+        "Files should be bound at the start of the block. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("let text;\n    {")
+            && rust_code.contains("text = std::io::read_to_string(&mut f)?;")
+            && rust_code.contains("std::io::Write::write_all(&mut out, text.as_bytes())?;"),
+        "Locals assigned in the block should outlive it. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("return Ok(text.len() as i64);"),
+        "The length should be returned as an int. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_with_lock_binds_mutex_guard() {
+    let python_source = r"
+class Counter:
+    def __init__(self):
+        self.lock = Lock()
+        self.total = 0
+
+    def bump(self):
+        with self.lock:
+            self.total += 1
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower with lock");
+
+    assert!(
+        rust_code.contains("pub lock: std::sync::Mutex<()>,")
+            && rust_code.contains("let lock = std::sync::Mutex::new(());"),
+        "A Lock should be a Mutex. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains(
+            "let _guard = self.lock.lock().unwrap_or_else(std::sync::PoisonError::into_inner);"
+        ),
+        "with should hold the MutexGuard. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_user_context_manager_becomes_guard_with_drop() {
+    let python_source = r"
+class Timer:
+    def __init__(self):
+        self.depth = 0
+
+    def __enter__(self):
+        self.depth += 1
+        return self
+
+    def __exit__(self, exc_type, exc, tb):
+        self.depth -= 1
+        return False
+
+
+def nested() -> int:
+    with Timer() as t:
+        depth = t.depth
+    return depth
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower context manager");
+
+    assert!(
+        rust_code.contains("pub fn enter(&mut self) -> TimerGuard<'_> {")
+            && rust_code.contains("return TimerGuard(self);"),
+        "__enter__ should hand out the guard. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub fn exit(&mut self) {"),
+        "__exit__ should drop the exception arguments. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub struct TimerGuard<'a>(&'a mut Timer);")
+            && rust_code.contains("impl Drop for TimerGuard<'_> {"),
+        "The guard should call exit when dropped. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("let mut _context = Timer::new();")
+            && rust_code.contains("let t = _context.enter();"),
+        "A temporary manager should outlive its guard. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_returning_a_read_converts_the_io_error() {
+    let python_source = r"
+def read_all(path: str) -> str:
+    with open(path) as f:
+        return f.read()
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower with open");
+
+    assert!(
+        rust_code.contains("return Ok(std::io::read_to_string(&mut f)?);"),
+        "The I/O error should be converted into OSError. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_context_manager_without_init_is_constructed() {
+    let python_source = r"
+class Timer:
+    def __enter__(self):
+        self.started = 1
+        return self
+
+    def __exit__(self, exc_type, exc, tb):
+        self.started = 0
+
+
+def timed() -> int:
+    with Timer() as t:
+        started = t.started
+    return started
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower context manager");
+
+    assert!(
+        rust_code.contains("pub fn new() -> Self {")
+            && rust_code.contains("let mut _context = Timer::new();"),
+        "The manager should get a default constructor. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_module_level_lock_becomes_static() {
+    let python_source = r"
+import threading
+
+lock = threading.Lock()
+
+
+def guarded(xs: list[int]) -> int:
+    with lock:
+        return len(xs)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower module-level lock");
+
+    assert!(
+        rust_code.contains("pub static lock: std::sync::Mutex<()> = std::sync::Mutex::new(());"),
+        "A module-level lock should be a static Mutex. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("let _guard = lock.lock()"),
+        "`with lock` should hold the static's guard. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_unknown_context_manager_is_reported() {
+    let python_source = r"
+def f(resource):
+    with resource:
+        pass
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject unknown manager");
+
+    assert!(
+        error
+            .to_string()
+            .contains("`with` on an unknown context manager"),
+        "Should name the unsupported construct. Got: {}",
+        error
+    );
+}
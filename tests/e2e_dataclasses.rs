@@ -0,0 +1,222 @@
+//! End-to-end dataclasses and enumerations
+//!
+//! `@dataclass` classes become structs with derived traits and a `new`
+//! constructor, and `Enum` subclasses become fieldless enums whose
+//! methods map the variants to and from the members' values.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_ordered_frozen_dataclass() {
+    let python_source = r"
+@dataclass(order=True, frozen=True)
+class Version:
+    major: int
+    minor: int = 0
+
+def is_newer(a: Version, b: Version) -> bool:
+    return a > b
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower dataclass");
+
+    assert!(
+        rust_code.contains(
+            "#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]\npub struct Version {"
+        ),
+        "Ordering and freezing should derive Ord and Hash. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub fn new(major: i64, minor: i64) -> Self {")
+            && rust_code.contains("Self { major, minor }"),
+        "The constructor should take every field. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_float_fields_only_derive_partial_traits() {
+    let python_source = r"
+@dataclass
+class Point:
+    x: float
+    y: float
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower dataclass");
+
+    assert!(
+        rust_code.contains("#[derive(Debug, Clone, PartialEq)]\npub struct Point {"),
+        "Floats rule out Eq. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_construction_fills_keywords_and_defaults() {
+    let python_source = r"
+@dataclass
+class Job:
+    name: str
+    retries: int = 3
+    tags: list[str] = field(default_factory=list)
+
+def make(name: str) -> Job:
+    return Job(tags=[name], name=name)
+
+def plain(name: str) -> Job:
+    return Job(name)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower construction");
+
+    assert!(
+        rust_code.contains(
+            "    return {\n        let tags = vec![name.clone()];\n        Job::new(name, 3, tags)\n    };"
+        ),
+        "Reordered keywords should be evaluated in source order, cloning shared variables. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("return Job::new(name, 3, Default::default());"),
+        "Left-out fields should take their defaults. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_construction_in_field_order_passes_arguments_directly() {
+    let python_source = r"
+@dataclass
+class Job:
+    name: str
+    retries: int = 3
+    tags: list[str] = field(default_factory=list)
+
+def make(name: str, tags: list[str], retries: int) -> Job:
+    return Job(name, retries=retries + 1, tags=tags)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower construction");
+
+    assert!(
+        rust_code.contains("return Job::new(name, retries + 1, tags);"),
+        "Keywords already in field order need no temporaries. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_frozen_dataclass_mutation_is_reported() {
+    let python_source = r"
+@dataclass(frozen=True)
+class Counter:
+    count: int = 0
+
+    def bump(self):
+        self.count += 1
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject mutation");
+
+    assert!(
+        error
+            .to_string()
+            .contains("method assigning to a frozen dataclass"),
+        "Should name the unsupported construct. Got: {}",
+        error
+    );
+}
+
+#[test]
+fn test_enum_value_mapping() {
+    let python_source = r"
+class Color(Enum):
+    RED = 1
+    DARK_BLUE = auto()
+
+def code(c: Color) -> int:
+    return c.value
+
+def parse(value: int) -> Color:
+    return Color(value)
+
+def default() -> Color:
+    return Color.DARK_BLUE
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower enum");
+
+    assert!(
+        rust_code.contains("pub enum Color {\n    Red,\n    DarkBlue,\n}"),
+        "Members should become CamelCase variants. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("Self::DarkBlue => 2,")
+            && rust_code.contains("2 => Some(Self::DarkBlue),"),
+        "auto() should continue from the last value. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("return c.value();")
+            && rust_code.contains(
+                "return Color::from_value(value).expect(\"value is not a valid Color\");"
+            )
+            && rust_code.contains("return Color::DarkBlue;"),
+        "Member access and lookup should use the enum. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_str_enum_auto_values() {
+    let python_source = r"
+class Mode(StrEnum):
+    FAST = auto()
+    SAFE = 'safe-mode'
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower StrEnum");
+
+    assert!(
+        rust_code.contains("pub fn value(&self) -> &'static str {")
+            && rust_code.contains("Self::Fast => \"fast\",")
+            && rust_code.contains("\"safe-mode\" => Some(Self::Safe),"),
+        "String values should map to variants. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_unknown_decorator_is_reported() {
+    let python_source = r"
+@functools.cache
+def fib(n: int) -> int:
+    return n
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject decorator");
+
+    assert!(
+        error.to_string().contains("decorator `@functools.cache`"),
+        "Should name the decorator. Got: {}",
+        error
+    );
+}
@@ -0,0 +1,192 @@
+//! End-to-end exception lowering
+//!
+//! Python exceptions become variants of a generated `Error` enum: functions
+//! that can raise return `Result`, `raise` returns `Err`, calls propagate
+//! with `?` and `try` matches the error against its handlers.
+//!
+//! Parse → Lower → Generate
+
+mod common;
+
+use common::assert_compiles;
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_pop_from_empty_list_raises_index_error() {
+    let python_source = r"
+def take_last(items: list[int]) -> int:
+    return items.pop()
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower pop");
+
+    assert!(
+        rust_code.contains("pub enum Error {") && rust_code.contains("IndexError(String),"),
+        "Should generate an error enum. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("pub fn take_last(items: Vec<i64>) -> Result<i64, Error> {"),
+        "A raising function should return Result. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains(
+            "    let mut items = items;\n    \
+             return items.pop().ok_or_else(|| Error::IndexError(\"pop from empty list\".to_string()));"
+        ),
+        "An empty pop should raise IndexError. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_custom_exception_propagates_to_callers() {
+    let python_source = r#"
+class InsufficientFunds(Exception):
+    """Not enough money in the account"""
+
+
+def withdraw(balance: int, amount: int) -> int:
+    if amount > balance:
+        raise InsufficientFunds("balance too low")
+    return balance - amount
+
+
+def withdraw_twice(balance: int, amount: int) -> int:
+    return withdraw(withdraw(balance, amount), amount)
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower raise");
+
+    assert!(
+        rust_code
+            .contains("    /// Not enough money in the account\n    InsufficientFunds(String),"),
+        "The exception class should become a documented variant. Got: {}",
+        rust_code
+    );
+    assert!(
+        !rust_code.contains("pub struct InsufficientFunds"),
+        "The exception class should not become a struct. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code
+            .contains("return Err(Error::InsufficientFunds(\"balance too low\".to_string()));"),
+        "raise should return Err. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code
+            .contains("pub fn withdraw_twice(balance: i64, amount: i64) -> Result<i64, Error> {")
+            && rust_code.contains("return withdraw(withdraw(balance, amount)?, amount);"),
+        "Callers should propagate with ?. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_try_except_matches_on_error_variants() {
+    let python_source = r"
+def parse_port(text: str) -> int:
+    if text == '':
+        raise ValueError('empty port')
+    return 8080
+
+
+def port_or_default(text: str) -> int:
+    port = 80
+    try:
+        port = parse_port(text)
+    except ValueError as err:
+        print(str(err))
+    return port
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower try");
+
+    assert!(
+        rust_code.contains("pub fn port_or_default(text: String) -> i64 {"),
+        "A function catching everything it raises stays infallible. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains(
+            "    let mut port = 80;\n    \
+             match (|| -> Result<(), Error> {\n        \
+             port = parse_port(text)?;\n"
+        ),
+        "The try body should assign the outer variable in a Result closure. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("Err(err @ Error::ValueError(_)) => {")
+            && rust_code.contains("println!(\"{}\", err.to_string());"),
+        "The handler should bind the caught variant. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("Err(_) => {") && rust_code.contains("unreachable!();"),
+        "Errors the body cannot raise should be unreachable. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_variable_first_assigned_in_try_is_visible_after_it() {
+    let python_source = r"
+def parse_port(text: str) -> int:
+    if text == '':
+        raise ValueError('empty port')
+    return 8080
+
+
+def first_port(text: str) -> int:
+    try:
+        port = parse_port(text)
+    except ValueError:
+        port = 0
+    return port
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower try");
+
+    assert!(
+        rust_code.contains(
+            "    let mut port = Default::default();\n    match (|| -> Result<(), Error> {"
+        ) && rust_code.contains("        port = parse_port(text)?;\n")
+            && rust_code.contains("            port = 0;\n"),
+        "The variable should be bound before the try and assigned in it. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_return_inside_try_is_reported() {
+    let python_source = r"
+def first(items: list[int]) -> int:
+    try:
+        return items.pop()
+    except IndexError:
+        return 0
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject return in try");
+
+    assert!(
+        error.to_string().contains("control flow out of `try`"),
+        "Should name the unsupported construct. Got: {}",
+        error
+    );
+}
@@ -0,0 +1,103 @@
+//! End-to-end string formatting lowering
+//!
+//! f-strings, `str.format` and `%`-formatting become `format!` with the
+//! Python format specs translated to Rust ones.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_f_string_with_format_specs() {
+    let python_source = r"
+def row(name: str, price: float, qty: int) -> str:
+    return f'{name:<10}|{price:>8.2f}|{qty:03d} {{total}}'
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower f-string");
+
+    assert!(
+        rust_code
+            .contains(r#"return format!("{:<10}|{:>8.2}|{:03} {{total}}", name, price, qty);"#),
+        "The specs should map onto format! specs. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_str_format_with_positions_and_keywords() {
+    let python_source = r"
+def greet(first: str, last: str, title: str) -> str:
+    return '{1}, {0} ({title})'.format(first, last, title=title)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower str.format");
+
+    assert!(
+        rust_code.contains(r#"return format!("{}, {} ({})", last, first, title);"#),
+        "Arguments should follow the order the template uses them. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_percent_formatting() {
+    let python_source = r"
+def line(name: str, count: int, ratio: float) -> str:
+    return '%-8s %5d %.1f%%' % (name, count, ratio)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower % formatting");
+
+    assert!(
+        rust_code.contains(r#"return format!("{:<8} {:>5} {:.1}%", name, count, ratio);"#),
+        "Conversions should become format! specs. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_concatenation_borrows_owned_right_operands() {
+    let python_source = r"
+def label(first: str, last: str) -> str:
+    return (first + ' ') + last + f'!{first}'
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower concatenation");
+
+    assert!(
+        rust_code.contains("((first.clone() + \" \") + &last) + &format!(\"!{}\", first)"),
+        "Owned right operands should be borrowed. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_unsupported_format_spec_is_reported() {
+    let python_source = r"
+def total(amount: float) -> str:
+    return f'{amount:,.2f}'
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject digit grouping");
+
+    assert!(
+        error
+            .to_string()
+            .contains("f-string format spec `:,.2f` (digit grouping with `,`) at test.py:3"),
+        "Should name the spec and the reason. Got: {}",
+        error
+    );
+}
@@ -0,0 +1,176 @@
+//! End-to-end generator lowering
+//!
+//! Functions containing `yield` become `impl Iterator` functions built
+//! with `std::iter::from_fn`: the statements before the first `yield` run
+//! up front, and each top-level loop or `yield from` is resumed one item
+//! at a time.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_while_generator_consumed_by_for_loop() {
+    let python_source = r"
+def countdown(n: int) -> Iterator[int]:
+    while n > 0:
+        yield n
+        n -= 1
+
+
+def total(n: int) -> int:
+    for x in countdown(n):
+        print(x)
+    return n
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower generator");
+
+    assert!(
+        rust_code.contains("pub fn countdown(n: i64) -> impl Iterator<Item = i64> {"),
+        "A generator should return impl Iterator. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("let mut n = n;")
+            && rust_code.contains("std::iter::from_fn(move || loop {"),
+        "The loop state should move into a from_fn closure. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("0 => if n > 0 {")
+            && rust_code.contains("pending.push_back(n);")
+            && rust_code.contains("stage = 1;"),
+        "The while loop should resume one iteration at a time. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("for x in countdown(n) {"),
+        "Callers should iterate over the generator. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_for_generator_with_setup_and_continue() {
+    let python_source = r"
+def running_totals(values: list[int]) -> Iterator[int]:
+    total = 0
+    for value in values:
+        if value < 0:
+            continue
+        total += value
+        yield total
+    yield total
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower generator");
+
+    assert!(
+        rust_code.contains("let mut total = 0;")
+            && rust_code.contains("let mut iter_0 = values.into_iter();"),
+        "Setup locals and iterables should be created up front. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("0 => match iter_0.next() {")
+            && rust_code.contains("Some(value) => {")
+            && rust_code.contains("None => stage = 1,"),
+        "The for loop should resume one item at a time. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("stage = 0;\n") && rust_code.contains("continue 'resume;"),
+        "continue should resume the loop stage. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_yield_from_in_method() {
+    let python_source = r"
+class Playlist:
+    def __init__(self, songs: list[str]):
+        self.songs = songs
+
+    def with_intro(self) -> Iterator[str]:
+        yield 'intro'
+        yield from self.songs
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower generator method");
+
+    assert!(
+        rust_code.contains("pub fn with_intro(&self) -> impl Iterator<Item = String> + '_ {"),
+        "A generator method should borrow self. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("let mut iter_1 = self.songs.clone().into_iter();")
+            && rust_code.contains("Some(item) => return Some(item),"),
+        "yield from should hand out the delegate's items. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_unannotated_generator_consumed_by_for_loop() {
+    let python_source = r"
+def prices(xs: list[float]):
+    for x in xs:
+        if x > 0.0:
+            yield x
+
+
+def total(xs: list[float], qty: int) -> float:
+    result = 0.0
+    for price in prices(xs):
+        result += price * qty
+    return result
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower generator");
+
+    assert!(
+        rust_code.contains("pub fn prices(xs: Vec<f64>) -> impl Iterator<Item = f64> {"),
+        "The item type should come from the yielded value. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("for price in prices(xs) {")
+            && rust_code.contains("result += price * (qty as f64);"),
+        "The loop variable should have the item type. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_return_with_value_in_generator_is_reported() {
+    let python_source = r"
+def numbers() -> Iterator[int]:
+    yield 1
+    return 2
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject return value");
+
+    assert!(
+        error
+            .to_string()
+            .contains("`return` with a value in a generator"),
+        "Should name the unsupported construct. Got: {}",
+        error
+    );
+}
@@ -0,0 +1,90 @@
+//! End-to-end keyword arguments
+//!
+//! Keyword arguments are bound to the parameters of the Python functions
+//! and methods that are lowered: `sorted` and `list.sort` take `key` and
+//! `reverse`. `dict.get` takes its default positionally.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_sorted_with_key_and_reverse() {
+    let python_source = r#"
+def longest_first(words: list[str]) -> list[str]:
+    return sorted(words, key=lambda w: len(w), reverse=True)
+
+
+def descending(xs: list[int]):
+    xs.sort(reverse=True)
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower sorts");
+
+    assert!(
+        rust_code.contains("sorted.sort_by_key(|w| std::cmp::Reverse(w.len() as i64));"),
+        "A reversed key should be wrapped in Reverse. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("xs.sort_by(|a, b| b.cmp(a));"),
+        "A reversed sort without a key should compare the other way. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_dict_get_with_default() {
+    let python_source = r#"
+def timeout(settings: dict[str, int], name: str) -> int:
+    return settings.get(name, 30)
+
+
+def label(names: dict[str, str], key: str) -> str:
+    return names.get(key, "none")
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower dict.get");
+
+    assert!(
+        rust_code.contains("return settings.get(&name).copied().unwrap_or(30);"),
+        "The default should become unwrap_or. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("return names.get(&key).cloned().unwrap_or(String::from(\"none\"));"),
+        "Values that are not Copy should be cloned. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_undeclared_keyword_is_reported() {
+    let python_source = r#"
+def timeout(settings: dict[str, int], name: str) -> int:
+    return settings.get(name, default=30)
+"#;
+
+    let error = lower_and_generate(python_source).expect_err("Should reject keyword default");
+
+    assert!(
+        error
+            .to_string()
+            .contains("keyword argument `default` in call to get()"),
+        "Should name the keyword. Got: {}",
+        error
+    );
+}
@@ -0,0 +1,83 @@
+//! End-to-end string literals
+//!
+//! Python's `str` is an owned Rust `String`, while a string literal is a
+//! `&str`. Literals that are returned, stored or passed on become
+//! `String::from(..)`; literals that are only compared or looked up stay
+//! borrowed.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_returned_literal_is_owned() {
+    let python_source = r#"
+def f() -> str:
+    return "hi"
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower function");
+
+    assert!(
+        rust_code.contains("pub fn f() -> String {")
+            && rust_code.contains("return String::from(\"hi\");"),
+        "A returned literal should become a String. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_list_of_literals_holds_strings() {
+    let python_source = r#"
+def names() -> list[str]:
+    known: list[str] = ["a", "b"]
+    return known
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower function");
+
+    assert!(
+        rust_code.contains("vec![String::from(\"a\"), String::from(\"b\")]"),
+        "List elements should become Strings. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_literal_argument_is_owned() {
+    let python_source = r#"
+def shout(word: str) -> str:
+    return word + "!"
+
+
+def greet() -> str:
+    return shout("hello")
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower functions");
+
+    assert!(
+        rust_code.contains("return shout(String::from(\"hello\"));"),
+        "A literal passed to a str parameter should become a String. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("\"!\""),
+        "The concatenated literal should stay borrowed. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
@@ -0,0 +1,129 @@
+//! End-to-end multi-module projects
+//!
+//! Every module of a Python project becomes a Rust module, imports between
+//! them become `use` declarations and unresolved third-party imports are
+//! reported as diagnostics.
+//!
+//! Load → Lower → Generate
+
+use spydecy_codegen::{generate_rust_crate, RustFile};
+use spydecy_hir::unified::Unifier;
+use spydecy_python::loader::load_project;
+use std::{fs, path::Path};
+
+fn write(root: &Path, file: &str, source: &str) {
+    let path = root.join(file);
+    fs::create_dir_all(path.parent().expect("File should have a parent"))
+        .expect("Should create package directory");
+    fs::write(path, source).expect("Should write module");
+}
+
+fn file<'a>(files: &'a [RustFile], path: &str) -> &'a str {
+    files
+        .iter()
+        .find(|file| file.path == path)
+        .map_or_else(|| panic!("Missing {path}"), |file| file.code.as_str())
+}
+
+#[test]
+fn test_project_becomes_module_tree() {
+    let dir = tempfile::tempdir().expect("Should create temp dir");
+    write(dir.path(), "app/__init__.py", "");
+    write(
+        dir.path(),
+        "app/util.py",
+        r"
+def double(x: int) -> int:
+    return x * 2
+",
+    );
+    write(
+        dir.path(),
+        "app/models.py",
+        r"
+from . import util
+from .util import double
+
+def quadruple(x: int) -> int:
+    return util.double(double(x))
+",
+    );
+    write(
+        dir.path(),
+        "main.py",
+        r"
+import numpy as np
+from app.models import quadruple
+
+def run(x: int) -> int:
+    return quadruple(x)
+",
+    );
+
+    let project = load_project(dir.path()).expect("Should load project");
+    let diagnostics: Vec<_> = project
+        .diagnostics
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(diagnostics.len(), 1, "Got: {diagnostics:?}");
+    assert!(
+        diagnostics[0].starts_with("warning: unresolved import `numpy`")
+            && diagnostics[0].ends_with("main.py:2:1"),
+        "Third-party imports should be reported with their location. Got: {}",
+        diagnostics[0]
+    );
+
+    let modules = project
+        .modules
+        .iter()
+        .map(|module| {
+            Unifier::new()
+                .with_symbols(project.symbols.clone())
+                .lower_python(&module.hir)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .expect("Should lower modules");
+    let files = generate_rust_crate(&modules).expect("Should generate crate");
+
+    assert!(
+        file(&files, "lib.rs").ends_with("pub mod app;\npub mod main;\n"),
+        "The crate root should declare the top-level modules. Got: {}",
+        file(&files, "lib.rs")
+    );
+    assert!(
+        file(&files, "app/mod.rs").contains("pub mod models;\npub mod util;\n"),
+        "A package should declare its submodules. Got: {}",
+        file(&files, "app/mod.rs")
+    );
+    let models = file(&files, "app/models.rs");
+    assert!(
+        models.contains("use crate::app::util;\nuse crate::app::util::double;\n"),
+        "Relative imports should resolve against the package. Got: {models}"
+    );
+    assert!(
+        models.contains("util::double(double(x))"),
+        "Module attributes should become paths. Got: {models}"
+    );
+    let main = file(&files, "main.rs");
+    assert!(
+        main.contains("use crate::app::models::quadruple;") && !main.contains("numpy"),
+        "Only project imports should become use declarations. Got: {main}"
+    );
+}
+
+#[test]
+fn test_missing_project_name_is_an_error() {
+    let dir = tempfile::tempdir().expect("Should create temp dir");
+    write(dir.path(), "util.py", "def helper():\n    pass\n");
+    write(dir.path(), "main.py", "from util import missing\n");
+
+    let project = load_project(dir.path()).expect("Should load project");
+
+    assert_eq!(project.diagnostics.len(), 1);
+    assert!(project.diagnostics[0].is_error());
+    assert_eq!(
+        project.diagnostics[0].message,
+        "module `util` has no `missing`"
+    );
+}
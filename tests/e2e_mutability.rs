@@ -0,0 +1,140 @@
+//! End-to-end local bindings
+//!
+//! A Python variable's first assignment becomes a `let`, marked `mut` when
+//! the variable changes later, and its other assignments become plain
+//! assignments. The generated code is compiled with rustc.
+//!
+//! Parse → Lower → Generate → Compile
+
+mod common;
+
+use common::assert_compiles;
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_reassigned_variable_is_mutable() {
+    let python_source = r"
+def step(flag: bool) -> int:
+    x = 1
+    if flag:
+        x = 2
+    while x < 10:
+        x = x + 1
+    return x
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower reassignments");
+
+    assert!(
+        rust_code.contains(
+            "    let mut x = 1;\n    \
+             if flag {\n        \
+             x = 2;\n    \
+             }\n    \
+             while x < 10 {\n        \
+             x = x + 1;\n    \
+             }"
+        ),
+        "Reassignments should assign the first binding. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_augmented_accumulator_is_mutable() {
+    let python_source = r"
+def total(values: list[int]) -> int:
+    result = 0
+    for value in values:
+        result += value
+    return result
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower accumulator");
+
+    assert!(
+        rust_code.contains("let mut result = 0;") && rust_code.contains("result += value;"),
+        "An augmented variable should be mutable. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_variable_assigned_in_branches_is_declared_before_them() {
+    let python_source = r"
+def sign(value: int) -> int:
+    if value < 0:
+        result = -1
+    else:
+        result = 1
+    return result
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower branches");
+
+    assert!(
+        rust_code.contains(
+            "    let result;\n    \
+             if value < 0 {\n        \
+             result = -1;\n    \
+             } else {\n        \
+             result = 1;\n    \
+             }"
+        ),
+        "A variable used after the branches assigning it should be declared first. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_mutated_parameter_is_rebound_mutable() {
+    let python_source = r"
+def push_twice(items: list[int], value: int) -> list[int]:
+    items.append(value)
+    items.append(value)
+    return items
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower mutated parameter");
+
+    assert!(
+        rust_code.contains("    let mut items = items;\n    items.push(value);"),
+        "A mutated parameter should be rebound mutable. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_tuple_swap_assigns_both_variables() {
+    let python_source = r"
+def fib(n: int) -> int:
+    a, b = 0, 1
+    while n > 0:
+        a, b = b, a + b
+        n -= 1
+    return a
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower tuple reassignment");
+
+    assert!(
+        rust_code.contains("let mut n = n;")
+            && rust_code.contains("let (mut a, mut b) = (0, 1);")
+            && rust_code.contains("(a, b) = (b, a + b);"),
+        "A tuple reassignment should assign both variables. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
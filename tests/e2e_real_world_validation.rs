@@ -179,6 +179,78 @@ static void* list_pop(void) {
     println!("{}", rust_code);
 }
 
+/// Real-world scenario: Method-call syntax on a shopping cart
+#[test]
+fn test_real_world_method_call_append() {
+    let python_source = r#"
+def add_to_cart(shopping_cart, item):
+    return shopping_cart.append(item)
+"#;
+
+    let c_source = r#"
+static int PyList_Append(void) {
+    return 0;
+}
+"#;
+
+    let rust_code = run_full_pipeline(python_source, c_source)
+        .expect("Should generate Rust code for cart.append(item)");
+
+    assert!(
+        rust_code.contains("shopping_cart.push(item)"),
+        "Should use the attribute's object as the receiver. Got: {}",
+        rust_code
+    );
+}
+
+/// Real-world scenario: Read a setting with a fallback via `d.get(k, v)`
+#[test]
+fn test_real_world_dict_get_with_default() {
+    let python_source = r#"
+def get_timeout(settings, name):
+    return settings.get(name, 30)
+"#;
+
+    let c_source = r#"
+static void* PyDict_GetItem(void) {
+    return 0;
+}
+"#;
+
+    let rust_code = run_full_pipeline(python_source, c_source)
+        .expect("Should generate Rust code for settings.get(name, 30)");
+
+    assert!(
+        rust_code.contains("settings.get(&name).copied().unwrap_or(30)"),
+        "The default should become unwrap_or. Got: {}",
+        rust_code
+    );
+}
+
+/// Real-world scenario: Evict a session with `d.pop(k)`
+#[test]
+fn test_real_world_method_call_dict_pop() {
+    let python_source = r#"
+def evict_session(sessions, session_id):
+    return sessions.pop(session_id)
+"#;
+
+    let c_source = r#"
+static int PyDict_DelItem(void) {
+    return 0;
+}
+"#;
+
+    let rust_code = run_full_pipeline(python_source, c_source)
+        .expect("Should generate Rust code for sessions.pop(session_id)");
+
+    assert!(
+        rust_code.contains("sessions.remove(&session_id)"),
+        "Should map dict.pop(key) to HashMap::remove. Got: {}",
+        rust_code
+    );
+}
+
 /// Validate all patterns produce safe Rust
 #[test]
 fn test_all_patterns_produce_safe_rust() {
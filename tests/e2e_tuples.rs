@@ -0,0 +1,159 @@
+//! End-to-end tuple packing and unpacking
+//!
+//! Tuple displays become Rust tuples and unpacking targets become
+//! destructuring patterns: tuple patterns where the compiler can check the
+//! arity, and slice patterns checked at runtime for lists and starred
+//! targets.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::unified::Unifier;
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_multiple_return_values_are_destructured() {
+    let python_source = r"
+def min_max(values: list[int]) -> tuple[int, int]:
+    return min(values), max(values)
+
+
+def spread(values: list[int]) -> int:
+    low, high = min_max(values)
+    low, high = high, low
+    return low - high
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower tuples");
+
+    assert!(
+        rust_code.contains("pub fn min_max(values: Vec<i64>) -> (i64, i64) {")
+            && rust_code.contains(
+                "return (values.iter().min().cloned().expect(\"min() iterable argument is empty\"), \
+                 values.iter().max().cloned().expect(\"max() iterable argument is empty\"));"
+            ),
+        "Multiple return values should be a tuple. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("let (mut low, mut high) = min_max(values);")
+            && rust_code.contains("(low, high) = (high, low);"),
+        "Unpacking should destructure the tuple, and reassign it when swapping. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_for_loop_over_dict_items() {
+    let python_source = r"
+def report(scores: dict[str, int]):
+    for name, score in scores.items():
+        print(name, score)
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower items loop");
+
+    assert!(
+        rust_code.contains(
+            "for (name, score) in scores.iter().map(|entry| (entry.0.clone(), entry.1.clone())) {"
+        ),
+        "The loop should destructure each key-value pair of the borrowed dict. Got: {}",
+        rust_code
+    );
+    assert!(
+        rust_code.contains("println!(\"{} {}\", name, score);"),
+        "print should become println!. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_comprehension_over_dict_items_leaves_dict_usable() {
+    let python_source = r"
+def names_of(scores: dict[str, int]) -> list[str]:
+    names = [name for name, score in scores.items()]
+    print(len(scores))
+    return names
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower items comprehension");
+
+    assert!(
+        rust_code.contains("scores.iter().map(|entry| (entry.0.clone(), entry.1.clone()))")
+            && !rust_code.contains("scores.clone()"),
+        "The comprehension should iterate the borrowed dict. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_starred_target_matches_slice() {
+    let python_source = r"
+def tail(values: list[int]) -> list[int]:
+    first, *rest = values
+    return rest
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower starred target");
+
+    assert!(
+        rust_code.contains("let (first, rest) = match &values[..] {")
+            && rust_code.contains("[first, rest @ ..] => (first.clone(), rest.to_vec()),")
+            && rust_code
+                .contains("_ => panic!(\"not enough values to unpack (expected at least 1)\"),"),
+        "A starred target should match a slice pattern. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_tuple_assignment_in_generator_updates_state() {
+    let python_source = r"
+def fibonacci() -> Iterator[int]:
+    a, b = 0, 1
+    while True:
+        yield a
+        a, b = b, a + b
+";
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower generator");
+
+    assert!(
+        rust_code.contains("let (mut a, mut b) = (0, 1);")
+            && rust_code.contains("(a, b) = (b, a + b);"),
+        "Stages should assign to the setup locals. Got: {}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_starred_loop_target_is_reported() {
+    let python_source = r"
+def heads(rows: list[list[int]]) -> int:
+    for first, *rest in rows:
+        return first
+    return 0
+";
+
+    let error = lower_and_generate(python_source).expect_err("Should reject starred loop target");
+
+    assert!(
+        error
+            .to_string()
+            .contains("list or starred unpacking in a loop target"),
+        "Should name the unsupported construct. Got: {}",
+        error
+    );
+}
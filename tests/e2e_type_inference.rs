@@ -0,0 +1,114 @@
+//! End-to-end inference of unannotated types
+//!
+//! Types missing from the annotations are taken from the code: generator
+//! items from what is yielded, field types from the values stored in the
+//! fields, and mixed `int` and `float` arithmetic converts the `int`.
+//! Whatever cannot be inferred is reported at its declaration.
+//!
+//! Parse → Lower → Generate
+
+use spydecy_codegen::generate_rust;
+use spydecy_hir::{diagnostics::Diagnostic, unified::Unifier};
+use spydecy_python::parse_python;
+
+mod common;
+use common::assert_compiles;
+
+fn lower_and_generate(python_source: &str) -> anyhow::Result<String> {
+    let python_hir = parse_python(python_source, "test.py")?;
+    let unified = Unifier::new().lower_python(&python_hir)?;
+    generate_rust(&unified)
+}
+
+#[test]
+fn test_generator_items_are_inferred_from_yields() {
+    let python_source = r#"
+def evens(xs: list[int]):
+    for x in xs:
+        if x % 2 == 0:
+            yield x
+
+
+def chain(xs: list[int], ys: list[int]):
+    yield from xs
+    yield from ys
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should infer item types");
+
+    assert!(
+        rust_code.contains("pub fn evens(xs: Vec<i64>) -> impl Iterator<Item = i64> {")
+            && rust_code
+                .contains("pub fn chain(xs: Vec<i64>, ys: Vec<i64>) -> impl Iterator<Item = i64> {"),
+        "Item types should come from the yielded values. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_empty_field_is_typed_by_its_uses() {
+    let python_source = r#"
+class Basket:
+    def __init__(self):
+        self.items = []
+        self.counts = {}
+
+    def add(self, item: str):
+        self.items.append(item)
+
+    def count(self, item: str, times: int):
+        self.counts[item] = times
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should infer field types");
+
+    assert!(
+        rust_code.contains("pub items: Vec<String>,")
+            && rust_code.contains("pub counts: std::collections::HashMap<String, i64>,"),
+        "Empty collections should be typed by what is stored in them. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_mixed_arithmetic_converts_the_int() {
+    let python_source = r#"
+class Product:
+    def __init__(self, price: float):
+        self.price = price
+
+    def total(self, qty: int) -> float:
+        return self.price * qty
+"#;
+
+    let rust_code = lower_and_generate(python_source).expect("Should lower arithmetic");
+
+    assert!(
+        rust_code.contains("return self.price * (qty as f64);"),
+        "The int operand should be converted to f64. Got: {}",
+        rust_code
+    );
+    assert_compiles(&rust_code);
+}
+
+#[test]
+fn test_uninferred_parameter_is_reported_at_its_function() {
+    let python_source = r#"
+def double(x) -> int:
+    return x * 2
+"#;
+
+    let error = lower_and_generate(python_source).expect_err("Should reject unknown parameter");
+
+    let diagnostic = error
+        .downcast_ref::<Diagnostic>()
+        .expect("Should be a diagnostic");
+    assert!(diagnostic.is_error());
+    assert_eq!(
+        diagnostic.to_string(),
+        "error: cannot infer the type of parameter `x` of `double`; add a type annotation\n  \
+         --> test.py:2:1"
+    );
+}
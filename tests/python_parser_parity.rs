@@ -0,0 +1,102 @@
+//! Python parser backend parity
+//!
+//! The pure-Rust parser must build exactly the AST CPython's `ast` module
+//! produces through PyO3 - node types, field order, attributes and spans -
+//! so everything downstream of the parser is backend-independent.
+//!
+//! Parse (PyO3) ≡ Parse (native) → Convert
+
+use spydecy_python::parse_python_with;
+use spydecy_python::parser::{parse_with, ParseOptions, ParserBackend};
+use spydecy_python::version::PythonVersion;
+use std::{fs, path::PathBuf};
+
+fn corpus() -> Vec<(PathBuf, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/real_world/python");
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .expect("Should read the real-world corpus")
+        .map(|entry| entry.expect("Should read corpus entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "py"))
+        .map(|path| {
+            let source = fs::read_to_string(&path).expect("Should read corpus file");
+            (path, source)
+        })
+        .collect();
+    files.sort();
+    assert!(files.len() > 1, "Corpus should not be empty");
+    files
+}
+
+#[test]
+fn test_native_ast_matches_pyo3_over_corpus() {
+    for (path, source) in corpus() {
+        let name = path.display().to_string();
+        let expected = parse_with(ParserBackend::Pyo3, &source, &name).expect("PyO3 should parse");
+        let actual =
+            parse_with(ParserBackend::Native, &source, &name).expect("Native should parse");
+        assert_eq!(actual, expected, "AST mismatch for {name}");
+    }
+}
+
+#[test]
+fn test_native_hir_matches_pyo3_over_corpus() {
+    for (path, source) in corpus() {
+        let name = path.display().to_string();
+        let expected = parse_python_with(ParserBackend::Pyo3, &source, &name);
+        let actual = parse_python_with(ParserBackend::Native, &source, &name);
+        match (actual, expected) {
+            (Ok(actual), Ok(expected)) => assert_eq!(actual, expected, "HIR mismatch for {name}"),
+            // Constructs the converter rejects must be rejected the same way
+            (Err(actual), Err(expected)) => {
+                assert_eq!(actual.to_string(), expected.to_string(), "{name}");
+            }
+            (actual, expected) => panic!("{name}: native {actual:?} vs PyO3 {expected:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_syntax_errors_are_reported_by_both_backends() {
+    for backend in [ParserBackend::Pyo3, ParserBackend::Native] {
+        let error = parse_with(backend, "def broken(:\n    pass\n", "broken.py")
+            .expect_err("Invalid syntax should fail");
+        assert!(
+            format!("{error:#}").contains("Failed to parse Python source code"),
+            "{backend}: {error:#}"
+        );
+    }
+}
+
+#[test]
+fn test_target_version_is_enforced_by_both_backends() {
+    let source = "match command:\n    case 'quit':\n        pass\n";
+    for backend in [ParserBackend::Pyo3, ParserBackend::Native] {
+        let options = ParseOptions::from(backend).with_target(PythonVersion::new(3, 9));
+        let error = parse_with(options, source, "cli.py").expect_err("3.9 has no match");
+        assert_eq!(
+            error.to_string(),
+            "`match` statements require Python 3.10 or newer, but the target is Python 3.9 \
+             (line 1, column 1 in cli.py)"
+        );
+        assert!(parse_with(
+            options.with_target(PythonVersion::new(3, 10)),
+            source,
+            "cli.py"
+        )
+        .is_ok());
+    }
+}
+
+#[test]
+fn test_backend_names() {
+    assert_eq!(
+        "native".parse::<ParserBackend>().unwrap(),
+        ParserBackend::Native
+    );
+    assert_eq!(
+        "pyo3".parse::<ParserBackend>().unwrap(),
+        ParserBackend::Pyo3
+    );
+    let error = "jython".parse::<ParserBackend>().unwrap_err();
+    assert!(error.to_string().contains("Unknown Python parser `jython`"));
+}
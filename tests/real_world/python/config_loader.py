@@ -0,0 +1,72 @@
+"""Load layered configuration files."""
+
+import json
+import logging
+from pathlib import Path
+
+log = logging.getLogger(__name__)
+
+DEFAULTS = {"retries": 3, "timeout": 2.5, "verbose": False, "paths": [], "extra": None}
+
+
+class ConfigError(Exception):
+    pass
+
+
+def load(path, defaults=DEFAULTS):
+    config = dict(defaults)
+    try:
+        with open(path, encoding="utf-8") as handle, open(path + ".lock", "a"):
+            config.update(json.load(handle))
+    except FileNotFoundError:
+        log.warning("missing config %s", path)
+    except (ValueError, KeyError) as error:
+        raise ConfigError(f"invalid config in {path}") from error
+    else:
+        log.debug("loaded %s", path)
+    finally:
+        config["source"] = str(path)
+    return config
+
+
+def merge(*layers):
+    merged = {}
+    for layer in layers:
+        merged = {**merged, **layer}
+    return merged
+
+
+def describe(value):
+    match value:
+        case {"retries": int(retries), **rest} if retries > 0:
+            return f"retrying {retries} times ({len(rest)} more keys)"
+        case [first, *others]:
+            return f"list starting with {first!r} and {len(others)} more"
+        case str() | bytes():
+            return "text"
+        case None | False:
+            return "disabled"
+        case -1 | 0:
+            return "sentinel"
+        case _:
+            return "unknown"
+
+
+def first_existing(candidates):
+    while candidates:
+        if (path := Path(candidates.pop(0))).exists():
+            return path
+    return None
+
+
+def retry(action, attempts=3):
+    last = None
+    for attempt in range(attempts):
+        try:
+            return action()
+        except OSError as error:
+            last = error
+            continue
+    else:
+        assert last is not None, "no attempts made"
+        raise last
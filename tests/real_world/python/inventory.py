@@ -0,0 +1,67 @@
+"""Warehouse inventory tracking."""
+
+from __future__ import annotations
+
+from dataclasses import dataclass, field
+from enum import Enum
+from typing import Optional
+
+
+class Category(Enum):
+    TOOLS = 1
+    PARTS = 2
+    SUPPLIES = 3
+
+
+@dataclass
+class Item:
+    sku: str
+    name: str
+    quantity: int = 0
+    price: float = 0.0
+    tags: list[str] = field(default_factory=list)
+
+    def total_value(self) -> float:
+        return self.quantity * self.price
+
+
+class Inventory:
+    """In-memory inventory keyed by SKU."""
+
+    def __init__(self, owner: str, *, strict: bool = True) -> None:
+        self.owner = owner
+        self.strict = strict
+        self._items: dict[str, Item] = {}
+
+    def __len__(self):
+        return len(self._items)
+
+    def add(self, item: Item, /, replace=False) -> None:
+        if item.sku in self._items and not replace:
+            raise KeyError(f"duplicate sku {item.sku!r}")
+        self._items[item.sku] = item
+
+    def find(self, sku: str) -> Optional[Item]:
+        return self._items.get(sku)
+
+    def restock(self, sku, amount=1, *reasons, **metadata):
+        item = self._items[sku]
+        item.quantity += amount
+        for reason in reasons:
+            item.tags.append(reason)
+        return item.quantity
+
+    @property
+    def total_value(self):
+        return sum(item.total_value() for item in self._items.values())
+
+    @staticmethod
+    def parse_sku(raw: str) -> tuple[str, int]:
+        prefix, _, number = raw.partition("-")
+        return prefix.upper(), int(number or 0)
+
+    def low_stock(self, threshold: int = 5) -> list[Item]:
+        return sorted(
+            (item for item in self._items.values() if item.quantity < threshold),
+            key=lambda item: (item.quantity, item.sku),
+        )
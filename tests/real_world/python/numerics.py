@@ -0,0 +1,73 @@
+import asyncio
+from functools import reduce
+
+MASK = 0xFF_FF
+FLAGS = 0b1010 | 0o17
+BIG = 123_456_789_012_345_678_901_234_567_890
+TINY = 1e-10
+HUGE = 1.5e300
+ANGLE = 3j + 0.5
+HALF = .5
+TOTAL = 10.
+
+
+def clamp(value, low=0, high=MASK):
+    return low if value < low else high if value > high else value
+
+
+def bits(n):
+    return [(n >> shift) & 1 for shift in range(8)][::-1]
+
+
+def norm(*values, power=2):
+    return sum(abs(v) ** power for v in values) ** (1 / power)
+
+
+def window(data, size=3, step=1):
+    return [data[i : i + size] for i in range(0, len(data) - size + 1, step)]
+
+
+def product(values):
+    return reduce(lambda acc, v: acc * v, values, 1)
+
+
+def swap_pairs(pairs):
+    result = []
+    for a, (b, c) in pairs:
+        result.append((c, b, a))
+    x, *rest = result or [None]
+    return x, rest
+
+
+def checks(a, b):
+    return not a and b or a is not None and b not in (1, 2) and -a <= ~b < a // 2 % 3 @ b
+
+
+async def fetch_all(urls, limit=4):
+    semaphore = asyncio.Semaphore(limit)
+
+    async def fetch(url):
+        async with semaphore:
+            await asyncio.sleep(0)
+            return url
+
+    results = [await task for task in asyncio.as_completed([fetch(u) for u in urls])]
+    async for item in stream(results):
+        yield item
+
+
+async def stream(items):
+    for item in items:
+        yield item
+    del items
+
+
+def counter(start=0):
+    total = start
+    while True:
+        received = yield total
+        if received is None:
+            break
+        total += received
+    global MASK
+    MASK ^= total
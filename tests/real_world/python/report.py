@@ -0,0 +1,48 @@
+import math
+import os.path as osp
+from collections import Counter, defaultdict
+
+WIDTH = 72
+SEPARATOR = "-" * WIDTH
+RAW = r"C:\reports\{name}"
+BANNER = b"\x00REPORT\xff"
+
+
+def header(title, width=WIDTH, fill="="):
+    padded = f" {title} "
+    return f"{padded:{fill}^{width}}"
+
+
+def row(name, value, unit="ms"):
+    return f"{name:<20}{value:>10.2f} {unit}" if value >= 0 else f"{name:<20}{'n/a':>10}"
+
+
+def summary(samples):
+    counts = Counter(samples)
+    by_bucket = defaultdict(list)
+    for sample in samples:
+        by_bucket[sample // 10 * 10].append(sample)
+    squares = [x**2 for x in samples if x % 2 == 0]
+    lookup = {k: v for k, v in counts.items() if v > 1}
+    unique = {abs(x) for x in samples}
+    mean = sum(samples) / len(samples) if samples else math.nan
+    spread = max(samples, default=0) - min(samples, default=0)
+    lines = [
+        header("Summary"),
+        row("mean", mean),
+        row("spread", spread),
+        "buckets: %d, unique: %s" % (len(by_bucket), len(unique)),
+        "{0} squares, {n} repeated".format(len(squares), n=len(lookup)),
+        f"{mean=}",
+        f"{osp.basename(RAW)!s:>{WIDTH // 2}}",
+        SEPARATOR,
+    ]
+    return "\n".join(lines)
+
+
+def histogram(samples, scale=1.0):
+    out = []
+    for value, count in sorted(Counter(samples).items(), key=lambda pair: -pair[1]):
+        bar = "#" * int(count * scale)
+        out.append(f"{value:5d} | {bar}")
+    return out